@@ -0,0 +1,187 @@
+//====================================================================
+// 200 jumping cubes with blob shadows, all drawn by the BlobShadowRenderer
+// in a single instanced draw call. The shadows land on raised platforms by
+// casting down against their RenderBounds. Press G to switch to projecting
+// straight down onto the floor instead.
+
+use roots_core::{
+    common::{
+        spatial::{Aabb, GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{
+        blob_shadow::{self, BlobShadow, ShadowGround},
+        renderer::components::{Model, RenderBounds},
+        HecsApp, State,
+    },
+    pipelines::{blob_shadow_renderer::BlobShadowRenderer, model_renderer::ModelRenderer},
+    renderer::lighting::GlobalLightData,
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const CHARACTER_COUNT: u32 = 200;
+const CHARACTER_SIZE: f32 = 0.6;
+const ARENA_SIZE: f32 = 30.;
+
+fn main() {
+    example_common::run::<App>("blob_shadows");
+}
+
+//====================================================================
+
+struct Jump {
+    base: f32,
+    height: f32,
+    speed: f32,
+    phase: f32,
+}
+
+struct App {
+    ground: ShadowGround,
+    elapsed: f32,
+    since_log: f32,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        state.renderer.add_managed_pipeline::<BlobShadowRenderer>(5);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_strength: 0.9,
+            ..Default::default()
+        });
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 18., -30.));
+        state.show_fps(true);
+
+        let cube = example_common::load_cube(state);
+        let floor_texture = example_common::load_checker_texture(
+            state,
+            64,
+            16,
+            [[200, 200, 200, 255], [170, 170, 170, 255]],
+        );
+        let character_texture = example_common::load_checker_texture(
+            state,
+            4,
+            1,
+            [[220, 120, 60, 255], [220, 120, 60, 255]],
+        );
+
+        // The floor and a few raised platforms to catch shadows
+        let platforms = [
+            (
+                glam::vec3(0., -0.5, 0.),
+                glam::vec3(ARENA_SIZE, 1., ARENA_SIZE),
+            ),
+            (glam::vec3(-6., 0.5, 4.), glam::vec3(6., 1., 6.)),
+            (glam::vec3(7., 1., -3.), glam::vec3(5., 2., 8.)),
+        ];
+
+        platforms.into_iter().for_each(|(translation, size)| {
+            state.world.spawn((
+                Model::new([(cube.clone(), floor_texture.clone())]).with_scale(size),
+                RenderBounds::Aabb(Aabb::new(-size / 2., size / 2.)),
+                Transform::from_translation(translation),
+                GlobalTransform::default(),
+            ));
+        });
+
+        state.seed_rng(0x5EED);
+        let mut rng = state.rng.fork("blob_shadows");
+        let half_extent = ARENA_SIZE / 2. - 1.;
+
+        let characters = (0..CHARACTER_COUNT)
+            .map(|_| {
+                let x = rng.gen_range_f32(-half_extent..half_extent);
+                let z = rng.gen_range_f32(-half_extent..half_extent);
+
+                // Stand on whichever platform is below
+                let base = platforms
+                    .iter()
+                    .filter(|(translation, size)| {
+                        (x - translation.x).abs() <= size.x / 2.
+                            && (z - translation.z).abs() <= size.z / 2.
+                    })
+                    .map(|(translation, size)| translation.y + size.y / 2.)
+                    .fold(0., f32::max)
+                    + CHARACTER_SIZE / 2.;
+
+                (
+                    Model::new([(cube.clone(), character_texture.clone())])
+                        .with_scale(glam::Vec3::splat(CHARACTER_SIZE)),
+                    BlobShadow::new(CHARACTER_SIZE * 0.8).with_max_drop_height(4.),
+                    Jump {
+                        base,
+                        height: rng.gen_range_f32(0.5..3.5),
+                        speed: rng.gen_range_f32(2. ..4.),
+                        phase: rng.gen_range_f32(0. ..std::f32::consts::TAU),
+                    },
+                    Transform::from_translation(glam::vec3(x, base, z)),
+                    GlobalTransform::default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        state.world.spawn_batch(characters);
+
+        Self {
+            ground: ShadowGround::Raycast { floor: Some(0.) },
+            elapsed: 0.,
+            since_log: 0.,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyG) {
+            self.ground = match self.ground {
+                ShadowGround::Raycast { .. } => ShadowGround::Plane(0.),
+                _ => ShadowGround::Raycast { floor: Some(0.) },
+            };
+        }
+
+        self.elapsed += state.time.delta_seconds();
+        let elapsed = self.elapsed;
+
+        state
+            .world
+            .query_mut::<(&Jump, &mut Transform)>()
+            .into_iter()
+            .for_each(|(_, (jump, transform))| {
+                let bounce = (elapsed * jump.speed + jump.phase).sin().abs();
+                transform.translation.y = jump.base + bounce * jump.height;
+            });
+
+        self.since_log += state.time.delta_seconds();
+
+        if self.since_log >= 1. {
+            let stats = state
+                .renderer
+                .with_managed_pipeline::<BlobShadowRenderer, _>(|pipeline| {
+                    (pipeline.shadow_count(), pipeline.draw_calls())
+                });
+
+            if let Some((shadows, draw_calls)) = stats {
+                log::info!("{} blob shadows in {} draw calls", shadows, draw_calls);
+            }
+
+            self.since_log = 0.;
+        }
+
+        example_common::process_fly_controller(state);
+        example_common::finish_tick(state);
+
+        blob_shadow::process_blob_shadows(state, &self.ground);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,184 @@
+//====================================================================
+// Textured, lit cubes viewed through a fly camera. Press ` to open the
+// console - try `spawn_cube 0 2 0`, `time_scale 0.2` or `help`. Spawned
+// cubes fade in over a second and F fades them out again, despawning them.
+// O logs the order the managed pipelines render in.
+// L toggles late latching of the camera's mouse look and K caps the frame
+// rate at 30fps, to compare how closely the view follows the mouse.
+
+use std::time::Duration;
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{
+        debug_text,
+        fade::{self, FadeIn, FadeOut},
+        renderer::components::Model,
+        HecsApp, State,
+    },
+    pipelines::model_renderer::ModelRenderer,
+    renderer::lighting::GlobalLightData,
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common::{self, Spin};
+
+//====================================================================
+
+fn main() {
+    example_common::run::<App>("cube");
+}
+
+//====================================================================
+
+struct App {
+    /// Frame time before the 30fps cap.
+    uncapped: Option<Duration>,
+}
+
+/// Cubes spawned from the console.
+struct SpawnedCube;
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_color: glam::vec3(1., 0.95, 0.85),
+            ambient_strength: 0.9,
+        });
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 1., -6.));
+        state.show_fps(true);
+
+        let cube = example_common::load_cube(state);
+        let texture = example_common::load_checker_texture(
+            state,
+            64,
+            8,
+            [[230, 230, 230, 255], [60, 90, 160, 255]],
+        );
+
+        // A large spinning cube surrounded by smaller tinted ones
+        state.world.spawn((
+            Model::new([(cube.clone(), texture.clone())]).with_scale(glam::Vec3::splat(1.5)),
+            Transform::default(),
+            GlobalTransform::default(),
+            Spin {
+                axis: glam::vec3(0.3, 1., 0.2).normalize(),
+                speed: 0.8,
+            },
+        ));
+
+        let colors = [
+            [1., 0.4, 0.4, 1.],
+            [0.4, 1., 0.4, 1.],
+            [0.4, 0.4, 1., 1.],
+            [1., 1., 0.4, 1.],
+        ];
+
+        colors.into_iter().enumerate().for_each(|(index, color)| {
+            let angle = index as f32 * std::f32::consts::FRAC_PI_2;
+
+            state.world.spawn((
+                Model::new([(cube.clone(), texture.clone())])
+                    .with_color(color)
+                    .with_scale(glam::Vec3::splat(0.5)),
+                Transform::from_translation(glam::vec3(angle.cos() * 3., 0., angle.sin() * 3.)),
+                GlobalTransform::default(),
+                Spin {
+                    axis: glam::Vec3::Y,
+                    speed: -1.5,
+                },
+            ));
+        });
+
+        state.register_command("spawn_cube", move |state, args| {
+            let position = glam::vec3(
+                args.parse_arg_or(0, 0.)?,
+                args.parse_arg_or(1, 0.)?,
+                args.parse_arg_or(2, 0.)?,
+            );
+
+            state.world.spawn((
+                Model::new([(cube.clone(), texture.clone())])
+                    .with_color([1., 1., 1., 0.])
+                    .with_scale(glam::Vec3::splat(0.5)),
+                Transform::from_translation(position),
+                GlobalTransform::default(),
+                FadeIn::new(1.),
+                SpawnedCube,
+            ));
+
+            Ok(format!("Spawned cube at {}", position))
+        });
+
+        Self { uncapped: None }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyO) {
+            log::info!("{}", state.renderer.describe_frame());
+        }
+
+        if state.keys.just_pressed(KeyCode::KeyF) {
+            let spawned = state
+                .world
+                .query_mut::<()>()
+                .with::<&SpawnedCube>()
+                .without::<&FadeOut>()
+                .into_iter()
+                .map(|(entity, _)| entity)
+                .collect::<Vec<_>>();
+
+            spawned.into_iter().for_each(|entity| {
+                state.world.remove_one::<FadeIn>(entity).ok();
+                state.world.insert_one(entity, FadeOut::despawning(1.)).ok();
+            });
+        }
+
+        if state.keys.just_pressed(KeyCode::KeyL) {
+            let enabled = !state.late_latch.enabled();
+            state.late_latch.set_enabled(enabled);
+            log::info!("Late latching {}", if enabled { "on" } else { "off" });
+        }
+
+        if state.keys.just_pressed(KeyCode::KeyK) {
+            match self.uncapped.take() {
+                Some(target_fps) => state.target_fps = target_fps,
+                None => {
+                    self.uncapped = Some(state.target_fps);
+                    state.target_fps = Duration::from_secs_f32(1. / 30.);
+                }
+            }
+        }
+
+        let spawned = state.world.query_mut::<&SpawnedCube>().into_iter().count();
+        debug_text!(state, "spawned cubes: {}", spawned);
+        debug_text!(
+            state,
+            "late latch: {} ({:.1} latched)",
+            if state.late_latch.enabled() {
+                "on"
+            } else {
+                "off"
+            },
+            state.mouse_input.late_motion().length()
+        );
+
+        example_common::process_fly_controller(state);
+        example_common::process_spin(state);
+        fade::process_fades(state);
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
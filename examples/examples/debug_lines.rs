@@ -0,0 +1,216 @@
+//====================================================================
+// Debug drawing with the line renderer - a ground grid, world axes and
+// wireframe gizmos that follow spinning transforms. The static box can be
+// moved with the transform gizmo - G to translate, R to rotate and hold
+// left control to snap.
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{
+        gizmo::{GizmoMode, TransformGizmo},
+        hecs::Entity,
+        renderer::components::LineBundle,
+        HecsApp, State,
+    },
+    pipelines::line_renderer::{LineInstance, LineRenderer},
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common::{self, Spin};
+
+//====================================================================
+
+fn main() {
+    example_common::run::<App>("debug_lines");
+}
+
+//====================================================================
+
+#[derive(Clone, Copy)]
+enum Gizmo {
+    Box { half_size: glam::Vec3 },
+    Ring { radius: f32, segments: u32 },
+}
+
+impl Gizmo {
+    fn lines(&self, transform: &glam::Affine3A, color: glam::Vec4) -> Vec<LineInstance> {
+        let points = match *self {
+            Gizmo::Box { half_size } => {
+                let corner = |index: u32| {
+                    glam::vec3(
+                        [-1., 1.][(index & 1) as usize],
+                        [-1., 1.][((index >> 1) & 1) as usize],
+                        [-1., 1.][((index >> 2) & 1) as usize],
+                    ) * half_size
+                };
+
+                // Connect every pair of corners that differ by exactly one axis
+                (0..8)
+                    .flat_map(|a| {
+                        [1, 2, 4]
+                            .into_iter()
+                            .filter(move |bit| a & bit == 0)
+                            .map(move |bit| (corner(a), corner(a | bit)))
+                    })
+                    .collect::<Vec<_>>()
+            }
+
+            Gizmo::Ring { radius, segments } => (0..segments)
+                .map(|index| {
+                    let angle = |index: u32| index as f32 / segments as f32 * std::f32::consts::TAU;
+                    let point = |angle: f32| glam::vec3(angle.cos(), 0., angle.sin()) * radius;
+
+                    (point(angle(index)), point(angle(index + 1)))
+                })
+                .collect(),
+        };
+
+        points
+            .into_iter()
+            .map(|(pos1, pos2)| LineInstance {
+                color,
+                pos1: transform.transform_point3(pos1),
+                pos2: transform.transform_point3(pos2),
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+struct GizmoColor(glam::Vec4);
+
+//====================================================================
+
+struct App {
+    camera: Entity,
+    gizmo: TransformGizmo,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<LineRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        let camera = example_common::spawn_perspective_camera(state, glam::vec3(0., 3., -8.));
+        if let Ok(mut controller) = state
+            .world
+            .get::<&mut example_common::FlyController>(camera)
+        {
+            controller.look.pitch = 0.35;
+        }
+        state.show_fps(true);
+
+        // Static grid and axes
+        let grid_color = glam::vec4(0.5, 0.5, 0.5, 1.);
+        let mut lines = (-10..=10)
+            .flat_map(|index| {
+                let offset = index as f32;
+                [
+                    (glam::vec3(offset, 0., -10.), glam::vec3(offset, 0., 10.)),
+                    (glam::vec3(-10., 0., offset), glam::vec3(10., 0., offset)),
+                ]
+            })
+            .map(|(pos1, pos2)| LineInstance {
+                color: grid_color,
+                pos1,
+                pos2,
+                thickness: 1.,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        lines.extend(
+            [
+                (glam::Vec3::X, glam::vec4(1., 0.2, 0.2, 1.)),
+                (glam::Vec3::Y, glam::vec4(0.2, 1., 0.2, 1.)),
+                (glam::Vec3::Z, glam::vec4(0.2, 0.2, 1., 1.)),
+            ]
+            .into_iter()
+            .map(|(axis, color)| LineInstance {
+                color,
+                pos1: glam::Vec3::Y * 0.01,
+                pos2: axis * 2. + glam::Vec3::Y * 0.01,
+                thickness: 4.,
+                ..Default::default()
+            }),
+        );
+
+        state.world.spawn((LineBundle { lines },));
+
+        // Gizmos rebuilt every frame from their transforms
+        state.world.spawn((
+            Gizmo::Box {
+                half_size: glam::Vec3::splat(0.75),
+            },
+            GizmoColor(glam::vec4(1., 0.8, 0.2, 1.)),
+            LineBundle { lines: Vec::new() },
+            Transform::from_translation(glam::vec3(-2., 1., 0.)),
+            GlobalTransform::default(),
+            Spin {
+                axis: glam::vec3(1., 1., 0.).normalize(),
+                speed: 0.7,
+            },
+        ));
+
+        state.world.spawn((
+            Gizmo::Ring {
+                radius: 1.,
+                segments: 48,
+            },
+            GizmoColor(glam::vec4(0.2, 0.9, 1., 1.)),
+            LineBundle { lines: Vec::new() },
+            Transform::from_translation(glam::vec3(2., 1., 0.)),
+            GlobalTransform::default(),
+            Spin {
+                axis: glam::Vec3::X,
+                speed: 1.2,
+            },
+        ));
+
+        let target = state.world.spawn((
+            Gizmo::Box {
+                half_size: glam::Vec3::splat(0.5),
+            },
+            GizmoColor(glam::vec4(0.9, 0.9, 0.9, 1.)),
+            LineBundle { lines: Vec::new() },
+            Transform::from_translation(glam::vec3(0., 0.5, 2.)),
+            GlobalTransform::default(),
+        ));
+
+        Self {
+            camera,
+            gizmo: TransformGizmo::new(state).with_target(target),
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyG) {
+            self.gizmo.mode = GizmoMode::Translate;
+        }
+        if state.keys.just_pressed(KeyCode::KeyR) {
+            self.gizmo.mode = GizmoMode::Rotate;
+        }
+        self.gizmo.update(state, self.camera);
+
+        example_common::process_fly_controller(state);
+        example_common::process_spin(state);
+
+        state
+            .world
+            .query_mut::<(&Gizmo, &GizmoColor, &GlobalTransform, &mut LineBundle)>()
+            .into_iter()
+            .for_each(|(_, (gizmo, color, global, bundle))| {
+                bundle.lines = gizmo.lines(&global.0, color.0)
+            });
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
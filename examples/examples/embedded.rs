@@ -0,0 +1,170 @@
+//====================================================================
+// Drives roots from a host loop without a window - the same setup an app
+// with its own event loop would use to render a viewport. Renders a few
+// seconds of frames into an offscreen texture, forwarding fake input and a
+// resize the way a host would forward its own events.
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{renderer::components::Model, renderer::RendererState, HecsApp, State},
+    pipelines::model_renderer::ModelRenderer,
+    runner::{prelude::KeyCode, WindowInputEvent},
+};
+use roots_examples::example_common::{self, Spin};
+
+//====================================================================
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+const FRAMES: u32 = 180;
+
+fn main() {
+    env_logger::builder()
+        .filter_module("embedded", log::LevelFilter::Trace)
+        .init();
+
+    // Everything here is owned by the host application
+    let (device, queue) = pollster::block_on(request_device());
+
+    let mut size = Size::new(640, 360);
+    let mut viewport = create_viewport(&device, size);
+
+    let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: FORMAT,
+        width: size.width,
+        height: size.height,
+        present_mode: wgpu::PresentMode::AutoVsync,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: Vec::new(),
+    };
+
+    let renderer = RendererState::from_parts(device.into(), queue.into(), None, config.into());
+    let mut state = State::new_embedded(renderer, size);
+    let mut app = App::new(&mut state);
+
+    (0..FRAMES).for_each(|frame| {
+        // Forward host events
+        match frame {
+            30 => state.inject_input(WindowInputEvent::KeyInput {
+                key: KeyCode::KeyW,
+                pressed: true,
+            }),
+            90 => state.inject_input(WindowInputEvent::KeyInput {
+                key: KeyCode::KeyW,
+                pressed: false,
+            }),
+            120 => {
+                size = Size::new(1280, 720);
+                viewport = create_viewport(&state.renderer.device, size);
+
+                state.inject_resize(size);
+                app.resize(&mut state, size);
+            }
+            _ => {}
+        }
+
+        roots_core::common::tick_time(&mut state.time);
+
+        app.tick(&mut state);
+
+        let view = viewport.create_view(&wgpu::TextureViewDescriptor::default());
+        state.renderer.run_frame_to(&mut state.world, view);
+
+        state.reset_inputs();
+        state.clear_changes();
+    });
+
+    log::info!(
+        "Rendered {} frames into a {} viewport. Camera ended at {}",
+        FRAMES,
+        size,
+        state
+            .world
+            .query_mut::<&Transform>()
+            .with::<&example_common::FlyController>()
+            .into_iter()
+            .next()
+            .map(|(_, transform)| transform.translation)
+            .unwrap_or_default()
+    );
+}
+
+async fn request_device() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::default();
+
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+        .expect("No adapter available");
+
+    adapter
+        .request_device(&wgpu::DeviceDescriptor::default(), None)
+        .await
+        .expect("Unable to request device")
+}
+
+fn create_viewport(device: &wgpu::Device, size: Size<u32>) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Viewport Texture"),
+        size: wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+//====================================================================
+
+struct App;
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 1., -6.));
+
+        let cube = example_common::load_cube(state);
+        let texture = example_common::load_checker_texture(
+            state,
+            64,
+            8,
+            [[230, 230, 230, 255], [160, 60, 90, 255]],
+        );
+
+        state.world.spawn((
+            Model::new([(cube, texture)]),
+            Transform::default(),
+            GlobalTransform::default(),
+            Spin {
+                axis: glam::Vec3::Y,
+                speed: 1.,
+            },
+        ));
+
+        Self
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        example_common::process_fly_controller(state);
+        example_common::process_spin(state);
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,122 @@
+//====================================================================
+// A fountain of 500,000 particles simulated by a compute shader and drawn
+// straight from the gpu buffer. Press G to move the emitter to and from the
+// cpu and compare the particle count and prep time logged every second.
+// Devices without compute shaders always fall back to the cpu.
+
+use std::time::Duration;
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{
+        particles::{self, ParticleEmitter, ParticleRenderer},
+        HecsApp, State,
+    },
+    pipelines::gpu_particles::GpuParticleRenderer,
+    renderer::watchdog::WatchdogPhase,
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const MAX_PARTICLES: usize = 500_000;
+const LIFETIME: f32 = 4.;
+
+fn main() {
+    example_common::run::<App>("gpu_particles");
+}
+
+//====================================================================
+
+struct App {
+    since_log: f32,
+    prep_time: Duration,
+    frames: u32,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ParticleRenderer>(0);
+        state
+            .renderer
+            .add_managed_pipeline::<GpuParticleRenderer>(1);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 10., -40.));
+        state.show_fps(true);
+
+        let texture = example_common::load_checker_texture(state, 4, 1, [[255, 255, 255, 255]; 2]);
+
+        let mut emitter = ParticleEmitter::new(texture)
+            .with_gpu(true)
+            .with_max_particles(MAX_PARTICLES)
+            .with_lifetime(LIFETIME)
+            .with_velocity(glam::vec3(0., 15., 0.), glam::vec3(5., 3., 5.))
+            .with_acceleration(glam::vec3(0., -9.8, 0.))
+            .with_color(glam::vec4(0.3, 0.6, 1., 1.), glam::vec4(1., 0.3, 0.6, 0.))
+            .with_size(glam::Vec2::splat(0.05), glam::Vec2::splat(0.02));
+        emitter.spawn_rate = MAX_PARTICLES as f32 / LIFETIME;
+
+        state.seed_rng(0x5EED);
+        state
+            .world
+            .spawn((emitter, Transform::default(), GlobalTransform::default()));
+
+        Self {
+            since_log: 0.,
+            prep_time: Duration::ZERO,
+            frames: 0,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyG) {
+            state
+                .world
+                .query_mut::<&mut ParticleEmitter>()
+                .into_iter()
+                .for_each(|(_, emitter)| emitter.gpu = !emitter.gpu);
+        }
+
+        particles::process_particles(state);
+
+        self.prep_time += state
+            .renderer
+            .watchdog
+            .last_frame_phase(WatchdogPhase::Prep);
+        self.frames += 1;
+        self.since_log += state.time.delta_seconds();
+
+        if self.since_log >= 1. {
+            state
+                .world
+                .query_mut::<&ParticleEmitter>()
+                .into_iter()
+                .for_each(|(_, emitter)| {
+                    log::info!(
+                        "Gpu = {}, {} particles, {:.3}ms average prep",
+                        emitter.on_gpu(),
+                        emitter.particle_count(),
+                        self.prep_time.as_secs_f32() * 1000. / self.frames as f32
+                    );
+                });
+
+            self.since_log = 0.;
+            self.prep_time = Duration::ZERO;
+            self.frames = 0;
+        }
+
+        example_common::process_fly_controller(state);
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
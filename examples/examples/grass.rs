@@ -0,0 +1,206 @@
+//====================================================================
+// 400 grass cards swaying in the wind. Each card is drawn by the animated
+// variant of the model pipeline, so the sway costs no extra prep - compare the
+// fps overlay with the wind off by pressing G.
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{renderer::components::Model, HecsApp, State},
+    pipelines::{
+        model_renderer::ModelRenderer,
+        wind::{Wind, WindWeight},
+    },
+    renderer::{
+        lighting::GlobalLightData,
+        model::{self, LoadedMesh, ModelVertex},
+    },
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const GRID_SIZE: u32 = 20;
+const SPACING: f32 = 0.6;
+
+const WIND: Wind = Wind {
+    direction: glam::vec3(1., 0., 0.4),
+    amplitude: 0.25,
+    frequency: 0.6,
+    wavelength: 5.,
+    weight: WindWeight::Height { base: 0., top: 1. },
+};
+
+fn main() {
+    example_common::run::<App>("grass");
+}
+
+//====================================================================
+
+struct GrassCard;
+
+struct App {
+    wind: bool,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_color: glam::vec3(1., 0.97, 0.9),
+            ambient_strength: 0.9,
+        });
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 2.5, -10.));
+        state.show_fps(true);
+
+        let ground = example_common::load_cube(state);
+        let ground_texture = example_common::load_checker_texture(
+            state,
+            64,
+            8,
+            [[110, 85, 60, 255], [95, 72, 50, 255]],
+        );
+
+        state.world.spawn((
+            Model::new([(ground, ground_texture)]).with_scale(glam::vec3(
+                GRID_SIZE as f32 * SPACING,
+                0.1,
+                GRID_SIZE as f32 * SPACING,
+            )),
+            Transform::from_translation(glam::vec3(0., -0.05, 0.)),
+            GlobalTransform::default(),
+        ));
+
+        let card = load_grass_card(state);
+        let texture = example_common::load_checker_texture(
+            state,
+            16,
+            4,
+            [[70, 150, 50, 255], [90, 170, 60, 255]],
+        );
+
+        state.seed_rng(0x6A55);
+        let mut rng = state.rng.fork("grass");
+
+        let half_extent = (GRID_SIZE - 1) as f32 * SPACING / 2.;
+
+        let cards = (0..GRID_SIZE * GRID_SIZE)
+            .map(|index| {
+                let (x, z) = (index % GRID_SIZE, index / GRID_SIZE);
+                let jitter = glam::vec3(
+                    rng.gen_range_f32(-0.25..0.25),
+                    0.,
+                    rng.gen_range_f32(-0.25..0.25),
+                );
+
+                (
+                    Model::new([(card.clone(), texture.clone())]).with_wind(WIND),
+                    Transform::from_scale_rotation_translation(
+                        glam::vec3(1., rng.gen_range_f32(0.6..1.4), 1.),
+                        glam::Quat::from_rotation_y(rng.gen_range_f32(0.0..std::f32::consts::PI)),
+                        glam::vec3(
+                            x as f32 * SPACING - half_extent,
+                            0.,
+                            z as f32 * SPACING - half_extent,
+                        ) + jitter,
+                    ),
+                    GlobalTransform::default(),
+                    GrassCard,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        log::info!(
+            "Spawned {} grass cards - press G to toggle the wind",
+            cards.len()
+        );
+        state.world.spawn_batch(cards);
+
+        Self { wind: true }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyG) {
+            self.wind = !self.wind;
+            let wind = self.wind.then_some(WIND);
+
+            state
+                .world
+                .query_mut::<&mut Model>()
+                .with::<&GrassCard>()
+                .into_iter()
+                .for_each(|(_, model)| model.wind = wind);
+
+            log::info!(
+                "Wind {}",
+                match self.wind {
+                    true => "on",
+                    false => "off",
+                }
+            );
+        }
+
+        // The sway animates on the gpu, so keep frames coming
+        state.set_animation_active("wind", self.wind);
+
+        example_common::process_fly_controller(state);
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
+
+/// Two crossed blades, tapering to a point at a height of 1. Each side of a blade is
+/// its own quad so they show from behind with back face culling.
+fn load_grass_card(state: &State) -> LoadedMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    [0., std::f32::consts::FRAC_PI_2]
+        .into_iter()
+        .for_each(|angle: f32| {
+            let right = glam::vec3(angle.cos(), 0., angle.sin());
+            let normal = right.cross(glam::Vec3::Y);
+
+            // Counter clockwise seen from the side the normal faces
+            [(normal, [0, 1, 2, 0, 2, 3]), (-normal, [0, 2, 1, 0, 3, 2])]
+                .into_iter()
+                .for_each(|(normal, quad)| {
+                    let first = vertices.len() as u32;
+
+                    vertices.extend(
+                        [
+                            (-right * 0.12, glam::vec2(0., 1.)),
+                            (right * 0.12, glam::vec2(1., 1.)),
+                            (right * 0.02 + glam::Vec3::Y, glam::vec2(1., 0.)),
+                            (-right * 0.02 + glam::Vec3::Y, glam::vec2(0., 0.)),
+                        ]
+                        .map(|(pos, uv)| ModelVertex {
+                            pos,
+                            uv,
+                            normal,
+                            tangent: glam::Vec4::ZERO,
+                        }),
+                    );
+
+                    indices.extend(quad.map(|index| first + index));
+                });
+        });
+
+    model::compute_tangents(&mut vertices, &indices);
+
+    LoadedMesh::load_from_data(&state.renderer.device, &vertices, &indices)
+}
+
+//====================================================================
@@ -0,0 +1,130 @@
+//====================================================================
+// A cube orbiting in front of a camera ten million units from the origin, where
+// f32 can only place things to the nearest unit. Press L to toggle large world
+// rendering - with it off, the cube and the ground snap between positions as they
+// move. Depth is reversed, keeping it precise out to the camera's far plane.
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform, WorldPosition},
+        Size,
+    },
+    hecs::{hecs::Entity, renderer::components::Model, HecsApp, State},
+    pipelines::model_renderer::ModelRenderer,
+    renderer::{lighting::GlobalLightData, shared::DepthConvention},
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common::{self, Spin};
+
+//====================================================================
+
+const FAR_AWAY: WorldPosition = WorldPosition(glam::DVec3::new(1e7, 0., 1e7));
+const ORBIT_RADIUS: f32 = 3.;
+const ORBIT_SPEED: f32 = 0.7;
+
+fn main() {
+    example_common::run::<App>("large_world");
+}
+
+//====================================================================
+
+struct App {
+    orbiting: Entity,
+    angle: f32,
+}
+
+impl HecsApp for App {
+    fn depth_convention() -> DepthConvention {
+        DepthConvention::REVERSED
+    }
+
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+        state.renderer.set_large_world(true);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_color: glam::vec3(1., 0.95, 0.85),
+            ambient_strength: 0.9,
+        });
+
+        let camera = example_common::spawn_perspective_camera(state, glam::vec3(0., 1.5, -7.));
+        state.world.insert_one(camera, FAR_AWAY).unwrap();
+        state.show_fps(true);
+
+        let cube = example_common::load_cube(state);
+        let texture = example_common::load_checker_texture(
+            state,
+            64,
+            8,
+            [[230, 230, 230, 255], [60, 90, 160, 255]],
+        );
+
+        let orbiting = state.world.spawn((
+            Model::new([(cube.clone(), texture.clone())]).with_scale(glam::Vec3::splat(0.5)),
+            Transform::default(),
+            GlobalTransform::default(),
+            FAR_AWAY,
+            Spin {
+                axis: glam::vec3(0.3, 1., 0.2).normalize(),
+                speed: 0.8,
+            },
+        ));
+
+        // A row of flat tiles under the orbit, to judge the camera's own movement against
+        (-4..=4).for_each(|index| {
+            state.world.spawn((
+                Model::new([(cube.clone(), texture.clone())])
+                    .with_color([0.6, 0.6, 0.6, 1.])
+                    .with_scale(glam::vec3(0.9, 0.05, 0.9)),
+                Transform::from_translation(glam::vec3(index as f32, -1., 0.)),
+                GlobalTransform::default(),
+                FAR_AWAY,
+            ));
+        });
+
+        log::info!("Large world rendering on - press L to toggle");
+
+        Self {
+            orbiting,
+            angle: 0.,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyL) {
+            let enabled = !state.renderer.large_world();
+            state.renderer.set_large_world(enabled);
+
+            log::info!(
+                "Large world rendering {}",
+                match enabled {
+                    true => "on",
+                    false => "off",
+                }
+            );
+        }
+
+        self.angle += ORBIT_SPEED * state.time.delta_seconds();
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(self.orbiting) {
+            transform.translation = glam::vec3(
+                self.angle.cos() * ORBIT_RADIUS,
+                0.5,
+                self.angle.sin() * ORBIT_RADIUS,
+            );
+        }
+        state.set_animation_active("orbit", true);
+
+        example_common::process_fly_controller(state);
+        example_common::process_spin(state);
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
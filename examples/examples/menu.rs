@@ -0,0 +1,251 @@
+//====================================================================
+// A Ui3d menu floating next to a cube. Use the up/down arrow keys to
+// change the selection (hold to repeat), enter to apply it and escape
+// to jump back to the top. "Toggle spin" is disabled while the cube is
+// white and gets skipped over. The arrow marking the selection is an
+// icon drawn in the same draw call as the text.
+// The menu only redraws on input, while a key is held or while the
+// cube is spinning.
+// Tab switches the menu between facing the camera beside the cube and
+// being pinned to the top right of the screen.
+// T cycles through the built-in themes, which color the menu and the
+// background until a background is picked from the menu.
+
+use roots_core::{
+    common::{
+        coords::Uv,
+        spatial::{GlobalTransform, Transform},
+        theme::Theme,
+        Size,
+    },
+    hecs::{
+        hecs::Entity,
+        menu::{MenuController, MenuEvent},
+        renderer::components::Model,
+        HecsApp, RedrawMode, State,
+    },
+    pipelines::model_renderer::ModelRenderer,
+    renderer::{lighting::GlobalLightData, Color},
+    runner::prelude::KeyCode,
+    text::{
+        icons::IconHandle,
+        shared::Color as TextColor,
+        ui3d_renderer::{Ui3d, Ui3dIcon, Ui3dPlacement},
+    },
+};
+use roots_examples::example_common::{self, Spin};
+
+//====================================================================
+
+fn main() {
+    example_common::run::<App>("menu");
+}
+
+//====================================================================
+
+#[derive(Clone, Copy)]
+enum MenuAction {
+    Background([f64; 3]),
+    CubeColor([f32; 4]),
+    ToggleSpin,
+}
+
+const MENU: [(&str, MenuAction); 6] = [
+    ("Dark background", MenuAction::Background([0.1, 0.1, 0.1])),
+    ("Blue background", MenuAction::Background([0.1, 0.2, 0.4])),
+    ("Red cube", MenuAction::CubeColor([1., 0.3, 0.3, 1.])),
+    ("Green cube", MenuAction::CubeColor([0.3, 1., 0.3, 1.])),
+    ("White cube", MenuAction::CubeColor([1., 1., 1., 1.])),
+    ("Toggle spin", MenuAction::ToggleSpin),
+];
+
+/// Each line is 0.15 world units tall beside the cube.
+const BILLBOARD: (Ui3dPlacement, f32) = (
+    Ui3dPlacement::Billboard {
+        position: glam::vec3(0.3, 0.8, 0.),
+        scale: 1.,
+    },
+    0.15,
+);
+
+/// Screen anchored menus are measured in pixels, so each line is 24 pixels tall.
+const PINNED: (Ui3dPlacement, f32) = (
+    Ui3dPlacement::ScreenAnchored {
+        anchor: Uv::new(1., 0.),
+        offset_px: glam::vec2(-20., 20.),
+    },
+    24.,
+);
+
+struct App {
+    menu: Entity,
+    cube: Entity,
+    arrow: Option<IconHandle>,
+    controller: MenuController,
+    /// Index into `Theme::builtin`.
+    theme: usize,
+}
+
+/// A right pointing triangle, generated rather than loaded to keep the example asset free.
+fn arrow_icon() -> (u32, Vec<u8>) {
+    const SIZE: u32 = 32;
+
+    let pixels = (0..SIZE * SIZE)
+        .flat_map(|index| {
+            let x = (index % SIZE) as f32 + 0.5;
+            let y = (index / SIZE) as f32 + 0.5;
+
+            let half = SIZE as f32 / 2.;
+            let inside = (y - half).abs() < half * (1. - x / SIZE as f32);
+
+            let alpha = match inside {
+                true => 255,
+                false => 0,
+            };
+
+            [255, 255, 255, alpha]
+        })
+        .collect();
+
+    (SIZE, pixels)
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.set_redraw_mode(RedrawMode::Reactive);
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_strength: 0.9,
+            ..Default::default()
+        });
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 0., -5.));
+
+        let mesh = example_common::load_cube(state);
+        let texture = example_common::load_checker_texture(
+            state,
+            32,
+            4,
+            [[255, 255, 255, 255], [120, 120, 120, 255]],
+        );
+
+        let cube = state.world.spawn((
+            Model::new([(mesh, texture)]),
+            Transform::from_translation(glam::vec3(-1.2, 0., 0.)),
+            GlobalTransform::default(),
+            Spin {
+                axis: glam::Vec3::Y,
+                speed: 1.,
+            },
+        ));
+
+        let menu = state.world.spawn((
+            Ui3d {
+                options: MENU.iter().map(|(name, _)| name.to_string()).collect(),
+                corner_radius: 8.,
+                world_units_per_point: Some(BILLBOARD.1),
+                ..Default::default()
+            },
+            BILLBOARD.0,
+        ));
+
+        let (size, pixels) = arrow_icon();
+        let arrow = example_common::with_icons(state, |icons, _| {
+            icons.add_image("arrow", size, size, &pixels)
+        });
+
+        Self {
+            menu,
+            cube,
+            arrow,
+            controller: MenuController::new(),
+            theme: 0,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        let mut activated = None;
+
+        if state.keys.just_pressed(KeyCode::KeyT) {
+            let themes = Theme::builtin();
+            self.theme = (self.theme + 1) % themes.len();
+            state.renderer.set_theme(themes[self.theme]);
+            log::info!("Theme: {}", themes[self.theme].name);
+        }
+
+        if state.keys.just_pressed(KeyCode::Tab) {
+            if let Ok((ui, placement)) = state
+                .world
+                .query_one_mut::<(&mut Ui3d, &mut Ui3dPlacement)>(self.menu)
+            {
+                let (next, units) = match *placement == BILLBOARD.0 {
+                    true => PINNED,
+                    false => BILLBOARD,
+                };
+
+                *placement = next;
+                ui.world_units_per_point = Some(units);
+            }
+        }
+
+        if let Ok(mut ui) = state.world.get::<&mut Ui3d>(self.menu) {
+            match self.controller.update(&state.keys, &state.time, &mut ui) {
+                Some(MenuEvent::Confirmed(index)) => activated = MENU.get(index),
+                Some(MenuEvent::Cancelled) => ui.selected = 0,
+                Some(MenuEvent::SelectionChanged(_)) | None => {}
+            }
+
+            if let Some(arrow) = self.arrow {
+                let icon =
+                    Ui3dIcon::on_option(arrow, ui.selected as usize, -ui.font_size * 0.6, &ui)
+                        .with_color(TextColor::rgb(240, 200, 60));
+                ui.icons = vec![icon];
+            }
+        }
+
+        // Keeps ticking in reactive mode so held keys repeat
+        state.set_animation_active("menu", self.controller.is_held());
+
+        if let Some((name, action)) = activated {
+            log::info!("Selected '{}'", name);
+
+            match *action {
+                MenuAction::Background([r, g, b]) => {
+                    state.renderer.clear_color = Some(Color::new(r, g, b, 1.))
+                }
+
+                MenuAction::CubeColor(color) => {
+                    if let Ok(mut model) = state.world.get::<&mut Model>(self.cube) {
+                        model.color = color;
+                    }
+
+                    let spin_option = MENU.len() - 1;
+                    self.controller
+                        .set_disabled(spin_option, color == [1., 1., 1., 1.]);
+                }
+
+                MenuAction::ToggleSpin => {
+                    if let Ok(mut spin) = state.world.get::<&mut Spin>(self.cube) {
+                        spin.speed = match spin.speed == 0. {
+                            true => 1.,
+                            false => 0.,
+                        };
+                    }
+                }
+            }
+        }
+
+        example_common::process_fly_controller(state);
+        example_common::process_spin(state);
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
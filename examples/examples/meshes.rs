@@ -0,0 +1,158 @@
+//====================================================================
+// Spawns 1,000 unique small meshes loaded into a shared MeshPool. Where the
+// device supports multi draw indirect they're drawn with a single indirect
+// draw, otherwise with one draw call each. Press I to toggle the indirect
+// path and compare the draw calls and encode time logged every second.
+
+use std::time::Duration;
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{renderer::components::Model, HecsApp, State},
+    pipelines::model_renderer::ModelRenderer,
+    renderer::{
+        lighting::GlobalLightData,
+        model::{self, MeshPool},
+        watchdog::WatchdogPhase,
+    },
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const GRID_SIZE: u32 = 32;
+const MESH_COUNT: u32 = 1000;
+const SPACING: f32 = 1.5;
+
+fn main() {
+    example_common::run::<App>("meshes");
+}
+
+//====================================================================
+
+struct App {
+    since_log: f32,
+    encode_time: Duration,
+    frames: u32,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_strength: 0.9,
+            ..Default::default()
+        });
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 20., -40.));
+        state.show_fps(true);
+
+        let texture = example_common::load_checker_texture(
+            state,
+            32,
+            4,
+            [[255, 255, 255, 255], [150, 150, 150, 255]],
+        );
+
+        let mut pool = MeshPool::new(
+            &state.renderer.device,
+            MESH_COUNT * model::CUBE_VERTICES.len() as u32,
+            MESH_COUNT * model::CUBE_INDEX_COUNT,
+        );
+
+        state.seed_rng(0x5EED);
+        let mut rng = state.rng.fork("meshes");
+
+        let half_extent = (GRID_SIZE - 1) as f32 * SPACING / 2.;
+
+        let models = (0..MESH_COUNT)
+            .filter_map(|index| {
+                // Every mesh is a uniquely squashed cube
+                let stretch = glam::vec3(
+                    rng.gen_range_f32(0.3..1.),
+                    rng.gen_range_f32(0.3..1.),
+                    rng.gen_range_f32(0.3..1.),
+                );
+                let vertices = model::CUBE_VERTICES.map(|mut vertex| {
+                    vertex.pos *= stretch;
+                    vertex
+                });
+
+                let mesh = pool.load(&state.renderer.queue, &vertices, &model::CUBE_INDICES)?;
+                let (x, z) = (index % GRID_SIZE, index / GRID_SIZE);
+
+                Some((
+                    Model::new([(mesh, texture.clone())]),
+                    Transform::from_translation(glam::vec3(
+                        x as f32 * SPACING - half_extent,
+                        0.,
+                        z as f32 * SPACING - half_extent,
+                    )),
+                    GlobalTransform::default(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        log::info!("Spawned {} unique meshes", models.len());
+        state.world.spawn_batch(models);
+
+        Self {
+            since_log: 0.,
+            encode_time: Duration::ZERO,
+            frames: 0,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyI) {
+            state
+                .renderer
+                .with_managed_pipeline::<ModelRenderer, _>(|pipeline| {
+                    pipeline.set_indirect(!pipeline.indirect_enabled())
+                });
+        }
+
+        self.encode_time += state
+            .renderer
+            .watchdog
+            .last_frame_phase(WatchdogPhase::Encode);
+        self.frames += 1;
+        self.since_log += state.time.delta_seconds();
+
+        if self.since_log >= 1. {
+            let stats = state
+                .renderer
+                .with_managed_pipeline::<ModelRenderer, _>(|pipeline| {
+                    (pipeline.indirect_active(), pipeline.draw_calls())
+                });
+
+            if let Some((indirect, draw_calls)) = stats {
+                log::info!(
+                    "Indirect = {}, {} draw calls, {:.3}ms average encode",
+                    indirect,
+                    draw_calls,
+                    self.encode_time.as_secs_f32() * 1000. / self.frames as f32
+                );
+            }
+
+            self.since_log = 0.;
+            self.encode_time = Duration::ZERO;
+            self.frames = 0;
+        }
+
+        example_common::process_fly_controller(state);
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,204 @@
+//====================================================================
+// Loads a manifest of textures behind a loading bar before showing them. The
+// textures are generated and encoded to png up front so the example has no asset
+// files. One optional entry points at a missing file and is skipped with a warning.
+// A splash screen with one of the textures as its logo covers startup and stays
+// up until the first row has loaded.
+// Press R to preload again, or F to preload with a missing required entry, which
+// fails and logs its report.
+
+use std::io::Cursor;
+
+use roots_core::{
+    common::Size,
+    hecs::{
+        preload::{PreloadEntry, PreloadHandle, PreloadKind, PreloadManifest, PreloadStatus},
+        renderer::components::Sprite,
+        HecsApp, State,
+    },
+    pipelines::texture2d_renderer::Texture2dRenderer,
+    renderer::{splash::SplashScreen, uploads::UploadStrategy, Color},
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const TEXTURE_COUNT: usize = 40;
+const TEXTURE_SIZE: u32 = 256;
+const COLUMNS: usize = 8;
+
+fn main() {
+    example_common::run::<App>("preload");
+}
+
+//====================================================================
+
+/// A png of concentric rings, tinted by `index`.
+fn encode_texture(index: usize) -> Vec<u8> {
+    let hue = index as f32 / TEXTURE_COUNT as f32;
+    let centre = TEXTURE_SIZE as f32 / 2.;
+
+    let image = image::RgbaImage::from_fn(TEXTURE_SIZE, TEXTURE_SIZE, |x, y| {
+        let ring = ((x as f32 - centre).hypot(y as f32 - centre) / 16.) as u32 % 2;
+        let shade = 120 + ring as u8 * 120;
+
+        image::Rgba([
+            shade,
+            (shade as f32 * hue) as u8,
+            (shade as f32 * (1. - hue)) as u8,
+            255,
+        ])
+    });
+
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+fn manifest(missing_required: bool) -> PreloadManifest {
+    let manifest = (0..TEXTURE_COUNT).fold(PreloadManifest::new(), |manifest, index| {
+        manifest.with_entry(
+            PreloadEntry::new(
+                format!("rings_{}", index),
+                PreloadKind::Texture,
+                encode_texture(index),
+            )
+            // The first row loads first
+            .with_priority(match index < COLUMNS {
+                true => 1,
+                false => 0,
+            }),
+        )
+    });
+
+    let manifest = manifest.with_entry(
+        PreloadEntry::new("optional_extra", PreloadKind::Texture, "missing/extra.png").optional(),
+    );
+
+    match missing_required {
+        true => manifest.with_texture("required_logo", "missing/logo.png"),
+        false => manifest,
+    }
+}
+
+//====================================================================
+
+struct App {
+    preload: Option<PreloadHandle>,
+}
+
+impl App {
+    fn start(&mut self, state: &mut State, missing_required: bool) {
+        let sprites = state
+            .world
+            .query_mut::<&Sprite>()
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        sprites.into_iter().for_each(|entity| {
+            state.world.despawn(entity).ok();
+        });
+
+        self.preload = Some(state.begin_preload(manifest(missing_required)));
+        state.show_loading_bar(Some(0.));
+    }
+
+    fn show_textures(&self, state: &mut State, preload: &PreloadHandle) {
+        let size = state.size();
+        let cell = size.width as f32 / COLUMNS as f32;
+        let origin = glam::vec2(
+            (cell - size.width as f32) / 2.,
+            (size.height as f32 - cell) / 2.,
+        );
+
+        (0..TEXTURE_COUNT).for_each(|index| {
+            let Some(texture) = preload.texture(&format!("rings_{}", index)) else {
+                return;
+            };
+
+            let column = (index % COLUMNS) as f32;
+            let row = (index / COLUMNS) as f32;
+
+            state.world.spawn((Sprite {
+                texture: texture.clone(),
+                size: glam::Vec2::splat(cell * 0.9),
+                pos: glam::vec3(origin.x + column * cell, origin.y - row * cell, 1.),
+                color: glam::Vec4::ONE,
+            },));
+        });
+    }
+}
+
+impl HecsApp for App {
+    fn splash() -> Option<SplashScreen> {
+        Some(
+            SplashScreen::new(Color::new(0.05, 0.05, 0.08, 1.))
+                .with_logo(encode_texture(0))
+                .with_fade_out(0.5),
+        )
+    }
+
+    fn new(state: &mut State) -> Self {
+        state.renderer.hold_splash();
+        state.renderer.add_managed_pipeline::<Texture2dRenderer>(0);
+        example_common::spawn_orthographic_camera(state);
+        state.show_fps(true);
+
+        // Spread the uploads over frames so the bar has something to show
+        state.renderer.set_upload_strategy(UploadStrategy::Deferred);
+
+        let mut app = Self { preload: None };
+        app.start(state, false);
+        app
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyR) {
+            self.start(state, false);
+        }
+        if state.keys.just_pressed(KeyCode::KeyF) {
+            self.start(state, true);
+        }
+
+        if let Some(mut preload) = self.preload.take() {
+            preload.update(state);
+            state.show_loading_bar(Some(preload.progress()));
+
+            let first_row_loaded =
+                (0..COLUMNS).all(|index| preload.texture(&format!("rings_{}", index)).is_some());
+
+            if first_row_loaded || preload.status() != PreloadStatus::Loading {
+                state.renderer.finish_splash();
+            }
+
+            match preload.status() {
+                PreloadStatus::Loading => self.preload = Some(preload),
+
+                PreloadStatus::Complete => {
+                    log::info!(
+                        "Preload complete, {} optional entries skipped",
+                        preload.report().optional().count()
+                    );
+
+                    state.show_loading_bar(None);
+                    self.show_textures(state, &preload);
+                }
+
+                PreloadStatus::Failed => {
+                    log::error!("Preload failed:\n{}", preload.report());
+                    state.show_loading_bar(None);
+                }
+            }
+        }
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
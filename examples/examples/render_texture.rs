@@ -0,0 +1,147 @@
+//====================================================================
+// A security camera sweeping over spinning cubes, rendered into an
+// offscreen target and shown on a screen standing in the same scene.
+// The screen is drawn by the same model pipeline it's rendered with,
+// so it also shows up on itself one frame late. Press C to pause the
+// security camera's sweep.
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{
+        renderer::components::{Camera, Model, OffscreenCamera},
+        HecsApp, State,
+    },
+    pipelines::model_renderer::ModelRenderer,
+    renderer::{camera::PerspectiveCamera, lighting::GlobalLightData, Color},
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common::{self, Spin};
+
+//====================================================================
+
+const SCREEN_SIZE: Size<u32> = Size {
+    width: 640,
+    height: 360,
+};
+
+fn main() {
+    example_common::run::<App>("render_texture");
+}
+
+//====================================================================
+
+struct SecurityCamera;
+
+struct App {
+    sweeping: bool,
+    elapsed: f32,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_color: glam::vec3(1., 0.95, 0.85),
+            ambient_strength: 0.9,
+        });
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 2., -9.));
+        state.show_fps(true);
+
+        let cube = example_common::load_cube(state);
+        let texture = example_common::load_checker_texture(
+            state,
+            64,
+            8,
+            [[230, 230, 230, 255], [60, 90, 160, 255]],
+        );
+
+        let colors = [
+            [1., 0.4, 0.4, 1.],
+            [0.4, 1., 0.4, 1.],
+            [0.4, 0.4, 1., 1.],
+            [1., 1., 0.4, 1.],
+            [1., 0.4, 1., 1.],
+        ];
+
+        colors.into_iter().enumerate().for_each(|(index, color)| {
+            let x = (index as f32 - 2.) * 1.8;
+
+            state.world.spawn((
+                Model::new([(cube.clone(), texture.clone())])
+                    .with_color(color)
+                    .with_scale(glam::Vec3::splat(0.8)),
+                Transform::from_translation(glam::vec3(x, 0., 3.)),
+                GlobalTransform::default(),
+                Spin {
+                    axis: glam::vec3(0.3, 1., 0.2).normalize(),
+                    speed: 0.5 + index as f32 * 0.3,
+                },
+            ));
+        });
+
+        let data = PerspectiveCamera {
+            aspect: SCREEN_SIZE.width as f32 / SCREEN_SIZE.height as f32,
+            fovy: 50_f32.to_radians(),
+            ..Default::default()
+        };
+
+        let offscreen = OffscreenCamera::new_sized(&state.renderer, SCREEN_SIZE, "Security Camera")
+            .with_clear_color(Color::new(0.05, 0.08, 0.1, 1.));
+        let screen_texture = offscreen.target().color().clone();
+
+        state.world.spawn((
+            Camera::new_perspective(&state.renderer, &data),
+            data,
+            offscreen,
+            Transform::from_translation(glam::vec3(0., 4., -4.)).looking_at(glam::vec3(0., 0., 3.)),
+            GlobalTransform::default(),
+            SecurityCamera,
+        ));
+
+        // The screen, off to the side where both cameras can see it
+        state.world.spawn((
+            Model::new([(cube.clone(), screen_texture)]).with_scale(glam::vec3(3.2, 1.8, 0.05)),
+            Transform::from_translation(glam::vec3(-5., 2.5, 3.))
+                .looking_at(glam::vec3(0., 2., -9.)),
+            GlobalTransform::default(),
+        ));
+
+        Self {
+            sweeping: true,
+            elapsed: 0.,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyC) {
+            self.sweeping = !self.sweeping;
+        }
+
+        if self.sweeping {
+            self.elapsed += state.time.delta_seconds();
+            let target = glam::vec3(self.elapsed.sin() * 4., 0., 3.);
+
+            state
+                .world
+                .query_mut::<&mut Transform>()
+                .with::<&SecurityCamera>()
+                .into_iter()
+                .for_each(|(_, transform)| transform.look_at(target, glam::Vec3::Y));
+        }
+
+        example_common::process_fly_controller(state);
+        example_common::process_spin(state);
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,105 @@
+//====================================================================
+// A one pixel checkerboard filling the window, which only looks sharp while the
+// surface matches the window's physical size. Resizing the window logs the new size
+// and F11 toggles fullscreen.
+//
+// On the web the canvas follows the size of the `roots_app` element, so a page like
+// this lets the container be dragged to any size:
+//
+//     <div id="roots_app" style="width: 640px; height: 480px; resize: both; overflow: hidden;"></div>
+
+use roots_core::{
+    common::Size,
+    hecs::{renderer::components::Sprite, HecsApp, State},
+    pipelines::texture2d_renderer::Texture2dRenderer,
+    renderer::texture::LoadedTexture,
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const CHECKER_SIZE: u32 = 256;
+
+fn main() {
+    example_common::run::<App>("resize");
+}
+
+//====================================================================
+
+struct App {
+    checker: LoadedTexture,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<Texture2dRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+        example_common::spawn_orthographic_camera(state);
+        state.show_fps(true);
+
+        let checker = example_common::load_checker_texture(
+            state,
+            CHECKER_SIZE,
+            CHECKER_SIZE,
+            [[255, 255, 255, 255], [0, 0, 0, 255]],
+        );
+
+        let mut app = Self { checker };
+        app.spawn_tiles(state, state.size());
+        app
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        log::info!("Window resized to {}", size);
+
+        example_common::resize_cameras(state, size);
+        self.spawn_tiles(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::F11) {
+            if let Some(window) = &state.window {
+                window.set_fullscreen(!window.is_fullscreen());
+            }
+        }
+
+        example_common::finish_tick(state);
+    }
+}
+
+impl App {
+    /// Cover the window in tiles with one texel per physical pixel.
+    fn spawn_tiles(&mut self, state: &mut State, size: Size<u32>) {
+        let despawn = state
+            .world
+            .query_mut::<&Sprite>()
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        despawn.into_iter().for_each(|entity| {
+            state.world.despawn(entity).ok();
+        });
+
+        let tile = CHECKER_SIZE as f32;
+        let columns = size.width.div_ceil(CHECKER_SIZE) as i32;
+        let rows = size.height.div_ceil(CHECKER_SIZE) as i32;
+        let origin = -glam::vec2(size.width as f32, size.height as f32) / 2.;
+
+        (0..columns)
+            .flat_map(|x| (0..rows).map(move |y| (x, y)))
+            .for_each(|(x, y)| {
+                let center = origin + glam::vec2(x as f32 + 0.5, y as f32 + 0.5) * tile;
+
+                state.world.spawn((Sprite {
+                    texture: self.checker.clone(),
+                    size: glam::Vec2::splat(tile),
+                    pos: center.extend(1.),
+                    color: glam::Vec4::ONE,
+                },));
+            });
+    }
+}
+
+//====================================================================
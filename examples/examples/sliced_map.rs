@@ -0,0 +1,181 @@
+//====================================================================
+// Generates and spawns a 100k tile map as a sliced task, so frames stay within
+// the task budget while a loading bar shows its progress.
+// Press G to generate the map again as a sliced task, E to generate it eagerly
+// in a single frame for comparison, or C to cancel a generation in progress.
+
+use roots_core::{
+    common::Size,
+    hecs::{
+        renderer::components::Sprite,
+        tasks::{self, TaskId},
+        HecsApp, State,
+    },
+    pipelines::texture2d_renderer::Texture2dRenderer,
+    renderer::texture::LoadedTexture,
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const MAP_SIZE: u32 = 316;
+const TILE_SIZE: f32 = 2.;
+
+fn main() {
+    example_common::run::<App>("sliced_map");
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy)]
+struct Tile;
+
+/// A few octaves of sine noise, slow enough that generating every tile at once
+/// takes a noticeable freeze.
+fn tile_height(x: u32, y: u32) -> f32 {
+    let (x, y) = (x as f32, y as f32);
+
+    (1..=64).fold(0., |height, octave| {
+        let frequency = octave as f32 * 0.013;
+        height + (x * frequency).sin() * (y * frequency * 1.3).cos() / octave as f32
+    })
+}
+
+fn generate_tile(texture: &LoadedTexture, index: u32) -> (Sprite, Tile) {
+    let (x, y) = (index % MAP_SIZE, index / MAP_SIZE);
+    let height = tile_height(x, y).clamp(-1., 1.) * 0.5 + 0.5;
+
+    let color = match height {
+        h if h < 0.4 => glam::vec4(0.1, 0.3, 0.8, 1.),
+        h if h < 0.45 => glam::vec4(0.8, 0.8, 0.5, 1.),
+        h if h < 0.7 => glam::vec4(0.2, 0.4 + h * 0.4, 0.2, 1.),
+        h => glam::vec4(h, h, h, 1.),
+    };
+
+    let half = MAP_SIZE as f32 * TILE_SIZE / 2.;
+
+    (
+        Sprite {
+            texture: texture.clone(),
+            size: glam::Vec2::splat(TILE_SIZE),
+            pos: glam::vec3(x as f32 * TILE_SIZE - half, y as f32 * TILE_SIZE - half, 1.),
+            color,
+        },
+        Tile,
+    )
+}
+
+//====================================================================
+
+struct App {
+    texture: LoadedTexture,
+    generating: Option<TaskId>,
+    /// Frames since the sliced generation started.
+    frames: u32,
+}
+
+impl App {
+    fn clear_map(state: &mut State) {
+        let tiles = state
+            .world
+            .query_mut::<()>()
+            .with::<&Tile>()
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        tiles.into_iter().for_each(|entity| {
+            state.world.despawn(entity).ok();
+        });
+    }
+
+    fn cancel(&mut self, state: &mut State) {
+        if let Some(task) = self.generating.take() {
+            state.tasks.cancel(task);
+            state.show_loading_bar(None);
+        }
+    }
+
+    fn generate_sliced(&mut self, state: &mut State) {
+        self.cancel(state);
+        Self::clear_map(state);
+
+        let texture = self.texture.clone();
+        let tiles = (0..MAP_SIZE * MAP_SIZE).map(move |index| generate_tile(&texture, index));
+
+        self.generating = Some(state.add_task("Generate map", tasks::sliced_spawn(tiles)));
+        self.frames = 0;
+        state.show_loading_bar(Some(0.));
+    }
+
+    fn generate_eager(&mut self, state: &mut State) {
+        self.cancel(state);
+        Self::clear_map(state);
+
+        let start = std::time::Instant::now();
+        let tiles = (0..MAP_SIZE * MAP_SIZE)
+            .map(|index| generate_tile(&self.texture, index))
+            .collect::<Vec<_>>();
+        state.world.spawn_batch(tiles);
+
+        log::info!("Generated map eagerly in {:?}", start.elapsed());
+    }
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<Texture2dRenderer>(0);
+        example_common::spawn_orthographic_camera(state);
+        state.show_fps(true);
+
+        let texture = LoadedTexture::load_blank(
+            &state.renderer.device,
+            &state.renderer.queue,
+            &state.renderer.shared,
+        );
+
+        let mut app = Self {
+            texture,
+            generating: None,
+            frames: 0,
+        };
+        app.generate_sliced(state);
+        app
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyG) {
+            self.generate_sliced(state);
+        }
+        if state.keys.just_pressed(KeyCode::KeyE) {
+            self.generate_eager(state);
+        }
+        if state.keys.just_pressed(KeyCode::KeyC) {
+            self.cancel(state);
+        }
+
+        if let Some(task) = self.generating {
+            match state.tasks.is_running(task) {
+                true => {
+                    self.frames += 1;
+                    let progress = state.tasks.progress(task).unwrap_or_default();
+                    state.show_loading_bar(Some(progress));
+                }
+                false => {
+                    log::info!("Generated map over {} frames", self.frames);
+                    state.show_loading_bar(None);
+                    self.generating = None;
+                }
+            }
+        }
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
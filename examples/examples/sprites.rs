@@ -0,0 +1,174 @@
+//====================================================================
+// Bouncing 2D sprites rendered through an orthographic camera. Press F3 to
+// toggle the draw order debug view. Drag with the middle mouse button to pan,
+// scroll to zoom toward the cursor and press Home to frame every sprite.
+// Two parallax layers scroll behind the sprites at different speeds while panning.
+
+use roots_core::{
+    common::Size,
+    hecs::{
+        pan_zoom::{self, PanZoomController},
+        renderer::components::{ParallaxLayer, Sprite},
+        HecsApp, State,
+    },
+    pipelines::{
+        parallax_renderer::{ParallaxRenderer, ParallaxTiling},
+        texture2d_renderer::Texture2dRenderer,
+    },
+    renderer::camera::OrthographicCamera,
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const SPRITE_COUNT: usize = 200;
+
+fn main() {
+    example_common::run::<App>("sprites");
+}
+
+//====================================================================
+
+struct Velocity(glam::Vec2);
+
+struct App {
+    bounds: glam::Vec2,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ParallaxRenderer>(0);
+        state.renderer.add_managed_pipeline::<Texture2dRenderer>(1);
+        example_common::add_ui3d_pipeline(state, 10);
+        let camera = example_common::spawn_orthographic_camera(state);
+
+        // Builds the draw order debug pipeline now so the first F3 doesn't stall
+        state.renderer.prewarm_pipelines();
+
+        let size = state.size();
+        let bounds = glam::vec2(size.width as f32, size.height as f32) / 2.;
+
+        state
+            .world
+            .insert_one(
+                camera,
+                PanZoomController::default()
+                    .with_zoom_limits(0.5, 8.)
+                    .with_bounds(-bounds * 2., bounds * 2.),
+            )
+            .unwrap();
+
+        let textures = [
+            example_common::load_checker_texture(
+                state,
+                16,
+                2,
+                [[255, 255, 255, 255], [180, 180, 180, 255]],
+            ),
+            example_common::load_checker_texture(
+                state,
+                16,
+                4,
+                [[255, 255, 255, 255], [120, 120, 120, 255]],
+            ),
+        ];
+
+        // A distant sky filling the view and a nearer strip of hills with gaps cut out
+        let sky = example_common::load_checker_texture(
+            state,
+            128,
+            2,
+            [[40, 50, 90, 255], [50, 60, 105, 255]],
+        );
+        let hills =
+            example_common::load_checker_texture(state, 64, 4, [[60, 90, 70, 255], [0, 0, 0, 0]]);
+
+        state.world.spawn((ParallaxLayer::new(sky, 0.1, 100.)
+            .with_tiling(ParallaxTiling::Repeat)
+            .with_tile_size(glam::Vec2::splat(256.)),));
+        state.world.spawn((ParallaxLayer::new(hills, 0.4, 50.)
+            .with_tile_size(glam::Vec2::splat(128.))
+            .with_offset_y(-bounds.y)
+            .with_pixel_snap(true),));
+
+        // Golden angle spread so the sprites start evenly distributed
+        (0..SPRITE_COUNT).for_each(|index| {
+            let t = index as f32 / SPRITE_COUNT as f32;
+            let angle = index as f32 * 2.399;
+
+            let pos = glam::Vec2::from_angle(angle) * bounds.min_element() * t.sqrt() * 0.9;
+            let velocity = glam::Vec2::from_angle(angle * 1.7) * (60. + 140. * t);
+
+            state.world.spawn((
+                Sprite {
+                    texture: textures[index % 2].clone(),
+                    size: glam::Vec2::splat(16. + 24. * (1. - t)),
+                    pos: pos.extend(1. + t),
+                    color: glam::vec4(0.3 + 0.7 * t, 0.5, 1. - 0.7 * t, 1.),
+                },
+                Velocity(velocity),
+            ));
+        });
+
+        Self { bounds }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+        self.bounds = glam::vec2(size.width as f32, size.height as f32) / 2.;
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::F3) {
+            let enabled = !state.renderer.draw_order_debug();
+            state.renderer.set_draw_order_debug(enabled);
+        }
+
+        if state.keys.just_pressed(KeyCode::Home) {
+            let (min, max) = state.world.query_mut::<&Sprite>().into_iter().fold(
+                (glam::Vec2::MAX, glam::Vec2::MIN),
+                |(min, max), (_, sprite)| {
+                    let half_size = sprite.size / 2.;
+                    let pos = sprite.pos.truncate();
+                    (min.min(pos - half_size), max.max(pos + half_size))
+                },
+            );
+
+            state
+                .world
+                .query_mut::<&mut OrthographicCamera>()
+                .into_iter()
+                .for_each(|(_, camera)| camera.fit_rect(min, max, 20.));
+        }
+
+        pan_zoom::process_pan_zoom(state);
+
+        let delta = state.time.delta_seconds();
+        let bounds = self.bounds;
+
+        state
+            .world
+            .query_mut::<(&mut Sprite, &mut Velocity)>()
+            .into_iter()
+            .for_each(|(_, (sprite, velocity))| {
+                let half_size = sprite.size / 2.;
+                let pos = sprite.pos.truncate() + velocity.0 * delta;
+
+                // Bounce off the edges of the window
+                if pos.x.abs() + half_size.x > bounds.x {
+                    velocity.0.x = -velocity.0.x.abs() * pos.x.signum();
+                }
+                if pos.y.abs() + half_size.y > bounds.y {
+                    velocity.0.y = -velocity.0.y.abs() * pos.y.signum();
+                }
+
+                sprite.pos = pos.extend(sprite.pos.z);
+            });
+
+        example_common::update_draw_order_labels(state, 1.);
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,149 @@
+//====================================================================
+// Spawns 2,000 static crates in two textures. Press B to bake them into a
+// handful of merged meshes and again to restore the individual models,
+// comparing the draw calls logged every second. The crates never move, so their
+// transforms are tracked and only propagated on the frame they're spawned.
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{
+        renderer::{
+            components::Model,
+            static_geometry::{self, MeshData, StaticBakeSettings, StaticGeometry},
+        },
+        tracked::Tracked,
+        HecsApp, State,
+    },
+    pipelines::model_renderer::ModelRenderer,
+    renderer::{lighting::GlobalLightData, model},
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const CRATE_COUNT: u32 = 2000;
+const GRID_SIZE: u32 = 50;
+const SPACING: f32 = 2.;
+
+fn main() {
+    example_common::run::<App>("static_bake");
+}
+
+//====================================================================
+
+struct App {
+    baked: bool,
+    since_log: f32,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_strength: 0.9,
+            ..Default::default()
+        });
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 30., -70.));
+        state.show_fps(true);
+
+        let cube = example_common::load_cube(state);
+        let data = MeshData::new(model::CUBE_VERTICES, model::CUBE_INDICES);
+
+        let textures = [
+            example_common::load_checker_texture(
+                state,
+                32,
+                4,
+                [[200, 150, 90, 255], [150, 100, 60, 255]],
+            ),
+            example_common::load_checker_texture(
+                state,
+                32,
+                2,
+                [[120, 120, 120, 255], [90, 90, 90, 255]],
+            ),
+        ];
+
+        state.seed_rng(0x5EED);
+        let mut rng = state.rng.fork("static_bake");
+
+        let half_extent = (GRID_SIZE - 1) as f32 * SPACING / 2.;
+
+        let crates = (0..CRATE_COUNT)
+            .map(|index| {
+                let (x, z) = (index % GRID_SIZE, index / GRID_SIZE);
+                // Stacked crates, some sharing faces so welding has work to do
+                let y = (index % 3) as f32;
+
+                (
+                    Model::new([(cube.clone(), textures[index as usize % 2].clone())]),
+                    StaticGeometry::new([data.clone()]),
+                    Tracked::new(Transform::from_rotation_translation(
+                        glam::Quat::from_rotation_y(rng.gen_range_f32(0. ..0.3)),
+                        glam::vec3(
+                            x as f32 * SPACING - half_extent,
+                            y,
+                            z as f32 * SPACING - half_extent,
+                        ),
+                    )),
+                    GlobalTransform::default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        state.world.spawn_batch(crates);
+
+        Self {
+            baked: false,
+            since_log: 0.,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyB) {
+            match self.baked {
+                true => static_geometry::unbake_static_geometry(&mut state.world),
+                false => {
+                    let merged = static_geometry::bake_static_geometry(
+                        &mut state.world,
+                        &state.renderer.device,
+                        StaticBakeSettings::default(),
+                        |_| true,
+                    );
+                    log::info!("Baked {} crates into {} meshes", CRATE_COUNT, merged.len());
+                }
+            }
+
+            self.baked = !self.baked;
+        }
+
+        self.since_log += state.time.delta_seconds();
+
+        if self.since_log >= 1. {
+            if let Some(draw_calls) = state
+                .renderer
+                .with_managed_pipeline::<ModelRenderer, _>(|pipeline| pipeline.draw_calls())
+            {
+                log::info!("Baked = {}, {} draw calls", self.baked, draw_calls);
+            }
+
+            self.since_log = 0.;
+        }
+
+        example_common::process_fly_controller(state);
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,197 @@
+//====================================================================
+// Spawns 10,000 spinning cubes as a baseline for performance work.
+// Press P to pause the spinning and measure a static scene. Cubes outside
+// the view are culled - C toggles culling, B shows the culling bounds and I
+// logs the culling stats. Clicking a cube picks it with the same bounds.
+// R toggles dynamic resolution, aiming for 20ms frames.
+
+use std::time::Duration;
+
+use roots_core::{
+    common::{
+        spatial::{Aabb, GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{
+        gizmo::Ray,
+        hecs::Entity,
+        renderer::{
+            components::{LineBundle, Model, RenderBounds},
+            culling,
+        },
+        HecsApp, State,
+    },
+    pipelines::{line_renderer::LineRenderer, model_renderer::ModelRenderer},
+    renderer::lighting::GlobalLightData,
+    runner::prelude::{KeyCode, MouseButton},
+};
+use roots_examples::example_common::{self, Spin};
+
+//====================================================================
+
+const GRID_SIZE: u32 = 100;
+const SPACING: f32 = 2.;
+
+fn main() {
+    example_common::run::<App>("stress");
+}
+
+//====================================================================
+
+const CUBE_BOUNDS: RenderBounds = RenderBounds::Aabb(Aabb {
+    min: glam::Vec3::splat(-0.5),
+    max: glam::Vec3::splat(0.5),
+});
+
+struct App {
+    paused: bool,
+    /// Holds the bounds outlines while they're shown.
+    bounds_lines: Option<Entity>,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        state.renderer.add_managed_pipeline::<LineRenderer>(5);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_strength: 0.9,
+            ..Default::default()
+        });
+
+        let camera = example_common::spawn_perspective_camera(state, glam::vec3(0., 40., -120.));
+        if let Ok(mut controller) = state
+            .world
+            .get::<&mut example_common::FlyController>(camera)
+        {
+            controller.look.pitch = 0.3;
+            controller.speed = 40.;
+        }
+        state.show_fps(true);
+
+        let cube = example_common::load_cube(state);
+        let texture = example_common::load_checker_texture(
+            state,
+            32,
+            4,
+            [[255, 255, 255, 255], [150, 150, 150, 255]],
+        );
+
+        let half_extent = (GRID_SIZE - 1) as f32 * SPACING / 2.;
+
+        // Seeded so every run spins the same way, keeping measurements comparable
+        state.seed_rng(0x5EED);
+        let mut rng = state.rng.fork("stress_spin");
+
+        let cubes = (0..GRID_SIZE * GRID_SIZE).map(move |index| {
+            let (x, z) = (index % GRID_SIZE, index / GRID_SIZE);
+            let t = glam::vec2(x as f32, z as f32) / GRID_SIZE as f32;
+
+            (
+                Model::new([(cube.clone(), texture.clone())]).with_color([t.x, 0.5, t.y, 1.]),
+                Transform::from_translation(glam::vec3(
+                    x as f32 * SPACING - half_extent,
+                    0.,
+                    z as f32 * SPACING - half_extent,
+                )),
+                GlobalTransform::default(),
+                CUBE_BOUNDS,
+                Spin {
+                    axis: rng.gen_unit_vec3(),
+                    speed: rng.gen_range_f32(0.5..2.),
+                },
+            )
+        });
+
+        state.world.spawn_batch(cubes);
+        log::info!("Spawned {} cubes", GRID_SIZE * GRID_SIZE);
+
+        Self {
+            paused: false,
+            bounds_lines: None,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if state.keys.just_pressed(KeyCode::KeyP) {
+            self.paused = !self.paused;
+            log::info!("Spinning paused = {}", self.paused);
+        }
+
+        if state.keys.just_pressed(KeyCode::KeyC) {
+            let enabled = !state.renderer.culling.enabled();
+            state.renderer.culling.set_enabled(enabled);
+            log::info!("Culling enabled = {}", enabled);
+        }
+
+        if state.keys.just_pressed(KeyCode::KeyI) {
+            log::info!("Culling stats: {:?}", state.renderer.culling.stats());
+        }
+
+        if state.keys.just_pressed(KeyCode::KeyR) {
+            match state.renderer.resolution.is_dynamic() {
+                true => state.renderer.set_resolution_scale(1.),
+                false => state
+                    .renderer
+                    .set_dynamic_resolution(Some(Duration::from_millis(20))),
+            }
+            log::info!(
+                "Dynamic resolution = {}",
+                state.renderer.resolution.is_dynamic()
+            );
+        }
+
+        if state.keys.just_pressed(KeyCode::KeyB) {
+            self.bounds_lines = match self.bounds_lines.take() {
+                Some(entity) => {
+                    let _ = state.world.despawn(entity);
+                    None
+                }
+                None => Some(state.world.spawn((LineBundle { lines: Vec::new() },))),
+            };
+        }
+
+        // Outlines are from the last culling pass, so they lag a frame behind
+        if let Some(entity) = self.bounds_lines {
+            let lines = state.renderer.culling.bounds_lines(
+                &mut state.world,
+                glam::vec4(0.2, 1., 0.2, 1.),
+                glam::vec4(1., 0.2, 0.2, 1.),
+            );
+
+            if let Ok(mut bundle) = state.world.get::<&mut LineBundle>(entity) {
+                bundle.lines = lines;
+            }
+        }
+
+        if state.mouse_buttons.just_pressed(MouseButton::Left) {
+            let origin = state.renderer.origin;
+            let picked = culling::camera_view_projection(&mut state.world, &origin)
+                .and_then(|view_projection| {
+                    Ray::from_screen(state.mouse_input.position(), state.size(), view_projection)
+                })
+                .and_then(|ray| culling::pick(&mut state.world, &origin, &ray));
+
+            match picked {
+                Some((entity, distance)) => {
+                    log::info!("Picked {:?} at distance {:.1}", entity, distance)
+                }
+                None => log::info!("Nothing picked"),
+            }
+        }
+
+        example_common::process_fly_controller(state);
+        if !self.paused {
+            example_common::process_spin(state);
+        }
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,162 @@
+//====================================================================
+// Two rows of 48 crates down a long corridor, each with its own 1024x1024
+// texture. The textures are uploaded as streamed textures, so only their
+// smallest mips are resident until the camera gets close, all within a 64 MiB
+// texture budget. Fly down the corridor and press M to log what's resident.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use roots_core::{
+    common::{
+        spatial::{GlobalTransform, Transform},
+        Size,
+    },
+    hecs::{renderer::components::Model, HecsApp, State},
+    pipelines::model_renderer::ModelRenderer,
+    renderer::{
+        lighting::GlobalLightData,
+        memory::{format_bytes, GpuMemoryTracker, MemoryCategory},
+        model::LoadedMesh,
+        uploads::{UploadContext, UploadStrategy, UploadTicket, Uploaded},
+    },
+    runner::prelude::KeyCode,
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const ROW_LENGTH: usize = 48;
+const TEXTURE_SIZE: u32 = 1024;
+const SPACING: f32 = 6.;
+const MEMORY_BUDGET: u64 = 64 * 1024 * 1024;
+
+fn main() {
+    example_common::run::<App>("texture_streaming");
+}
+
+//====================================================================
+
+struct App {
+    crate_mesh: LoadedMesh,
+    receiver: Receiver<(usize, UploadTicket)>,
+    tickets: Vec<(usize, UploadTicket)>,
+}
+
+/// Generate the images, with their mips, and push them to the upload queue. Runs on
+/// a worker thread on native, and inline on wasm.
+fn produce(context: UploadContext, sender: Sender<(usize, UploadTicket)>) {
+    (0..ROW_LENGTH * 2).for_each(|index| {
+        let hue = index as f32 / (ROW_LENGTH * 2) as f32;
+
+        // Fine lines that only resolve at full resolution, so blurry mips are easy to spot
+        let image = image::RgbaImage::from_fn(TEXTURE_SIZE, TEXTURE_SIZE, |x, y| {
+            let plank = (y / 128) % 2 == 0;
+            let grain = (x + y / 3) % 16 < 2;
+
+            let shade = match (plank, grain) {
+                (_, true) => 90,
+                (true, false) => 220,
+                (false, false) => 180,
+            } as f32;
+
+            image::Rgba([
+                (shade * (0.6 + hue * 0.4)) as u8,
+                (shade * 0.75) as u8,
+                (shade * (1. - hue) * 0.5) as u8,
+                255,
+            ])
+        });
+
+        let ticket = context.upload_streamed_texture(
+            &image::DynamicImage::ImageRgba8(image),
+            Some(format!("Crate Texture {}", index)),
+        );
+
+        let _ = sender.send((index, ticket));
+    });
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<ModelRenderer>(0);
+        example_common::add_ui3d_pipeline(state, 10);
+
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_color: glam::vec3(1., 1., 1.),
+            ambient_strength: 1.,
+        });
+
+        example_common::spawn_perspective_camera(state, glam::vec3(0., 2., -10.));
+        state.show_fps(true);
+
+        state
+            .renderer
+            .streaming
+            .set_memory_budget(Some(MEMORY_BUDGET));
+        state.renderer.set_upload_strategy(UploadStrategy::Deferred);
+
+        let context = state.renderer.upload_context();
+        let (sender, receiver) = mpsc::channel();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || produce(context, sender));
+
+        #[cfg(target_arch = "wasm32")]
+        produce(context, sender);
+
+        Self {
+            crate_mesh: example_common::load_cube(state),
+            receiver,
+            tickets: Vec::new(),
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        self.tickets.extend(self.receiver.try_iter());
+
+        self.tickets.retain(|(index, ticket)| {
+            let Some(Uploaded::Texture(texture)) = ticket.take() else {
+                return true;
+            };
+
+            let side = match index % 2 {
+                0 => -1.,
+                _ => 1.,
+            };
+
+            state.world.spawn((
+                Model::new([(self.crate_mesh.clone(), texture)]).with_scale(glam::Vec3::splat(3.)),
+                Transform::from_translation(glam::vec3(
+                    side * 4.,
+                    1.5,
+                    (index / 2) as f32 * SPACING,
+                )),
+                GlobalTransform::default(),
+            ));
+
+            false
+        });
+
+        if state.keys.just_pressed(KeyCode::KeyM) {
+            let stats = state.renderer.streaming.stats();
+
+            log::info!(
+                "{} streamed textures with {} of detail resident, {} pending - {} of textures in total",
+                stats.textures,
+                format_bytes(stats.detail_bytes),
+                stats.pending,
+                format_bytes(GpuMemoryTracker::total(MemoryCategory::Textures)),
+            );
+        }
+
+        example_common::process_fly_controller(state);
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
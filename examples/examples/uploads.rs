@@ -0,0 +1,148 @@
+//====================================================================
+// Streams 200 textures in during gameplay. A worker thread generates the
+// images and pushes them through the deferred upload queue, which creates a
+// few each frame within its time budget. Each texture is shown as a sprite
+// once it's ready, and the longest frame while loading is logged.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use roots_core::{
+    common::Size,
+    hecs::{renderer::components::Sprite, HecsApp, State},
+    pipelines::texture2d_renderer::Texture2dRenderer,
+    renderer::uploads::{UploadContext, UploadStrategy, UploadTicket, Uploaded},
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const TEXTURE_COUNT: usize = 200;
+const TEXTURE_SIZE: u32 = 512;
+const COLUMNS: usize = 20;
+
+fn main() {
+    example_common::run::<App>("uploads");
+}
+
+//====================================================================
+
+struct App {
+    receiver: Receiver<(usize, UploadTicket)>,
+    tickets: Vec<(usize, UploadTicket)>,
+    loaded: usize,
+    worst_frame: f32,
+}
+
+/// Generate the images and push them to the upload queue. Runs on a worker thread
+/// on native, and inline on wasm where the images are still trickled in by the queue.
+fn produce(context: UploadContext, sender: Sender<(usize, UploadTicket)>) {
+    (0..TEXTURE_COUNT).for_each(|index| {
+        let hue = index as f32 / TEXTURE_COUNT as f32;
+
+        let centre = TEXTURE_SIZE as f32 / 2.;
+
+        let image = image::RgbaImage::from_fn(TEXTURE_SIZE, TEXTURE_SIZE, |x, y| {
+            let ring = ((x as f32 - centre).hypot(y as f32 - centre) / 24.) as u32 % 2;
+            let shade = 140 + ring as u8 * 100;
+
+            image::Rgba([
+                (shade as f32 * hue) as u8,
+                (shade as f32 * (1. - hue)) as u8,
+                shade,
+                255,
+            ])
+        });
+
+        let ticket = context.upload_texture(
+            image::DynamicImage::ImageRgba8(image),
+            Some(format!("Streamed Texture {}", index)),
+        );
+
+        let _ = sender.send((index, ticket));
+    });
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<Texture2dRenderer>(0);
+        example_common::spawn_orthographic_camera(state);
+        state.show_fps(true);
+
+        // The default on native creates textures directly on the worker. Deferring
+        // shows the budgeted path used on wasm.
+        state.renderer.set_upload_strategy(UploadStrategy::Deferred);
+
+        let context = state.renderer.upload_context();
+        let (sender, receiver) = mpsc::channel();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || produce(context, sender));
+
+        #[cfg(target_arch = "wasm32")]
+        produce(context, sender);
+
+        Self {
+            receiver,
+            tickets: Vec::new(),
+            loaded: 0,
+            worst_frame: 0.,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        let size = state.size();
+        let cell = size.width as f32 / COLUMNS as f32;
+        let origin = glam::vec2(
+            (cell - size.width as f32) / 2.,
+            (size.height as f32 - cell) / 2.,
+        );
+
+        self.tickets.extend(self.receiver.try_iter());
+
+        // Frames are only measured once the first upload is queued
+        if !self.tickets.is_empty() {
+            self.worst_frame = self.worst_frame.max(state.time.delta_seconds());
+        }
+
+        let mut loaded = 0;
+
+        self.tickets.retain(|(index, ticket)| {
+            let Some(Uploaded::Texture(texture)) = ticket.take() else {
+                return true;
+            };
+
+            let column = (index % COLUMNS) as f32;
+            let row = (index / COLUMNS) as f32;
+
+            state.world.spawn((Sprite {
+                texture,
+                size: glam::Vec2::splat(cell * 0.9),
+                pos: glam::vec3(origin.x + column * cell, origin.y - row * cell, 1.),
+                color: glam::Vec4::ONE,
+            },));
+
+            loaded += 1;
+            false
+        });
+
+        if loaded > 0 {
+            self.loaded += loaded;
+
+            if self.loaded == TEXTURE_COUNT {
+                log::info!(
+                    "Loaded {} textures, longest frame {:.1}ms",
+                    TEXTURE_COUNT,
+                    self.worst_frame * 1000.
+                );
+            }
+        }
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,188 @@
+//====================================================================
+// Top down y sorting. Two characters of different heights walk past each other
+// in front of a row of trees, all in one y sorted sprite layer over a ground
+// layer. The character in front swaps exactly when their feet cross, which is
+// logged.
+
+use roots_core::{
+    common::Size,
+    hecs::{
+        hecs::Entity,
+        renderer::components::{Sprite, SpriteLayer},
+        HecsApp, State,
+    },
+    pipelines::texture2d_renderer::{SortMode, Texture2dRenderer},
+};
+use roots_examples::example_common;
+
+//====================================================================
+
+const GROUND_LAYER: u32 = 0;
+const CHARACTER_LAYER: u32 = 1;
+
+/// Depth of each layer. Smaller is nearer the camera.
+const GROUND_DEPTH: f32 = 10.;
+const CHARACTER_DEPTH: f32 = 5.;
+
+fn main() {
+    example_common::run::<App>("ysort");
+}
+
+//====================================================================
+
+/// Walks up and down around `centre`.
+struct Walker {
+    centre: glam::Vec2,
+    range: f32,
+    speed: f32,
+    phase: f32,
+}
+
+struct App {
+    characters: [Entity; 2],
+    /// The character drawn in front last frame.
+    front: Option<Entity>,
+    time: f32,
+}
+
+impl HecsApp for App {
+    fn new(state: &mut State) -> Self {
+        state.renderer.add_managed_pipeline::<Texture2dRenderer>(0);
+        example_common::spawn_orthographic_camera(state);
+
+        state
+            .renderer
+            .with_managed_pipeline::<Texture2dRenderer, _>(|renderer| {
+                renderer.set_layer_sort(GROUND_LAYER, SortMode::Depth);
+                renderer.set_layer_sort(
+                    CHARACTER_LAYER,
+                    SortMode::YSort {
+                        origin_offset: -0.5,
+                    },
+                );
+            });
+
+        let ground = example_common::load_checker_texture(
+            state,
+            64,
+            8,
+            [[90, 140, 80, 255], [80, 125, 70, 255]],
+        );
+        let trunk = example_common::load_checker_texture(
+            state,
+            16,
+            1,
+            [[255, 255, 255, 255], [255, 255, 255, 255]],
+        );
+        let character = example_common::load_checker_texture(
+            state,
+            16,
+            2,
+            [[255, 255, 255, 255], [200, 200, 200, 255]],
+        );
+
+        state.world.spawn((
+            Sprite {
+                texture: ground,
+                size: glam::vec2(800., 600.),
+                pos: glam::vec3(0., 0., GROUND_DEPTH),
+                color: glam::Vec4::ONE,
+            },
+            SpriteLayer::new(GROUND_LAYER),
+        ));
+
+        // Trees are sprites in the character layer so characters can walk behind them
+        (0..5).for_each(|index| {
+            state.world.spawn((
+                Sprite {
+                    texture: trunk.clone(),
+                    size: glam::vec2(50., 140.),
+                    pos: glam::vec3(-240. + index as f32 * 120., 40., CHARACTER_DEPTH),
+                    color: glam::vec4(0.25, 0.45, 0.2, 1.),
+                },
+                SpriteLayer::new(CHARACTER_LAYER),
+            ));
+        });
+
+        let characters = [
+            (
+                glam::vec2(40., 80.),
+                glam::vec4(0.9, 0.3, 0.3, 1.),
+                -12.,
+                0.,
+            ),
+            (
+                glam::vec2(60., 120.),
+                glam::vec4(0.3, 0.4, 0.9, 1.),
+                12.,
+                std::f32::consts::PI,
+            ),
+        ]
+        .map(|(size, color, x, phase)| {
+            state.world.spawn((
+                Sprite {
+                    texture: character.clone(),
+                    size,
+                    pos: glam::vec3(x, 0., CHARACTER_DEPTH),
+                    color,
+                },
+                SpriteLayer::new(CHARACTER_LAYER),
+                Walker {
+                    centre: glam::vec2(x, 0.),
+                    range: 150.,
+                    speed: 0.8,
+                    phase,
+                },
+            ))
+        });
+
+        Self {
+            characters,
+            front: None,
+            time: 0.,
+        }
+    }
+
+    fn resize(&mut self, state: &mut State, size: Size<u32>) {
+        example_common::resize_cameras(state, size);
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        self.time += state.time.delta_seconds();
+        let time = self.time;
+
+        state
+            .world
+            .query_mut::<(&mut Sprite, &Walker)>()
+            .into_iter()
+            .for_each(|(_, (sprite, walker))| {
+                let y = walker.centre.y + (time * walker.speed + walker.phase).sin() * walker.range;
+                sprite.pos = glam::vec3(walker.centre.x, y, sprite.pos.z);
+            });
+
+        // Matches the renderer - the lowest feet are drawn in front
+        let feet = self.characters.map(|entity| {
+            let sprite = state.world.get::<&Sprite>(entity).unwrap();
+            (sprite.pos.y - sprite.size.y / 2., entity)
+        });
+
+        let front = match feet[0].0 < feet[1].0 {
+            true => feet[0].1,
+            false => feet[1].1,
+        };
+
+        if self.front != Some(front) {
+            log::info!(
+                "{:?} now in front - feet at {:.1} and {:.1}",
+                front,
+                feet[0].0,
+                feet[1].0
+            );
+            self.front = Some(front);
+        }
+
+        example_common::finish_tick(state);
+    }
+}
+
+//====================================================================
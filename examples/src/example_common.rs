@@ -0,0 +1,458 @@
+//====================================================================
+// Boilerplate shared between the examples - camera spawning, spinning
+// models and the Ui3d pipeline adapter.
+
+use roots_core::{
+    common::{
+        coords::Uv,
+        spatial::{GlobalTransform, Transform, WorldPosition},
+        Size,
+    },
+    hecs::{
+        hecs::{Entity, World},
+        renderer::{
+            components::{Camera, OffscreenCamera},
+            pipelines::Pipeline,
+            RendererState,
+        },
+        spatial, spatial_hash, validation, HecsApp, State, StateOuter,
+    },
+    pipelines::{
+        manager::{RenderContext, RenderPipeline},
+        model_renderer::ModelRenderer,
+        texture2d_renderer::Texture2dRenderer,
+    },
+    renderer::{
+        camera::{OrthographicCamera, PerspectiveCamera},
+        model::{self, LoadedMesh},
+        texture::{LoadedTexture, Texture},
+        RenderPass,
+    },
+    runner::{prelude::LevelFilter, Runner},
+    text::{
+        icons::IconSet,
+        shared::{FontSystem, TextResources},
+        ui3d_renderer::{Ui3d, Ui3dPlacement, Ui3dRenderer, Ui3dView},
+    },
+};
+
+pub use roots_core::hecs::camera_control::{process_fly_controller, FlyController};
+
+//====================================================================
+
+/// Run a `HecsApp` with logging enabled for the example and this crate.
+pub fn run<A: HecsApp>(name: &str) {
+    Runner::<StateOuter<A>>::run(Some(&[
+        (name, LevelFilter::Trace),
+        ("roots_examples", LevelFilter::Trace),
+        ("roots_hecs", LevelFilter::Debug),
+    ]));
+}
+
+/// Propagate transforms. Call at the end of `HecsApp::tick`, before the runner
+/// renders the frame and syncs the cameras.
+pub fn finish_tick(state: &mut State) {
+    update_console_text(state);
+
+    spatial::process_global_transform(state);
+    spatial::process_transform_hierarchy(state);
+    spatial_hash::process_spatial_hash(state);
+}
+
+//====================================================================
+
+pub fn spawn_perspective_camera(state: &mut State, translation: glam::Vec3) -> Entity {
+    let size = state.size();
+
+    let data = PerspectiveCamera {
+        aspect: size.width as f32 / size.height as f32,
+        fovy: 45_f32.to_radians(),
+        ..Default::default()
+    };
+
+    let camera = Camera::new_perspective(&state.renderer, &data);
+    state.late_latch.register::<FlyController>();
+
+    state.world.spawn((
+        camera,
+        data,
+        Transform::from_translation(translation),
+        GlobalTransform::default(),
+        FlyController::new(translation),
+    ))
+}
+
+/// Spawn an orthographic camera centered on the origin, using pixels as units.
+pub fn spawn_orthographic_camera(state: &mut State) -> Entity {
+    let size = state.size();
+    let data = OrthographicCamera::new_centered(size.width as f32 / 2., size.height as f32 / 2.);
+
+    let camera = Camera::new_orthographic(&state.renderer, &data);
+
+    state.world.spawn((
+        camera,
+        data,
+        Transform::default(),
+        GlobalTransform::default(),
+    ))
+}
+
+pub fn resize_cameras(state: &mut State, size: Size<u32>) {
+    if size.width == 0 || size.height == 0 {
+        return;
+    }
+
+    state
+        .world
+        .query_mut::<&mut OrthographicCamera>()
+        .without::<&OffscreenCamera>()
+        .into_iter()
+        .for_each(|(_, camera)| {
+            camera.set_size_centered(size.width as f32 / 2., size.height as f32 / 2.)
+        });
+}
+
+//====================================================================
+
+/// Rotates an entity around `axis` by `speed` radians per second.
+pub struct Spin {
+    pub axis: glam::Vec3,
+    pub speed: f32,
+}
+
+pub fn process_spin(state: &mut State) {
+    let delta = state.time.delta_seconds();
+    let mut spinning = false;
+
+    state
+        .world
+        .query_mut::<(&Spin, &mut Transform)>()
+        .into_iter()
+        .for_each(|(_, (spin, transform))| {
+            spinning |= spin.speed != 0.;
+            transform.rotation =
+                glam::Quat::from_axis_angle(spin.axis, spin.speed * delta) * transform.rotation
+        });
+
+    state.set_animation_active("spin", spinning);
+}
+
+//====================================================================
+
+/// Add the `Ui3dPipeline` and release a panel's gpu data as soon as its entity
+/// is removed with `State::despawn_tracked`.
+pub fn add_ui3d_pipeline(state: &mut State, priority: usize) {
+    state
+        .renderer
+        .add_managed_pipeline::<Ui3dPipeline>(priority);
+
+    state.on_remove::<Ui3d>(|state, entity, _| {
+        state
+            .renderer
+            .with_managed_pipeline::<Ui3dPipeline, _>(|pipeline| pipeline.renderer.remove(&entity));
+    });
+}
+
+/// Access the `Ui3dPipeline`'s icon set, for example to register icons at startup.
+/// Returns `None` if the pipeline hasn't been added.
+pub fn with_icons<R>(
+    state: &State,
+    f: impl FnOnce(&mut IconSet, &mut FontSystem) -> R,
+) -> Option<R> {
+    state
+        .renderer
+        .with_managed_pipeline::<Ui3dPipeline, _>(|pipeline| {
+            f(&mut pipeline.text.icons, &mut pipeline.text.font_system)
+        })
+}
+
+/// Renders every entity with a `Ui3d`. Menus are placed by their `Ui3dPlacement` if
+/// they have one, otherwise by their `GlobalTransform`. Camera relative placements
+/// follow the first camera.
+pub struct Ui3dPipeline {
+    renderer: Ui3dRenderer<Entity>,
+    text: TextResources,
+}
+
+impl RenderPipeline for Ui3dPipeline {
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        self.renderer
+            .render(render_pass, &self.text.text_atlas, context.camera);
+        self.text.text_atlas.post_render_trim();
+    }
+
+    #[inline]
+    fn stereo(&self) -> bool {
+        false
+    }
+}
+
+impl Pipeline for Ui3dPipeline {
+    fn new(state: &RendererState) -> Self {
+        let text = TextResources::new(&state.device, &state.shared);
+        let renderer = Ui3dRenderer::new(&state.device, &state.config, &state.shared);
+
+        Self { renderer, text }
+    }
+
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        if let Some(view) = ui3d_view(state, world) {
+            self.renderer.set_view(view);
+        }
+        self.renderer.set_theme(state.theme());
+
+        let mut invalid = Vec::new();
+
+        world
+            .query_mut::<(
+                &Ui3d,
+                Option<&Ui3dPlacement>,
+                Option<&GlobalTransform>,
+                Option<&WorldPosition>,
+            )>()
+            .into_iter()
+            .for_each(|(entity, (ui, placement, global, position))| {
+                let origin = &state.origin;
+
+                let placement = match (placement, global) {
+                    (Some(Ui3dPlacement::WorldFixed(transform)), _) => {
+                        Ui3dPlacement::WorldFixed(origin.relative_matrix(*transform))
+                    }
+                    (Some(Ui3dPlacement::Billboard { position, scale }), _) => {
+                        Ui3dPlacement::Billboard {
+                            position: origin.relative_point(*position),
+                            scale: *scale,
+                        }
+                    }
+                    (Some(placement), _) => *placement,
+                    (None, Some(global)) => {
+                        if validation::CHECK_TRANSFORMS && !global.is_finite() {
+                            invalid.push(entity);
+                            return;
+                        }
+                        Ui3dPlacement::WorldFixed(origin.relative(global, position).into())
+                    }
+                    (None, None) => return,
+                };
+
+                self.renderer.prep_text(
+                    &state.device,
+                    &state.queue,
+                    &mut self.text,
+                    entity,
+                    ui,
+                    placement,
+                )
+            });
+
+        self.renderer.finish_prep(&state.device, &state.queue);
+
+        validation::warn_non_finite(world, "Ui3dPipeline", &invalid);
+    }
+}
+
+/// The view of the first perspective or orthographic camera, relative to the
+/// renderer's origin.
+fn ui3d_view(state: &RendererState, world: &mut World) -> Option<Ui3dView> {
+    let viewport = Size::new(state.config.width, state.config.height);
+    let origin = &state.origin;
+
+    let perspective = world
+        .query_mut::<(&PerspectiveCamera, &GlobalTransform, Option<&WorldPosition>)>()
+        .with::<&Camera>()
+        .into_iter()
+        .next()
+        .map(|(_, (camera, global, position))| {
+            Ui3dView::from_camera(camera, &origin.relative(global, position), viewport)
+        });
+
+    perspective.or_else(|| {
+        world
+            .query_mut::<(
+                &OrthographicCamera,
+                &GlobalTransform,
+                Option<&WorldPosition>,
+            )>()
+            .with::<&Camera>()
+            .into_iter()
+            .next()
+            .map(|(_, (camera, global, position))| {
+                Ui3dView::from_camera(camera, &origin.relative(global, position), viewport)
+            })
+    })
+}
+
+//====================================================================
+
+const CONSOLE_ROWS: usize = 12;
+
+/// Marker for the Ui3d panel showing the console while it is open.
+pub struct ConsoleText;
+
+/// Show the developer console (toggled with backtick) as a Ui3d panel in the top
+/// left of the first camera's view. Requires the `Ui3dPipeline`.
+pub fn update_console_text(state: &mut State) {
+    let panel = state
+        .world
+        .query_mut::<()>()
+        .with::<&ConsoleText>()
+        .into_iter()
+        .next()
+        .map(|(entity, _)| entity);
+
+    if !state.console.is_open() {
+        if let Some(panel) = panel {
+            let _ = state.despawn_tracked(panel);
+        }
+        return;
+    }
+
+    let mut options = state
+        .console
+        .visible_output(CONSOLE_ROWS)
+        .into_iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+    options.push(state.console.prompt());
+
+    let selected = (options.len() - 1) as u8;
+
+    if let Some(panel) = panel {
+        if let Ok(ui) = state.world.query_one_mut::<&mut Ui3d>(panel) {
+            if ui.options != options {
+                ui.options = options;
+                ui.selected = selected;
+            }
+        }
+        return;
+    }
+
+    state.world.spawn((
+        ConsoleText,
+        Ui3d {
+            options,
+            selected,
+            font_size: 20.,
+            menu_color: Some([0.05, 0.05, 0.05, 0.85]),
+            selection_color: Some([0.15, 0.15, 0.2, 0.9]),
+            ..Default::default()
+        },
+        Ui3dPlacement::ScreenAnchored {
+            anchor: Uv::default(),
+            offset_px: glam::vec2(10., 10.),
+        },
+    ));
+}
+
+//====================================================================
+
+/// Marker for the Ui3d labels spawned by `update_draw_order_labels`.
+pub struct DrawOrderLabel;
+
+/// Label each batch drawn by the sprite and model renderers with its submission
+/// order, ids and instance count. Labels are removed while draw order debugging
+/// is disabled. Requires the `Ui3dPipeline`.
+pub fn update_draw_order_labels(state: &mut State, scale: f32) {
+    let mut batches = Vec::new();
+
+    state
+        .renderer
+        .with_managed_pipeline::<Texture2dRenderer, _>(|pipeline| {
+            batches.extend_from_slice(pipeline.draw_order_batches())
+        });
+    state
+        .renderer
+        .with_managed_pipeline::<ModelRenderer, _>(|pipeline| {
+            batches.extend_from_slice(pipeline.draw_order_batches())
+        });
+
+    let labels = state
+        .world
+        .query_mut::<()>()
+        .with::<&DrawOrderLabel>()
+        .into_iter()
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+
+    labels.iter().skip(batches.len()).for_each(|entity| {
+        let _ = state.despawn_tracked(*entity);
+    });
+
+    batches.iter().enumerate().for_each(|(index, batch)| {
+        let text = match batch.mesh {
+            Some(mesh) => format!(
+                "Model #{} - mesh {} texture {} x{}",
+                batch.order, mesh, batch.texture, batch.instances
+            ),
+            None => format!(
+                "Sprite #{} - texture {} x{}",
+                batch.order, batch.texture, batch.instances
+            ),
+        };
+
+        let transform = Transform::from_scale_translation(glam::Vec3::splat(scale), batch.position);
+
+        match labels.get(index) {
+            Some(entity) => {
+                if let Ok((ui, label_transform)) = state
+                    .world
+                    .query_one_mut::<(&mut Ui3d, &mut Transform)>(*entity)
+                {
+                    if ui.options[0] != text {
+                        ui.options = vec![text];
+                    }
+                    *label_transform = transform;
+                }
+            }
+
+            None => {
+                state.world.spawn((
+                    DrawOrderLabel,
+                    Ui3d {
+                        options: vec![text],
+                        font_size: 20.,
+                        ..Default::default()
+                    },
+                    transform,
+                    GlobalTransform::default(),
+                ));
+            }
+        }
+    });
+}
+
+//====================================================================
+
+pub fn load_cube(state: &State) -> LoadedMesh {
+    let (vertices, indices) = model::cube();
+    LoadedMesh::load_from_data(&state.renderer.device, &vertices, &indices)
+}
+
+/// Generate a checkerboard texture so the examples don't depend on any asset files.
+pub fn load_checker_texture(
+    state: &State,
+    size: u32,
+    cells: u32,
+    colors: [[u8; 4]; 2],
+) -> LoadedTexture {
+    let cell_size = (size / cells.max(1)).max(1);
+
+    let image = image::RgbaImage::from_fn(size, size, |x, y| {
+        image::Rgba(colors[((x / cell_size + y / cell_size) % 2) as usize])
+    });
+
+    let texture = Texture::from_image(
+        &state.renderer.device,
+        &state.renderer.queue,
+        &image.into(),
+        Some("Checker Texture"),
+        Some(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }),
+    );
+
+    LoadedTexture::load_texture(&state.renderer.device, &state.renderer.shared, texture)
+}
+
+//====================================================================
@@ -0,0 +1,33 @@
+//====================================================================
+// Runnable examples for the roots crates. Run with
+// `cargo run -p roots_examples --example <name>`:
+//
+// - cube - textured, lit cubes with a fly camera and a developer console command
+// - sprites - 2D sprites over parallax layers with an orthographic camera to pan and zoom,
+//   F3 shows the draw order
+// - ysort - top down characters and trees drawn in order of their feet
+// - menu - Ui3d menu driven by MenuController with held key repeat, tab pins it to the screen
+// - debug_lines - grid, axes and wireframe gizmos using the line renderer
+// - stress - 10,000 spinning cubes with the fps overlay, culling, picking and dynamic resolution
+// - meshes - 1,000 unique meshes from a MeshPool, drawn with multi draw indirect
+// - static_bake - 2,000 static crates baked into a few merged meshes with B
+// - gpu_particles - 500,000 particles simulated by a compute shader, G toggles the cpu path
+// - uploads - streams 200 textures in through the budgeted upload queue
+// - resize - a fine checkerboard that follows the window size, F11 toggles fullscreen
+// - preload - loads a manifest of textures behind a splash screen and loading bar,
+//   skipping a missing optional entry
+// - large_world - a cube orbiting ten million units from the origin, L toggles camera
+//   relative rendering
+// - grass - 400 grass cards swaying in the wind on the gpu, G toggles the wind
+// - texture_streaming - 96 large textures streamed in by camera distance under a 64 MiB
+//   budget, M logs what's resident
+// - embedded - renders into an offscreen viewport from a host loop, without a window
+//
+// Every windowed example opens the developer console with `.
+//
+// All examples avoid asset files. Apart from embedded, they also avoid native
+// only features so they build for wasm32.
+
+pub mod example_common;
+
+//====================================================================
@@ -0,0 +1,501 @@
+//====================================================================
+//
+// Coordinate spaces used across roots. Each type documents its origin and axes, and
+// conversions between spaces only happen through the functions here.
+//
+// | Type        | Origin              | +X    | +Y   | Range            |
+// |-------------|---------------------|-------|------|------------------|
+// | `WindowPx`  | Top left of window  | Right | Down | 0..window size   |
+// | `NdcPos`    | Center of viewport  | Right | Up   | -1..1            |
+// | `Uv`        | Top left of texture | Right | Down | 0..1             |
+// | `WorldPos2` | World origin        | Right | Up   | World units      |
+// | `WorldPos3` | World origin        | Right | *    | World units      |
+//
+// * 3D axes depend on the active `CoordinateConvention`, left handed Y up by default.
+// The screen camera used by screen space pipelines is a `WorldPos2` space with one
+// unit per pixel and its origin at the bottom left of the window.
+//
+//====================================================================
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::Size;
+
+//====================================================================
+
+/// Size of `window` in pixels, never smaller than 1x1 so conversions stay finite.
+#[inline]
+fn extent(window: Size<u32>) -> glam::Vec2 {
+    glam::vec2(window.width.max(1) as f32, window.height.max(1) as f32)
+}
+
+//====================================================================
+
+/// Pixels from the top left of the window, with +Y down. Cursor positions from
+/// `MouseInput` are in this space.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WindowPx(pub glam::Vec2);
+
+impl WindowPx {
+    #[inline]
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self(glam::vec2(x, y))
+    }
+
+    #[inline]
+    pub fn to_ndc(self, window: Size<u32>) -> NdcPos {
+        let t = self.0 / extent(window);
+        NdcPos(glam::vec2(t.x * 2. - 1., 1. - t.y * 2.))
+    }
+
+    #[inline]
+    pub fn to_uv(self, window: Size<u32>) -> Uv {
+        Uv(self.0 / extent(window))
+    }
+
+    /// The same point in the screen camera's space, with its origin at the bottom left.
+    #[inline]
+    pub fn to_screen_space(self, window: Size<u32>) -> WorldPos2 {
+        WorldPos2(glam::vec2(self.0.x, extent(window).y - self.0.y))
+    }
+}
+
+//--------------------------------------------------
+
+/// Normalized device coordinates, from -1 to 1 across the viewport with +Y up.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NdcPos(pub glam::Vec2);
+
+impl NdcPos {
+    #[inline]
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self(glam::vec2(x, y))
+    }
+
+    #[inline]
+    pub fn to_window(self, window: Size<u32>) -> WindowPx {
+        let t = glam::vec2(self.0.x + 1., 1. - self.0.y) / 2.;
+        WindowPx(t * extent(window))
+    }
+
+    #[inline]
+    pub fn to_uv(self) -> Uv {
+        Uv(glam::vec2(self.0.x + 1., 1. - self.0.y) / 2.)
+    }
+
+    /// The world position at `depth` in clip space, using the inverse of a camera's
+    /// view projection.
+    #[inline]
+    pub fn unproject(self, depth: f32, inverse_view_projection: glam::Mat4) -> WorldPos3 {
+        WorldPos3(inverse_view_projection.project_point3(self.0.extend(depth)))
+    }
+
+    /// The world position under this point for a 2D camera, using the inverse of its
+    /// view projection.
+    #[inline]
+    pub fn to_world2(self, inverse_view_projection: glam::Mat4) -> WorldPos2 {
+        WorldPos2(self.unproject(0., inverse_view_projection).0.truncate())
+    }
+}
+
+//--------------------------------------------------
+
+/// Texture coordinates, from 0 to 1 with the origin at the top left and +Y down.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Uv(pub glam::Vec2);
+
+impl Uv {
+    #[inline]
+    pub const fn new(u: f32, v: f32) -> Self {
+        Self(glam::vec2(u, v))
+    }
+
+    #[inline]
+    pub fn to_ndc(self) -> NdcPos {
+        NdcPos(glam::vec2(self.0.x * 2. - 1., 1. - self.0.y * 2.))
+    }
+
+    #[inline]
+    pub fn to_window(self, window: Size<u32>) -> WindowPx {
+        WindowPx(self.0 * extent(window))
+    }
+}
+
+//--------------------------------------------------
+
+/// A position in a 2D world, with +Y up.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WorldPos2(pub glam::Vec2);
+
+impl WorldPos2 {
+    #[inline]
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self(glam::vec2(x, y))
+    }
+
+    #[inline]
+    pub fn to_ndc(self, view_projection: glam::Mat4) -> NdcPos {
+        NdcPos(view_projection.project_point3(self.0.extend(0.)).truncate())
+    }
+
+    /// The window position of a point in the screen camera's space.
+    #[inline]
+    pub fn screen_space_to_window(self, window: Size<u32>) -> WindowPx {
+        WindowPx(glam::vec2(self.0.x, extent(window).y - self.0.y))
+    }
+}
+
+//--------------------------------------------------
+
+/// A position in the 3D world, with axes set by the active `CoordinateConvention`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WorldPos3(pub glam::Vec3);
+
+impl WorldPos3 {
+    #[inline]
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self(glam::vec3(x, y, z))
+    }
+
+    /// Where this point lands in the viewport, or `None` if it's behind the camera.
+    pub fn project(self, view_projection: glam::Mat4) -> Option<NdcPos> {
+        let clip = view_projection * self.0.extend(1.);
+
+        match clip.w > 0. {
+            true => Some(NdcPos(clip.truncate().truncate() / clip.w)),
+            false => None,
+        }
+    }
+
+    /// The window position of this point, or `None` if it's behind the camera.
+    #[inline]
+    pub fn to_window(self, view_projection: glam::Mat4, window: Size<u32>) -> Option<WindowPx> {
+        self.project(view_projection)
+            .map(|ndc| ndc.to_window(window))
+    }
+}
+
+//====================================================================
+
+/// Handedness and axes of the 3D world. Selects the camera projections, which local
+/// axis is forward for `Transform::forward` and cameras, and how imported assets are
+/// converted. Meshes are always wound counter clockwise in the active convention.
+///
+/// Process wide, as transforms have no access to the renderer. Set it before loading
+/// assets or creating cameras, with `HecsApp::coordinate_convention` or `set_active`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CoordinateConvention {
+    /// +X right, +Y up, +Z forward.
+    #[default]
+    LeftHandedYUp,
+    /// +X right, +Y up, -Z forward. Blender's and OpenGL's camera space.
+    RightHandedYUp,
+    /// +X right, +Z up, +Y forward. Blender's world space.
+    RightHandedZUp,
+}
+
+static ACTIVE_CONVENTION: AtomicU8 = AtomicU8::new(0);
+
+/// Axes of glTF assets - +X left, +Y up and +Z towards the front of the asset.
+pub const GLTF_BASIS: glam::Mat3 =
+    glam::Mat3::from_cols(glam::Vec3::NEG_X, glam::Vec3::Y, glam::Vec3::Z);
+
+impl CoordinateConvention {
+    #[inline]
+    pub fn active() -> Self {
+        match ACTIVE_CONVENTION.load(Ordering::Relaxed) {
+            1 => Self::RightHandedYUp,
+            2 => Self::RightHandedZUp,
+            _ => Self::LeftHandedYUp,
+        }
+    }
+
+    pub fn set_active(self) {
+        let value = match self {
+            Self::LeftHandedYUp => 0,
+            Self::RightHandedYUp => 1,
+            Self::RightHandedZUp => 2,
+        };
+
+        ACTIVE_CONVENTION.store(value, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn is_right_handed(self) -> bool {
+        !matches!(self, Self::LeftHandedYUp)
+    }
+
+    #[inline]
+    pub fn right(self) -> glam::Vec3 {
+        glam::Vec3::X
+    }
+
+    #[inline]
+    pub fn up(self) -> glam::Vec3 {
+        match self {
+            Self::LeftHandedYUp | Self::RightHandedYUp => glam::Vec3::Y,
+            Self::RightHandedZUp => glam::Vec3::Z,
+        }
+    }
+
+    #[inline]
+    pub fn forward(self) -> glam::Vec3 {
+        match self {
+            Self::LeftHandedYUp => glam::Vec3::Z,
+            Self::RightHandedYUp => glam::Vec3::NEG_Z,
+            Self::RightHandedZUp => glam::Vec3::Y,
+        }
+    }
+
+    /// Right, up and forward as columns.
+    #[inline]
+    pub fn basis(self) -> glam::Mat3 {
+        glam::Mat3::from_cols(self.right(), self.up(), self.forward())
+    }
+
+    /// Maps positions and directions from axes with the given right, up and forward
+    /// columns into this convention. Flips handedness if the determinant is negative,
+    /// but describes the same mesh from the viewer's point of view, so triangle winding
+    /// is kept.
+    #[inline]
+    pub fn conversion_from_basis(self, basis: glam::Mat3) -> glam::Mat3 {
+        self.basis() * basis.transpose()
+    }
+
+    #[inline]
+    pub fn conversion_from(self, source: CoordinateConvention) -> glam::Mat3 {
+        self.conversion_from_basis(source.basis())
+    }
+
+    /// A `[0, 1]` depth perspective projection looking along the view's forward axis.
+    #[inline]
+    pub fn perspective(self, fovy: f32, aspect: f32, z_near: f32, z_far: f32) -> glam::Mat4 {
+        match self.is_right_handed() {
+            true => glam::Mat4::perspective_rh(fovy, aspect, z_near, z_far),
+            false => glam::Mat4::perspective_lh(fovy, aspect, z_near, z_far),
+        }
+    }
+
+    /// A `[0, 1]` depth perspective projection with no far plane, with depth reaching 1
+    /// at infinity. Loses precision in the distance unless depth is reversed.
+    #[inline]
+    pub fn perspective_infinite(self, fovy: f32, aspect: f32, z_near: f32) -> glam::Mat4 {
+        match self.is_right_handed() {
+            true => glam::Mat4::perspective_infinite_rh(fovy, aspect, z_near),
+            false => glam::Mat4::perspective_infinite_lh(fovy, aspect, z_near),
+        }
+    }
+
+    /// View matrix for an eye at `eye` looking along `direction`.
+    #[inline]
+    pub fn look_to(self, eye: glam::Vec3, direction: glam::Vec3, up: glam::Vec3) -> glam::Mat4 {
+        match self.is_right_handed() {
+            true => glam::Mat4::look_to_rh(eye, direction, up),
+            false => glam::Mat4::look_to_lh(eye, direction, up),
+        }
+    }
+
+    /// Rotation turning the local forward axis to `direction`, keeping the local up
+    /// axis as close to `up` as possible.
+    pub fn rotation_looking_to(self, direction: glam::Vec3, up: glam::Vec3) -> glam::Quat {
+        let forward = direction.try_normalize().unwrap_or(self.forward());
+
+        let right = match self.is_right_handed() {
+            true => forward.cross(up),
+            false => up.cross(forward),
+        }
+        .try_normalize()
+        .unwrap_or_else(|| forward.any_orthogonal_vector());
+
+        let up = match self.is_right_handed() {
+            true => right.cross(forward),
+            false => forward.cross(right),
+        };
+
+        let world = glam::Mat3::from_cols(right, up, forward);
+        glam::Quat::from_mat3(&(world * self.basis().transpose())).normalize()
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec2, vec3, Mat4, Vec2, Vec3};
+
+    use super::*;
+
+    fn windows() -> [Size<u32>; 4] {
+        [
+            Size::new(800, 600),
+            Size::new(600, 800),
+            Size::new(1, 1),
+            Size::new(0, 0),
+        ]
+    }
+
+    const CONVENTIONS: [CoordinateConvention; 3] = [
+        CoordinateConvention::LeftHandedYUp,
+        CoordinateConvention::RightHandedYUp,
+        CoordinateConvention::RightHandedZUp,
+    ];
+
+    fn assert_near2(a: Vec2, b: Vec2) {
+        assert!(a.abs_diff_eq(b, 1e-3), "{} != {}", a, b);
+    }
+
+    /// Relative to distance, as depth loses precision far from the camera.
+    fn assert_near3(a: Vec3, b: Vec3) {
+        let tolerance = (b.length() * 1e-4).max(1e-3);
+        assert!(a.abs_diff_eq(b, tolerance), "{} != {}", a, b);
+    }
+
+    /// Points across and just outside the window, as fractions of its size.
+    fn fractions() -> impl Iterator<Item = Vec2> {
+        (-1..=9).flat_map(|x| (-1..=9).map(move |y| vec2(x as f32, y as f32) / 8.))
+    }
+
+    #[test]
+    fn window_ndc_uv_round_trip() {
+        windows().into_iter().for_each(|window| {
+            let size = extent(window);
+
+            fractions().for_each(|t| {
+                let px = WindowPx(t * size);
+
+                assert_near2(px.to_ndc(window).to_window(window).0, px.0);
+                assert_near2(px.to_uv(window).to_window(window).0, px.0);
+                assert_near2(px.to_ndc(window).to_uv().0, px.to_uv(window).0);
+                assert_near2(px.to_uv(window).to_ndc().0, px.to_ndc(window).0);
+                assert_near2(px.to_uv(window).0, t);
+
+                let screen = px.to_screen_space(window);
+                assert_near2(screen.screen_space_to_window(window).0, px.0);
+            });
+        });
+    }
+
+    #[test]
+    fn window_corners() {
+        let window = Size::new(800, 600);
+        let corners = [
+            (WindowPx::new(0., 0.), vec2(-1., 1.), vec2(0., 600.)),
+            (WindowPx::new(800., 0.), vec2(1., 1.), vec2(800., 600.)),
+            (WindowPx::new(0., 600.), vec2(-1., -1.), vec2(0., 0.)),
+            (WindowPx::new(800., 600.), vec2(1., -1.), vec2(800., 0.)),
+            (WindowPx::new(400., 300.), vec2(0., 0.), vec2(400., 300.)),
+        ];
+
+        corners.into_iter().for_each(|(px, ndc, screen)| {
+            assert_near2(px.to_ndc(window).0, ndc);
+            assert_near2(px.to_screen_space(window).0, screen);
+        });
+
+        // Empty windows are treated as 1x1 rather than dividing by zero
+        let ndc = WindowPx::new(1., 1.).to_ndc(Size::new(0, 0));
+        assert_eq!(ndc.0, vec2(1., -1.));
+    }
+
+    #[test]
+    fn world2_round_trip() {
+        let window = Size::new(800, 600);
+        let view_projection = Mat4::orthographic_rh(-40., 40., -30., 30., 0., 100.)
+            * Mat4::from_translation(vec3(-5., 7., 0.));
+        let inverse = view_projection.inverse();
+
+        fractions().for_each(|t| {
+            let px = WindowPx(t * extent(window));
+            let world = px.to_ndc(window).to_world2(inverse);
+
+            assert_near2(world.to_ndc(view_projection).0, px.to_ndc(window).0);
+            assert_near2(world.to_ndc(view_projection).to_window(window).0, px.0);
+        });
+
+        // The view is centered on the camera, with +Y up in both spaces
+        let center = WindowPx::new(400., 300.).to_ndc(window).to_world2(inverse);
+        assert_near2(center.0, vec2(5., -7.));
+        let top = WindowPx::new(400., 0.).to_ndc(window).to_world2(inverse);
+        assert_near2(top.0, vec2(5., 23.));
+    }
+
+    #[test]
+    fn screen_camera_matches_screen_space() {
+        windows().into_iter().for_each(|window| {
+            let size = extent(window);
+            let view_projection = Mat4::orthographic_rh(0., size.x, 0., size.y, 0., 1.);
+            let inverse = view_projection.inverse();
+
+            fractions().for_each(|t| {
+                let px = WindowPx(t * size);
+                assert_near2(
+                    px.to_ndc(window).to_world2(inverse).0,
+                    px.to_screen_space(window).0,
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn world3_round_trip_per_convention() {
+        let window = Size::new(800, 600);
+
+        CONVENTIONS.into_iter().for_each(|convention| {
+            let eye = vec3(1., 2., 3.);
+            let view_projection = convention.perspective(1., 800. / 600., 0.1, 100.)
+                * convention.look_to(eye, convention.forward(), convention.up());
+            let inverse = view_projection.inverse();
+
+            let ahead = |right: f32, up: f32, forward: f32| {
+                WorldPos3(
+                    eye + convention.right() * right
+                        + convention.up() * up
+                        + convention.forward() * forward,
+                )
+            };
+
+            [-2., 0., 3.].into_iter().for_each(|right| {
+                [-1., 0., 2.].into_iter().for_each(|up| {
+                    [0.5, 5., 50.].into_iter().for_each(|forward| {
+                        let point = ahead(right, up, forward);
+                        let ndc = point.project(view_projection).unwrap();
+                        let depth = view_projection.project_point3(point.0).z;
+
+                        assert_near3(ndc.unproject(depth, inverse).0, point.0);
+
+                        let px = point.to_window(view_projection, window).unwrap();
+                        assert_near2(px.to_ndc(window).0, ndc.0);
+                    });
+                });
+            });
+
+            // Right and up on screen match the convention's axes
+            let ndc = ahead(1., 1., 5.).project(view_projection).unwrap();
+            assert!(ndc.0.x > 0. && ndc.0.y > 0., "{:?}: {}", convention, ndc.0);
+
+            let px = ahead(1., 1., 5.)
+                .to_window(view_projection, window)
+                .unwrap();
+            assert!(px.0.x > 400. && px.0.y < 300., "{:?}: {}", convention, px.0);
+
+            assert_eq!(ahead(0., 0., -5.).project(view_projection), None);
+        });
+    }
+
+    #[test]
+    fn conversions_between_conventions_round_trip() {
+        CONVENTIONS.into_iter().for_each(|from| {
+            CONVENTIONS.into_iter().for_each(|to| {
+                let there = to.conversion_from(from);
+                let back = from.conversion_from(to);
+
+                [vec3(1., 2., 3.), from.forward(), from.up()]
+                    .into_iter()
+                    .for_each(|point| assert_near3(back * (there * point), point));
+
+                assert_near3(there * from.forward(), to.forward());
+                assert_near3(there * from.up(), to.up());
+                assert_near3(there * from.right(), to.right());
+            });
+        });
+    }
+}
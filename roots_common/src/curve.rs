@@ -0,0 +1,438 @@
+//====================================================================
+
+//====================================================================
+
+/// Samples used per cubic segment when building arc length tables.
+const SEGMENT_SAMPLES: usize = 32;
+
+/// A 3D curve parameterized by `t` between 0 and 1.
+pub trait Curve {
+    fn sample(&self, t: f32) -> glam::Vec3;
+
+    /// Derivative of the curve with respect to `t`. Points in the direction of travel.
+    fn sample_derivative(&self, t: f32) -> glam::Vec3;
+
+    fn arc_length_table(&self) -> &ArcLengthTable;
+
+    #[inline]
+    fn arc_length(&self) -> f32 {
+        self.arc_length_table().length()
+    }
+
+    /// The `t` value at `distance` along the curve. Distance is clamped to the curve.
+    #[inline]
+    fn t_at_distance(&self, distance: f32) -> f32 {
+        self.arc_length_table().t_at_distance(distance)
+    }
+
+    /// Sample the curve by distance rather than `t`, giving constant speed movement.
+    #[inline]
+    fn sample_at_distance(&self, distance: f32) -> glam::Vec3 {
+        self.sample(self.t_at_distance(distance))
+    }
+}
+
+//====================================================================
+
+/// Cumulative distances sampled at evenly spaced `t` values, used to map distance to `t`.
+#[derive(Debug, Clone)]
+pub struct ArcLengthTable {
+    distances: Vec<f32>,
+}
+
+impl ArcLengthTable {
+    pub fn new(samples: usize, sample: impl Fn(f32) -> glam::Vec3) -> Self {
+        let samples = samples.max(2);
+        let step = 1. / (samples - 1) as f32;
+
+        let mut previous = sample(0.);
+        let mut total = 0.;
+
+        let distances = (0..samples)
+            .map(|index| {
+                let point = sample(index as f32 * step);
+                total += point.distance(previous);
+                previous = point;
+                total
+            })
+            .collect();
+
+        Self { distances }
+    }
+
+    #[inline]
+    pub fn length(&self) -> f32 {
+        *self.distances.last().unwrap()
+    }
+
+    pub fn t_at_distance(&self, distance: f32) -> f32 {
+        let length = self.length();
+        if length <= 0. {
+            return 0.;
+        }
+
+        let distance = distance.clamp(0., length);
+        let last = self.distances.len() - 1;
+
+        // First sample at or past the distance
+        let upper = self
+            .distances
+            .partition_point(|sample| *sample < distance)
+            .clamp(1, last);
+
+        let start = self.distances[upper - 1];
+        let span = self.distances[upper] - start;
+
+        let local = match span > 0. {
+            true => (distance - start) / span,
+            false => 0.,
+        };
+
+        (upper - 1) as f32 / last as f32 + local / last as f32
+    }
+}
+
+//====================================================================
+
+/// Split `t` into a segment index and the `t` within that segment.
+#[inline]
+fn segment_t(t: f32, segments: usize) -> (usize, f32) {
+    let scaled = t.clamp(0., 1.) * segments as f32;
+    let index = (scaled as usize).min(segments - 1);
+    (index, scaled - index as f32)
+}
+
+//--------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct CubicBezier {
+    points: [glam::Vec3; 4],
+    table: ArcLengthTable,
+}
+
+impl CubicBezier {
+    pub fn new(
+        start: glam::Vec3,
+        control_a: glam::Vec3,
+        control_b: glam::Vec3,
+        end: glam::Vec3,
+    ) -> Self {
+        let points = [start, control_a, control_b, end];
+        let table = ArcLengthTable::new(SEGMENT_SAMPLES * 4, |t| bezier(&points, t));
+
+        Self { points, table }
+    }
+
+    #[inline]
+    pub fn points(&self) -> &[glam::Vec3; 4] {
+        &self.points
+    }
+}
+
+fn bezier([p0, p1, p2, p3]: &[glam::Vec3; 4], t: f32) -> glam::Vec3 {
+    let inv = 1. - t;
+    *p0 * (inv * inv * inv)
+        + *p1 * (3. * inv * inv * t)
+        + *p2 * (3. * inv * t * t)
+        + *p3 * (t * t * t)
+}
+
+impl Curve for CubicBezier {
+    #[inline]
+    fn sample(&self, t: f32) -> glam::Vec3 {
+        bezier(&self.points, t.clamp(0., 1.))
+    }
+
+    fn sample_derivative(&self, t: f32) -> glam::Vec3 {
+        let [p0, p1, p2, p3] = self.points;
+        let t = t.clamp(0., 1.);
+        let inv = 1. - t;
+
+        (p1 - p0) * (3. * inv * inv) + (p2 - p1) * (6. * inv * t) + (p3 - p2) * (3. * t * t)
+    }
+
+    #[inline]
+    fn arc_length_table(&self) -> &ArcLengthTable {
+        &self.table
+    }
+}
+
+//--------------------------------------------------
+
+/// Uniform Catmull-Rom spline passing through every control point.
+#[derive(Debug, Clone)]
+pub struct CatmullRom {
+    points: Vec<glam::Vec3>,
+    table: ArcLengthTable,
+}
+
+impl CatmullRom {
+    /// Returns `None` if fewer than 2 points are provided.
+    pub fn new(points: Vec<glam::Vec3>) -> Option<Self> {
+        if points.len() < 2 {
+            return None;
+        }
+
+        let samples = SEGMENT_SAMPLES * (points.len() - 1);
+        let table = ArcLengthTable::new(samples, |t| catmull_rom(&points, t).0);
+
+        Some(Self { points, table })
+    }
+
+    #[inline]
+    pub fn points(&self) -> &[glam::Vec3] {
+        &self.points
+    }
+}
+
+/// Returns the position and derivative (with respect to the whole curve's `t`).
+fn catmull_rom(points: &[glam::Vec3], t: f32) -> (glam::Vec3, glam::Vec3) {
+    let segments = points.len() - 1;
+    let (index, t) = segment_t(t, segments);
+
+    // End points are duplicated so the curve reaches them
+    let p0 = points[index.saturating_sub(1)];
+    let p1 = points[index];
+    let p2 = points[index + 1];
+    let p3 = points[(index + 2).min(segments)];
+
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    let position = 0.5
+        * (2. * p1
+            + (p2 - p0) * t
+            + (2. * p0 - 5. * p1 + 4. * p2 - p3) * t2
+            + (3. * p1 - p0 - 3. * p2 + p3) * t3);
+
+    let derivative = 0.5
+        * ((p2 - p0)
+            + (2. * p0 - 5. * p1 + 4. * p2 - p3) * (2. * t)
+            + (3. * p1 - p0 - 3. * p2 + p3) * (3. * t2));
+
+    (position, derivative * segments as f32)
+}
+
+impl Curve for CatmullRom {
+    #[inline]
+    fn sample(&self, t: f32) -> glam::Vec3 {
+        catmull_rom(&self.points, t).0
+    }
+
+    #[inline]
+    fn sample_derivative(&self, t: f32) -> glam::Vec3 {
+        catmull_rom(&self.points, t).1
+    }
+
+    #[inline]
+    fn arc_length_table(&self) -> &ArcLengthTable {
+        &self.table
+    }
+}
+
+//--------------------------------------------------
+
+/// Straight lines between each point. Each segment covers an equal range of `t`.
+#[derive(Debug, Clone)]
+pub struct LinearPath {
+    points: Vec<glam::Vec3>,
+    table: ArcLengthTable,
+}
+
+impl LinearPath {
+    /// Returns `None` if fewer than 2 points are provided.
+    pub fn new(points: Vec<glam::Vec3>) -> Option<Self> {
+        if points.len() < 2 {
+            return None;
+        }
+
+        // Sampling exactly at each point makes the table exact
+        let last = (points.len() - 1) as f32;
+        let table = ArcLengthTable::new(points.len(), |t| points[(t * last).round() as usize]);
+
+        Some(Self { points, table })
+    }
+
+    #[inline]
+    pub fn points(&self) -> &[glam::Vec3] {
+        &self.points
+    }
+}
+
+impl Curve for LinearPath {
+    fn sample(&self, t: f32) -> glam::Vec3 {
+        let (index, t) = segment_t(t, self.points.len() - 1);
+        self.points[index].lerp(self.points[index + 1], t)
+    }
+
+    fn sample_derivative(&self, t: f32) -> glam::Vec3 {
+        let segments = self.points.len() - 1;
+        let (index, _) = segment_t(t, segments);
+        (self.points[index + 1] - self.points[index]) * segments as f32
+    }
+
+    #[inline]
+    fn arc_length_table(&self) -> &ArcLengthTable {
+        &self.table
+    }
+}
+
+//====================================================================
+
+/// What happens when something moving along a curve reaches the end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LoopMode {
+    /// Stop at the end.
+    #[default]
+    Once,
+    /// Jump back to the start.
+    Loop,
+    /// Reverse direction at either end.
+    PingPong,
+}
+
+impl LoopMode {
+    /// Map a total distance travelled to a distance along a curve of `length`.
+    /// Also returns whether the curve is currently being travelled in reverse.
+    pub fn distance_along(&self, travelled: f32, length: f32) -> (f32, bool) {
+        if length <= 0. {
+            return (0., false);
+        }
+
+        match self {
+            LoopMode::Once => (travelled.clamp(0., length), false),
+            LoopMode::Loop => (travelled.rem_euclid(length), false),
+            LoopMode::PingPong => {
+                let distance = travelled.rem_euclid(length * 2.);
+                match distance > length {
+                    true => (length * 2. - distance, true),
+                    false => (distance, false),
+                }
+            }
+        }
+    }
+
+    /// Keep the travelled distance small for repeating modes to avoid losing precision.
+    #[inline]
+    pub fn wrap(&self, travelled: f32, length: f32) -> f32 {
+        match (self, length > 0.) {
+            (LoopMode::Once, _) | (_, false) => travelled,
+            (LoopMode::Loop, true) => travelled.rem_euclid(length),
+            (LoopMode::PingPong, true) => travelled.rem_euclid(length * 2.),
+        }
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+
+    use super::*;
+
+    #[test]
+    fn constructors_need_two_points() {
+        assert!(CatmullRom::new(vec![Vec3::ZERO]).is_none());
+        assert!(LinearPath::new(Vec::new()).is_none());
+        assert!(CatmullRom::new(vec![Vec3::ZERO, Vec3::X]).is_some());
+        assert!(LinearPath::new(vec![Vec3::ZERO, Vec3::X]).is_some());
+    }
+
+    #[test]
+    fn arc_length_of_known_curves() {
+        let line = CubicBezier::new(Vec3::ZERO, Vec3::X, vec3(2., 0., 0.), vec3(3., 0., 0.));
+        assert!((line.arc_length() - 3.).abs() < 1e-4);
+
+        // Standard bezier approximation of a unit quarter circle
+        let k = 0.552_284_8;
+        let arc = CubicBezier::new(Vec3::X, vec3(1., k, 0.), vec3(k, 1., 0.), Vec3::Y);
+        assert!((arc.arc_length() - std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+
+        let spline = CatmullRom::new(vec![Vec3::ZERO, Vec3::X, vec3(2., 0., 0.)]).unwrap();
+        assert!((spline.arc_length() - 2.).abs() < 1e-4);
+
+        let path = LinearPath::new(vec![Vec3::ZERO, vec3(3., 0., 0.), vec3(3., 4., 0.)]).unwrap();
+        assert_eq!(path.arc_length(), 7.);
+    }
+
+    #[test]
+    fn sampling_by_distance_is_constant_speed() {
+        // Eases in and out, so `t` and distance differ away from the ends and middle
+        let curve = CubicBezier::new(Vec3::ZERO, Vec3::ZERO, vec3(3., 0., 0.), vec3(3., 0., 0.));
+        assert!((curve.sample(0.25).x - 0.75).abs() > 0.1);
+
+        (0..=30).for_each(|step| {
+            let distance = step as f32 * 0.1;
+            let point = curve.sample_at_distance(distance);
+            assert!(
+                (point.x - distance).abs() < 1e-2,
+                "{} at {}",
+                point.x,
+                distance
+            );
+        });
+
+        let path = LinearPath::new(vec![Vec3::ZERO, Vec3::X, vec3(5., 0., 0.)]).unwrap();
+        assert!((path.sample_at_distance(3.).x - 3.).abs() < 1e-5);
+        assert_eq!(path.sample_at_distance(-1.), Vec3::ZERO);
+        assert_eq!(path.sample_at_distance(10.), vec3(5., 0., 0.));
+    }
+
+    #[test]
+    fn once_clamps_to_the_ends() {
+        assert_eq!(LoopMode::Once.distance_along(-1., 10.), (0., false));
+        assert_eq!(LoopMode::Once.distance_along(10., 10.), (10., false));
+        assert_eq!(LoopMode::Once.distance_along(15., 10.), (10., false));
+        assert_eq!(LoopMode::Once.wrap(15., 10.), 15.);
+    }
+
+    #[test]
+    fn loop_wraps_to_the_start() {
+        assert_eq!(LoopMode::Loop.distance_along(9.5, 10.), (9.5, false));
+        assert_eq!(LoopMode::Loop.distance_along(10., 10.), (0., false));
+        assert_eq!(LoopMode::Loop.distance_along(25., 10.), (5., false));
+        assert_eq!(LoopMode::Loop.distance_along(-1., 10.), (9., false));
+        assert_eq!(LoopMode::Loop.wrap(25., 10.), 5.);
+    }
+
+    #[test]
+    fn ping_pong_reverses_at_either_end() {
+        assert_eq!(LoopMode::PingPong.distance_along(10., 10.), (10., false));
+        assert_eq!(LoopMode::PingPong.distance_along(12., 10.), (8., true));
+        assert_eq!(LoopMode::PingPong.distance_along(20., 10.), (0., false));
+        assert_eq!(LoopMode::PingPong.distance_along(23., 10.), (3., false));
+        assert_eq!(LoopMode::PingPong.distance_along(-2., 10.), (2., true));
+        assert_eq!(LoopMode::PingPong.wrap(45., 10.), 5.);
+    }
+
+    #[test]
+    fn wrapping_keeps_the_position() {
+        [LoopMode::Once, LoopMode::Loop, LoopMode::PingPong]
+            .into_iter()
+            .for_each(|mode| {
+                [-13., 0., 7., 10., 20., 33.]
+                    .into_iter()
+                    .for_each(|travelled| {
+                        assert_eq!(
+                            mode.distance_along(mode.wrap(travelled, 10.), 10.),
+                            mode.distance_along(travelled, 10.),
+                            "{:?} at {}",
+                            mode,
+                            travelled
+                        );
+                    });
+            });
+    }
+
+    #[test]
+    fn empty_curves_stay_at_the_start() {
+        [LoopMode::Once, LoopMode::Loop, LoopMode::PingPong]
+            .into_iter()
+            .for_each(|mode| {
+                assert_eq!(mode.distance_along(5., 0.), (0., false));
+                assert_eq!(mode.wrap(5., 0.), 5.);
+            });
+    }
+}
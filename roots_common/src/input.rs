@@ -2,7 +2,7 @@
 
 use std::{collections::HashSet, hash::Hash};
 
-use crate::FastHasher;
+use crate::{coords::WindowPx, FastHasher};
 
 //====================================================================
 
@@ -76,9 +76,14 @@ pub fn reset_input<T>(input: &mut Input<T>) {
 
 #[derive(Debug, Default)]
 pub struct MouseInput {
-    position: glam::Vec2,
+    position: WindowPx,
     motion_delta: glam::Vec2,
     scroll: glam::Vec2,
+
+    /// Motion already used this frame, by the tick and then by late latching. Motion
+    /// past it is kept for the next frame on reset. `None` unless late latching.
+    used_motion: Option<glam::Vec2>,
+    late_motion: glam::Vec2,
 }
 
 impl MouseInput {
@@ -87,8 +92,9 @@ impl MouseInput {
         Self::default()
     }
 
+    /// Cursor position in window pixels.
     #[inline]
-    pub fn position(&self) -> glam::Vec2 {
+    pub fn position(&self) -> WindowPx {
         self.position
     }
 
@@ -101,11 +107,18 @@ impl MouseInput {
     pub fn scroll(&self) -> glam::Vec2 {
         self.scroll
     }
+
+    /// Motion that arrived after the last tick started and was applied by late
+    /// latching, so the tick can report what the previous frame latched.
+    #[inline]
+    pub fn late_motion(&self) -> glam::Vec2 {
+        self.late_motion
+    }
 }
 
 #[inline]
 pub fn process_mouse_position(input: &mut MouseInput, position: (f64, f64)) {
-    input.position = glam::vec2(position.0 as f32, position.1 as f32);
+    input.position = WindowPx::new(position.0 as f32, position.1 as f32);
 }
 
 #[inline]
@@ -118,8 +131,33 @@ pub fn process_mouse_scroll(input: &mut MouseInput, delta: (f32, f32)) {
     input.scroll += glam::vec2(delta.0, delta.1);
 }
 
+/// Mark the motion so far as seen by the tick, so `take_late_motion` only returns
+/// what arrives after.
+#[inline]
+pub fn begin_tick_motion(input: &mut MouseInput) {
+    input.used_motion = Some(input.motion_delta);
+    input.late_motion = glam::Vec2::ZERO;
+}
+
+/// Motion since `begin_tick_motion` or the last call, marked as used so it isn't
+/// carried over to the next tick.
+pub fn take_late_motion(input: &mut MouseInput) -> glam::Vec2 {
+    let Some(used) = input.used_motion else {
+        return glam::Vec2::ZERO;
+    };
+
+    let late = input.motion_delta - used;
+    input.used_motion = Some(input.motion_delta);
+    input.late_motion += late;
+    late
+}
+
+/// Motion not yet used by the tick or late latching is kept for the next frame.
 pub fn reset_mouse_input(input: &mut MouseInput) {
-    input.motion_delta = glam::Vec2::ZERO;
+    input.motion_delta = match input.used_motion.take() {
+        Some(used) => input.motion_delta - used,
+        None => glam::Vec2::ZERO,
+    };
     input.scroll = glam::Vec2::ZERO;
 }
 
@@ -9,8 +9,15 @@ use std::{
 use rustc_hash::FxHasher;
 use web_time::{Duration, Instant};
 
+pub mod coords;
+pub mod curve;
 pub mod input;
+pub mod rng;
 pub mod spatial;
+pub mod theme;
+pub mod timeline;
+#[cfg(feature = "serde")]
+pub mod versioned;
 
 //====================================================================
 
@@ -65,6 +72,16 @@ pub struct Time {
     last_frame: Instant,
     delta: Duration,
     delta_seconds: f32,
+    scale: f32,
+
+    throttled: Option<ThrottleReason>,
+}
+
+/// Why the app is currently running in its background mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrottleReason {
+    Unfocused,
+    Occluded,
 }
 
 impl Default for Time {
@@ -74,6 +91,8 @@ impl Default for Time {
             last_frame: Instant::now(),
             delta: Duration::ZERO,
             delta_seconds: 0.,
+            scale: 1.,
+            throttled: None,
         }
     }
 }
@@ -89,24 +108,55 @@ impl Time {
         &self.elapsed
     }
 
+    /// Real time since the last frame. Unaffected by the time scale.
     #[inline]
     pub fn delta(&self) -> &Duration {
         &self.delta
     }
 
+    /// Seconds since the last frame, multiplied by the time scale.
     #[inline]
     pub fn delta_seconds(&self) -> f32 {
         self.delta_seconds
     }
+
+    #[inline]
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Speed up or slow down `delta_seconds` from the next frame. Negative values are clamped to 0.
+    #[inline]
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.);
+    }
+
+    /// Why this frame was throttled or paused by the background behavior. `None` while
+    /// in the foreground, or in the background with `BackgroundBehavior::Continue`.
+    #[inline]
+    pub fn throttled(&self) -> Option<ThrottleReason> {
+        self.throttled
+    }
 }
 
 pub fn tick_time(time: &mut Time) {
     time.delta = time.last_frame.elapsed();
-    time.delta_seconds = time.delta.as_secs_f32();
+    time.delta_seconds = time.delta.as_secs_f32() * time.scale;
 
     time.last_frame = Instant::now();
 }
 
+/// Limit the current delta. Useful after a pause so simulations don't jump.
+pub fn clamp_delta(time: &mut Time, max: Duration) {
+    time.delta = time.delta.min(max);
+    time.delta_seconds = time.delta.as_secs_f32() * time.scale;
+}
+
+#[inline]
+pub fn set_throttled(time: &mut Time, reason: Option<ThrottleReason>) {
+    time.throttled = reason;
+}
+
 //====================================================================
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,392 @@
+//====================================================================
+
+use std::{
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut, Range},
+};
+
+use rustc_hash::FxHasher;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+//====================================================================
+
+const MULTIPLIER: u64 = 6364136223846793005;
+
+/// Small, fast PCG32 random number generator. The same seed always produces the
+/// same sequence on every platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+    increment: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, 0xda3e_39cb_94b9_5bdb)
+    }
+
+    fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            seed,
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Seeded from the system time. Not reproducible between runs.
+    pub fn from_entropy() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+
+        let mut hasher = FxHasher::default();
+        nanos.hash(&mut hasher);
+        (&nanos as *const u64 as usize).hash(&mut hasher);
+
+        Self::new(hasher.finish())
+    }
+
+    /// The seed this generator was created with.
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Create an independent child stream. The child only depends on this
+    /// generator's seed and `label`, not on how many values have been drawn, so
+    /// systems stay deterministic regardless of the order they run in.
+    pub fn fork(&self, label: &str) -> Rng {
+        let mut hasher = FxHasher::default();
+        self.seed.hash(&mut hasher);
+        label.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        Self::with_stream(hash, hash.rotate_left(32))
+    }
+}
+
+//--------------------------------------------------
+
+impl Rng {
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// Uniform value in `0..1`.
+    #[inline]
+    pub fn gen_f32(&mut self) -> f32 {
+        // 24 bits is the full precision of an f32 mantissa
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Uniform value in `-1..1`.
+    #[inline]
+    pub fn gen_signed(&mut self) -> f32 {
+        self.gen_f32() * 2. - 1.
+    }
+
+    #[inline]
+    pub fn gen_bool(&mut self, probability: f32) -> bool {
+        self.gen_f32() < probability
+    }
+
+    #[inline]
+    pub fn gen_range_f32(&mut self, range: Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.gen_f32()
+    }
+
+    /// Uniform value in the range. Returns `range.start` if the range is empty.
+    pub fn gen_range_u32(&mut self, range: Range<u32>) -> u32 {
+        let span = range.end.saturating_sub(range.start);
+        if span == 0 {
+            return range.start;
+        }
+
+        // Lemire's method - multiply and reject the biased low values
+        let threshold = span.wrapping_neg() % span;
+        loop {
+            let value = self.next_u32() as u64 * span as u64;
+            if value as u32 >= threshold {
+                return range.start + (value >> 32) as u32;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn gen_range_i32(&mut self, range: Range<i32>) -> i32 {
+        let span = range.end.wrapping_sub(range.start) as u32;
+        match range.start < range.end {
+            true => range.start.wrapping_add(self.gen_range_u32(0..span) as i32),
+            false => range.start,
+        }
+    }
+
+    #[inline]
+    pub fn gen_range_usize(&mut self, range: Range<usize>) -> usize {
+        let span = range.end.saturating_sub(range.start).min(u32::MAX as usize) as u32;
+        range.start + self.gen_range_u32(0..span) as usize
+    }
+
+    pub fn gen_unit_vec2(&mut self) -> glam::Vec2 {
+        let angle = self.gen_f32() * std::f32::consts::TAU;
+        glam::Vec2::from_angle(angle)
+    }
+
+    pub fn gen_unit_vec3(&mut self) -> glam::Vec3 {
+        let z = self.gen_signed();
+        let radius = (1. - z * z).max(0.).sqrt();
+        let (sin, cos) = (self.gen_f32() * std::f32::consts::TAU).sin_cos();
+
+        glam::vec3(radius * cos, radius * sin, z)
+    }
+
+    /// Uniformly distributed point inside a unit sphere.
+    #[inline]
+    pub fn gen_in_sphere(&mut self) -> glam::Vec3 {
+        self.gen_unit_vec3() * self.gen_f32().cbrt()
+    }
+
+    /// Uniformly distributed point inside a unit circle.
+    #[inline]
+    pub fn gen_in_circle(&mut self) -> glam::Vec2 {
+        self.gen_unit_vec2() * self.gen_f32().sqrt()
+    }
+
+    #[inline]
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        match items.is_empty() {
+            true => None,
+            false => Some(&items[self.gen_range_usize(0..items.len())]),
+        }
+    }
+
+    /// Pick an index with probability proportional to its weight. Negative weights
+    /// count as zero. Returns `None` if no weight is positive.
+    pub fn weighted_index(&mut self, weights: &[f32]) -> Option<usize> {
+        let total = weights.iter().map(|weight| weight.max(0.)).sum::<f32>();
+        if total <= 0. {
+            return None;
+        }
+
+        let mut target = self.gen_f32() * total;
+
+        weights
+            .iter()
+            .position(|weight| {
+                target -= weight.max(0.);
+                target < 0.
+            })
+            // Float rounding can leave the target just above zero
+            .or_else(|| weights.iter().rposition(|weight| *weight > 0.))
+    }
+
+    /// Pick an item with probability proportional to its weight.
+    #[inline]
+    pub fn choose_weighted<'a, T>(&mut self, items: &'a [(T, f32)]) -> Option<&'a T> {
+        let weights = items.iter().map(|(_, weight)| *weight).collect::<Vec<_>>();
+        self.weighted_index(&weights).map(|index| &items[index].0)
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        (1..items.len()).rev().for_each(|index| {
+            let other = self.gen_range_usize(0..index + 1);
+            items.swap(index, other);
+        });
+    }
+}
+
+//====================================================================
+
+/// The random stream shared by a world's systems. Seed it explicitly for
+/// reproducible simulations. Defaults to an entropy seed.
+#[derive(Debug, Clone)]
+pub struct WorldRng(Rng);
+
+impl WorldRng {
+    #[inline]
+    pub fn new(seed: u64) -> Self {
+        Self(Rng::new(seed))
+    }
+
+    #[inline]
+    pub fn from_entropy() -> Self {
+        Self(Rng::from_entropy())
+    }
+}
+
+impl Default for WorldRng {
+    #[inline]
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl Deref for WorldRng {
+    type Target = Rng;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for WorldRng {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(rng: &mut Rng) -> Vec<u32> {
+        (0..64).map(|_| rng.next_u32()).collect()
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(1234);
+        let mut b = Rng::new(1234);
+        assert_eq!(sequence(&mut a), sequence(&mut b));
+        assert_eq!(a, b);
+        assert_eq!(a.seed(), 1234);
+
+        assert_ne!(sequence(&mut Rng::new(1234)), sequence(&mut Rng::new(1235)));
+    }
+
+    #[test]
+    fn sequence_is_stable() {
+        // Saved games and replays rely on these never changing
+        let mut rng = Rng::new(42);
+        let values = (0..4).map(|_| rng.next_u32()).collect::<Vec<_>>();
+        assert_eq!(values, [1898997482, 1014631766, 4096008554, 633901381]);
+    }
+
+    #[test]
+    fn values_stay_in_range() {
+        let mut rng = Rng::new(7);
+
+        (0..10_000).for_each(|_| {
+            let value = rng.gen_f32();
+            assert!((0. ..1.).contains(&value));
+
+            let value = rng.gen_signed();
+            assert!((-1. ..1.).contains(&value));
+
+            let value = rng.gen_range_f32(-3. ..5.);
+            assert!((-3. ..5.).contains(&value));
+
+            assert!((10..13).contains(&rng.gen_range_u32(10..13)));
+            assert!((-5..-2).contains(&rng.gen_range_i32(-5..-2)));
+            assert!((i32::MIN..i32::MAX).contains(&rng.gen_range_i32(i32::MIN..i32::MAX)));
+            assert!((3..4).contains(&rng.gen_range_usize(3..4)));
+
+            assert!((rng.gen_unit_vec3().length() - 1.).abs() < 1e-5);
+            assert!(rng.gen_in_sphere().length() <= 1. + 1e-5);
+            assert!(rng.gen_in_circle().length() <= 1. + 1e-5);
+        });
+    }
+
+    #[test]
+    fn ranges_cover_every_value() {
+        let mut rng = Rng::new(99);
+        let mut seen = [false; 6];
+
+        (0..1000).for_each(|_| seen[rng.gen_range_usize(0..6)] = true);
+        assert!(seen.iter().all(|seen| *seen));
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn empty_ranges_return_start() {
+        let mut rng = Rng::new(0);
+
+        assert_eq!(rng.gen_range_u32(5..5), 5);
+        assert_eq!(rng.gen_range_u32(5..2), 5);
+        assert_eq!(rng.gen_range_i32(-2..-2), -2);
+        assert_eq!(rng.gen_range_usize(8..3), 8);
+        assert_eq!(rng.choose::<u32>(&[]), None);
+        assert_eq!(rng.weighted_index(&[0., -1.]), None);
+    }
+
+    #[test]
+    fn weighted_index_skips_non_positive_weights() {
+        let mut rng = Rng::new(3);
+
+        (0..1000).for_each(|_| {
+            let index = rng.weighted_index(&[0., 1., -2., 3.]).unwrap();
+            assert!(index == 1 || index == 3);
+        });
+    }
+
+    #[test]
+    fn shuffle_is_a_deterministic_permutation() {
+        let mut items = (0..32).collect::<Vec<_>>();
+        let mut other = items.clone();
+
+        Rng::new(5).shuffle(&mut items);
+        Rng::new(5).shuffle(&mut other);
+        assert_eq!(items, other);
+
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn forks_ignore_draw_order() {
+        let parent = Rng::new(2024);
+        let mut drawn = parent.clone();
+        sequence(&mut drawn);
+
+        // Forking after drawing gives the same child stream
+        assert_eq!(
+            sequence(&mut parent.fork("particles")),
+            sequence(&mut drawn.fork("particles"))
+        );
+
+        assert_ne!(
+            sequence(&mut parent.fork("particles")),
+            sequence(&mut parent.fork("ai"))
+        );
+        assert_ne!(
+            sequence(&mut parent.fork("particles")),
+            sequence(&mut Rng::new(2024).fork("particles").fork("particles"))
+        );
+        assert_ne!(
+            sequence(&mut parent.fork("particles")),
+            sequence(&mut Rng::new(2025).fork("particles"))
+        );
+        assert_ne!(
+            sequence(&mut parent.clone()),
+            sequence(&mut parent.fork(""))
+        );
+    }
+
+    #[test]
+    fn world_rng_seeds_like_rng() {
+        let mut world = WorldRng::new(11);
+        assert_eq!(sequence(&mut world), sequence(&mut Rng::new(11)));
+    }
+}
@@ -1,5 +1,7 @@
 //====================================================================
 
+use crate::coords::CoordinateConvention;
+
 //====================================================================
 
 #[derive(Default, Debug)]
@@ -20,11 +22,34 @@ impl GlobalTransform {
     pub fn to_scale_rotation_translation(&self) -> (glam::Vec3, glam::Quat, glam::Vec3) {
         self.0.to_scale_rotation_translation()
     }
+
+    /// False if any element is NaN or infinite.
+    #[inline]
+    pub fn is_finite(&self) -> bool {
+        self.0.is_finite()
+    }
+}
+
+//--------------------------------------------------
+
+/// An f64 position for entities far from the origin. The `Transform` (and so the
+/// `GlobalTransform`) is relative to it, and the two are added together when rendering.
+/// Children in a transform hierarchy don't inherit it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldPosition(pub glam::DVec3);
+
+impl WorldPosition {
+    #[inline]
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self(glam::dvec3(x, y, z))
+    }
 }
 
 //--------------------------------------------------
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
     pub translation: glam::Vec3,
     pub rotation: glam::Quat,
@@ -129,14 +154,10 @@ impl Transform {
 }
 
 impl Transform {
+    /// Turn the forward axis of the active `CoordinateConvention` towards `direction`.
+    #[inline]
     pub fn look_to(&mut self, direction: glam::Vec3, up: glam::Vec3) {
-        let back = -direction.normalize();
-        let right = up
-            .cross(back)
-            .try_normalize()
-            .unwrap_or_else(|| up.any_orthogonal_vector());
-        let up = back.cross(right);
-        self.rotation = glam::Quat::from_mat3(&glam::Mat3::from_cols(right, up, back));
+        self.rotation = CoordinateConvention::active().rotation_looking_to(direction, up);
     }
 
     #[inline]
@@ -144,14 +165,26 @@ impl Transform {
         self.look_to(target.into() - self.translation, up.into());
     }
 
+    /// A copy turned to face `target`, with the up axis of the active convention.
+    #[inline]
+    pub fn looking_at(mut self, target: impl Into<glam::Vec3>) -> Self {
+        self.look_at(target, CoordinateConvention::active().up());
+        self
+    }
+
     #[inline]
     pub fn forward(&self) -> glam::Vec3 {
-        (self.rotation * glam::Vec3::Z).normalize_or_zero()
+        (self.rotation * CoordinateConvention::active().forward()).normalize_or_zero()
     }
 
     #[inline]
     pub fn right(&self) -> glam::Vec3 {
-        (self.rotation * glam::Vec3::X).normalize_or_zero()
+        (self.rotation * CoordinateConvention::active().right()).normalize_or_zero()
+    }
+
+    #[inline]
+    pub fn up(&self) -> glam::Vec3 {
+        (self.rotation * CoordinateConvention::active().up()).normalize_or_zero()
     }
 
     #[inline]
@@ -163,6 +196,12 @@ impl Transform {
 }
 
 impl Transform {
+    /// False if the translation, rotation or scale contain a NaN or infinite value.
+    #[inline]
+    pub fn is_finite(&self) -> bool {
+        self.translation.is_finite() && self.rotation.is_finite() && self.scale.is_finite()
+    }
+
     #[inline]
     pub fn to_matrix(&self) -> glam::Mat4 {
         glam::Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
@@ -252,3 +291,78 @@ impl std::ops::Sub<&Self> for Transform {
 }
 
 //====================================================================
+
+/// An axis aligned box, such as the local space bounds of a mesh.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: glam::Vec3,
+    pub max: glam::Vec3,
+}
+
+impl Aabb {
+    #[inline]
+    pub fn new(min: glam::Vec3, max: glam::Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box containing all `points`. Empty at the origin if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = glam::Vec3>) -> Self {
+        let mut points = points.into_iter();
+
+        let Some(first) = points.next() else {
+            return Self::default();
+        };
+
+        points.fold(Self::new(first, first), |aabb, point| Self {
+            min: aabb.min.min(point),
+            max: aabb.max.max(point),
+        })
+    }
+
+    #[inline]
+    pub fn center(&self) -> glam::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    #[inline]
+    pub fn size(&self) -> glam::Vec3 {
+        self.max - self.min
+    }
+
+    /// Whether `point` is inside or on the surface of the box.
+    #[inline]
+    pub fn contains_point(&self, point: glam::Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// The smallest box containing both boxes.
+    #[inline]
+    pub fn merge(&self, other: &Aabb) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn corners(&self) -> [glam::Vec3; 8] {
+        std::array::from_fn(|index| {
+            glam::Vec3::select(
+                glam::BVec3::new(index & 1 == 0, index & 2 == 0, index & 4 == 0),
+                self.min,
+                self.max,
+            )
+        })
+    }
+
+    /// The axis aligned box containing this one once transformed, such as from local
+    /// to world space. Grows to fit rotated boxes, so may be larger than needed.
+    pub fn transform(&self, transform: &glam::Affine3A) -> Self {
+        Self::from_points(
+            self.corners()
+                .into_iter()
+                .map(|corner| transform.transform_point3(corner)),
+        )
+    }
+}
+
+//====================================================================
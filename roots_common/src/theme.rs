@@ -0,0 +1,140 @@
+//====================================================================
+
+// Colors are the values handed to the shaders, so they're linear - they're encoded
+// to sRGB on the way to the surface. `srgb` converts from the usual hex values.
+
+/// WCAG's minimum contrast ratio for body text.
+pub const MIN_TEXT_CONTRAST: f32 = 4.5;
+
+/// The Okabe-Ito palette, distinguishable under the common forms of color blindness.
+/// In order: black, orange, sky blue, bluish green, yellow, blue, vermillion and
+/// reddish purple, as sRGB hex.
+pub const OKABE_ITO: [u32; 8] = [
+    0x000000, 0xE69F00, 0x56B4E9, 0x009E73, 0xF0E442, 0x0072B2, 0xD55E00, 0xCC79A7,
+];
+
+/// Linear color from an sRGB hex value such as `0xE69F00`.
+pub fn srgb(hex: u32) -> glam::Vec4 {
+    let channel = |shift: u32| {
+        let value = ((hex >> shift) & 0xFF) as f32 / 255.;
+        match value <= 0.04045 {
+            true => value / 12.92,
+            false => ((value + 0.055) / 1.055).powf(2.4),
+        }
+    };
+
+    glam::vec4(channel(16), channel(8), channel(0), 1.)
+}
+
+/// Relative luminance of a linear color, ignoring alpha.
+#[inline]
+pub fn relative_luminance(color: glam::Vec4) -> f32 {
+    color.truncate().dot(glam::vec3(0.2126, 0.7152, 0.0722))
+}
+
+/// WCAG contrast ratio between two colors, from 1 for equal luminance to 21 for
+/// black on white.
+pub fn contrast_ratio(a: glam::Vec4, b: glam::Vec4) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+//====================================================================
+
+/// Named colors used as defaults by the built-in UI - the fps overlay, debug text,
+/// loading bars, text selection and `Ui3d` menus - and the clear color. Set with
+/// `RendererState::set_theme`. Components with an explicit color keep it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Clear color and overlay backdrops.
+    pub background: glam::Vec4,
+    /// Panels drawn over the background, such as menus and loading bar tracks.
+    pub surface: glam::Vec4,
+    /// Fills and highlights, such as loading bars and selected text.
+    pub primary: glam::Vec4,
+    /// The selected option of a menu.
+    pub accent: glam::Vec4,
+    pub warning: glam::Vec4,
+    pub text: glam::Vec4,
+    pub text_disabled: glam::Vec4,
+}
+
+impl Default for Theme {
+    #[inline]
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// Matches the colors used before themes were added.
+    pub fn dark() -> Self {
+        Self {
+            name: "dark",
+            background: glam::vec4(0.2, 0.2, 0.2, 1.),
+            surface: glam::vec4(0.5, 0.5, 0.5, 0.7),
+            primary: glam::vec4(0.2, 0.4, 0.9, 1.),
+            accent: glam::vec4(0.7, 0.7, 0.7, 0.8),
+            warning: glam::vec4(0.95, 0.55, 0.1, 1.),
+            text: glam::vec4(1., 1., 1., 1.),
+            text_disabled: glam::vec4(0.55, 0.55, 0.55, 1.),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            name: "light",
+            background: glam::vec4(0.85, 0.85, 0.87, 1.),
+            surface: glam::vec4(0.97, 0.97, 0.97, 0.9),
+            primary: glam::vec4(0.05, 0.25, 0.75, 1.),
+            accent: glam::vec4(0.6, 0.72, 0.95, 0.9),
+            warning: glam::vec4(0.75, 0.3, 0., 1.),
+            text: glam::vec4(0.02, 0.02, 0.02, 1.),
+            text_disabled: glam::vec4(0.3, 0.3, 0.3, 1.),
+        }
+    }
+
+    /// Dark theme built from the `OKABE_ITO` palette, so no two roles rely on a
+    /// red-green or blue-yellow difference alone.
+    pub fn color_blind_safe() -> Self {
+        Self {
+            name: "color_blind_safe",
+            background: srgb(0x101010),
+            surface: srgb(0x3A3A3A).with_w(0.85),
+            primary: srgb(OKABE_ITO[5]),
+            accent: srgb(OKABE_ITO[1]).with_w(0.9),
+            warning: srgb(OKABE_ITO[6]),
+            text: glam::Vec4::ONE,
+            text_disabled: srgb(0x9A9A9A),
+        }
+    }
+
+    /// Every built-in theme, for listing in a settings menu.
+    pub fn builtin() -> [Theme; 3] {
+        [Self::dark(), Self::light(), Self::color_blind_safe()]
+    }
+
+    /// A built-in theme by `name`.
+    pub fn by_name(name: &str) -> Option<Theme> {
+        Self::builtin().into_iter().find(|theme| theme.name == name)
+    }
+
+    /// A readable text color over `background`. Prefers the theme's text color, then
+    /// its background color, then whichever of black or white contrasts more.
+    pub fn contrast_text_for(&self, background: glam::Vec4) -> glam::Vec4 {
+        [self.text, self.background.with_w(1.)]
+            .into_iter()
+            .find(|color| contrast_ratio(*color, background) >= MIN_TEXT_CONTRAST)
+            .unwrap_or_else(|| {
+                match contrast_ratio(glam::Vec4::ONE, background)
+                    >= contrast_ratio(glam::Vec4::W, background)
+                {
+                    true => glam::Vec4::ONE,
+                    false => glam::Vec4::W,
+                }
+            })
+    }
+}
+
+//====================================================================
@@ -0,0 +1,573 @@
+//====================================================================
+
+use crate::Time;
+
+//====================================================================
+
+// Positions are kept in f64 seconds so long timelines don't drift. An occurrence is
+// due once the timeline's position reaches its time, and is delivered exactly once
+// by `drain_due_events` however the position got there - several occurrences of a
+// repeating event can come out of a single long frame.
+
+pub type TimelineEventId = u32;
+
+/// How `Timeline::seek` treats events between the current position and a later target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SeekPolicy {
+    /// Events before the target are consumed without being delivered.
+    #[default]
+    Skip,
+    /// Events before the target are delivered by the next `drain_due_events`, late.
+    Replay,
+}
+
+/// Repeats of an event after its first occurrence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Repeat {
+    /// Seconds between occurrences.
+    pub interval: f64,
+    /// Total occurrences including the first, or forever if `None`.
+    pub count: Option<u32>,
+    /// Fraction of the interval every second occurrence is delayed by, for swung
+    /// rhythms. Clamped below 1 so occurrences stay in order.
+    pub swing: f64,
+}
+
+impl Repeat {
+    #[inline]
+    pub fn every(interval: f64) -> Self {
+        Self {
+            interval,
+            count: None,
+            swing: 0.,
+        }
+    }
+
+    #[inline]
+    pub fn with_count(mut self, count: u32) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    #[inline]
+    pub fn with_swing(mut self, swing: f64) -> Self {
+        self.swing = swing;
+        self
+    }
+}
+
+/// An occurrence delivered by `Timeline::drain_due_events`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DueEvent<T> {
+    pub id: TimelineEventId,
+    /// Which occurrence of a repeating event this is, from 0.
+    pub occurrence: u32,
+    /// Scheduled timeline position in seconds.
+    pub time: f64,
+    /// Timeline seconds between the scheduled time and the position it was delivered at.
+    pub late: f64,
+    pub data: T,
+}
+
+impl<T> DueEvent<T> {
+    /// How many frames of `frame_delta` seconds late the event is, to pre-advance
+    /// effects spawned by it.
+    #[inline]
+    pub fn late_frames(&self, frame_delta: f64) -> f64 {
+        match frame_delta > 0. {
+            true => self.late / frame_delta,
+            false => 0.,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Scheduled<T> {
+    id: TimelineEventId,
+    start: f64,
+    repeat: Option<Repeat>,
+    /// Next occurrence not yet delivered, or `None` once all are.
+    next: Option<u32>,
+    data: T,
+}
+
+impl<T> Scheduled<T> {
+    fn occurrence_time(&self, occurrence: u32) -> f64 {
+        match self.repeat {
+            Some(repeat) => {
+                let swing = match occurrence % 2 {
+                    1 => repeat.swing.clamp(0., 0.999) * repeat.interval,
+                    _ => 0.,
+                };
+                self.start + occurrence as f64 * repeat.interval + swing
+            }
+            None => self.start,
+        }
+    }
+
+    #[inline]
+    fn in_range(&self, occurrence: u32) -> bool {
+        match self.repeat {
+            Some(repeat) => repeat.count.is_none_or(|count| occurrence < count),
+            None => occurrence == 0,
+        }
+    }
+
+    /// Rewind or fast forward to the first occurrence after `position`.
+    fn reset_after(&mut self, position: f64) {
+        let first = match self.repeat {
+            Some(repeat) if repeat.interval > 0. && position > self.start => {
+                // Start a step early, as swing can push an occurrence past the estimate
+                (((position - self.start) / repeat.interval).floor() as i64 - 1).max(0) as u32
+            }
+            _ => 0,
+        };
+
+        let mut occurrence = first;
+        while self.in_range(occurrence) && self.occurrence_time(occurrence) <= position {
+            occurrence += 1;
+        }
+
+        self.next = self.in_range(occurrence).then_some(occurrence);
+    }
+}
+
+//====================================================================
+
+/// Events scheduled on a clock, for rhythm mechanics and scripted sequences. The
+/// timeline is advanced from a time source each frame - `tick` with the app's `Time`,
+/// `advance` with a manual delta, or `sync` with an absolute position such as an
+/// audio playback position - then `drain_due_events` returns what came due.
+#[derive(Debug, Clone)]
+pub struct Timeline<T> {
+    events: Vec<Scheduled<T>>,
+    next_id: TimelineEventId,
+
+    position: f64,
+    /// Position up to which events have been delivered.
+    drained: f64,
+    rate: f64,
+    paused: bool,
+    /// Beats per minute used by the `_beat` methods.
+    bpm: f64,
+}
+
+impl<T> Default for Timeline<T> {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            next_id: 0,
+            position: 0.,
+            drained: f64::NEG_INFINITY,
+            rate: 1.,
+            paused: false,
+            bpm: 120.,
+        }
+    }
+}
+
+impl<T: Clone> Timeline<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_bpm(mut self, bpm: f64) -> Self {
+        self.bpm = bpm;
+        self
+    }
+
+    //--------------------------------------------------
+
+    /// Schedule a one-shot event at `time` seconds.
+    pub fn add_at(&mut self, time: f64, data: T) -> TimelineEventId {
+        self.add(time, None, data)
+    }
+
+    /// Schedule an event first at `start` seconds, then repeating. Panics if the
+    /// interval isn't positive, as every occurrence would be due at once.
+    pub fn add_repeating(&mut self, start: f64, repeat: Repeat, data: T) -> TimelineEventId {
+        assert!(
+            repeat.interval > 0.,
+            "Repeat interval must be positive, got {}",
+            repeat.interval
+        );
+        self.add(start, Some(repeat), data)
+    }
+
+    /// Schedule a one-shot event at `beat`, counted from 0 at position 0.
+    #[inline]
+    pub fn add_at_beat(&mut self, beat: f64, data: T) -> TimelineEventId {
+        self.add_at(self.beats_to_seconds(beat), data)
+    }
+
+    fn add(&mut self, start: f64, repeat: Option<Repeat>, data: T) -> TimelineEventId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut scheduled = Scheduled {
+            id,
+            start,
+            repeat,
+            next: None,
+            data,
+        };
+        // Occurrences at or before the last drain are never delivered
+        scheduled.reset_after(self.drained);
+
+        self.events.push(scheduled);
+        id
+    }
+
+    /// Unschedule an event. Finished events are kept until removed, so seeking back
+    /// before them makes them due again. Returns whether it existed.
+    pub fn remove(&mut self, id: TimelineEventId) -> bool {
+        let len = self.events.len();
+        self.events.retain(|event| event.id != id);
+        self.events.len() != len
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    //--------------------------------------------------
+
+    /// Advance by the app's scaled frame time.
+    #[inline]
+    pub fn tick(&mut self, time: &Time) {
+        self.advance(time.delta_seconds() as f64);
+    }
+
+    /// Advance by `delta` seconds, multiplied by the rate. Does nothing while paused.
+    pub fn advance(&mut self, delta: f64) {
+        if self.paused || delta <= 0. {
+            return;
+        }
+
+        self.position += delta * self.rate;
+    }
+
+    /// Move forward to an absolute position from an external clock. Earlier positions
+    /// are ignored so a jittery clock can't fire events twice - use `seek` to go back.
+    /// The rate is not applied, as the external clock is already running at its own.
+    pub fn sync(&mut self, position: f64) {
+        if self.paused || position <= self.position {
+            return;
+        }
+
+        self.position = position;
+    }
+
+    /// Jump to `position`. Events after it are due again when the timeline reaches
+    /// them, and events between the current position and a later target follow `policy`.
+    pub fn seek(&mut self, position: f64, policy: SeekPolicy) {
+        let forward = position > self.drained;
+
+        match (forward, policy) {
+            (true, SeekPolicy::Replay) => {}
+            _ => {
+                self.drained = position;
+                self.events
+                    .iter_mut()
+                    .for_each(|event| event.reset_after(position));
+            }
+        }
+
+        self.position = position;
+    }
+
+    //--------------------------------------------------
+
+    /// Every occurrence that came due since the last drain, in time order. Ties keep
+    /// the order the events were added in.
+    pub fn drain_due_events(&mut self) -> Vec<DueEvent<T>> {
+        let position = self.position;
+        let mut due = Vec::new();
+
+        self.events.iter_mut().for_each(|event| {
+            while let Some(occurrence) = event.next {
+                let time = event.occurrence_time(occurrence);
+                if time > position {
+                    break;
+                }
+
+                due.push(DueEvent {
+                    id: event.id,
+                    occurrence,
+                    time,
+                    late: position - time,
+                    data: event.data.clone(),
+                });
+
+                let next = occurrence + 1;
+                event.next = event.in_range(next).then_some(next);
+            }
+        });
+
+        // Stable, so events added first stay first at equal times
+        due.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        self.drained = position;
+        due
+    }
+
+    //--------------------------------------------------
+
+    #[inline]
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    /// Position in beats at the current bpm.
+    #[inline]
+    pub fn beat(&self) -> f64 {
+        self.seconds_to_beats(self.position)
+    }
+
+    #[inline]
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Speed up or slow down `advance` and `tick`. Negative values are clamped to 0.
+    #[inline]
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate.max(0.);
+    }
+
+    #[inline]
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    #[inline]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    #[inline]
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// Changes where future `_beat` events are placed. Scheduled events keep their time.
+    #[inline]
+    pub fn set_bpm(&mut self, bpm: f64) {
+        self.bpm = bpm;
+    }
+
+    #[inline]
+    pub fn beats_to_seconds(&self, beats: f64) -> f64 {
+        beats * 60. / self.bpm
+    }
+
+    #[inline]
+    pub fn seconds_to_beats(&self, seconds: f64) -> f64 {
+        seconds * self.bpm / 60.
+    }
+
+    /// Scheduled events, including finished ones.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// No event has occurrences left to deliver.
+    #[inline]
+    pub fn finished(&self) -> bool {
+        self.events.iter().all(|event| event.next.is_none())
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Steps are powers of two so positions and lateness compare exactly
+
+    fn drained(timeline: &mut Timeline<char>) -> Vec<(char, u32, f64, f64)> {
+        timeline
+            .drain_due_events()
+            .into_iter()
+            .map(|event| (event.data, event.occurrence, event.time, event.late))
+            .collect()
+    }
+
+    #[test]
+    fn irregular_steps_deliver_each_occurrence_once() {
+        let mut timeline = Timeline::new();
+        timeline.add_at(0.5, 'a');
+        timeline.add_repeating(1., Repeat::every(1.).with_count(3), 'b');
+
+        timeline.advance(0.25);
+        assert!(drained(&mut timeline).is_empty());
+
+        timeline.advance(0.375);
+        assert_eq!(drained(&mut timeline), vec![('a', 0, 0.5, 0.125)]);
+
+        // One long frame delivers several occurrences, each with its own lateness
+        timeline.advance(1.5);
+        assert_eq!(
+            drained(&mut timeline),
+            vec![('b', 0, 1., 1.125), ('b', 1, 2., 0.125)]
+        );
+
+        timeline.advance(0.);
+        assert!(drained(&mut timeline).is_empty());
+
+        timeline.advance(8.);
+        assert_eq!(drained(&mut timeline), vec![('b', 2, 3., 7.125)]);
+        assert!(timeline.finished());
+        assert!(drained(&mut timeline).is_empty());
+    }
+
+    #[test]
+    fn ties_keep_insertion_order() {
+        let mut timeline = Timeline::new();
+        timeline.add_at(1., 'b');
+        timeline.add_at(0.5, 'c');
+        timeline.add_at(1., 'a');
+
+        timeline.advance(1.);
+        let order = drained(&mut timeline)
+            .into_iter()
+            .map(|(data, ..)| data)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!['c', 'b', 'a']);
+    }
+
+    #[test]
+    fn paused_timeline_ignores_time() {
+        let mut timeline = Timeline::new();
+        timeline.add_at(1., 'a');
+
+        timeline.set_paused(true);
+        timeline.advance(4.);
+        timeline.sync(4.);
+        assert_eq!(timeline.position(), 0.);
+        assert!(drained(&mut timeline).is_empty());
+
+        timeline.set_paused(false);
+        timeline.advance(1.);
+        assert_eq!(drained(&mut timeline), vec![('a', 0, 1., 0.)]);
+    }
+
+    #[test]
+    fn rate_scales_advance_but_not_sync() {
+        let mut timeline = Timeline::<char>::new();
+
+        timeline.set_rate(2.);
+        timeline.advance(0.5);
+        assert_eq!(timeline.position(), 1.);
+
+        timeline.sync(1.5);
+        assert_eq!(timeline.position(), 1.5);
+
+        // Syncing backwards is ignored
+        timeline.sync(1.);
+        assert_eq!(timeline.position(), 1.5);
+
+        timeline.set_rate(-1.);
+        assert_eq!(timeline.rate(), 0.);
+        timeline.advance(1.);
+        assert_eq!(timeline.position(), 1.5);
+    }
+
+    #[test]
+    fn seek_skip_consumes_passed_events() {
+        let mut timeline = Timeline::new();
+        timeline.add_at(1., 'a');
+        timeline.add_repeating(0., Repeat::every(2.), 'b');
+
+        timeline.seek(3., SeekPolicy::Skip);
+        assert!(drained(&mut timeline).is_empty());
+
+        timeline.advance(1.);
+        assert_eq!(drained(&mut timeline), vec![('b', 2, 4., 0.)]);
+
+        // Seeking back makes earlier events due again
+        timeline.seek(0.5, SeekPolicy::Skip);
+        timeline.advance(0.5);
+        assert_eq!(drained(&mut timeline), vec![('a', 0, 1., 0.)]);
+    }
+
+    #[test]
+    fn seek_replay_delivers_passed_events_late() {
+        let mut timeline = Timeline::new();
+        timeline.add_at(1., 'a');
+        timeline.add_repeating(0., Repeat::every(2.).with_count(2), 'b');
+
+        timeline.seek(3., SeekPolicy::Replay);
+        assert_eq!(
+            drained(&mut timeline),
+            vec![('b', 0, 0., 3.), ('a', 0, 1., 2.), ('b', 1, 2., 1.)]
+        );
+        assert!(timeline.finished());
+
+        // Replay only applies going forward - going back still resets
+        timeline.seek(1.5, SeekPolicy::Replay);
+        assert!(drained(&mut timeline).is_empty());
+        timeline.advance(0.5);
+        assert_eq!(drained(&mut timeline), vec![('b', 1, 2., 0.)]);
+    }
+
+    #[test]
+    fn swing_delays_every_second_occurrence() {
+        let mut timeline = Timeline::new();
+        timeline.add_repeating(0., Repeat::every(1.).with_count(4).with_swing(0.5), 'a');
+
+        timeline.advance(4.);
+        let times = drained(&mut timeline)
+            .into_iter()
+            .map(|(_, _, time, _)| time)
+            .collect::<Vec<_>>();
+        assert_eq!(times, vec![0., 1.5, 2., 3.5]);
+
+        // Seeking between a beat and its swung offbeat doesn't skip the offbeat
+        timeline.seek(1.25, SeekPolicy::Skip);
+        timeline.advance(0.25);
+        assert_eq!(drained(&mut timeline), vec![('a', 1, 1.5, 0.)]);
+    }
+
+    #[test]
+    fn late_frames() {
+        let mut timeline = Timeline::new();
+        timeline.add_at(0.25, 'a');
+        timeline.advance(1.);
+
+        let event = timeline.drain_due_events().remove(0);
+        assert_eq!(event.late, 0.75);
+        assert_eq!(event.late_frames(0.25), 3.);
+        assert_eq!(event.late_frames(0.), 0.);
+    }
+
+    #[test]
+    fn events_added_in_the_past_are_not_delivered() {
+        let mut timeline = Timeline::new();
+        timeline.advance(2.);
+        timeline.drain_due_events();
+
+        timeline.add_at(1., 'a');
+        timeline.add_repeating(0., Repeat::every(1.5), 'b');
+
+        timeline.advance(1.);
+        assert_eq!(drained(&mut timeline), vec![('b', 2, 3., 0.)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "interval must be positive")]
+    fn zero_interval_is_rejected() {
+        let mut timeline = Timeline::new();
+        timeline.add_repeating(0., Repeat::every(0.), 'a');
+    }
+}
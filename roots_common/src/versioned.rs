@@ -0,0 +1,481 @@
+//====================================================================
+
+use std::{fmt::Display, sync::Mutex};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+//====================================================================
+
+/// Upgrades the json of one version of a format to the next.
+pub type MigrationFn = fn(Value) -> Value;
+
+struct Migration {
+    format: &'static str,
+    from_version: u32,
+    migrate: MigrationFn,
+}
+
+static MIGRATIONS: Mutex<Vec<Migration>> = Mutex::new(Vec::new());
+
+/// How fields in saved data that the current version of a type doesn't have are
+/// handled, such as ones left behind by a rename without a migration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownFields {
+    /// Ignore them, such as for settings where losing an option beats losing the file.
+    #[default]
+    Ignore,
+    /// Fail with `VersionError::UnknownFields`, such as for scenes where dropped
+    /// data would go unnoticed.
+    Deny,
+}
+
+/// A type saved with a format name and version, so older saves can be migrated and
+/// newer or unrelated ones rejected. Bump `VERSION` and register a migration with
+/// `register_migration` whenever a change would break existing saves.
+pub trait VersionedFormat: Serialize + DeserializeOwned {
+    /// Name stored in every save, such as `"graphics_settings"`.
+    const FORMAT: &'static str;
+    /// Version written by this build. Starts at 1.
+    const VERSION: u32;
+    const UNKNOWN_FIELDS: UnknownFields = UnknownFields::Ignore;
+}
+
+/// Register a migration from `from_version` of `T`'s format to the next version.
+/// Loading data saved at an older version applies each migration in sequence up to
+/// `T::VERSION`. Replaces any migration already registered from the same version.
+pub fn register_migration<T: VersionedFormat>(from_version: u32, migrate: MigrationFn) {
+    debug_assert!(
+        from_version < T::VERSION,
+        "Migration of '{}' from v{} doesn't lead to the current v{}",
+        T::FORMAT,
+        from_version,
+        T::VERSION
+    );
+
+    let mut migrations = MIGRATIONS.lock().unwrap();
+    migrations.retain(|migration| {
+        !(migration.format == T::FORMAT && migration.from_version == from_version)
+    });
+    migrations.push(Migration {
+        format: T::FORMAT,
+        from_version,
+        migrate,
+    });
+}
+
+fn find_migration(format: &str, from_version: u32) -> Option<MigrationFn> {
+    MIGRATIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|migration| migration.format == format && migration.from_version == from_version)
+        .map(|migration| migration.migrate)
+}
+
+//====================================================================
+
+/// The envelope every versioned save is stored in.
+#[derive(Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub format: String,
+    pub version: u32,
+    pub data: T,
+}
+
+/// A value read by `from_str`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loaded<T> {
+    pub value: T,
+    /// The version the data was saved at, if it was older and had to be migrated.
+    /// Worth logging, and saving again to skip the migration next time.
+    pub migrated_from: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionError {
+    /// Not json, or not in a `Versioned` envelope.
+    Corrupt(String),
+    /// A save of a different format, such as a scene loaded as settings.
+    WrongFormat {
+        expected: &'static str,
+        found: String,
+    },
+    /// Saved by a newer build, which this build can't read.
+    NewerVersion {
+        format: &'static str,
+        version: u32,
+        supported: u32,
+    },
+    /// An older save with no migration registered from one of the versions between.
+    MissingMigration {
+        format: &'static str,
+        from_version: u32,
+    },
+    /// Fields the current version doesn't have, under `UnknownFields::Deny`.
+    UnknownFields {
+        format: &'static str,
+        fields: Vec<String>,
+    },
+    /// The envelope was fine but its data doesn't match the type, after any migrations.
+    InvalidData {
+        format: &'static str,
+        migrated_from: Option<u32>,
+        error: String,
+    },
+}
+
+impl Display for VersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionError::Corrupt(error) => write!(f, "Corrupt save: {}", error),
+            VersionError::WrongFormat { expected, found } => {
+                write!(f, "Expected a '{}' save, found '{}'", expected, found)
+            }
+            VersionError::NewerVersion {
+                format,
+                version,
+                supported,
+            } => write!(
+                f,
+                "'{}' v{} was saved by a newer build - this build reads up to v{}",
+                format, version, supported
+            ),
+            VersionError::MissingMigration {
+                format,
+                from_version,
+            } => write!(
+                f,
+                "No migration registered for '{}' from v{}",
+                format, from_version
+            ),
+            VersionError::UnknownFields { format, fields } => {
+                write!(f, "Unknown fields in '{}': {}", format, fields.join(", "))
+            }
+            VersionError::InvalidData {
+                format,
+                migrated_from: Some(version),
+                error,
+            } => write!(
+                f,
+                "Invalid '{}' data after migrating from v{}: {}",
+                format, version, error
+            ),
+            VersionError::InvalidData {
+                format,
+                migrated_from: None,
+                error,
+            } => write!(f, "Invalid '{}' data: {}", format, error),
+        }
+    }
+}
+
+impl std::error::Error for VersionError {}
+
+//====================================================================
+
+/// Save `value` as pretty printed json in a `Versioned` envelope at `T::VERSION`.
+pub fn to_string<T: VersionedFormat>(value: &T) -> String {
+    serde_json::to_string_pretty(&Versioned {
+        format: T::FORMAT.to_string(),
+        version: T::VERSION,
+        data: value,
+    })
+    .expect("Versioned data should serialize to json")
+}
+
+/// Load json saved by `to_string`, migrating it from older versions as needed.
+pub fn from_str<T: VersionedFormat>(text: &str) -> Result<Loaded<T>, VersionError> {
+    let envelope = serde_json::from_str::<Versioned<Value>>(text)
+        .map_err(|e| VersionError::Corrupt(e.to_string()))?;
+
+    if envelope.format != T::FORMAT {
+        return Err(VersionError::WrongFormat {
+            expected: T::FORMAT,
+            found: envelope.format,
+        });
+    }
+
+    if envelope.version > T::VERSION {
+        return Err(VersionError::NewerVersion {
+            format: T::FORMAT,
+            version: envelope.version,
+            supported: T::VERSION,
+        });
+    }
+
+    let migrated_from = (envelope.version < T::VERSION).then_some(envelope.version);
+
+    let data = (envelope.version..T::VERSION).try_fold(envelope.data, |data, version| {
+        find_migration(T::FORMAT, version)
+            .map(|migrate| migrate(data))
+            .ok_or(VersionError::MissingMigration {
+                format: T::FORMAT,
+                from_version: version,
+            })
+    })?;
+
+    let invalid = |error: serde_json::Error| VersionError::InvalidData {
+        format: T::FORMAT,
+        migrated_from,
+        error: error.to_string(),
+    };
+
+    let value = T::deserialize(&data).map_err(invalid)?;
+
+    if T::UNKNOWN_FIELDS == UnknownFields::Deny {
+        // Serde drops unknown fields silently, so compare against what was read
+        let known = serde_json::to_value(&value).map_err(invalid)?;
+
+        let mut fields = Vec::new();
+        unknown_fields(&data, &known, "", &mut fields);
+
+        if !fields.is_empty() {
+            return Err(VersionError::UnknownFields {
+                format: T::FORMAT,
+                fields,
+            });
+        }
+    }
+
+    Ok(Loaded {
+        value,
+        migrated_from,
+    })
+}
+
+/// Paths of the object fields in `data` that aren't in `known`. Fields skipped when
+/// serializing, such as with `skip_serializing_if`, count as unknown.
+fn unknown_fields(data: &Value, known: &Value, path: &str, fields: &mut Vec<String>) {
+    match (data, known) {
+        (Value::Object(data), Value::Object(known)) => {
+            data.iter().for_each(|(key, value)| {
+                let field = match path.is_empty() {
+                    true => key.clone(),
+                    false => format!("{}.{}", path, key),
+                };
+
+                match known.get(key) {
+                    Some(known) => unknown_fields(value, known, &field, fields),
+                    None => fields.push(field),
+                }
+            });
+        }
+
+        (Value::Array(data), Value::Array(known)) => {
+            data.iter()
+                .zip(known)
+                .enumerate()
+                .for_each(|(index, (value, known))| {
+                    unknown_fields(value, known, &format!("{}[{}]", path, index), fields)
+                });
+        }
+
+        _ => {}
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // Migrations are registered process wide, so every test type has its own format
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct RoundTrip {
+        name: String,
+        volume: f32,
+    }
+
+    impl VersionedFormat for RoundTrip {
+        const FORMAT: &'static str = "test_round_trip";
+        const VERSION: u32 = 2;
+    }
+
+    fn save(format: &str, version: u32, data: Value) -> String {
+        serde_json::to_string(&Versioned {
+            format: format.to_string(),
+            version,
+            data,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = RoundTrip {
+            name: "Main".into(),
+            volume: 0.5,
+        };
+
+        let loaded = from_str::<RoundTrip>(&to_string(&value)).unwrap();
+        assert_eq!(loaded.value, value);
+        assert_eq!(loaded.migrated_from, None);
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Chain {
+        volume: f32,
+        fullscreen: bool,
+    }
+
+    impl VersionedFormat for Chain {
+        const FORMAT: &'static str = "test_migration_chain";
+        const VERSION: u32 = 3;
+    }
+
+    #[test]
+    fn migrates_through_every_version() {
+        // v1 stored volume as a percentage under another name
+        register_migration::<Chain>(1, |mut data| {
+            let percent = data["vol"].take().as_f64().unwrap();
+            json!({ "volume": percent / 100. })
+        });
+        // v3 added fullscreen
+        register_migration::<Chain>(2, |mut data| {
+            data["fullscreen"] = json!(false);
+            data
+        });
+
+        let expected = Chain {
+            volume: 0.25,
+            fullscreen: false,
+        };
+
+        let loaded = from_str::<Chain>(&save(Chain::FORMAT, 1, json!({ "vol": 25 }))).unwrap();
+        assert_eq!(loaded.value, expected);
+        assert_eq!(loaded.migrated_from, Some(1));
+
+        let loaded = from_str::<Chain>(&save(Chain::FORMAT, 2, json!({ "volume": 0.25 }))).unwrap();
+        assert_eq!(loaded.value, expected);
+        assert_eq!(loaded.migrated_from, Some(2));
+
+        let loaded = from_str::<Chain>(&to_string(&expected)).unwrap();
+        assert_eq!(loaded.migrated_from, None);
+    }
+
+    #[test]
+    fn rejects_other_formats_and_newer_versions() {
+        let text = save("test_other_format", 1, json!({}));
+        assert_eq!(
+            from_str::<RoundTrip>(&text),
+            Err(VersionError::WrongFormat {
+                expected: RoundTrip::FORMAT,
+                found: "test_other_format".into(),
+            })
+        );
+
+        let text = save(RoundTrip::FORMAT, 3, json!({ "name": "", "volume": 1. }));
+        assert_eq!(
+            from_str::<RoundTrip>(&text),
+            Err(VersionError::NewerVersion {
+                format: RoundTrip::FORMAT,
+                version: 3,
+                supported: 2,
+            })
+        );
+
+        assert!(matches!(
+            from_str::<RoundTrip>("not json"),
+            Err(VersionError::Corrupt(_))
+        ));
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Gap {
+        value: u32,
+    }
+
+    impl VersionedFormat for Gap {
+        const FORMAT: &'static str = "test_missing_migration";
+        const VERSION: u32 = 3;
+    }
+
+    #[test]
+    fn reports_missing_migrations() {
+        register_migration::<Gap>(2, |data| data);
+
+        let text = save(Gap::FORMAT, 1, json!({ "value": 1 }));
+        assert_eq!(
+            from_str::<Gap>(&text),
+            Err(VersionError::MissingMigration {
+                format: Gap::FORMAT,
+                from_version: 1,
+            })
+        );
+
+        let text = save(Gap::FORMAT, 2, json!({ "value": "one" }));
+        assert!(matches!(
+            from_str::<Gap>(&text),
+            Err(VersionError::InvalidData {
+                migrated_from: Some(2),
+                ..
+            })
+        ));
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Node {
+        name: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Camera {
+        fov: f32,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Scene {
+        nodes: Vec<Node>,
+        camera: Camera,
+    }
+
+    impl VersionedFormat for Scene {
+        const FORMAT: &'static str = "test_unknown_fields";
+        const VERSION: u32 = 1;
+        const UNKNOWN_FIELDS: UnknownFields = UnknownFields::Deny;
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct LenientScene {
+        nodes: Vec<Node>,
+        camera: Camera,
+    }
+
+    impl VersionedFormat for LenientScene {
+        const FORMAT: &'static str = "test_unknown_fields";
+        const VERSION: u32 = 1;
+    }
+
+    #[test]
+    fn denies_unknown_nested_fields() {
+        let data = json!({
+            "nodes": [{ "name": "a" }, { "name": "b", "tag": "player" }],
+            "camera": { "fov": 1.0, "zoom": 2.0 },
+            "lights": [],
+        });
+        let text = save(Scene::FORMAT, 1, data);
+
+        let Err(VersionError::UnknownFields { format, mut fields }) = from_str::<Scene>(&text)
+        else {
+            panic!("Expected unknown fields");
+        };
+        fields.sort();
+        assert_eq!(format, Scene::FORMAT);
+        assert_eq!(fields, vec!["camera.zoom", "lights", "nodes[1].tag"]);
+
+        // The same data loads when unknown fields are ignored
+        let loaded = from_str::<LenientScene>(&text).unwrap();
+        assert_eq!(loaded.value.nodes.len(), 2);
+
+        let scene = Scene {
+            nodes: vec![Node { name: "a".into() }],
+            camera: Camera { fov: 1. },
+        };
+        assert_eq!(from_str::<Scene>(&to_string(&scene)).unwrap().value, scene);
+    }
+}
@@ -0,0 +1,188 @@
+//====================================================================
+// Renders a spinning cube using the pipeline manager directly, without hecs.
+
+use roots_core::{
+    common::{Size, Time},
+    pipelines::{
+        manager::{PipelineManager, PipelineTargets, RenderContext},
+        model_renderer::{ModelData, ModelRenderer},
+    },
+    renderer::{
+        camera::{Camera, OrthographicCamera, PerspectiveCamera},
+        lighting::LightingManager,
+        model::{self, LoadedMesh},
+        shared::SharedRenderResources,
+        texture::{LoadedTexture, Texture},
+        Color, Device, Queue, RenderCore, RenderCoreConfig, RenderEncoder, Surface, SurfaceConfig,
+    },
+    runner::{
+        prelude::{ActiveEventLoop, StartCause},
+        window::Window,
+        winit::event_loop::ControlFlow,
+        Runner, RunnerState, WindowInputEvent,
+    },
+};
+
+//====================================================================
+
+fn main() {
+    Runner::<App>::run(Some(&[("manual_render", log::LevelFilter::Trace)]));
+}
+
+//====================================================================
+
+struct App {
+    window: Window,
+    device: Device,
+    queue: Queue,
+    surface: Surface<'static>,
+    config: SurfaceConfig,
+
+    shared: SharedRenderResources,
+    lighting: LightingManager,
+    depth_texture: Texture,
+
+    pipelines: PipelineManager,
+
+    camera: Camera,
+    camera_data: PerspectiveCamera,
+    screen_camera: Camera,
+
+    cube: Vec<(LoadedMesh, LoadedTexture)>,
+    time: Time,
+}
+
+impl RunnerState for App {
+    fn new(event_loop: &ActiveEventLoop) -> Self {
+        let window = Window::new(event_loop, None);
+
+        let core_config = RenderCoreConfig::default().with_vsync(true);
+        let (device, queue, surface, config) =
+            RenderCore::new_blocked(window.clone_arc(), window.size(), &core_config)
+                .unwrap()
+                .break_down();
+
+        let shared = SharedRenderResources::new(&device);
+        let lighting = LightingManager::new(&device, &shared);
+        let depth_texture = Texture::create_depth_texture(&device, window.size(), 1, None);
+
+        let mut pipelines = PipelineManager::new();
+        pipelines.add(0, ModelRenderer::new(&device, &config, &shared, &lighting));
+
+        let camera_data = PerspectiveCamera {
+            aspect: config.width as f32 / config.height as f32,
+            ..Default::default()
+        };
+        let camera = shared.create_camera(&device, &camera_data);
+        let screen_camera = shared.create_camera(
+            &device,
+            &OrthographicCamera::new_sized(config.width as f32, config.height as f32),
+        );
+
+        let cube = vec![(
+            LoadedMesh::load_from_data(&device, &model::CUBE_VERTICES, &model::CUBE_INDICES),
+            LoadedTexture::load_blank(&device, &queue, &shared),
+        )];
+
+        Self {
+            window,
+            device,
+            queue,
+            surface,
+            config,
+            shared,
+            lighting,
+            depth_texture,
+            pipelines,
+            camera,
+            camera_data,
+            screen_camera,
+            cube,
+            time: Time::new(),
+        }
+    }
+
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            self.window.inner().request_redraw();
+        }
+    }
+
+    fn input_event(&mut self, _event: WindowInputEvent) {}
+
+    fn resized(&mut self, new_size: Size<u32>) {
+        if new_size.width == 0 || new_size.height == 0 {
+            return;
+        }
+
+        self.config.width = new_size.width;
+        self.config.height = new_size.height;
+        self.surface.configure(&self.device, &self.config);
+
+        self.depth_texture = Texture::create_depth_texture(&self.device, new_size, 1, None);
+        self.camera_data.aspect = new_size.width as f32 / new_size.height as f32;
+        self.screen_camera.update_camera(
+            &self.queue,
+            &OrthographicCamera::new_sized(new_size.width as f32, new_size.height as f32),
+            &glam::Affine3A::IDENTITY,
+        );
+    }
+
+    fn tick(&mut self, event_loop: &ActiveEventLoop) {
+        event_loop.set_control_flow(ControlFlow::wait_duration(
+            std::time::Duration::from_secs_f32(1. / 75.),
+        ));
+
+        roots_core::common::tick_time(&mut self.time);
+
+        // Prep - each pipeline is fed explicitly
+        let elapsed = self.time.elapsed().elapsed().as_secs_f32();
+        let transform = glam::Mat4::from_rotation_translation(
+            glam::Quat::from_euler(glam::EulerRot::YXZ, elapsed, elapsed * 0.5, 0.),
+            glam::vec3(0., 0., 3.),
+        );
+
+        if let Some(renderer) = self.pipelines.get_mut::<ModelRenderer>() {
+            renderer.prep_model(
+                ModelData {
+                    meshes: &self.cube,
+                    color: [1., 0.6, 0.2, 1.],
+                    scale: glam::Vec3::ONE,
+                    wind: None,
+                },
+                transform,
+            );
+            renderer.finish_prep(&self.device, &self.queue);
+        }
+
+        self.camera
+            .update_camera(&self.queue, &self.camera_data, &glam::Affine3A::IDENTITY);
+
+        // Render
+        let mut encoder =
+            match RenderEncoder::new_configured(&self.device, &self.surface, &self.config) {
+                Ok(encoder) => encoder,
+                Err(_) => return,
+            };
+
+        self.pipelines.render(
+            &mut encoder,
+            &PipelineTargets {
+                color: None,
+                multisampled: None,
+                depth: &self.depth_texture.view,
+                clear_color: Some(Color::new(0.2, 0.2, 0.2, 1.)),
+                depth_convention: *self.shared.depth_convention(),
+            },
+            &RenderContext {
+                camera: self.camera.bind_group(),
+                lighting: self.lighting.bind_group(),
+                screen_camera: self.screen_camera.bind_group(),
+            },
+        );
+
+        encoder.finish(&self.queue);
+    }
+}
+
+//====================================================================
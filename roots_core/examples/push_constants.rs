@@ -0,0 +1,165 @@
+//====================================================================
+// Scrolling color bands drawn by one pipeline three times, each draw
+// given its own offset through a push constant instead of a bind group.
+// Needs an adapter supporting push constants (not available on wasm).
+
+use roots_core::{
+    common::Size,
+    renderer::{
+        tools::{self, RenderPipelineDescriptor},
+        Color, Device, Queue, RenderCore, RenderCoreConfig, RenderEncoder, RenderPassDesc, Surface,
+        SurfaceConfig,
+    },
+    runner::{
+        prelude::{ActiveEventLoop, StartCause},
+        window::Window,
+        winit::event_loop::ControlFlow,
+        Runner, RunnerState, WindowInputEvent,
+    },
+};
+
+//====================================================================
+
+const SHADER: &str = r#"
+var<push_constant> offset: u32;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2. - 1., 0., 1.);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let band = (u32(position.x) / 32u + offset) % 12u;
+    let hue = f32(band) / 12.;
+    let color = clamp(abs(fract(hue + vec3<f32>(0., 2. / 3., 1. / 3.)) * 6. - 3.) - 1., vec3<f32>(0.), vec3<f32>(1.));
+    return vec4<f32>(color, 1.);
+}
+"#;
+
+const ROWS: u32 = 3;
+
+fn main() {
+    Runner::<App>::run(Some(&[("push_constants", log::LevelFilter::Trace)]));
+}
+
+//====================================================================
+
+struct App {
+    window: Window,
+    device: Device,
+    queue: Queue,
+    surface: Surface<'static>,
+    config: SurfaceConfig,
+
+    /// `None` if the device doesn't support push constants.
+    pipeline: Option<wgpu::RenderPipeline>,
+    frame: u32,
+}
+
+impl RunnerState for App {
+    fn new(event_loop: &ActiveEventLoop) -> Self {
+        let window = Window::new(event_loop, None);
+
+        let core_config = RenderCoreConfig::default().with_vsync(true);
+        let (device, queue, surface, config) =
+            RenderCore::new_blocked(window.clone_arc(), window.size(), &core_config)
+                .unwrap()
+                .break_down();
+
+        let pipeline = match device.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+            true => Some(tools::create_pipeline(
+                &device,
+                &config,
+                "Push Constant Bands",
+                &[],
+                &[],
+                SHADER,
+                RenderPipelineDescriptor::default().with_push_constants(&[
+                    wgpu::PushConstantRange {
+                        stages: wgpu::ShaderStages::FRAGMENT,
+                        range: 0..4,
+                    },
+                ]),
+            )),
+            false => {
+                log::error!("The adapter doesn't support push constants, nothing will be drawn");
+                None
+            }
+        };
+
+        Self {
+            window,
+            device,
+            queue,
+            surface,
+            config,
+            pipeline,
+            frame: 0,
+        }
+    }
+
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            self.window.inner().request_redraw();
+        }
+    }
+
+    fn input_event(&mut self, _event: WindowInputEvent) {}
+
+    fn resized(&mut self, new_size: Size<u32>) {
+        if new_size.width == 0 || new_size.height == 0 {
+            return;
+        }
+
+        self.config.width = new_size.width;
+        self.config.height = new_size.height;
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    fn tick(&mut self, event_loop: &ActiveEventLoop) {
+        event_loop.set_control_flow(ControlFlow::wait_duration(
+            std::time::Duration::from_secs_f32(1. / 75.),
+        ));
+
+        self.frame = self.frame.wrapping_add(1);
+
+        let mut encoder =
+            match RenderEncoder::new_configured(&self.device, &self.surface, &self.config) {
+                Ok(encoder) => encoder,
+                Err(_) => return,
+            };
+
+        let mut pass = encoder.begin_render_pass(RenderPassDesc {
+            clear_color: Some(Color::new(0.1, 0.1, 0.1, 1.)),
+            ..Default::default()
+        });
+
+        if let Some(pipeline) = &self.pipeline {
+            pass.set_pipeline(pipeline);
+
+            // Each row scrolls at its own speed, with no buffers or bind groups
+            let row_height = self.config.height.div_ceil(ROWS);
+
+            (0..ROWS).for_each(|row| {
+                let y = row * row_height;
+                let height = row_height.min(self.config.height.saturating_sub(y));
+                if height == 0 {
+                    return;
+                }
+
+                let offset = self.frame.wrapping_mul(row + 1) / 8;
+
+                pass.set_scissor_rect(0, y, self.config.width, height);
+                pass.set_push_constants(wgpu::ShaderStages::FRAGMENT, 0, &offset.to_ne_bytes());
+                pass.draw(0..3, 0..1);
+            });
+        }
+
+        drop(pass);
+        encoder.finish(&self.queue);
+    }
+}
+
+//====================================================================
@@ -3,9 +3,12 @@
 pub use roots_common as common;
 #[cfg(feature = "hecs")]
 pub use roots_hecs as hecs;
+#[cfg(feature = "pipelines")]
 pub use roots_pipelines as pipelines;
 pub use roots_renderer as renderer;
+#[cfg(feature = "runner")]
 pub use roots_runner as runner;
+#[cfg(feature = "text")]
 pub use roots_text as text;
 
 //====================================================================
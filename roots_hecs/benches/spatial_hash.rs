@@ -0,0 +1,155 @@
+//====================================================================
+// Maintains a `SpatialHash` over 50k entities, most of them static, and
+// compares radius queries against a scan of the world.
+// Run with `cargo bench -p roots_hecs --bench spatial_hash`.
+
+use std::time::Instant;
+
+use hecs::World;
+use roots_common::spatial::{GlobalTransform, Transform};
+use roots_hecs::{
+    spatial_hash::{SpatialHash, SpatialIndexed},
+    tracked::{self, Tracked},
+};
+
+//====================================================================
+
+const ENTITY_COUNT: usize = 50_000;
+const MOVING_COUNT: usize = 1_000;
+const WORLD_SIZE: f32 = 1000.;
+const FRAMES: u32 = 100;
+const QUERIES: u32 = 10_000;
+const QUERY_RADIUS: f32 = 10.;
+
+/// Small deterministic random stream, so runs are comparable.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn point(&mut self) -> glam::Vec3 {
+        glam::vec3(self.next(), self.next() * 0.05, self.next()) * WORLD_SIZE
+    }
+}
+
+fn main() {
+    let mut rng = Lcg(0x5eed);
+    let mut world = World::new();
+
+    (0..ENTITY_COUNT - MOVING_COUNT).for_each(|_| {
+        let transform = Transform::from_translation(rng.point());
+
+        world.spawn((
+            SpatialIndexed::new(0.5 + rng.next()),
+            GlobalTransform(transform.to_affine()),
+            Tracked::new(transform),
+        ));
+    });
+
+    (0..MOVING_COUNT).for_each(|_| {
+        let transform = Transform::from_translation(rng.point());
+
+        world.spawn((
+            SpatialIndexed::new(0.5 + rng.next()),
+            GlobalTransform(transform.to_affine()),
+            transform,
+        ));
+    });
+
+    let mut hash = SpatialHash::new();
+
+    let start = Instant::now();
+    hash.update(&mut world);
+    let build = start.elapsed().as_secs_f64();
+
+    tracked::clear_changed::<Transform>(&mut world);
+
+    println!(
+        "{} entities - initial build: {:.3}ms, cell size {}",
+        hash.len(),
+        build * 1000.,
+        hash.cell_size()
+    );
+
+    // The moving entities drift a little each frame, as they would under gameplay code
+    let maintenance = (0..FRAMES)
+        .map(|frame| {
+            let offset = glam::vec3(0.1, 0., (frame % 7) as f32 * 0.1);
+
+            world
+                .query_mut::<(&mut Transform, &mut GlobalTransform)>()
+                .into_iter()
+                .for_each(|(_, (transform, global))| {
+                    transform.translation += offset;
+                    global.0 = transform.to_affine();
+                });
+
+            let start = Instant::now();
+            hash.update(&mut world);
+            start.elapsed().as_secs_f64()
+        })
+        .sum::<f64>()
+        / FRAMES as f64;
+
+    println!(
+        "{} moving entities - per frame maintenance: {:.3}ms",
+        MOVING_COUNT,
+        maintenance * 1000.
+    );
+
+    let points = (0..QUERIES).map(|_| rng.point()).collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let found = points
+        .iter()
+        .map(|point| hash.query_radius(*point, QUERY_RADIUS).count())
+        .sum::<usize>();
+    let query = start.elapsed().as_secs_f64() / QUERIES as f64;
+
+    let scanned_points = &points[..QUERIES as usize / 100];
+    let start = Instant::now();
+    let scanned = scanned_points
+        .iter()
+        .map(|point| {
+            world
+                .query_mut::<(&SpatialIndexed, &GlobalTransform)>()
+                .into_iter()
+                .filter(|(_, (indexed, global))| {
+                    global.translation().distance(*point) <= QUERY_RADIUS + indexed.radius
+                })
+                .count()
+        })
+        .sum::<usize>();
+    let scan = start.elapsed().as_secs_f64() / scanned_points.len() as f64;
+
+    println!(
+        "query_radius({}): {:.2}us per query, {:.1} results on average - world scan: {:.2}us ({:.1} results)",
+        QUERY_RADIUS,
+        query * 1_000_000.,
+        found as f32 / QUERIES as f32,
+        scan * 1_000_000.,
+        scanned as f32 / scanned_points.len() as f32,
+    );
+
+    let start = Instant::now();
+    let nearest = points
+        .iter()
+        .filter(|point| hash.nearest(**point, |_| true).is_some())
+        .count();
+    let nearest_time = start.elapsed().as_secs_f64() / QUERIES as f64;
+
+    println!(
+        "nearest: {:.2}us per query, {} of {} found",
+        nearest_time * 1_000_000.,
+        nearest,
+        QUERIES
+    );
+}
+
+//====================================================================
@@ -0,0 +1,351 @@
+//====================================================================
+
+use std::hash::Hash;
+
+use roots_common::input::Input;
+use roots_runner::prelude::{KeyCode, MouseButton};
+
+use crate::State;
+
+//====================================================================
+
+/// Modifier keys held alongside a binding's key or button. Either the left or right
+/// key counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub super_key: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Self = Self {
+        ctrl: false,
+        shift: false,
+        alt: false,
+        super_key: false,
+    };
+
+    /// The modifiers currently held.
+    pub fn held(keys: &Input<KeyCode>) -> Self {
+        let either = |left, right| keys.pressed(left) || keys.pressed(right);
+
+        Self {
+            ctrl: either(KeyCode::ControlLeft, KeyCode::ControlRight),
+            shift: either(KeyCode::ShiftLeft, KeyCode::ShiftRight),
+            alt: either(KeyCode::AltLeft, KeyCode::AltRight),
+            super_key: either(KeyCode::SuperLeft, KeyCode::SuperRight),
+        }
+    }
+
+    /// Whether every modifier in `other` is also in `self`.
+    #[inline]
+    pub fn contains(&self, other: Modifiers) -> bool {
+        (self.ctrl || !other.ctrl)
+            && (self.shift || !other.shift)
+            && (self.alt || !other.alt)
+            && (self.super_key || !other.super_key)
+    }
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trigger {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Trigger {
+    #[inline]
+    fn pressed(&self, state: &State) -> bool {
+        match *self {
+            Trigger::Key(key) => state.keys.pressed(key),
+            Trigger::Mouse(button) => state.mouse_buttons.pressed(button),
+        }
+    }
+
+    #[inline]
+    fn just_pressed(&self, state: &State) -> bool {
+        match *self {
+            Trigger::Key(key) => state.keys.just_pressed(key),
+            Trigger::Mouse(button) => state.mouse_buttons.just_pressed(button),
+        }
+    }
+}
+
+/// A key or mouse button, optionally with modifiers that must be held with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Binding {
+    pub trigger: Trigger,
+    pub modifiers: Modifiers,
+}
+
+impl Binding {
+    #[inline]
+    pub fn key(key: KeyCode) -> Self {
+        Self {
+            trigger: Trigger::Key(key),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    #[inline]
+    pub fn mouse(button: MouseButton) -> Self {
+        Self {
+            trigger: Trigger::Mouse(button),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    #[inline]
+    pub fn with_ctrl(mut self) -> Self {
+        self.modifiers.ctrl = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_shift(mut self) -> Self {
+        self.modifiers.shift = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_alt(mut self) -> Self {
+        self.modifiers.alt = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_super(mut self) -> Self {
+        self.modifiers.super_key = true;
+        self
+    }
+}
+
+impl From<KeyCode> for Binding {
+    #[inline]
+    fn from(value: KeyCode) -> Self {
+        Self::key(value)
+    }
+}
+
+impl From<MouseButton> for Binding {
+    #[inline]
+    fn from(value: MouseButton) -> Self {
+        Self::mouse(value)
+    }
+}
+
+//====================================================================
+
+/// Maps actions to key and mouse bindings, including chords such as Ctrl+S.
+///
+/// A chord is just pressed when its key or button goes down while its modifiers are
+/// already held. Pressing a modifier after the key, or releasing one mid hold, doesn't
+/// trigger it again. When several bindings share a key, only the most specific ones
+/// with their modifiers held count, so Ctrl+S doesn't also trigger a plain S binding.
+///
+/// With the `serde` feature, bindings are saved with `roots_common::versioned`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActionMap<A> {
+    bindings: Vec<(A, Binding)>,
+}
+
+impl<A> Default for ActionMap<A> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<A> roots_common::versioned::VersionedFormat for ActionMap<A>
+where
+    A: serde::Serialize + serde::de::DeserializeOwned,
+{
+    const FORMAT: &'static str = "action_map";
+    const VERSION: u32 = 1;
+}
+
+impl<A> ActionMap<A>
+where
+    A: Copy + Eq + Hash,
+{
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_binding(mut self, action: A, binding: impl Into<Binding>) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    /// Add a binding for `action`. Actions can have any number of bindings.
+    pub fn bind(&mut self, action: A, binding: impl Into<Binding>) {
+        let binding = binding.into();
+
+        if !self.bindings.contains(&(action, binding)) {
+            self.bindings.push((action, binding));
+        }
+    }
+
+    /// Remove every binding of `action`.
+    #[inline]
+    pub fn unbind(&mut self, action: A) {
+        self.bindings.retain(|(existing, _)| *existing != action);
+    }
+
+    #[inline]
+    pub fn bindings(&self, action: A) -> impl Iterator<Item = &Binding> {
+        self.bindings
+            .iter()
+            .filter(move |(existing, _)| *existing == action)
+            .map(|(_, binding)| binding)
+    }
+
+    /// Whether any binding of `action` is held with its modifiers.
+    pub fn pressed(&self, state: &State, action: A) -> bool {
+        let held = Modifiers::held(&state.keys);
+
+        self.bindings(action)
+            .any(|binding| binding.trigger.pressed(state) && self.is_most_specific(binding, held))
+    }
+
+    /// Whether any binding of `action` was pressed this frame with its modifiers held.
+    pub fn just_pressed(&self, state: &State, action: A) -> bool {
+        let held = Modifiers::held(&state.keys);
+
+        self.bindings(action).any(|binding| {
+            binding.trigger.just_pressed(state) && self.is_most_specific(binding, held)
+        })
+    }
+
+    /// Whether `binding`'s modifiers are held and no binding on the same trigger with
+    /// more modifiers is also held.
+    fn is_most_specific(&self, binding: &Binding, held: Modifiers) -> bool {
+        if !held.contains(binding.modifiers) {
+            return false;
+        }
+
+        !self.bindings.iter().any(|(_, other)| {
+            other.trigger == binding.trigger
+                && other.modifiers != binding.modifiers
+                && other.modifiers.contains(binding.modifiers)
+                && held.contains(other.modifiers)
+        })
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use roots_common::input::{process_inputs, reset_input};
+
+    use super::*;
+    use crate::test_utils;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Action {
+        Step,
+        Save,
+        SaveAs,
+    }
+
+    const ACTIONS: [Action; 3] = [Action::Step, Action::Save, Action::SaveAs];
+
+    fn map() -> ActionMap<Action> {
+        ActionMap::new()
+            .with_binding(Action::Step, KeyCode::KeyS)
+            .with_binding(Action::Save, Binding::key(KeyCode::KeyS).with_ctrl())
+            .with_binding(
+                Action::SaveAs,
+                Binding::key(KeyCode::KeyS).with_ctrl().with_shift(),
+            )
+    }
+
+    fn just_pressed(map: &ActionMap<Action>, state: &State) -> Vec<Action> {
+        ACTIONS
+            .into_iter()
+            .filter(|action| map.just_pressed(state, *action))
+            .collect()
+    }
+
+    fn pressed(map: &ActionMap<Action>, state: &State) -> Vec<Action> {
+        ACTIONS
+            .into_iter()
+            .filter(|action| map.pressed(state, *action))
+            .collect()
+    }
+
+    fn next_frame(state: &mut State) {
+        reset_input(&mut state.keys);
+    }
+
+    #[test]
+    fn modifier_pressed_after_key() {
+        let Some(mut state) = test_utils::state(16, 16) else {
+            return;
+        };
+        let map = map();
+
+        process_inputs(&mut state.keys, KeyCode::KeyS, true);
+        assert_eq!(just_pressed(&map, &state), vec![Action::Step]);
+
+        // The chord is held but wasn't pressed in order, so it doesn't fire
+        next_frame(&mut state);
+        process_inputs(&mut state.keys, KeyCode::ControlLeft, true);
+        assert!(just_pressed(&map, &state).is_empty());
+        assert_eq!(pressed(&map, &state), vec![Action::Save]);
+    }
+
+    #[test]
+    fn modifier_released_mid_hold() {
+        let Some(mut state) = test_utils::state(16, 16) else {
+            return;
+        };
+        let map = map();
+
+        process_inputs(&mut state.keys, KeyCode::ControlRight, true);
+        next_frame(&mut state);
+        process_inputs(&mut state.keys, KeyCode::KeyS, true);
+        assert_eq!(just_pressed(&map, &state), vec![Action::Save]);
+
+        // Releasing Ctrl leaves plain S held without pressing it again
+        next_frame(&mut state);
+        process_inputs(&mut state.keys, KeyCode::ControlRight, false);
+        assert!(just_pressed(&map, &state).is_empty());
+        assert_eq!(pressed(&map, &state), vec![Action::Step]);
+    }
+
+    #[test]
+    fn chords_sharing_a_key() {
+        let Some(mut state) = test_utils::state(16, 16) else {
+            return;
+        };
+        let map = map();
+
+        process_inputs(&mut state.keys, KeyCode::ControlLeft, true);
+        process_inputs(&mut state.keys, KeyCode::ShiftLeft, true);
+        next_frame(&mut state);
+        process_inputs(&mut state.keys, KeyCode::KeyS, true);
+        assert_eq!(just_pressed(&map, &state), vec![Action::SaveAs]);
+
+        next_frame(&mut state);
+        process_inputs(&mut state.keys, KeyCode::KeyS, false);
+        process_inputs(&mut state.keys, KeyCode::ShiftLeft, false);
+        next_frame(&mut state);
+        process_inputs(&mut state.keys, KeyCode::KeyS, true);
+        assert_eq!(just_pressed(&map, &state), vec![Action::Save]);
+    }
+}
@@ -0,0 +1,283 @@
+//====================================================================
+
+use hecs::World;
+use roots_common::{
+    coords::CoordinateConvention,
+    spatial::{GlobalTransform, WorldPosition},
+};
+use roots_pipelines::blob_shadow_renderer::BlobShadowRenderer;
+
+use crate::{
+    profiler,
+    renderer::{culling, large_world, pipelines::Pipeline},
+    RendererState, State,
+};
+
+//====================================================================
+
+/// Seconds for a shadow to fade in or out when the ground appears or is lost.
+const GROUND_FADE_DURATION: f32 = 0.15;
+
+/// Scale of a shadow's radius at `max_drop_height` above the ground.
+const MIN_HEIGHT_SCALE: f32 = 0.5;
+
+/// A soft round shadow drawn on the ground below the entity's `GlobalTransform` by the
+/// `BlobShadowRenderer`. The ground is found by `process_blob_shadows`.
+///
+/// Shadows shrink, soften and fade as the entity rises above the ground, and fade out
+/// when no ground is found within `max_drop_height`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlobShadow {
+    /// Radius of the shadow while on the ground.
+    pub radius: f32,
+    /// Opacity of the shadow while on the ground.
+    pub opacity: f32,
+    /// Highest above the ground the shadow is drawn. Shadows fade out as the entity
+    /// approaches it.
+    pub max_drop_height: f32,
+    /// Fraction of the radius faded out toward the edge while on the ground.
+    pub softness: f32,
+
+    /// Distance down to the ground and its normal, kept while fading out.
+    ground: Option<(f32, glam::Vec3)>,
+    /// Faded toward 1 while the ground is found, and 0 while it isn't.
+    visibility: f32,
+}
+
+impl BlobShadow {
+    #[inline]
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            opacity: 0.6,
+            max_drop_height: 5.,
+            softness: 0.5,
+            ground: None,
+            visibility: 0.,
+        }
+    }
+
+    #[inline]
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    #[inline]
+    pub fn with_max_drop_height(mut self, max_drop_height: f32) -> Self {
+        self.max_drop_height = max_drop_height;
+        self
+    }
+
+    #[inline]
+    pub fn with_softness(mut self, softness: f32) -> Self {
+        self.softness = softness;
+        self
+    }
+
+    /// Distance from the entity down to the ground found last frame.
+    #[inline]
+    pub fn drop_height(&self) -> Option<f32> {
+        self.ground.map(|(drop, _)| drop)
+    }
+
+    /// Radius, opacity and softness at the current height above the ground. `None`
+    /// if fully faded out.
+    fn appearance(&self) -> Option<(f32, f32, f32)> {
+        let (drop, _) = self.ground?;
+
+        let height = match self.max_drop_height > 0. {
+            true => (drop / self.max_drop_height).clamp(0., 1.),
+            false => 0.,
+        };
+
+        let opacity = self.opacity * self.visibility * (1. - height);
+        if opacity <= 0. {
+            return None;
+        }
+
+        let radius = self.radius * (1. + (MIN_HEIGHT_SCALE - 1.) * height);
+        let softness = self.softness + (1. - self.softness) * height;
+
+        Some((radius, opacity, softness))
+    }
+}
+
+//====================================================================
+
+/// Ground below a point, from a `ShadowGround::Custom` lookup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroundHit {
+    /// Height of the ground along the up axis of the active `CoordinateConvention`.
+    pub height: f32,
+    /// Surface normal, to lay shadows along slopes. Flat if `None`.
+    pub normal: Option<glam::Vec3>,
+}
+
+impl GroundHit {
+    #[inline]
+    pub fn flat(height: f32) -> Self {
+        Self {
+            height,
+            normal: None,
+        }
+    }
+}
+
+/// Where `process_blob_shadows` finds the ground below each shadow.
+pub enum ShadowGround {
+    /// Project straight down onto a flat plane at this height along the up axis.
+    /// The cheapest option, with no lookups.
+    Plane(f32),
+    /// Cast down against the `RenderBounds` of every other entity, as used for
+    /// culling, falling back to `floor` if nothing is hit. Shadows lie flat.
+    Raycast { floor: Option<f32> },
+    /// A lookup from a world position, such as sampling a terrain heightmap. Returns
+    /// `None` where there is no ground.
+    Custom(Box<dyn Fn(glam::Vec3) -> Option<GroundHit> + Send + Sync>),
+}
+
+impl ShadowGround {
+    #[inline]
+    pub fn custom(
+        lookup: impl Fn(glam::Vec3) -> Option<GroundHit> + Send + Sync + 'static,
+    ) -> Self {
+        Self::Custom(Box::new(lookup))
+    }
+}
+
+//====================================================================
+
+/// Find the ground below every `BlobShadow` and fade shadows in or out as it is found
+/// or lost. Call once per tick after transforms are propagated.
+pub fn process_blob_shadows(state: &mut State, ground: &ShadowGround) {
+    let delta = state.time.delta_seconds();
+    let up = CoordinateConvention::active().up();
+
+    let shadows =
+        profiler::profiled_query::<(&BlobShadow, &GlobalTransform, Option<&WorldPosition>)>(
+            &mut state.world,
+            "blob_shadows",
+        )
+        .map(|(entity, (shadow, global, position))| {
+            (
+                entity,
+                large_world::world_translation(global, position),
+                state
+                    .renderer
+                    .origin
+                    .relative(global, position)
+                    .translation
+                    .into(),
+                shadow.max_drop_height,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let hits = shadows
+        .into_iter()
+        .map(|(entity, world_translation, relative, max_drop_height)| {
+            let hit = match ground {
+                ShadowGround::Plane(height) => {
+                    plane_drop(world_translation.as_vec3(), up, *height).map(|drop| (drop, up))
+                }
+
+                ShadowGround::Raycast { floor } => culling::raycast(
+                    &mut state.world,
+                    &state.renderer.origin,
+                    relative,
+                    -up,
+                    |hit| hit != entity,
+                )
+                .map(|(_, distance)| distance)
+                .or_else(|| {
+                    floor.and_then(|height| plane_drop(world_translation.as_vec3(), up, height))
+                })
+                .map(|drop| (drop, up)),
+
+                ShadowGround::Custom(lookup) => {
+                    let position = world_translation.as_vec3();
+                    lookup(position).and_then(|hit| {
+                        plane_drop(position, up, hit.height)
+                            .map(|drop| (drop, hit.normal.unwrap_or(up)))
+                    })
+                }
+            };
+
+            let hit = hit.filter(|(drop, _)| *drop <= max_drop_height);
+            (entity, hit)
+        })
+        .collect::<Vec<_>>();
+
+    let mut fading = false;
+
+    hits.into_iter().for_each(|(entity, hit)| {
+        let Ok(mut shadow) = state.world.get::<&mut BlobShadow>(entity) else {
+            return;
+        };
+
+        let target = match hit {
+            Some(hit) => {
+                shadow.ground = Some(hit);
+                1.
+            }
+            None => 0.,
+        };
+
+        let step = delta / GROUND_FADE_DURATION;
+        shadow.visibility = match target > shadow.visibility {
+            true => (shadow.visibility + step).min(target),
+            false => (shadow.visibility - step).max(target),
+        };
+
+        if shadow.visibility == 0. {
+            shadow.ground = None;
+        }
+
+        fading |= shadow.visibility != target;
+    });
+
+    state.set_animation_active("blob_shadows", fading);
+}
+
+/// Distance from `position` down to a plane at `height` along `up`. `None` if the
+/// plane is above.
+#[inline]
+fn plane_drop(position: glam::Vec3, up: glam::Vec3, height: f32) -> Option<f32> {
+    let drop = position.dot(up) - height;
+    (drop >= 0.).then_some(drop)
+}
+
+//====================================================================
+
+impl Pipeline for BlobShadowRenderer {
+    #[inline]
+    fn new(state: &RendererState) -> Self {
+        Self::new(&state.device, &state.config, &state.shared)
+    }
+
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        let up = CoordinateConvention::active().up();
+
+        profiler::profiled_query::<(&BlobShadow, &GlobalTransform, Option<&WorldPosition>)>(
+            world,
+            "blob_shadow_renderer",
+        )
+        .for_each(|(_, (shadow, global, position))| {
+            let (Some((drop, normal)), Some((radius, opacity, softness))) =
+                (shadow.ground, shadow.appearance())
+            else {
+                return;
+            };
+
+            let center =
+                glam::Vec3::from(state.origin.relative(global, position).translation) - up * drop;
+
+            self.prep_shadow(center, normal, radius, opacity, softness);
+        });
+
+        self.finish_prep(&state.device, &state.queue);
+    }
+}
+
+//====================================================================
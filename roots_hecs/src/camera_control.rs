@@ -0,0 +1,353 @@
+//====================================================================
+
+use std::any::TypeId;
+
+use hecs::{Component, World};
+use roots_common::{
+    coords::CoordinateConvention,
+    input::{self, Input, MouseInput},
+    spatial::{GlobalTransform, Transform},
+    Time,
+};
+use roots_runner::prelude::{KeyCode, MouseButton};
+
+use crate::State;
+
+//====================================================================
+
+/// Yaw and pitch turned by mouse motion, shared by camera controllers so the tick
+/// and late latching rotate the camera the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouseLook {
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Radians turned per unit of mouse motion.
+    pub sensitivity: f32,
+    /// Pitch is clamped to this many radians above or below the horizon.
+    pub pitch_limit: f32,
+    pub invert_y: bool,
+}
+
+impl Default for MouseLook {
+    fn default() -> Self {
+        Self {
+            yaw: 0.,
+            pitch: 0.,
+            sensitivity: 0.003,
+            pitch_limit: std::f32::consts::FRAC_PI_2 - 0.01,
+            invert_y: false,
+        }
+    }
+}
+
+impl MouseLook {
+    #[inline]
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    /// Turn by `motion`, in the units of `MouseInput::motion_delta`.
+    pub fn turn(&mut self, motion: glam::Vec2) {
+        let motion_y = match self.invert_y {
+            true => -motion.y,
+            false => motion.y,
+        };
+
+        self.yaw += motion.x * self.sensitivity;
+        self.pitch =
+            (self.pitch + motion_y * self.sensitivity).clamp(-self.pitch_limit, self.pitch_limit);
+    }
+
+    #[inline]
+    pub fn rotation(&self) -> glam::Quat {
+        glam::Quat::from_euler(glam::EulerRot::YXZ, self.yaw, self.pitch, 0.)
+    }
+}
+
+/// A camera controller component turned by mouse motion. Controllers call `look`
+/// from their own update, and `LateLatch` calls it again just before rendering.
+pub trait LookController: Component {
+    fn mouse_look(&mut self) -> &mut MouseLook;
+
+    /// Whether mouse motion turns the camera, such as while a button is held.
+    fn is_looking(&self, mouse_buttons: &Input<MouseButton>) -> bool;
+
+    /// Turn by `motion` if looking, and rotate `transform` to match.
+    fn look(
+        &mut self,
+        motion: glam::Vec2,
+        mouse_buttons: &Input<MouseButton>,
+        transform: &mut Transform,
+    ) {
+        if self.is_looking(mouse_buttons) {
+            self.mouse_look().turn(motion);
+        }
+
+        transform.rotation = self.mouse_look().rotation();
+    }
+}
+
+//====================================================================
+
+/// Keys moving a `FlyController`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlyKeys {
+    pub forward: KeyCode,
+    pub back: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+}
+
+impl Default for FlyKeys {
+    fn default() -> Self {
+        Self {
+            forward: KeyCode::KeyW,
+            back: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+            up: KeyCode::Space,
+            down: KeyCode::ShiftLeft,
+        }
+    }
+}
+
+/// Free look camera controller. `FlyKeys` move along the camera's forward and right
+/// axes and the world's up axis, and mouse motion turns it while `look_button` is held.
+/// Axes follow the active `CoordinateConvention`.
+///
+/// Usable on its own through `update`, or as a component updated by
+/// `process_fly_controller`, which moves the entity's `Transform`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlyController {
+    pub position: glam::Vec3,
+    pub look: MouseLook,
+    /// Units moved per second.
+    pub speed: f32,
+    pub keys: FlyKeys,
+    /// Button held to look around. `None` always looks.
+    pub look_button: Option<MouseButton>,
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self {
+            position: glam::Vec3::ZERO,
+            look: MouseLook::default(),
+            speed: 5.,
+            keys: FlyKeys::default(),
+            look_button: Some(MouseButton::Right),
+        }
+    }
+}
+
+impl FlyController {
+    #[inline]
+    pub fn new(position: glam::Vec3) -> Self {
+        Self {
+            position,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    #[inline]
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.look.sensitivity = sensitivity;
+        self
+    }
+
+    #[inline]
+    pub fn with_look_button(mut self, look_button: Option<MouseButton>) -> Self {
+        self.look_button = look_button;
+        self
+    }
+
+    #[inline]
+    pub fn position(&self) -> glam::Vec3 {
+        self.position
+    }
+
+    #[inline]
+    pub fn forward(&self) -> glam::Vec3 {
+        (self.look.rotation() * CoordinateConvention::active().forward()).normalize_or_zero()
+    }
+
+    #[inline]
+    pub fn right(&self) -> glam::Vec3 {
+        (self.look.rotation() * CoordinateConvention::active().right()).normalize_or_zero()
+    }
+
+    /// The direction held keys move in, relative to the camera - x right, y up and
+    /// z forward, each -1, 0 or 1.
+    pub fn movement_input(&self, keys: &Input<KeyCode>) -> glam::Vec3 {
+        let axis = |positive: KeyCode, negative: KeyCode| {
+            keys.pressed(positive) as i8 as f32 - keys.pressed(negative) as i8 as f32
+        };
+
+        glam::vec3(
+            axis(self.keys.right, self.keys.left),
+            axis(self.keys.up, self.keys.down),
+            axis(self.keys.forward, self.keys.back),
+        )
+    }
+
+    /// Turn and move from this frame's input, returning the camera's new transform.
+    /// Movement is scaled by the frame's delta, so speed doesn't depend on frame rate.
+    pub fn update(
+        &mut self,
+        keys: &Input<KeyCode>,
+        mouse_buttons: &Input<MouseButton>,
+        mouse: &MouseInput,
+        time: &Time,
+    ) -> glam::Affine3A {
+        if self.is_looking(mouse_buttons) {
+            self.look.turn(mouse.motion_delta());
+        }
+
+        let input = self.movement_input(keys);
+        let movement = self.right() * input.x
+            + CoordinateConvention::active().up() * input.y
+            + self.forward() * input.z;
+
+        self.position += movement.normalize_or_zero() * self.speed * time.delta_seconds();
+
+        self.transform()
+    }
+
+    #[inline]
+    pub fn transform(&self) -> glam::Affine3A {
+        glam::Affine3A::from_rotation_translation(self.look.rotation(), self.position)
+    }
+}
+
+impl LookController for FlyController {
+    #[inline]
+    fn mouse_look(&mut self) -> &mut MouseLook {
+        &mut self.look
+    }
+
+    #[inline]
+    fn is_looking(&self, mouse_buttons: &Input<MouseButton>) -> bool {
+        self.look_button
+            .map(|button| mouse_buttons.pressed(button))
+            .unwrap_or(true)
+    }
+}
+
+/// Update entities with a `FlyController` and `Transform`. The controller starts from
+/// the transform's translation, so entities moved elsewhere aren't snapped back.
+pub fn process_fly_controller(state: &mut State) {
+    let mut moving = false;
+
+    state
+        .world
+        .query_mut::<(&mut FlyController, &mut Transform)>()
+        .into_iter()
+        .for_each(|(_, (controller, transform))| {
+            controller.position = transform.translation;
+
+            controller.update(
+                &state.keys,
+                &state.mouse_buttons,
+                &state.mouse_input,
+                &state.time,
+            );
+
+            transform.rotation = controller.look.rotation();
+            transform.translation = controller.position;
+
+            moving |= controller.movement_input(&state.keys) != glam::Vec3::ZERO;
+        });
+
+    // Held keys don't send events, so keep drawing while moving
+    state.set_animation_active("fly_controller", moving);
+}
+
+//====================================================================
+
+type LatchFn = fn(&mut World, &Input<MouseButton>, glam::Vec2);
+
+/// Opt in reduced mouse look latency. Mouse motion normally reaches the camera at
+/// the next tick, so the rendered view is a frame behind it. With late latching the
+/// runner defers rendering until events queued during the tick are handled, then
+/// turns every registered `LookController` by the motion that arrived since the
+/// tick started, just before prepping the cameras. Only camera rotation is latched -
+/// gameplay still reads motion in the tick, and latched motion isn't seen twice.
+///
+/// Cameras parented with a `LocalTransform` aren't latched.
+#[derive(Default)]
+pub struct LateLatch {
+    enabled: bool,
+    controllers: Vec<(TypeId, LatchFn)>,
+}
+
+impl LateLatch {
+    /// Latch controllers of type `C`. Doesn't enable late latching.
+    pub fn register<C: LookController>(&mut self) {
+        let id = TypeId::of::<C>();
+
+        if !self
+            .controllers
+            .iter()
+            .any(|(registered, _)| *registered == id)
+        {
+            self.controllers.push((id, latch::<C>));
+        }
+    }
+
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[inline]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Enabled with at least one controller registered.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.enabled && !self.controllers.is_empty()
+    }
+}
+
+fn latch<C: LookController>(
+    world: &mut World,
+    mouse_buttons: &Input<MouseButton>,
+    motion: glam::Vec2,
+) {
+    world
+        .query_mut::<(&mut C, &mut Transform, &mut GlobalTransform)>()
+        .into_iter()
+        .for_each(|(_, (controller, transform, global))| {
+            controller.look(motion, mouse_buttons, transform);
+            global.0 = transform.to_affine();
+        });
+}
+
+impl State {
+    /// Turn the registered look controllers by the motion that arrived since the tick
+    /// started. Called by the runner before rendering while `LateLatch` is enabled.
+    pub fn apply_late_latch(&mut self) {
+        let motion = input::take_late_motion(&mut self.mouse_input);
+        if motion == glam::Vec2::ZERO {
+            return;
+        }
+
+        self.late_latch
+            .controllers
+            .iter()
+            .for_each(|(_, latch)| latch(&mut self.world, &self.mouse_buttons, motion));
+    }
+}
+
+//====================================================================
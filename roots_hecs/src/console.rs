@@ -0,0 +1,569 @@
+//====================================================================
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    rc::Rc,
+    str::FromStr,
+};
+
+use roots_common::theme::Theme;
+use roots_renderer::{
+    lighting::GlobalLightData,
+    memory::{self, GpuMemoryTracker},
+    Color,
+};
+use roots_runner::{prelude::KeyCode, WindowInputEvent};
+
+use crate::{profiler, State};
+
+//====================================================================
+
+pub type CommandResult = Result<String, String>;
+type Command = Rc<dyn Fn(&mut State, &CommandArgs) -> CommandResult>;
+
+const MAX_OUTPUT: usize = 200;
+const MAX_HISTORY: usize = 50;
+
+//====================================================================
+
+/// Arguments passed to a console command. Split on whitespace, with double quotes
+/// grouping words together.
+#[derive(Debug, Default, Clone)]
+pub struct CommandArgs {
+    args: Vec<String>,
+}
+
+impl CommandArgs {
+    /// Split a line into the command name and its arguments.
+    pub fn parse(line: &str) -> Option<(String, Self)> {
+        let mut args = Vec::new();
+        let mut current = String::new();
+        let mut quoted = false;
+
+        line.chars().for_each(|c| match (c, quoted) {
+            ('"', _) => quoted = !quoted,
+            (c, false) if c.is_whitespace() => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            (c, _) => current.push(c),
+        });
+
+        if !current.is_empty() {
+            args.push(current);
+        }
+
+        if args.is_empty() {
+            return None;
+        }
+
+        let name = args.remove(0);
+        Some((name, Self { args }))
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().map(|arg| arg.as_str())
+    }
+
+    pub fn get(&self, index: usize) -> Result<&str, String> {
+        self.args
+            .get(index)
+            .map(|arg| arg.as_str())
+            .ok_or_else(|| format!("Missing argument {}", index + 1))
+    }
+
+    pub fn parse_arg<T: FromStr>(&self, index: usize) -> Result<T, String> {
+        let arg = self.get(index)?;
+        arg.parse()
+            .map_err(|_| format!("Invalid argument {} '{}'", index + 1, arg))
+    }
+
+    /// Like `parse_arg` but returns `default` if the argument wasn't provided.
+    pub fn parse_arg_or<T: FromStr>(&self, index: usize, default: T) -> Result<T, String> {
+        match index < self.args.len() {
+            true => self.parse_arg(index),
+            false => Ok(default),
+        }
+    }
+
+    /// All arguments from `index` onwards, joined with spaces.
+    #[inline]
+    pub fn rest(&self, index: usize) -> String {
+        self.args.get(index..).unwrap_or_default().join(" ")
+    }
+}
+
+//====================================================================
+
+/// In-game developer console. Toggled with the backtick key. While open it captures
+/// all keyboard input - key releases still pass through so held keys don't get stuck.
+///
+/// The console only stores its state. Rendering the output and input line is left
+/// to the app, see `visible_output` and `prompt`.
+#[derive(Default)]
+pub struct Console {
+    open: bool,
+    input: Vec<char>,
+    cursor: usize,
+
+    history: VecDeque<String>,
+    history_index: Option<usize>,
+
+    output: VecDeque<String>,
+    scroll: usize,
+
+    commands: BTreeMap<String, Command>,
+}
+
+impl Console {
+    /// Create a console with the built in commands registered.
+    pub fn new() -> Self {
+        let mut console = Self::default();
+        register_builtin_commands(&mut console);
+        console
+    }
+
+    /// Register a command. Returning `Ok` prints the (non-empty) message and `Err`
+    /// prints it as an error.
+    pub fn register(
+        &mut self,
+        name: &str,
+        command: impl Fn(&mut State, &CommandArgs) -> CommandResult + 'static,
+    ) {
+        if self
+            .commands
+            .insert(name.to_string(), Rc::new(command))
+            .is_some()
+        {
+            log::warn!("Replacing existing console command '{}'", name);
+        }
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    #[inline]
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// Add lines to the output, dropping the oldest past the output limit.
+    pub fn print(&mut self, text: impl AsRef<str>) {
+        text.as_ref().lines().for_each(|line| {
+            self.output.push_back(line.to_string());
+        });
+
+        while self.output.len() > MAX_OUTPUT {
+            self.output.pop_front();
+        }
+    }
+
+    #[inline]
+    pub fn clear_output(&mut self) {
+        self.output.clear();
+        self.scroll = 0;
+    }
+
+    #[inline]
+    pub fn output(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.output.iter().map(|line| line.as_str())
+    }
+
+    /// Up to `rows` lines of output, oldest first, offset by the current scroll.
+    pub fn visible_output(&self, rows: usize) -> Vec<&str> {
+        let end = self.output.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(rows);
+
+        self.output
+            .range(start..end)
+            .map(|line| line.as_str())
+            .collect()
+    }
+
+    #[inline]
+    pub fn input(&self) -> String {
+        self.input.iter().collect()
+    }
+
+    /// The input line prefixed with `> ` and with the cursor drawn as `_`.
+    pub fn prompt(&self) -> String {
+        let (before, after) = self.input.split_at(self.cursor);
+
+        format!(
+            "> {}_{}",
+            before.iter().collect::<String>(),
+            after.iter().collect::<String>()
+        )
+    }
+
+    #[inline]
+    pub fn command_names(&self) -> impl Iterator<Item = &str> {
+        self.commands.keys().map(|name| name.as_str())
+    }
+
+    /// Registered command names starting with `prefix`, in alphabetical order.
+    pub fn complete(&self, prefix: &str) -> Vec<&str> {
+        self.command_names()
+            .filter(|name| name.starts_with(prefix))
+            .collect()
+    }
+
+    #[inline]
+    fn set_input(&mut self, text: &str) {
+        self.input = text.chars().collect();
+        self.cursor = self.input.len();
+    }
+
+    fn take_input(&mut self) -> String {
+        let line = self.input();
+        self.input.clear();
+        self.cursor = 0;
+        self.history_index = None;
+        self.scroll = 0;
+
+        if !line.trim().is_empty() && self.history.back() != Some(&line) {
+            self.history.push_back(line.clone());
+
+            if self.history.len() > MAX_HISTORY {
+                self.history.pop_front();
+            }
+        }
+
+        line
+    }
+
+    fn history_step(&mut self, up: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let last = self.history.len() - 1;
+
+        self.history_index = match (self.history_index, up) {
+            (None, true) => Some(last),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index < last => Some(index + 1),
+            (Some(_), false) => None,
+        };
+
+        match self.history_index {
+            Some(index) => {
+                let line = self.history[index].clone();
+                self.set_input(&line);
+            }
+            None => self.set_input(""),
+        }
+    }
+
+    fn tab_complete(&mut self) {
+        let input = self.input();
+
+        // Only the command name is completed
+        if input.contains(char::is_whitespace) {
+            return;
+        }
+
+        let matches = self.complete(&input);
+
+        match matches.as_slice() {
+            [] => {}
+            [name] => {
+                let completed = format!("{} ", name);
+                self.set_input(&completed);
+            }
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.to_string(), |common, name| {
+                    common
+                        .chars()
+                        .zip(name.chars())
+                        .take_while(|(a, b)| a == b)
+                        .map(|(a, _)| a)
+                        .collect()
+                });
+
+                let candidates = matches.join("  ");
+                self.set_input(&common);
+                self.print(candidates);
+            }
+        }
+    }
+
+    /// Returns the submitted line when enter is pressed.
+    fn key_pressed(&mut self, key: KeyCode) -> Option<String> {
+        match key {
+            KeyCode::Enter | KeyCode::NumpadEnter => return Some(self.take_input()),
+
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.input.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.input.len() => {
+                self.input.remove(self.cursor);
+            }
+
+            KeyCode::ArrowLeft => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::ArrowRight => self.cursor = (self.cursor + 1).min(self.input.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.input.len(),
+
+            KeyCode::ArrowUp => self.history_step(true),
+            KeyCode::ArrowDown => self.history_step(false),
+            KeyCode::Tab => self.tab_complete(),
+
+            KeyCode::PageUp => {
+                self.scroll = (self.scroll + 5).min(self.output.len().saturating_sub(1))
+            }
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(5),
+
+            KeyCode::Escape => self.open = false,
+
+            _ => {}
+        }
+
+        None
+    }
+
+    fn insert_text(&mut self, text: &str) {
+        text.chars()
+            .filter(|c| !c.is_control() && *c != '`')
+            .for_each(|c| {
+                self.input.insert(self.cursor, c);
+                self.cursor += 1;
+            });
+    }
+}
+
+//====================================================================
+
+/// Called before input reaches the rest of the state. Returns true if the console consumed the event.
+pub(crate) fn capture_input(state: &mut State, event: &WindowInputEvent) -> bool {
+    match event {
+        WindowInputEvent::KeyInput {
+            key: KeyCode::Backquote,
+            pressed: true,
+        } => {
+            state.console.open = !state.console.open;
+            true
+        }
+
+        WindowInputEvent::KeyInput { key, pressed: true } if state.console.open => {
+            if let Some(line) = state.console.key_pressed(*key) {
+                state.run_command(&line);
+            }
+            true
+        }
+
+        WindowInputEvent::Text { text } if state.console.open => {
+            state.console.insert_text(text);
+            true
+        }
+
+        _ => false,
+    }
+}
+
+//====================================================================
+
+impl State {
+    /// Register a console command. See `Console::register`.
+    #[inline]
+    pub fn register_command(
+        &mut self,
+        name: &str,
+        command: impl Fn(&mut State, &CommandArgs) -> CommandResult + 'static,
+    ) {
+        self.console.register(name, command);
+    }
+
+    /// Run a line as if it was entered into the console.
+    pub fn run_command(&mut self, line: &str) {
+        let Some((name, args)) = CommandArgs::parse(line) else {
+            return;
+        };
+
+        self.console.print(format!("> {}", line.trim()));
+
+        let command = match self.console.commands.get(&name) {
+            Some(command) => command.clone(),
+            None => {
+                self.console.print(format!(
+                    "Unknown command '{}'. Type 'help' for a list.",
+                    name
+                ));
+                return;
+            }
+        };
+
+        match command(self, &args) {
+            Ok(message) if message.is_empty() => {}
+            Ok(message) => self.console.print(message),
+            Err(message) => self.console.print(format!("Error: {}", message)),
+        }
+    }
+}
+
+//====================================================================
+
+fn register_builtin_commands(console: &mut Console) {
+    console.register("help", |state, _| {
+        Ok(state.console.command_names().collect::<Vec<_>>().join("  "))
+    });
+
+    console.register("clear", |state, _| {
+        state.console.clear_output();
+        Ok(String::new())
+    });
+
+    console.register("clear_color", |state, args| {
+        state.renderer.clear_color = Some(Color::new(
+            args.parse_arg(0)?,
+            args.parse_arg(1)?,
+            args.parse_arg(2)?,
+            args.parse_arg_or(3, 1.)?,
+        ));
+        Ok(String::new())
+    });
+
+    console.register("theme", |state, args| {
+        if !args.is_empty() {
+            let name = args.get(0)?;
+            let theme = Theme::by_name(name).ok_or_else(|| {
+                let names = Theme::builtin().map(|theme| theme.name);
+                format!("No theme named '{}' - try {}", name, names.join(", "))
+            })?;
+            state.renderer.set_theme(theme);
+        }
+        Ok(format!("Theme = {}", state.renderer.theme().name))
+    });
+
+    console.register("pipelines", |state, _| {
+        Ok(state
+            .renderer
+            .managed_pipeline_names()
+            .into_iter()
+            .map(|(name, enabled)| format!("{} ({})", name, if enabled { "on" } else { "off" }))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    });
+
+    console.register("toggle_pipeline", |state, args| {
+        let name = args.get(0)?;
+        let enabled = state
+            .renderer
+            .managed_pipeline_enabled(name)
+            .ok_or_else(|| format!("No managed pipeline named '{}'", name))?;
+
+        state.renderer.set_managed_pipeline_enabled(name, !enabled);
+        Ok(format!(
+            "{} {}",
+            name,
+            if enabled { "disabled" } else { "enabled" }
+        ))
+    });
+
+    console.register("time_scale", |state, args| {
+        if !args.is_empty() {
+            state.time.set_scale(args.parse_arg(0)?);
+        }
+        Ok(format!("Time scale = {}", state.time.scale()))
+    });
+
+    console.register("ambient", |state, args| {
+        state.renderer.update_light_globals(GlobalLightData {
+            ambient_color: glam::vec3(args.parse_arg(0)?, args.parse_arg(1)?, args.parse_arg(2)?),
+            ambient_strength: args.parse_arg_or(3, 1.)?,
+        });
+        Ok(String::new())
+    });
+
+    console.register("world", |state, _| {
+        let report = profiler::archetype_report(&state.world);
+        let bytes = report.iter().map(|archetype| archetype.bytes).sum();
+
+        let archetypes = report
+            .iter()
+            .map(|archetype| format!("  {}", archetype))
+            .collect::<Vec<_>>();
+
+        Ok(format!(
+            "{} entities in {} archetypes ({} total) ~{}\n{}",
+            state.world.len(),
+            archetypes.len(),
+            state.world.archetypes().len(),
+            memory::format_bytes(bytes),
+            archetypes.join("\n")
+        ))
+    });
+
+    console.register("queries", |state, _| {
+        if !state.profiler.enabled() {
+            return Err("Profiling is off - turn it on with 'profiler on'".into());
+        }
+
+        let queries = state
+            .profiler
+            .queries()
+            .iter()
+            .map(|stats| format!("  {}", stats))
+            .chain(
+                state
+                    .profiler
+                    .warnings()
+                    .iter()
+                    .map(|warning| format!("! {}", warning)),
+            )
+            .collect::<Vec<_>>();
+
+        Ok(format!(
+            "{:.3}ms in profiled queries last frame\n{}",
+            state.profiler.total_time().as_secs_f32() * 1000.,
+            queries.join("\n")
+        ))
+    });
+
+    console.register("profiler", |state, args| {
+        match args.get(0)? {
+            "on" => state.profiler.set_enabled(true),
+            "off" => state.profiler.set_enabled(false),
+            "overlay" => {
+                let overlay = !state.profiler.overlay();
+                state.profiler.set_overlay(overlay);
+                if overlay {
+                    state.profiler.set_enabled(true);
+                }
+            }
+            other => return Err(format!("Expected on, off or overlay, got '{}'", other)),
+        }
+
+        Ok(String::new())
+    });
+
+    console.register("memory", |state, args| {
+        let report = GpuMemoryTracker::report(args.parse_arg_or(0, 5)?);
+        let pool = &state.renderer.target_pool;
+
+        Ok(format!(
+            "{}Pooled targets: {} ({} in use)",
+            report,
+            pool.target_count(),
+            pool.in_use_count()
+        ))
+    });
+}
+
+//====================================================================
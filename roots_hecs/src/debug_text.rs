@@ -0,0 +1,294 @@
+//====================================================================
+
+use hecs::World;
+use roots_common::coords::WindowPx;
+use roots_pipelines::{
+    manager::{RenderContext, RenderPipeline},
+    texture2d_renderer::{Texture2dRenderer, TextureData},
+};
+use roots_renderer::{texture::LoadedTexture, RenderPass};
+use roots_text::{
+    fallback::FontFallback,
+    shared::{color_from_vec4, Color, TextResources},
+    text2d_renderer::{Text2d, Text2dRenderer},
+};
+
+use crate::renderer::{pipelines::Pipeline, RendererState};
+
+//====================================================================
+
+/// Priority the `DebugTextPipeline` is added with the first time a line is pushed,
+/// drawing it over everything but loading bars and the fps overlay.
+pub const DEBUG_TEXT_PIPELINE_PRIORITY: usize = crate::fps::FPS_PIPELINE_PRIORITY - 2;
+
+/// Push a formatted line to the debug text overlay for this frame.
+///
+/// ```ignore
+/// debug_text!(state, "player pos: {:?}", pos);
+/// ```
+#[macro_export]
+macro_rules! debug_text {
+    ($state:expr, $($arg:tt)*) => {
+        $state.renderer.debug_text.push_line(::std::format!($($arg)*))
+    };
+}
+
+#[derive(Debug, Clone)]
+struct DebugLine {
+    text: String,
+    /// `None` for the style's color.
+    color: Option<Color>,
+}
+
+/// Lines of text shown in the top left of the window for a single frame, in the
+/// system monospace font. Pushed with `debug_text!` or `RendererState::debug_text`
+/// and cleared once drawn. Nothing is created or drawn until the first line is pushed.
+#[derive(Debug, Default)]
+pub struct DebugTextQueue {
+    pub style: DebugTextStyle,
+    lines: Vec<DebugLine>,
+    dropped: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DebugTextStyle {
+    /// Lines pushed past this in a frame are counted rather than shown.
+    pub max_lines: usize,
+    pub font_size: f32,
+    /// Used by `DebugTextQueue::push_line`. Defaults to the theme's text color.
+    pub color: Option<Color>,
+    /// Drawn behind the lines for readability. Fully transparent to disable.
+    /// Defaults to the theme's background, partly transparent.
+    pub background: Option<glam::Vec4>,
+    /// Pixels between the text and the edges of the window.
+    pub margin: f32,
+}
+
+impl Default for DebugTextStyle {
+    fn default() -> Self {
+        Self {
+            max_lines: 32,
+            font_size: 14.,
+            color: None,
+            background: None,
+            margin: 8.,
+        }
+    }
+}
+
+impl DebugTextQueue {
+    #[inline]
+    pub fn push_line(&mut self, text: impl Into<String>) {
+        self.push_line_color(text, None);
+    }
+
+    #[inline]
+    pub fn push_colored(&mut self, text: impl Into<String>, color: Color) {
+        self.push_line_color(text, Some(color));
+    }
+
+    fn push_line_color(&mut self, text: impl Into<String>, color: Option<Color>) {
+        if self.lines.len() >= self.style.max_lines {
+            self.dropped += 1;
+            return;
+        }
+
+        self.lines.push(DebugLine {
+            text: text.into(),
+            color,
+        });
+    }
+
+    /// No lines were pushed, including any past `max_lines`.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.dropped == 0
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    #[inline]
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(|line| line.text.as_str())
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.dropped = 0;
+    }
+}
+
+//====================================================================
+
+/// Renders the lines handed over from the `DebugTextQueue` each frame.
+pub struct DebugTextPipeline {
+    background: Texture2dRenderer,
+    blank: LoadedTexture,
+    renderer: Text2dRenderer<usize>,
+    text: TextResources,
+
+    style: DebugTextStyle,
+    lines: Vec<DebugLine>,
+    dropped: usize,
+}
+
+impl RenderPipeline for DebugTextPipeline {
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        if self.lines.is_empty() && self.dropped == 0 {
+            return;
+        }
+
+        self.background.render(render_pass, context.screen_camera);
+        self.renderer
+            .render(render_pass, &self.text.text_atlas, context.screen_camera);
+        self.text.text_atlas.post_render_trim();
+    }
+
+    #[inline]
+    fn needs_depth(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn stereo(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn screen_space(&self) -> bool {
+        true
+    }
+}
+
+impl Pipeline for DebugTextPipeline {
+    fn new(state: &RendererState) -> Self {
+        let background = Texture2dRenderer::new_with_blend(
+            &state.device,
+            &state.config,
+            &state.shared,
+            false,
+            wgpu::BlendState::ALPHA_BLENDING,
+        );
+        let blank = LoadedTexture::load_blank(&state.device, &state.queue, &state.shared);
+
+        let mut text = TextResources::new(&state.device, &state.shared);
+        text.fallback = FontFallback::monospace(&text.font_system);
+
+        let renderer = Text2dRenderer::new(&state.device, &state.config, &state.shared);
+
+        Self {
+            background,
+            blank,
+            renderer,
+            text,
+            style: DebugTextStyle::default(),
+            lines: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    fn prep(&mut self, state: &RendererState, _world: &mut World) {
+        let target = roots_common::Size::new(state.config.width, state.config.height);
+        let style = self.style;
+        let theme = state.theme();
+        let color = style.color.unwrap_or_else(|| color_from_vec4(theme.text));
+        let background = style
+            .background
+            .unwrap_or_else(|| theme.background.with_w(0.6));
+        let line_height = style.font_size * 1.2;
+
+        let dropped = match self.dropped {
+            0 => None,
+            dropped => Some(DebugLine {
+                text: format!("+{} more", dropped),
+                color: None,
+            }),
+        };
+
+        let mut width = 0_f32;
+        let mut count = 0;
+
+        self.lines
+            .iter()
+            .chain(dropped.as_ref())
+            .enumerate()
+            .for_each(|(index, line)| {
+                self.renderer.prep_text(
+                    &state.device,
+                    &state.queue,
+                    &mut self.text,
+                    index,
+                    &Text2d {
+                        text: line.text.clone(),
+                        position: WindowPx::new(
+                            style.margin,
+                            style.margin + index as f32 * line_height,
+                        ),
+                        anchor: glam::Vec2::ZERO,
+                        font_size: style.font_size,
+                        color: line.color.unwrap_or(color),
+                    },
+                    target,
+                );
+
+                width = width.max(self.renderer.text_size(&index).unwrap_or_default().x);
+                count += 1;
+            });
+
+        self.renderer.finish_prep();
+
+        if count > 0 && background.w > 0. {
+            // Padded by half the margin on every side
+            let padding = style.margin / 2.;
+            let size = glam::vec2(width, count as f32 * line_height) + padding * 2.;
+            let top_left = glam::vec2(padding, target.height as f32 - padding);
+
+            self.background.prep_texture(TextureData {
+                texture: &self.blank,
+                size,
+                pos: (top_left + glam::vec2(size.x, -size.y) / 2.).extend(1.),
+                color: background,
+            });
+        }
+
+        self.background.finish_prep(&state.device, &state.queue);
+    }
+}
+
+//====================================================================
+
+/// Hand this frame's lines to the `DebugTextPipeline`, adding it the first time
+/// there are any. Called at the start of `RendererState::prep_managed`.
+pub(crate) fn flush(state: &mut RendererState) {
+    let shown = state.with_managed_pipeline::<DebugTextPipeline, _>(|pipeline| {
+        !pipeline.lines.is_empty() || pipeline.dropped > 0
+    });
+
+    if state.debug_text.is_empty() && shown != Some(true) {
+        return;
+    }
+
+    if shown.is_none() {
+        state.add_managed_pipeline::<DebugTextPipeline>(DEBUG_TEXT_PIPELINE_PRIORITY);
+    }
+
+    // Swap rather than move so both sides keep their allocations
+    let mut lines = std::mem::take(&mut state.debug_text.lines);
+    let dropped = std::mem::take(&mut state.debug_text.dropped);
+    let style = state.debug_text.style;
+
+    state.with_managed_pipeline::<DebugTextPipeline, _>(|pipeline| {
+        std::mem::swap(&mut pipeline.lines, &mut lines);
+        pipeline.dropped = dropped;
+        pipeline.style = style;
+    });
+
+    lines.clear();
+    state.debug_text.lines = lines;
+}
+
+//====================================================================
@@ -0,0 +1,159 @@
+//====================================================================
+
+use crate::{
+    renderer::components::{ArraySprite, Model, Sprite},
+    State,
+};
+
+//====================================================================
+
+/// Raises the alpha of a `Model`, `Sprite` or `ArraySprite` on the same entity to 1,
+/// then removes itself. Updated by `process_fades`.
+///
+/// Starts from the current alpha, so spawn with an alpha of 0 to fade in from nothing.
+/// Don't combine with a `FadeOut`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FadeIn {
+    /// Seconds to go from fully transparent to opaque.
+    pub duration: f32,
+}
+
+impl FadeIn {
+    #[inline]
+    pub fn new(duration: f32) -> Self {
+        Self { duration }
+    }
+}
+
+/// Lowers the alpha of a `Model`, `Sprite` or `ArraySprite` on the same entity to 0,
+/// then removes itself or despawns the entity. Updated by `process_fades`.
+///
+/// Starts from the current alpha, so replacing a `FadeIn` partway through doesn't pop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FadeOut {
+    /// Seconds to go from opaque to fully transparent.
+    pub duration: f32,
+    /// Despawn the entity with `State::despawn_tracked` once faded out.
+    pub despawn_on_complete: bool,
+}
+
+impl FadeOut {
+    #[inline]
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            despawn_on_complete: false,
+        }
+    }
+
+    /// Fade out then despawn the entity.
+    #[inline]
+    pub fn despawning(duration: f32) -> Self {
+        Self {
+            duration,
+            despawn_on_complete: true,
+        }
+    }
+}
+
+//====================================================================
+
+/// Step `alpha` by `delta` seconds of a fade lasting `duration`. Returns true once the
+/// fade has reached `target`.
+#[inline]
+fn step_alpha(alpha: &mut f32, target: f32, duration: f32, delta: f32) -> bool {
+    let step = match duration > 0. {
+        true => delta / duration,
+        false => 1.,
+    };
+
+    *alpha = match target > *alpha {
+        true => (*alpha + step).min(target),
+        false => (*alpha - step).max(target),
+    };
+
+    *alpha == target
+}
+
+/// Apply `step` to the alpha of every fadeable component on an entity. Returns true
+/// once all of them have finished, or if there are none.
+fn step_components(
+    model: Option<&mut Model>,
+    sprite: Option<&mut Sprite>,
+    array_sprite: Option<&mut ArraySprite>,
+    step: impl Fn(&mut f32) -> bool,
+) -> bool {
+    [
+        model.map(|model| step(&mut model.color[3])),
+        sprite.map(|sprite| step(&mut sprite.color.w)),
+        array_sprite.map(|sprite| step(&mut sprite.color.w)),
+    ]
+    .into_iter()
+    .flatten()
+    .all(|finished| finished)
+}
+
+/// Update every `FadeIn` and `FadeOut` from `State::time`. Finished fades are removed
+/// and `FadeOut::despawn_on_complete` entities are despawned with
+/// `State::despawn_tracked`, so their remove hooks run.
+pub fn process_fades(state: &mut State) {
+    let delta = state.time.delta_seconds();
+
+    let mut finished_in = Vec::new();
+    let mut finished_out = Vec::new();
+
+    state
+        .world
+        .query_mut::<(
+            &FadeIn,
+            Option<&mut Model>,
+            Option<&mut Sprite>,
+            Option<&mut ArraySprite>,
+        )>()
+        .into_iter()
+        .for_each(|(entity, (fade, model, sprite, array_sprite))| {
+            let finished = step_components(model, sprite, array_sprite, |alpha| {
+                step_alpha(alpha, 1., fade.duration, delta)
+            });
+
+            if finished {
+                finished_in.push(entity);
+            }
+        });
+
+    state
+        .world
+        .query_mut::<(
+            &FadeOut,
+            Option<&mut Model>,
+            Option<&mut Sprite>,
+            Option<&mut ArraySprite>,
+        )>()
+        .into_iter()
+        .for_each(|(entity, (fade, model, sprite, array_sprite))| {
+            let finished = step_components(model, sprite, array_sprite, |alpha| {
+                step_alpha(alpha, 0., fade.duration, delta)
+            });
+
+            if finished {
+                finished_out.push((entity, fade.despawn_on_complete));
+            }
+        });
+
+    finished_in.into_iter().for_each(|entity| {
+        state.world.remove_one::<FadeIn>(entity).ok();
+    });
+
+    finished_out
+        .into_iter()
+        .for_each(|(entity, despawn)| match despawn {
+            true => {
+                state.despawn_tracked(entity).ok();
+            }
+            false => {
+                state.world.remove_one::<FadeOut>(entity).ok();
+            }
+        });
+}
+
+//====================================================================
@@ -0,0 +1,390 @@
+//====================================================================
+
+use std::collections::VecDeque;
+
+use hecs::{Entity, World};
+use roots_common::{coords::WindowPx, theme::Theme};
+use roots_pipelines::manager::{RenderContext, RenderPipeline};
+use roots_renderer::{
+    memory::{self, GpuMemoryTracker},
+    RenderPass,
+};
+use roots_text::{
+    shared::{color_from_vec4, Color, TextResources},
+    text2d_renderer::{Text2d, Text2dRenderer},
+};
+use web_time::Instant;
+
+use crate::{
+    renderer::{pipelines::Pipeline, RendererState},
+    State,
+};
+
+//====================================================================
+
+/// Frames older than this are dropped from the stats.
+const STATS_WINDOW: f32 = 1.;
+
+/// Priority the `FpsPipeline` is added with by `State::show_fps`, so it's drawn
+/// over everything else.
+pub const FPS_PIPELINE_PRIORITY: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FpsCorner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Shows the current fps, average frame time and worst frame over the last second
+/// in a corner of the window. Drawn in screen space by the `FpsPipeline`, so no
+/// camera is needed.
+#[derive(Debug, Clone)]
+pub struct FpsOverlay {
+    pub corner: FpsCorner,
+    /// The theme's text color if `None`.
+    pub color: Option<Color>,
+    pub font_size: f32,
+    /// Pixels between the text and the edges of the window.
+    pub margin: f32,
+    /// Seconds between updates of the text, so it isn't laid out every frame.
+    pub update_interval: f32,
+    /// Also show the gpu memory recorded by the `GpuMemoryTracker`.
+    pub show_memory: bool,
+
+    text: String,
+    since_update: f32,
+}
+
+impl Default for FpsOverlay {
+    fn default() -> Self {
+        Self {
+            corner: FpsCorner::default(),
+            color: None,
+            font_size: 16.,
+            margin: 8.,
+            update_interval: 0.25,
+            show_memory: false,
+            text: String::new(),
+            since_update: 0.,
+        }
+    }
+}
+
+impl FpsOverlay {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_corner(mut self, corner: FpsCorner) -> Self {
+        self.corner = corner;
+        self
+    }
+
+    #[inline]
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    #[inline]
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    #[inline]
+    pub fn with_update_interval(mut self, update_interval: f32) -> Self {
+        self.update_interval = update_interval;
+        self
+    }
+
+    #[inline]
+    pub fn with_memory(mut self, show_memory: bool) -> Self {
+        self.show_memory = show_memory;
+        self
+    }
+
+    /// The text currently shown.
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn to_text2d(&self, window: glam::Vec2, theme: &Theme) -> Text2d {
+        let (position, anchor) = match self.corner {
+            FpsCorner::TopLeft => (glam::vec2(self.margin, self.margin), glam::vec2(0., 0.)),
+            FpsCorner::TopRight => (
+                glam::vec2(window.x - self.margin, self.margin),
+                glam::vec2(1., 0.),
+            ),
+            FpsCorner::BottomLeft => (
+                glam::vec2(self.margin, window.y - self.margin),
+                glam::vec2(0., 1.),
+            ),
+            FpsCorner::BottomRight => (window - self.margin, glam::vec2(1., 1.)),
+        };
+
+        Text2d {
+            text: self.text.clone(),
+            position: WindowPx(position),
+            anchor,
+            font_size: self.font_size,
+            color: self.color.unwrap_or_else(|| color_from_vec4(theme.text)),
+        }
+    }
+}
+
+//====================================================================
+
+/// Frame times over the last second.
+#[derive(Debug)]
+pub struct FrameStats {
+    frames: VecDeque<f32>,
+    total: f32,
+    resolution_scale: f32,
+    faulted_pipelines: usize,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            total: 0.,
+            resolution_scale: 1.,
+            faulted_pipelines: 0,
+        }
+    }
+}
+
+impl FrameStats {
+    /// Add a frame time in seconds, dropping frames that are now outside the window.
+    pub fn push(&mut self, frame_time: f32) {
+        self.frames.push_back(frame_time);
+        self.total += frame_time;
+
+        while self.total > STATS_WINDOW && self.frames.len() > 1 {
+            if let Some(old) = self.frames.pop_front() {
+                self.total -= old;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn fps(&self) -> f32 {
+        match self.total > 0. {
+            true => self.frames.len() as f32 / self.total,
+            false => 0.,
+        }
+    }
+
+    /// Average frame time in milliseconds.
+    #[inline]
+    pub fn average_frame_time(&self) -> f32 {
+        match self.frames.is_empty() {
+            true => 0.,
+            false => self.total * 1000. / self.frames.len() as f32,
+        }
+    }
+
+    /// Longest frame in milliseconds.
+    #[inline]
+    pub fn worst_frame_time(&self) -> f32 {
+        self.frames
+            .iter()
+            .fold(0_f32, |worst, frame| worst.max(*frame))
+            * 1000.
+    }
+
+    /// Internal resolution of the 3D scene, see `RendererState::set_resolution_scale`.
+    #[inline]
+    pub fn resolution_scale(&self) -> f32 {
+        self.resolution_scale
+    }
+
+    #[inline]
+    pub fn set_resolution_scale(&mut self, scale: f32) {
+        self.resolution_scale = scale;
+    }
+
+    /// Managed pipelines disabled after panicking, see `RendererState::pipeline_faults`.
+    #[inline]
+    pub fn faulted_pipelines(&self) -> usize {
+        self.faulted_pipelines
+    }
+
+    #[inline]
+    pub fn set_faulted_pipelines(&mut self, faulted_pipelines: usize) {
+        self.faulted_pipelines = faulted_pipelines;
+    }
+}
+
+//====================================================================
+
+/// Renders every `FpsOverlay`. Frames are timed between preps, so time spent
+/// asleep in `RedrawMode::Reactive` counts towards the frame it ends.
+pub struct FpsPipeline {
+    renderer: Text2dRenderer<Entity>,
+    text: TextResources,
+
+    stats: FrameStats,
+    last_prep: Option<Instant>,
+}
+
+impl FpsPipeline {
+    #[inline]
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+}
+
+impl RenderPipeline for FpsPipeline {
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        self.renderer
+            .render(render_pass, &self.text.text_atlas, context.screen_camera);
+        self.text.text_atlas.post_render_trim();
+    }
+
+    #[inline]
+    fn needs_depth(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn stereo(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn screen_space(&self) -> bool {
+        true
+    }
+}
+
+impl Pipeline for FpsPipeline {
+    fn new(state: &RendererState) -> Self {
+        let text = TextResources::new(&state.device, &state.shared);
+        let renderer = Text2dRenderer::new(&state.device, &state.config, &state.shared);
+
+        Self {
+            renderer,
+            text,
+            stats: FrameStats::default(),
+            last_prep: None,
+        }
+    }
+
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        let now = Instant::now();
+        let delta = self
+            .last_prep
+            .map(|last| now.duration_since(last).as_secs_f32())
+            .unwrap_or_default();
+
+        if self.last_prep.is_some() {
+            self.stats.push(delta);
+        }
+        self.last_prep = Some(now);
+        self.stats.set_resolution_scale(state.resolution_scale());
+        self.stats
+            .set_faulted_pipelines(state.pipeline_faults().len());
+
+        let target = roots_common::Size::new(state.config.width, state.config.height);
+        let window = glam::vec2(target.width as f32, target.height as f32);
+
+        world
+            .query_mut::<&mut FpsOverlay>()
+            .into_iter()
+            .for_each(|(entity, overlay)| {
+                overlay.since_update += delta;
+
+                if overlay.text.is_empty() || overlay.since_update >= overlay.update_interval {
+                    overlay.since_update = 0.;
+                    overlay.text = format!(
+                        "{:.0} fps\n{:.2} ms avg\n{:.2} ms worst",
+                        self.stats.fps(),
+                        self.stats.average_frame_time(),
+                        self.stats.worst_frame_time()
+                    );
+
+                    if self.stats.resolution_scale() < 1. {
+                        overlay.text.push_str(&format!(
+                            "\n{:.0}% res",
+                            self.stats.resolution_scale() * 100.
+                        ));
+                    }
+
+                    if self.stats.faulted_pipelines() > 0 {
+                        overlay
+                            .text
+                            .push_str(&format!("\n{} faulted", self.stats.faulted_pipelines()));
+                    }
+
+                    if overlay.show_memory {
+                        overlay.text.push_str(&format!(
+                            "\n{} gpu",
+                            memory::format_bytes(GpuMemoryTracker::total_all())
+                        ));
+                    }
+                }
+
+                self.renderer.prep_text(
+                    &state.device,
+                    &state.queue,
+                    &mut self.text,
+                    entity,
+                    &overlay.to_text2d(window, state.theme()),
+                    target,
+                );
+            });
+
+        self.renderer.finish_prep();
+    }
+}
+
+//====================================================================
+
+/// Marker for the overlay spawned by `State::show_fps`.
+struct BuiltinFpsOverlay;
+
+impl State {
+    /// Show or hide an `FpsOverlay` in the top left of the window. The `FpsPipeline`
+    /// is added the first time this is called. Spawn an `FpsOverlay` directly to
+    /// change where and how it's shown.
+    pub fn show_fps(&mut self, show: bool) {
+        let overlay = self
+            .world
+            .query_mut::<()>()
+            .with::<&BuiltinFpsOverlay>()
+            .into_iter()
+            .next()
+            .map(|(entity, _)| entity);
+
+        match (show, overlay) {
+            (true, None) => {
+                if self
+                    .renderer
+                    .with_managed_pipeline::<FpsPipeline, _>(|_| ())
+                    .is_none()
+                {
+                    self.renderer
+                        .add_managed_pipeline::<FpsPipeline>(FPS_PIPELINE_PRIORITY);
+                }
+
+                self.world.spawn((BuiltinFpsOverlay, FpsOverlay::default()));
+            }
+
+            (false, Some(entity)) => {
+                let _ = self.world.despawn(entity);
+            }
+
+            _ => {}
+        }
+    }
+}
+
+//====================================================================
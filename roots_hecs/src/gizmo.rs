@@ -0,0 +1,671 @@
+//====================================================================
+
+use hecs::{Entity, World};
+use roots_common::{
+    coords::WindowPx,
+    spatial::{GlobalTransform, Transform},
+    Size,
+};
+use roots_pipelines::line_renderer::LineInstance;
+use roots_renderer::camera::{CameraUniform, OrthographicCamera, PerspectiveCamera};
+use roots_runner::prelude::{KeyCode, MouseButton};
+
+use crate::{renderer::components::LineBundle, spatial::LocalTransform, State};
+
+//====================================================================
+
+/// Segments used to draw and hit test rotation rings.
+const RING_SEGMENTS: u32 = 48;
+
+/// How close the cursor ray has to pass to a handle, as a fraction of the gizmo size.
+const HIT_TOLERANCE: f32 = 0.08;
+
+/// Plane handles cover this range along both of their axes, as a fraction of the gizmo size.
+const PLANE_HANDLE: (f32, f32) = (0.25, 0.45);
+
+const RING_RADIUS: f32 = 0.8;
+
+const HIGHLIGHT_COLOR: glam::Vec4 = glam::vec4(1., 0.9, 0.2, 1.);
+
+//====================================================================
+
+/// A ray in world space, such as from the camera through the cursor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: glam::Vec3,
+    /// Always normalized.
+    pub direction: glam::Vec3,
+}
+
+impl Ray {
+    #[inline]
+    pub fn new(origin: glam::Vec3, direction: glam::Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize_or_zero(),
+        }
+    }
+
+    /// Ray through a point in the window using the same view projection the camera
+    /// renders with.
+    pub fn from_screen(
+        point: WindowPx,
+        viewport: Size<u32>,
+        view_projection: glam::Mat4,
+    ) -> Option<Self> {
+        if viewport.width == 0 || viewport.height == 0 {
+            return None;
+        }
+
+        let ndc = point.to_ndc(viewport);
+
+        let inverse = view_projection.inverse();
+        let near = ndc.unproject(0., inverse).0;
+        let far = ndc.unproject(1., inverse).0;
+
+        let ray = Self::new(near, far - near);
+
+        match ray.origin.is_finite() && ray.direction != glam::Vec3::ZERO {
+            true => Some(ray),
+            false => None,
+        }
+    }
+
+    #[inline]
+    pub fn at(&self, distance: f32) -> glam::Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Distance along the ray to a plane. `None` if parallel or behind the origin.
+    pub fn intersect_plane(&self, point: glam::Vec3, normal: glam::Vec3) -> Option<f32> {
+        let facing = normal.dot(self.direction);
+        if facing.abs() < 1e-6 {
+            return None;
+        }
+
+        let distance = (point - self.origin).dot(normal) / facing;
+
+        match distance >= 0. {
+            true => Some(distance),
+            false => None,
+        }
+    }
+
+    /// Closest approach to the infinite line through `point` along the normalized `axis`.
+    /// Returns the distance along the ray and along the line. `None` if parallel.
+    pub fn closest_to_line(&self, point: glam::Vec3, axis: glam::Vec3) -> Option<(f32, f32)> {
+        let offset = self.origin - point;
+        let alignment = self.direction.dot(axis);
+
+        let denominator = 1. - alignment * alignment;
+        if denominator < 1e-6 {
+            return None;
+        }
+
+        let ray_offset = self.direction.dot(offset);
+        let axis_offset = axis.dot(offset);
+
+        Some((
+            (alignment * axis_offset - ray_offset) / denominator,
+            (axis_offset - alignment * ray_offset) / denominator,
+        ))
+    }
+
+    /// Shortest distance between the ray and a segment, and the distance along the
+    /// ray where it happens.
+    pub fn distance_to_segment(&self, start: glam::Vec3, end: glam::Vec3) -> (f32, f32) {
+        let length = start.distance(end);
+        let axis = (end - start).normalize_or_zero();
+
+        let along = match self.closest_to_line(start, axis) {
+            Some((_, along)) => along,
+            None => (self.origin - start).dot(axis),
+        }
+        .clamp(0., length);
+
+        let point = start + axis * along;
+        let distance = (point - self.origin).dot(self.direction).max(0.);
+
+        (self.at(distance).distance(point), distance)
+    }
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+}
+
+/// World axes the gizmo's handles are aligned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl GizmoAxis {
+    pub const ALL: [GizmoAxis; 3] = [GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z];
+
+    #[inline]
+    pub fn direction(&self) -> glam::Vec3 {
+        match self {
+            GizmoAxis::X => glam::Vec3::X,
+            GizmoAxis::Y => glam::Vec3::Y,
+            GizmoAxis::Z => glam::Vec3::Z,
+        }
+    }
+
+    /// The two axes perpendicular to this one, in right handed order.
+    #[inline]
+    fn tangents(&self) -> (glam::Vec3, glam::Vec3) {
+        match self {
+            GizmoAxis::X => (glam::Vec3::Y, glam::Vec3::Z),
+            GizmoAxis::Y => (glam::Vec3::Z, glam::Vec3::X),
+            GizmoAxis::Z => (glam::Vec3::X, glam::Vec3::Y),
+        }
+    }
+
+    #[inline]
+    fn color(&self) -> glam::Vec4 {
+        match self {
+            GizmoAxis::X => glam::vec4(1., 0.2, 0.2, 1.),
+            GizmoAxis::Y => glam::vec4(0.2, 1., 0.2, 1.),
+            GizmoAxis::Z => glam::vec4(0.2, 0.4, 1., 1.),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoHandle {
+    /// Translate along an axis.
+    Axis(GizmoAxis),
+    /// Translate within the plane facing an axis.
+    Plane(GizmoAxis),
+    /// Rotate about an axis.
+    Ring(GizmoAxis),
+}
+
+//--------------------------------------------------
+
+struct Drag {
+    handle: GizmoHandle,
+    start_translation: glam::Vec3,
+    start_rotation: glam::Quat,
+    /// Where the cursor ray first hit the handle.
+    start_hit: glam::Vec3,
+
+    /// Rotation is accumulated so dragging past half a turn doesn't flip.
+    last_hit: glam::Vec3,
+    angle: f32,
+
+    translation: glam::Vec3,
+    rotation: glam::Quat,
+}
+
+#[derive(Clone, Copy)]
+struct Snap {
+    translate: f32,
+    rotate: f32,
+}
+
+impl Drag {
+    fn new(
+        handle: GizmoHandle,
+        ray: Ray,
+        translation: glam::Vec3,
+        rotation: glam::Quat,
+    ) -> Option<Self> {
+        let start_hit = match handle {
+            GizmoHandle::Axis(axis) => {
+                let (_, along) = ray.closest_to_line(translation, axis.direction())?;
+                translation + axis.direction() * along
+            }
+            GizmoHandle::Plane(axis) | GizmoHandle::Ring(axis) => {
+                ray.at(ray.intersect_plane(translation, axis.direction())?)
+            }
+        };
+
+        Some(Self {
+            handle,
+            start_translation: translation,
+            start_rotation: rotation,
+            start_hit,
+            last_hit: start_hit,
+            angle: 0.,
+            translation,
+            rotation,
+        })
+    }
+
+    /// Move to follow the ray. Returns false if the ray can't be projected onto the
+    /// handle this frame, such as when it is edge on to the camera.
+    fn update(&mut self, ray: Ray, snap: Option<Snap>) -> bool {
+        match self.handle {
+            GizmoHandle::Axis(axis) => {
+                let direction = axis.direction();
+                let Some((distance, along)) =
+                    ray.closest_to_line(self.start_translation, direction)
+                else {
+                    return false;
+                };
+
+                if distance < 0. {
+                    return false;
+                }
+
+                let start = self.start_translation.dot(direction);
+                let offset = along - (self.start_hit - self.start_translation).dot(direction);
+
+                let target = match snap {
+                    Some(snap) => snap_to(start + offset, snap.translate),
+                    None => start + offset,
+                };
+
+                self.translation = self.start_translation + direction * (target - start);
+            }
+
+            GizmoHandle::Plane(axis) => {
+                let direction = axis.direction();
+                let Some(distance) = ray.intersect_plane(self.start_translation, direction) else {
+                    return false;
+                };
+
+                let moved = self.start_translation + ray.at(distance) - self.start_hit;
+
+                self.translation = match snap {
+                    Some(snap) => {
+                        let snapped = (moved / snap.translate.max(f32::EPSILON)).round()
+                            * snap.translate.max(f32::EPSILON);
+
+                        // Only snap within the plane
+                        snapped - direction * snapped.dot(direction)
+                            + direction * moved.dot(direction)
+                    }
+                    None => moved,
+                };
+            }
+
+            GizmoHandle::Ring(axis) => {
+                let direction = axis.direction();
+                let Some(distance) = ray.intersect_plane(self.start_translation, direction) else {
+                    return false;
+                };
+
+                let hit = ray.at(distance);
+                let from = (self.last_hit - self.start_translation).normalize_or_zero();
+                let to = (hit - self.start_translation).normalize_or_zero();
+
+                if from == glam::Vec3::ZERO || to == glam::Vec3::ZERO {
+                    return false;
+                }
+
+                self.angle += direction.dot(from.cross(to)).atan2(from.dot(to));
+                self.last_hit = hit;
+
+                let angle = match snap {
+                    Some(snap) => snap_to(self.angle, snap.rotate.to_radians()),
+                    None => self.angle,
+                };
+
+                self.rotation = (glam::Quat::from_axis_angle(direction, angle)
+                    * self.start_rotation)
+                    .normalize();
+            }
+        }
+
+        true
+    }
+}
+
+#[inline]
+fn snap_to(value: f32, step: f32) -> f32 {
+    match step > 0. {
+        true => (value / step).round() * step,
+        false => value,
+    }
+}
+
+//====================================================================
+
+/// Editor style translate and rotate handles for a single entity. Handles are aligned
+/// to the world axes and stay the same size on screen. Dragging with the left mouse
+/// button writes into the target's `Transform`, or its `LocalTransform` if it is parented.
+/// The handles are drawn with the `LineRenderer`, which must be added as a pipeline.
+pub struct TransformGizmo {
+    pub target: Option<Entity>,
+    pub mode: GizmoMode,
+    /// Length of the handles as a fraction of the viewport height.
+    pub screen_size: f32,
+    /// Translation grid used while `snap_key` is held.
+    pub translate_snap: f32,
+    /// Rotation increment in degrees used while `snap_key` is held.
+    pub rotate_snap: f32,
+    pub snap_key: KeyCode,
+
+    lines: Entity,
+    hovered: Option<GizmoHandle>,
+    drag: Option<Drag>,
+}
+
+impl TransformGizmo {
+    /// Spawns the entity the handles are drawn with.
+    pub fn new(state: &mut State) -> Self {
+        let lines = state.world.spawn((LineBundle { lines: Vec::new() },));
+
+        Self {
+            target: None,
+            mode: GizmoMode::default(),
+            screen_size: 0.15,
+            translate_snap: 0.5,
+            rotate_snap: 15.,
+            snap_key: KeyCode::ControlLeft,
+            lines,
+            hovered: None,
+            drag: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_target(mut self, target: Entity) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    #[inline]
+    pub fn with_mode(mut self, mode: GizmoMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    #[inline]
+    pub fn with_snapping(
+        mut self,
+        translate_snap: f32,
+        rotate_snap: f32,
+        snap_key: KeyCode,
+    ) -> Self {
+        self.translate_snap = translate_snap;
+        self.rotate_snap = rotate_snap;
+        self.snap_key = snap_key;
+        self
+    }
+
+    /// The handle under the cursor, or the one being dragged.
+    #[inline]
+    pub fn hovered(&self) -> Option<GizmoHandle> {
+        match &self.drag {
+            Some(drag) => Some(drag.handle),
+            None => self.hovered,
+        }
+    }
+
+    /// Apps should ignore the mouse for their own controls while this is true.
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// The entity holding the gizmo's `LineBundle`.
+    #[inline]
+    pub fn line_entity(&self) -> Entity {
+        self.lines
+    }
+
+    /// Hit test, drag and redraw the handles as seen from `camera`. Call once per frame
+    /// after input has been processed.
+    pub fn update(&mut self, state: &mut State, camera: Entity) {
+        let lines = self.process(state, camera).unwrap_or_default();
+
+        if let Ok(mut bundle) = state.world.get::<&mut LineBundle>(self.lines) {
+            bundle.lines = lines;
+        }
+    }
+
+    fn process(&mut self, state: &mut State, camera: Entity) -> Option<Vec<LineInstance>> {
+        let Some(target) = self.target else {
+            self.hovered = None;
+            self.drag = None;
+            return None;
+        };
+
+        if !state.mouse_buttons.pressed(MouseButton::Left) {
+            self.drag = None;
+        }
+
+        let (projection, view) = camera_matrices(&state.world, camera)?;
+        let view_projection = projection * view;
+
+        let (_, rotation, translation) = state
+            .world
+            .get::<&GlobalTransform>(target)
+            .ok()?
+            .to_scale_rotation_translation();
+
+        // Follow the drag directly - the hierarchy hasn't propagated it yet
+        let center = match &self.drag {
+            Some(drag) => drag.translation,
+            None => translation,
+        };
+
+        // Keep the same size on screen. `w` is the view depth for perspective
+        // projections and 1 for orthographic ones.
+        let clip_w = (view_projection * center.extend(1.)).w;
+        if clip_w <= 0. {
+            self.hovered = None;
+            return None;
+        }
+        let size = self.screen_size * 2. * clip_w / projection.y_axis.y.abs().max(f32::EPSILON);
+
+        let ray = Ray::from_screen(state.mouse_input.position(), state.size(), view_projection);
+
+        match (&mut self.drag, ray) {
+            (Some(drag), Some(ray)) => {
+                let snap = match state.keys.pressed(self.snap_key) {
+                    true => Some(Snap {
+                        translate: self.translate_snap,
+                        rotate: self.rotate_snap,
+                    }),
+                    false => None,
+                };
+
+                if drag.update(ray, snap) {
+                    write_world_transform(
+                        &mut state.world,
+                        target,
+                        drag.translation,
+                        drag.rotation,
+                    );
+                }
+            }
+
+            (None, Some(ray)) => {
+                self.hovered = self.hit_test(ray, center, size);
+
+                if let (true, Some(handle)) = (
+                    state.mouse_buttons.just_pressed(MouseButton::Left),
+                    self.hovered,
+                ) {
+                    self.drag = Drag::new(handle, ray, translation, rotation);
+                }
+            }
+
+            (_, None) => self.hovered = None,
+        }
+
+        Some(self.build_lines(center, size))
+    }
+
+    /// The closest handle hit by the ray.
+    fn hit_test(&self, ray: Ray, center: glam::Vec3, size: f32) -> Option<GizmoHandle> {
+        let tolerance = size * HIT_TOLERANCE;
+
+        let hits = GizmoAxis::ALL.into_iter().flat_map(|axis| {
+            let direction = axis.direction();
+
+            let hits: [Option<(GizmoHandle, f32)>; 2] = match self.mode {
+                GizmoMode::Translate => {
+                    let (distance, along) =
+                        ray.distance_to_segment(center, center + direction * size);
+                    let axis_hit =
+                        (distance < tolerance).then_some((GizmoHandle::Axis(axis), along));
+
+                    let plane_hit = ray.intersect_plane(center, direction).and_then(|along| {
+                        let local = ray.at(along) - center;
+                        let (u, v) = axis.tangents();
+                        let range = PLANE_HANDLE.0 * size..PLANE_HANDLE.1 * size;
+
+                        match range.contains(&local.dot(u)) && range.contains(&local.dot(v)) {
+                            true => Some((GizmoHandle::Plane(axis), along)),
+                            false => None,
+                        }
+                    });
+
+                    [axis_hit, plane_hit]
+                }
+
+                GizmoMode::Rotate => {
+                    let ring = ring_points(axis, center, size * RING_RADIUS);
+                    let ring_hit = ring
+                        .windows(2)
+                        .map(|segment| ray.distance_to_segment(segment[0], segment[1]))
+                        .filter(|(distance, _)| *distance < tolerance)
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .map(|(_, along)| (GizmoHandle::Ring(axis), along));
+
+                    [ring_hit, None]
+                }
+            };
+
+            hits.into_iter().flatten()
+        });
+
+        hits.min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(handle, _)| handle)
+    }
+
+    fn build_lines(&self, center: glam::Vec3, size: f32) -> Vec<LineInstance> {
+        let highlighted = self.hovered();
+        let color = |handle: GizmoHandle, axis: GizmoAxis| match highlighted == Some(handle) {
+            true => HIGHLIGHT_COLOR,
+            false => axis.color(),
+        };
+
+        let line = |pos1, pos2, color| LineInstance {
+            color,
+            pos1,
+            pos2,
+            ..Default::default()
+        };
+
+        GizmoAxis::ALL
+            .into_iter()
+            .flat_map(|axis| {
+                let direction = axis.direction();
+                let (u, v) = axis.tangents();
+
+                match self.mode {
+                    GizmoMode::Translate => {
+                        let axis_color = color(GizmoHandle::Axis(axis), axis);
+                        let tip = center + direction * size;
+                        let base = tip - direction * size * 0.15;
+
+                        let mut lines = vec![line(center, tip, axis_color)];
+
+                        // Arrow head
+                        lines.extend(
+                            [u, -u, v, -v]
+                                .into_iter()
+                                .map(|side| line(tip, base + side * size * 0.06, axis_color)),
+                        );
+
+                        // Square for the plane facing this axis
+                        let plane_color =
+                            color(GizmoHandle::Plane(axis), axis) * glam::vec4(1., 1., 1., 0.7);
+                        let (min, max) = (PLANE_HANDLE.0 * size, PLANE_HANDLE.1 * size);
+                        let corners = [
+                            center + u * min + v * min,
+                            center + u * max + v * min,
+                            center + u * max + v * max,
+                            center + u * min + v * max,
+                        ];
+
+                        lines.extend((0..4).map(|index| {
+                            line(corners[index], corners[(index + 1) % 4], plane_color)
+                        }));
+
+                        lines
+                    }
+
+                    GizmoMode::Rotate => {
+                        let ring_color = color(GizmoHandle::Ring(axis), axis);
+
+                        ring_points(axis, center, size * RING_RADIUS)
+                            .windows(2)
+                            .map(|segment| line(segment[0], segment[1], ring_color))
+                            .collect()
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+//====================================================================
+
+/// Closed loop of points around `axis`. The first point is repeated at the end.
+fn ring_points(axis: GizmoAxis, center: glam::Vec3, radius: f32) -> Vec<glam::Vec3> {
+    let (u, v) = axis.tangents();
+
+    (0..=RING_SEGMENTS)
+        .map(|index| {
+            let (sin, cos) =
+                (index as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU).sin_cos();
+            center + (u * cos + v * sin) * radius
+        })
+        .collect()
+}
+
+/// Projection and view matrices of a perspective or orthographic camera entity.
+fn camera_matrices(world: &World, camera: Entity) -> Option<(glam::Mat4, glam::Mat4)> {
+    let global = world.get::<&GlobalTransform>(camera).ok()?.0;
+
+    if let Ok(data) = world.get::<&PerspectiveCamera>(camera) {
+        return Some((data.get_projection_matrix(), data.get_view_matrix(&global)));
+    }
+
+    let data = world.get::<&OrthographicCamera>(camera).ok()?;
+    Some((data.get_projection_matrix(), data.get_view_matrix(&global)))
+}
+
+/// Set an entity's world translation and rotation. Parented entities are converted
+/// into their parent's space, as the hierarchy stores them relative to the parent.
+fn write_world_transform(
+    world: &mut World,
+    entity: Entity,
+    translation: glam::Vec3,
+    rotation: glam::Quat,
+) {
+    if let Ok(mut local) = world.get::<&mut LocalTransform>(entity) {
+        let parent = world
+            .get::<&GlobalTransform>(local.parent)
+            .map(|global| global.0)
+            .unwrap_or(glam::Affine3A::IDENTITY);
+
+        let (_, parent_rotation, _) = parent.to_scale_rotation_translation();
+
+        local.transform.translation = parent.inverse().transform_point3(translation);
+        local.transform.rotation = (parent_rotation.inverse() * rotation).normalize();
+        return;
+    }
+
+    if let Ok(mut transform) = world.get::<&mut Transform>(entity) {
+        transform.translation = translation;
+        transform.rotation = rotation;
+    }
+}
+
+//====================================================================
@@ -0,0 +1,147 @@
+//====================================================================
+
+use std::any::TypeId;
+
+use hecs::{Component, Entity, NoSuchEntity};
+use roots_common::FastHasher;
+
+use crate::State;
+
+//====================================================================
+
+type RemoveHook = Box<dyn Fn(&mut State, Entity)>;
+
+/// Callbacks run when tracked components are removed through `State::despawn_tracked`.
+#[derive(Default)]
+pub(crate) struct RemoveHooks {
+    hooks: std::collections::HashMap<TypeId, RemoveHook, FastHasher>,
+}
+
+//====================================================================
+
+impl State {
+    /// Register a callback for when an entity holding a `T` is despawned with
+    /// `despawn_tracked`. The callback receives the removed component so it can
+    /// release any resources it owns. Only one hook can be registered per type.
+    ///
+    /// None of the built in components need hooks - pipelines release their
+    /// meshes and textures during the next prep, which happens later the same frame.
+    pub fn on_remove<T: Component>(&mut self, hook: fn(&mut State, Entity, T)) {
+        let erased: RemoveHook = Box::new(move |state, entity| {
+            if let Ok(component) = state.world.remove_one::<T>(entity) {
+                hook(state, entity, component);
+            }
+        });
+
+        if self
+            .remove_hooks
+            .hooks
+            .insert(TypeId::of::<T>(), erased)
+            .is_some()
+        {
+            log::warn!(
+                "Replacing existing remove hook for '{}'",
+                std::any::type_name::<T>()
+            );
+        }
+    }
+
+    /// Despawn an entity, first running the remove hooks of any tracked components it has.
+    pub fn despawn_tracked(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        if !self.world.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+
+        // Take the hooks so they can mutate the state. Hooks registered while
+        // running are kept.
+        let hooks = std::mem::take(&mut self.remove_hooks.hooks);
+        hooks.values().for_each(|hook| hook(self, entity));
+
+        let registered = std::mem::replace(&mut self.remove_hooks.hooks, hooks);
+        self.remove_hooks.hooks.extend(registered);
+
+        self.world.despawn(entity)
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use roots_common::Size;
+
+    use super::*;
+    use crate::test_utils;
+
+    struct Tracked(Arc<AtomicUsize>);
+
+    fn state() -> Option<State> {
+        let renderer = test_utils::renderer(16, 16)?;
+        let mut state = State::new_embedded(renderer, Size::new(16, 16));
+        state.on_remove::<Tracked>(|_, _, tracked| {
+            tracked.0.fetch_add(1, Ordering::Relaxed);
+        });
+
+        Some(state)
+    }
+
+    #[test]
+    fn despawn_tracked_runs_hooks() {
+        let Some(mut state) = state() else {
+            return;
+        };
+        let removed = Arc::new(AtomicUsize::new(0));
+
+        let tracked = state.world.spawn((Tracked(removed.clone()),));
+        let untracked = state.world.spawn(());
+
+        state.despawn_tracked(untracked).unwrap();
+        assert_eq!(removed.load(Ordering::Relaxed), 0);
+
+        state.despawn_tracked(tracked).unwrap();
+        assert_eq!(removed.load(Ordering::Relaxed), 1);
+        assert!(!state.world.contains(tracked));
+
+        assert!(state.despawn_tracked(tracked).is_err());
+        assert_eq!(removed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn plain_despawn_skips_hooks() {
+        let Some(mut state) = state() else {
+            return;
+        };
+        let removed = Arc::new(AtomicUsize::new(0));
+
+        let entity = state.world.spawn((Tracked(removed.clone()),));
+        state.world.despawn(entity).unwrap();
+
+        assert_eq!(removed.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn hooks_registered_by_hooks_are_kept() {
+        let Some(mut state) = state() else {
+            return;
+        };
+
+        struct Registers;
+        state.on_remove::<Registers>(|state, _, _| {
+            state.on_remove::<u32>(|_, _, _| {});
+        });
+
+        let entity = state.world.spawn((Registers,));
+        state.despawn_tracked(entity).unwrap();
+
+        assert!(state
+            .remove_hooks
+            .hooks
+            .contains_key(&TypeId::of::<Tracked>()));
+        assert!(state.remove_hooks.hooks.contains_key(&TypeId::of::<u32>()));
+    }
+}
@@ -1,70 +1,439 @@
 //====================================================================
 
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
-use hecs::World;
+use hecs::{Component, Entity, World};
 use renderer::RendererState;
+#[cfg(feature = "winit")]
+use roots_common::input::Input;
 use roots_common::{
-    input::{Input, MouseInput},
-    Size, Time,
+    coords::CoordinateConvention,
+    input::{self, MouseInput},
+    rng::WorldRng,
+    spatial::Transform,
+    Size, ThrottleReason, Time,
 };
+use roots_renderer::{shared::DepthConvention, splash::SplashScreen, RenderCoreConfig};
+#[cfg(feature = "winit")]
 use roots_runner::{
     prelude::{KeyCode, MouseButton},
     window::Window,
+    WindowInputEvent,
 };
 
+#[cfg(feature = "winit")]
+pub mod actions;
+pub mod blob_shadow;
+#[cfg(feature = "winit")]
+pub mod camera_control;
+#[cfg(feature = "console")]
+pub mod console;
+pub mod debug_text;
+pub mod fade;
+pub mod fps;
+#[cfg(feature = "winit")]
+pub mod gizmo;
+mod hooks;
+#[cfg(feature = "winit")]
+pub mod menu;
+#[cfg(feature = "winit")]
+pub mod pan_zoom;
+pub mod particles;
+pub mod path;
+pub mod preload;
+pub mod profiler;
 pub mod renderer;
+#[cfg(feature = "serde")]
+pub mod replication;
+#[cfg(feature = "winit")]
 pub mod runner;
+#[cfg(feature = "winit")]
+pub mod selection;
 pub mod spatial;
+pub mod spatial_hash;
+pub mod tasks;
+#[cfg(test)]
+mod test_utils;
+pub mod text;
+pub mod tracked;
+pub mod validation;
 
 pub use hecs;
 
 //====================================================================
 
 pub trait HecsApp: 'static {
+    /// Shown by the runner from as soon as the renderer exists until `new` returns, or
+    /// until `RendererState::finish_splash` if held with `RendererState::hold_splash`.
+    fn splash() -> Option<SplashScreen>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Handedness and up axis of the 3D world. Made active by the runner before the
+    /// renderer is created, so cameras and loaded assets all use it.
+    fn coordinate_convention() -> CoordinateConvention
+    where
+        Self: Sized,
+    {
+        CoordinateConvention::default()
+    }
+
+    /// Options the runner creates the renderer with, such as the present mode.
+    fn render_core_config() -> RenderCoreConfig
+    where
+        Self: Sized,
+    {
+        RenderCoreConfig::default()
+    }
+
+    /// Depth format and reversed-Z used by every camera and managed pipeline. Applied
+    /// by the runner before `new`, so pipelines added there already agree with it.
+    /// Reversed-Z keeps depth precise in scenes with a far `z_far`.
+    fn depth_convention() -> DepthConvention
+    where
+        Self: Sized,
+    {
+        DepthConvention::default()
+    }
+
     fn new(state: &mut State) -> Self
     where
         Self: Sized;
 
     fn resize(&mut self, state: &mut State, size: Size<u32>);
+
+    /// Called once per frame, before the managed pipelines are prepped and rendered.
     fn tick(&mut self, state: &mut State);
 }
 
+#[cfg(feature = "winit")]
 pub struct StateOuter<A: HecsApp> {
     state: State,
     app: A,
+    /// The tick ran and rendering was deferred for late latching.
+    render_pending: bool,
+}
+
+/// How the app behaves while the window is unfocused or occluded.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BackgroundBehavior {
+    /// Keep running at the full target fps.
+    #[default]
+    Continue,
+    /// Keep ticking and rendering at a reduced fps.
+    ThrottleTo(f32),
+    /// Keep ticking at the target fps but skip all rendering.
+    PauseRendering,
+    /// Stop ticking and rendering until the window is back in the foreground.
+    PauseAll,
+}
+
+/// When the runner draws new frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RedrawMode {
+    /// Tick and render every frame at the target fps.
+    #[default]
+    Continuous,
+    /// Only tick and render after input, a window change, `State::request_redraw`,
+    /// or while an animation is active. The app sleeps in between.
+    Reactive,
 }
 
 pub struct State {
     pub world: World,
-    pub window: Window,
+    /// The window created by the runner. `None` when embedded in a host application.
+    #[cfg(feature = "winit")]
+    pub window: Option<Window>,
+    size: Size<u32>,
     pub target_fps: Duration,
+    pub background_behavior: BackgroundBehavior,
+    redraw_mode: RedrawMode,
+    redraw_requested: bool,
+    active_animations: HashSet<&'static str>,
+
+    focused: bool,
+    occluded: bool,
+    #[cfg(feature = "winit")]
+    clamp_next_delta: bool,
+    /// The runner is waiting for an event before drawing the next frame.
+    #[cfg(feature = "winit")]
+    idle: bool,
+    remove_hooks: hooks::RemoveHooks,
+    change_tracking: tracked::ChangeTracking,
 
     pub renderer: RendererState,
     pub time: Time,
+    /// Shared random stream. Entropy seeded unless set with `State::seed_rng`.
+    pub rng: WorldRng,
+    #[cfg(feature = "console")]
+    pub console: console::Console,
 
+    #[cfg(feature = "winit")]
     pub keys: Input<KeyCode>,
+    #[cfg(feature = "winit")]
     pub mouse_buttons: Input<MouseButton>,
     pub mouse_input: MouseInput,
+    /// Late latching of camera mouse look. Off by default.
+    #[cfg(feature = "winit")]
+    pub late_latch: camera_control::LateLatch,
+    /// Neighbour queries over entities with `SpatialIndexed`. Updated by
+    /// `spatial_hash::process_spatial_hash`.
+    pub spatial_hash: spatial_hash::SpatialHash,
+    /// Main thread work spread over frames. See `State::add_task`.
+    pub tasks: tasks::TaskSlicer,
+    /// Query timings and archetype stats. See `profiler::profiled_query`.
+    pub profiler: profiler::WorldProfiler,
 }
 
 impl State {
-    fn new(window: Window) -> Self {
-        let world = World::new();
+    #[cfg(feature = "winit")]
+    fn new(window: Window, core_config: &RenderCoreConfig) -> Self {
+        let size = window.size();
+        let renderer = RendererState::new_with_config(&window, core_config);
 
-        let renderer = RendererState::new(&window);
+        let mut state = Self::new_embedded(renderer, size);
+        state.window = Some(window);
+        state.match_monitor_refresh();
+        state
+    }
 
+    /// Create a state for a host application that owns the window and event loop.
+    /// The host is expected to forward its events with `inject_input` and
+    /// `inject_resize`, and to drive the frame itself.
+    pub fn new_embedded(renderer: RendererState, size: Size<u32>) -> Self {
+        let world = World::new();
         let time = Time::new();
 
-        State {
+        let mut change_tracking = tracked::ChangeTracking::default();
+        change_tracking.register::<Transform>();
+
+        let mut state = State {
             world,
-            window,
+            #[cfg(feature = "winit")]
+            window: None,
+            size,
             renderer,
             target_fps: Duration::from_secs_f32(1. / 75.),
+            background_behavior: BackgroundBehavior::default(),
+            redraw_mode: RedrawMode::default(),
+            redraw_requested: false,
+            active_animations: HashSet::new(),
+            focused: true,
+            occluded: false,
+            #[cfg(feature = "winit")]
+            clamp_next_delta: false,
+            #[cfg(feature = "winit")]
+            idle: false,
+            remove_hooks: hooks::RemoveHooks::default(),
+            change_tracking,
             time,
+            rng: WorldRng::default(),
+            #[cfg(feature = "winit")]
             keys: Input::new(),
+            #[cfg(feature = "winit")]
             mouse_buttons: Input::new(),
             mouse_input: MouseInput::new(),
+            #[cfg(feature = "winit")]
+            late_latch: camera_control::LateLatch::default(),
+            #[cfg(feature = "console")]
+            console: console::Console::new(),
+            spatial_hash: spatial_hash::SpatialHash::new(),
+            tasks: tasks::TaskSlicer::new(),
+            profiler: profiler::WorldProfiler::default(),
+        };
+
+        profiler::register_builtin_components();
+
+        state.on_remove::<spatial_hash::SpatialIndexed>(|state, entity, mut indexed| {
+            state.spatial_hash.remove(entity, &mut indexed)
+        });
+
+        state
+    }
+
+    /// Reseed the shared rng, for example from `HecsApp::new`, to make a run reproducible.
+    #[inline]
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = WorldRng::new(seed);
+    }
+
+    /// Size of the window or viewport being rendered to.
+    #[inline]
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+
+    /// Resize the renderer and fit the aspect of perspective cameras to `size`. Called
+    /// by the runner, or by the host application when its viewport changes size.
+    pub fn inject_resize(&mut self, size: Size<u32>) {
+        self.size = size;
+        self.renderer.resize(size);
+        self.renderer.fit_camera_aspects(&mut self.world, size);
+    }
+
+    #[cfg(feature = "winit")]
+    pub fn inject_input(&mut self, event: WindowInputEvent) {
+        self.request_redraw();
+
+        #[cfg(feature = "console")]
+        if console::capture_input(self, &event) {
+            return;
+        }
+
+        match event {
+            WindowInputEvent::KeyInput { key, pressed } => {
+                input::process_inputs(&mut self.keys, key, pressed)
+            }
+            WindowInputEvent::MouseInput { button, pressed } => {
+                input::process_inputs(&mut self.mouse_buttons, button, pressed)
+            }
+            WindowInputEvent::CursorMoved { position } => {
+                input::process_mouse_position(&mut self.mouse_input, position)
+            }
+            WindowInputEvent::CursorEntered => {}
+            WindowInputEvent::CursorLeft => {}
+            WindowInputEvent::MouseWheel { delta } => {
+                input::process_mouse_scroll(&mut self.mouse_input, delta)
+            }
+            WindowInputEvent::MouseMotion { delta } => {
+                input::process_mouse_motion(&mut self.mouse_input, delta)
+            }
+            WindowInputEvent::Text { .. } => {}
+        }
+    }
+
+    /// Clear the just pressed/released inputs. Call at the end of each frame.
+    pub fn reset_inputs(&mut self) {
+        #[cfg(feature = "winit")]
+        {
+            input::reset_input(&mut self.keys);
+            input::reset_input(&mut self.mouse_buttons);
+        }
+        input::reset_mouse_input(&mut self.mouse_input);
+    }
+
+    /// Clear the changes to every `Tracked<T>` registered with `track_changes`.
+    /// Called by the runner at the end of each frame, after rendering. Host
+    /// applications should call it alongside `reset_inputs`.
+    #[inline]
+    pub fn clear_changes(&mut self) {
+        self.change_tracking.clear(&mut self.world);
+    }
+
+    /// Opt in to change tracking for `Tracked<T>` components, so their changes are
+    /// cleared by `clear_changes`. `Tracked<Transform>` is always tracked.
+    #[inline]
+    pub fn track_changes<T: Component>(&mut self) {
+        self.change_tracking.register::<T>();
+    }
+
+    /// Entities whose `Tracked<T>` changed this frame. Doesn't clear the changes, so
+    /// every interested system sees them. Without `track_changes`, changes are
+    /// never cleared and this returns every entity written to since it was spawned.
+    pub fn changed<T: Component>(&mut self) -> impl Iterator<Item = Entity> + '_ {
+        debug_assert!(
+            self.change_tracking.is_tracked::<T>(),
+            "Changes to '{}' aren't tracked - call State::track_changes first",
+            std::any::type_name::<T>()
+        );
+
+        tracked::changed::<T>(&mut self.world)
+    }
+
+    /// The occluded signal is unreliable on some platforms, so the window only counts
+    /// as being in the background while it is also unfocused.
+    pub fn background_reason(&self) -> Option<ThrottleReason> {
+        match (self.focused, self.occluded) {
+            (true, _) => None,
+            (false, true) => Some(ThrottleReason::Occluded),
+            (false, false) => Some(ThrottleReason::Unfocused),
+        }
+    }
+
+    #[inline]
+    pub fn redraw_mode(&self) -> RedrawMode {
+        self.redraw_mode
+    }
+
+    pub fn set_redraw_mode(&mut self, redraw_mode: RedrawMode) {
+        self.redraw_mode = redraw_mode;
+        self.request_redraw();
+    }
+
+    /// Draw another frame in `RedrawMode::Reactive`. Does nothing in continuous mode.
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+
+        #[cfg(feature = "winit")]
+        if self.idle {
+            if let Some(window) = &self.window {
+                window.inner().request_redraw();
+            }
+        }
+    }
+
+    /// Mark an animation as running. While any animation is active, reactive mode keeps
+    /// drawing at the target fps. Systems should set this every frame they step.
+    pub fn set_animation_active(&mut self, name: &'static str, active: bool) {
+        match active {
+            true => self.active_animations.insert(name),
+            false => self.active_animations.remove(name),
+        };
+    }
+
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        !self.active_animations.is_empty()
+    }
+
+    /// Whether another frame needs to be drawn after the current one.
+    #[inline]
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw_mode == RedrawMode::Continuous || self.redraw_requested || self.is_animating()
+    }
+
+    #[inline]
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    #[inline]
+    pub fn occluded(&self) -> bool {
+        self.occluded
+    }
+
+    /// Pace frames to the refresh rate of the window's monitor when presenting with
+    /// vsync. Called when the window is created and whenever it moves. Does nothing
+    /// without vsync, leaving `target_fps` as set.
+    #[cfg(feature = "winit")]
+    pub fn match_monitor_refresh(&mut self) {
+        let vsync = matches!(
+            self.renderer.config.present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::Fifo | wgpu::PresentMode::FifoRelaxed
+        );
+
+        if !vsync {
+            return;
+        }
+
+        // Skips listing the monitor's video modes, as this runs on every move
+        let refresh_rate = self
+            .window
+            .as_ref()
+            .and_then(|window| window.inner().current_monitor())
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .map(|millihertz| millihertz as f32 / 1000.);
+
+        if let Some(refresh_rate) = refresh_rate.filter(|rate| *rate > 0.) {
+            let target_fps = Duration::from_secs_f32(1. / refresh_rate);
+
+            if target_fps != self.target_fps {
+                log::debug!("Matching monitor refresh rate of {}hz", refresh_rate);
+                self.target_fps = target_fps;
+            }
         }
     }
 }
@@ -0,0 +1,426 @@
+//====================================================================
+
+use std::collections::HashSet;
+
+use roots_common::{input::Input, Time};
+use roots_runner::prelude::KeyCode;
+use roots_text::ui3d_renderer::Ui3d;
+use web_time::Duration;
+
+//====================================================================
+
+/// Keys used by a `MenuController`. Any key in a list triggers its action.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuBindings {
+    pub up: Vec<KeyCode>,
+    pub down: Vec<KeyCode>,
+    pub confirm: Vec<KeyCode>,
+    pub cancel: Vec<KeyCode>,
+}
+
+impl Default for MenuBindings {
+    fn default() -> Self {
+        Self {
+            up: vec![KeyCode::ArrowUp],
+            down: vec![KeyCode::ArrowDown],
+            confirm: vec![KeyCode::Enter, KeyCode::NumpadEnter],
+            cancel: vec![KeyCode::Escape],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEvent {
+    /// The selection moved to this option.
+    SelectionChanged(usize),
+    /// The selected option was activated.
+    Confirmed(usize),
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+}
+
+//====================================================================
+
+/// Keyboard navigation for a `Ui3d` menu. Call `update` once per tick - up and down
+/// move the selection, repeating while held, and confirm and cancel are returned
+/// as events.
+#[derive(Debug, Clone)]
+pub struct MenuController {
+    pub bindings: MenuBindings,
+    /// Wrap around at the ends of the menu instead of stopping.
+    pub wrap: bool,
+    /// How long a direction is held before it starts repeating.
+    pub repeat_delay: Duration,
+    /// Time between repeats once repeating.
+    pub repeat_rate: Duration,
+
+    disabled: HashSet<usize>,
+    /// The held direction, how long it's been held and when it next repeats.
+    held: Option<(Direction, Duration, Duration)>,
+}
+
+impl Default for MenuController {
+    fn default() -> Self {
+        Self {
+            bindings: MenuBindings::default(),
+            wrap: true,
+            repeat_delay: Duration::from_millis(400),
+            repeat_rate: Duration::from_millis(80),
+            disabled: HashSet::new(),
+            held: None,
+        }
+    }
+}
+
+impl MenuController {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_bindings(mut self, bindings: MenuBindings) -> Self {
+        self.bindings = bindings;
+        self
+    }
+
+    #[inline]
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    #[inline]
+    pub fn with_repeat(mut self, delay: Duration, rate: Duration) -> Self {
+        self.repeat_delay = delay;
+        self.repeat_rate = rate;
+        self
+    }
+
+    /// Disabled options are skipped over and can't be confirmed.
+    #[inline]
+    pub fn set_disabled(&mut self, option: usize, disabled: bool) {
+        match disabled {
+            true => self.disabled.insert(option),
+            false => self.disabled.remove(&option),
+        };
+    }
+
+    #[inline]
+    pub fn is_disabled(&self, option: usize) -> bool {
+        self.disabled.contains(&option)
+    }
+
+    /// Whether up or down is held, and may repeat on a later update.
+    #[inline]
+    pub fn is_held(&self) -> bool {
+        self.held.is_some()
+    }
+
+    /// Handle this tick's input. Held directions are timed with the unscaled frame
+    /// delta, so menus keep working while the game is slowed or paused.
+    pub fn update(
+        &mut self,
+        keys: &Input<KeyCode>,
+        time: &Time,
+        ui: &mut Ui3d,
+    ) -> Option<MenuEvent> {
+        self.update_with_delta(keys, *time.delta(), ui)
+    }
+
+    /// Same as `update`, with the time since the last update given directly.
+    pub fn update_with_delta(
+        &mut self,
+        keys: &Input<KeyCode>,
+        delta: Duration,
+        ui: &mut Ui3d,
+    ) -> Option<MenuEvent> {
+        let any = |bindings: &[KeyCode], check: fn(&Input<KeyCode>, KeyCode) -> bool| {
+            bindings.iter().any(|key| check(keys, *key))
+        };
+
+        if any(&self.bindings.cancel, Input::just_pressed) {
+            self.held = None;
+            return Some(MenuEvent::Cancelled);
+        }
+
+        let start = ui.selected as usize;
+
+        // A disabled selection moves to the next enabled option
+        if self.is_disabled(start) {
+            if let Some(next) = self.step(start, Direction::Down, ui.options.len(), true) {
+                ui.selected = next as u8;
+                return Some(MenuEvent::SelectionChanged(next));
+            }
+        }
+
+        if any(&self.bindings.confirm, Input::just_pressed) {
+            self.held = None;
+
+            return match start < ui.options.len() && !self.is_disabled(start) {
+                true => Some(MenuEvent::Confirmed(start)),
+                false => None,
+            };
+        }
+
+        let direction = match (
+            any(&self.bindings.up, Input::pressed),
+            any(&self.bindings.down, Input::pressed),
+        ) {
+            (true, false) => Some(Direction::Up),
+            (false, true) => Some(Direction::Down),
+            _ => None,
+        };
+
+        let steps = match (direction, self.held) {
+            (None, _) => {
+                self.held = None;
+                0
+            }
+
+            (Some(direction), Some((held, held_for, mut next_repeat))) if held == direction => {
+                let held_for = held_for + delta;
+                let mut steps = 0;
+
+                // Bounded so a long stall doesn't scroll through the menu many times
+                while held_for >= next_repeat && steps < ui.options.len() {
+                    steps += 1;
+                    next_repeat += self.repeat_rate.max(Duration::from_millis(1));
+                }
+
+                if held_for >= next_repeat {
+                    next_repeat = held_for + self.repeat_rate;
+                }
+
+                self.held = Some((direction, held_for, next_repeat));
+                steps
+            }
+
+            // Newly pressed, or switched direction
+            (Some(direction), _) => {
+                self.held = Some((direction, Duration::ZERO, self.repeat_delay));
+                1
+            }
+        };
+
+        let direction = direction?;
+        let mut selected = start;
+
+        for _ in 0..steps {
+            match self.step(selected, direction, ui.options.len(), self.wrap) {
+                Some(next) => selected = next,
+                None => break,
+            }
+        }
+
+        match selected != start {
+            true => {
+                ui.selected = selected as u8;
+                Some(MenuEvent::SelectionChanged(selected))
+            }
+            false => None,
+        }
+    }
+
+    /// The next enabled option in `direction`. `None` if there isn't one.
+    fn step(&self, from: usize, direction: Direction, count: usize, wrap: bool) -> Option<usize> {
+        (1..count).find_map(|offset| {
+            let index = match (direction, wrap) {
+                (Direction::Up, true) => (from + count - offset) % count,
+                (Direction::Down, true) => (from + offset) % count,
+                (Direction::Up, false) => from.checked_sub(offset)?,
+                (Direction::Down, false) => Some(from + offset).filter(|index| *index < count)?,
+            };
+
+            match self.is_disabled(index) {
+                true => None,
+                false => Some(index),
+            }
+        })
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use roots_common::input::{process_inputs, reset_input};
+
+    use super::*;
+
+    fn ui(options: usize) -> Ui3d {
+        Ui3d {
+            options: (0..options)
+                .map(|index| format!("Option {}", index))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Run one update `millis` after the last, then end the frame.
+    fn frame(
+        controller: &mut MenuController,
+        keys: &mut Input<KeyCode>,
+        millis: u64,
+        ui: &mut Ui3d,
+    ) -> Option<MenuEvent> {
+        let event = controller.update_with_delta(keys, Duration::from_millis(millis), ui);
+        reset_input(keys);
+        event
+    }
+
+    #[test]
+    fn steps_and_wraps() {
+        let mut controller = MenuController::new();
+        let mut keys = Input::new();
+        let mut ui = ui(4);
+
+        process_inputs(&mut keys, KeyCode::ArrowDown, true);
+        assert_eq!(
+            frame(&mut controller, &mut keys, 16, &mut ui),
+            Some(MenuEvent::SelectionChanged(1))
+        );
+        assert_eq!(frame(&mut controller, &mut keys, 16, &mut ui), None);
+
+        process_inputs(&mut keys, KeyCode::ArrowDown, false);
+        assert_eq!(frame(&mut controller, &mut keys, 16, &mut ui), None);
+        assert!(!controller.is_held());
+
+        process_inputs(&mut keys, KeyCode::ArrowUp, true);
+        frame(&mut controller, &mut keys, 16, &mut ui);
+        process_inputs(&mut keys, KeyCode::ArrowUp, false);
+        frame(&mut controller, &mut keys, 16, &mut ui);
+
+        process_inputs(&mut keys, KeyCode::ArrowUp, true);
+        assert_eq!(
+            frame(&mut controller, &mut keys, 16, &mut ui),
+            Some(MenuEvent::SelectionChanged(3))
+        );
+        assert_eq!(ui.selected, 3);
+
+        // Without wrapping the selection stops at the end
+        let mut controller = MenuController::new().with_wrap(false);
+        let mut keys = Input::new();
+        ui.selected = 0;
+
+        process_inputs(&mut keys, KeyCode::ArrowUp, true);
+        assert_eq!(frame(&mut controller, &mut keys, 16, &mut ui), None);
+        assert_eq!(ui.selected, 0);
+    }
+
+    #[test]
+    fn held_direction_repeats() {
+        let mut controller = MenuController::new()
+            .with_wrap(false)
+            .with_repeat(Duration::from_millis(400), Duration::from_millis(100));
+        let mut keys = Input::new();
+        let mut ui = ui(8);
+
+        process_inputs(&mut keys, KeyCode::ArrowDown, true);
+        let events = [0, 150, 150, 100, 100, 250, 40]
+            .into_iter()
+            .map(|millis| frame(&mut controller, &mut keys, millis, &mut ui))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                Some(MenuEvent::SelectionChanged(1)),
+                None,
+                None,
+                // Held for 400ms, then every 100ms
+                Some(MenuEvent::SelectionChanged(2)),
+                Some(MenuEvent::SelectionChanged(3)),
+                // A long frame catches up on both repeats it covered
+                Some(MenuEvent::SelectionChanged(5)),
+                None,
+            ]
+        );
+
+        // A stall stops at the end of the menu rather than overshooting
+        assert_eq!(
+            frame(&mut controller, &mut keys, 5000, &mut ui),
+            Some(MenuEvent::SelectionChanged(7))
+        );
+
+        process_inputs(&mut keys, KeyCode::ArrowDown, false);
+        assert_eq!(frame(&mut controller, &mut keys, 100, &mut ui), None);
+        assert!(!controller.is_held());
+        assert_eq!(ui.selected, 7);
+    }
+
+    #[test]
+    fn switching_direction_restarts_the_delay() {
+        let mut controller = MenuController::new()
+            .with_repeat(Duration::from_millis(400), Duration::from_millis(100));
+        let mut keys = Input::new();
+        let mut ui = ui(8);
+        ui.selected = 4;
+
+        process_inputs(&mut keys, KeyCode::ArrowDown, true);
+        frame(&mut controller, &mut keys, 0, &mut ui);
+        frame(&mut controller, &mut keys, 300, &mut ui);
+
+        process_inputs(&mut keys, KeyCode::ArrowDown, false);
+        process_inputs(&mut keys, KeyCode::ArrowUp, true);
+        assert_eq!(
+            frame(&mut controller, &mut keys, 16, &mut ui),
+            Some(MenuEvent::SelectionChanged(4))
+        );
+        assert_eq!(frame(&mut controller, &mut keys, 300, &mut ui), None);
+        assert_eq!(ui.selected, 4);
+    }
+
+    #[test]
+    fn disabled_options_are_skipped() {
+        let mut controller = MenuController::new();
+        let mut keys = Input::new();
+        let mut ui = ui(4);
+        controller.set_disabled(1, true);
+
+        process_inputs(&mut keys, KeyCode::ArrowDown, true);
+        assert_eq!(
+            frame(&mut controller, &mut keys, 16, &mut ui),
+            Some(MenuEvent::SelectionChanged(2))
+        );
+        process_inputs(&mut keys, KeyCode::ArrowDown, false);
+
+        process_inputs(&mut keys, KeyCode::Enter, true);
+        assert_eq!(
+            frame(&mut controller, &mut keys, 16, &mut ui),
+            Some(MenuEvent::Confirmed(2))
+        );
+        process_inputs(&mut keys, KeyCode::Enter, false);
+
+        // A selection set onto a disabled option moves off it
+        ui.selected = 1;
+        assert_eq!(
+            frame(&mut controller, &mut keys, 16, &mut ui),
+            Some(MenuEvent::SelectionChanged(2))
+        );
+    }
+
+    #[test]
+    fn cancel_ends_a_hold() {
+        let mut controller = MenuController::new();
+        let mut keys = Input::new();
+        let mut ui = ui(4);
+
+        process_inputs(&mut keys, KeyCode::ArrowDown, true);
+        frame(&mut controller, &mut keys, 16, &mut ui);
+        assert!(controller.is_held());
+
+        process_inputs(&mut keys, KeyCode::Escape, true);
+        assert_eq!(
+            frame(&mut controller, &mut keys, 16, &mut ui),
+            Some(MenuEvent::Cancelled)
+        );
+        assert!(!controller.is_held());
+        assert_eq!(ui.selected, 1);
+    }
+}
@@ -0,0 +1,189 @@
+//====================================================================
+
+use roots_common::coords::WindowPx;
+use roots_renderer::camera::OrthographicCamera;
+use roots_runner::prelude::MouseButton;
+
+use crate::State;
+
+//====================================================================
+
+/// 2D map and editor navigation for entities with an `OrthographicCamera`. Dragging
+/// with `pan_button` pans so the content stays under the cursor, and scrolling zooms
+/// toward the cursor. Updated by `process_pan_zoom`.
+#[derive(Debug, Clone)]
+pub struct PanZoomController {
+    pub pan_button: MouseButton,
+    /// How much one step of the scroll wheel zooms by.
+    pub zoom_speed: f32,
+    /// Zoom limits in screen pixels per world unit.
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// Rect, as min and max corners, that the view can't leave. In the same space as
+    /// the camera's extents, which is world space while its transform is at the origin.
+    pub bounds: Option<(glam::Vec2, glam::Vec2)>,
+
+    last_cursor: Option<WindowPx>,
+}
+
+impl Default for PanZoomController {
+    fn default() -> Self {
+        Self {
+            pan_button: MouseButton::Middle,
+            zoom_speed: 0.1,
+            min_zoom: 0.1,
+            max_zoom: 10.,
+            bounds: None,
+            last_cursor: None,
+        }
+    }
+}
+
+impl PanZoomController {
+    #[inline]
+    pub fn with_zoom_limits(mut self, min_zoom: f32, max_zoom: f32) -> Self {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    #[inline]
+    pub fn with_bounds(mut self, min: glam::Vec2, max: glam::Vec2) -> Self {
+        self.bounds = Some((min, max));
+        self
+    }
+
+    /// Whether a pan drag is in progress.
+    #[inline]
+    pub fn is_panning(&self) -> bool {
+        self.last_cursor.is_some()
+    }
+
+    /// Pan and zoom `camera` from this frame's mouse input.
+    pub fn update(&mut self, state: &State, camera: &mut OrthographicCamera) {
+        let size = state.size();
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        let cursor = state.mouse_input.position();
+
+        // Panning follows the cursor rather than raw mouse motion, so the content
+        // under it stays put regardless of pointer acceleration
+        self.last_cursor = match state.mouse_buttons.pressed(self.pan_button) {
+            true => {
+                if let Some(last) = self.last_cursor {
+                    camera.pan_by_pixels(cursor.0 - last.0, size);
+                }
+                Some(cursor)
+            }
+            false => None,
+        };
+
+        let scroll = state.mouse_input.scroll().y;
+        if scroll != 0. {
+            let zoom = camera.pixels_per_unit(size);
+            let target =
+                (zoom * (1. + self.zoom_speed).powf(scroll)).clamp(self.min_zoom, self.max_zoom);
+
+            camera.zoom_by(target / zoom, cursor, size);
+        }
+
+        if let Some((min, max)) = self.bounds {
+            clamp_to_bounds(camera, min, max);
+        }
+    }
+}
+
+/// Move the view back inside `min` and `max`, centering it on any axis where it's
+/// larger than the bounds.
+fn clamp_to_bounds(camera: &mut OrthographicCamera, min: glam::Vec2, max: glam::Vec2) {
+    let half = camera.size() / 2.;
+    let center = camera.center();
+
+    let clamp_axis = |center: f32, half: f32, min: f32, max: f32| match half * 2. >= max - min {
+        true => (min + max) / 2.,
+        false => center.clamp(min + half, max - half),
+    };
+
+    let target = glam::vec2(
+        clamp_axis(center.x, half.x, min.x, max.x),
+        clamp_axis(center.y, half.y, min.y, max.y),
+    );
+
+    camera.translate(target - center);
+}
+
+//====================================================================
+
+/// Update every `PanZoomController` and its `OrthographicCamera`.
+pub fn process_pan_zoom(state: &mut State) {
+    let mut query = state
+        .world
+        .query::<(&mut PanZoomController, &mut OrthographicCamera)>();
+
+    query.iter().for_each(|(_, (controller, camera))| {
+        controller.update(state, camera);
+    });
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use roots_common::input::{process_mouse_position, process_mouse_scroll, reset_mouse_input};
+
+    use super::*;
+    use crate::test_utils;
+
+    const ANCHOR: (f64, f64) = (150., 30.);
+
+    fn extents(camera: &OrthographicCamera) -> [f32; 4] {
+        [camera.left, camera.right, camera.bottom, camera.top]
+    }
+
+    fn assert_near(a: [f32; 4], b: [f32; 4]) {
+        a.iter().zip(b).for_each(|(a, b)| {
+            assert!((a - b).abs() < 1e-3, "{a:?} != {b:?}");
+        });
+    }
+
+    fn scroll(state: &mut State, steps: f32) {
+        reset_mouse_input(&mut state.mouse_input);
+        process_mouse_position(&mut state.mouse_input, ANCHOR);
+        process_mouse_scroll(&mut state.mouse_input, (0., steps));
+        process_pan_zoom(state);
+    }
+
+    fn camera(state: &State, entity: hecs::Entity) -> OrthographicCamera {
+        let camera = state.world.get::<&OrthographicCamera>(entity).unwrap();
+        OrthographicCamera::clone(&camera)
+    }
+
+    #[test]
+    fn zoom_in_then_out_restores_extents() {
+        let Some(mut state) = test_utils::state(200, 100) else {
+            return;
+        };
+
+        let entity = state.world.spawn((
+            PanZoomController::default(),
+            OrthographicCamera::new_sized(200., 100.),
+        ));
+        let original = camera(&state, entity);
+        let size = state.size();
+        let anchor = WindowPx::new(ANCHOR.0 as f32, ANCHOR.1 as f32);
+        let under_anchor = original.screen_to_view(anchor, size).0;
+
+        scroll(&mut state, 3.);
+        let zoomed = camera(&state, entity);
+        assert!(zoomed.size().x < original.size().x);
+        assert!(zoomed
+            .screen_to_view(anchor, size)
+            .0
+            .abs_diff_eq(under_anchor, 1e-3));
+
+        scroll(&mut state, -3.);
+        assert_near(extents(&camera(&state, entity)), extents(&original));
+    }
+}
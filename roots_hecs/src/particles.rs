@@ -0,0 +1,577 @@
+//====================================================================
+
+use std::collections::VecDeque;
+
+use hecs::World;
+use roots_common::{rng::Rng, spatial::GlobalTransform};
+use roots_pipelines::{
+    gpu_particles::{GpuEmitterUniform, GpuParticleRenderer},
+    manager::{RenderContext, RenderPipeline},
+    texture2d_renderer::{Texture2dRenderer, TextureData},
+};
+use roots_renderer::{texture::LoadedTexture, RenderPass};
+
+use crate::{renderer::pipelines::Pipeline, RendererState};
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emission {
+    /// Spawn `ParticleEmitter::spawn_rate` particles per second.
+    Continuous,
+    /// Spawn `count` particles at once, repeating every `interval` seconds if provided.
+    Burst { count: u32, interval: Option<f32> },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    pos: glam::Vec3,
+    velocity: glam::Vec3,
+    age: f32,
+}
+
+/// Simulation parameters gathered since the last upload to the `GpuParticleRenderer`.
+#[derive(Debug, Clone, Copy, Default)]
+struct GpuFrame {
+    pos: glam::Vec3,
+    delta: f32,
+    spawn_start: u32,
+    spawn_count: u32,
+    seed: u32,
+    reset: bool,
+}
+
+/// Particle emitter. Particles are spawned at the entity's `GlobalTransform` and
+/// rendered additively by the `ParticleRenderer`, or by the `GpuParticleRenderer`
+/// when simulated on the gpu.
+pub struct ParticleEmitter {
+    pub emission: Emission,
+    pub spawn_rate: f32,
+    pub max_particles: usize,
+    pub lifetime: f32,
+
+    pub velocity: glam::Vec3,
+    /// Random offset (per axis) applied to each particle's initial velocity.
+    pub velocity_variance: glam::Vec3,
+    pub acceleration: glam::Vec3,
+
+    pub start_color: glam::Vec4,
+    pub end_color: glam::Vec4,
+    pub start_size: glam::Vec2,
+    pub end_size: glam::Vec2,
+
+    pub texture: LoadedTexture,
+    pub active: bool,
+    /// Simulate with a compute shader where the device supports it, falling back to
+    /// the cpu otherwise. Gpu particles can't be read back, so keep this off for
+    /// emitters that gameplay code needs the particles of.
+    pub gpu: bool,
+
+    particles: Vec<Particle>,
+    spawn_timer: f32,
+    bursts_fired: u32,
+
+    on_gpu: bool,
+    gpu_frame: GpuFrame,
+    gpu_cursor: u32,
+    /// Age and size of each spawn still alive on the gpu, oldest first.
+    gpu_spawns: VecDeque<(f32, u32)>,
+}
+
+impl ParticleEmitter {
+    pub fn new(texture: LoadedTexture) -> Self {
+        Self {
+            emission: Emission::Continuous,
+            spawn_rate: 20.,
+            max_particles: 1000,
+            lifetime: 1.,
+            velocity: glam::Vec3::Y,
+            velocity_variance: glam::Vec3::splat(0.5),
+            acceleration: glam::Vec3::ZERO,
+            start_color: glam::Vec4::ONE,
+            end_color: glam::Vec4::new(1., 1., 1., 0.),
+            start_size: glam::Vec2::splat(0.2),
+            end_size: glam::Vec2::splat(0.05),
+            texture,
+            active: true,
+            gpu: false,
+            particles: Vec::new(),
+            spawn_timer: 0.,
+            bursts_fired: 0,
+            on_gpu: false,
+            gpu_frame: GpuFrame::default(),
+            gpu_cursor: 0,
+            gpu_spawns: VecDeque::new(),
+        }
+    }
+
+    #[inline]
+    pub fn with_emission(mut self, emission: Emission) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    #[inline]
+    pub fn with_lifetime(mut self, lifetime: f32) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    #[inline]
+    pub fn with_velocity(mut self, velocity: glam::Vec3, variance: glam::Vec3) -> Self {
+        self.velocity = velocity;
+        self.velocity_variance = variance;
+        self
+    }
+
+    #[inline]
+    pub fn with_acceleration(mut self, acceleration: glam::Vec3) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    #[inline]
+    pub fn with_color(mut self, start: glam::Vec4, end: glam::Vec4) -> Self {
+        self.start_color = start;
+        self.end_color = end;
+        self
+    }
+
+    #[inline]
+    pub fn with_size(mut self, start: glam::Vec2, end: glam::Vec2) -> Self {
+        self.start_size = start;
+        self.end_size = end;
+        self
+    }
+
+    #[inline]
+    pub fn with_max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    #[inline]
+    pub fn with_gpu(mut self, gpu: bool) -> Self {
+        self.gpu = gpu;
+        self
+    }
+
+    /// Whether the emitter is currently simulated on the gpu.
+    #[inline]
+    pub fn on_gpu(&self) -> bool {
+        self.on_gpu
+    }
+
+    /// Live particles. Estimated from the spawn history for gpu emitters.
+    #[inline]
+    pub fn particle_count(&self) -> usize {
+        match self.on_gpu {
+            true => self
+                .gpu_spawns
+                .iter()
+                .map(|(_, count)| *count as usize)
+                .sum::<usize>()
+                .min(self.max_particles),
+            false => self.particles.len(),
+        }
+    }
+
+    /// Positions of live particles. Always empty for gpu emitters.
+    #[inline]
+    pub fn particle_positions(&self) -> impl Iterator<Item = glam::Vec3> + '_ {
+        self.particles.iter().map(|particle| particle.pos)
+    }
+
+    /// Whether the emitter still has live particles or will spawn more.
+    pub fn is_animating(&self) -> bool {
+        let emitting = match self.emission {
+            Emission::Continuous => self.spawn_rate > 0.,
+            Emission::Burst { interval, .. } => self.bursts_fired == 0 || interval.is_some(),
+        };
+
+        self.particle_count() > 0 || (self.active && emitting)
+    }
+
+    /// Restart burst emission and remove all live particles.
+    pub fn reset(&mut self) {
+        self.particles.clear();
+        self.spawn_timer = 0.;
+        self.bursts_fired = 0;
+
+        self.gpu_spawns.clear();
+        self.gpu_cursor = 0;
+        self.gpu_frame = GpuFrame {
+            reset: true,
+            ..Default::default()
+        };
+    }
+
+    /// Move the simulation to or from the gpu. Live particles don't carry over.
+    fn set_on_gpu(&mut self, on_gpu: bool) {
+        if self.on_gpu != on_gpu {
+            self.reset();
+            self.on_gpu = on_gpu;
+        }
+    }
+
+    fn spawn(&mut self, pos: glam::Vec3, count: u32, rng: &mut Rng) {
+        if self.on_gpu {
+            self.spawn_gpu(count, rng);
+            return;
+        }
+
+        let available = self.max_particles.saturating_sub(self.particles.len());
+
+        (0..(count as usize).min(available)).for_each(|_| {
+            let variance = glam::vec3(rng.gen_signed(), rng.gen_signed(), rng.gen_signed())
+                * self.velocity_variance;
+
+            self.particles.push(Particle {
+                pos,
+                velocity: self.velocity + variance,
+                age: 0.,
+            });
+        });
+    }
+
+    /// Queue `count` particles for the gpu to spawn. Unlike the cpu path, new
+    /// particles replace the oldest once `max_particles` are alive.
+    fn spawn_gpu(&mut self, count: u32, rng: &mut Rng) {
+        let capacity = self.gpu_capacity();
+        // Particles already queued this frame may exceed max_particles if it shrank
+        let count = count.min(capacity.saturating_sub(self.gpu_frame.spawn_count));
+
+        if count == 0 {
+            return;
+        }
+
+        // The cursor may be past the end if max_particles shrank
+        let start = self.gpu_cursor % capacity;
+
+        if self.gpu_frame.spawn_count == 0 {
+            self.gpu_frame.spawn_start = start;
+            self.gpu_frame.seed = rng.next_u32();
+        }
+
+        self.gpu_frame.spawn_count += count;
+        self.gpu_cursor = (start + count) % capacity;
+        self.gpu_spawns.push_back((0., count));
+    }
+
+    /// Ring size of the gpu particle buffer.
+    #[inline]
+    fn gpu_capacity(&self) -> u32 {
+        u32::try_from(self.max_particles).unwrap_or(u32::MAX)
+    }
+
+    /// Step the simulation. Called by `process_particles` with the world's rng, so
+    /// the same seed and deltas always produce the same particles.
+    pub fn update(&mut self, pos: glam::Vec3, delta: f32, rng: &mut Rng) {
+        match self.on_gpu {
+            true => self.update_gpu(pos, delta),
+            false => self.update_cpu(delta),
+        }
+
+        if !self.active {
+            return;
+        }
+
+        match self.emission {
+            Emission::Continuous => {
+                self.spawn_timer += delta * self.spawn_rate;
+                let count = self.spawn_timer.floor();
+                self.spawn_timer -= count;
+                self.spawn(pos, count as u32, rng);
+            }
+
+            Emission::Burst { count, interval } => {
+                self.spawn_timer -= delta;
+
+                let fire = match (self.bursts_fired, interval) {
+                    (0, _) => true,
+                    (_, Some(_)) => self.spawn_timer <= 0.,
+                    (_, None) => false,
+                };
+
+                if fire {
+                    self.spawn(pos, count, rng);
+                    self.bursts_fired += 1;
+                    self.spawn_timer = interval.unwrap_or(0.);
+                }
+            }
+        }
+    }
+
+    /// Age particles and recycle dead ones.
+    fn update_cpu(&mut self, delta: f32) {
+        let mut index = 0;
+        while index < self.particles.len() {
+            let particle = &mut self.particles[index];
+            particle.age += delta;
+
+            if particle.age >= self.lifetime {
+                self.particles.swap_remove(index);
+                continue;
+            }
+
+            particle.velocity += self.acceleration * delta;
+            particle.pos += particle.velocity * delta;
+            index += 1;
+        }
+    }
+
+    /// Particles are stepped by the gpu, so only track when each spawn dies.
+    fn update_gpu(&mut self, pos: glam::Vec3, delta: f32) {
+        self.gpu_frame.pos = pos;
+        self.gpu_frame.delta += delta;
+
+        self.gpu_spawns
+            .iter_mut()
+            .for_each(|(age, _)| *age += delta);
+
+        while let Some((age, _)) = self.gpu_spawns.front() {
+            match *age >= self.lifetime {
+                true => self.gpu_spawns.pop_front(),
+                false => break,
+            };
+        }
+    }
+
+    /// Take the parameters gathered since the last upload.
+    fn take_gpu_uniform(&mut self) -> GpuEmitterUniform {
+        let frame = std::mem::take(&mut self.gpu_frame);
+        let capacity = self.gpu_capacity();
+
+        GpuEmitterUniform {
+            position: frame.pos,
+            lifetime: self.lifetime,
+            velocity: self.velocity,
+            delta: frame.delta,
+            velocity_variance: self.velocity_variance,
+            // Clamped in case max_particles shrank after spawns were queued
+            spawn_start: frame.spawn_start.checked_rem(capacity).unwrap_or(0),
+            acceleration: self.acceleration,
+            spawn_count: frame.spawn_count.min(capacity),
+            start_color: self.start_color,
+            end_color: self.end_color,
+            start_size: self.start_size,
+            end_size: self.end_size,
+            capacity,
+            seed: frame.seed,
+            reset: frame.reset as u32,
+            pad: 0,
+        }
+    }
+}
+
+//====================================================================
+
+pub fn process_particles(state: &mut crate::State) {
+    let delta = state.time.delta_seconds();
+    let rng = &mut state.rng;
+    let mut animating = false;
+    // Gpu particles are simulated in world space, so can't follow a large world origin
+    let gpu_supported =
+        GpuParticleRenderer::is_supported(&state.renderer.device) && !state.renderer.large_world();
+
+    state
+        .world
+        .query_mut::<(&mut ParticleEmitter, &GlobalTransform)>()
+        .into_iter()
+        .for_each(|(_, (emitter, global))| {
+            emitter.set_on_gpu(emitter.gpu && gpu_supported);
+            emitter.update(global.translation(), delta, rng);
+            animating |= emitter.is_animating();
+        });
+
+    state.set_animation_active("particles", animating);
+}
+
+//====================================================================
+
+/// Renders all `ParticleEmitter`s simulated on the cpu using additive blending.
+pub struct ParticleRenderer(Texture2dRenderer);
+
+impl ParticleRenderer {
+    pub const BLEND: wgpu::BlendState = wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::SrcAlpha,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent::OVER,
+    };
+}
+
+impl RenderPipeline for ParticleRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        self.0.render(render_pass, context.camera);
+    }
+}
+
+impl Pipeline for ParticleRenderer {
+    #[inline]
+    fn new(state: &RendererState) -> Self {
+        Self(Texture2dRenderer::new_with_blend(
+            &state.device,
+            &state.config,
+            &state.shared,
+            true,
+            Self::BLEND,
+        ))
+    }
+
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        world
+            .query_mut::<&ParticleEmitter>()
+            .into_iter()
+            .filter(|(_, emitter)| !emitter.on_gpu)
+            .for_each(|(_, emitter)| {
+                emitter.particles.iter().for_each(|particle| {
+                    let t = (particle.age / emitter.lifetime).clamp(0., 1.);
+
+                    self.0.prep_texture(TextureData {
+                        texture: &emitter.texture,
+                        size: emitter.start_size.lerp(emitter.end_size, t),
+                        pos: state.origin.relative_point(particle.pos),
+                        color: emitter.start_color.lerp(emitter.end_color, t),
+                    })
+                })
+            });
+
+        self.0.finish_prep(&state.device, &state.queue);
+    }
+}
+
+//====================================================================
+
+// Simulates and renders all `ParticleEmitter`s running on the gpu, with the same
+// blending as the `ParticleRenderer`. Add both pipelines so emitters still render
+// when the device falls back to the cpu.
+impl Pipeline for GpuParticleRenderer {
+    #[inline]
+    fn new(state: &RendererState) -> Self {
+        Self::new(
+            &state.device,
+            &state.config,
+            &state.shared,
+            ParticleRenderer::BLEND,
+        )
+    }
+
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        world
+            .query_mut::<&mut ParticleEmitter>()
+            .into_iter()
+            .filter(|(_, emitter)| emitter.on_gpu)
+            .for_each(|(entity, emitter)| {
+                let uniform = emitter.take_gpu_uniform();
+
+                self.prep_emitter(
+                    &state.device,
+                    &state.queue,
+                    entity.to_bits().get(),
+                    &emitter.texture,
+                    uniform,
+                );
+            });
+
+        self.finish_prep();
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use roots_renderer::shared::SharedRenderResources;
+
+    use super::*;
+    use crate::test_utils;
+
+    fn texture() -> Option<LoadedTexture> {
+        let (device, queue) = test_utils::device()?;
+        let shared = SharedRenderResources::new(&device);
+        Some(LoadedTexture::load_blank(&device, &queue, &shared))
+    }
+
+    fn gpu_emitter() -> Option<ParticleEmitter> {
+        let mut emitter = ParticleEmitter::new(texture()?).with_gpu(true);
+        emitter.set_on_gpu(true);
+        Some(emitter)
+    }
+
+    /// Positions after `steps` updates, as raw bits so runs compare byte for byte.
+    fn simulate(texture: &LoadedTexture, seed: u64, steps: u32) -> Vec<[u32; 3]> {
+        let mut emitter = ParticleEmitter::new(texture.clone())
+            .with_acceleration(glam::Vec3::NEG_Y)
+            .with_lifetime(2.);
+        let mut rng = Rng::new(seed);
+
+        (0..steps).for_each(|step| {
+            let pos = glam::Vec3::new(step as f32 * 0.01, 0., 0.);
+            emitter.update(pos, 1. / 60., &mut rng);
+        });
+
+        emitter
+            .particle_positions()
+            .map(|pos| pos.to_array().map(f32::to_bits))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_identical_particles() {
+        let Some(texture) = texture() else {
+            return;
+        };
+
+        let positions = simulate(&texture, 17, 1000);
+        assert!(!positions.is_empty());
+        assert_eq!(positions, simulate(&texture, 17, 1000));
+        assert_ne!(positions, simulate(&texture, 18, 1000));
+    }
+
+    #[test]
+    fn shrinking_capacity_clamps_gpu_spawns() {
+        let Some(mut emitter) = gpu_emitter() else {
+            return;
+        };
+        let mut rng = Rng::new(0);
+
+        emitter.max_particles = 100;
+        emitter.spawn_gpu(30, &mut rng);
+        emitter.spawn_gpu(50, &mut rng);
+
+        // More particles are queued this frame than now fit
+        emitter.max_particles = 10;
+        emitter.spawn_gpu(5, &mut rng);
+
+        let uniform = emitter.take_gpu_uniform();
+        assert_eq!(uniform.capacity, 10);
+        assert_eq!(uniform.spawn_count, 10);
+        assert!(uniform.spawn_start < 10);
+        assert_eq!(emitter.particle_count(), 10);
+
+        // The cursor was left past the new end
+        emitter.spawn_gpu(5, &mut rng);
+
+        let uniform = emitter.take_gpu_uniform();
+        assert_eq!(uniform.spawn_count, 5);
+        assert!(uniform.spawn_start < 10);
+    }
+
+    #[test]
+    fn gpu_capacity_saturates() {
+        let Some(mut emitter) = gpu_emitter() else {
+            return;
+        };
+
+        emitter.max_particles = usize::MAX;
+        assert_eq!(emitter.take_gpu_uniform().capacity, u32::MAX);
+
+        emitter.max_particles = 0;
+        emitter.spawn_gpu(5, &mut Rng::new(0));
+        assert_eq!(emitter.take_gpu_uniform().spawn_count, 0);
+    }
+}
@@ -0,0 +1,137 @@
+//====================================================================
+
+use std::sync::Arc;
+
+use roots_common::{
+    curve::{Curve, LoopMode},
+    spatial::Transform,
+};
+
+use crate::State;
+
+//====================================================================
+
+pub type SharedCurve = Arc<dyn Curve + Send + Sync>;
+
+/// Moves an entity's `Transform` along a curve at a constant speed.
+pub struct FollowPath {
+    pub curve: SharedCurve,
+    /// Units per second. Negative values travel the curve backwards.
+    pub speed: f32,
+    pub loop_mode: LoopMode,
+    /// Rotate the entity so its forward (+Z) faces the direction of travel.
+    pub align_to_tangent: bool,
+    pub paused: bool,
+
+    travelled: f32,
+}
+
+impl FollowPath {
+    pub fn new(curve: SharedCurve, speed: f32) -> Self {
+        Self {
+            curve,
+            speed,
+            loop_mode: LoopMode::Once,
+            align_to_tangent: false,
+            paused: false,
+            travelled: 0.,
+        }
+    }
+
+    #[inline]
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    #[inline]
+    pub fn with_align_to_tangent(mut self, align_to_tangent: bool) -> Self {
+        self.align_to_tangent = align_to_tangent;
+        self
+    }
+
+    /// Current distance along the curve.
+    #[inline]
+    pub fn distance(&self) -> f32 {
+        self.loop_mode
+            .distance_along(self.travelled, self.curve.arc_length())
+            .0
+    }
+
+    #[inline]
+    pub fn set_distance(&mut self, distance: f32) {
+        self.travelled = distance;
+    }
+
+    /// Whether a `LoopMode::Once` path has reached its end. Always false for repeating modes.
+    pub fn finished(&self) -> bool {
+        let length = self.curve.arc_length();
+
+        match self.loop_mode {
+            LoopMode::Once => match self.speed < 0. {
+                true => self.travelled <= 0.,
+                false => self.travelled >= length,
+            },
+            LoopMode::Loop | LoopMode::PingPong => false,
+        }
+    }
+}
+
+//====================================================================
+
+pub fn process_follow_path(state: &mut State) {
+    let delta = state.time.delta_seconds();
+    let mut animating = false;
+
+    state
+        .world
+        .query_mut::<(&mut FollowPath, &mut Transform)>()
+        .into_iter()
+        .for_each(|(_, (path, transform))| {
+            let length = path.curve.arc_length();
+            animating |= !path.paused && !path.finished();
+
+            if !path.paused {
+                path.travelled = path
+                    .loop_mode
+                    .wrap(path.travelled + path.speed * delta, length);
+
+                if path.loop_mode == LoopMode::Once {
+                    path.travelled = path.travelled.clamp(0., length);
+                }
+            }
+
+            let (distance, reversed) = path.loop_mode.distance_along(path.travelled, length);
+            let t = path.curve.t_at_distance(distance);
+
+            transform.translation = path.curve.sample(t);
+
+            if !path.align_to_tangent {
+                return;
+            }
+
+            let mut direction = path.curve.sample_derivative(t).normalize_or_zero();
+            if reversed ^ (path.speed < 0.) {
+                direction = -direction;
+            }
+
+            if direction != glam::Vec3::ZERO {
+                transform.rotation = face_direction(direction);
+            }
+        });
+
+    state.set_animation_active("follow_path", animating);
+}
+
+/// Rotation with +Z facing `forward`, keeping +Y as close to world up as possible.
+fn face_direction(forward: glam::Vec3) -> glam::Quat {
+    let right = glam::Vec3::Y
+        .cross(forward)
+        .try_normalize()
+        .unwrap_or_else(|| forward.any_orthogonal_vector());
+    let up = forward.cross(right);
+
+    glam::Quat::from_mat3(&glam::Mat3::from_cols(right, up, forward))
+}
+
+//====================================================================
@@ -0,0 +1,877 @@
+//====================================================================
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    path::PathBuf,
+    sync::{mpsc, Arc},
+};
+
+use hecs::{Entity, World};
+use roots_common::coords::WindowPx;
+use roots_pipelines::{
+    manager::{RenderContext, RenderPipeline},
+    texture2d_renderer::{Texture2dRenderer, TextureData},
+};
+use roots_renderer::{
+    texture::LoadedTexture,
+    uploads::{UploadContext, UploadTicket, Uploaded},
+    RenderPass,
+};
+use roots_text::{
+    shared::{color_from_vec4, FontSystem, TextResources},
+    text2d_renderer::{Text2d, Text2dRenderer},
+};
+use web_time::Instant;
+
+use crate::{
+    renderer::{pipelines::Pipeline, RendererState},
+    State,
+};
+
+//====================================================================
+
+/// Where the bytes of a preloaded asset come from.
+#[derive(Debug, Clone)]
+pub enum PreloadSource {
+    /// Read from disk. Always fails on wasm, where there is no file system.
+    Path(PathBuf),
+    Bytes(Arc<[u8]>),
+}
+
+impl PreloadSource {
+    fn read(&self) -> Result<Arc<[u8]>, String> {
+        match self {
+            PreloadSource::Path(path) => std::fs::read(path)
+                .map(Into::into)
+                .map_err(|e| format!("Unable to read '{}': {}", path.display(), e)),
+            PreloadSource::Bytes(bytes) => Ok(bytes.clone()),
+        }
+    }
+}
+
+impl From<PathBuf> for PreloadSource {
+    #[inline]
+    fn from(path: PathBuf) -> Self {
+        PreloadSource::Path(path)
+    }
+}
+
+impl From<&str> for PreloadSource {
+    #[inline]
+    fn from(path: &str) -> Self {
+        PreloadSource::Path(path.into())
+    }
+}
+
+impl From<Vec<u8>> for PreloadSource {
+    #[inline]
+    fn from(bytes: Vec<u8>) -> Self {
+        PreloadSource::Bytes(bytes.into())
+    }
+}
+
+impl From<&'static [u8]> for PreloadSource {
+    #[inline]
+    fn from(bytes: &'static [u8]) -> Self {
+        PreloadSource::Bytes(bytes.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreloadKind {
+    /// Any image format the image crate can decode.
+    Texture,
+    /// A texture that keeps only its smallest mips resident until they are needed.
+    /// See `LoadedTexture::load_streamed`.
+    StreamedTexture,
+    /// A gltf or glb scene. Requires the `gltf` feature.
+    Model,
+    /// Font data, added to a font system with `PreloadHandle::load_fonts`.
+    Font,
+}
+
+/// What happens when an entry can't be loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PreloadPolicy {
+    /// The preload fails, with the entry listed in its report.
+    #[default]
+    Required,
+    /// A warning is logged and loading continues without it.
+    Optional,
+}
+
+#[derive(Debug, Clone)]
+pub struct PreloadEntry {
+    /// Name the loaded asset is looked up by.
+    pub name: String,
+    pub kind: PreloadKind,
+    pub source: PreloadSource,
+    /// Entries with a higher priority are loaded first.
+    pub priority: i32,
+    pub policy: PreloadPolicy,
+}
+
+impl PreloadEntry {
+    pub fn new(
+        name: impl Into<String>,
+        kind: PreloadKind,
+        source: impl Into<PreloadSource>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            source: source.into(),
+            priority: 0,
+            policy: PreloadPolicy::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[inline]
+    pub fn with_policy(mut self, policy: PreloadPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    #[inline]
+    pub fn optional(self) -> Self {
+        self.with_policy(PreloadPolicy::Optional)
+    }
+}
+
+/// A list of assets to load before the app continues, such as everything the main
+/// menu needs. Started with `State::begin_preload`.
+#[derive(Debug, Clone, Default)]
+pub struct PreloadManifest {
+    entries: Vec<PreloadEntry>,
+}
+
+impl PreloadManifest {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_entry(mut self, entry: PreloadEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    #[inline]
+    pub fn with_texture(self, name: impl Into<String>, source: impl Into<PreloadSource>) -> Self {
+        self.with_entry(PreloadEntry::new(name, PreloadKind::Texture, source))
+    }
+
+    #[inline]
+    pub fn with_streamed_texture(
+        self,
+        name: impl Into<String>,
+        source: impl Into<PreloadSource>,
+    ) -> Self {
+        self.with_entry(PreloadEntry::new(
+            name,
+            PreloadKind::StreamedTexture,
+            source,
+        ))
+    }
+
+    #[inline]
+    pub fn with_model(self, name: impl Into<String>, source: impl Into<PreloadSource>) -> Self {
+        self.with_entry(PreloadEntry::new(name, PreloadKind::Model, source))
+    }
+
+    #[inline]
+    pub fn with_font(self, name: impl Into<String>, source: impl Into<PreloadSource>) -> Self {
+        self.with_entry(PreloadEntry::new(name, PreloadKind::Font, source))
+    }
+
+    #[inline]
+    pub fn push(&mut self, entry: PreloadEntry) {
+        self.entries.push(entry);
+    }
+
+    #[inline]
+    pub fn entries(&self) -> &[PreloadEntry] {
+        &self.entries
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+//====================================================================
+
+/// An entry that couldn't be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreloadFailure {
+    pub name: String,
+    pub kind: PreloadKind,
+    pub policy: PreloadPolicy,
+    pub reason: String,
+}
+
+/// Every entry that has failed so far.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PreloadReport {
+    pub failures: Vec<PreloadFailure>,
+}
+
+impl PreloadReport {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    #[inline]
+    pub fn required(&self) -> impl Iterator<Item = &PreloadFailure> {
+        self.failures
+            .iter()
+            .filter(|failure| failure.policy == PreloadPolicy::Required)
+    }
+
+    #[inline]
+    pub fn optional(&self) -> impl Iterator<Item = &PreloadFailure> {
+        self.failures
+            .iter()
+            .filter(|failure| failure.policy == PreloadPolicy::Optional)
+    }
+}
+
+impl fmt::Display for PreloadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.failures.iter().try_for_each(|failure| {
+            writeln!(
+                f,
+                "{:?} {:?} '{}': {}",
+                failure.policy, failure.kind, failure.name, failure.reason
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreloadStatus {
+    Loading,
+    /// Every entry finished and all required entries loaded.
+    Complete,
+    /// A required entry couldn't be loaded. The rest keep loading.
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub enum PreloadedAsset {
+    Texture(LoadedTexture),
+    #[cfg(feature = "gltf")]
+    Model(roots_renderer::gltf::GltfScene),
+    Font(Arc<[u8]>),
+}
+
+//====================================================================
+
+/// An entry partway through loading.
+enum Pending {
+    /// Decoded and waiting in the upload queue.
+    Texture(UploadTicket),
+    /// Built on the main thread, which owns the device.
+    Model(PreloadSource),
+    Font(Arc<[u8]>),
+}
+
+/// Reads and decodes entries in priority order, pushing textures to the upload queue.
+/// Runs on a worker thread on native and within the upload budget on wasm.
+struct PreloadWorker {
+    entries: VecDeque<(usize, PreloadEntry)>,
+    context: UploadContext,
+    sender: mpsc::Sender<(usize, Result<Pending, String>)>,
+}
+
+impl PreloadWorker {
+    /// Start the next entry. Returns false once there are none left.
+    fn step(&mut self) -> bool {
+        let Some((index, entry)) = self.entries.pop_front() else {
+            return false;
+        };
+
+        let result = match entry.kind {
+            PreloadKind::Texture | PreloadKind::StreamedTexture => {
+                entry.source.read().and_then(|bytes| {
+                    let image = image::load_from_memory(&bytes)
+                        .map_err(|e| format!("Unable to decode texture: {}", e))?;
+
+                    Ok(Pending::Texture(match entry.kind {
+                        PreloadKind::StreamedTexture => self
+                            .context
+                            .upload_streamed_texture(&image, Some(entry.name)),
+                        _ => self.context.upload_texture(image, Some(entry.name)),
+                    }))
+                })
+            }
+            PreloadKind::Model => Ok(Pending::Model(entry.source)),
+            PreloadKind::Font => entry.source.read().map(Pending::Font),
+        };
+
+        self.sender.send((index, result)).is_ok()
+    }
+}
+
+//====================================================================
+
+/// Tracks a preload started with `State::begin_preload`. Call `update` every frame
+/// until `status` is no longer `Loading`.
+pub struct PreloadHandle {
+    entries: Vec<(String, PreloadKind, PreloadPolicy)>,
+    #[cfg(target_arch = "wasm32")]
+    worker: PreloadWorker,
+    receiver: mpsc::Receiver<(usize, Result<Pending, String>)>,
+    pending: Vec<(usize, Pending)>,
+
+    finished: usize,
+    assets: HashMap<String, PreloadedAsset>,
+    report: PreloadReport,
+}
+
+impl PreloadHandle {
+    fn new(manifest: PreloadManifest, context: UploadContext) -> Self {
+        log::debug!("Starting preload of {} entries", manifest.len());
+
+        let entries = manifest
+            .entries
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.kind, entry.policy))
+            .collect();
+
+        let mut queue = manifest.entries.into_iter().enumerate().collect::<Vec<_>>();
+        queue.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.priority));
+
+        let (sender, receiver) = mpsc::channel();
+
+        let worker = PreloadWorker {
+            entries: queue.into(),
+            context,
+            sender,
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || {
+            let mut worker = worker;
+            while worker.step() {}
+        });
+
+        Self {
+            entries,
+            #[cfg(target_arch = "wasm32")]
+            worker,
+            receiver,
+            pending: Vec::new(),
+            finished: 0,
+            assets: HashMap::new(),
+            report: PreloadReport::default(),
+        }
+    }
+
+    /// Collect finished entries and build models, within the upload budget.
+    pub fn update(&mut self, state: &State) {
+        let start = Instant::now();
+        let budget = state.renderer.uploads.budget();
+
+        #[cfg(target_arch = "wasm32")]
+        while self.worker.step() && start.elapsed() < budget {}
+
+        let received = self.receiver.try_iter().collect::<Vec<_>>();
+        received
+            .into_iter()
+            .for_each(|(index, result)| match result {
+                Ok(pending) => self.pending.push((index, pending)),
+                Err(reason) => self.fail(index, reason),
+            });
+
+        let mut built_model = false;
+
+        std::mem::take(&mut self.pending)
+            .into_iter()
+            .for_each(|(index, pending)| match pending {
+                Pending::Texture(ticket) => match ticket.take() {
+                    Some(Uploaded::Texture(texture)) => {
+                        self.finish(index, PreloadedAsset::Texture(texture))
+                    }
+                    Some(Uploaded::Mesh(_)) => unreachable!("Texture uploads create textures"),
+                    None => self.pending.push((index, Pending::Texture(ticket))),
+                },
+
+                // At least one model is built per update so loading always progresses
+                Pending::Model(source) => match built_model && start.elapsed() >= budget {
+                    true => self.pending.push((index, Pending::Model(source))),
+                    false => {
+                        built_model = true;
+
+                        match build_model(state, &source) {
+                            Ok(asset) => self.finish(index, asset),
+                            Err(reason) => self.fail(index, reason),
+                        }
+                    }
+                },
+
+                Pending::Font(bytes) => self.finish(index, PreloadedAsset::Font(bytes)),
+            });
+    }
+
+    /// Fraction of entries finished, whether they loaded or failed.
+    #[inline]
+    pub fn progress(&self) -> f32 {
+        match self.entries.is_empty() {
+            true => 1.,
+            false => self.finished as f32 / self.entries.len() as f32,
+        }
+    }
+
+    pub fn status(&self) -> PreloadStatus {
+        if self.report.required().next().is_some() {
+            return PreloadStatus::Failed;
+        }
+
+        match self.finished == self.entries.len() {
+            true => PreloadStatus::Complete,
+            false => PreloadStatus::Loading,
+        }
+    }
+
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.status() == PreloadStatus::Complete
+    }
+
+    #[inline]
+    pub fn is_failed(&self) -> bool {
+        self.status() == PreloadStatus::Failed
+    }
+
+    /// Entries that couldn't be loaded so far.
+    #[inline]
+    pub fn report(&self) -> &PreloadReport {
+        &self.report
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&PreloadedAsset> {
+        self.assets.get(name)
+    }
+
+    #[inline]
+    pub fn texture(&self, name: &str) -> Option<&LoadedTexture> {
+        match self.assets.get(name) {
+            Some(PreloadedAsset::Texture(texture)) => Some(texture),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "gltf")]
+    #[inline]
+    pub fn model(&self, name: &str) -> Option<&roots_renderer::gltf::GltfScene> {
+        match self.assets.get(name) {
+            Some(PreloadedAsset::Model(scene)) => Some(scene),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn font(&self, name: &str) -> Option<&Arc<[u8]>> {
+        match self.assets.get(name) {
+            Some(PreloadedAsset::Font(bytes)) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Add every preloaded font to `font_system`.
+    pub fn load_fonts(&self, font_system: &mut FontSystem) {
+        self.assets.values().for_each(|asset| {
+            if let PreloadedAsset::Font(bytes) = asset {
+                font_system.db_mut().load_font_data(bytes.to_vec());
+            }
+        });
+    }
+
+    fn finish(&mut self, index: usize, asset: PreloadedAsset) {
+        let (name, _, _) = &self.entries[index];
+        log::trace!("Preloaded '{}'", name);
+
+        self.assets.insert(name.clone(), asset);
+        self.finished += 1;
+    }
+
+    fn fail(&mut self, index: usize, reason: String) {
+        let (name, kind, policy) = self.entries[index].clone();
+
+        match policy {
+            PreloadPolicy::Required => {
+                log::error!("Failed to preload required '{}': {}", name, reason)
+            }
+            PreloadPolicy::Optional => {
+                log::warn!("Skipping optional preload '{}': {}", name, reason)
+            }
+        }
+
+        self.report.failures.push(PreloadFailure {
+            name,
+            kind,
+            policy,
+            reason,
+        });
+        self.finished += 1;
+    }
+}
+
+#[cfg(feature = "gltf")]
+fn build_model(state: &State, source: &PreloadSource) -> Result<PreloadedAsset, String> {
+    let renderer = &state.renderer;
+
+    // Loading from a path resolves external buffers and images next to the file
+    let scene = match source {
+        PreloadSource::Path(path) => {
+            roots_renderer::gltf::load(&renderer.device, &renderer.queue, &renderer.shared, path)
+        }
+        PreloadSource::Bytes(bytes) => roots_renderer::gltf::load_from_slice(
+            &renderer.device,
+            &renderer.queue,
+            &renderer.shared,
+            bytes,
+        ),
+    };
+
+    scene.map(PreloadedAsset::Model).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "gltf"))]
+fn build_model(_state: &State, _source: &PreloadSource) -> Result<PreloadedAsset, String> {
+    Err("Models require the gltf feature".into())
+}
+
+impl State {
+    /// Start loading every entry in `manifest`, highest priority first. Textures go
+    /// through the current upload strategy, so deferred uploads are spread over frames
+    /// within the upload budget.
+    #[inline]
+    pub fn begin_preload(&self, manifest: PreloadManifest) -> PreloadHandle {
+        PreloadHandle::new(manifest, self.renderer.upload_context())
+    }
+}
+
+//====================================================================
+
+/// A progress bar with a label above it, centred in the window. Drawn in screen
+/// space by the `LoadingBarPipeline`, so no camera is needed.
+#[derive(Debug, Clone)]
+pub struct LoadingBar {
+    /// From 0 to 1.
+    pub progress: f32,
+    pub label: String,
+    /// Size of the bar in pixels.
+    pub size: glam::Vec2,
+    /// The theme's primary color if `None`.
+    pub color: Option<glam::Vec4>,
+    /// The theme's surface color if `None`.
+    pub background: Option<glam::Vec4>,
+    pub font_size: f32,
+}
+
+impl Default for LoadingBar {
+    fn default() -> Self {
+        Self {
+            progress: 0.,
+            label: "Loading".into(),
+            size: glam::vec2(320., 12.),
+            color: None,
+            background: None,
+            font_size: 20.,
+        }
+    }
+}
+
+impl LoadingBar {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    #[inline]
+    pub fn with_size(mut self, size: glam::Vec2) -> Self {
+        self.size = size;
+        self
+    }
+
+    #[inline]
+    pub fn with_color(mut self, color: glam::Vec4) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+/// Renders every `LoadingBar`.
+pub struct LoadingBarPipeline {
+    bars: Texture2dRenderer,
+    blank: LoadedTexture,
+    renderer: Text2dRenderer<Entity>,
+    text: TextResources,
+}
+
+impl RenderPipeline for LoadingBarPipeline {
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        self.bars.render(render_pass, context.screen_camera);
+        self.renderer
+            .render(render_pass, &self.text.text_atlas, context.screen_camera);
+        self.text.text_atlas.post_render_trim();
+    }
+
+    #[inline]
+    fn needs_depth(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn stereo(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn screen_space(&self) -> bool {
+        true
+    }
+}
+
+impl Pipeline for LoadingBarPipeline {
+    fn new(state: &RendererState) -> Self {
+        let bars =
+            Texture2dRenderer::new_with_depth(&state.device, &state.config, &state.shared, false);
+        let blank = LoadedTexture::load_blank(&state.device, &state.queue, &state.shared);
+
+        let text = TextResources::new(&state.device, &state.shared);
+        let renderer = Text2dRenderer::new(&state.device, &state.config, &state.shared);
+
+        Self {
+            bars,
+            blank,
+            renderer,
+            text,
+        }
+    }
+
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        let target = roots_common::Size::new(state.config.width, state.config.height);
+        let centre = glam::vec2(target.width as f32, target.height as f32) / 2.;
+        let theme = state.theme();
+
+        world
+            .query_mut::<&LoadingBar>()
+            .into_iter()
+            .for_each(|(entity, bar)| {
+                let progress = bar.progress.clamp(0., 1.);
+                let fill = bar.size.x * progress;
+
+                // Drawn in submission order without depth, so the fill covers the background
+                self.bars.prep_texture(TextureData {
+                    texture: &self.blank,
+                    size: bar.size,
+                    pos: centre.extend(1.),
+                    color: bar.background.unwrap_or(theme.surface),
+                });
+
+                self.bars.prep_texture(TextureData {
+                    texture: &self.blank,
+                    size: glam::vec2(fill, bar.size.y),
+                    pos: glam::vec3(centre.x - (bar.size.x - fill) / 2., centre.y, 1.),
+                    color: bar.color.unwrap_or(theme.primary),
+                });
+
+                self.renderer.prep_text(
+                    &state.device,
+                    &state.queue,
+                    &mut self.text,
+                    entity,
+                    &Text2d {
+                        text: format!("{} {:.0}%", bar.label, progress * 100.),
+                        position: WindowPx::new(centre.x, centre.y - bar.size.y / 2. - 8.),
+                        anchor: glam::vec2(0.5, 1.),
+                        font_size: bar.font_size,
+                        color: color_from_vec4(theme.text),
+                    },
+                    target,
+                );
+            });
+
+        self.bars.finish_prep(&state.device, &state.queue);
+        self.renderer.finish_prep();
+    }
+}
+
+//--------------------------------------------------
+
+/// Marker for the bar spawned by `State::show_loading_bar`.
+struct BuiltinLoadingBar;
+
+impl State {
+    /// Show a `LoadingBar` at `progress`, or hide it with `None`. The
+    /// `LoadingBarPipeline` is added the first time a bar is shown.
+    pub fn show_loading_bar(&mut self, progress: Option<f32>) {
+        let bar = self
+            .world
+            .query_mut::<()>()
+            .with::<&BuiltinLoadingBar>()
+            .into_iter()
+            .next()
+            .map(|(entity, _)| entity);
+
+        match (progress, bar) {
+            (Some(progress), Some(entity)) => {
+                if let Ok(mut bar) = self.world.get::<&mut LoadingBar>(entity) {
+                    bar.progress = progress;
+                }
+            }
+
+            (Some(progress), None) => {
+                if self
+                    .renderer
+                    .with_managed_pipeline::<LoadingBarPipeline, _>(|_| ())
+                    .is_none()
+                {
+                    self.renderer.add_managed_pipeline::<LoadingBarPipeline>(
+                        crate::fps::FPS_PIPELINE_PRIORITY - 1,
+                    );
+                }
+
+                self.world.spawn((
+                    BuiltinLoadingBar,
+                    LoadingBar {
+                        progress,
+                        ..Default::default()
+                    },
+                ));
+            }
+
+            (None, Some(entity)) => {
+                let _ = self.world.despawn(entity);
+            }
+
+            (None, None) => {}
+        }
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use super::*;
+    use crate::test_utils;
+
+    fn png() -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    /// Update until every entry has loaded or failed.
+    fn finish(handle: &mut PreloadHandle, state: &State) {
+        let start = Instant::now();
+
+        while handle.progress() < 1. {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "Preload timed out"
+            );
+            handle.update(state);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn names<'a>(failures: impl Iterator<Item = &'a PreloadFailure>) -> Vec<&'a str> {
+        let mut names = failures
+            .map(|failure| failure.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn missing_optional_entries_still_complete() {
+        let Some(state) = test_utils::state(16, 16) else {
+            return;
+        };
+
+        let manifest = PreloadManifest::new()
+            .with_texture("logo", png())
+            .with_entry(
+                PreloadEntry::new("icons", PreloadKind::Texture, "missing/icons.png").optional(),
+            )
+            .with_entry(
+                PreloadEntry::new("font", PreloadKind::Font, "missing/font.ttf").optional(),
+            );
+
+        let mut handle = state.begin_preload(manifest);
+        finish(&mut handle, &state);
+
+        assert_eq!(handle.status(), PreloadStatus::Complete);
+        assert!(handle.texture("logo").is_some());
+        assert!(handle.texture("icons").is_none());
+
+        assert_eq!(names(handle.report().optional()), ["font", "icons"]);
+        assert_eq!(handle.report().required().count(), 0);
+    }
+
+    #[test]
+    fn missing_required_entries_are_reported_by_name() {
+        let Some(state) = test_utils::state(16, 16) else {
+            return;
+        };
+
+        let manifest = PreloadManifest::new()
+            .with_texture("level", "missing/level.png")
+            .with_texture("logo", png())
+            .with_entry(
+                PreloadEntry::new("music", PreloadKind::Font, "missing/music.ogg").optional(),
+            );
+
+        let mut handle = state.begin_preload(manifest);
+        finish(&mut handle, &state);
+
+        // The rest still load
+        assert_eq!(handle.status(), PreloadStatus::Failed);
+        assert!(handle.texture("logo").is_some());
+
+        let report = handle.report();
+        assert_eq!(names(report.required()), ["level"]);
+        assert_eq!(names(report.optional()), ["music"]);
+
+        let failure = report.required().next().unwrap();
+        assert_eq!(failure.kind, PreloadKind::Texture);
+        assert!(
+            failure.reason.contains("missing/level.png"),
+            "{}",
+            failure.reason
+        );
+        assert!(report.to_string().contains("Required Texture 'level'"));
+    }
+}
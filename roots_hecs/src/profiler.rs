@@ -0,0 +1,528 @@
+//====================================================================
+
+use std::{
+    any::TypeId,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use hecs::{Component, Query, QueryIter, World};
+use roots_common::spatial::{GlobalTransform, Transform, WorldPosition};
+use roots_renderer::{
+    camera::{OrthographicCamera, PerspectiveCamera},
+    memory,
+};
+use web_time::Instant;
+
+use crate::{
+    blob_shadow::BlobShadow,
+    fade::{FadeIn, FadeOut},
+    particles::ParticleEmitter,
+    renderer::components::{
+        ArraySprite, Camera, LineBundle, Model, OffscreenCamera, Panel, ParallaxLayer,
+        RenderBounds, Sprite, SpriteLayer,
+    },
+    spatial::LocalTransform,
+    spatial_hash::SpatialIndexed,
+    text::{TextArea, UiRect},
+    tracked::Tracked,
+    State,
+};
+
+//====================================================================
+
+/// Archetypes with this many entities or fewer count as near empty.
+const SMALL_ARCHETYPE_ENTITIES: u32 = 2;
+/// Near empty archetypes allowed before warning of fragmentation.
+const FRAGMENTED_ARCHETYPES: usize = 200;
+
+/// Fraction of the world a query has to match to count as broad.
+const BROAD_QUERY_FRACTION: f32 = 0.9;
+/// Consecutive frames a query has to be broad for before warning.
+const BROAD_QUERY_FRAMES: u32 = 120;
+/// Worlds smaller than this are cheap to query in full.
+const BROAD_QUERY_MIN_ENTITIES: u32 = 1000;
+
+/// Queries shown by the overlay, slowest first.
+const OVERLAY_QUERIES: usize = 5;
+
+static RECORDING: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+static QUERIES: Mutex<Vec<QueryTiming>> = Mutex::new(Vec::new());
+static COMPONENTS: Mutex<Vec<ComponentInfo>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy)]
+struct QueryTiming {
+    label: &'static str,
+    time: Duration,
+    matched: u32,
+    total: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ComponentInfo {
+    id: TypeId,
+    name: &'static str,
+    size: usize,
+}
+
+//====================================================================
+
+/// Name component type `T` in archetype reports and count its size towards their
+/// memory. The engine's own components are registered when the `State` is created.
+pub fn register_component<T: Component>() {
+    let id = TypeId::of::<T>();
+    let mut components = COMPONENTS.lock().unwrap();
+
+    if !components.iter().any(|component| component.id == id) {
+        components.push(ComponentInfo {
+            id,
+            name: std::any::type_name::<T>(),
+            size: std::mem::size_of::<T>(),
+        });
+    }
+}
+
+pub(crate) fn register_builtin_components() {
+    register_component::<Transform>();
+    register_component::<Tracked<Transform>>();
+    register_component::<GlobalTransform>();
+    register_component::<LocalTransform>();
+    register_component::<WorldPosition>();
+    register_component::<Camera>();
+    register_component::<OffscreenCamera>();
+    register_component::<PerspectiveCamera>();
+    register_component::<OrthographicCamera>();
+    register_component::<Model>();
+    register_component::<Sprite>();
+    register_component::<SpriteLayer>();
+    register_component::<ArraySprite>();
+    register_component::<ParallaxLayer>();
+    register_component::<Panel>();
+    register_component::<LineBundle>();
+    register_component::<RenderBounds>();
+    register_component::<SpatialIndexed>();
+    register_component::<ParticleEmitter>();
+    register_component::<FadeIn>();
+    register_component::<FadeOut>();
+    register_component::<BlobShadow>();
+    register_component::<TextArea>();
+    register_component::<UiRect>();
+}
+
+/// `type_name` without module paths, so `roots_hecs::tracked::Tracked<glam::Vec3>`
+/// becomes `Tracked<Vec3>`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut segment = String::new();
+    let mut chars = name.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ':' if chars.peek() == Some(&':') => {
+                chars.next();
+                segment.clear();
+            }
+            c if c.is_alphanumeric() || c == '_' => segment.push(c),
+            c => {
+                short.push_str(&segment);
+                segment.clear();
+                short.push(c);
+            }
+        }
+    }
+
+    short.push_str(&segment);
+    short
+}
+
+//====================================================================
+
+/// One archetype of the world, as listed by `archetype_report`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchetypeInfo {
+    /// Registered component names, sorted. See `register_component`.
+    pub components: Vec<String>,
+    /// Components of types that aren't registered, so can't be named or sized.
+    pub unregistered: usize,
+    pub entities: u32,
+    /// Entities times the size of their registered components and id. Excludes
+    /// spare capacity and anything the components own on the heap.
+    pub bytes: u64,
+}
+
+impl Display for ArchetypeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} x [{}", self.entities, self.components.join(", "))?;
+
+        match (self.unregistered, self.components.is_empty()) {
+            (0, _) => {}
+            (unregistered, true) => write!(f, "{} unregistered", unregistered)?,
+            (unregistered, false) => write!(f, " +{} unregistered", unregistered)?,
+        }
+
+        write!(f, "] ~{}", memory::format_bytes(self.bytes))
+    }
+}
+
+/// Every archetype holding entities, most entities first.
+pub fn archetype_report(world: &World) -> Vec<ArchetypeInfo> {
+    let registered = COMPONENTS.lock().unwrap();
+
+    let mut report = world
+        .archetypes()
+        .filter(|archetype| !archetype.is_empty())
+        .map(|archetype| {
+            let mut components = Vec::new();
+            let mut unregistered = 0;
+            let mut size = std::mem::size_of::<u32>();
+
+            archetype.component_types().for_each(|id| {
+                match registered.iter().find(|component| component.id == id) {
+                    Some(component) => {
+                        components.push(short_type_name(component.name));
+                        size += component.size;
+                    }
+                    None => unregistered += 1,
+                }
+            });
+
+            components.sort();
+
+            ArchetypeInfo {
+                components,
+                unregistered,
+                entities: archetype.len(),
+                bytes: archetype.len() as u64 * size as u64,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    report.sort_by_key(|archetype| std::cmp::Reverse(archetype.entities));
+    report
+}
+
+//====================================================================
+
+/// Iterates a query like `QueryMut::into_iter`, recording the time from creation
+/// until it's dropped and the entities it matched under its label.
+pub struct ProfiledIter<'w, Q: Query> {
+    inner: QueryIter<'w, Q>,
+    timing: Option<(QueryTiming, Instant)>,
+}
+
+impl<'w, Q: Query> Iterator for ProfiledIter<'w, Q> {
+    type Item = (hecs::Entity, Q::Item<'w>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<Q: Query> Drop for ProfiledIter<'_, Q> {
+    fn drop(&mut self) {
+        if let Some((mut timing, start)) = self.timing.take() {
+            timing.time = start.elapsed();
+            QUERIES.lock().unwrap().push(timing);
+        }
+    }
+}
+
+/// `world.query_mut::<Q>().into_iter()`, timed under `label` for the `WorldProfiler`.
+/// Costs nothing extra while profiling is disabled.
+pub fn profiled_query<'w, Q: Query>(
+    world: &'w mut World,
+    label: &'static str,
+) -> ProfiledIter<'w, Q> {
+    let timing = RECORDING.load(Ordering::Relaxed).then(|| {
+        let matched = world
+            .archetypes()
+            .filter(|archetype| archetype.satisfies::<Q>())
+            .map(|archetype| archetype.len())
+            .sum();
+
+        let timing = QueryTiming {
+            label,
+            time: Duration::ZERO,
+            matched,
+            total: world.len(),
+        };
+
+        (timing, Instant::now())
+    });
+
+    ProfiledIter {
+        inner: world.query_mut::<Q>().into_iter(),
+        timing,
+    }
+}
+
+//====================================================================
+
+/// Totals of every profiled query with the same label over a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueryStats {
+    pub label: &'static str,
+    pub calls: u32,
+    pub time: Duration,
+    /// Entities matched, summed over calls.
+    pub matched: u32,
+    /// Entities in the world when the query last ran.
+    pub world_entities: u32,
+}
+
+impl Display for QueryStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {:.3}ms, {} of {} entities",
+            self.label,
+            self.time.as_secs_f32() * 1000.,
+            self.matched / self.calls.max(1),
+            self.world_entities
+        )?;
+
+        if self.calls > 1 {
+            write!(f, " per call, {} calls", self.calls)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A pattern in the world that tends to make ticks slow.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfilerWarning {
+    /// Many archetypes hold almost no entities, usually from marker components being
+    /// added to and removed from single entities. Every query checks every archetype.
+    FragmentedArchetypes { near_empty: usize, total: usize },
+    /// A query matched most of the world every frame for a while.
+    BroadQuery { label: &'static str, fraction: f32 },
+}
+
+impl Display for ProfilerWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfilerWarning::FragmentedArchetypes { near_empty, total } => write!(
+                f,
+                "{} of {} archetypes hold {} or fewer entities - avoid inserting and \
+                removing marker components per entity, such as by using a field instead",
+                near_empty, total, SMALL_ARCHETYPE_ENTITIES
+            ),
+            ProfilerWarning::BroadQuery { label, fraction } => write!(
+                f,
+                "Query '{}' matches {:.0}% of entities every frame - narrow it with a \
+                marker component or `Tracked` changes",
+                label,
+                fraction * 100.
+            ),
+        }
+    }
+}
+
+/// Collects the timings of `profiled_query` calls each frame and watches the world
+/// for fragmentation. The built-in systems and pipelines profile their queries, so
+/// the stats are populated without any setup. Enabled by default in debug builds.
+///
+/// Shown by the `world` and `queries` console commands, or over the frame with
+/// `set_overlay`.
+#[derive(Debug, Default)]
+pub struct WorldProfiler {
+    last_frame: Vec<QueryStats>,
+    /// Consecutive frames each broad query has been broad for.
+    broad_frames: Vec<(&'static str, u32)>,
+    warnings: Vec<ProfilerWarning>,
+    overlay: bool,
+}
+
+impl WorldProfiler {
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        RECORDING.load(Ordering::Relaxed)
+    }
+
+    /// Stops `profiled_query` recording anything.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        RECORDING.store(enabled, Ordering::Relaxed);
+
+        if !enabled {
+            QUERIES.lock().unwrap().clear();
+            self.last_frame.clear();
+            self.broad_frames.clear();
+            self.warnings.clear();
+        }
+    }
+
+    #[inline]
+    pub fn overlay(&self) -> bool {
+        self.overlay
+    }
+
+    /// Show the slowest queries and any warnings as debug text.
+    #[inline]
+    pub fn set_overlay(&mut self, overlay: bool) {
+        self.overlay = overlay;
+    }
+
+    /// Stats of the last finished frame, slowest first.
+    #[inline]
+    pub fn queries(&self) -> &[QueryStats] {
+        &self.last_frame
+    }
+
+    /// Total time of profiled queries last frame.
+    #[inline]
+    pub fn total_time(&self) -> Duration {
+        self.last_frame.iter().map(|stats| stats.time).sum()
+    }
+
+    #[inline]
+    pub fn warnings(&self) -> &[ProfilerWarning] {
+        &self.warnings
+    }
+
+    /// Gather the queries profiled since the last call and update the warnings,
+    /// logging any new ones.
+    pub fn finish_frame(&mut self, world: &World) {
+        if !self.enabled() {
+            return;
+        }
+
+        let timings = std::mem::take(&mut *QUERIES.lock().unwrap());
+
+        self.last_frame.clear();
+        timings.into_iter().for_each(|timing| {
+            match self
+                .last_frame
+                .iter_mut()
+                .find(|stats| stats.label == timing.label)
+            {
+                Some(stats) => {
+                    stats.calls += 1;
+                    stats.time += timing.time;
+                    stats.matched += timing.matched;
+                    stats.world_entities = timing.total;
+                }
+                None => self.last_frame.push(QueryStats {
+                    label: timing.label,
+                    calls: 1,
+                    time: timing.time,
+                    matched: timing.matched,
+                    world_entities: timing.total,
+                }),
+            }
+        });
+
+        self.last_frame
+            .sort_by_key(|stats| std::cmp::Reverse(stats.time));
+
+        let warnings = self.check(world);
+
+        warnings
+            .iter()
+            .filter(|warning| !self.warnings.contains(warning))
+            .for_each(|warning| log::warn!("{}", warning));
+
+        self.warnings = warnings;
+    }
+
+    fn check(&mut self, world: &World) -> Vec<ProfilerWarning> {
+        let mut warnings = Vec::new();
+
+        let total = world.archetypes().len();
+        let near_empty = world
+            .archetypes()
+            .filter(|archetype| archetype.len() <= SMALL_ARCHETYPE_ENTITIES)
+            .count();
+
+        if near_empty > FRAGMENTED_ARCHETYPES {
+            warnings.push(ProfilerWarning::FragmentedArchetypes { near_empty, total });
+        }
+
+        let broad = self
+            .last_frame
+            .iter()
+            .filter(|stats| stats.world_entities >= BROAD_QUERY_MIN_ENTITIES)
+            .filter_map(|stats| {
+                // Matches are summed over calls, so compare the average call
+                let fraction =
+                    stats.matched as f32 / stats.calls as f32 / stats.world_entities as f32;
+
+                (fraction > BROAD_QUERY_FRACTION).then_some((stats.label, fraction))
+            })
+            .collect::<Vec<_>>();
+
+        // Queries that weren't broad this frame start counting again
+        self.broad_frames
+            .retain(|(label, _)| broad.iter().any(|(broad, _)| broad == label));
+
+        broad.into_iter().for_each(|(label, fraction)| {
+            let frames = match self
+                .broad_frames
+                .iter_mut()
+                .find(|(existing, _)| *existing == label)
+            {
+                Some((_, frames)) => {
+                    *frames += 1;
+                    *frames
+                }
+                None => {
+                    self.broad_frames.push((label, 1));
+                    1
+                }
+            };
+
+            if frames >= BROAD_QUERY_FRAMES {
+                // Rounded so the warning isn't logged again as the fraction wobbles
+                let fraction = (fraction * 100.).round() / 100.;
+                warnings.push(ProfilerWarning::BroadQuery { label, fraction });
+            }
+        });
+
+        warnings
+    }
+}
+
+impl State {
+    /// Finish profiling the frame, showing the overlay if enabled. Called by the
+    /// runner after rendering. Host applications call it alongside `reset_inputs`.
+    pub fn profile_frame(&mut self) {
+        self.profiler.finish_frame(&self.world);
+
+        if !self.profiler.overlay() || !self.profiler.enabled() {
+            return;
+        }
+
+        // Pushed after the frame rendered, so shown with the next one
+        let debug_text = &mut self.renderer.debug_text;
+
+        debug_text.push_line(format!(
+            "queries: {:.3}ms, {} entities in {} archetypes",
+            self.profiler.total_time().as_secs_f32() * 1000.,
+            self.world.len(),
+            self.world.archetypes().len()
+        ));
+
+        self.profiler
+            .queries()
+            .iter()
+            .take(OVERLAY_QUERIES)
+            .for_each(|stats| debug_text.push_line(format!("  {}", stats)));
+
+        self.profiler
+            .warnings()
+            .iter()
+            .for_each(|warning| debug_text.push_line(format!("! {}", warning)));
+    }
+}
+
+//====================================================================
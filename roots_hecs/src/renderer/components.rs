@@ -2,9 +2,21 @@
 
 use std::ops::{Deref, DerefMut};
 
-use roots_common::WasmWrapper;
-use roots_pipelines::line_renderer::LineInstance;
-use roots_renderer::{model::LoadedMesh, texture::LoadedTexture};
+use roots_common::Size;
+use roots_common::{spatial::Aabb, WasmWrapper};
+use roots_pipelines::{
+    line_renderer::LineInstance, parallax_renderer::ParallaxTiling, wind::Wind,
+    world_panel_renderer::WorldPanel,
+};
+use roots_renderer::{
+    camera::{CameraUniform, CameraUniformRaw, OrthographicCamera, PerspectiveCamera},
+    model::LoadedMesh,
+    offscreen::OffscreenTarget,
+    texture::{LoadedTexture, LoadedTextureArray},
+    Color,
+};
+
+use super::RendererState;
 
 //====================================================================
 
@@ -12,6 +24,8 @@ pub struct Model {
     pub meshes: WasmWrapper<Vec<(LoadedMesh, LoadedTexture)>>,
     pub color: [f32; 4],
     pub scale: glam::Vec3,
+    /// Sways the model's vertices over time. Models with wind aren't baked into static geometry.
+    pub wind: Option<Wind>,
 }
 
 impl Model {
@@ -21,6 +35,7 @@ impl Model {
             meshes: WasmWrapper::new(meshes.into_iter().collect()),
             color: [1., 1., 1., 1.],
             scale: glam::Vec3::ONE,
+            wind: None,
         }
     }
 
@@ -35,6 +50,139 @@ impl Model {
         self.scale = scale.into();
         self
     }
+
+    #[inline]
+    pub fn with_wind(mut self, wind: Wind) -> Self {
+        self.wind = Some(wind);
+        self
+    }
+
+    /// Load a .gltf/.glb file as a single model, with one mesh per primitive and node
+    /// transforms baked in. Colored with the base color of the first primitive. Use
+    /// `spawn_gltf_scene` to keep the nodes as separate entities.
+    #[cfg(feature = "gltf")]
+    pub fn load_gltf(
+        state: &RendererState,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, roots_renderer::Error> {
+        let primitives = roots_renderer::gltf::load_primitives(
+            &state.device,
+            &state.queue,
+            &state.shared,
+            path,
+        )?;
+
+        Ok(Self::from_gltf_primitives(primitives))
+    }
+
+    /// `load_gltf` from memory. Only embedded (glb or data uri) buffers and images can
+    /// be resolved this way.
+    #[cfg(feature = "gltf")]
+    pub fn load_gltf_from_slice(
+        state: &RendererState,
+        bytes: &[u8],
+    ) -> Result<Self, roots_renderer::Error> {
+        let primitives = roots_renderer::gltf::load_primitives_from_slice(
+            &state.device,
+            &state.queue,
+            &state.shared,
+            bytes,
+        )?;
+
+        Ok(Self::from_gltf_primitives(primitives))
+    }
+
+    /// Load an .obj file as a single model, with one mesh per material. Colored with
+    /// the diffuse color of the first material.
+    #[cfg(feature = "obj")]
+    pub fn load_obj(
+        state: &RendererState,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, roots_renderer::Error> {
+        let meshes = roots_renderer::obj::load(&state.device, &state.queue, &state.shared, path)?;
+
+        Ok(Self::from_obj_meshes(meshes))
+    }
+
+    /// `load_obj` from memory. `resolve` returns the contents of the material libraries
+    /// and textures the file names.
+    #[cfg(feature = "obj")]
+    pub fn load_obj_from_slice(
+        state: &RendererState,
+        bytes: &[u8],
+        resolve: impl Fn(&std::path::Path) -> Option<Vec<u8>>,
+    ) -> Result<Self, roots_renderer::Error> {
+        let meshes = roots_renderer::obj::load_from_slice(
+            &state.device,
+            &state.queue,
+            &state.shared,
+            bytes,
+            resolve,
+        )?;
+
+        Ok(Self::from_obj_meshes(meshes))
+    }
+
+    #[cfg(feature = "obj")]
+    fn from_obj_meshes(meshes: Vec<roots_renderer::obj::ObjMesh>) -> Self {
+        let color = meshes
+            .first()
+            .map(|mesh| mesh.diffuse)
+            .unwrap_or([1., 1., 1., 1.]);
+
+        Self::new(meshes.into_iter().map(|mesh| (mesh.mesh, mesh.texture))).with_color(color)
+    }
+
+    #[cfg(feature = "gltf")]
+    fn from_gltf_primitives(primitives: Vec<roots_renderer::gltf::GltfPrimitive>) -> Self {
+        let color = primitives
+            .first()
+            .map(|primitive| primitive.base_color)
+            .unwrap_or([1., 1., 1., 1.]);
+
+        Self::new(
+            primitives
+                .into_iter()
+                .map(|primitive| (primitive.mesh, primitive.texture)),
+        )
+        .with_color(color)
+    }
+}
+
+#[cfg(feature = "gltf")]
+/// Spawn every node of a gltf scene into the world. The scene root nodes are
+/// parented to a new root entity using `transform`. Returns the root entity
+/// and the entity for each node in `scene.nodes`.
+pub fn spawn_gltf_scene(
+    world: &mut hecs::World,
+    scene: &roots_renderer::gltf::GltfScene,
+    transform: roots_common::spatial::Transform,
+) -> (hecs::Entity, Vec<hecs::Entity>) {
+    use crate::spatial::LocalTransform;
+    use roots_common::spatial::GlobalTransform;
+
+    let root = world.spawn((transform, GlobalTransform::default()));
+
+    // Nodes are ordered parent-first so parents are always spawned before their children
+    let nodes = scene.nodes.iter().fold(Vec::new(), |mut acc, node| {
+        let parent = node.parent.map(|index| acc[index]).unwrap_or(root);
+
+        let mut builder = hecs::EntityBuilder::new();
+        builder.add(LocalTransform {
+            parent,
+            transform: node.transform.clone(),
+        });
+        builder.add(GlobalTransform::default());
+
+        if !node.primitives.is_empty() {
+            builder.add(Model::new(node.meshes()).with_color(node.base_color()));
+        }
+
+        acc.push(world.spawn(builder.build()));
+        acc
+    });
+
+    (root, nodes)
 }
 
 //====================================================================
@@ -50,23 +198,270 @@ pub struct Sprite {
     pub color: glam::Vec4,
 }
 
+/// Places a `Sprite` in a layer of the `Texture2dRenderer`, sorted by the layer's
+/// `SortMode` (see `Texture2dRenderer::set_layer_sort`). Sprites without one are
+/// batched by texture.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SpriteLayer {
+    pub layer: u32,
+    /// Added to the sprite's sort origin in world units, for sprites whose feet
+    /// aren't at the layer's origin offset.
+    pub sort_offset: f32,
+}
+
+impl SpriteLayer {
+    #[inline]
+    pub fn new(layer: u32) -> Self {
+        Self {
+            layer,
+            sort_offset: 0.,
+        }
+    }
+
+    #[inline]
+    pub fn with_sort_offset(mut self, sort_offset: f32) -> Self {
+        self.sort_offset = sort_offset;
+        self
+    }
+}
+
+/// An infinitely scrolling background drawn by the `ParallaxRenderer` through the first
+/// orthographic camera. Layers sort against sprites by `z`.
+pub struct ParallaxLayer {
+    pub texture: LoadedTexture,
+    /// Fraction of the camera's movement the layer follows. `1` moves with the world
+    /// and `0` stays fixed on screen.
+    pub scroll_factor: glam::Vec2,
+    pub tiling: ParallaxTiling,
+    /// World size of one repeat of the texture.
+    pub tile_size: glam::Vec2,
+    pub z: f32,
+    /// World y of the bottom of the strip, before scrolling.
+    pub offset_y: f32,
+    pub color: glam::Vec4,
+    /// Snap scrolling to whole texels, for pixel art.
+    pub pixel_snap: bool,
+}
+
+impl ParallaxLayer {
+    /// A horizontal strip with one texel per world unit.
+    pub fn new(texture: LoadedTexture, scroll_factor: f32, z: f32) -> Self {
+        let size = &texture.texture().texture;
+        let tile_size = glam::vec2(size.width() as f32, size.height() as f32);
+
+        Self {
+            texture,
+            scroll_factor: glam::Vec2::splat(scroll_factor),
+            tiling: ParallaxTiling::RepeatX,
+            tile_size,
+            z,
+            offset_y: 0.,
+            color: glam::Vec4::ONE,
+            pixel_snap: false,
+        }
+    }
+
+    #[inline]
+    pub fn with_tiling(mut self, tiling: ParallaxTiling) -> Self {
+        self.tiling = tiling;
+        self
+    }
+
+    #[inline]
+    pub fn with_tile_size(mut self, tile_size: glam::Vec2) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    #[inline]
+    pub fn with_offset_y(mut self, offset_y: f32) -> Self {
+        self.offset_y = offset_y;
+        self
+    }
+
+    #[inline]
+    pub fn with_color(mut self, color: glam::Vec4) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[inline]
+    pub fn with_pixel_snap(mut self, pixel_snap: bool) -> Self {
+        self.pixel_snap = pixel_snap;
+        self
+    }
+}
+
+/// A sprite drawn from one layer of a texture array by the `TextureArrayRenderer`.
+pub struct ArraySprite {
+    pub texture: LoadedTextureArray,
+    pub layer: u32,
+    pub size: glam::Vec2,
+    pub pos: glam::Vec3,
+    pub color: glam::Vec4,
+}
+
+/// Overrides the volume an entity is culled and picked with, relative to its
+/// `GlobalTransform` (or a sprite's position). Any mesh scale, such as `Model::scale`,
+/// isn't applied. Entities without bounds are never culled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderBounds {
+    /// A local space box, such as the full extent of an animation or displacement.
+    Aabb(Aabb),
+    /// A sphere around the origin, scaled by the largest axis of the transform.
+    Sphere(f32),
+    /// Never culled, such as for skybox props and full screen effects. Can't be picked.
+    Always,
+}
+
+impl From<Aabb> for RenderBounds {
+    #[inline]
+    fn from(aabb: Aabb) -> Self {
+        Self::Aabb(aabb)
+    }
+}
+
+/// A `WorldPanel` drawn by the `WorldPanelRenderer` at the entity's `GlobalTransform`.
+pub struct Panel {
+    pub panel: WorldPanel,
+    pub texture: Option<LoadedTexture>,
+}
+
+impl Panel {
+    #[inline]
+    pub fn new(panel: WorldPanel) -> Self {
+        Self {
+            panel,
+            texture: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_texture(mut self, texture: LoadedTexture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
 //====================================================================
 
-pub struct Camera(WasmWrapper<roots_renderer::camera::Camera>);
+/// A gpu camera. Entities with a `Camera`, a `PerspectiveCamera` or `OrthographicCamera`
+/// and a `GlobalTransform` are synced to the gpu by `RendererState::sync_cameras` before
+/// each frame is prepped.
+pub struct Camera {
+    camera: WasmWrapper<roots_renderer::camera::Camera>,
+    /// The last uniform written, so unchanged cameras skip the write.
+    synced: Option<CameraUniformRaw>,
+}
+
+impl Camera {
+    #[inline]
+    pub fn new(camera: roots_renderer::camera::Camera) -> Self {
+        Self {
+            camera: WasmWrapper::new(camera),
+            synced: None,
+        }
+    }
+
+    #[inline]
+    pub fn new_perspective(state: &RendererState, data: &PerspectiveCamera) -> Self {
+        Self::new(state.shared.create_camera(&state.device, data))
+    }
+
+    #[inline]
+    pub fn new_orthographic(state: &RendererState, data: &OrthographicCamera) -> Self {
+        Self::new(state.shared.create_camera(&state.device, data))
+    }
+
+    /// Write the camera uniform if the projection, transform or shader time changed
+    /// since the last sync. Returns true if it was written.
+    pub fn sync<C: CameraUniform>(
+        &mut self,
+        queue: &wgpu::Queue,
+        data: &C,
+        transform: &glam::Affine3A,
+        time: f32,
+    ) -> bool {
+        let raw = data
+            .get_camera_uniform_with(transform, self.camera.depth_convention())
+            .with_time(time);
+
+        if self.synced == Some(raw) {
+            return false;
+        }
+
+        self.camera.update_camera_raw(queue, &raw);
+        self.synced = Some(raw);
+        true
+    }
+}
 
 impl Deref for Camera {
     type Target = roots_renderer::camera::Camera;
 
     #[inline]
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.camera
     }
 }
 
+/// Writing through the inner camera directly (such as with `update_camera`) isn't
+/// tracked, so the next `sync` always writes.
 impl DerefMut for Camera {
     #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        self.synced = None;
+        &mut self.camera
+    }
+}
+
+/// Renders the entity's `Camera` into an `OffscreenTarget` each frame instead of the
+/// screen, such as for a minimap or mirror. The result can be drawn like any other
+/// texture with `target().color()`. Offscreen cameras are never the main camera.
+///
+/// Pipelines are prepped once per frame from the main camera, so entities it culls are
+/// missing from offscreen views too. Screen space pipelines aren't drawn.
+pub struct OffscreenCamera {
+    target: WasmWrapper<OffscreenTarget>,
+    /// Clears the target before drawing. Uses the renderer's clear color when `None`.
+    pub clear_color: Option<Color>,
+}
+
+impl OffscreenCamera {
+    #[inline]
+    pub fn new(target: OffscreenTarget) -> Self {
+        Self {
+            target: WasmWrapper::new(target),
+            clear_color: None,
+        }
+    }
+
+    /// A target of `size` matching the renderer's surface format and sample count.
+    #[inline]
+    pub fn new_sized(state: &RendererState, size: Size<u32>, label: &str) -> Self {
+        Self::new(OffscreenTarget::new(
+            &state.device,
+            &state.shared,
+            state.config.format,
+            size,
+            label,
+        ))
+    }
+
+    #[inline]
+    pub fn with_clear_color(mut self, clear_color: Color) -> Self {
+        self.clear_color = Some(clear_color);
+        self
+    }
+
+    #[inline]
+    pub fn target(&self) -> &OffscreenTarget {
+        &self.target
+    }
+
+    #[inline]
+    pub fn target_mut(&mut self) -> &mut OffscreenTarget {
+        &mut self.target
     }
 }
 
@@ -0,0 +1,420 @@
+//====================================================================
+
+use std::collections::HashSet;
+
+use hecs::{Entity, World};
+use roots_common::spatial::{GlobalTransform, WorldPosition};
+use roots_pipelines::line_renderer::LineInstance;
+use roots_renderer::camera::{CameraUniform, OrthographicCamera, PerspectiveCamera};
+
+use super::{
+    components::{ArraySprite, Camera, OffscreenCamera, RenderBounds, Sprite},
+    large_world::RenderOrigin,
+};
+use crate::profiler;
+
+//====================================================================
+
+/// Segments in each ring drawn around sphere bounds.
+const SPHERE_SEGMENTS: u32 = 24;
+
+/// The planes of a camera's view volume, with normals pointing inwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [glam::Vec4; 6],
+}
+
+impl Frustum {
+    /// Extract the planes from a view projection with a 0 to 1 depth range.
+    pub fn from_view_projection(view_projection: glam::Mat4) -> Self {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|index| view_projection.row(index));
+
+        let planes = [w + x, w - x, w + y, w - y, z, w - z].map(|plane| {
+            let length = plane.truncate().length();
+            match length > 0. {
+                true => plane / length,
+                false => plane,
+            }
+        });
+
+        Self { planes }
+    }
+
+    #[inline]
+    pub fn intersects_sphere(&self, centre: glam::Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(centre) + plane.w >= -radius)
+    }
+
+    /// False only if every point is outside the same plane.
+    #[inline]
+    pub fn intersects_points(&self, points: &[glam::Vec3]) -> bool {
+        self.planes.iter().all(|plane| {
+            points
+                .iter()
+                .any(|point| plane.truncate().dot(*point) + plane.w >= 0.)
+        })
+    }
+}
+
+//====================================================================
+
+impl RenderBounds {
+    /// Corners of `Aabb` bounds in world space.
+    pub fn world_corners(&self, transform: &glam::Affine3A) -> Option<[glam::Vec3; 8]> {
+        let RenderBounds::Aabb(aabb) = self else {
+            return None;
+        };
+
+        Some(
+            aabb.corners()
+                .map(|corner| transform.transform_point3(corner)),
+        )
+    }
+
+    /// Centre and radius of `Sphere` bounds in world space.
+    pub fn world_sphere(&self, transform: &glam::Affine3A) -> Option<(glam::Vec3, f32)> {
+        let RenderBounds::Sphere(radius) = *self else {
+            return None;
+        };
+
+        let scale = transform
+            .matrix3
+            .x_axis
+            .length()
+            .max(transform.matrix3.y_axis.length())
+            .max(transform.matrix3.z_axis.length());
+
+        Some((transform.translation.into(), radius * scale))
+    }
+
+    /// Whether the bounds are at least partly inside `frustum`. `Always` is always visible.
+    pub fn is_visible(&self, frustum: &Frustum, transform: &glam::Affine3A) -> bool {
+        match self {
+            RenderBounds::Aabb(_) => {
+                frustum.intersects_points(&self.world_corners(transform).unwrap())
+            }
+            RenderBounds::Sphere(_) => {
+                let (centre, radius) = self.world_sphere(transform).unwrap();
+                frustum.intersects_sphere(centre, radius)
+            }
+            RenderBounds::Always => true,
+        }
+    }
+
+    /// Distance along a ray to the bounds. `Always` has no volume and is never hit.
+    pub fn intersect_ray(
+        &self,
+        transform: &glam::Affine3A,
+        origin: glam::Vec3,
+        direction: glam::Vec3,
+    ) -> Option<f32> {
+        match *self {
+            RenderBounds::Aabb(aabb) => {
+                // Test in local space, where the box is axis aligned
+                let inverse = transform.inverse();
+                let local_origin = inverse.transform_point3(origin);
+                let local_direction = inverse.transform_vector3(direction);
+
+                let near = (aabb.min - local_origin) / local_direction;
+                let far = (aabb.max - local_origin) / local_direction;
+
+                let enter = near.min(far).max_element().max(0.);
+                let exit = near.max(far).min_element();
+
+                match enter <= exit {
+                    // Scales from local to world distance, as direction is normalized
+                    true => Some(
+                        transform
+                            .transform_vector3(local_direction * enter)
+                            .length(),
+                    ),
+                    false => None,
+                }
+            }
+
+            RenderBounds::Sphere(_) => {
+                let (centre, radius) = self.world_sphere(transform).unwrap();
+
+                let offset = origin - centre;
+                let along = offset.dot(direction);
+                let discriminant = along * along - (offset.length_squared() - radius * radius);
+
+                if discriminant < 0. {
+                    return None;
+                }
+
+                let root = discriminant.sqrt();
+                let (enter, exit) = (-along - root, -along + root);
+
+                // Starting inside the sphere hits immediately
+                match exit >= 0. {
+                    true => Some(enter.max(0.)),
+                    false => None,
+                }
+            }
+
+            RenderBounds::Always => None,
+        }
+    }
+}
+
+/// The transform `RenderBounds` are relative to, placed relative to `origin`. The
+/// `GlobalTransform` if there is one, otherwise the position of a sprite.
+fn bounds_transform(
+    origin: &RenderOrigin,
+    global: Option<&GlobalTransform>,
+    position: Option<&WorldPosition>,
+    sprite: Option<&Sprite>,
+    array_sprite: Option<&ArraySprite>,
+) -> glam::Affine3A {
+    match (global, sprite, array_sprite) {
+        (Some(global), _, _) => origin.relative(global, position),
+        (None, Some(sprite), _) => {
+            glam::Affine3A::from_translation(origin.relative_point(sprite.pos))
+        }
+        (None, None, Some(sprite)) => {
+            glam::Affine3A::from_translation(origin.relative_point(sprite.pos))
+        }
+        (None, None, None) => glam::Affine3A::IDENTITY,
+    }
+}
+
+//====================================================================
+
+/// Entities checked by the last culling pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    /// Entities with `Aabb` or `Sphere` bounds tested against the frustum.
+    pub tested: u32,
+    pub culled: u32,
+    /// Entities with `RenderBounds::Always`.
+    pub always: u32,
+}
+
+/// Hides entities whose `RenderBounds` are outside the first camera's view. Run by
+/// `RendererState::prep_managed` before the managed pipelines are prepped. Entities
+/// without `RenderBounds` are never culled.
+#[derive(Debug, Default)]
+pub struct Culling {
+    disabled: bool,
+    frustum: Option<Frustum>,
+    culled: HashSet<Entity>,
+    stats: CullingStats,
+}
+
+impl Culling {
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        !self.disabled
+    }
+
+    #[inline]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.disabled = !enabled;
+
+        if !enabled {
+            self.culled.clear();
+            self.stats = CullingStats::default();
+        }
+    }
+
+    #[inline]
+    pub fn stats(&self) -> CullingStats {
+        self.stats
+    }
+
+    /// Whether `entity` was culled this frame.
+    #[inline]
+    pub fn is_culled(&self, entity: Entity) -> bool {
+        self.culled.contains(&entity)
+    }
+
+    /// The frustum used for the last pass. `None` without a camera.
+    #[inline]
+    pub fn frustum(&self) -> Option<&Frustum> {
+        self.frustum.as_ref()
+    }
+
+    pub fn update(&mut self, world: &mut World, origin: &RenderOrigin) {
+        self.culled.clear();
+        self.stats = CullingStats::default();
+
+        if self.disabled {
+            return;
+        }
+
+        self.frustum = camera_view_projection(world, origin).map(Frustum::from_view_projection);
+        let Some(frustum) = &self.frustum else {
+            return;
+        };
+
+        profiler::profiled_query::<(
+            &RenderBounds,
+            Option<&GlobalTransform>,
+            Option<&WorldPosition>,
+            Option<&Sprite>,
+            Option<&ArraySprite>,
+        )>(world, "culling")
+        .for_each(
+            |(entity, (bounds, global, position, sprite, array_sprite))| {
+                if *bounds == RenderBounds::Always {
+                    self.stats.always += 1;
+                    return;
+                }
+
+                self.stats.tested += 1;
+
+                let transform = bounds_transform(origin, global, position, sprite, array_sprite);
+                if !bounds.is_visible(frustum, &transform) {
+                    self.stats.culled += 1;
+                    self.culled.insert(entity);
+                }
+            },
+        );
+    }
+
+    /// World space outlines of every entity's `RenderBounds`, using `culled_color` for
+    /// entities culled this frame. Put them in a `LineBundle` to diagnose popping.
+    pub fn bounds_lines(
+        &self,
+        world: &mut World,
+        visible_color: glam::Vec4,
+        culled_color: glam::Vec4,
+    ) -> Vec<LineInstance> {
+        let mut lines = Vec::new();
+
+        world
+            .query_mut::<(
+                &RenderBounds,
+                Option<&GlobalTransform>,
+                Option<&WorldPosition>,
+                Option<&Sprite>,
+                Option<&ArraySprite>,
+            )>()
+            .into_iter()
+            .for_each(
+                |(entity, (bounds, global, position, sprite, array_sprite))| {
+                    let transform = bounds_transform(
+                        &RenderOrigin::default(),
+                        global,
+                        position,
+                        sprite,
+                        array_sprite,
+                    );
+                    let color = match self.is_culled(entity) {
+                        true => culled_color,
+                        false => visible_color,
+                    };
+
+                    let mut line = |pos1, pos2| {
+                        lines.push(LineInstance {
+                            color,
+                            pos1,
+                            pos2,
+                            ..Default::default()
+                        })
+                    };
+
+                    if let Some(corners) = bounds.world_corners(&transform) {
+                        // Each edge joins corners differing in one axis bit
+                        (0..8_usize).for_each(|start| {
+                            [1, 2, 4]
+                                .into_iter()
+                                .filter(|bit| start & bit == 0)
+                                .for_each(|bit| line(corners[start], corners[start | bit]));
+                        });
+                    }
+
+                    if let Some((centre, radius)) = bounds.world_sphere(&transform) {
+                        [
+                            (glam::Vec3::X, glam::Vec3::Y),
+                            (glam::Vec3::Y, glam::Vec3::Z),
+                            (glam::Vec3::Z, glam::Vec3::X),
+                        ]
+                        .into_iter()
+                        .for_each(|(a, b)| {
+                            let point = |segment: u32| {
+                                let angle =
+                                    segment as f32 / SPHERE_SEGMENTS as f32 * std::f32::consts::TAU;
+                                centre + (a * angle.cos() + b * angle.sin()) * radius
+                            };
+
+                            (0..SPHERE_SEGMENTS)
+                                .for_each(|segment| line(point(segment), point(segment + 1)));
+                        });
+                    }
+                },
+            );
+
+        lines
+    }
+}
+
+/// View projection of the main camera relative to `origin`, as it renders without
+/// any depth convention.
+pub fn camera_view_projection(world: &mut World, origin: &RenderOrigin) -> Option<glam::Mat4> {
+    let (entity, _) = world
+        .query_mut::<&Camera>()
+        .without::<&OffscreenCamera>()
+        .into_iter()
+        .next()?;
+    let global = world.get::<&GlobalTransform>(entity).ok()?;
+    let position = world.get::<&WorldPosition>(entity).ok();
+    let transform = origin.relative(&global, position.as_deref());
+
+    if let Ok(camera) = world.get::<&PerspectiveCamera>(entity) {
+        return Some(camera.get_projection_matrix() * camera.get_view_matrix(&transform));
+    }
+
+    let camera = world.get::<&OrthographicCamera>(entity).ok()?;
+    Some(camera.get_projection_matrix() * camera.get_view_matrix(&transform))
+}
+
+//====================================================================
+
+/// The closest entity whose `RenderBounds` are hit by a ray relative to `origin`, such
+/// as one from `camera_view_projection`, using the same volumes as culling. Returns
+/// the entity and the distance along the ray.
+#[cfg(feature = "winit")]
+pub fn pick(
+    world: &mut World,
+    origin: &RenderOrigin,
+    ray: &crate::gizmo::Ray,
+) -> Option<(Entity, f32)> {
+    raycast(world, origin, ray.origin, ray.direction, |_| true)
+}
+
+/// The closest entity whose `RenderBounds` are hit by a ray relative to `origin`, out
+/// of those passing `filter`. `direction` must be normalized.
+pub(crate) fn raycast(
+    world: &mut World,
+    origin: &RenderOrigin,
+    ray_origin: glam::Vec3,
+    direction: glam::Vec3,
+    filter: impl Fn(Entity) -> bool,
+) -> Option<(Entity, f32)> {
+    world
+        .query_mut::<(
+            &RenderBounds,
+            Option<&GlobalTransform>,
+            Option<&WorldPosition>,
+            Option<&Sprite>,
+            Option<&ArraySprite>,
+        )>()
+        .into_iter()
+        .filter(|(entity, _)| filter(*entity))
+        .filter_map(
+            |(entity, (bounds, global, position, sprite, array_sprite))| {
+                let transform = bounds_transform(origin, global, position, sprite, array_sprite);
+
+                bounds
+                    .intersect_ray(&transform, ray_origin, direction)
+                    .map(|distance| (entity, distance))
+            },
+        )
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+//====================================================================
@@ -0,0 +1,100 @@
+//====================================================================
+
+use hecs::World;
+use roots_common::spatial::{GlobalTransform, WorldPosition};
+
+use super::components::{Camera, OffscreenCamera};
+
+//====================================================================
+
+/// The point everything is rendered relative to. With large world rendering on, this
+/// follows the first camera and transforms are moved next to it on the cpu in f64, so
+/// the gpu only works with small values and far away content doesn't jitter. Otherwise
+/// it stays at zero. See `RendererState::set_large_world`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderOrigin {
+    enabled: bool,
+    origin: glam::DVec3,
+}
+
+impl RenderOrigin {
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[inline]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+
+        if !enabled {
+            self.origin = glam::DVec3::ZERO;
+        }
+    }
+
+    /// The world position subtracted from everything rendered.
+    #[inline]
+    pub fn origin(&self) -> glam::DVec3 {
+        self.origin
+    }
+
+    /// Move the origin to the first camera. Run by `RendererState::prep_managed`.
+    pub fn update(&mut self, world: &mut World) {
+        if !self.enabled {
+            return;
+        }
+
+        if let Some((_, (global, position))) = world
+            .query_mut::<(&GlobalTransform, Option<&WorldPosition>)>()
+            .with::<&Camera>()
+            .without::<&OffscreenCamera>()
+            .into_iter()
+            .next()
+        {
+            self.origin = world_translation(global, position);
+        }
+    }
+
+    /// `global` offset by `position` and moved relative to the origin.
+    #[inline]
+    pub fn relative(
+        &self,
+        global: &GlobalTransform,
+        position: Option<&WorldPosition>,
+    ) -> glam::Affine3A {
+        glam::Affine3A {
+            matrix3: global.0.matrix3,
+            translation: (world_translation(global, position) - self.origin).as_vec3a(),
+        }
+    }
+
+    /// A world space point moved relative to the origin.
+    #[inline]
+    pub fn relative_point(&self, point: glam::Vec3) -> glam::Vec3 {
+        (point.as_dvec3() - self.origin).as_vec3()
+    }
+
+    /// A world space matrix moved relative to the origin.
+    #[inline]
+    pub fn relative_matrix(&self, mut matrix: glam::Mat4) -> glam::Mat4 {
+        let translation = self.relative_point(matrix.w_axis.truncate());
+        matrix.w_axis = translation.extend(matrix.w_axis.w);
+        matrix
+    }
+}
+
+/// The full world translation of an entity, its `WorldPosition` plus its `GlobalTransform`.
+#[inline]
+pub fn world_translation(
+    global: &GlobalTransform,
+    position: Option<&WorldPosition>,
+) -> glam::DVec3 {
+    let translation = global.0.translation.as_dvec3();
+
+    match position {
+        Some(position) => position.0 + translation,
+        None => translation,
+    }
+}
+
+//====================================================================
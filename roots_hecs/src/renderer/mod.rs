@@ -1,46 +1,206 @@
 //====================================================================
 
-use std::sync::{Arc, RwLock};
+use std::{sync::Arc, time::Duration};
 
 use hecs::World;
-use roots_common::Size;
+use parking_lot::RwLock;
+use roots_common::{
+    spatial::{GlobalTransform, WorldPosition},
+    theme::Theme,
+    Size,
+};
+use roots_pipelines::{
+    manager::{FrameDescription, PipelineFault, PipelineManager, PipelineTargets, RenderContext},
+    resolution::{ResolutionScaler, SceneTarget},
+};
+#[cfg(feature = "winit")]
+use roots_renderer::RenderCoreConfig;
 use roots_renderer::{
-    lighting::LightingManager, shared::SharedRenderResources, texture::Texture, Color, Device,
-    Queue, RenderCore, RenderEncoder, RenderPassDesc, Surface, SurfaceConfig, SurfaceError,
+    camera::{Camera, OrthographicCamera, PerspectiveCamera, StereoCamera},
+    capabilities::{CapabilityReport, DegradedFeature, GraphicsSettings},
+    capture::RgbaImage,
+    lighting::{GlobalLightData, Light, LightInstance, LightingManager},
+    pipeline_cache::PersistentPipelineCache,
+    shared::{DepthConvention, SharedRenderResources},
+    splash::{SplashRenderer, SplashScreen},
+    streaming::TextureStreamer,
+    target_pool::{PooledTarget, RenderTargetPool, TargetDesc, TargetSize},
+    uploads::{DeferredUploads, UploadContext, UploadStrategy},
+    watchdog::{FrameWatchdog, WatchdogPhase},
+    Color, Device, Queue, RenderCore, RenderEncoder, RenderPassDesc, Surface, SurfaceConfig,
+    SurfaceError,
 };
+#[cfg(feature = "winit")]
 use roots_runner::window::Window;
+use web_time::Instant;
+
+use crate::{
+    debug_text::{self, DebugTextQueue},
+    profiler,
+};
 
 pub mod components;
+pub mod culling;
+pub mod large_world;
 pub mod pipelines;
+pub mod static_geometry;
 
 //====================================================================
 
+/// Where the renderer is within the current frame. Calls made out of this order
+/// debug assert (and log an error in release builds).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FramePhase {
+    /// Between frames. Lighting may be updated.
+    #[default]
+    Idle,
+    /// `begin_frame` was called. Lighting may still be updated.
+    Begun,
+    /// `prep_managed` was called.
+    Prepped,
+    /// `render` was called. Only `end_frame` is valid.
+    Rendered,
+}
+
 pub struct RendererState {
     pub device: Device,
     pub queue: Queue,
-    pub surface: Surface<'static>,
+    /// `None` when rendering into views provided by a host application with `render_to`.
+    pub surface: Option<Surface<'static>>,
+    /// Always kept up to date with the render size, even without a surface.
     pub config: SurfaceConfig,
 
     pub shared: SharedRenderResources,
     pub lighting: LightingManager,
-    depth_texture: Texture,
+    /// Render targets shared between passes. See `RenderTargetPool::acquire`.
+    pub target_pool: RenderTargetPool,
+    depth_texture: PooledTarget,
+    /// Only exists while multisampling. See `set_sample_count`.
+    multisampled: Option<PooledTarget>,
+
+    /// Overrides the theme's background as the clear color.
+    pub clear_color: Option<Color>,
+    /// Default colors of the built-in UI. See `set_theme`.
+    theme: Theme,
+    paused: bool,
+    frame_phase: FramePhase,
+    draw_order_debug: bool,
+
+    stereo_eyes: Option<[Camera; 2]>,
+    screen_camera: Camera,
+
+    /// Internal resolution of the 3D scene. See `set_resolution_scale`.
+    pub resolution: ResolutionScaler,
+    /// Only exists while the scene is rendered below native resolution.
+    scene_target: Option<SceneTarget>,
+
+    /// Not poisoned by panics, which are caught for each pipeline anyway.
+    managed_pipelines: Arc<RwLock<PipelineManager<dyn pipelines::Pipeline>>>,
+    /// Copied from the managed pipelines at the end of each frame.
+    pipeline_faults: Vec<PipelineFault>,
+
+    /// Created at the start of each frame. See `upload_context`.
+    pub uploads: DeferredUploads,
+    upload_strategy: UploadStrategy,
+    /// Streams texture detail in and out at the start of each frame, within the
+    /// `uploads` budget left over after deferred uploads.
+    pub streaming: TextureStreamer,
+
+    /// Times each frame and reports phases over its threshold.
+    pub watchdog: FrameWatchdog,
+    /// Updated at the start of `prep_managed`.
+    pub culling: culling::Culling,
+    /// Updated at the start of `prep_managed`. See `set_large_world`.
+    pub origin: large_world::RenderOrigin,
+    /// Written to each camera uniform. See `advance_shader_time`.
+    shader_time: f32,
 
-    pub clear_color: Color,
+    /// Lines shown over everything for a single frame. See `debug_text!`.
+    pub debug_text: DebugTextQueue,
 
-    managed_pipelines: Arc<RwLock<Vec<ManagedPipeline>>>,
+    /// See `request_capture`.
+    capture_requested: bool,
+    captured: Option<Result<RgbaImage, roots_renderer::Error>>,
+
+    /// See `set_pipeline_cache`.
+    pipeline_cache: Option<PersistentPipelineCache>,
+
+    capabilities: CapabilityReport,
+    /// Requested settings turned down to fit `capabilities`.
+    graphics: GraphicsSettings,
+    degraded_features: Vec<DegradedFeature>,
+
+    /// Covers the managed pipelines until finished, then fades out over them.
+    splash: Option<SplashRenderer>,
+    splash_held: bool,
 }
 
 impl RendererState {
+    #[cfg(feature = "winit")]
+    #[inline]
     pub fn new(window: &Window) -> Self {
+        Self::new_with_config(window, &RenderCoreConfig::default())
+    }
+
+    #[cfg(feature = "winit")]
+    pub fn new_with_config(window: &Window, core_config: &RenderCoreConfig) -> Self {
         log::info!("Creating renderer");
-        let (device, queue, surface, config) =
-            RenderCore::new_blocked(window.clone_arc(), window.size())
-                .unwrap()
-                .break_down();
+        let core = RenderCore::new_blocked(window.clone_arc(), window.size(), core_config)
+            .unwrap_or_else(|e| panic!("Unable to create renderer: {}", e));
 
-        let shared = SharedRenderResources::new(&device);
-        let lighting = LightingManager::new(&device);
-        let depth_texture = Texture::create_depth_texture(&device, window.size(), None);
+        Self::from_core(core)
+    }
+
+    /// Create the renderer from a surface and device created by a host application.
+    #[inline]
+    pub fn from_core(mut core: RenderCore<'static>) -> Self {
+        let capabilities = core.capabilities.clone();
+        let pipeline_cache = core.pipeline_cache.take();
+        let (device, queue, surface, config) = core.break_down();
+
+        let mut state =
+            Self::from_parts_with_capabilities(device, queue, Some(surface), config, capabilities);
+        state.set_pipeline_cache(pipeline_cache);
+        state
+    }
+
+    /// Create the renderer from existing wgpu handles. Without a surface, frames must be
+    /// rendered with `render_to` and `config` only describes the size and format of the
+    /// target views.
+    pub fn from_parts(
+        device: Device,
+        queue: Queue,
+        surface: Option<Surface<'static>>,
+        config: SurfaceConfig,
+    ) -> Self {
+        let capabilities = CapabilityReport::from_device(&device);
+        Self::from_parts_with_capabilities(device, queue, surface, config, capabilities)
+    }
+
+    fn from_parts_with_capabilities(
+        device: Device,
+        queue: Queue,
+        surface: Option<Surface<'static>>,
+        config: SurfaceConfig,
+        capabilities: CapabilityReport,
+    ) -> Self {
+        let (graphics, degraded_features) =
+            GraphicsSettings::platform_default().evaluate(&capabilities);
+        degraded_features
+            .iter()
+            .for_each(|degraded| log::warn!("{}", degraded));
+
+        let mut shared = SharedRenderResources::new(&device);
+        shared.set_sample_count(graphics.msaa_samples);
+
+        let lighting = LightingManager::new_with_path(&device, &shared, graphics.lighting);
+        let mut target_pool = RenderTargetPool::new(Size::new(config.width, config.height));
+        let (depth_texture, multisampled) =
+            acquire_main_targets(&device, &mut target_pool, &config, &shared);
+        let screen_camera = shared.create_camera(
+            &device,
+            &OrthographicCamera::new_sized(config.width as f32, config.height as f32),
+        );
 
         Self {
             device,
@@ -49,9 +209,36 @@ impl RendererState {
             config,
             shared,
             lighting,
+            target_pool,
             depth_texture,
-            clear_color: Color::new(0.2, 0.2, 0.2, 1.),
+            multisampled,
+            clear_color: None,
+            theme: Theme::default(),
+            paused: false,
+            frame_phase: FramePhase::Idle,
+            draw_order_debug: false,
+            stereo_eyes: None,
+            screen_camera,
+            resolution: ResolutionScaler::default(),
+            scene_target: None,
             managed_pipelines: Arc::default(),
+            pipeline_faults: Vec::new(),
+            uploads: DeferredUploads::default(),
+            upload_strategy: UploadStrategy::platform_default(),
+            streaming: TextureStreamer::default(),
+            watchdog: FrameWatchdog::default(),
+            culling: culling::Culling::default(),
+            origin: large_world::RenderOrigin::default(),
+            shader_time: 0.,
+            debug_text: DebugTextQueue::default(),
+            capture_requested: false,
+            captured: None,
+            pipeline_cache: None,
+            capabilities,
+            graphics,
+            degraded_features,
+            splash: None,
+            splash_held: false,
         }
     }
 
@@ -66,69 +253,1120 @@ impl RendererState {
 
         self.config.width = size.width;
         self.config.height = size.height;
+        self.reconfigure_surface();
+
+        self.target_pool.resize(size);
+        self.reacquire_main_targets();
+
+        self.update_screen_camera();
+    }
+
+    fn update_screen_camera(&mut self) {
+        self.screen_camera.update_camera(
+            &self.queue,
+            &OrthographicCamera::new_sized(self.config.width as f32, self.config.height as f32),
+            &glam::Affine3A::IDENTITY,
+        );
+    }
+
+    /// Orthographic camera covering the window in pixels, with the origin at the
+    /// bottom left. Kept up to date on resize.
+    #[inline]
+    pub fn screen_camera(&self) -> &Camera {
+        &self.screen_camera
+    }
+
+    #[inline]
+    pub fn depth_convention(&self) -> &DepthConvention {
+        self.shared.depth_convention()
+    }
+
+    /// Switch depth format and/or reversed-Z. Pipelines and cameras only pick up
+    /// the convention when they are created, so this should be called before
+    /// any managed pipelines are added.
+    pub fn set_depth_convention(&mut self, depth_convention: DepthConvention) {
+        if !self.managed_pipelines.read().is_empty() {
+            log::warn!(
+                "Setting depth convention after managed pipelines were added - existing pipelines will not match."
+            );
+        }
+
+        self.shared.set_depth_convention(depth_convention);
+        self.reacquire_main_targets();
+
+        self.screen_camera.set_depth_convention(depth_convention);
+        self.update_screen_camera();
+
+        // Recreated with the new depth format next frame
+        self.scene_target = None;
+    }
 
-        self.surface.configure(&self.device, &self.config);
+    /// Switch reversed-Z, keeping the depth format. See `set_depth_convention`.
+    #[inline]
+    pub fn set_reversed_z(&mut self, reversed_z: bool) {
+        self.set_depth_convention(DepthConvention {
+            reversed_z,
+            ..*self.depth_convention()
+        });
+    }
+
+    /// Samples per pixel of the main color and depth targets. Every managed pipeline
+    /// is created with this count, read from `shared`. Starts at the graphics settings'
+    /// `msaa_samples`.
+    #[inline]
+    pub fn sample_count(&self) -> u32 {
+        self.shared.sample_count()
+    }
+
+    /// Switch multisampling, with 1 turning it off. Pipelines only pick up the sample
+    /// count when they are created and can't be drawn into targets with another, so
+    /// this is ignored once any managed pipelines have been added. Returns whether the
+    /// count was applied.
+    pub fn set_sample_count(&mut self, sample_count: u32) -> bool {
+        let sample_count = sample_count.max(1);
+
+        if sample_count == self.shared.sample_count() {
+            return true;
+        }
+
+        if !self.managed_pipelines.read().is_empty() {
+            log::warn!(
+                "Unable to set sample count to {} after managed pipelines were added - keeping {}",
+                sample_count,
+                self.shared.sample_count()
+            );
+            return false;
+        }
+
+        if !self.capabilities.supports_sample_count(sample_count) {
+            log::warn!(
+                "Sample count {} isn't supported - keeping {}. Supported counts = {:?}",
+                sample_count,
+                self.shared.sample_count(),
+                self.capabilities.sample_counts
+            );
+            return false;
+        }
+
+        self.shared.set_sample_count(sample_count);
+        self.reacquire_main_targets();
+
+        // Recreated with the new sample count next frame
+        self.scene_target = None;
+
+        true
+    }
+
+    #[inline]
+    pub fn pipeline_cache(&self) -> Option<&PersistentPipelineCache> {
+        self.pipeline_cache.as_ref()
+    }
 
-        self.depth_texture = Texture::create_depth_texture(&self.device, size, None);
+    /// Cache pipelines are created with from now on, such as one loaded by
+    /// `RenderCore` from `RenderCoreConfig::pipeline_cache_dir`.
+    pub fn set_pipeline_cache(&mut self, pipeline_cache: Option<PersistentPipelineCache>) {
+        self.shared
+            .set_pipeline_cache(pipeline_cache.as_ref().map(|cache| cache.cache().clone()));
+        self.pipeline_cache = pipeline_cache;
+    }
+
+    /// Write the pipeline cache (if any) back to its file, so the next launch can skip
+    /// compiling the pipelines created so far. Called when the window is closed.
+    pub fn save_pipeline_cache(&self) {
+        if let Some(pipeline_cache) = &self.pipeline_cache {
+            if let Err(e) = pipeline_cache.save() {
+                log::warn!(
+                    "Unable to save pipeline cache {:?}: {}",
+                    pipeline_cache.path(),
+                    e
+                );
+            }
+        }
+    }
+
+    fn reacquire_main_targets(&mut self) {
+        // Release the old targets first so the pool can hand them straight back
+        self.multisampled = None;
+
+        let (depth_texture, multisampled) = acquire_main_targets(
+            &self.device,
+            &mut self.target_pool,
+            &self.config,
+            &self.shared,
+        );
+        self.depth_texture = depth_texture;
+        self.multisampled = multisampled;
+    }
+
+    /// Scale the internal resolution of the 3D scene, between `resolution.min_scale`
+    /// and `resolution.max_scale` (50% - 100% by default). Screen space pipelines
+    /// are still rendered at native resolution. Turns off dynamic resolution.
+    #[inline]
+    pub fn set_resolution_scale(&mut self, scale: f32) {
+        self.resolution.set_scale(scale);
+    }
+
+    #[inline]
+    pub fn resolution_scale(&self) -> f32 {
+        self.resolution.scale()
+    }
+
+    /// Lower the resolution scale whenever frames take longer than `budget` and
+    /// slowly raise it again while there is headroom, or stop with `None`. Frames are
+    /// timed by the `watchdog`, so it must be enabled. With vsync, frames never take
+    /// less than the refresh interval, so the budget should sit above it.
+    #[inline]
+    pub fn set_dynamic_resolution(&mut self, budget: Option<Duration>) {
+        self.resolution.set_target_frame_time(budget);
+    }
+
+    /// Keep the scene target matching the scaled render size. Returns false at full
+    /// scale, where the scene is rendered straight into the surface.
+    fn update_scene_target(&mut self) -> bool {
+        if self.resolution.scale() >= 1. {
+            self.scene_target = None;
+            return false;
+        }
+
+        let size = self
+            .resolution
+            .scaled_size(Size::new(self.config.width, self.config.height));
+
+        match &mut self.scene_target {
+            Some(target) if target.size() == size => {}
+            Some(target) => target.resize(
+                &self.device,
+                &self.config,
+                &self.shared,
+                &mut self.target_pool,
+                size,
+            ),
+            None => {
+                self.scene_target = Some(SceneTarget::new(
+                    &self.device,
+                    &self.config,
+                    &self.shared,
+                    &mut self.target_pool,
+                    size,
+                ))
+            }
+        }
+
+        true
+    }
+
+    /// Whether rendering is currently paused. While paused, `prep_managed` and
+    /// `render` do nothing.
+    #[inline]
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    #[inline]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    #[inline]
+    pub fn draw_order_debug(&self) -> bool {
+        self.draw_order_debug
+    }
+
+    /// Tint sprites and models by the order they are drawn in. Supporting managed
+    /// pipelines switch to their debug variant during the next prep.
+    #[inline]
+    pub fn set_draw_order_debug(&mut self, enabled: bool) {
+        self.draw_order_debug = enabled;
+    }
+
+    #[inline]
+    pub fn large_world(&self) -> bool {
+        self.origin.enabled()
+    }
+
+    /// Render relative to the first camera, for worlds with content far from the
+    /// origin. Each frame, the camera's position is subtracted from every transform
+    /// in f64 on the cpu, so the gpu never multiplies large values. Costs a little
+    /// extra prep, so is off by default. Light positions should be made relative with
+    /// `origin.relative_point` and gpu simulated particles fall back to the cpu.
+    #[inline]
+    pub fn set_large_world(&mut self, enabled: bool) {
+        self.origin.set_enabled(enabled);
+    }
+
+    /// Seconds passed to shaders that animate, such as model wind.
+    #[inline]
+    pub fn shader_time(&self) -> f32 {
+        self.shader_time
+    }
+
+    /// Move the shader time on by `delta` seconds before the cameras are next synced.
+    /// The runner does this each frame with the scaled delta, so animation follows the
+    /// time scale. Apps rendering with `run_frame` directly should do the same.
+    #[inline]
+    pub fn advance_shader_time(&mut self, delta: f32) {
+        self.shader_time += delta;
+    }
+
+    /// Show `splash` instead of the managed pipelines, drawing its first frame straight
+    /// away. The runner calls this before `HecsApp::new` when `HecsApp::splash` returns
+    /// one, and finishes it once `new` returns unless it was held with `hold_splash`.
+    ///
+    /// Frames can only be drawn while the app gives control back, so during `new` the
+    /// splash is only redrawn by `prewarm_pipelines`. On wasm nothing reaches the canvas
+    /// until `new` returns, so the splash mostly covers held loading there.
+    pub fn show_splash(&mut self, splash: &SplashScreen) {
+        if self.surface.is_none() {
+            log::warn!("Unable to show splash screen - renderer has no surface");
+            return;
+        }
+
+        self.splash = Some(SplashRenderer::new(
+            &self.device,
+            &self.queue,
+            &self.config,
+            splash,
+        ));
+        self.render_splash();
+    }
+
+    /// Keep the splash up after `HecsApp::new` returns, such as while preloading.
+    /// Ticks carry on as usual but nothing is prepped or rendered until `finish_splash`.
+    #[inline]
+    pub fn hold_splash(&mut self) {
+        self.splash_held = true;
+    }
+
+    #[inline]
+    pub fn splash_held(&self) -> bool {
+        self.splash_held
+    }
+
+    /// Fade the splash out over the managed pipelines. Does nothing without a splash.
+    #[inline]
+    pub fn finish_splash(&mut self) {
+        self.splash_held = false;
+
+        if let Some(splash) = &mut self.splash {
+            splash.finish();
+        }
+    }
+
+    /// Whether a splash is covering the screen or still fading out.
+    #[inline]
+    pub fn splash_showing(&self) -> bool {
+        self.splash.is_some()
+    }
+
+    /// Whether the splash is still up and the managed pipelines are skipped.
+    #[inline]
+    fn splash_covering(&self) -> bool {
+        self.splash
+            .as_ref()
+            .is_some_and(|splash| !splash.is_finished())
+    }
+
+    /// Present a frame of the splash on its own, if it's covering the screen.
+    fn render_splash(&self) {
+        if let (Some(splash), Some(surface)) = (&self.splash, &self.surface) {
+            if !splash.is_finished() {
+                splash.render(&self.device, &self.queue, surface, &self.config);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn frame_phase(&self) -> FramePhase {
+        self.frame_phase
+    }
+
+    fn advance_phase(&mut self, call: &str, expected: &[FramePhase], next: FramePhase) {
+        if !expected.contains(&self.frame_phase) {
+            let message = format!(
+                "RendererState::{} called during {:?} phase - expected one of {:?}. \
+                Each frame should call begin_frame, prep_managed, render then end_frame (or run_frame).",
+                call, self.frame_phase, expected
+            );
+
+            log::error!("{}", message);
+            debug_assert!(false, "{}", message);
+        }
+
+        self.frame_phase = next;
+    }
+
+    /// Start a new frame. Must be followed by `prep_managed`, `render` and `end_frame`.
+    #[inline]
+    pub fn begin_frame(&mut self) {
+        self.advance_phase("begin_frame", &[FramePhase::Idle], FramePhase::Begun);
+        self.watchdog.start_frame();
+
+        let start = Instant::now();
+        self.uploads
+            .process(&self.device, &self.queue, &self.shared);
+        self.streaming.update(
+            &self.device,
+            &self.queue,
+            &self.shared,
+            self.uploads.budget().saturating_sub(start.elapsed()),
+        );
+        self.watchdog
+            .record(WatchdogPhase::Uploads, start.elapsed());
+    }
+
+    #[inline]
+    pub fn end_frame(&mut self) {
+        self.advance_phase("end_frame", &[FramePhase::Rendered], FramePhase::Idle);
+
+        let managed_pipelines = &self.managed_pipelines;
+        self.watchdog.finish_frame(|| {
+            managed_pipelines
+                .read()
+                .names()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect()
+        });
+
+        if self.watchdog.enabled() && !self.paused {
+            self.resolution.update(self.watchdog.last_frame_time());
+        }
+
+        self.pipeline_faults = self.managed_pipelines.read().faults().cloned().collect();
+        self.target_pool.end_frame();
+    }
+
+    /// Managed pipelines that panicked and were disabled, as of the end of the last
+    /// frame. Re-enable one with `set_managed_pipeline_enabled` to run it again.
+    #[inline]
+    pub fn pipeline_faults(&self) -> &[PipelineFault] {
+        &self.pipeline_faults
+    }
+
+    /// Create the gpu state managed pipelines would otherwise create on first use,
+    /// then wait for the device to finish. Call during a loading screen, after the
+    /// pipelines are added, to keep shader compilation out of gameplay.
+    pub fn prewarm_pipelines(&mut self) {
+        let start = Instant::now();
+
+        self.managed_pipelines
+            .write()
+            .for_each_isolated("prewarm", |pipeline| {
+                pipeline.prewarm(self);
+                self.render_splash();
+            });
+
+        self.device.poll(wgpu::Maintain::Wait);
+
+        log::debug!("Prewarmed managed pipelines in {:?}", start.elapsed());
+    }
+
+    #[inline]
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Restyle the built-in UI from the next `prep_managed`. Pipelines read the theme
+    /// each prep, so nothing needs respawning.
+    #[inline]
+    pub fn set_theme(&mut self, theme: Theme) {
+        log::debug!("Setting theme '{}'", theme.name);
+        self.theme = theme;
+    }
+
+    /// `clear_color` if set, otherwise the theme's background.
+    pub fn clear_color(&self) -> Color {
+        self.clear_color.unwrap_or_else(|| {
+            let background = self.theme.background.as_dvec4();
+            Color::new(background.x, background.y, background.z, background.w)
+        })
+    }
+
+    #[inline]
+    pub fn capabilities(&self) -> &CapabilityReport {
+        &self.capabilities
     }
 
+    /// The settings in use, after turning down whatever the device can't provide.
     #[inline]
+    pub fn graphics_settings(&self) -> &GraphicsSettings {
+        &self.graphics
+    }
+
+    /// Requested settings the device couldn't provide and why, for showing in a
+    /// settings menu.
+    #[inline]
+    pub fn degraded_features(&self) -> &[DegradedFeature] {
+        &self.degraded_features
+    }
+
+    /// Request new settings, turning down whatever the device can't provide. Defaults
+    /// to `GraphicsSettings::platform_default`. The lighting path is fixed once the
+    /// renderer is created, and so is the MSAA sample count once managed pipelines are
+    /// added. See `set_sample_count`.
+    pub fn set_graphics_settings(&mut self, desired: GraphicsSettings) {
+        let (mut graphics, degraded_features) = desired.evaluate(&self.capabilities);
+        degraded_features
+            .iter()
+            .filter(|degraded| !self.degraded_features.contains(degraded))
+            .for_each(|degraded| log::warn!("{}", degraded));
+
+        if !self.set_sample_count(graphics.msaa_samples) {
+            graphics.msaa_samples = self.sample_count();
+        }
+
+        self.graphics = graphics;
+        self.degraded_features = degraded_features;
+    }
+
+    #[inline]
+    pub fn upload_strategy(&self) -> UploadStrategy {
+        self.upload_strategy
+    }
+
+    /// Choose how resources pushed through `upload_context` are created. Defaults to
+    /// `UploadStrategy::platform_default`. `Direct` is only available on native - on
+    /// wasm it falls back to `Deferred`.
+    #[inline]
+    pub fn set_upload_strategy(&mut self, upload_strategy: UploadStrategy) {
+        self.upload_strategy = upload_strategy;
+    }
+
+    /// A handle for creating textures and meshes from worker threads, using the
+    /// current upload strategy. Deferred uploads are created in `begin_frame` within
+    /// the `uploads` budget.
+    pub fn upload_context(&self) -> UploadContext {
+        match self.upload_strategy {
+            #[cfg(not(target_arch = "wasm32"))]
+            UploadStrategy::Direct => UploadContext::Direct {
+                device: self.device.clone(),
+                queue: self.queue.clone(),
+                shared: self.shared.clone(),
+            },
+            _ => self.uploads.context(),
+        }
+    }
+
+    /// Prep and render all managed pipelines as a single frame.
+    pub fn run_frame(&mut self, world: &mut World) {
+        self.begin_frame();
+        self.prep_managed(world);
+        self.render(world);
+        self.end_frame();
+    }
+
+    /// Like `run_frame` but renders into a view owned by the host application.
+    pub fn run_frame_to(&mut self, world: &mut World, view: wgpu::TextureView) {
+        self.begin_frame();
+        self.prep_managed(world);
+        self.render_to(world, view);
+        self.end_frame();
+    }
+
+    /// Update the lights used by the managed pipelines. Only valid before `prep_managed`.
+    /// In large world mode, positions are relative to the `origin` of the last prep.
+    pub fn update_lights(&mut self, lights: &[Light]) {
+        self.advance_phase(
+            "update_lights",
+            &[FramePhase::Idle, FramePhase::Begun],
+            self.frame_phase,
+        );
+
+        let lights = lights
+            .iter()
+            .map(|light| LightInstance::from(*light))
+            .collect::<Vec<_>>();
+
+        self.lighting
+            .update_lights(&self.device, &self.queue, &lights);
+    }
+
+    /// Update the global lighting data. Only valid before `prep_managed`.
+    pub fn update_light_globals(&mut self, data: GlobalLightData) {
+        self.advance_phase(
+            "update_light_globals",
+            &[FramePhase::Idle, FramePhase::Begun],
+            self.frame_phase,
+        );
+        self.lighting.update_globals(&self.queue, data);
+    }
+
+    /// Configure the surface again with the current config, such as after it was lost
+    /// or became outdated. Does nothing if the renderer has no surface.
+    #[inline]
+    pub fn reconfigure_surface(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+
+    /// Errors with `SurfaceError::Lost` if the renderer has no surface.
+    ///
+    /// If the surface is lost or outdated, such as after a minimize or GPU reset, it is
+    /// reconfigured and the surface texture requested once more before giving up.
     pub fn create_encoder(&self) -> Result<RenderEncoder, SurfaceError> {
-        let encoder = match RenderEncoder::new(&self.device, &self.surface) {
-            Ok(encoder) => encoder,
-            Err(e) => {
-                log::warn!("Unable to get surface this frame");
-                return Err(e);
+        let Some(surface) = &self.surface else {
+            log::warn!(
+                "Unable to create encoder - renderer has no surface. Use render_to instead."
+            );
+            return Err(SurfaceError::Lost);
+        };
+
+        let encoder = match RenderEncoder::new_configured(&self.device, surface, &self.config) {
+            Ok(encoder) => Ok(encoder),
+            Err(e @ (SurfaceError::Lost | SurfaceError::Outdated)) => {
+                log::debug!("Surface {:?} - reconfiguring and retrying", e);
+                self.reconfigure_surface();
+                RenderEncoder::new_configured(&self.device, surface, &self.config)
             }
+            Err(e) => Err(e),
         };
 
-        Ok(encoder)
+        encoder.inspect_err(|e| log::warn!("Unable to get surface this frame: {}", e))
     }
 
     pub fn add_managed_pipeline<P: pipelines::Pipeline>(&mut self, priority: usize) {
         let pipeline = Box::new(P::new(&self));
 
-        let mut managed_pipelines = self.managed_pipelines.write().unwrap();
+        self.managed_pipelines.write().add_boxed_named(
+            priority,
+            std::any::type_name::<P>(),
+            pipeline,
+        );
+    }
+
+    /// Names and enabled state of the managed pipelines, in render order.
+    pub fn managed_pipeline_names(&self) -> Vec<(&'static str, bool)> {
+        self.managed_pipelines.read().names().collect()
+    }
 
-        managed_pipelines.push(ManagedPipeline { priority, pipeline });
-        managed_pipelines.sort_by_key(|val| val.priority);
+    /// Enable or disable managed pipelines by type name. Disabled pipelines are
+    /// still prepped but not rendered. Returns the number of pipelines matched.
+    #[inline]
+    pub fn set_managed_pipeline_enabled(&mut self, name: &str, enabled: bool) -> usize {
+        self.managed_pipelines.write().set_enabled(name, enabled)
+    }
+
+    #[inline]
+    pub fn managed_pipeline_enabled(&self, name: &str) -> Option<bool> {
+        self.managed_pipelines.read().enabled(name)
+    }
+
+    /// Move every managed pipeline of type `P` to `priority`, taking effect from the
+    /// next `prep_managed`. Render passes are regrouped around its new neighbours.
+    /// Returns the number of pipelines matched.
+    #[inline]
+    pub fn set_pipeline_priority<P: pipelines::Pipeline>(&mut self, priority: usize) -> usize {
+        self.managed_pipelines
+            .write()
+            .set_priority(std::any::type_name::<P>(), priority)
+    }
+
+    /// The managed pipelines in execution order with their passes and last frame's
+    /// timings. Displays as a table, for `log::info!("{}", renderer.describe_frame())`.
+    #[inline]
+    pub fn describe_frame(&self) -> FrameDescription {
+        self.managed_pipelines.read().describe()
+    }
+
+    /// Run `f` on the first managed pipeline of type `P`, if one was added.
+    pub fn with_managed_pipeline<P: pipelines::Pipeline, R>(
+        &self,
+        f: impl FnOnce(&mut P) -> R,
+    ) -> Option<R> {
+        self.managed_pipelines.write().get_mut::<P>().map(f)
+    }
+
+    /// Match the aspect of every perspective camera drawing to the window to `size`,
+    /// picked up by the next `sync_cameras`. Called by `State` when the window resizes.
+    /// Stereo cameras fit a single eye, which covers half the width, and offscreen
+    /// cameras keep the aspect of their own target.
+    pub fn fit_camera_aspects(&self, world: &mut World, size: Size<u32>) {
+        world
+            .query_mut::<(&mut PerspectiveCamera, Option<&StereoCamera>)>()
+            .without::<&components::OffscreenCamera>()
+            .into_iter()
+            .for_each(|(_, (camera, stereo))| match stereo {
+                Some(_) => camera.set_aspect(size.width / 2, size.height),
+                None => camera.set_aspect(size.width, size.height),
+            });
+    }
+
+    /// Write the uniform of every camera whose projection or `GlobalTransform` changed
+    /// since it was last synced. Called by `prep_managed`, so transforms should be
+    /// propagated before then. Cameras are placed relative to the `origin`.
+    pub fn sync_cameras(&self, world: &mut World) {
+        profiler::profiled_query::<(
+            &mut components::Camera,
+            &PerspectiveCamera,
+            &GlobalTransform,
+            Option<&WorldPosition>,
+        )>(world, "sync_perspective_cameras")
+        .for_each(|(_, (camera, data, global, position))| {
+            camera.sync(
+                &self.queue,
+                data,
+                &self.origin.relative(global, position),
+                self.shader_time,
+            );
+        });
+
+        profiler::profiled_query::<(
+            &mut components::Camera,
+            &OrthographicCamera,
+            &GlobalTransform,
+            Option<&WorldPosition>,
+        )>(world, "sync_orthographic_cameras")
+        .for_each(|(_, (camera, data, global, position))| {
+            camera.sync(
+                &self.queue,
+                data,
+                &self.origin.relative(global, position),
+                self.shader_time,
+            );
+        });
     }
 
     pub fn prep_managed(&mut self, world: &mut World) {
+        self.advance_phase("prep_managed", &[FramePhase::Begun], FramePhase::Prepped);
+        debug_text::flush(self);
+        self.managed_pipelines.write().begin_frame();
+
+        if self.paused || self.splash_covering() {
+            return;
+        }
+
+        let start = Instant::now();
+
+        self.origin.update(world);
+        self.sync_cameras(world);
+        self.culling.update(world, &self.origin);
+
         self.managed_pipelines
             .write()
-            .unwrap()
-            .iter_mut()
-            .for_each(|pipeline_data| pipeline_data.pipeline.prep(self, world));
+            .for_each_isolated("prep", |pipeline| pipeline.prep(self, world));
+
+        self.watchdog.record(WatchdogPhase::Prep, start.elapsed());
     }
 
     pub fn render(&mut self, world: &mut World) {
-        let mut encoder = match self.create_encoder() {
+        self.advance_phase("render", &[FramePhase::Prepped], FramePhase::Rendered);
+
+        if self.paused {
+            return;
+        }
+
+        if self.splash_covering() {
+            self.render_splash();
+            return;
+        }
+
+        let start = Instant::now();
+        let encoder = self.create_encoder();
+        self.watchdog
+            .record(WatchdogPhase::SurfaceAcquire, start.elapsed());
+
+        let encoder = match encoder {
             Ok(encoder) => encoder,
             Err(_) => return,
         };
 
-        let mut render_pass = encoder.begin_render_pass(RenderPassDesc {
-            use_depth: Some(&self.depth_texture.view),
-            clear_color: Some(self.clear_color),
-        });
+        self.render_encoder(world, encoder);
+    }
 
-        self.managed_pipelines
-            .write()
-            .unwrap()
-            .iter_mut()
-            .for_each(|pipeline_data| pipeline_data.pipeline.render(&mut render_pass, self, world));
+    /// Render into a view owned by the host application instead of the surface.
+    /// The view should match the size and format of `config`. Follows the same
+    /// frame order as `render`.
+    pub fn render_to(&mut self, world: &mut World, view: wgpu::TextureView) {
+        self.advance_phase("render_to", &[FramePhase::Prepped], FramePhase::Rendered);
+
+        if self.paused {
+            return;
+        }
+
+        let encoder = RenderEncoder::from_view(&self.device, view);
+        self.render_encoder(world, encoder);
+    }
+
+    /// Capture the next frame rendered to the surface, such as for screenshots or
+    /// integration tests, and read it with `take_capture`. Reading it back blocks
+    /// that frame until the gpu has finished, and always fails on wasm.
+    #[inline]
+    pub fn request_capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    /// The frame captured since `request_capture`, once it has been rendered.
+    #[inline]
+    pub fn take_capture(&mut self) -> Option<Result<RgbaImage, roots_renderer::Error>> {
+        self.captured.take()
+    }
+
+    fn render_encoder(&mut self, world: &mut World, mut encoder: RenderEncoder) {
+        let start = Instant::now();
+
+        // Render side-by-side when the camera has a stereo component
+        let eye_uniforms = world
+            .query_mut::<(
+                &components::Camera,
+                &PerspectiveCamera,
+                &GlobalTransform,
+                Option<&WorldPosition>,
+                &StereoCamera,
+            )>()
+            .into_iter()
+            .next()
+            .map(|(_, (_, data, global, position, stereo))| {
+                stereo
+                    .eye_uniforms(
+                        data,
+                        &self.origin.relative(global, position),
+                        self.shared.depth_convention(),
+                    )
+                    .map(|raw| raw.with_time(self.shader_time))
+            });
+
+        if let Some(uniforms) = eye_uniforms {
+            self.stereo_eyes
+                .get_or_insert_with(|| {
+                    let data = PerspectiveCamera::default();
+                    [
+                        self.shared.create_camera(&self.device, &data),
+                        self.shared.create_camera(&self.device, &data),
+                    ]
+                })
+                .iter()
+                .zip(uniforms)
+                .for_each(|(eye, raw)| eye.update_camera_raw(&self.queue, &raw));
+        }
+
+        self.render_offscreen_cameras(world, &mut encoder);
+
+        // Stereo keeps rendering at native resolution
+        let scaled = eye_uniforms.is_none() && self.update_scene_target();
+
+        let targets = PipelineTargets {
+            color: None,
+            multisampled: self.multisampled.as_ref().map(|target| &target.view),
+            depth: &self.depth_texture.view,
+            clear_color: Some(self.clear_color()),
+            depth_convention: *self.shared.depth_convention(),
+        };
+
+        match (pipelines::get_camera(world), &self.stereo_eyes) {
+            (Some((_, camera)), Some([left, right])) if eye_uniforms.is_some() => {
+                let lighting = self.lighting.bind_group();
+                let screen_camera = self.screen_camera.bind_group();
+                let [mono, left, right] = [&**camera, left, right].map(|camera| RenderContext {
+                    camera: camera.bind_group(),
+                    lighting,
+                    screen_camera,
+                });
+
+                self.managed_pipelines.write().render_stereo(
+                    &mut encoder,
+                    &targets,
+                    Size::new(self.config.width, self.config.height),
+                    &mono,
+                    [&left, &right],
+                );
+            }
+
+            (Some((_, camera)), _) => {
+                let context = RenderContext {
+                    camera: camera.bind_group(),
+                    lighting: self.lighting.bind_group(),
+                    screen_camera: self.screen_camera.bind_group(),
+                };
+
+                let mut managed_pipelines = self.managed_pipelines.write();
+
+                match (&self.scene_target, scaled) {
+                    (Some(scene), true) => {
+                        managed_pipelines.render_scaled(&mut encoder, scene, &targets, &context)
+                    }
+                    _ => managed_pipelines.render(&mut encoder, &targets, &context),
+                }
+            }
+
+            // Still clear the screen and draw anything that doesn't need a camera
+            (None, _) => {
+                log::trace!("No camera available - only rendering screen space pipelines");
+                self.managed_pipelines.write().render_screen_space(
+                    &mut encoder,
+                    &targets,
+                    &RenderContext {
+                        camera: self.screen_camera.bind_group(),
+                        lighting: self.lighting.bind_group(),
+                        screen_camera: self.screen_camera.bind_group(),
+                    },
+                );
+            }
+        }
+
+        if let Some(splash) = &self.splash {
+            let mut pass = encoder.begin_render_pass(RenderPassDesc::none());
+            splash.draw(
+                &self.queue,
+                &mut pass,
+                Size::new(self.config.width, self.config.height),
+            );
+        }
+
+        if self.splash.as_ref().is_some_and(SplashRenderer::is_faded) {
+            log::debug!("Splash screen finished");
+            self.splash = None;
+        }
 
-        std::mem::drop(render_pass);
-        encoder.finish(&self.queue);
+        self.watchdog.record(WatchdogPhase::Encode, start.elapsed());
+
+        let capture = std::mem::take(&mut self.capture_requested)
+            .then(|| encoder.capture_surface(&self.device));
+
+        let start = Instant::now();
+        let surface_texture = encoder.submit(&self.queue);
+        self.watchdog.record(WatchdogPhase::Submit, start.elapsed());
+
+        if let Some(capture) = capture {
+            let captured = capture.and_then(|capture| capture.read(&self.device));
+            if let Err(e) = &captured {
+                log::warn!("{}", e);
+            }
+
+            self.captured = Some(captured);
+        }
+
+        if let Some(surface_texture) = surface_texture {
+            let start = Instant::now();
+            surface_texture.present();
+            self.watchdog
+                .record(WatchdogPhase::Present, start.elapsed());
+        }
+    }
+
+    /// Render every `OffscreenCamera` into its target, ahead of the main passes so
+    /// they can draw the results this frame.
+    fn render_offscreen_cameras(&self, world: &mut World, encoder: &mut RenderEncoder) {
+        let mut managed_pipelines = self.managed_pipelines.write();
+
+        world
+            .query_mut::<(&components::Camera, &components::OffscreenCamera)>()
+            .into_iter()
+            .for_each(|(_, (camera, offscreen))| {
+                let target = offscreen.target();
+
+                let targets = PipelineTargets {
+                    color: Some(target.render_view()),
+                    multisampled: target.multisampled_view(),
+                    depth: target.depth_view(),
+                    clear_color: Some(offscreen.clear_color.unwrap_or_else(|| self.clear_color())),
+                    depth_convention: *self.shared.depth_convention(),
+                };
+
+                let context = RenderContext {
+                    camera: camera.bind_group(),
+                    lighting: self.lighting.bind_group(),
+                    screen_camera: self.screen_camera.bind_group(),
+                };
+
+                managed_pipelines.render_offscreen(encoder, &targets, &context);
+                target.present(encoder);
+            });
     }
 }
 
-//====================================================================
+/// The main depth target and, when multisampling, the color target resolved into the
+/// surface. Both are sized to the window.
+fn acquire_main_targets(
+    device: &Device,
+    target_pool: &mut RenderTargetPool,
+    config: &SurfaceConfig,
+    shared: &SharedRenderResources,
+) -> (PooledTarget, Option<PooledTarget>) {
+    let sample_count = shared.sample_count();
+
+    let depth = target_pool.acquire(
+        device,
+        TargetDesc::depth(TargetSize::WINDOW, shared.depth_convention())
+            .with_sample_count(sample_count),
+        "Depth Texture: default",
+    );
+
+    let multisampled = (sample_count > 1).then(|| {
+        target_pool.acquire(
+            device,
+            TargetDesc::new(
+                TargetSize::WINDOW,
+                config.format,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+            .with_sample_count(sample_count),
+            "Multisampled Color Texture: default",
+        )
+    });
 
-pub struct ManagedPipeline {
-    priority: usize,
-    pipeline: Box<dyn pipelines::Pipeline>,
+    (depth, multisampled)
 }
 
 //====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    /// Run `calls` on a fresh renderer and check it was rejected as out of order.
+    #[cfg(debug_assertions)]
+    fn assert_misuse(call: &str, calls: impl FnOnce(&mut RendererState, &mut World)) {
+        let Some(mut renderer) = test_utils::renderer(16, 16) else {
+            return;
+        };
+        let mut world = World::new();
+
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            calls(&mut renderer, &mut world)
+        }))
+        .expect_err("out of order call was accepted");
+
+        let message = panic
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .unwrap_or_default();
+        assert!(
+            message.contains(&format!("RendererState::{} called during", call)),
+            "unexpected panic: {}",
+            message
+        );
+    }
+
+    #[test]
+    fn frame_phases_advance_in_order() {
+        let Some(mut renderer) = test_utils::renderer(16, 16) else {
+            return;
+        };
+        let mut world = World::new();
+        let target = test_utils::render_target(&renderer);
+
+        assert_eq!(renderer.frame_phase(), FramePhase::Idle);
+        renderer.update_light_globals(GlobalLightData::default());
+        renderer.begin_frame();
+        assert_eq!(renderer.frame_phase(), FramePhase::Begun);
+        renderer.update_lights(&[]);
+        renderer.prep_managed(&mut world);
+        assert_eq!(renderer.frame_phase(), FramePhase::Prepped);
+        renderer.render_to(&mut world, target.create_view(&Default::default()));
+        assert_eq!(renderer.frame_phase(), FramePhase::Rendered);
+        renderer.end_frame();
+        assert_eq!(renderer.frame_phase(), FramePhase::Idle);
+    }
+
+    #[test]
+    fn run_frame_returns_to_idle() {
+        let Some(mut renderer) = test_utils::renderer(16, 16) else {
+            return;
+        };
+        let mut world = World::new();
+        let target = test_utils::render_target(&renderer);
+
+        (0..3).for_each(|_| {
+            renderer.run_frame_to(&mut world, target.create_view(&Default::default()));
+            assert_eq!(renderer.frame_phase(), FramePhase::Idle);
+        });
+
+        // Without a surface nothing is rendered, but the frame still completes
+        renderer.run_frame(&mut world);
+        assert_eq!(renderer.frame_phase(), FramePhase::Idle);
+    }
+
+    #[test]
+    fn stereo_cameras_fit_one_eye() {
+        let Some(renderer) = test_utils::renderer(16, 16) else {
+            return;
+        };
+        let mut world = World::new();
+
+        let mono = world.spawn((PerspectiveCamera::default(),));
+        let stereo = world.spawn((PerspectiveCamera::default(), StereoCamera::default()));
+
+        renderer.fit_camera_aspects(&mut world, Size::new(1600, 400));
+
+        assert_eq!(world.get::<&PerspectiveCamera>(mono).unwrap().aspect, 4.);
+        assert_eq!(world.get::<&PerspectiveCamera>(stereo).unwrap().aspect, 2.);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn begin_frame_twice() {
+        assert_misuse("begin_frame", |renderer, _| {
+            renderer.begin_frame();
+            renderer.begin_frame();
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn prep_without_begin_frame() {
+        assert_misuse("prep_managed", |renderer, world| {
+            renderer.prep_managed(world)
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn render_without_prep() {
+        assert_misuse("render", |renderer, world| {
+            renderer.begin_frame();
+            renderer.render(world);
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn render_to_without_prep() {
+        assert_misuse("render_to", |renderer, world| {
+            let target = test_utils::render_target(renderer);
+            renderer.begin_frame();
+            renderer.render_to(world, target.create_view(&Default::default()));
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn end_frame_without_render() {
+        assert_misuse("end_frame", |renderer, world| {
+            renderer.begin_frame();
+            renderer.prep_managed(world);
+            renderer.end_frame();
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn update_lights_after_prep() {
+        assert_misuse("update_lights", |renderer, world| {
+            renderer.begin_frame();
+            renderer.prep_managed(world);
+            renderer.update_lights(&[]);
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn update_light_globals_after_render() {
+        assert_misuse("update_light_globals", |renderer, world| {
+            renderer.begin_frame();
+            renderer.prep_managed(world);
+            renderer.render(world);
+            renderer.update_light_globals(GlobalLightData::default());
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn run_frame_during_frame() {
+        assert_misuse("begin_frame", |renderer, world| {
+            renderer.begin_frame();
+            renderer.run_frame(world);
+        });
+    }
+}
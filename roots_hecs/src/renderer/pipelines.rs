@@ -1,21 +1,36 @@
 //====================================================================
 
 use hecs::{Entity, World};
-use roots_common::spatial::GlobalTransform;
+use roots_common::spatial::{GlobalTransform, WorldPosition};
 use roots_pipelines::{
-    line_renderer::LineRenderer,
+    line_renderer::{LineInstance, LineRenderer},
+    manager::RenderPipeline,
     model_renderer::{ModelData, ModelRenderer},
+    parallax_renderer::{ParallaxData, ParallaxRenderer},
     texture2d_renderer::{Texture2dRenderer, TextureData},
+    texture_array_renderer::{TextureArrayData, TextureArrayRenderer},
+    transparency::TransparentSort,
+    world_panel_renderer::WorldPanelRenderer,
 };
-use roots_renderer::{camera::PerspectiveCamera, RenderPass};
+use roots_renderer::{camera::OrthographicCamera, streaming};
 
-use crate::{renderer::components::Camera, RendererState};
+use crate::{
+    profiler,
+    renderer::{components::Camera, culling, large_world},
+    validation::{self, CHECK_TRANSFORMS},
+    RendererState,
+};
 
-use super::components::{LineBundle, Model, Sprite};
+use super::components::{
+    ArraySprite, LineBundle, Model, OffscreenCamera, Panel, ParallaxLayer, RenderBounds, Sprite,
+    SpriteLayer,
+};
 
 //====================================================================
 
-pub trait Pipeline: 'static {
+/// Adapter that lets a `RenderPipeline` be created and prepped from the hecs world.
+/// Rendering itself is handled by `RenderPipeline::render`.
+pub trait Pipeline: RenderPipeline {
     fn new(state: &RendererState) -> Self
     where
         Self: Sized;
@@ -25,17 +40,74 @@ pub trait Pipeline: 'static {
         let _ = state;
     }
 
-    fn render(&mut self, render_pass: &mut RenderPass, state: &RendererState, world: &mut World);
+    /// Create any gpu state that would otherwise be created on first use. Called by
+    /// `RendererState::prewarm_pipelines`.
+    fn prewarm(&mut self, state: &RendererState) {
+        let _ = state;
+    }
 }
 
+/// The first camera in the world that isn't an `OffscreenCamera`, regardless of its
+/// projection.
 #[inline]
-fn get_perspective_camera(world: &mut World) -> Option<(Entity, (&Camera, &PerspectiveCamera))> {
+pub(crate) fn get_camera(world: &mut World) -> Option<(Entity, &Camera)> {
     world
-        .query_mut::<(&Camera, &PerspectiveCamera)>()
+        .query_mut::<&Camera>()
+        .without::<&OffscreenCamera>()
         .into_iter()
         .next()
 }
 
+/// Back to front order from the first camera, for pipelines with transparent draws.
+pub(crate) fn transparent_sort(state: &RendererState, world: &mut World) -> TransparentSort {
+    culling::camera_view_projection(world, &state.origin)
+        .map(|view_projection| TransparentSort::new(view_projection, state.depth_convention()))
+        .unwrap_or_default()
+}
+
+/// Pass how large a model appears to any streamed textures it uses. Bounds are used
+/// for its size when present, otherwise the mesh is assumed to be about a unit across.
+fn request_model_detail(
+    model: &Model,
+    bounds: Option<&RenderBounds>,
+    transform: &glam::Affine3A,
+    view_projection: glam::Mat4,
+    target_height: f32,
+) {
+    if !model
+        .meshes
+        .iter()
+        .any(|(_, texture)| texture.is_streamed())
+    {
+        return;
+    }
+
+    let scale = transform
+        .matrix3
+        .x_axis
+        .length()
+        .max(transform.matrix3.y_axis.length())
+        .max(transform.matrix3.z_axis.length());
+
+    let size = match bounds {
+        Some(RenderBounds::Aabb(aabb)) => aabb.size().length() * scale,
+        Some(RenderBounds::Sphere(radius)) => radius * 2. * scale,
+        _ => model.scale.max_element() * scale,
+    };
+
+    let pixels = streaming::projected_size(
+        view_projection,
+        target_height,
+        transform.translation.into(),
+        size,
+    );
+
+    model
+        .meshes
+        .iter()
+        .for_each(|(_, texture)| texture.request_detail(pixels));
+}
+
 //====================================================================
 
 impl Pipeline for ModelRenderer {
@@ -44,49 +116,207 @@ impl Pipeline for ModelRenderer {
         Self::new(&state.device, &state.config, &state.shared, &state.lighting)
     }
 
+    #[inline]
+    fn prewarm(&mut self, state: &RendererState) {
+        self.prewarm_draw_order_debug(&state.device, &state.config, &state.shared, &state.lighting);
+    }
+
     #[inline]
     fn prep(&mut self, state: &RendererState, world: &mut World) {
-        world
-            .query_mut::<(&Model, &GlobalTransform)>()
-            .into_iter()
-            .for_each(|(_, (model, global))| {
-                self.prep_model(
-                    ModelData {
-                        meshes: &model.meshes,
-                        color: model.color,
-                        scale: model.scale,
-                    },
-                    global.to_matrix(),
-                )
+        self.set_transparent_sort(transparent_sort(state, world));
+
+        let view_projection = culling::camera_view_projection(world, &state.origin);
+        let target_height = state.config.height as f32;
+
+        let mut invalid = Vec::new();
+
+        let models = profiler::profiled_query::<(
+            &Model,
+            &GlobalTransform,
+            Option<&WorldPosition>,
+            Option<&RenderBounds>,
+        )>(world, "model_renderer")
+        .filter_map(|(entity, (model, global, position, bounds))| {
+            if state.culling.is_culled(entity) {
+                return None;
+            }
+
+            if CHECK_TRANSFORMS && !(global.is_finite() && model.scale.is_finite()) {
+                invalid.push(entity);
+                return None;
+            }
+
+            let transform = state.origin.relative(global, position);
+
+            if let Some(view_projection) = view_projection {
+                request_model_detail(model, bounds, &transform, view_projection, target_height);
+            }
+
+            Some((
+                ModelData {
+                    meshes: &model.meshes,
+                    color: model.color,
+                    scale: model.scale,
+                    wind: model.wind,
+                },
+                transform.into(),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+        self.set_draw_order_debug(
+            &state.device,
+            &state.config,
+            &state.shared,
+            &state.lighting,
+            state.draw_order_debug(),
+        );
+
+        self.prep_models(&models);
+
+        self.finish_prep(&state.device, &state.queue);
+
+        validation::warn_non_finite(world, "ModelRenderer", &invalid);
+    }
+}
+
+//====================================================================
+
+impl Pipeline for Texture2dRenderer {
+    #[inline]
+    fn new(state: &RendererState) -> Self {
+        Self::new(&state.device, &state.config, &state.shared)
+    }
+
+    #[inline]
+    fn prewarm(&mut self, state: &RendererState) {
+        self.prewarm_draw_order_debug(&state.device, &state.config, &state.shared);
+    }
+
+    #[inline]
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        self.set_draw_order_debug(
+            &state.device,
+            &state.config,
+            &state.shared,
+            state.draw_order_debug(),
+        );
+        self.set_transparent_sort(transparent_sort(state, world));
+
+        let view_projection = culling::camera_view_projection(world, &state.origin);
+        let target_height = state.config.height as f32;
+
+        let mut invalid = Vec::new();
+
+        profiler::profiled_query::<(&Sprite, Option<&SpriteLayer>)>(world, "texture2d_renderer")
+            .for_each(|(entity, (sprite, layer))| {
+                if state.culling.is_culled(entity) {
+                    return;
+                }
+
+                if CHECK_TRANSFORMS && !(sprite.pos.is_finite() && sprite.size.is_finite()) {
+                    invalid.push(entity);
+                    return;
+                }
+
+                let pos = state.origin.relative_point(sprite.pos);
+
+                if let Some(view_projection) =
+                    view_projection.filter(|_| sprite.texture.is_streamed())
+                {
+                    sprite.texture.request_detail(streaming::projected_size(
+                        view_projection,
+                        target_height,
+                        pos,
+                        sprite.size.max_element(),
+                    ));
+                }
+
+                let data = TextureData {
+                    texture: &sprite.texture,
+                    size: sprite.size,
+                    pos,
+                    color: sprite.color,
+                };
+
+                match layer {
+                    Some(layer) => self.prep_texture_in_layer(
+                        data,
+                        layer.layer,
+                        layer.sort_offset,
+                        entity.to_bits().get(),
+                    ),
+                    None => self.prep_texture(data),
+                }
             });
 
         self.finish_prep(&state.device, &state.queue);
+
+        validation::warn_non_finite(world, "Texture2dRenderer", &invalid);
     }
+}
 
-    fn render(&mut self, render_pass: &mut RenderPass, state: &RendererState, world: &mut World) {
-        if !self.has_instances_to_render() {
-            return;
-        }
+//====================================================================
 
-        let camera = match get_perspective_camera(world) {
-            Some(data) => data.1 .0,
-            None => {
-                log::warn!("Unable to render models - no camera available");
-                return;
-            }
+impl Pipeline for ParallaxRenderer {
+    #[inline]
+    fn new(state: &RendererState) -> Self {
+        Self::new(&state.device, &state.config, &state.shared)
+    }
+
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        // Layers cover the view of the first orthographic camera
+        let Some((center, size)) = world
+            .query_mut::<(
+                &OrthographicCamera,
+                &GlobalTransform,
+                Option<&WorldPosition>,
+            )>()
+            .with::<&Camera>()
+            .into_iter()
+            .next()
+            .map(|(_, (camera, global, position))| {
+                let translation = large_world::world_translation(global, position);
+                (
+                    translation.truncate().as_vec2() + camera.center(),
+                    camera.size(),
+                )
+            })
+        else {
+            self.finish_prep(&state.device, &state.queue);
+            return;
         };
 
-        self.render(
-            render_pass,
-            camera.bind_group(),
-            state.lighting.bind_group(),
+        self.set_view(center, size);
+        self.set_render_origin(state.origin.origin().truncate());
+        self.set_transparent_sort(transparent_sort(state, world));
+
+        profiler::profiled_query::<&ParallaxLayer>(world, "parallax_renderer").for_each(
+            |(_, layer)| {
+                self.prep_layer(
+                    &state.device,
+                    &state.shared,
+                    ParallaxData {
+                        texture: &layer.texture,
+                        tiling: layer.tiling,
+                        scroll_factor: layer.scroll_factor,
+                        tile_size: layer.tile_size,
+                        offset_y: layer.offset_y,
+                        z: layer.z,
+                        color: layer.color,
+                        pixel_snap: layer.pixel_snap,
+                    },
+                )
+            },
         );
+
+        self.finish_prep(&state.device, &state.queue);
     }
 }
 
 //====================================================================
 
-impl Pipeline for Texture2dRenderer {
+impl Pipeline for TextureArrayRenderer {
     #[inline]
     fn new(state: &RendererState) -> Self {
         Self::new(&state.device, &state.config, &state.shared)
@@ -94,31 +324,71 @@ impl Pipeline for Texture2dRenderer {
 
     #[inline]
     fn prep(&mut self, state: &RendererState, world: &mut World) {
-        world
-            .query_mut::<&Sprite>()
-            .into_iter()
-            .for_each(|(_, sprite)| {
-                self.prep_texture(TextureData {
+        let mut invalid = Vec::new();
+
+        profiler::profiled_query::<&ArraySprite>(world, "texture_array_renderer").for_each(
+            |(entity, sprite)| {
+                if state.culling.is_culled(entity) {
+                    return;
+                }
+
+                if CHECK_TRANSFORMS && !(sprite.pos.is_finite() && sprite.size.is_finite()) {
+                    invalid.push(entity);
+                    return;
+                }
+
+                self.prep_texture(TextureArrayData {
                     texture: &sprite.texture,
+                    layer: sprite.layer,
                     size: sprite.size,
-                    pos: sprite.pos,
+                    pos: state.origin.relative_point(sprite.pos),
                     color: sprite.color,
                 })
-            });
+            },
+        );
 
         self.finish_prep(&state.device, &state.queue);
+
+        validation::warn_non_finite(world, "TextureArrayRenderer", &invalid);
     }
+}
+
+//====================================================================
+
+impl Pipeline for WorldPanelRenderer {
+    #[inline]
+    fn new(state: &RendererState) -> Self {
+        Self::new(&state.device, &state.config, &state.shared)
+    }
+
+    #[inline]
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        let mut invalid = Vec::new();
 
-    fn render(&mut self, render_pass: &mut RenderPass, _state: &RendererState, world: &mut World) {
-        let camera = match get_perspective_camera(world) {
-            Some(data) => data.1 .0,
-            None => {
-                log::warn!("Unable to render models - no camera available");
+        profiler::profiled_query::<(&Panel, &GlobalTransform, Option<&WorldPosition>)>(
+            world,
+            "world_panel_renderer",
+        )
+        .for_each(|(entity, (panel, global, position))| {
+            if state.culling.is_culled(entity) {
                 return;
             }
-        };
 
-        Self::render(self, render_pass, camera.bind_group());
+            if CHECK_TRANSFORMS && !(global.is_finite() && panel.panel.size.is_finite()) {
+                invalid.push(entity);
+                return;
+            }
+
+            self.prep_panel(
+                &panel.panel,
+                panel.texture.as_ref(),
+                state.origin.relative(global, position).into(),
+            );
+        });
+
+        self.finish_prep(&state.device, &state.queue);
+
+        validation::warn_non_finite(world, "WorldPanelRenderer", &invalid);
     }
 }
 
@@ -132,24 +402,26 @@ impl Pipeline for LineRenderer {
 
     #[inline]
     fn prep(&mut self, state: &RendererState, world: &mut World) {
-        world
-            .query_mut::<&LineBundle>()
-            .into_iter()
-            .for_each(|(_, line)| self.prep_lines(&line.lines));
-
-        self.finish_prep(&state.device, &state.queue);
-    }
+        let origin = &state.origin;
 
-    fn render(&mut self, render_pass: &mut RenderPass, _state: &RendererState, world: &mut World) {
-        let camera = match get_perspective_camera(world) {
-            Some(data) => data.1 .0,
-            None => {
-                log::warn!("Unable to render models - no camera available");
-                return;
+        profiler::profiled_query::<&LineBundle>(world, "line_renderer").for_each(|(_, line)| {
+            match origin.enabled() {
+                true => self.prep_lines(
+                    &line
+                        .lines
+                        .iter()
+                        .map(|line| LineInstance {
+                            pos1: origin.relative_point(line.pos1),
+                            pos2: origin.relative_point(line.pos2),
+                            ..*line
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+                false => self.prep_lines(&line.lines),
             }
-        };
+        });
 
-        Self::render(self, render_pass, camera.bind_group());
+        self.finish_prep(&state.device, &state.queue);
     }
 }
 
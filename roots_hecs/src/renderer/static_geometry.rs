@@ -0,0 +1,290 @@
+//====================================================================
+
+use std::{collections::HashMap, sync::Arc};
+
+use hecs::{Entity, World};
+use roots_common::{
+    spatial::{GlobalTransform, Transform},
+    FastHasher,
+};
+use roots_renderer::{
+    model::{LoadedMesh, ModelVertex},
+    texture::{LoadedTexture, TextureId},
+};
+
+use super::components::Model;
+
+//====================================================================
+
+/// Cpu copy of a mesh's data. Gpu meshes can't be read back, so static geometry keeps
+/// its own copy for baking.
+#[derive(Clone)]
+pub struct MeshData {
+    pub vertices: Arc<[ModelVertex]>,
+    pub indices: Arc<[u32]>,
+}
+
+impl MeshData {
+    #[inline]
+    pub fn new(vertices: impl Into<Arc<[ModelVertex]>>, indices: impl Into<Arc<[u32]>>) -> Self {
+        Self {
+            vertices: vertices.into(),
+            indices: indices.into(),
+        }
+    }
+}
+
+/// Marks a `Model` that never moves, so `bake_static_geometry` can merge it with others.
+/// Holds the data of each of the model's meshes, in the same order as `Model::meshes`.
+pub struct StaticGeometry {
+    pub meshes: Vec<MeshData>,
+}
+
+impl StaticGeometry {
+    #[inline]
+    pub fn new(meshes: impl IntoIterator<Item = MeshData>) -> Self {
+        Self {
+            meshes: meshes.into_iter().collect(),
+        }
+    }
+}
+
+/// A merged mesh created by `bake_static_geometry`.
+pub struct StaticBatch;
+
+/// The `Model` of static geometry that was merged into a `StaticBatch`. Restored by
+/// `unbake_static_geometry`.
+pub struct BakedModel(pub Model);
+
+#[derive(Debug, Clone, Copy)]
+pub struct StaticBakeSettings {
+    /// Vertices closer than this, with matching uvs and normals, are merged.
+    pub weld_epsilon: f32,
+    /// Batches are split before reaching this many vertices. Can't exceed `u32::MAX`.
+    pub max_batch_vertices: u32,
+}
+
+impl Default for StaticBakeSettings {
+    fn default() -> Self {
+        Self {
+            weld_epsilon: 0.0001,
+            // 128MB of vertices, within wgpu's default buffer size limit
+            max_batch_vertices: 1 << 22,
+        }
+    }
+}
+
+//====================================================================
+
+/// Merge every `StaticGeometry` model accepted by `filter` into as few meshes as
+/// possible, one or more per texture and color. Vertices are moved into world space
+/// by each entity's `GlobalTransform`, so call this after transforms have propagated.
+///
+/// A `StaticBatch` entity is spawned for each merged mesh and the original models are
+/// moved into `BakedModel`s, which `unbake_static_geometry` restores. Returns the
+/// merged meshes with their textures.
+pub fn bake_static_geometry(
+    world: &mut World,
+    device: &wgpu::Device,
+    settings: StaticBakeSettings,
+    filter: impl Fn(Entity) -> bool,
+) -> Vec<(LoadedMesh, LoadedTexture)> {
+    let mut batches: HashMap<(TextureId, [u32; 4]), Vec<BatchBuilder>, FastHasher> =
+        HashMap::default();
+    let mut baked = Vec::new();
+
+    world
+        .query_mut::<(&Model, &StaticGeometry, &GlobalTransform)>()
+        .into_iter()
+        .filter(|(entity, _)| filter(*entity))
+        .for_each(|(entity, (model, geometry, global))| {
+            if model.wind.is_some() {
+                log::debug!("Skipping static geometry {:?} - it has wind", entity);
+                return;
+            }
+
+            if model.meshes.len() != geometry.meshes.len() {
+                log::warn!(
+                    "Skipping static geometry {:?} - it has {} meshes but data for {}",
+                    entity,
+                    model.meshes.len(),
+                    geometry.meshes.len()
+                );
+                return;
+            }
+
+            let transform = global.to_matrix();
+            let rotation = glam::Mat3::from_quat(global.to_scale_rotation_translation().1);
+            let color = model.color.map(f32::to_bits);
+
+            model
+                .meshes
+                .iter()
+                .zip(&geometry.meshes)
+                .for_each(|((_, texture), data)| {
+                    let builders = batches.entry((texture.id(), color)).or_default();
+
+                    let needs_split = match builders.last() {
+                        Some(builder) => {
+                            builder.vertices.len() + data.vertices.len()
+                                > settings.max_batch_vertices as usize
+                        }
+                        None => true,
+                    };
+
+                    if needs_split {
+                        builders.push(BatchBuilder::new(texture.clone(), model.color));
+                    }
+
+                    builders.last_mut().unwrap().append(
+                        data,
+                        transform,
+                        rotation,
+                        settings.weld_epsilon,
+                    );
+                });
+
+            baked.push(entity);
+        });
+
+    let merged = batches
+        .into_values()
+        .flatten()
+        .filter(|builder| !builder.indices.is_empty())
+        .map(|builder| {
+            let mesh = LoadedMesh::load_from_data(device, &builder.vertices, &builder.indices);
+
+            world.spawn((
+                Model::new([(mesh.clone(), builder.texture.clone())]).with_color(builder.color),
+                Transform::default(),
+                GlobalTransform::default(),
+                StaticBatch,
+            ));
+
+            (mesh, builder.texture)
+        })
+        .collect::<Vec<_>>();
+
+    baked.into_iter().for_each(|entity| {
+        if let Ok(model) = world.remove_one::<Model>(entity) {
+            world.insert_one(entity, BakedModel(model)).ok();
+        }
+    });
+
+    log::debug!("Baked static geometry into {} meshes", merged.len());
+
+    merged
+}
+
+/// Despawn every `StaticBatch` and restore the models merged into them.
+pub fn unbake_static_geometry(world: &mut World) {
+    let batches = world
+        .query_mut::<&StaticBatch>()
+        .into_iter()
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+
+    batches.into_iter().for_each(|entity| {
+        world.despawn(entity).ok();
+    });
+
+    let baked = world
+        .query_mut::<&BakedModel>()
+        .into_iter()
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+
+    baked.into_iter().for_each(|entity| {
+        if let Ok(BakedModel(model)) = world.remove_one::<BakedModel>(entity) {
+            world.insert_one(entity, model).ok();
+        }
+    });
+}
+
+//====================================================================
+
+struct BatchBuilder {
+    texture: LoadedTexture,
+    color: [f32; 4],
+    vertices: Vec<ModelVertex>,
+    indices: Vec<u32>,
+    welded: HashMap<WeldKey, u32, FastHasher>,
+}
+
+/// Vertex attributes snapped to a grid, so nearby vertices share a key.
+#[derive(PartialEq, Eq, Hash)]
+struct WeldKey {
+    pos: glam::I64Vec3,
+    uv: glam::IVec2,
+    normal: glam::IVec3,
+    tangent: glam::IVec4,
+}
+
+impl BatchBuilder {
+    fn new(texture: LoadedTexture, color: [f32; 4]) -> Self {
+        Self {
+            texture,
+            color,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            welded: HashMap::default(),
+        }
+    }
+
+    fn append(
+        &mut self,
+        data: &MeshData,
+        transform: glam::Mat4,
+        rotation: glam::Mat3,
+        weld_epsilon: f32,
+    ) {
+        let snap = 1. / weld_epsilon.max(f32::EPSILON);
+
+        let remap = data
+            .vertices
+            .iter()
+            .map(|vertex| {
+                let vertex = ModelVertex {
+                    pos: transform.transform_point3(vertex.pos),
+                    uv: vertex.uv,
+                    normal: (rotation * vertex.normal).normalize_or_zero(),
+                    tangent: (rotation * vertex.tangent.truncate())
+                        .normalize_or_zero()
+                        .extend(vertex.tangent.w),
+                };
+
+                let key = WeldKey {
+                    pos: (vertex.pos * snap).round().as_i64vec3(),
+                    uv: (vertex.uv * snap).round().as_ivec2(),
+                    normal: (vertex.normal * snap).round().as_ivec3(),
+                    tangent: (vertex.tangent * snap).round().as_ivec4(),
+                };
+
+                *self.welded.entry(key).or_insert_with(|| {
+                    self.vertices.push(vertex);
+                    self.vertices.len() as u32 - 1
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let area_epsilon = weld_epsilon * weld_epsilon;
+
+        data.indices.chunks_exact(3).for_each(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|index| remap[triangle[index] as usize]);
+
+            if a == b || b == c || a == c {
+                return;
+            }
+
+            let [pos_a, pos_b, pos_c] = [a, b, c].map(|index| self.vertices[index as usize].pos);
+            if (pos_b - pos_a).cross(pos_c - pos_a).length_squared() <= area_epsilon * area_epsilon
+            {
+                return;
+            }
+
+            self.indices.extend([a, b, c]);
+        });
+    }
+}
+
+//====================================================================
@@ -0,0 +1,592 @@
+//====================================================================
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::BuildHasher,
+};
+
+use hecs::{Component, Entity, World};
+use roots_common::FastHasher;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+//====================================================================
+
+pub type ComponentId = u16;
+
+/// Stable identity of an entity shared between peers. The top 16 bits are the peer
+/// that assigned it, so peers can't hand out the same id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NetworkId(pub u64);
+
+impl NetworkId {
+    const INDEX_BITS: u32 = 48;
+
+    #[inline]
+    pub fn new(peer_id: u16, index: u64) -> Self {
+        Self(((peer_id as u64) << Self::INDEX_BITS) | (index & ((1 << Self::INDEX_BITS) - 1)))
+    }
+
+    /// The peer that assigned the id.
+    #[inline]
+    pub fn peer_id(&self) -> u16 {
+        (self.0 >> Self::INDEX_BITS) as u16
+    }
+
+    #[inline]
+    pub fn index(&self) -> u64 {
+        self.0 & ((1 << Self::INDEX_BITS) - 1)
+    }
+}
+
+//--------------------------------------------------
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    entities: Vec<EntitySnapshot>,
+    /// Components removed from entities that are still replicated. Only sent in deltas.
+    removed: Vec<(NetworkId, ComponentId)>,
+    despawned: Vec<NetworkId>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntitySnapshot {
+    id: NetworkId,
+    components: Vec<(ComponentId, Vec<u8>)>,
+}
+
+/// Entities and hashes of the components sent in the previous delta snapshot.
+#[derive(Default)]
+pub struct DeltaState {
+    sent: HashSet<NetworkId, FastHasher>,
+    hashes: HashMap<(NetworkId, ComponentId), u64, FastHasher>,
+}
+
+impl DeltaState {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all previously sent data so the next delta contains everything.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.sent.clear();
+        self.hashes.clear();
+    }
+}
+
+//====================================================================
+
+type ExtractFn = Box<dyn Fn(&World, Entity) -> Option<bincode::Result<Vec<u8>>> + Send + Sync>;
+type ApplyFn = Box<dyn Fn(&mut World, Entity, &[u8]) -> bincode::Result<()> + Send + Sync>;
+type RemoveFn = Box<dyn Fn(&mut World, Entity) + Send + Sync>;
+
+struct Registration {
+    id: ComponentId,
+    name: &'static str,
+    extract: ExtractFn,
+    apply: ApplyFn,
+    remove: RemoveFn,
+}
+
+/// Component types that opt in to replication. Both peers must register the
+/// same types with the same ids.
+#[derive(Default)]
+pub struct ReplicationRegistry {
+    registrations: Vec<Registration>,
+    peer_id: u16,
+    next_network_id: u64,
+}
+
+impl ReplicationRegistry {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Namespace the `NetworkId`s this registry assigns. Every peer that assigns ids
+    /// needs its own peer id, such as 0 for the host and a connection index for each
+    /// client. Defaults to 0.
+    #[inline]
+    pub fn with_peer_id(mut self, peer_id: u16) -> Self {
+        self.peer_id = peer_id;
+        self
+    }
+
+    #[inline]
+    pub fn peer_id(&self) -> u16 {
+        self.peer_id
+    }
+
+    /// Register a component that is serialized as is.
+    pub fn register<T>(&mut self, id: ComponentId) -> &mut Self
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        self.add_registration(Registration {
+            id,
+            name: std::any::type_name::<T>(),
+            extract: Box::new(|world, entity| {
+                world
+                    .get::<&T>(entity)
+                    .ok()
+                    .map(|component| bincode::serialize(&*component))
+            }),
+            apply: Box::new(|world, entity, bytes| {
+                let component = bincode::deserialize::<T>(bytes)?;
+                let _ = world.insert_one(entity, component);
+                Ok(())
+            }),
+            remove: Box::new(|world, entity| {
+                let _ = world.remove_one::<T>(entity);
+            }),
+        })
+    }
+
+    /// Register a component that is replicated through an intermediate type.
+    /// Useful for components holding local resources (such as a `Sprite` texture)
+    /// which need to be resolved on the receiving side in `apply`.
+    pub fn register_with<T, D>(
+        &mut self,
+        id: ComponentId,
+        to_data: impl Fn(&T) -> D + Send + Sync + 'static,
+        apply: impl Fn(&mut World, Entity, D) + Send + Sync + 'static,
+    ) -> &mut Self
+    where
+        T: Component,
+        D: Serialize + DeserializeOwned,
+    {
+        self.add_registration(Registration {
+            id,
+            name: std::any::type_name::<T>(),
+            extract: Box::new(move |world, entity| {
+                world
+                    .get::<&T>(entity)
+                    .ok()
+                    .map(|component| bincode::serialize(&to_data(&component)))
+            }),
+            apply: Box::new(move |world, entity, bytes| {
+                let data = bincode::deserialize::<D>(bytes)?;
+                apply(world, entity, data);
+                Ok(())
+            }),
+            remove: Box::new(|world, entity| {
+                let _ = world.remove_one::<T>(entity);
+            }),
+        })
+    }
+
+    fn add_registration(&mut self, registration: Registration) -> &mut Self {
+        if let Some(index) = self
+            .registrations
+            .iter()
+            .position(|existing| existing.id == registration.id)
+        {
+            log::warn!(
+                "Replication component id {} already registered for '{}' - replacing with '{}'",
+                registration.id,
+                self.registrations[index].name,
+                registration.name
+            );
+            self.registrations.remove(index);
+        }
+
+        self.registrations.push(registration);
+        self
+    }
+
+    //--------------------------------------------------
+
+    /// Give an entity a `NetworkId` (if it doesn't have one) so it is included in snapshots.
+    pub fn assign_network_id(&mut self, world: &mut World, entity: Entity) -> NetworkId {
+        if let Ok(id) = world.get::<&NetworkId>(entity) {
+            return *id;
+        }
+
+        let id = NetworkId::new(self.peer_id, self.next_network_id);
+        self.next_network_id += 1;
+
+        if world.insert_one(entity, id).is_err() {
+            log::warn!("Unable to assign network id to missing entity {:?}", entity);
+        }
+
+        id
+    }
+
+    //--------------------------------------------------
+
+    /// Serialize all registered components of entities with a `NetworkId`.
+    pub fn snapshot(
+        &self,
+        world: &World,
+        filter: impl FnMut(Entity) -> bool,
+    ) -> bincode::Result<Vec<u8>> {
+        self.build_snapshot(world, filter, None)
+    }
+
+    /// Like `snapshot` but only includes components that changed since the previous
+    /// delta, components that have since been removed and the ids of entities that are
+    /// no longer present. `previous` is only updated if the delta is serialized.
+    pub fn snapshot_delta(
+        &self,
+        world: &World,
+        filter: impl FnMut(Entity) -> bool,
+        previous: &mut DeltaState,
+    ) -> bincode::Result<Vec<u8>> {
+        self.build_snapshot(world, filter, Some(previous))
+    }
+
+    fn build_snapshot(
+        &self,
+        world: &World,
+        mut filter: impl FnMut(Entity) -> bool,
+        previous: Option<&mut DeltaState>,
+    ) -> bincode::Result<Vec<u8>> {
+        let networked = world
+            .query::<&NetworkId>()
+            .iter()
+            .filter(|(entity, _)| filter(*entity))
+            .map(|(entity, id)| (entity, *id))
+            .collect::<Vec<_>>();
+
+        let current = networked
+            .iter()
+            .map(|(_, id)| *id)
+            .collect::<HashSet<_, FastHasher>>();
+
+        let mut hashes = HashMap::<_, _, FastHasher>::default();
+
+        let entities = networked
+            .into_iter()
+            .filter_map(|(entity, id)| {
+                let components = self
+                    .registrations
+                    .iter()
+                    .filter_map(|registration| {
+                        let bytes = match (registration.extract)(world, entity)? {
+                            Ok(bytes) => bytes,
+                            Err(e) => {
+                                log::warn!("Unable to serialize '{}': {}", registration.name, e);
+                                return None;
+                            }
+                        };
+
+                        let key = (id, registration.id);
+                        let hash = FastHasher::default().hash_one(&bytes);
+                        hashes.insert(key, hash);
+
+                        match previous
+                            .as_ref()
+                            .and_then(|previous| previous.hashes.get(&key))
+                        {
+                            Some(old) if *old == hash => None,
+                            _ => Some((registration.id, bytes)),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                // New entities are sent once even without any registered components
+                let known = previous
+                    .as_ref()
+                    .is_some_and(|previous| previous.sent.contains(&id));
+
+                match components.is_empty() && known {
+                    true => None,
+                    false => Some(EntitySnapshot { id, components }),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let (removed, despawned) = match previous.as_ref() {
+            Some(previous) => {
+                let (mut removed, mut despawned) = (Vec::new(), Vec::new());
+
+                previous
+                    .hashes
+                    .keys()
+                    .filter(|(id, _)| current.contains(id))
+                    .filter(|key| !hashes.contains_key(key))
+                    .for_each(|key| removed.push(*key));
+
+                previous
+                    .sent
+                    .iter()
+                    .filter(|id| !current.contains(id))
+                    .for_each(|id| despawned.push(*id));
+
+                removed.sort_by_key(|(id, component_id)| (id.0, *component_id));
+                despawned.sort_by_key(|id| id.0);
+
+                (removed, despawned)
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+
+        let bytes = bincode::serialize(&Snapshot {
+            entities,
+            removed,
+            despawned,
+        })?;
+
+        if let Some(previous) = previous {
+            previous.sent = current;
+            previous.hashes = hashes;
+        }
+
+        Ok(bytes)
+    }
+
+    //--------------------------------------------------
+
+    /// Create or update entities from a (full or delta) snapshot. `id_map` links network
+    /// ids to local entities and is updated as entities are spawned and despawned.
+    pub fn apply_snapshot(
+        &self,
+        world: &mut World,
+        bytes: &[u8],
+        id_map: &mut HashMap<NetworkId, Entity>,
+    ) -> bincode::Result<()> {
+        let snapshot = bincode::deserialize::<Snapshot>(bytes)?;
+
+        snapshot.despawned.into_iter().for_each(|id| {
+            if let Some(entity) = id_map.remove(&id) {
+                let _ = world.despawn(entity);
+            }
+        });
+
+        snapshot.removed.into_iter().for_each(|(id, component_id)| {
+            let Some(entity) = id_map.get(&id) else {
+                return;
+            };
+
+            if let Some(registration) = self
+                .registrations
+                .iter()
+                .find(|registration| registration.id == component_id)
+            {
+                (registration.remove)(world, *entity);
+            }
+        });
+
+        snapshot
+            .entities
+            .into_iter()
+            .try_for_each(|entity_snapshot| {
+                let entity = match id_map.get(&entity_snapshot.id) {
+                    Some(entity) if world.contains(*entity) => *entity,
+                    _ => {
+                        let entity = world.spawn((entity_snapshot.id,));
+                        id_map.insert(entity_snapshot.id, entity);
+                        entity
+                    }
+                };
+
+                entity_snapshot
+                    .components
+                    .into_iter()
+                    .try_for_each(|(component_id, bytes)| {
+                        match self
+                            .registrations
+                            .iter()
+                            .find(|registration| registration.id == component_id)
+                        {
+                            Some(registration) => (registration.apply)(world, entity, &bytes),
+                            None => {
+                                log::warn!("Received unregistered component id {}", component_id);
+                                Ok(())
+                            }
+                        }
+                    })
+            })
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3, Vec4};
+    use roots_common::spatial::Transform;
+    use roots_renderer::{shared::SharedRenderResources, texture::LoadedTexture};
+
+    use super::*;
+    use crate::{renderer::components::Sprite, test_utils};
+
+    #[derive(Serialize, Deserialize)]
+    struct SpriteData {
+        size: Vec2,
+        pos: Vec3,
+        color: Vec4,
+    }
+
+    fn registry(texture: LoadedTexture) -> ReplicationRegistry {
+        let mut registry = ReplicationRegistry::new();
+        registry
+            .register::<Transform>(0)
+            .register_with::<Sprite, _>(
+                1,
+                |sprite| SpriteData {
+                    size: sprite.size,
+                    pos: sprite.pos,
+                    color: sprite.color,
+                },
+                move |world, entity, data| {
+                    let _ = world.insert_one(
+                        entity,
+                        Sprite {
+                            texture: texture.clone(),
+                            size: data.size,
+                            pos: data.pos,
+                            color: data.color,
+                        },
+                    );
+                },
+            );
+        registry
+    }
+
+    fn sprite_data(world: &World, entity: Entity) -> (Vec2, Vec3, Vec4) {
+        let sprite = world.get::<&Sprite>(entity).unwrap();
+        (sprite.size, sprite.pos, sprite.color)
+    }
+
+    #[test]
+    fn loopback_transform_and_sprite() {
+        let Some((device, queue)) = test_utils::device() else {
+            return;
+        };
+        let shared = SharedRenderResources::new(&device);
+        let texture = LoadedTexture::load_blank(&device, &queue, &shared);
+
+        let mut sender = registry(texture.clone());
+        let receiver = registry(texture.clone());
+
+        let mut sender_world = World::new();
+        let mut receiver_world = World::new();
+        let mut delta = DeltaState::new();
+        let mut id_map = HashMap::new();
+
+        let transform = Transform::from_translation(Vec3::new(1., 2., 3.));
+        let sprite = Sprite {
+            texture: texture.clone(),
+            size: Vec2::new(32., 16.),
+            pos: Vec3::new(0., 0., -1.),
+            color: Vec4::new(1., 0.5, 0.25, 1.),
+        };
+
+        let a = sender_world.spawn((transform.clone(), sprite));
+        let b = sender_world.spawn((Transform::default(),));
+        let a_id = sender.assign_network_id(&mut sender_world, a);
+        let b_id = sender.assign_network_id(&mut sender_world, b);
+
+        // Full sync
+        let bytes = sender
+            .snapshot_delta(&sender_world, |_| true, &mut delta)
+            .unwrap();
+        receiver
+            .apply_snapshot(&mut receiver_world, &bytes, &mut id_map)
+            .unwrap();
+
+        let received = id_map[&a_id];
+        assert_eq!(
+            *receiver_world.get::<&Transform>(received).unwrap(),
+            transform
+        );
+        assert_eq!(
+            sprite_data(&receiver_world, received),
+            sprite_data(&sender_world, a)
+        );
+        assert!(receiver_world.get::<&Sprite>(id_map[&b_id]).is_err());
+
+        // Delta only contains the changed transform
+        sender_world.get::<&mut Transform>(a).unwrap().translation.x = 10.;
+
+        let bytes = sender
+            .snapshot_delta(&sender_world, |_| true, &mut delta)
+            .unwrap();
+        let snapshot = bincode::deserialize::<Snapshot>(&bytes).unwrap();
+        assert_eq!(snapshot.entities.len(), 1);
+        assert_eq!(snapshot.entities[0].components.len(), 1);
+
+        receiver
+            .apply_snapshot(&mut receiver_world, &bytes, &mut id_map)
+            .unwrap();
+        assert_eq!(
+            receiver_world
+                .get::<&Transform>(received)
+                .unwrap()
+                .translation
+                .x,
+            10.
+        );
+
+        // Removed components
+        sender_world.remove_one::<Sprite>(a).unwrap();
+
+        let bytes = sender
+            .snapshot_delta(&sender_world, |_| true, &mut delta)
+            .unwrap();
+        receiver
+            .apply_snapshot(&mut receiver_world, &bytes, &mut id_map)
+            .unwrap();
+        assert!(receiver_world.get::<&Sprite>(received).is_err());
+        assert!(receiver_world.get::<&Transform>(received).is_ok());
+
+        // New entities without registered components are sent once
+        let c = sender_world.spawn(());
+        let c_id = sender.assign_network_id(&mut sender_world, c);
+
+        let bytes = sender
+            .snapshot_delta(&sender_world, |_| true, &mut delta)
+            .unwrap();
+        let snapshot = bincode::deserialize::<Snapshot>(&bytes).unwrap();
+        assert_eq!(snapshot.entities.len(), 1);
+        assert_eq!(snapshot.entities[0].id, c_id);
+        assert!(snapshot.entities[0].components.is_empty());
+
+        receiver
+            .apply_snapshot(&mut receiver_world, &bytes, &mut id_map)
+            .unwrap();
+        assert!(receiver_world.contains(id_map[&c_id]));
+
+        let bytes = sender
+            .snapshot_delta(&sender_world, |_| true, &mut delta)
+            .unwrap();
+        let snapshot = bincode::deserialize::<Snapshot>(&bytes).unwrap();
+        assert!(snapshot.entities.is_empty());
+
+        // Despawned entities, including ones without registered components
+        sender_world.despawn(b).unwrap();
+        sender_world.despawn(c).unwrap();
+
+        let bytes = sender
+            .snapshot_delta(&sender_world, |_| true, &mut delta)
+            .unwrap();
+        let received_b = id_map[&b_id];
+        let received_c = id_map[&c_id];
+        receiver
+            .apply_snapshot(&mut receiver_world, &bytes, &mut id_map)
+            .unwrap();
+        assert!(!receiver_world.contains(received_b));
+        assert!(!receiver_world.contains(received_c));
+        assert!(!id_map.contains_key(&b_id));
+        assert!(!id_map.contains_key(&c_id));
+        assert_eq!(receiver_world.len(), 1);
+    }
+
+    #[test]
+    fn network_ids_are_namespaced_by_peer() {
+        let mut host = ReplicationRegistry::new();
+        let mut client = ReplicationRegistry::new().with_peer_id(1);
+        let mut world = World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+
+        let host_id = host.assign_network_id(&mut world, a);
+        let client_id = client.assign_network_id(&mut world, b);
+
+        assert_ne!(host_id, client_id);
+        assert_eq!((host_id.peer_id(), host_id.index()), (0, 0));
+        assert_eq!((client_id.peer_id(), client_id.index()), (1, 0));
+    }
+}
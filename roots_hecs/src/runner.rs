@@ -1,22 +1,120 @@
 //====================================================================
 
-use roots_common::input;
+use std::time::Duration;
+
+use roots_renderer::watchdog::WatchdogPhase;
 use roots_runner::{
-    prelude::StartCause, window::Window, winit::event_loop::ControlFlow, WindowInputEvent,
+    prelude::{StartCause, WindowEvent},
+    window::Window,
+    winit::event_loop::ControlFlow,
 };
 
-use crate::{HecsApp, State, StateOuter};
+use web_time::Instant;
+
+use crate::{BackgroundBehavior, HecsApp, RedrawMode, State, StateOuter};
 
 //====================================================================
 
+impl<A: HecsApp> StateOuter<A> {
+    #[inline]
+    fn request_redraw(&self) {
+        if let Some(window) = &self.state.window {
+            window.inner().request_redraw();
+        }
+    }
+
+    /// Render the frame ticked by `tick` and decide when the next one is drawn.
+    fn finish_frame(&mut self, event_loop: &roots_runner::prelude::ActiveEventLoop) {
+        if std::mem::take(&mut self.render_pending) {
+            self.state.apply_late_latch();
+        }
+
+        self.state
+            .renderer
+            .advance_shader_time(self.state.time.delta_seconds());
+        self.state.renderer.run_frame(&mut self.state.world);
+        self.state.profile_frame();
+
+        self.state.reset_inputs();
+        self.state.clear_changes();
+
+        let splash_showing = self.state.renderer.splash_showing();
+        self.state.set_animation_active("splash", splash_showing);
+
+        // Sleep until the next event. Animations resume from a clamped delta.
+        if self.state.redraw_mode == RedrawMode::Reactive && !self.state.needs_redraw() {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            self.state.idle = true;
+            self.state.clamp_next_delta = true;
+        }
+    }
+}
+
 impl<A: HecsApp> roots_runner::RunnerState for StateOuter<A> {
     fn new(event_loop: &roots_runner::prelude::ActiveEventLoop) -> Self {
+        A::coordinate_convention().set_active();
+
         let window = Window::new(event_loop, None);
-        let mut state = State::new(window);
+        let mut state = State::new(window, &A::render_core_config());
+
+        let depth_convention = A::depth_convention();
+        if *state.renderer.depth_convention() != depth_convention {
+            state.renderer.set_depth_convention(depth_convention);
+        }
+
+        if let Some(splash) = A::splash() {
+            state.renderer.show_splash(&splash);
+        }
 
         let app = A::new(&mut state);
 
-        Self { state, app }
+        if !state.renderer.splash_held() {
+            state.renderer.finish_splash();
+        }
+
+        Self {
+            state,
+            app,
+            render_pending: false,
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        _event_loop: &roots_runner::prelude::ActiveEventLoop,
+        _window_id: roots_runner::prelude::WindowId,
+        event: &WindowEvent,
+    ) {
+        let was_background = self.state.background_reason().is_some();
+
+        match event {
+            WindowEvent::Focused(focused) => self.state.focused = *focused,
+            WindowEvent::Occluded(occluded) => self.state.occluded = *occluded,
+            WindowEvent::ScaleFactorChanged { .. } => {
+                self.state.request_redraw();
+                return;
+            }
+            // The window may have moved to a monitor with a different refresh rate
+            WindowEvent::Moved(_) => {
+                self.state.match_monitor_refresh();
+                return;
+            }
+            // Expose events from the OS arrive as RedrawRequested and tick directly
+            _ => return,
+        }
+
+        match (was_background, self.state.background_reason()) {
+            (false, Some(reason)) => log::debug!("App moved to background ({:?})", reason),
+
+            (true, None) => {
+                log::debug!("App returned to foreground");
+                self.state.clamp_next_delta = true;
+                self.state.renderer.set_paused(false);
+                self.request_redraw();
+            }
+
+            _ => {}
+        }
     }
 
     fn new_events(
@@ -25,48 +123,120 @@ impl<A: HecsApp> roots_runner::RunnerState for StateOuter<A> {
         cause: roots_runner::prelude::StartCause,
     ) {
         if let StartCause::ResumeTimeReached { .. } = cause {
-            self.state.window.inner().request_redraw();
+            self.request_redraw();
         }
     }
 
+    #[inline]
     fn input_event(&mut self, event: roots_runner::WindowInputEvent) {
-        match event {
-            WindowInputEvent::KeyInput { key, pressed } => {
-                input::process_inputs(&mut self.state.keys, key, pressed)
-            }
-            WindowInputEvent::MouseInput { button, pressed } => {
-                input::process_inputs(&mut self.state.mouse_buttons, button, pressed)
-            }
-            WindowInputEvent::CursorMoved { position } => {
-                input::process_mouse_position(&mut self.state.mouse_input, position)
-            }
-            WindowInputEvent::CursorEntered => {}
-            WindowInputEvent::CursorLeft => {}
-            WindowInputEvent::MouseWheel { delta } => {
-                input::process_mouse_scroll(&mut self.state.mouse_input, delta)
-            }
-            WindowInputEvent::MouseMotion { delta } => {
-                input::process_mouse_motion(&mut self.state.mouse_input, delta)
-            }
-        }
+        self.state.inject_input(event);
     }
 
     fn resized(&mut self, new_size: roots_common::Size<u32>) {
         log::debug!("Resizing window. New size = {}", new_size);
+        // The app sees the fitted cameras, so it can still override their aspect
+        self.state.inject_resize(new_size);
         self.app.resize(&mut self.state, new_size);
-        self.state.renderer.resize(new_size);
+        self.state.request_redraw();
+    }
+
+    fn close_requested(&mut self, event_loop: &roots_runner::prelude::ActiveEventLoop) {
+        log::info!("Close requested. Closing App.");
+        self.state.renderer.save_pipeline_cache();
+        event_loop.exit();
+    }
+
+    // The surface follows the physical size from the resize sent after this
+    #[inline]
+    fn scale_factor_changed(&mut self, scale_factor: f64) {
+        log::debug!("Window scale factor changed to {}", scale_factor);
     }
 
     fn tick(&mut self, event_loop: &roots_runner::prelude::ActiveEventLoop) {
-        event_loop.set_control_flow(ControlFlow::wait_duration(self.state.target_fps));
+        // A second redraw before the deferred frame rendered
+        if self.render_pending {
+            self.finish_frame(event_loop);
+        }
+
+        self.state.idle = false;
+
+        let background = self.state.background_reason();
+
+        let behavior = match background {
+            Some(_) => self.state.background_behavior,
+            None => BackgroundBehavior::Continue,
+        };
+
+        // Only reported when the background behavior actually slows the app down
+        let throttled = background.filter(|_| behavior != BackgroundBehavior::Continue);
+
+        match behavior {
+            BackgroundBehavior::Continue | BackgroundBehavior::PauseRendering => {
+                event_loop.set_control_flow(ControlFlow::wait_duration(self.state.target_fps))
+            }
+
+            BackgroundBehavior::ThrottleTo(fps) => {
+                event_loop.set_control_flow(ControlFlow::wait_duration(
+                    Duration::from_secs_f32(1. / fps.max(0.1)).max(self.state.target_fps),
+                ))
+            }
+
+            // Wait for a focus event to request the next redraw
+            BackgroundBehavior::PauseAll => {
+                event_loop.set_control_flow(ControlFlow::Wait);
+                roots_common::set_throttled(&mut self.state.time, throttled);
+                return;
+            }
+        }
+
+        self.state
+            .renderer
+            .set_paused(behavior == BackgroundBehavior::PauseRendering);
 
         roots_common::tick_time(&mut self.state.time);
+        roots_common::set_throttled(&mut self.state.time, throttled);
+
+        if self.state.clamp_next_delta {
+            roots_common::clamp_delta(&mut self.state.time, self.state.target_fps);
+            self.state.clamp_next_delta = false;
+        }
 
+        self.state.redraw_requested = false;
+
+        // Started before the tick so it's included in the frame
+        self.state.renderer.watchdog.start_frame();
+
+        let late_latch = self.state.late_latch.is_active();
+        if late_latch {
+            roots_common::input::begin_tick_motion(&mut self.state.mouse_input);
+        }
+
+        let start = Instant::now();
         self.app.tick(&mut self.state);
+        self.state
+            .renderer
+            .watchdog
+            .record(WatchdogPhase::Tick, start.elapsed());
+
+        let start = Instant::now();
+        self.state.run_tasks();
+        self.state
+            .renderer
+            .watchdog
+            .record(WatchdogPhase::Tasks, start.elapsed());
 
-        roots_common::input::reset_input(&mut self.state.keys);
-        roots_common::input::reset_input(&mut self.state.mouse_buttons);
-        roots_common::input::reset_mouse_input(&mut self.state.mouse_input);
+        // Render once the events queued during the tick are handled, so the motion
+        // they carry can be latched
+        match late_latch {
+            true => self.render_pending = true,
+            false => self.finish_frame(event_loop),
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &roots_runner::prelude::ActiveEventLoop) {
+        if self.render_pending {
+            self.finish_frame(event_loop);
+        }
     }
 }
 
@@ -0,0 +1,416 @@
+//====================================================================
+
+use hecs::World;
+use roots_common::{coords::WindowPx, Size};
+use roots_pipelines::{
+    manager::{RenderContext, RenderPipeline},
+    texture2d_renderer::{Texture2dRenderer, TextureData},
+};
+use roots_renderer::{texture::LoadedTexture, RenderPass};
+use roots_runner::prelude::{KeyCode, MouseButton};
+use roots_text::selection::{self, Selection};
+use web_time::{Duration, Instant};
+
+use crate::{
+    actions::Modifiers,
+    renderer::pipelines::Pipeline,
+    text::{Rect, TextArea},
+    RendererState, State,
+};
+
+//====================================================================
+
+/// Clicks closer together than this select a whole word.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// Lets the text of a `TextArea` on the same entity be selected with the mouse and
+/// copied with Ctrl+C. Updated by `process_selectable_text`.
+///
+/// Dragging selects, shift-clicking extends the selection and double-clicking selects
+/// a word. Highlights are drawn by the `SelectionRenderer`.
+#[derive(Debug, Clone, Default)]
+pub struct SelectableText {
+    /// The theme's primary color at half opacity if `None`.
+    pub highlight_color: Option<glam::Vec4>,
+
+    selection: Option<Selection>,
+    dragging: bool,
+    last_click: Option<Instant>,
+    copied: Option<String>,
+    /// Screen space highlight rects from the last update.
+    rects: Vec<Rect>,
+}
+
+impl SelectableText {
+    #[inline]
+    pub fn with_highlight_color(mut self, color: glam::Vec4) -> Self {
+        self.highlight_color = Some(color);
+        self
+    }
+
+    #[inline]
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    #[inline]
+    pub fn set_selection(&mut self, selection: Option<Selection>) {
+        self.selection = selection;
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.selection = None;
+        self.dragging = false;
+    }
+
+    /// Whether a drag selection is in progress.
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// The selected text of `area`. Empty without a selection.
+    pub fn selected_text(&self, area: &TextArea) -> String {
+        match self.selection {
+            Some(selection) => selection::selected_text(area.buffer(), &selection),
+            None => String::new(),
+        }
+    }
+
+    /// Text copied with Ctrl+C since the last call. Also written to the system
+    /// clipboard with the `clipboard` feature.
+    #[inline]
+    pub fn take_copied(&mut self) -> Option<String> {
+        self.copied.take()
+    }
+
+    /// Highlight rects in screen pixels from the top left of the window.
+    #[inline]
+    pub fn highlight_rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    fn update(&mut self, state: &State, area: &TextArea) {
+        let Some(rect) = area.resolved_rect() else {
+            self.clear();
+            self.rects.clear();
+            return;
+        };
+
+        let mouse = state.mouse_input.position();
+        let modifiers = Modifiers::held(&state.keys);
+        let local = mouse.0 - rect.position;
+        let inside = local.cmpge(glam::Vec2::ZERO).all() && local.cmplt(rect.size).all();
+
+        if state.mouse_buttons.just_pressed(MouseButton::Left) {
+            match inside {
+                true => self.click(area, local, modifiers.shift),
+                false => self.clear(),
+            }
+        }
+
+        if self.dragging {
+            match state.mouse_buttons.pressed(MouseButton::Left) {
+                true => {
+                    // Keep hitting the text while dragging outside of it
+                    let local = local.clamp(glam::Vec2::ZERO, rect.size);
+
+                    if let (Some(selection), Some(cursor)) = (
+                        self.selection.as_mut(),
+                        selection::hit_position(area.buffer(), local),
+                    ) {
+                        selection.focus = cursor;
+                    }
+                }
+                false => self.dragging = false,
+            }
+        }
+
+        if modifiers.ctrl && state.keys.just_pressed(KeyCode::KeyC) {
+            let text = self.selected_text(area);
+
+            if !text.is_empty() {
+                #[cfg(feature = "clipboard")]
+                copy_to_clipboard(&text);
+
+                self.copied = Some(text);
+            }
+        }
+
+        self.rects = match &self.selection {
+            Some(selection) => selection::selection_rects(area.buffer(), selection)
+                .into_iter()
+                .map(|(position, size)| Rect::new(rect.position + position, size))
+                .collect(),
+            None => Vec::new(),
+        };
+    }
+
+    fn click(&mut self, area: &TextArea, local: glam::Vec2, shift: bool) {
+        let Some(cursor) = selection::hit_position(area.buffer(), local) else {
+            self.clear();
+            return;
+        };
+
+        let now = Instant::now();
+        let double_click = self
+            .last_click
+            .is_some_and(|last| now.duration_since(last) < DOUBLE_CLICK_TIME);
+
+        self.last_click = match double_click {
+            // A third click starts over rather than selecting again
+            true => None,
+            false => Some(now),
+        };
+
+        match (self.selection.as_mut(), shift, double_click) {
+            (Some(selection), true, _) => selection.focus = cursor,
+            (_, _, true) => {
+                self.selection = Some(selection::word_at(area.buffer(), cursor));
+                self.dragging = false;
+                return;
+            }
+            _ => self.selection = Some(Selection::new(cursor)),
+        }
+
+        self.dragging = true;
+    }
+}
+
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) {
+    let result = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text));
+
+    if let Err(e) = result {
+        log::warn!("Failed to copy selected text to the clipboard: {}", e);
+    }
+}
+
+//====================================================================
+
+/// Update the selection of every `SelectableText` from this frame's mouse and key input.
+/// Call after `process_text_areas`, so the text and rects are up to date.
+pub fn process_selectable_text(state: &mut State) {
+    let mut query = state.world.query::<(&mut SelectableText, &TextArea)>();
+
+    query.iter().for_each(|(_, (selectable, area))| {
+        selectable.update(state, area);
+    });
+}
+
+//====================================================================
+
+/// Draws the highlights of every `SelectableText` in screen space. Add it with a lower
+/// priority than the pipeline drawing the text, so highlights are behind the glyphs.
+pub struct SelectionRenderer {
+    renderer: Texture2dRenderer,
+    texture: LoadedTexture,
+}
+
+impl RenderPipeline for SelectionRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        self.renderer.render(render_pass, context.screen_camera);
+    }
+
+    #[inline]
+    fn needs_depth(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn stereo(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn screen_space(&self) -> bool {
+        true
+    }
+}
+
+impl Pipeline for SelectionRenderer {
+    fn new(state: &RendererState) -> Self {
+        Self {
+            renderer: Texture2dRenderer::new_with_blend(
+                &state.device,
+                &state.config,
+                &state.shared,
+                false,
+                wgpu::BlendState::ALPHA_BLENDING,
+            ),
+            texture: LoadedTexture::load_blank(&state.device, &state.queue, &state.shared),
+        }
+    }
+
+    fn prep(&mut self, state: &RendererState, world: &mut World) {
+        let window = Size::new(state.config.width, state.config.height);
+
+        world
+            .query_mut::<&SelectableText>()
+            .into_iter()
+            .for_each(|(_, selectable)| {
+                selectable.rects.iter().for_each(|rect| {
+                    let center = WindowPx(rect.position + rect.size / 2.).to_screen_space(window);
+
+                    self.renderer.prep_texture(TextureData {
+                        texture: &self.texture,
+                        size: rect.size,
+                        pos: center.0.extend(1.),
+                        color: selectable
+                            .highlight_color
+                            .unwrap_or(state.theme().primary.with_w(0.5)),
+                    });
+                });
+            });
+
+        self.renderer.finish_prep(&state.device, &state.queue);
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use roots_common::input::{process_inputs, process_mouse_position, reset_input};
+    use roots_text::shared::TextResources;
+
+    use super::*;
+    use crate::{
+        test_utils,
+        text::{process_text_areas, TextRect},
+    };
+
+    const TEXT: &str = "hello world\nsecond line\nthird";
+    const AREA: Rect = Rect {
+        position: glam::Vec2::new(50., 40.),
+        size: glam::Vec2::new(300., 200.),
+    };
+    const LINE_HEIGHT: f32 = 20.;
+
+    /// A state with one selectable text area, or `None` without a gpu or fonts.
+    fn setup() -> Option<(State, hecs::Entity)> {
+        let mut state = test_utils::state(400, 300)?;
+        let mut text = TextResources::new(&state.renderer.device, &state.renderer.shared);
+
+        if text.font_system.db().faces().next().is_none() {
+            println!("No system fonts available - skipping");
+            return None;
+        }
+
+        let area = TextArea::new(&mut text.font_system, TEXT, TextRect::Pixels(AREA))
+            .with_font_size(16., LINE_HEIGHT / 16.);
+        let entity = state.world.spawn((SelectableText::default(), area));
+
+        process_text_areas(&mut state, &mut text);
+        Some((state, entity))
+    }
+
+    /// Move the mouse to `local`, relative to the area, and update the selection.
+    fn frame(state: &mut State, local: glam::Vec2, pressed: Option<bool>) {
+        let position = AREA.position + local;
+        process_mouse_position(
+            &mut state.mouse_input,
+            (position.x as f64, position.y as f64),
+        );
+
+        if let Some(pressed) = pressed {
+            process_inputs(&mut state.mouse_buttons, MouseButton::Left, pressed);
+        }
+
+        process_selectable_text(state);
+        reset_input(&mut state.mouse_buttons);
+        reset_input(&mut state.keys);
+    }
+
+    fn selection(state: &State, entity: hecs::Entity) -> ((usize, usize), (usize, usize)) {
+        let selection = state
+            .world
+            .get::<&SelectableText>(entity)
+            .unwrap()
+            .selection()
+            .unwrap();
+
+        (
+            (selection.anchor.line, selection.anchor.index),
+            (selection.focus.line, selection.focus.index),
+        )
+    }
+
+    #[test]
+    fn drag_across_lines() {
+        let Some((mut state, entity)) = setup() else {
+            return;
+        };
+
+        frame(&mut state, glam::vec2(0., 1.), Some(true));
+        frame(&mut state, glam::vec2(1., LINE_HEIGHT * 2. + 1.), None);
+        frame(&mut state, glam::vec2(250., LINE_HEIGHT * 2. + 1.), None);
+
+        // Releasing ends the drag where the last update left it
+        frame(&mut state, glam::vec2(0., 0.), Some(false));
+
+        let selectable = state.world.get::<&SelectableText>(entity).unwrap();
+        assert!(!selectable.is_dragging());
+
+        let rects = selectable.highlight_rects();
+        assert_eq!(rects.len(), 3);
+
+        // Rects are in window pixels, one per line from the top of the area
+        rects.iter().enumerate().for_each(|(line, rect)| {
+            assert_eq!(rect.position.x, AREA.position.x);
+            assert_eq!(rect.position.y, AREA.position.y + line as f32 * LINE_HEIGHT);
+            assert_eq!(rect.size.y, LINE_HEIGHT);
+        });
+
+        let area = state.world.get::<&TextArea>(entity).unwrap();
+        assert_eq!(selectable.selected_text(&area), TEXT);
+    }
+
+    #[test]
+    fn clicks_past_a_line_end_stay_on_the_line() {
+        let Some((mut state, entity)) = setup() else {
+            return;
+        };
+
+        // Pressed past the end of the first line, just above the second
+        frame(&mut state, glam::vec2(250., LINE_HEIGHT - 1.), Some(true));
+        assert_eq!(selection(&state, entity), ((0, 11), (0, 11)));
+
+        // Just below the boundary is the start of the second line
+        frame(&mut state, glam::vec2(0., LINE_HEIGHT + 1.), None);
+        assert_eq!(selection(&state, entity), ((0, 11), (1, 0)));
+
+        let selectable = state.world.get::<&SelectableText>(entity).unwrap();
+        let area = state.world.get::<&TextArea>(entity).unwrap();
+        assert_eq!(selectable.selected_text(&area), "\n");
+    }
+
+    #[test]
+    fn dragging_outside_clamps_to_the_area() {
+        let Some((mut state, entity)) = setup() else {
+            return;
+        };
+
+        frame(&mut state, glam::vec2(0., LINE_HEIGHT + 1.), Some(true));
+        frame(&mut state, glam::vec2(-100., -100.), None);
+        assert_eq!(selection(&state, entity), ((1, 0), (0, 0)));
+
+        // Below the text hits the last line
+        frame(&mut state, glam::vec2(1000., 1000.), None);
+        assert_eq!(selection(&state, entity), ((1, 0), (2, 5)));
+
+        // Copying joins the lines
+        process_inputs(&mut state.keys, KeyCode::ControlLeft, true);
+        process_inputs(&mut state.keys, KeyCode::KeyC, true);
+        frame(&mut state, glam::vec2(1000., 1000.), Some(false));
+
+        let mut selectable = state.world.get::<&mut SelectableText>(entity).unwrap();
+        assert_eq!(
+            selectable.take_copied().as_deref(),
+            Some("second line\nthird")
+        );
+        assert_eq!(selectable.highlight_rects().len(), 2);
+    }
+}
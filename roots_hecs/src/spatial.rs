@@ -5,14 +5,43 @@ use std::collections::{HashMap, HashSet};
 use hecs::{Entity, World};
 use roots_common::spatial::{GlobalTransform, Transform};
 
+use crate::{
+    profiler,
+    tracked::Tracked,
+    validation::{self, CHECK_TRANSFORMS},
+};
+
 //====================================================================
 
+/// Entities with a non-finite `Transform` keep their previous `GlobalTransform`.
+/// Entities with a `Tracked<Transform>` instead are only updated when it changed, so
+/// static entities cost nothing once spawned.
 pub fn process_global_transform(state: &mut crate::State) {
-    state
-        .world
-        .query_mut::<(&Transform, &mut GlobalTransform)>()
-        .into_iter()
-        .for_each(|(_, (transform, global))| global.0 = transform.to_affine());
+    let mut invalid = Vec::new();
+
+    let mut apply = |entity, transform: &Transform, global: &mut GlobalTransform| {
+        if CHECK_TRANSFORMS && !transform.is_finite() {
+            invalid.push(entity);
+            return;
+        }
+
+        global.0 = transform.to_affine()
+    };
+
+    profiler::profiled_query::<(&Transform, &mut GlobalTransform)>(
+        &mut state.world,
+        "global_transform",
+    )
+    .for_each(|(entity, (transform, global))| apply(entity, transform, global));
+
+    profiler::profiled_query::<(&Tracked<Transform>, &mut GlobalTransform)>(
+        &mut state.world,
+        "global_transform_tracked",
+    )
+    .filter(|(_, (transform, _))| transform.is_changed())
+    .for_each(|(entity, (transform, global))| apply(entity, transform, global));
+
+    validation::warn_non_finite(&state.world, "process_global_transform", &invalid);
 }
 
 //====================================================================
@@ -30,19 +59,20 @@ pub fn process_transform_hierarchy(state: &mut crate::State) {
         links: HashMap<Entity, Vec<Entity>>,
     }
 
-    let hierarchy = state.world.query_mut::<&LocalTransform>().into_iter().fold(
-        Hierarchy::default(),
-        |mut acc, (entity, local)| {
-            acc.entries.insert(entity);
+    let hierarchy = profiler::profiled_query::<&LocalTransform>(
+        &mut state.world,
+        "transform_hierarchy",
+    )
+    .fold(Hierarchy::default(), |mut acc, (entity, local)| {
+        acc.entries.insert(entity);
 
-            acc.links
-                .entry(local.parent)
-                .or_insert(Vec::new())
-                .push(entity);
+        acc.links
+            .entry(local.parent)
+            .or_insert(Vec::new())
+            .push(entity);
 
-            acc
-        },
-    );
+        acc
+    });
 
     let roots = hierarchy
         .links
@@ -50,8 +80,14 @@ pub fn process_transform_hierarchy(state: &mut crate::State) {
         .filter(|val| !hierarchy.entries.contains(val))
         .collect::<Vec<_>>();
 
+    let mut invalid = Vec::new();
+
     roots.into_iter().for_each(|root| {
         let root_transform = match state.world.get::<&GlobalTransform>(*root) {
+            Ok(transform) if CHECK_TRANSFORMS && !transform.is_finite() => {
+                invalid.push(*root);
+                glam::Affine3A::IDENTITY
+            }
             Ok(transform) => transform.0,
             Err(_) => {
                 log::warn!(
@@ -69,29 +105,48 @@ pub fn process_transform_hierarchy(state: &mut crate::State) {
             .unwrap()
             .into_iter()
             .for_each(|child| {
-                cascade_transform(&mut state.world, &hierarchy.links, *child, root_transform);
+                cascade_transform(
+                    &mut state.world,
+                    &hierarchy.links,
+                    *child,
+                    root_transform,
+                    &mut invalid,
+                );
             });
     });
+
+    validation::warn_non_finite(&state.world, "process_transform_hierarchy", &invalid);
 }
 
+/// A non-finite entity keeps its previous `GlobalTransform` and its children
+/// continue from an identity transform rather than inheriting the bad values.
 fn cascade_transform(
     world: &mut World,
     links: &HashMap<Entity, Vec<Entity>>,
     current: Entity,
     mut transform: glam::Affine3A,
+    invalid: &mut Vec<Entity>,
 ) {
     if let Ok(local) = world.get::<&LocalTransform>(current) {
         transform *= local.transform.to_affine();
     }
 
-    if let Ok(mut entity_transform) = world.get::<&mut GlobalTransform>(current) {
-        entity_transform.0 = transform;
+    match CHECK_TRANSFORMS && !transform.is_finite() {
+        true => {
+            invalid.push(current);
+            transform = glam::Affine3A::IDENTITY;
+        }
+        false => {
+            if let Ok(mut entity_transform) = world.get::<&mut GlobalTransform>(current) {
+                entity_transform.0 = transform;
+            }
+        }
     }
 
     if let Some(child_links) = links.get(&current) {
         child_links
             .into_iter()
-            .for_each(|child| cascade_transform(world, links, *child, transform))
+            .for_each(|child| cascade_transform(world, links, *child, transform, invalid))
     }
 }
 
@@ -0,0 +1,566 @@
+//====================================================================
+
+use std::collections::HashMap;
+
+use glam::{IVec3, Vec3};
+use hecs::{Entity, World};
+use roots_common::{
+    spatial::{GlobalTransform, Transform},
+    FastHasher,
+};
+use smallvec::SmallVec;
+
+use crate::{tracked::Tracked, State};
+
+//====================================================================
+
+/// Cell size used until the hash has entities to derive one from.
+pub const DEFAULT_CELL_SIZE: f32 = 4.;
+
+/// Automatic cells are this many times the average radius, so most entities span
+/// one or two cells on each axis.
+const CELL_RADIUS_RATIO: f32 = 4.;
+
+/// Entities spanning more cells than this on any axis are kept in a separate list
+/// checked by every query, rather than filling hundreds of cells.
+const MAX_CELL_SPAN: i32 = 4;
+
+//====================================================================
+
+/// Opt-in marker for entities with a `GlobalTransform` that should be found by
+/// `SpatialHash` queries. The radius is in world units.
+#[derive(Debug)]
+pub struct SpatialIndexed {
+    pub radius: f32,
+    /// Where the entity was last bucketed, so unmoved entities skip the hash.
+    bucketed: Option<Bucketed>,
+}
+
+impl Clone for SpatialIndexed {
+    /// Clones aren't in the hash yet, so they don't keep the original's bucket.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::new(self.radius)
+    }
+}
+
+impl SpatialIndexed {
+    #[inline]
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            bucketed: None,
+        }
+    }
+
+    /// An entity indexed by its position only.
+    #[inline]
+    pub fn point() -> Self {
+        Self::new(0.)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucketed {
+    generation: u32,
+    position: Vec3,
+    radius: f32,
+    /// `None` for oversized entities.
+    cells: Option<(IVec3, IVec3)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    entity: Entity,
+    position: Vec3,
+    radius: f32,
+    /// First cell the entity covers, so queries spanning several of its cells only
+    /// report it once.
+    min_cell: IVec3,
+}
+
+//====================================================================
+
+/// Uniform grid of the entities with `SpatialIndexed`, for neighbour queries that
+/// don't touch the rest of the world. Kept up to date by `process_spatial_hash`,
+/// which only re-buckets entities that moved.
+///
+/// Entities with a `Tracked<Transform>` are skipped entirely while it is unchanged.
+/// Those moved only by a parent through `LocalTransform` should use a plain
+/// `Transform` instead, as their tracked transform won't change.
+pub struct SpatialHash {
+    cell_size: f32,
+    /// Derive the cell size from the average radius of the first entities indexed.
+    auto_cell_size: bool,
+    /// Bumped when every entity needs to be re-bucketed.
+    generation: u32,
+
+    cells: HashMap<IVec3, SmallVec<[Entry; 4]>, FastHasher>,
+    oversized: Vec<Entry>,
+    /// Cells that have held an entity since the last rebuild, to bound `nearest`.
+    bounds: Option<(IVec3, IVec3)>,
+    len: usize,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self {
+            cell_size: DEFAULT_CELL_SIZE,
+            auto_cell_size: true,
+            generation: 0,
+            cells: HashMap::default(),
+            oversized: Vec::new(),
+            bounds: None,
+            len: 0,
+        }
+    }
+}
+
+impl SpatialHash {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.set_cell_size(cell_size);
+        self
+    }
+
+    #[inline]
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Use a fixed cell size. Everything is re-bucketed on the next update.
+    pub fn set_cell_size(&mut self, cell_size: f32) {
+        debug_assert!(cell_size > 0., "Spatial hash cell size must be positive");
+
+        self.auto_cell_size = false;
+        self.cell_size = cell_size;
+        self.clear();
+    }
+
+    /// Derive the cell size from the average radius of the entities indexed on the
+    /// next update. The default.
+    pub fn set_auto_cell_size(&mut self) {
+        self.auto_cell_size = true;
+        self.clear();
+    }
+
+    /// Number of indexed entities.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drop every entity. They are re-bucketed on the next update.
+    pub fn clear(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.cells.clear();
+        self.oversized.clear();
+        self.bounds = None;
+        self.len = 0;
+    }
+}
+
+//====================================================================
+
+impl SpatialHash {
+    /// Bucket new entities and re-bucket ones that moved or changed radius. Entities
+    /// despawned with `State::despawn_tracked` are removed straight away, others are
+    /// found by a sweep of the hash on the frame they disappear.
+    pub fn update(&mut self, world: &mut World) {
+        if self.auto_cell_size && self.len == 0 {
+            self.fit_cell_size(world);
+        }
+
+        let mut visited = 0;
+
+        world
+            .query_mut::<(
+                &mut SpatialIndexed,
+                &GlobalTransform,
+                Option<&Tracked<Transform>>,
+            )>()
+            .into_iter()
+            .for_each(|(entity, (indexed, global, tracked))| {
+                visited += 1;
+
+                let current = indexed
+                    .bucketed
+                    .filter(|bucketed| bucketed.generation == self.generation);
+
+                let position = match (current, tracked) {
+                    (Some(bucketed), Some(tracked))
+                        if !tracked.is_changed() && bucketed.radius == indexed.radius =>
+                    {
+                        return
+                    }
+                    _ => global.translation(),
+                };
+
+                match current {
+                    Some(bucketed)
+                        if bucketed.position == position && bucketed.radius == indexed.radius => {}
+
+                    Some(bucketed) => {
+                        indexed.bucketed =
+                            Some(self.relocate(entity, bucketed, position, indexed.radius));
+                    }
+
+                    None => {
+                        indexed.bucketed = Some(self.insert(entity, position, indexed.radius));
+                        self.len += 1;
+                    }
+                }
+            });
+
+        if visited < self.len {
+            self.sweep(world);
+        }
+    }
+
+    /// Remove an entity whose `SpatialIndexed` was taken out of the world, for example
+    /// with `World::remove_one`. Called by `State::despawn_tracked`.
+    pub fn remove(&mut self, entity: Entity, indexed: &mut SpatialIndexed) {
+        let Some(bucketed) = indexed
+            .bucketed
+            .take()
+            .filter(|bucketed| bucketed.generation == self.generation)
+        else {
+            return;
+        };
+
+        self.remove_entries(entity, bucketed.cells);
+        self.len = self.len.saturating_sub(1);
+    }
+
+    fn fit_cell_size(&mut self, world: &mut World) {
+        let (count, total) = world
+            .query_mut::<&SpatialIndexed>()
+            .into_iter()
+            .fold((0, 0.), |(count, total), (_, indexed)| {
+                (count + 1, total + indexed.radius.max(0.))
+            });
+
+        if count == 0 {
+            return;
+        }
+
+        let average = total / count as f32;
+
+        self.cell_size = match average > 0. {
+            true => average * CELL_RADIUS_RATIO,
+            false => DEFAULT_CELL_SIZE,
+        };
+        self.auto_cell_size = false;
+
+        log::debug!(
+            "Spatial hash cell size of {} from an average radius of {} over {} entities",
+            self.cell_size,
+            average,
+            count
+        );
+    }
+
+    fn insert(&mut self, entity: Entity, position: Vec3, radius: f32) -> Bucketed {
+        let min_cell = self.cell_of(position - radius);
+        let max_cell = self.cell_of(position + radius);
+
+        let entry = Entry {
+            entity,
+            position,
+            radius,
+            min_cell,
+        };
+
+        let cells = match (max_cell - min_cell).max_element() < MAX_CELL_SPAN {
+            true => {
+                cell_range(min_cell, max_cell).for_each(|cell| {
+                    self.cells.entry(cell).or_default().push(entry);
+                });
+
+                self.bounds = Some(match self.bounds {
+                    Some((min, max)) => (min.min(min_cell), max.max(max_cell)),
+                    None => (min_cell, max_cell),
+                });
+
+                Some((min_cell, max_cell))
+            }
+            false => {
+                self.oversized.push(entry);
+                None
+            }
+        };
+
+        Bucketed {
+            generation: self.generation,
+            position,
+            radius,
+            cells,
+        }
+    }
+
+    fn relocate(
+        &mut self,
+        entity: Entity,
+        previous: Bucketed,
+        position: Vec3,
+        radius: f32,
+    ) -> Bucketed {
+        let min_cell = self.cell_of(position - radius);
+        let max_cell = self.cell_of(position + radius);
+
+        match previous.cells {
+            // Still covers the same cells - update the entries in place
+            Some(cells) if cells == (min_cell, max_cell) => {
+                cell_range(min_cell, max_cell).for_each(|cell| {
+                    if let Some(entry) = self
+                        .cells
+                        .get_mut(&cell)
+                        .and_then(|entries| entries.iter_mut().find(|e| e.entity == entity))
+                    {
+                        entry.position = position;
+                        entry.radius = radius;
+                    }
+                });
+
+                Bucketed {
+                    position,
+                    radius,
+                    ..previous
+                }
+            }
+
+            _ => {
+                self.remove_entries(entity, previous.cells);
+                self.insert(entity, position, radius)
+            }
+        }
+    }
+
+    fn remove_entries(&mut self, entity: Entity, cells: Option<(IVec3, IVec3)>) {
+        match cells {
+            Some((min, max)) => cell_range(min, max).for_each(|cell| {
+                if let Some(entries) = self.cells.get_mut(&cell) {
+                    entries.retain(|entry| entry.entity != entity);
+
+                    if entries.is_empty() {
+                        self.cells.remove(&cell);
+                    }
+                }
+            }),
+            None => self.oversized.retain(|entry| entry.entity != entity),
+        }
+    }
+
+    /// Drop entries for entities that were despawned or lost their `SpatialIndexed`.
+    fn sweep(&mut self, world: &World) {
+        let generation = self.generation;
+
+        // The entity still has the bucket this entry was made from
+        let is_current = |entry: &Entry, cell: Option<IVec3>| {
+            let Ok(indexed) = world.get::<&SpatialIndexed>(entry.entity) else {
+                return false;
+            };
+
+            match indexed.bucketed {
+                Some(bucketed)
+                    if bucketed.generation == generation
+                        && bucketed.position == entry.position
+                        && bucketed.radius == entry.radius =>
+                {
+                    match (bucketed.cells, cell) {
+                        (Some((min, max)), Some(cell)) => {
+                            cell.cmpge(min).all() && cell.cmple(max).all()
+                        }
+                        (None, None) => true,
+                        _ => false,
+                    }
+                }
+                _ => false,
+            }
+        };
+
+        // Components removed and added again in place can leave duplicates behind
+        let mut seen = Vec::new();
+        let keep = |entry: &Entry, cell: Option<IVec3>, seen: &mut Vec<Entity>| {
+            let keep = is_current(entry, cell) && !seen.contains(&entry.entity);
+
+            if keep {
+                seen.push(entry.entity);
+            }
+
+            keep
+        };
+
+        self.cells.retain(|cell, entries| {
+            seen.clear();
+            entries.retain(|entry| keep(entry, Some(*cell), &mut seen));
+            !entries.is_empty()
+        });
+
+        seen.clear();
+        self.oversized.retain(|entry| keep(entry, None, &mut seen));
+
+        let before = self.len;
+        self.len = world
+            .query::<&SpatialIndexed>()
+            .iter()
+            .filter(|(_, indexed)| {
+                indexed
+                    .bucketed
+                    .is_some_and(|bucketed| bucketed.generation == generation)
+            })
+            .count();
+
+        log::trace!(
+            "Swept {} stale entities from the spatial hash",
+            before.saturating_sub(self.len)
+        );
+    }
+}
+
+//====================================================================
+
+impl SpatialHash {
+    #[inline]
+    fn cell_of(&self, position: Vec3) -> IVec3 {
+        (position / self.cell_size).floor().as_ivec3()
+    }
+
+    /// Entities whose radius overlaps the sphere at `point`.
+    pub fn query_radius(&self, point: Vec3, radius: f32) -> impl Iterator<Item = Entity> + '_ {
+        let radius = radius.max(0.);
+
+        self.query_cells(point - radius, point + radius, move |entry| {
+            let reach = radius + entry.radius;
+            entry.position.distance_squared(point) <= reach * reach
+        })
+    }
+
+    /// Entities whose radius overlaps the box between `min` and `max`.
+    pub fn query_aabb(&self, min: Vec3, max: Vec3) -> impl Iterator<Item = Entity> + '_ {
+        self.query_cells(min, max, move |entry| {
+            let closest = entry.position.clamp(min, max);
+            closest.distance_squared(entry.position) <= entry.radius * entry.radius
+        })
+    }
+
+    fn query_cells<F>(&self, min: Vec3, max: Vec3, overlaps: F) -> impl Iterator<Item = Entity> + '_
+    where
+        F: Fn(&Entry) -> bool + Copy + 'static,
+    {
+        let min_cell = self.cell_of(min);
+        let max_cell = self.cell_of(max);
+
+        let bucketed = cell_range(min_cell, max_cell).flat_map(move |cell| {
+            self.cells
+                .get(&cell)
+                .into_iter()
+                .flatten()
+                // Report entities spanning several cells from the first one the query covers
+                .filter(move |entry| entry.min_cell.max(min_cell) == cell)
+                .filter(move |entry| overlaps(entry))
+        });
+
+        self.oversized
+            .iter()
+            .filter(move |entry| overlaps(entry))
+            .chain(bucketed)
+            .map(|entry| entry.entity)
+    }
+
+    /// The entity with its position closest to `point` that passes `filter`. Searches
+    /// outwards a shell of cells at a time, so nearby results are found quickly.
+    pub fn nearest(&self, point: Vec3, mut filter: impl FnMut(Entity) -> bool) -> Option<Entity> {
+        let mut best: Option<(Entity, f32)> = None;
+
+        let mut consider = |entry: &Entry, best: &mut Option<(Entity, f32)>| {
+            let distance = entry.position.distance_squared(point);
+
+            let closer = match best {
+                Some((_, closest)) => distance < *closest,
+                None => true,
+            };
+
+            if closer && filter(entry.entity) {
+                *best = Some((entry.entity, distance));
+            }
+        };
+
+        self.oversized
+            .iter()
+            .for_each(|entry| consider(entry, &mut best));
+
+        let Some((min, max)) = self.bounds else {
+            return best.map(|(entity, _)| entity);
+        };
+
+        let centre = self.cell_of(point);
+        let last_shell = (centre - min).max(max - centre).max_element().max(0);
+
+        for shell in 0..=last_shell {
+            cell_shell(centre, shell)
+                .filter_map(|cell| self.cells.get(&cell).map(|entries| (cell, entries)))
+                .for_each(|(cell, entries)| {
+                    entries
+                        .iter()
+                        // Only consider entities from the cell holding their centre
+                        .filter(|entry| self.cell_of(entry.position) == cell)
+                        .for_each(|entry| consider(entry, &mut best));
+                });
+
+            // Anything centred further out is at least this far away
+            let reach = shell as f32 * self.cell_size;
+            if best.is_some_and(|(_, closest)| closest <= reach * reach) {
+                break;
+            }
+        }
+
+        best.map(|(entity, _)| entity)
+    }
+}
+
+//====================================================================
+
+fn cell_range(min: IVec3, max: IVec3) -> impl Iterator<Item = IVec3> {
+    (min.x..=max.x).flat_map(move |x| {
+        (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| IVec3::new(x, y, z)))
+    })
+}
+
+/// Cells exactly `shell` cells away from `centre` on at least one axis.
+fn cell_shell(centre: IVec3, shell: i32) -> impl Iterator<Item = IVec3> {
+    (-shell..=shell).flat_map(move |x| {
+        (-shell..=shell).flat_map(move |y| {
+            let on_edge = x.abs() == shell || y.abs() == shell;
+
+            let step = match on_edge || shell == 0 {
+                true => 1,
+                false => (shell * 2) as usize,
+            };
+
+            (-shell..=shell)
+                .step_by(step)
+                .map(move |z| centre + IVec3::new(x, y, z))
+        })
+    })
+}
+
+//====================================================================
+
+/// Keep `State::spatial_hash` up to date. Call after transforms have been propagated,
+/// before changes are cleared at the end of the frame.
+pub fn process_spatial_hash(state: &mut State) {
+    state.spatial_hash.update(&mut state.world);
+}
+
+//====================================================================
@@ -0,0 +1,442 @@
+//====================================================================
+
+use std::{cell::Cell, time::Duration};
+
+use hecs::{CommandBuffer, DynamicBundle};
+use roots_renderer::uploads::{UploadData, UploadTicket, Uploaded};
+use web_time::Instant;
+
+use crate::State;
+
+//====================================================================
+
+/// Time given to sliced tasks each frame unless set with `TaskSlicer::set_budget`.
+pub const DEFAULT_TASK_BUDGET: Duration = Duration::from_millis(3);
+
+pub type TaskId = u32;
+
+/// Returned by a task after each slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    /// More work is left. The task is run again this frame if there's budget left.
+    Continue,
+    /// Waiting on something outside the task, such as uploads. The task isn't run
+    /// again until the next frame.
+    Waiting,
+    /// The task is removed.
+    Done,
+}
+
+/// The time a task may spend in its current slice. Tasks should check `exhausted`
+/// between units of work and return once it's spent.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget<'a> {
+    start: Instant,
+    deadline: Instant,
+    progress: &'a Cell<Option<f32>>,
+}
+
+impl Budget<'_> {
+    #[inline]
+    pub fn exhausted(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Report how far through the task is, from 0 to 1.
+    #[inline]
+    pub fn set_progress(&self, progress: f32) {
+        self.progress.set(Some(progress.clamp(0., 1.)));
+    }
+}
+
+/// Work resumed a slice at a time. Implemented for closures taking the state and a
+/// `Budget`, and by the `sliced_` helpers.
+pub trait SlicedTask: 'static {
+    fn run(&mut self, state: &mut State, budget: Budget) -> TaskStatus;
+}
+
+impl<F> SlicedTask for F
+where
+    F: FnMut(&mut State, Budget) -> TaskStatus + 'static,
+{
+    #[inline]
+    fn run(&mut self, state: &mut State, budget: Budget) -> TaskStatus {
+        self(state, budget)
+    }
+}
+
+//====================================================================
+
+struct Task {
+    id: TaskId,
+    name: String,
+    task: Box<dyn SlicedTask>,
+    progress: Cell<Option<f32>>,
+    time_spent: Duration,
+    slices: u32,
+}
+
+/// A running task, as listed by `TaskSlicer::tasks`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub name: String,
+    /// Last progress reported, or `None` if the task never reported any.
+    pub progress: Option<f32>,
+    pub time_spent: Duration,
+    pub slices: u32,
+}
+
+/// Work that has to run on the main thread but takes longer than a frame, such as
+/// world generation or gpu uploads on wasm. Each frame after `HecsApp::tick` the
+/// runner gives pending tasks a measured time budget, running them round-robin
+/// until it's spent. Host applications call `State::run_tasks` themselves.
+pub struct TaskSlicer {
+    tasks: Vec<Task>,
+    next_id: TaskId,
+    budget: Duration,
+
+    /// Tasks are taken out while running, so cancellations of them are applied after.
+    running: Vec<TaskId>,
+    cancelled: Vec<TaskId>,
+    last_frame: Duration,
+}
+
+impl Default for TaskSlicer {
+    fn default() -> Self {
+        Self {
+            tasks: Vec::new(),
+            next_id: 0,
+            budget: DEFAULT_TASK_BUDGET,
+            running: Vec::new(),
+            cancelled: Vec::new(),
+            last_frame: Duration::ZERO,
+        }
+    }
+}
+
+impl TaskSlicer {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, task: impl SlicedTask) -> TaskId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.tasks.push(Task {
+            id,
+            name: name.into(),
+            task: Box::new(task),
+            progress: Cell::new(None),
+            time_spent: Duration::ZERO,
+            slices: 0,
+        });
+
+        id
+    }
+
+    /// Drop a task without running it again. Returns whether it was still running.
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        if self.running.contains(&id) {
+            self.cancelled.push(id);
+            return true;
+        }
+
+        let len = self.tasks.len();
+        self.tasks.retain(|task| task.id != id);
+        self.tasks.len() != len
+    }
+
+    pub fn cancel_all(&mut self) {
+        self.cancelled.extend_from_slice(&self.running);
+        self.tasks.clear();
+    }
+
+    /// Whether the task hasn't finished or been cancelled.
+    #[inline]
+    pub fn is_running(&self, id: TaskId) -> bool {
+        (self.running.contains(&id) && !self.cancelled.contains(&id))
+            || self.tasks.iter().any(|task| task.id == id)
+    }
+
+    /// Last progress the task reported, from 0 to 1.
+    pub fn progress(&self, id: TaskId) -> Option<f32> {
+        self.tasks
+            .iter()
+            .find(|task| task.id == id)
+            .and_then(|task| task.progress.get())
+    }
+
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        self.tasks
+            .iter()
+            .map(|task| TaskInfo {
+                id: task.id,
+                name: task.name.clone(),
+                progress: task.progress.get(),
+                time_spent: task.time_spent,
+                slices: task.slices,
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    #[inline]
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    #[inline]
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Time spent running tasks last frame, including any overrun of the budget.
+    #[inline]
+    pub fn last_frame(&self) -> Duration {
+        self.last_frame
+    }
+}
+
+impl State {
+    /// Queue a sliced task. See `TaskSlicer`.
+    #[inline]
+    pub fn add_task(&mut self, name: impl Into<String>, task: impl SlicedTask) -> TaskId {
+        self.tasks.add(name, task)
+    }
+
+    /// Run pending tasks round-robin until the task budget is spent. Called by the
+    /// runner after `HecsApp::tick`.
+    pub fn run_tasks(&mut self) {
+        if self.tasks.tasks.is_empty() {
+            self.tasks.last_frame = Duration::ZERO;
+            return;
+        }
+
+        let start = Instant::now();
+        let deadline = start + self.tasks.budget;
+
+        let mut tasks = std::mem::take(&mut self.tasks.tasks);
+        self.tasks.running = tasks.iter().map(|task| task.id).collect();
+
+        // Tasks that can still make progress this frame
+        let mut active = vec![true; tasks.len()];
+        let mut done = vec![false; tasks.len()];
+
+        loop {
+            let pending = active.iter().filter(|active| **active).count();
+            let now = Instant::now();
+
+            if pending == 0 || now >= deadline {
+                break;
+            }
+
+            // Split what's left evenly between the tasks still active this round
+            let slice = (deadline - now) / pending as u32;
+
+            for index in 0..tasks.len() {
+                if !active[index] {
+                    continue;
+                }
+
+                let slice_start = Instant::now();
+                if slice_start >= deadline {
+                    break;
+                }
+
+                let task = &mut tasks[index];
+                let budget = Budget {
+                    start: slice_start,
+                    deadline: (slice_start + slice).min(deadline),
+                    progress: &task.progress,
+                };
+
+                let status = task.task.run(self, budget);
+
+                task.time_spent += slice_start.elapsed();
+                task.slices += 1;
+
+                match status {
+                    TaskStatus::Continue => {}
+                    TaskStatus::Waiting => active[index] = false,
+                    TaskStatus::Done => {
+                        active[index] = false;
+                        done[index] = true;
+                    }
+                }
+            }
+        }
+
+        self.tasks.running.clear();
+        let cancelled = std::mem::take(&mut self.tasks.cancelled);
+
+        let first = tasks[0].id;
+        let mut done = done.into_iter();
+
+        tasks.retain(|task| {
+            let done = done.next().unwrap();
+            let cancelled = cancelled.contains(&task.id);
+
+            match (done, cancelled) {
+                (true, _) => log::debug!("Task '{}' done in {:?}", task.name, task.time_spent),
+                (false, true) => log::debug!("Task '{}' cancelled", task.name),
+                _ => {}
+            }
+
+            !done && !cancelled
+        });
+
+        // The first task goes last so it can't starve the others of the split budget
+        if tasks.first().is_some_and(|task| task.id == first) {
+            tasks.rotate_left(1);
+        }
+
+        // Tasks added while running go after the existing ones
+        tasks.append(&mut self.tasks.tasks);
+        self.tasks.tasks = tasks;
+
+        self.tasks.last_frame = start.elapsed();
+    }
+}
+
+//====================================================================
+
+/// Call `f` on each item in turn until they're all used.
+pub fn sliced_iter<T, F>(items: Vec<T>, mut f: F) -> impl SlicedTask
+where
+    T: 'static,
+    F: FnMut(&mut State, T) + 'static,
+{
+    let total = items.len();
+    let mut items = items.into_iter();
+
+    move |state: &mut State, budget: Budget| {
+        loop {
+            let Some(item) = items.next() else {
+                budget.set_progress(1.);
+                return TaskStatus::Done;
+            };
+
+            f(state, item);
+
+            if budget.exhausted() {
+                break;
+            }
+        }
+
+        budget.set_progress(1. - items.len() as f32 / total.max(1) as f32);
+        TaskStatus::Continue
+    }
+}
+
+/// Spawn every bundle from `bundles`, pulling them lazily so generating them is
+/// sliced too. Each slice's spawns are recorded in a `CommandBuffer` and applied to
+/// the world in one go at the end of the slice.
+pub fn sliced_spawn<I, B>(bundles: I) -> impl SlicedTask
+where
+    I: ExactSizeIterator<Item = B> + 'static,
+    B: DynamicBundle + 'static,
+{
+    let total = bundles.len();
+    let mut bundles = bundles;
+    let mut commands = CommandBuffer::new();
+
+    move |state: &mut State, budget: Budget| {
+        let status = loop {
+            let Some(bundle) = bundles.next() else {
+                break TaskStatus::Done;
+            };
+
+            commands.spawn(bundle);
+
+            if budget.exhausted() {
+                break TaskStatus::Continue;
+            }
+        };
+
+        commands.run_on(&mut state.world);
+        budget.set_progress(1. - bundles.len() as f32 / total.max(1) as f32);
+        status
+    }
+}
+
+/// Push uploads through the current `UploadContext`, keeping at most `in_flight`
+/// unfinished at once so they don't all land on the deferred upload queue at once.
+/// `on_uploaded` is called with each upload's index as it finishes.
+pub fn sliced_uploads<F>(
+    uploads: Vec<UploadData>,
+    in_flight: usize,
+    mut on_uploaded: F,
+) -> impl SlicedTask
+where
+    F: FnMut(&mut State, usize, Uploaded) + 'static,
+{
+    let total = uploads.len();
+    let in_flight = in_flight.max(1);
+    let mut uploads = uploads.into_iter().enumerate();
+    let mut tickets: Vec<(usize, UploadTicket)> = Vec::new();
+    let mut sent = 0;
+    let mut finished = 0;
+
+    move |state: &mut State, budget: Budget| {
+        tickets.retain(|(index, ticket)| match ticket.take() {
+            Some(uploaded) => {
+                on_uploaded(state, *index, uploaded);
+                finished += 1;
+                false
+            }
+            None => true,
+        });
+
+        let context = state.renderer.upload_context();
+
+        while tickets.len() < in_flight && !budget.exhausted() {
+            let Some((index, data)) = uploads.next() else {
+                break;
+            };
+
+            let ticket = context.upload(data);
+            sent += 1;
+
+            // Direct uploads are ready immediately
+            match ticket.take() {
+                Some(uploaded) => {
+                    on_uploaded(state, index, uploaded);
+                    finished += 1;
+                }
+                None => tickets.push((index, ticket)),
+            }
+        }
+
+        budget.set_progress(finished as f32 / total.max(1) as f32);
+
+        match (finished == total, tickets.len() < in_flight && sent < total) {
+            (true, _) => TaskStatus::Done,
+            (false, true) => TaskStatus::Continue,
+            (false, false) => TaskStatus::Waiting,
+        }
+    }
+}
+
+//====================================================================
@@ -0,0 +1,78 @@
+//====================================================================
+// Shared setup for tests that need a gpu. Tests should return early when no
+// adapter is available (such as on ci machines without a gpu).
+// Not every feature set uses every helper.
+#![allow(dead_code)]
+
+use roots_common::Size;
+
+use crate::{renderer::RendererState, State};
+
+//====================================================================
+
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+pub fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+    let Some(adapter) = pollster::block_on(instance.request_adapter(&Default::default())) else {
+        println!("No adapter available - skipping");
+        return None;
+    };
+
+    let device_queue =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .unwrap();
+
+    Some(device_queue)
+}
+
+/// A renderer without a surface. Render into views from `render_target`.
+pub fn renderer(width: u32, height: u32) -> Option<RendererState> {
+    let (device, queue) = device()?;
+
+    let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: FORMAT,
+        width,
+        height,
+        present_mode: wgpu::PresentMode::AutoNoVsync,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: Vec::new(),
+    };
+
+    Some(RendererState::from_parts(
+        device.into(),
+        queue.into(),
+        None,
+        config.into(),
+    ))
+}
+
+/// An embedded state around a renderer from `renderer`.
+pub fn state(width: u32, height: u32) -> Option<State> {
+    let renderer = renderer(width, height)?;
+    Some(State::new_embedded(renderer, Size::new(width, height)))
+}
+
+/// A texture matching the renderer's config that can be read back with
+/// `roots_renderer::capture::read_texture`.
+pub fn render_target(renderer: &RendererState) -> wgpu::Texture {
+    renderer.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Test Render Target"),
+        size: wgpu::Extent3d {
+            width: renderer.config.width,
+            height: renderer.config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+//====================================================================
@@ -0,0 +1,303 @@
+//====================================================================
+
+use std::collections::HashMap;
+
+use hecs::Entity;
+use roots_common::Size;
+use roots_text::{
+    fallback::FontFallback,
+    overflow::{self, FitResult, TextOverflow},
+    shared::{Attrs, Buffer, FontSystem, Metrics, TextResources},
+};
+
+use crate::State;
+
+//====================================================================
+
+/// Screen space rectangle in pixels, with the origin at the top left of the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rect {
+    pub position: glam::Vec2,
+    pub size: glam::Vec2,
+}
+
+impl Rect {
+    #[inline]
+    pub fn new(position: glam::Vec2, size: glam::Vec2) -> Self {
+        Self { position, size }
+    }
+
+    /// Shrink by `padding` on every side.
+    #[inline]
+    pub fn inset(&self, padding: f32) -> Self {
+        Self {
+            position: self.position + padding,
+            size: (self.size - padding * 2.).max(glam::Vec2::ZERO),
+        }
+    }
+}
+
+/// The resolved screen rect of a UI element. Written by `process_text_areas` for
+/// every `TextArea` and read through `TextRect::Parent`, so other UI elements can
+/// act as parents by providing one.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UiRect(pub Rect);
+
+/// Where a `TextArea` is placed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextRect {
+    Pixels(Rect),
+    /// Position and size as fractions of the window size.
+    Window {
+        position: glam::Vec2,
+        size: glam::Vec2,
+    },
+    /// The `UiRect` of another entity, shrunk by `padding`.
+    Parent {
+        parent: Entity,
+        padding: f32,
+    },
+}
+
+impl TextRect {
+    fn resolve(&self, window: Size<u32>, parent: impl Fn(Entity) -> Option<Rect>) -> Option<Rect> {
+        match self {
+            TextRect::Pixels(rect) => Some(*rect),
+            TextRect::Window { position, size } => {
+                let window = glam::vec2(window.width as f32, window.height as f32);
+                Some(Rect::new(*position * window, *size * window))
+            }
+            TextRect::Parent {
+                parent: entity,
+                padding,
+            } => parent(*entity).map(|rect| rect.inset(*padding)),
+        }
+    }
+}
+
+//====================================================================
+
+/// Text laid out inside a rect that follows the window or a parent UI element.
+/// The buffer is only laid out again when the text or the resolved rect changes.
+pub struct TextArea {
+    pub rect: TextRect,
+    overflow: TextOverflow,
+    metrics: Metrics,
+
+    text: String,
+    buffer: Buffer,
+
+    resolved: Option<Rect>,
+    fit: FitResult,
+    dirty: bool,
+}
+
+impl TextArea {
+    pub fn new(font_system: &mut FontSystem, text: impl Into<String>, rect: TextRect) -> Self {
+        let metrics = Metrics::relative(30., 1.2);
+
+        Self {
+            rect,
+            overflow: TextOverflow::default(),
+            metrics,
+            text: text.into(),
+            buffer: Buffer::new(font_system, metrics),
+            resolved: None,
+            fit: FitResult::default(),
+            dirty: true,
+        }
+    }
+
+    #[inline]
+    pub fn with_overflow(mut self, overflow: TextOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Font size in pixels and line height relative to the font size.
+    #[inline]
+    pub fn with_font_size(mut self, font_size: f32, line_height: f32) -> Self {
+        self.metrics = Metrics::relative(font_size, line_height);
+        self
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Does nothing if the text hasn't changed.
+    pub fn set_text(&mut self, text: &str) {
+        if self.text != text {
+            self.text = text.to_string();
+            self.dirty = true;
+        }
+    }
+
+    #[inline]
+    pub fn overflow(&self) -> TextOverflow {
+        self.overflow
+    }
+
+    #[inline]
+    pub fn set_overflow(&mut self, overflow: TextOverflow) {
+        if self.overflow != overflow {
+            self.overflow = overflow;
+            self.dirty = true;
+        }
+    }
+
+    #[inline]
+    pub fn set_font_size(&mut self, font_size: f32, line_height: f32) {
+        let metrics = Metrics::relative(font_size, line_height);
+        if self.metrics != metrics {
+            self.metrics = metrics;
+            self.dirty = true;
+        }
+    }
+
+    /// The laid out text, ready to be prepped by a text renderer.
+    #[inline]
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// The rect from the last `process_text_areas`. `None` until resolved.
+    #[inline]
+    pub fn resolved_rect(&self) -> Option<Rect> {
+        self.resolved
+    }
+
+    /// Size and font size of the laid out text after overflow was applied.
+    #[inline]
+    pub fn fit(&self) -> FitResult {
+        self.fit
+    }
+
+    /// Scissor rect (x, y, width, height) for renderers when using `TextOverflow::Clip`,
+    /// clamped to the render target.
+    pub fn scissor_rect(&self, target: Size<u32>) -> Option<[u32; 4]> {
+        let rect = self.resolved?;
+
+        let min = rect.position.max(glam::Vec2::ZERO);
+        let max =
+            (rect.position + rect.size).min(glam::vec2(target.width as f32, target.height as f32));
+
+        match max.x > min.x && max.y > min.y {
+            true => Some([
+                min.x as u32,
+                min.y as u32,
+                (max.x - min.x) as u32,
+                (max.y - min.y) as u32,
+            ]),
+            false => None,
+        }
+    }
+
+    fn layout(&mut self, font_system: &mut FontSystem, fallback: &FontFallback, rect: Rect) {
+        // Shrinking to fit changes the buffer's metrics, so start from ours each time
+        self.buffer.set_metrics(font_system, self.metrics);
+
+        self.fit = overflow::fit_text(
+            font_system,
+            &mut self.buffer,
+            &self.text,
+            Attrs::new(),
+            fallback,
+            rect.size,
+            self.overflow,
+        );
+
+        self.resolved = Some(rect);
+        self.dirty = false;
+    }
+}
+
+//====================================================================
+
+/// Resolve every `TextArea`'s rect and lay out the ones whose text or rect changed.
+/// Parents are resolved before their children, so a chain of areas settles in one call.
+pub fn process_text_areas(state: &mut State, text: &mut TextResources) {
+    let window = state.size();
+
+    let areas = state
+        .world
+        .query_mut::<&TextArea>()
+        .into_iter()
+        .map(|(entity, area)| (entity, area.rect))
+        .collect::<HashMap<_, _>>();
+
+    let mut resolved = HashMap::new();
+
+    areas.keys().for_each(|entity| {
+        resolve_rect(state, &areas, &mut resolved, window, *entity, 0);
+    });
+
+    let mut changed = Vec::new();
+
+    state
+        .world
+        .query_mut::<&mut TextArea>()
+        .into_iter()
+        .for_each(|(entity, area)| {
+            let Some(Some(rect)) = resolved.get(&entity) else {
+                return;
+            };
+
+            if area.dirty || area.resolved != Some(*rect) {
+                // Moving without resizing doesn't change the layout
+                let resized = area.resolved.map(|old| old.size) != Some(rect.size);
+
+                match area.dirty || resized {
+                    true => area.layout(&mut text.font_system, &text.fallback, *rect),
+                    false => area.resolved = Some(*rect),
+                }
+
+                changed.push((entity, UiRect(*rect)));
+            }
+        });
+
+    changed.into_iter().for_each(|(entity, rect)| {
+        let _ = state.world.insert_one(entity, rect);
+    });
+}
+
+/// Areas deeper than this are assumed to be part of a parent cycle.
+const MAX_DEPTH: usize = 32;
+
+fn resolve_rect(
+    state: &State,
+    areas: &HashMap<Entity, TextRect>,
+    resolved: &mut HashMap<Entity, Option<Rect>>,
+    window: Size<u32>,
+    entity: Entity,
+    depth: usize,
+) -> Option<Rect> {
+    if let Some(rect) = resolved.get(&entity) {
+        return *rect;
+    }
+
+    if depth > MAX_DEPTH {
+        log::warn!("TextArea '{:?}' has a parent cycle", entity);
+        return None;
+    }
+
+    let rect = match areas.get(&entity) {
+        Some(TextRect::Parent { parent, .. }) if areas.contains_key(parent) => {
+            let parent_rect = resolve_rect(state, areas, resolved, window, *parent, depth + 1);
+            areas[&entity].resolve(window, |_| parent_rect)
+        }
+
+        Some(rect) => rect.resolve(window, |parent| {
+            state.world.get::<&UiRect>(parent).ok().map(|rect| rect.0)
+        }),
+
+        // Not a text area - use its UiRect directly
+        None => state.world.get::<&UiRect>(entity).ok().map(|rect| rect.0),
+    };
+
+    resolved.insert(entity, rect);
+    rect
+}
+
+//====================================================================
@@ -0,0 +1,156 @@
+//====================================================================
+
+use std::{
+    any::TypeId,
+    ops::{Deref, DerefMut},
+};
+
+use hecs::{Component, Entity, World};
+
+//====================================================================
+
+/// Wraps a component so writes through `DerefMut` mark it as changed, for systems
+/// that only need to do work when something changed. Reads cost nothing extra.
+/// Changes are seen by `State::changed` until `State::clear_changes` at the end of the
+/// frame, for types registered with `State::track_changes`.
+#[derive(Debug, Clone)]
+pub struct Tracked<T> {
+    value: T,
+    changed: bool,
+}
+
+impl<T: Default> Default for Tracked<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for Tracked<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Tracked<T> {
+    /// Starts changed, so systems see the first value.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            changed: true,
+        }
+    }
+
+    #[inline]
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    #[inline]
+    pub fn mark_changed(&mut self) {
+        self.changed = true;
+    }
+
+    /// Only replaces (and marks changed) if `value` is different, so systems writing
+    /// the same value every frame don't cause spurious work. Returns true if replaced.
+    #[inline]
+    pub fn set_if_neq(&mut self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        match self.value == value {
+            true => false,
+            false => {
+                self.value = value;
+                self.changed = true;
+                true
+            }
+        }
+    }
+
+    /// Mutable access that doesn't mark the value changed, for writes no system needs
+    /// to react to.
+    #[inline]
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.changed = true;
+        &mut self.value
+    }
+}
+
+//====================================================================
+
+/// Entities whose `Tracked<T>` changed since changes were last cleared.
+pub fn changed<T: Component>(world: &mut World) -> impl Iterator<Item = Entity> + '_ {
+    world
+        .query_mut::<&Tracked<T>>()
+        .into_iter()
+        .filter(|(_, tracked)| tracked.changed)
+        .map(|(entity, _)| entity)
+}
+
+type ClearChanges = fn(&mut World);
+
+/// The `Tracked` types whose changes are cleared by `State::clear_changes`.
+#[derive(Default)]
+pub(crate) struct ChangeTracking {
+    clears: Vec<(TypeId, ClearChanges)>,
+}
+
+impl ChangeTracking {
+    /// Does nothing if `T` is already tracked.
+    pub fn register<T: Component>(&mut self) {
+        let id = TypeId::of::<T>();
+
+        if self.clears.iter().any(|(tracked, _)| *tracked == id) {
+            return;
+        }
+
+        log::trace!("Tracking changes to '{}'", std::any::type_name::<T>());
+        self.clears.push((id, clear_changed::<T>));
+    }
+
+    #[inline]
+    pub fn is_tracked<T: Component>(&self) -> bool {
+        let id = TypeId::of::<T>();
+        self.clears.iter().any(|(tracked, _)| *tracked == id)
+    }
+
+    #[inline]
+    pub fn clear(&self, world: &mut World) {
+        self.clears.iter().for_each(|(_, clear)| clear(world));
+    }
+}
+
+/// Clear the changes to `Tracked<T>` for worlds not owned by a `State`, which
+/// clears its tracked types with `State::clear_changes`.
+pub fn clear_changed<T: Component>(world: &mut World) {
+    world
+        .query_mut::<&mut Tracked<T>>()
+        .into_iter()
+        .for_each(|(_, tracked)| tracked.changed = false);
+}
+
+//====================================================================
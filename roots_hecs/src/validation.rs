@@ -0,0 +1,102 @@
+//====================================================================
+
+use std::sync::Mutex;
+
+use hecs::{Entity, World};
+use web_time::{Duration, Instant};
+
+//====================================================================
+
+/// Whether transforms are checked for NaN and infinite values before they are
+/// propagated or uploaded. Always on in debug builds. Release builds only check
+/// with the `transform_checks` feature, skipping bad entities without logging.
+pub const CHECK_TRANSFORMS: bool = cfg!(any(debug_assertions, feature = "transform_checks"));
+
+const WARN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Optional name for an entity, shown in warnings about it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Name(pub String);
+
+impl Name {
+    #[inline]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+}
+
+//====================================================================
+
+struct WarnLimit {
+    context: &'static str,
+    last: Instant,
+    suppressed: usize,
+}
+
+/// Last warning per context. Pipelines are prepped without access to `State` so this is shared.
+static WARN_LIMITS: Mutex<Vec<WarnLimit>> = Mutex::new(Vec::new());
+
+/// Entity id with its `Name` if it has one.
+pub fn describe_entity(world: &World, entity: Entity) -> String {
+    match world.get::<&Name>(entity) {
+        Ok(name) => format!("{:?} '{}'", entity, name.0),
+        Err(_) => format!("{:?}", entity),
+    }
+}
+
+/// Warn about entities with non-finite transforms that were skipped. Rate limited
+/// per `context` and only logged in debug builds.
+pub fn warn_non_finite(world: &World, context: &'static str, entities: &[Entity]) {
+    if !cfg!(debug_assertions) || entities.is_empty() {
+        return;
+    }
+
+    let mut limits = WARN_LIMITS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let now = Instant::now();
+
+    let suppressed = match limits.iter_mut().find(|limit| limit.context == context) {
+        Some(limit) => {
+            if now.duration_since(limit.last) < WARN_INTERVAL {
+                limit.suppressed += entities.len();
+                return;
+            }
+
+            limit.last = now;
+            std::mem::take(&mut limit.suppressed)
+        }
+        None => {
+            limits.push(WarnLimit {
+                context,
+                last: now,
+                suppressed: 0,
+            });
+            0
+        }
+    };
+
+    drop(limits);
+
+    let names = entities
+        .iter()
+        .take(8)
+        .map(|entity| describe_entity(world, *entity))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let more = match entities.len() > 8 {
+        true => format!(" and {} more", entities.len() - 8),
+        false => String::new(),
+    };
+
+    log::warn!(
+        "{}: skipped non-finite transform on {}{} ({} similar warnings suppressed)",
+        context,
+        names,
+        more,
+        suppressed
+    );
+}
+
+//====================================================================
@@ -0,0 +1,143 @@
+//====================================================================
+// Spawns and despawns textured sprites and checks gpu memory stays stable. The
+// memory tracker is global, so this runs in its own test binary where no other
+// tests allocate alongside it.
+
+use roots_common::{
+    spatial::{GlobalTransform, Transform},
+    Size,
+};
+use roots_hecs::{
+    renderer::{
+        components::{Camera, Sprite},
+        RendererState,
+    },
+    State,
+};
+use roots_pipelines::texture2d_renderer::Texture2dRenderer;
+use roots_renderer::{
+    camera::OrthographicCamera, memory::GpuMemoryTracker, texture::LoadedTexture,
+};
+
+//====================================================================
+
+const SIZE: u32 = 64;
+const SPRITES: usize = 200;
+const ROUNDS: usize = 5;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+fn state() -> Option<State> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+    let Some(adapter) = pollster::block_on(instance.request_adapter(&Default::default())) else {
+        println!("No adapter available - skipping");
+        return None;
+    };
+
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .unwrap();
+
+    let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: FORMAT,
+        width: SIZE,
+        height: SIZE,
+        present_mode: wgpu::PresentMode::AutoNoVsync,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: Vec::new(),
+    };
+
+    let mut renderer = RendererState::from_parts(device.into(), queue.into(), None, config.into());
+    renderer.add_managed_pipeline::<Texture2dRenderer>(0);
+
+    Some(State::new_embedded(renderer, Size::new(SIZE, SIZE)))
+}
+
+fn render(state: &mut State, target: &wgpu::Texture) {
+    state
+        .renderer
+        .run_frame_to(&mut state.world, target.create_view(&Default::default()));
+    state.renderer.device.poll(wgpu::Maintain::Wait);
+}
+
+/// Spawn sprites with their own textures, render them, then despawn them all.
+fn round(state: &mut State, target: &wgpu::Texture) {
+    let sprites = (0..SPRITES)
+        .map(|index| {
+            let texture = LoadedTexture::load_blank(
+                &state.renderer.device,
+                &state.renderer.queue,
+                &state.renderer.shared,
+            );
+
+            state.world.spawn((Sprite {
+                texture,
+                size: glam::Vec2::splat(4.),
+                pos: glam::vec3(
+                    (index % 16) as f32 * 4. - 32.,
+                    (index / 16) as f32 * 4. - 32.,
+                    1.,
+                ),
+                color: glam::Vec4::ONE,
+            },))
+        })
+        .collect::<Vec<_>>();
+
+    render(state, target);
+
+    sprites
+        .into_iter()
+        .for_each(|entity| state.despawn_tracked(entity).unwrap());
+
+    // Pipelines release their textures in the next prep
+    render(state, target);
+}
+
+#[test]
+fn despawned_sprites_release_memory() {
+    let Some(mut state) = state() else {
+        return;
+    };
+
+    let target = state
+        .renderer
+        .device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("Sprite Memory Target"),
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+    let data = OrthographicCamera::new_centered(SIZE as f32 / 2., SIZE as f32 / 2.);
+    let camera = Camera::new_orthographic(&state.renderer, &data);
+    state.world.spawn((
+        camera,
+        data,
+        Transform::default(),
+        GlobalTransform::default(),
+    ));
+
+    // The first round creates buffers and targets that are reused afterwards
+    round(&mut state, &target);
+    let baseline = GpuMemoryTracker::total_all();
+
+    (0..ROUNDS).for_each(|_| {
+        round(&mut state, &target);
+        assert_eq!(GpuMemoryTracker::total_all(), baseline);
+    });
+
+    assert_eq!(state.world.len(), 1);
+}
+
+//====================================================================
@@ -0,0 +1,146 @@
+//====================================================================
+// Compares serial `prep_model` calls against the batched `prep_models` path
+// and reports instance upload bytes for a static scene.
+// Run with `cargo bench -p roots_pipelines --features rayon` to enable the
+// parallel gather - without the feature both paths are serial.
+
+use std::time::Instant;
+
+use roots_pipelines::model_renderer::{ModelData, ModelRenderer};
+use roots_renderer::{
+    lighting::LightingManager,
+    model::{LoadedMesh, CUBE_INDICES, CUBE_VERTICES},
+    shared::SharedRenderResources,
+    texture::LoadedTexture,
+};
+
+//====================================================================
+
+const INSTANCE_COUNT: usize = 50_000;
+const MESH_COUNT: usize = 8;
+const TEXTURE_COUNT: usize = 8;
+const ITERATIONS: u32 = 20;
+const STATIC_INSTANCE_COUNT: usize = 5_000;
+
+fn main() {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+    let adapter = match pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        force_fallback_adapter: false,
+        ..Default::default()
+    })) {
+        Some(adapter) => adapter,
+        None => {
+            println!("No adapter available - skipping model prep benchmark");
+            return;
+        }
+    };
+
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .unwrap();
+
+    let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        width: 1,
+        height: 1,
+        present_mode: wgpu::PresentMode::AutoNoVsync,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: vec![],
+    };
+
+    let shared = SharedRenderResources::new(&device);
+    let lighting = LightingManager::new(&device, &shared);
+    let mut renderer = ModelRenderer::new(&device, &config, &shared, &lighting);
+
+    let meshes = (0..MESH_COUNT)
+        .flat_map(|_| {
+            let mesh = LoadedMesh::load_from_data(&device, &CUBE_VERTICES, &CUBE_INDICES);
+            (0..TEXTURE_COUNT)
+                .map(|_| {
+                    (
+                        mesh.clone(),
+                        LoadedTexture::load_blank(&device, &queue, &shared),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let models = (0..INSTANCE_COUNT)
+        .map(|index| {
+            let mesh = index % meshes.len();
+            let transform = glam::Mat4::from_translation(glam::vec3(index as f32, 0., 0.));
+
+            (
+                ModelData {
+                    meshes: &meshes[mesh..mesh + 1],
+                    color: [1., 1., 1., 1.],
+                    scale: glam::Vec3::ONE,
+                    wind: None,
+                },
+                transform,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let serial = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            models
+                .iter()
+                .for_each(|(model, transform)| renderer.prep_model(*model, *transform));
+            let elapsed = start.elapsed();
+
+            renderer.finish_prep(&device, &queue);
+            elapsed.as_secs_f64()
+        })
+        .sum::<f64>()
+        / ITERATIONS as f64;
+
+    let batched = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            renderer.prep_models(&models);
+            let elapsed = start.elapsed();
+
+            renderer.finish_prep(&device, &queue);
+            elapsed.as_secs_f64()
+        })
+        .sum::<f64>()
+        / ITERATIONS as f64;
+
+    println!(
+        "{} instances - serial prep_model: {:.3}ms, prep_models{}: {:.3}ms",
+        INSTANCE_COUNT,
+        serial * 1000.,
+        match cfg!(feature = "rayon") {
+            true => " (rayon)",
+            false => "",
+        },
+        batched * 1000.,
+    );
+
+    // Static scene - identical instance data should not be uploaded again
+    let static_models = &models[..STATIC_INSTANCE_COUNT];
+
+    renderer.prep_models(&[]);
+    renderer.finish_prep(&device, &queue);
+
+    let uploads = (0..2)
+        .map(|_| {
+            renderer.prep_models(static_models);
+            renderer.finish_prep(&device, &queue);
+            renderer.uploaded_bytes()
+        })
+        .collect::<Vec<_>>();
+
+    println!(
+        "{} static instances - first frame uploaded {} bytes, second frame uploaded {} bytes",
+        STATIC_INSTANCE_COUNT, uploads[0], uploads[1],
+    );
+}
+
+//====================================================================
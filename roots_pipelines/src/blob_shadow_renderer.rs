@@ -0,0 +1,179 @@
+//====================================================================
+
+use roots_renderer::{
+    layouts,
+    shared::{SharedRenderResources, Vertex},
+    tools,
+};
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+pub struct BlobShadowInstance {
+    pub transform: glam::Mat4,
+    pub color: glam::Vec4,
+    pub softness: f32,
+    pub pad: [f32; 3],
+}
+
+impl Vertex for BlobShadowInstance {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+            0 => Float32x4, // Transform
+            1 => Float32x4,
+            2 => Float32x4,
+            3 => Float32x4,
+            4 => Float32x4, // Color
+            5 => Float32, // Softness
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &VERTEX_ATTRIBUTES,
+        }
+    }
+}
+
+//====================================================================
+
+/// Renders soft round shadows on the ground below objects, as a cheap alternative to
+/// shadow mapping. Every shadow is drawn in a single instanced draw call, in the
+/// transparent pass so it lands on the opaque scene. Shadows are alpha blended, depth
+/// tested with a small bias toward the camera, and don't write depth.
+#[derive(Debug)]
+pub struct BlobShadowRenderer {
+    pipeline: wgpu::RenderPipeline,
+    color: glam::Vec3,
+
+    to_prep: Vec<BlobShadowInstance>,
+    instances: tools::InstanceBuffer<BlobShadowInstance>,
+}
+
+impl BlobShadowRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> Self {
+        log::debug!("Creating Blob Shadow Renderer");
+
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let depth_convention = shared.depth_convention();
+        let mut depth_stencil =
+            depth_convention.depth_stencil_state(false, depth_convention.compare_equal());
+        depth_stencil.bias = depth_convention.bias_toward_camera(2, 2.);
+
+        // Shadows can be seen from below on slopes, so aren't culled
+        let descriptor = tools::RenderPipelineDescriptor {
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                cull_mode: None,
+                ..Default::default()
+            },
+            fragment_targets: Some(&fragment_targets),
+            depth_stencil: Some(depth_stencil),
+            multisample: shared.multisample(),
+            cache: shared.pipeline_cache(),
+            ..Default::default()
+        };
+
+        let label = "Blob Shadow Pipeline";
+        let layouts = &[layouts::CAMERA];
+
+        let shader = include_str!("shaders/blob_shadow.wgsl");
+        shared
+            .layouts()
+            .debug_validate_shader(label, shader, layouts);
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            label,
+            &shared.layouts().layouts(layouts),
+            &[BlobShadowInstance::desc()],
+            shader,
+            descriptor,
+        );
+
+        Self {
+            pipeline,
+            color: glam::Vec3::ZERO,
+            to_prep: Vec::new(),
+            instances: tools::InstanceBuffer::new(device, &[]),
+        }
+    }
+
+    /// Color of every shadow. Black by default.
+    #[inline]
+    pub fn color(&self) -> glam::Vec3 {
+        self.color
+    }
+
+    #[inline]
+    pub fn set_color(&mut self, color: glam::Vec3) {
+        self.color = color;
+    }
+
+    /// Queue a shadow for this frame, centered on `center` and lying flat on the
+    /// ground facing `normal`. `softness` is the fraction of the radius faded out
+    /// toward the edge. Shadows are only kept until the next `finish_prep`.
+    pub fn prep_shadow(
+        &mut self,
+        center: glam::Vec3,
+        normal: glam::Vec3,
+        radius: f32,
+        opacity: f32,
+        softness: f32,
+    ) {
+        let rotation =
+            glam::Quat::from_rotation_arc(glam::Vec3::Z, normal.normalize_or(glam::Vec3::Z));
+
+        self.to_prep.push(BlobShadowInstance {
+            transform: glam::Mat4::from_scale_rotation_translation(
+                glam::Vec3::splat(radius),
+                rotation,
+                center,
+            ),
+            color: self.color.extend(opacity),
+            softness,
+            pad: [0.; 3],
+        });
+    }
+
+    pub fn finish_prep(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.instances.update(device, queue, &self.to_prep);
+        self.to_prep.clear();
+    }
+
+    /// Number of shadows prepped last frame.
+    #[inline]
+    pub fn shadow_count(&self) -> u32 {
+        self.instances.count()
+    }
+
+    /// Draw calls recorded by `render`, at most one.
+    #[inline]
+    pub fn draw_calls(&self) -> u32 {
+        (self.instances.count() > 0) as u32
+    }
+
+    pub fn render(&self, pass: &mut wgpu::RenderPass, camera_bind_group: &wgpu::BindGroup) {
+        if self.instances.count() == 0 {
+            return;
+        }
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, camera_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.instances.slice(..));
+        pass.draw(0..4, 0..self.instances.count());
+    }
+}
+
+//====================================================================
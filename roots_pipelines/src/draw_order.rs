@@ -0,0 +1,173 @@
+//====================================================================
+
+use roots_renderer::{model::MeshId, texture::TextureId};
+
+//====================================================================
+
+/// Per batch data read by the `*_draw_order` shader entry points.
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+struct DrawOrderUniform {
+    batch: u32,
+    batch_count: u32,
+    first_instance: u32,
+    total_instances: u32,
+}
+
+/// A single draw call made by a renderer while draw order debugging is enabled.
+#[derive(Debug, Clone, Copy)]
+pub struct DrawOrderBatch {
+    /// Position of the batch in submission order.
+    pub order: u32,
+    pub mesh: Option<MeshId>,
+    pub texture: TextureId,
+    pub instances: u32,
+    /// Position of the first instance in the batch, for placing labels.
+    pub position: glam::Vec3,
+}
+
+//====================================================================
+
+/// Debug pipeline variant that tints every instance by when it was drawn - blue for
+/// the first instance submitted through to red for the last, with alternate batches
+/// shaded darker. The normal pipelines are untouched so this costs nothing while disabled.
+#[derive(Debug)]
+pub struct DrawOrderDebug {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    stride: u32,
+    capacity: u32,
+
+    batches: Vec<DrawOrderBatch>,
+}
+
+impl DrawOrderDebug {
+    pub fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Draw Order Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(
+                        std::mem::size_of::<DrawOrderUniform>() as u64,
+                    ),
+                },
+                count: None,
+            }],
+        })
+    }
+
+    /// `pipeline` should be created with `layout` bound to the shader's draw order group.
+    pub fn new(
+        device: &wgpu::Device,
+        layout: wgpu::BindGroupLayout,
+        pipeline: wgpu::RenderPipeline,
+    ) -> Self {
+        let stride = device.limits().min_uniform_buffer_offset_alignment;
+        let capacity = 16;
+        let (buffer, bind_group) = Self::create_buffer(device, &layout, stride, capacity);
+
+        Self {
+            pipeline,
+            layout,
+            buffer,
+            bind_group,
+            stride,
+            capacity,
+            batches: Vec::new(),
+        }
+    }
+
+    fn create_buffer(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        stride: u32,
+        capacity: u32,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Draw Order Uniform Buffer"),
+            size: stride as u64 * capacity as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Draw Order Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<DrawOrderUniform>() as u64),
+                }),
+            }],
+        });
+
+        (buffer, bind_group)
+    }
+
+    /// Upload the batches in the order they will be drawn this frame.
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        batches: Vec<DrawOrderBatch>,
+    ) {
+        let batch_count = batches.len() as u32;
+
+        if batch_count > self.capacity {
+            self.capacity = batch_count.next_power_of_two();
+            (self.buffer, self.bind_group) =
+                Self::create_buffer(device, &self.layout, self.stride, self.capacity);
+        }
+
+        let total_instances = batches.iter().map(|batch| batch.instances).sum();
+        let mut first_instance = 0;
+
+        let mut data = vec![0_u8; (self.stride * batch_count) as usize];
+
+        batches.iter().for_each(|batch| {
+            let uniform = DrawOrderUniform {
+                batch: batch.order,
+                batch_count,
+                first_instance,
+                total_instances,
+            };
+            first_instance += batch.instances;
+
+            let start = (batch.order * self.stride) as usize;
+            data[start..start + std::mem::size_of::<DrawOrderUniform>()]
+                .copy_from_slice(bytemuck::bytes_of(&uniform));
+        });
+
+        if !data.is_empty() {
+            queue.write_buffer(&self.buffer, 0, &data);
+        }
+
+        self.batches = batches;
+    }
+
+    #[inline]
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    #[inline]
+    pub fn set_bind_group(&self, pass: &mut wgpu::RenderPass, index: u32, batch: u32) {
+        pass.set_bind_group(index, &self.bind_group, &[batch * self.stride]);
+    }
+
+    #[inline]
+    pub fn batches(&self) -> &[DrawOrderBatch] {
+        &self.batches
+    }
+}
+
+//====================================================================
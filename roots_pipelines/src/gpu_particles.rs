@@ -0,0 +1,335 @@
+//====================================================================
+
+use std::collections::{HashMap, HashSet};
+
+use roots_common::FastHasher;
+use roots_renderer::{
+    memory::{GpuMemoryTracker, MemoryCategory, MemoryGuard},
+    shared::{SharedRenderResources, Vertex},
+    texture::{
+        LoadedTexture, TextureRectVertex, TEXTURE_RECT_INDEX_COUNT, TEXTURE_RECT_INDICES,
+        TEXTURE_RECT_VERTICES,
+    },
+    tools, RenderEncoder,
+};
+
+//====================================================================
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Size of a particle in the storage buffer. Matches `Particle` in the shader.
+const PARTICLE_SIZE: u64 = 32;
+
+/// Emitter parameters for one frame of simulation. Particles are spawned into a ring
+/// of `capacity` slots, `spawn_count` at a time starting from `spawn_start`, so the
+/// oldest particles are replaced first.
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug, Default)]
+pub struct GpuEmitterUniform {
+    pub position: glam::Vec3,
+    pub lifetime: f32,
+    pub velocity: glam::Vec3,
+    pub delta: f32,
+    pub velocity_variance: glam::Vec3,
+    pub spawn_start: u32,
+    pub acceleration: glam::Vec3,
+    pub spawn_count: u32,
+    pub start_color: glam::Vec4,
+    pub end_color: glam::Vec4,
+    pub start_size: glam::Vec2,
+    pub end_size: glam::Vec2,
+    pub capacity: u32,
+    /// Varies the random velocity of each frame's spawns.
+    pub seed: u32,
+    /// Non zero to kill every live particle before spawning.
+    pub reset: u32,
+    pub pad: u32,
+}
+
+#[derive(Debug)]
+struct GpuEmitter {
+    uniform_buffer: wgpu::Buffer,
+    compute_bind_group: wgpu::BindGroup,
+    render_bind_group: wgpu::BindGroup,
+    capacity: u32,
+    texture: LoadedTexture,
+    memory: MemoryGuard,
+}
+
+//====================================================================
+
+/// Simulates and draws particles entirely on the gpu. Each emitter's particles live
+/// in a storage buffer that a compute pass steps every frame, which the render pass
+/// then draws from directly. Check `is_supported` first, as downlevel devices
+/// (including WebGL) lack compute shaders.
+#[derive(Debug)]
+pub struct GpuParticleRenderer {
+    compute_pipeline: wgpu::ComputePipeline,
+    render_pipeline: wgpu::RenderPipeline,
+    compute_layout: wgpu::BindGroupLayout,
+    render_layout: wgpu::BindGroupLayout,
+
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+
+    emitters: HashMap<u64, GpuEmitter, FastHasher>,
+    prepped: HashSet<u64, FastHasher>,
+}
+
+impl GpuParticleRenderer {
+    /// Whether the device can run compute shaders and read storage buffers while drawing.
+    pub fn is_supported(device: &wgpu::Device) -> bool {
+        let limits = device.limits();
+
+        limits.max_storage_buffers_per_shader_stage > 0
+            && limits.max_compute_invocations_per_workgroup >= WORKGROUP_SIZE
+    }
+
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        blend: wgpu::BlendState,
+    ) -> Self {
+        log::debug!("Creating Gpu Particle Renderer");
+
+        let compute_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Gpu Particle Simulation Bind Group Layout"),
+            entries: &[
+                tools::bgl_entry(tools::BgEntryType::Uniform, 0, wgpu::ShaderStages::COMPUTE),
+                tools::bgl_entry(
+                    tools::BgEntryType::StorageWritable,
+                    1,
+                    wgpu::ShaderStages::COMPUTE,
+                ),
+            ],
+        });
+
+        let render_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Gpu Particle Render Bind Group Layout"),
+            entries: &[
+                tools::bgl_entry(tools::BgEntryType::Uniform, 0, wgpu::ShaderStages::VERTEX),
+                tools::bgl_entry(tools::BgEntryType::Storage, 1, wgpu::ShaderStages::VERTEX),
+            ],
+        });
+
+        let compute_pipeline = tools::create_compute_pipeline(
+            device,
+            "Gpu Particle Simulation Pipeline",
+            &[&compute_layout],
+            include_str!("shaders/gpu_particles.wgsl"),
+            "cs_main",
+            shared.pipeline_cache(),
+        );
+
+        let depth_convention = shared.depth_convention();
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(blend),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let render_pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Gpu Particle Pipeline",
+            &[
+                shared.camera_bind_group_layout(),
+                shared.texture_bind_group_layout(),
+                &render_layout,
+            ],
+            &[TextureRectVertex::desc()],
+            include_str!("shaders/gpu_particles.wgsl"),
+            tools::RenderPipelineDescriptor {
+                depth_stencil: Some(
+                    depth_convention.depth_stencil_state(false, depth_convention.compare()),
+                ),
+                fragment_targets: Some(&fragment_targets),
+                multisample: shared.multisample(),
+                cache: shared.pipeline_cache(),
+                ..Default::default()
+            },
+        );
+
+        let vertex_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Vertex,
+            "Gpu Particle",
+            &TEXTURE_RECT_VERTICES,
+        );
+
+        let index_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Index,
+            "Gpu Particle",
+            &TEXTURE_RECT_INDICES,
+        );
+
+        Self {
+            compute_pipeline,
+            render_pipeline,
+            compute_layout,
+            render_layout,
+            vertex_buffer,
+            index_buffer,
+            emitters: HashMap::default(),
+            prepped: HashSet::default(),
+        }
+    }
+
+    /// Particle slots allocated across all emitters.
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.emitters.values().map(|emitter| emitter.capacity).sum()
+    }
+
+    /// Bytes of particle and uniform buffers allocated across all emitters.
+    #[inline]
+    pub fn allocated_bytes(&self) -> u64 {
+        self.emitters
+            .values()
+            .map(|emitter| emitter.memory.bytes())
+            .sum()
+    }
+
+    /// Upload this frame's parameters for the emitter identified by `key`. Its
+    /// buffers are created on first use and recreated if `capacity` changes.
+    pub fn prep_emitter(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        key: u64,
+        texture: &LoadedTexture,
+        uniform: GpuEmitterUniform,
+    ) {
+        if uniform.capacity == 0 {
+            return;
+        }
+
+        self.prepped.insert(key);
+
+        let recreate = match self.emitters.get(&key) {
+            Some(emitter) => emitter.capacity != uniform.capacity,
+            None => true,
+        };
+
+        if recreate {
+            let emitter = self.create_emitter(device, uniform.capacity, texture);
+            self.emitters.insert(key, emitter);
+        }
+
+        let emitter = self.emitters.get_mut(&key).unwrap();
+
+        if emitter.texture.id() != texture.id() {
+            emitter.texture = texture.clone();
+        }
+
+        queue.write_buffer(&emitter.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    fn create_emitter(
+        &self,
+        device: &wgpu::Device,
+        capacity: u32,
+        texture: &LoadedTexture,
+    ) -> GpuEmitter {
+        let uniform_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Uniform,
+            "Gpu Particle Emitter",
+            &[GpuEmitterUniform::default()],
+        );
+
+        // Zeroed, so every slot starts dead
+        let particle_buffer = tools::create_buffer_empty(
+            device,
+            tools::BufferType::Storage,
+            "Gpu Particles",
+            capacity as u64 * PARTICLE_SIZE,
+        );
+
+        let entries = [
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: particle_buffer.as_entire_binding(),
+            },
+        ];
+
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Gpu Particle Simulation Bind Group"),
+            layout: &self.compute_layout,
+            entries: &entries,
+        });
+
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Gpu Particle Render Bind Group"),
+            layout: &self.render_layout,
+            entries: &entries,
+        });
+
+        let memory = GpuMemoryTracker::track(
+            MemoryCategory::InstanceBuffers,
+            "Gpu Particles",
+            particle_buffer.size() + uniform_buffer.size(),
+        );
+
+        GpuEmitter {
+            uniform_buffer,
+            compute_bind_group,
+            render_bind_group,
+            capacity,
+            texture: texture.clone(),
+            memory,
+        }
+    }
+
+    /// Drop the buffers of emitters that weren't prepped this frame.
+    pub fn finish_prep(&mut self) {
+        let prepped = &self.prepped;
+        self.emitters.retain(|key, _| prepped.contains(key));
+        self.prepped.clear();
+    }
+
+    #[inline]
+    pub fn has_emitters(&self) -> bool {
+        !self.emitters.is_empty()
+    }
+
+    /// Step every emitter's particles. Called before the render passes each frame.
+    pub fn simulate(&mut self, encoder: &mut RenderEncoder) {
+        if self.emitters.is_empty() {
+            return;
+        }
+
+        let mut pass = encoder.begin_compute_pass("Gpu Particle Simulation Pass");
+        pass.set_pipeline(&self.compute_pipeline);
+
+        self.emitters.values().for_each(|emitter| {
+            pass.set_bind_group(0, &emitter.compute_bind_group, &[]);
+            pass.dispatch_workgroups(emitter.capacity.div_ceil(WORKGROUP_SIZE), 1, 1);
+        });
+    }
+
+    pub fn render(&mut self, pass: &mut wgpu::RenderPass, camera_bind_group: &wgpu::BindGroup) {
+        if self.emitters.is_empty() {
+            return;
+        }
+
+        pass.set_pipeline(&self.render_pipeline);
+        pass.set_bind_group(0, camera_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        self.emitters.values().for_each(|emitter| {
+            pass.set_bind_group(1, emitter.texture.bind_group(), &[]);
+            pass.set_bind_group(2, &emitter.render_bind_group, &[]);
+            pass.draw_indexed(0..TEXTURE_RECT_INDEX_COUNT, 0, 0..emitter.capacity);
+        });
+    }
+}
+
+//====================================================================
@@ -1,8 +1,20 @@
 //====================================================================
 
+pub mod blob_shadow_renderer;
+pub mod draw_order;
+pub mod gpu_particles;
 pub mod line_renderer;
+pub mod manager;
 pub mod model_renderer;
+pub mod parallax_renderer;
+pub mod resolution;
+#[cfg(test)]
+mod test_utils;
 pub mod texture2d_renderer;
+pub mod texture_array_renderer;
+pub mod transparency;
+pub mod wind;
+pub mod world_panel_renderer;
 
 //====================================================================
 
@@ -1,6 +1,7 @@
 //====================================================================
 
 use roots_renderer::{
+    layouts,
     shared::{SharedRenderResources, Vertex},
     tools,
 };
@@ -92,6 +93,8 @@ pub struct LineRenderer {
     instance_count: u32,
 
     to_prep: Vec<LineInstance>,
+
+    use_depth: bool,
 }
 
 impl LineRenderer {
@@ -112,21 +115,29 @@ impl LineRenderer {
 
         let descriptor = tools::RenderPipelineDescriptor {
             fragment_targets: Some(&fragment_targets),
+            multisample: shared.multisample(),
+            cache: shared.pipeline_cache(),
             ..Default::default()
         };
 
         let descriptor = match use_depth {
-            true => descriptor.with_depth_stencil(),
+            true => descriptor.with_depth(shared.depth_convention()),
             false => descriptor,
         };
 
+        let shader = include_str!("shaders/line.wgsl");
+        let layouts = [layouts::CAMERA];
+        shared
+            .layouts()
+            .debug_validate_shader("Line Pipeline", shader, &layouts);
+
         let pipeline = tools::create_pipeline(
             device,
             config,
             "Line Pipeline",
-            &[shared.camera_bind_group_layout()],
+            &shared.layouts().layouts(&layouts),
             &[LineVertex::desc(), LineInstance::desc()],
-            include_str!("shaders/line.wgsl"),
+            shader,
             descriptor,
         );
 
@@ -154,9 +165,15 @@ impl LineRenderer {
             instance_buffer,
             instance_count,
             to_prep: Vec::new(),
+            use_depth,
         }
     }
 
+    #[inline]
+    pub fn use_depth(&self) -> bool {
+        self.use_depth
+    }
+
     #[inline]
     pub fn prep_lines(&mut self, line: &[LineInstance]) {
         self.to_prep.extend_from_slice(line)
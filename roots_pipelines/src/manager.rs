@@ -0,0 +1,1208 @@
+//====================================================================
+
+use std::{
+    any::Any,
+    fmt::Display,
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    time::Duration,
+};
+
+use roots_common::Size;
+use roots_renderer::{shared::DepthConvention, Color, RenderEncoder, RenderPass, RenderPassDesc};
+use web_time::Instant;
+
+use crate::{
+    blob_shadow_renderer::BlobShadowRenderer, gpu_particles::GpuParticleRenderer,
+    line_renderer::LineRenderer, model_renderer::ModelRenderer,
+    parallax_renderer::ParallaxRenderer, resolution::SceneTarget,
+    texture2d_renderer::Texture2dRenderer, texture_array_renderer::TextureArrayRenderer,
+    world_panel_renderer::WorldPanelRenderer,
+};
+
+//====================================================================
+
+/// Shared bind groups available to every pipeline while rendering.
+pub struct RenderContext<'a> {
+    pub camera: &'a wgpu::BindGroup,
+    pub lighting: &'a wgpu::BindGroup,
+    /// Orthographic camera covering the render target in pixels, with the origin
+    /// at the bottom left. Used by screen space pipelines.
+    pub screen_camera: &'a wgpu::BindGroup,
+}
+
+/// Targets the managed pipelines render into.
+pub struct PipelineTargets<'a> {
+    /// Color view to render into, or the encoder's surface view when `None`.
+    pub color: Option<&'a wgpu::TextureView>,
+    /// Multisampled view rendered into and resolved into `color`, when multisampling.
+    /// `depth` must share its sample count.
+    pub multisampled: Option<&'a wgpu::TextureView>,
+    pub depth: &'a wgpu::TextureView,
+    pub clear_color: Option<Color>,
+    pub depth_convention: DepthConvention,
+}
+
+/// A managed pipeline that panicked and was disabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineFault {
+    pub name: &'static str,
+    /// The call that panicked, such as `prep` or `render`.
+    pub stage: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for PipelineFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} panicked in {}: {}",
+            self.name, self.stage, self.message
+        )
+    }
+}
+
+/// The message of a panic payload, for the common `&str` and `String` cases.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "unknown panic payload".into(),
+        },
+    }
+}
+
+//====================================================================
+
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A pipeline that can be driven by a `PipelineManager`. Preparing data is done
+/// through each pipeline's own methods before calling `PipelineManager::render`.
+pub trait RenderPipeline: AsAny {
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext);
+
+    /// Draw anything alpha blended, such as models faded below full opacity. Called for
+    /// pipelines that need depth in the last depth pass, after every pipeline's `render`,
+    /// so the opaque scene is already in the depth buffer.
+    #[inline]
+    fn render_transparent(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        let _ = (render_pass, context);
+    }
+
+    /// Record compute work, such as simulating particles, before any render passes
+    /// begin. Only called for enabled pipelines.
+    #[inline]
+    fn compute(&mut self, encoder: &mut RenderEncoder) {
+        let _ = encoder;
+    }
+
+    /// Whether this pipeline renders with a depth attachment. Pipelines that return
+    /// false are grouped into render passes without depth, so they must be created
+    /// without a depth stencil state.
+    #[inline]
+    fn needs_depth(&self) -> bool {
+        true
+    }
+
+    /// Whether this pipeline is rendered once per eye when rendering in stereo. Pipelines
+    /// that return false (such as text or UI) are rendered once across the full frame.
+    #[inline]
+    fn stereo(&self) -> bool {
+        true
+    }
+
+    /// Whether this pipeline only draws with `RenderContext::screen_camera`. Screen
+    /// space pipelines can still be rendered without a world camera, see
+    /// `PipelineManager::render_screen_space`.
+    #[inline]
+    fn screen_space(&self) -> bool {
+        false
+    }
+
+    /// Draw calls recorded last frame, for `PipelineManager::describe`. `None` if the
+    /// pipeline doesn't count them.
+    #[inline]
+    fn draw_calls(&self) -> Option<u32> {
+        None
+    }
+}
+
+//====================================================================
+
+/// Which of a pipeline's draws are being rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RenderQueue {
+    Opaque,
+    Transparent,
+}
+
+impl RenderQueue {
+    #[inline]
+    fn render<P: ?Sized + RenderPipeline>(
+        self,
+        pipeline: &mut P,
+        render_pass: &mut RenderPass,
+        context: &RenderContext,
+    ) {
+        match self {
+            RenderQueue::Opaque => pipeline.render(render_pass, context),
+            RenderQueue::Transparent => pipeline.render_transparent(render_pass, context),
+        }
+    }
+}
+
+struct ManagedPipeline<P: ?Sized> {
+    priority: usize,
+    /// Set by `PipelineManager::set_priority` and applied by `begin_frame`.
+    pending_priority: Option<usize>,
+    needs_depth: bool,
+    enabled: bool,
+    name: &'static str,
+    fault: Option<PipelineFault>,
+    /// Time spent in `prep` since `begin_frame`.
+    prep_time: Duration,
+    /// Time spent in `compute`, `render` and `render_transparent` since `begin_frame`.
+    render_time: Duration,
+    pipeline: Box<P>,
+}
+
+impl<P: ?Sized> ManagedPipeline<P> {
+    #[inline]
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.name.rsplit("::").next() == Some(name)
+    }
+
+    /// Run `f` on the pipeline, disabling it instead of unwinding if it panics.
+    fn run_isolated(&mut self, stage: &'static str, f: impl FnOnce(&mut P)) {
+        let start = Instant::now();
+
+        // Asserted unwind safe as a pipeline that panicked isn't run again until it's
+        // re-enabled, so state it left half updated is never observed
+        let pipeline = &mut *self.pipeline;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(pipeline)));
+
+        match stage {
+            "prep" => self.prep_time += start.elapsed(),
+            _ => self.render_time += start.elapsed(),
+        }
+
+        let Err(payload) = result else {
+            return;
+        };
+
+        let fault = PipelineFault {
+            name: self.name,
+            stage,
+            message: panic_message(&*payload),
+        };
+
+        log::error!("Disabling managed pipeline - {}", fault);
+
+        self.enabled = false;
+        self.fault = Some(fault);
+    }
+}
+
+/// Stores pipelines ordered by priority and renders them in as few passes as possible.
+///
+/// Each pipeline is run in isolation - one that panics is disabled and recorded in
+/// `faults` while the rest keep rendering. Panics abort on wasm, so can't be caught there.
+pub struct PipelineManager<P: ?Sized + RenderPipeline = dyn RenderPipeline> {
+    pipelines: Vec<ManagedPipeline<P>>,
+}
+
+impl<P: ?Sized + RenderPipeline> Default for PipelineManager<P> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            pipelines: Vec::new(),
+        }
+    }
+}
+
+impl PipelineManager {
+    #[inline]
+    pub fn add<T: RenderPipeline>(&mut self, priority: usize, pipeline: T) {
+        self.add_boxed_named(priority, std::any::type_name::<T>(), Box::new(pipeline));
+    }
+}
+
+impl<P: ?Sized + RenderPipeline> PipelineManager<P> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn add_boxed(&mut self, priority: usize, pipeline: Box<P>) {
+        self.add_boxed_named(priority, std::any::type_name::<P>(), pipeline);
+    }
+
+    /// Add a pipeline with a name that can be used to enable or disable it later.
+    /// Usually the pipeline's type name.
+    pub fn add_boxed_named(&mut self, priority: usize, name: &'static str, pipeline: Box<P>) {
+        let needs_depth = pipeline.needs_depth();
+
+        self.pipelines.push(ManagedPipeline {
+            priority,
+            pending_priority: None,
+            needs_depth,
+            enabled: true,
+            name,
+            fault: None,
+            prep_time: Duration::ZERO,
+            render_time: Duration::ZERO,
+            pipeline,
+        });
+        self.pipelines.sort_by_key(|val| val.priority);
+    }
+
+    /// Move every pipeline matching `name` (see `set_enabled`) to `priority` from the
+    /// next `begin_frame`, so the order can't change partway through a frame. Pipelines
+    /// sharing a priority keep the order they were added in. Returns the number of
+    /// pipelines matched.
+    pub fn set_priority(&mut self, name: &str, priority: usize) -> usize {
+        self.pipelines
+            .iter_mut()
+            .filter(|managed| managed.matches(name))
+            .fold(0, |count, managed| {
+                managed.pending_priority = Some(priority);
+                count + 1
+            })
+    }
+
+    /// Apply priorities set since the last frame and reset the timings shown by
+    /// `describe`. Call at the start of each frame, before prepping the pipelines.
+    pub fn begin_frame(&mut self) {
+        self.apply_priorities();
+
+        self.pipelines.iter_mut().for_each(|managed| {
+            managed.prep_time = Duration::ZERO;
+            managed.render_time = Duration::ZERO;
+        });
+    }
+
+    /// Also applied before rendering, for managers driven without `begin_frame`.
+    fn apply_priorities(&mut self) {
+        let mut changed = false;
+
+        self.pipelines.iter_mut().for_each(|managed| {
+            if let Some(priority) = managed.pending_priority.take() {
+                log::debug!(
+                    "Moving managed pipeline {} from priority {} to {}",
+                    managed.name,
+                    managed.priority,
+                    priority
+                );
+                changed |= managed.priority != priority;
+                managed.priority = priority;
+            }
+        });
+
+        // Stable, so equal priorities keep their relative order
+        if changed {
+            self.pipelines.sort_by_key(|managed| managed.priority);
+        }
+    }
+
+    /// Names and enabled state of each pipeline, in render order.
+    #[inline]
+    pub fn names(&self) -> impl Iterator<Item = (&'static str, bool)> + '_ {
+        self.pipelines
+            .iter()
+            .map(|managed| (managed.name, managed.enabled))
+    }
+
+    /// Enable or disable every pipeline whose name matches, either fully or by the
+    /// last path segment (`ModelRenderer` matches `roots_pipelines::model_renderer::ModelRenderer`).
+    /// Disabled pipelines are skipped while rendering. Enabling a faulted pipeline clears
+    /// its fault and runs it again. Returns the number of pipelines matched.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> usize {
+        self.pipelines
+            .iter_mut()
+            .filter(|managed| managed.matches(name))
+            .fold(0, |count, managed| {
+                managed.enabled = enabled;
+                if enabled {
+                    managed.fault = None;
+                }
+                count + 1
+            })
+    }
+
+    /// Pipelines disabled after panicking, in render order.
+    #[inline]
+    pub fn faults(&self) -> impl Iterator<Item = &PipelineFault> {
+        self.pipelines
+            .iter()
+            .filter_map(|managed| managed.fault.as_ref())
+    }
+
+    /// Run `f` on every pipeline that hasn't faulted, including disabled ones, such as
+    /// to prep them. Pipelines that panic are disabled and recorded as faulted under
+    /// `stage`.
+    pub fn for_each_isolated(&mut self, stage: &'static str, mut f: impl FnMut(&mut P)) {
+        self.pipelines
+            .iter_mut()
+            .filter(|managed| managed.fault.is_none())
+            .for_each(|managed| managed.run_isolated(stage, &mut f));
+    }
+
+    /// Whether the first pipeline matching `name` is enabled. See `set_enabled`.
+    pub fn enabled(&self, name: &str) -> Option<bool> {
+        self.pipelines
+            .iter()
+            .find(|managed| managed.matches(name))
+            .map(|managed| managed.enabled)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    pub fn get<T: RenderPipeline>(&self) -> Option<&T> {
+        self.pipelines
+            .iter()
+            .find_map(|managed| (*managed.pipeline).as_any().downcast_ref())
+    }
+
+    pub fn get_mut<T: RenderPipeline>(&mut self) -> Option<&mut T> {
+        self.pipelines
+            .iter_mut()
+            .find_map(|managed| (*managed.pipeline).as_any_mut().downcast_mut())
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &P> {
+        self.pipelines.iter().map(|managed| &*managed.pipeline)
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut P> {
+        self.pipelines
+            .iter_mut()
+            .map(|managed| &mut *managed.pipeline)
+    }
+
+    #[inline]
+    pub fn render(
+        &mut self,
+        encoder: &mut RenderEncoder,
+        targets: &PipelineTargets,
+        context: &RenderContext,
+    ) {
+        self.compute(encoder);
+        self.render_passes(
+            encoder,
+            targets,
+            |_| true,
+            |render_pass, pipeline, queue| queue.render(pipeline, render_pass, context),
+        );
+    }
+
+    /// Render only the screen space pipelines, such as when there is no world camera
+    /// for the rest. Passes are still begun for every group so the targets are cleared.
+    #[inline]
+    pub fn render_screen_space(
+        &mut self,
+        encoder: &mut RenderEncoder,
+        targets: &PipelineTargets,
+        context: &RenderContext,
+    ) {
+        self.compute(encoder);
+        self.render_passes(
+            encoder,
+            targets,
+            |_| true,
+            |render_pass, pipeline, queue| {
+                if pipeline.screen_space() {
+                    queue.render(pipeline, render_pass, context)
+                }
+            },
+        );
+    }
+
+    /// Render the pipelines that aren't screen space into `targets` without running
+    /// their compute passes again, such as for an offscreen view alongside the main
+    /// frame. Should come before the frame's other passes in the same encoder.
+    #[inline]
+    pub fn render_offscreen(
+        &mut self,
+        encoder: &mut RenderEncoder,
+        targets: &PipelineTargets,
+        context: &RenderContext,
+    ) {
+        self.render_passes(
+            encoder,
+            targets,
+            |pipeline| !pipeline.screen_space(),
+            |render_pass, pipeline, queue| queue.render(pipeline, render_pass, context),
+        );
+    }
+
+    /// Render the pipelines that aren't screen space into `scene`, upscale it to
+    /// `targets` and then render the screen space pipelines over the top at native
+    /// resolution. Screen space pipelines are always drawn after the scene, whatever
+    /// their priority.
+    pub fn render_scaled(
+        &mut self,
+        encoder: &mut RenderEncoder,
+        scene: &SceneTarget,
+        targets: &PipelineTargets,
+        context: &RenderContext,
+    ) {
+        self.compute(encoder);
+
+        let scene_targets = PipelineTargets {
+            color: Some(scene.color_view()),
+            multisampled: scene.multisampled_view(),
+            depth: scene.depth_view(),
+            clear_color: targets.clear_color,
+            depth_convention: targets.depth_convention,
+        };
+
+        self.render_passes(
+            encoder,
+            &scene_targets,
+            |pipeline| !pipeline.screen_space(),
+            |render_pass, pipeline, queue| queue.render(pipeline, render_pass, context),
+        );
+
+        scene.upscale(encoder, targets);
+
+        let overlay_targets = PipelineTargets {
+            clear_color: None,
+            ..*targets
+        };
+
+        self.render_passes(
+            encoder,
+            &overlay_targets,
+            |pipeline| pipeline.screen_space(),
+            |render_pass, pipeline, queue| queue.render(pipeline, render_pass, context),
+        );
+    }
+
+    /// Render side-by-side stereo. Stereo pipelines are rendered into the left and right
+    /// halves of the frame with each eye's context while the rest use `mono` across the
+    /// full frame.
+    pub fn render_stereo(
+        &mut self,
+        encoder: &mut RenderEncoder,
+        targets: &PipelineTargets,
+        size: Size<u32>,
+        mono: &RenderContext,
+        eyes: [&RenderContext; 2],
+    ) {
+        let eye_width = size.width as f32 / 2.;
+        let height = size.height as f32;
+
+        self.compute(encoder);
+        self.render_passes(
+            encoder,
+            targets,
+            |_| true,
+            |render_pass, pipeline, queue| match pipeline.stereo() {
+                true => eyes.iter().enumerate().for_each(|(index, eye)| {
+                    render_pass.set_viewport(
+                        eye_width * index as f32,
+                        0.,
+                        eye_width,
+                        height,
+                        0.,
+                        1.,
+                    );
+                    queue.render(pipeline, render_pass, eye);
+                }),
+
+                false => {
+                    render_pass.set_viewport(0., 0., size.width as f32, height, 0., 1.);
+                    queue.render(pipeline, render_pass, mono);
+                }
+            },
+        );
+    }
+
+    fn compute(&mut self, encoder: &mut RenderEncoder) {
+        self.apply_priorities();
+
+        self.pipelines
+            .iter_mut()
+            .filter(|managed| managed.enabled)
+            .for_each(|managed| {
+                managed.run_isolated("compute", |pipeline| pipeline.compute(encoder))
+            });
+    }
+
+    /// Consecutive pipelines (by priority) that agree on depth share a render pass.
+    fn pass_groups(&self) -> Vec<Range<usize>> {
+        let mut groups = Vec::new();
+        self.pipelines
+            .chunk_by(|a, b| a.needs_depth == b.needs_depth)
+            .fold(0, |start, group| {
+                groups.push(start..start + group.len());
+                start + group.len()
+            });
+
+        groups
+    }
+
+    /// The pass that also renders every depth pipeline's transparent queue.
+    #[inline]
+    fn last_depth_group(&self, groups: &[Range<usize>]) -> Option<usize> {
+        groups
+            .iter()
+            .rposition(|group| self.pipelines[group.start].needs_depth)
+    }
+
+    /// The pipelines in execution order, with the pass each renders in and last
+    /// frame's timings. See `FrameDescription`.
+    pub fn describe(&self) -> FrameDescription {
+        let groups = self.pass_groups();
+        let transparent_pass = self.last_depth_group(&groups);
+
+        let pipelines = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(pass, group)| {
+                self.pipelines[group.clone()]
+                    .iter()
+                    .map(move |managed| PipelineDescription {
+                        name: managed.name,
+                        priority: managed.priority,
+                        pending_priority: managed.pending_priority,
+                        pass,
+                        needs_depth: managed.needs_depth,
+                        screen_space: managed.pipeline.screen_space(),
+                        stereo: managed.pipeline.stereo(),
+                        enabled: managed.enabled,
+                        faulted: managed.fault.is_some(),
+                        prep_time: managed.prep_time,
+                        render_time: managed.render_time,
+                        draw_calls: managed.pipeline.draw_calls(),
+                    })
+            })
+            .collect();
+
+        FrameDescription {
+            pipelines,
+            passes: groups.len(),
+            transparent_pass,
+        }
+    }
+
+    /// Render the enabled pipelines accepted by `filter`.
+    fn render_passes(
+        &mut self,
+        encoder: &mut RenderEncoder,
+        targets: &PipelineTargets,
+        filter: impl Fn(&P) -> bool,
+        mut render: impl FnMut(&mut RenderPass, &mut P, RenderQueue),
+    ) {
+        // Make sure the surface is still cleared when there is nothing to render
+        if self.pipelines.is_empty() {
+            encoder.begin_render_pass(RenderPassDesc {
+                color_target: targets.color,
+                multisampled: targets.multisampled,
+                use_depth: None,
+                clear_color: targets.clear_color,
+                ..RenderPassDesc::none()
+            });
+            return;
+        }
+
+        let groups = self.pass_groups();
+        let last_depth_group = self.last_depth_group(&groups);
+
+        let mut color_cleared = false;
+        let mut depth_cleared = false;
+
+        // Only the first pass clears the surface and only the first depth pass clears depth.
+        groups.into_iter().enumerate().for_each(|(index, group)| {
+            let needs_depth = self.pipelines[group.start].needs_depth;
+
+            let mut render_pass = encoder.begin_render_pass(RenderPassDesc {
+                color_target: targets.color,
+                multisampled: targets.multisampled,
+                use_depth: match needs_depth {
+                    true => Some(targets.depth),
+                    false => None,
+                },
+                clear_color: match color_cleared {
+                    true => None,
+                    false => targets.clear_color,
+                },
+                clear_depth: !depth_cleared,
+                depth_convention: targets.depth_convention,
+            });
+
+            color_cleared = true;
+            depth_cleared |= needs_depth;
+
+            self.pipelines[group]
+                .iter_mut()
+                .filter(|managed| managed.enabled && filter(&managed.pipeline))
+                .for_each(|managed| {
+                    managed.run_isolated("render", |pipeline| {
+                        render(&mut render_pass, pipeline, RenderQueue::Opaque)
+                    })
+                });
+
+            // Blended draws of every depth pipeline go last, over the whole opaque scene
+            if Some(index) == last_depth_group {
+                self.pipelines
+                    .iter_mut()
+                    .filter(|managed| {
+                        managed.enabled && managed.needs_depth && filter(&managed.pipeline)
+                    })
+                    .for_each(|managed| {
+                        managed.run_isolated("render_transparent", |pipeline| {
+                            render(&mut render_pass, pipeline, RenderQueue::Transparent)
+                        })
+                    });
+            }
+        });
+    }
+}
+
+//====================================================================
+
+/// A managed pipeline as listed by `FrameDescription`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelineDescription {
+    pub name: &'static str,
+    pub priority: usize,
+    /// Priority set with `PipelineManager::set_priority`, applied next frame.
+    pub pending_priority: Option<usize>,
+    /// Index of the render pass the pipeline's opaque queue is drawn in.
+    pub pass: usize,
+    pub needs_depth: bool,
+    pub screen_space: bool,
+    pub stereo: bool,
+    pub enabled: bool,
+    pub faulted: bool,
+    pub prep_time: Duration,
+    pub render_time: Duration,
+    pub draw_calls: Option<u32>,
+}
+
+/// How the managed pipelines make up a frame, from `PipelineManager::describe`.
+/// Displays as a table for logging.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameDescription {
+    /// In execution order.
+    pub pipelines: Vec<PipelineDescription>,
+    pub passes: usize,
+    /// The pass every depth pipeline's transparent queue is drawn in, after the
+    /// opaque queues of that pass.
+    pub transparent_pass: Option<usize>,
+}
+
+impl FrameDescription {
+    /// Pipelines in pass `pass`, in execution order.
+    #[inline]
+    pub fn pass(&self, pass: usize) -> impl Iterator<Item = &PipelineDescription> {
+        self.pipelines
+            .iter()
+            .filter(move |pipeline| pipeline.pass == pass)
+    }
+}
+
+impl Display for FrameDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Frame of {} managed pipelines in {} passes",
+            self.pipelines.len(),
+            self.passes
+        )?;
+
+        let name_width = self
+            .pipelines
+            .iter()
+            .map(|pipeline| short_name(pipeline.name).len())
+            .max()
+            .unwrap_or(0);
+
+        (0..self.passes).try_for_each(|pass| {
+            let depth = self
+                .pass(pass)
+                .next()
+                .is_some_and(|pipeline| pipeline.needs_depth);
+
+            writeln!(
+                f,
+                "  Pass {} ({})",
+                pass,
+                match depth {
+                    true => "depth",
+                    false => "no depth",
+                }
+            )?;
+
+            self.pass(pass)
+                .try_for_each(|pipeline| write_pipeline(f, pipeline, name_width))?;
+
+            // Timings of the transparent queue are included in the opaque rows
+            if self.transparent_pass == Some(pass) {
+                self.pipelines
+                    .iter()
+                    .filter(|pipeline| pipeline.needs_depth && pipeline.enabled)
+                    .try_for_each(|pipeline| {
+                        writeln!(
+                            f,
+                            "    [{:>5}] {:<name_width$}  transparent",
+                            pipeline.priority,
+                            short_name(pipeline.name),
+                        )
+                    })?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// The last path segment of a type name, keeping generics readable.
+fn short_name(name: &str) -> &str {
+    let path = name.split('<').next().unwrap_or(name);
+    match path.rsplit_once("::") {
+        Some((prefix, _)) => &name[prefix.len() + 2..],
+        None => name,
+    }
+}
+
+fn write_pipeline(
+    f: &mut std::fmt::Formatter<'_>,
+    pipeline: &PipelineDescription,
+    name_width: usize,
+) -> std::fmt::Result {
+    let state = match (pipeline.faulted, pipeline.enabled) {
+        (true, _) => "faulted",
+        (false, true) => "enabled",
+        (false, false) => "disabled",
+    };
+
+    write!(
+        f,
+        "    [{:>5}] {:<name_width$}  opaque       {:<8}  prep {:>7.3}ms  render {:>7.3}ms",
+        pipeline.priority,
+        short_name(pipeline.name),
+        state,
+        pipeline.prep_time.as_secs_f64() * 1000.,
+        pipeline.render_time.as_secs_f64() * 1000.,
+    )?;
+
+    if let Some(draw_calls) = pipeline.draw_calls {
+        write!(f, "  draws {}", draw_calls)?;
+    }
+    if pipeline.screen_space {
+        f.write_str("  screen space")?;
+    }
+    if let Some(priority) = pipeline.pending_priority {
+        write!(f, "  -> priority {} next frame", priority)?;
+    }
+
+    writeln!(f)
+}
+
+//====================================================================
+
+impl RenderPipeline for ModelRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        if !self.has_instances_to_render() {
+            return;
+        }
+
+        Self::render(self, render_pass, context.camera, context.lighting);
+    }
+
+    #[inline]
+    fn render_transparent(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render_transparent(self, render_pass, context.camera, context.lighting);
+    }
+
+    #[inline]
+    fn draw_calls(&self) -> Option<u32> {
+        Some(Self::draw_calls(self))
+    }
+}
+
+impl RenderPipeline for Texture2dRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render(self, render_pass, context.camera);
+    }
+
+    #[inline]
+    fn render_transparent(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render_transparent(self, render_pass, context.camera);
+    }
+
+    #[inline]
+    fn needs_depth(&self) -> bool {
+        self.use_depth()
+    }
+}
+
+impl RenderPipeline for ParallaxRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render(self, render_pass, context.camera);
+    }
+
+    #[inline]
+    fn render_transparent(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render_transparent(self, render_pass, context.camera);
+    }
+}
+
+impl RenderPipeline for TextureArrayRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render(self, render_pass, context.camera);
+    }
+}
+
+impl RenderPipeline for WorldPanelRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render(self, render_pass, context.camera);
+    }
+}
+
+impl RenderPipeline for LineRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render(self, render_pass, context.camera);
+    }
+
+    #[inline]
+    fn needs_depth(&self) -> bool {
+        self.use_depth()
+    }
+}
+
+impl RenderPipeline for BlobShadowRenderer {
+    // Shadows are drawn in the transparent pass, over the opaque scene
+    #[inline]
+    fn render(&mut self, _render_pass: &mut RenderPass, _context: &RenderContext) {}
+
+    #[inline]
+    fn render_transparent(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render(self, render_pass, context.camera);
+    }
+
+    #[inline]
+    fn draw_calls(&self) -> Option<u32> {
+        Some(Self::draw_calls(self))
+    }
+}
+
+impl RenderPipeline for GpuParticleRenderer {
+    #[inline]
+    fn render(&mut self, render_pass: &mut RenderPass, context: &RenderContext) {
+        Self::render(self, render_pass, context.camera);
+    }
+
+    #[inline]
+    fn compute(&mut self, encoder: &mut RenderEncoder) {
+        self.simulate(encoder);
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::test_utils;
+
+    type Log = Rc<RefCell<Vec<(&'static str, RenderQueue)>>>;
+
+    /// Records each call instead of drawing, optionally panicking in `render`.
+    struct Recorder {
+        name: &'static str,
+        needs_depth: bool,
+        panics: bool,
+        log: Log,
+    }
+
+    impl RenderPipeline for Recorder {
+        fn render(&mut self, _render_pass: &mut RenderPass, _context: &RenderContext) {
+            if self.panics {
+                panic!("{} failed to render", self.name);
+            }
+            self.log.borrow_mut().push((self.name, RenderQueue::Opaque));
+        }
+
+        fn render_transparent(&mut self, _render_pass: &mut RenderPass, _context: &RenderContext) {
+            self.log
+                .borrow_mut()
+                .push((self.name, RenderQueue::Transparent));
+        }
+
+        fn needs_depth(&self) -> bool {
+            self.needs_depth
+        }
+    }
+
+    fn add(manager: &mut PipelineManager, priority: usize, name: &'static str, log: &Log) {
+        add_recorder(manager, priority, name, true, false, log);
+    }
+
+    fn add_recorder(
+        manager: &mut PipelineManager,
+        priority: usize,
+        name: &'static str,
+        needs_depth: bool,
+        panics: bool,
+        log: &Log,
+    ) {
+        let recorder = Recorder {
+            name,
+            needs_depth,
+            panics,
+            log: log.clone(),
+        };
+        manager.add_boxed_named(priority, name, Box::new(recorder));
+    }
+
+    struct Gpu {
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        color: wgpu::Texture,
+        depth: wgpu::TextureView,
+        bind_group: wgpu::BindGroup,
+    }
+
+    impl Gpu {
+        fn new() -> Option<Self> {
+            let (device, queue) = test_utils::device()?;
+
+            let texture = |label, format| {
+                device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: 4,
+                        height: 4,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+            };
+
+            let color = texture("Test Color", wgpu::TextureFormat::Rgba8Unorm);
+            let depth = texture("Test Depth", DepthConvention::STANDARD.texture_format())
+                .create_view(&wgpu::TextureViewDescriptor::default());
+
+            let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Test Empty Layout"),
+                entries: &[],
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Test Empty Bind Group"),
+                layout: &layout,
+                entries: &[],
+            });
+
+            Some(Self {
+                device,
+                queue,
+                color,
+                depth,
+                bind_group,
+            })
+        }
+
+        /// Render a frame and return the calls made, in order.
+        fn frame(
+            &self,
+            manager: &mut PipelineManager,
+            log: &Log,
+        ) -> Vec<(&'static str, RenderQueue)> {
+            log.borrow_mut().clear();
+            manager.begin_frame();
+
+            let view = self
+                .color
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            let mut encoder = RenderEncoder::from_view(&self.device, view);
+
+            let targets = PipelineTargets {
+                color: None,
+                multisampled: None,
+                depth: &self.depth,
+                clear_color: Some(Color::new(0., 0., 0., 1.)),
+                depth_convention: DepthConvention::STANDARD,
+            };
+            let context = RenderContext {
+                camera: &self.bind_group,
+                lighting: &self.bind_group,
+                screen_camera: &self.bind_group,
+            };
+
+            manager.render(&mut encoder, &targets, &context);
+            encoder.finish(&self.queue);
+            self.device.poll(wgpu::Maintain::Wait);
+
+            log.take()
+        }
+    }
+
+    fn names(calls: &[(&'static str, RenderQueue)], queue: RenderQueue) -> Vec<&'static str> {
+        calls
+            .iter()
+            .filter(|(_, call_queue)| *call_queue == queue)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    #[test]
+    fn panicking_pipeline_is_disabled_and_recorded() {
+        let Some(gpu) = Gpu::new() else {
+            return;
+        };
+        let log = Log::default();
+
+        let mut manager = PipelineManager::new();
+        add(&mut manager, 0, "Working", &log);
+        add_recorder(&mut manager, 1, "Faulty", true, true, &log);
+        add_recorder(&mut manager, 2, "Overlay", false, false, &log);
+
+        let calls = gpu.frame(&mut manager, &log);
+        assert_eq!(names(&calls, RenderQueue::Opaque), ["Working", "Overlay"]);
+        // Disabled before the transparent queue, so it isn't run again this frame
+        assert_eq!(names(&calls, RenderQueue::Transparent), ["Working"]);
+
+        let faults = manager.faults().cloned().collect::<Vec<_>>();
+        assert_eq!(
+            faults,
+            [PipelineFault {
+                name: "Faulty",
+                stage: "render",
+                message: "Faulty failed to render".into(),
+            }]
+        );
+        assert_eq!(manager.enabled("Faulty"), Some(false));
+        assert!(manager.describe().pipelines[1].faulted);
+
+        // The rest keep rendering on later frames
+        let calls = gpu.frame(&mut manager, &log);
+        assert_eq!(names(&calls, RenderQueue::Opaque), ["Working", "Overlay"]);
+        assert_eq!(manager.faults().count(), 1);
+
+        // Prep panics are recorded under their stage, and skip faulted pipelines
+        let mut prepped = Vec::new();
+        manager.for_each_isolated("prep", |pipeline| {
+            let recorder = (*pipeline).as_any().downcast_ref::<Recorder>().unwrap();
+            prepped.push(recorder.name);
+            if recorder.name == "Overlay" {
+                panic!("Overlay failed to prep");
+            }
+        });
+        assert_eq!(prepped, ["Working", "Overlay"]);
+
+        let stages = manager
+            .faults()
+            .map(|fault| (fault.name, fault.stage))
+            .collect::<Vec<_>>();
+        assert_eq!(stages, [("Faulty", "render"), ("Overlay", "prep")]);
+
+        // Re-enabling clears the fault and runs the pipeline again
+        manager.set_enabled("Overlay", true);
+        let calls = gpu.frame(&mut manager, &log);
+        assert_eq!(names(&calls, RenderQueue::Opaque), ["Working", "Overlay"]);
+        assert_eq!(manager.faults().count(), 1);
+    }
+
+    #[test]
+    fn reordering_between_frames_renders_each_pipeline_once() {
+        let Some(gpu) = Gpu::new() else {
+            return;
+        };
+        let log = Log::default();
+
+        let mut manager = PipelineManager::new();
+        add(&mut manager, 0, "A", &log);
+        add(&mut manager, 1, "B", &log);
+        add_recorder(&mut manager, 2, "C", false, false, &log);
+        add(&mut manager, 3, "D", &log);
+
+        let calls = gpu.frame(&mut manager, &log);
+        assert_eq!(names(&calls, RenderQueue::Opaque), ["A", "B", "C", "D"]);
+        assert_eq!(names(&calls, RenderQueue::Transparent), ["A", "B", "D"]);
+        assert_eq!(manager.describe().passes, 3);
+
+        // Move a depth pipeline to the end and the depth-less one to the front
+        assert_eq!(manager.set_priority("A", 5), 1);
+        assert_eq!(manager.set_priority("C", 0), 1);
+        assert_eq!(manager.set_priority("Missing", 0), 0);
+
+        let pending = manager
+            .describe()
+            .pipelines
+            .iter()
+            .map(|pipeline| (pipeline.name, pipeline.pending_priority))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pending,
+            [("A", Some(5)), ("B", None), ("C", Some(0)), ("D", None)]
+        );
+
+        let calls = gpu.frame(&mut manager, &log);
+        assert_eq!(names(&calls, RenderQueue::Opaque), ["C", "B", "D", "A"]);
+        assert_eq!(names(&calls, RenderQueue::Transparent), ["B", "D", "A"]);
+
+        let description = manager.describe();
+        assert_eq!(description.passes, 2);
+        assert_eq!(
+            description.pass(0).map(|p| p.name).collect::<Vec<_>>(),
+            ["C"]
+        );
+        assert_eq!(
+            description.pass(1).map(|p| p.name).collect::<Vec<_>>(),
+            ["B", "D", "A"]
+        );
+        assert_eq!(description.transparent_pass, Some(1));
+
+        // Back across the boundary, tying with another pipeline's priority
+        manager.set_priority("C", 3);
+
+        let calls = gpu.frame(&mut manager, &log);
+        assert_eq!(names(&calls, RenderQueue::Opaque), ["B", "C", "D", "A"]);
+        assert_eq!(names(&calls, RenderQueue::Transparent), ["B", "D", "A"]);
+        assert_eq!(manager.describe().passes, 3);
+        assert_eq!(manager.len(), 4);
+    }
+}
@@ -1,18 +1,36 @@
 //====================================================================
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Range,
+};
 
+#[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+use rayon::prelude::*;
 use roots_common::FastHasher;
 use roots_renderer::{
+    layouts,
     lighting::LightingManager,
-    model::{LoadedMesh, MeshId, ModelVertex},
+    model::{LoadedMesh, MeshId, MeshPoolId, ModelVertex},
     shared::{SharedRenderResources, Vertex},
     texture::{LoadedTexture, TextureId},
     tools::{self},
+    OPTIONAL_FEATURES,
+};
+
+use crate::{
+    draw_order::{DrawOrderBatch, DrawOrderDebug},
+    transparency::TransparentSort,
+    wind::Wind,
 };
 
 //====================================================================
 
+/// Lighting bindings are prepended from `LightingPath::shader_declarations`.
+const SHADER: &str = include_str!("shaders/model.wgsl");
+
+//====================================================================
+
 #[repr(C)]
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
 struct ModelInstance {
@@ -20,20 +38,43 @@ struct ModelInstance {
     pub color: glam::Vec4,
     pub normal: glam::Mat3,
     pub scale: glam::Vec3,
+    /// Zero for models without wind. See `Wind::to_raw`.
+    pub wind: glam::Vec4,
+    pub wind_shape: glam::Vec4,
+}
+
+impl ModelInstance {
+    #[inline]
+    fn new(model: &ModelData, transform: glam::Mat4) -> Self {
+        let rotation = transform.to_scale_rotation_translation().1;
+        let normal_matrix = glam::Mat3::from_quat(rotation);
+        let (wind, wind_shape) = model.wind.map(Wind::to_raw).unwrap_or_default();
+
+        Self {
+            transform,
+            color: model.color.into(),
+            normal: normal_matrix,
+            scale: model.scale,
+            wind,
+            wind_shape,
+        }
+    }
 }
 
 impl Vertex for ModelInstance {
     fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 9] = wgpu::vertex_attr_array![
-            3 => Float32x4, // Transform
-            4 => Float32x4,
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 11] = wgpu::vertex_attr_array![
+            4 => Float32x4, // Transform
             5 => Float32x4,
             6 => Float32x4,
-            7 => Float32x4, // Color
-            8 => Float32x3, // Normal
-            9 => Float32x3,
+            7 => Float32x4,
+            8 => Float32x4, // Color
+            9 => Float32x3, // Normal
             10 => Float32x3,
-            11 => Float32x3, // Scale
+            11 => Float32x3,
+            12 => Float32x3, // Scale
+            13 => Float32x4, // Wind
+            14 => Float32x4,
         ];
 
         wgpu::VertexBufferLayout {
@@ -44,10 +85,68 @@ impl Vertex for ModelInstance {
     }
 }
 
+/// Matches the layout `multi_draw_indexed_indirect` reads.
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+struct DrawIndexedArgs {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+/// Pooled meshes sharing a texture, drawn with one indirect draw.
+#[derive(Debug)]
+struct IndirectBatch {
+    /// Any mesh from the pool, for its buffers.
+    mesh: MeshId,
+    texture: TextureId,
+    first_draw: u32,
+    draw_count: u32,
+}
+
+/// Instances of every pooled mesh in one buffer, with the draw args for each mesh.
+#[derive(Debug)]
+struct IndirectDraws {
+    instances: tools::InstanceBuffer<ModelInstance>,
+    args: wgpu::Buffer,
+    args_count: u32,
+    batches: Vec<IndirectBatch>,
+}
+
+impl IndirectDraws {
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            instances: tools::InstanceBuffer::new(device, &[]),
+            args: tools::create_buffer_empty(
+                device,
+                tools::BufferType::Indirect,
+                "Model Draw Args",
+                0,
+            ),
+            args_count: 0,
+            batches: Vec::new(),
+        }
+    }
+}
+
+/// A model instance with alpha below 1, recorded during prep.
+#[derive(Debug, Clone, Copy)]
+struct TransparentInstance {
+    depth: f32,
+    mesh: MeshId,
+    texture: TextureId,
+    instance: ModelInstance,
+}
+
+#[derive(Clone, Copy)]
 pub struct ModelData<'a> {
     pub meshes: &'a [(LoadedMesh, LoadedTexture)],
     pub color: [f32; 4],
     pub scale: glam::Vec3,
+    /// Drawn with the animated pipeline when set.
+    pub wind: Option<Wind>,
 }
 
 pub struct MeshInstance<'a> {
@@ -65,6 +164,28 @@ pub struct ModelRenderer {
     instances: HashMap<MeshId, HashMap<TextureId, tools::InstanceBuffer<ModelInstance>>>,
     texture_storage: HashMap<u32, LoadedTexture, FastHasher>,
     mesh_storage: HashMap<u32, LoadedMesh, FastHasher>,
+
+    uploaded_bytes: u64,
+    draw_calls: u32,
+    draw_order: Option<DrawOrderDebug>,
+    /// Kept while draw order debugging is off, so enabling it again doesn't stall.
+    draw_order_cache: Option<DrawOrderDebug>,
+    /// `None` if the device doesn't support indirect drawing.
+    indirect: Option<IndirectDraws>,
+    indirect_enabled: bool,
+
+    /// Draws models with a color alpha below 1, back to front after everything opaque.
+    transparent_pipeline: wgpu::RenderPipeline,
+    transparent_sort: TransparentSort,
+    transparent_to_prep: Vec<TransparentInstance>,
+    transparent_instances: Option<tools::InstanceBuffer<ModelInstance>>,
+    /// Consecutive sorted instances sharing a mesh and texture, drawn with one call each.
+    transparent_runs: Vec<(MeshId, TextureId, Range<u32>)>,
+
+    /// Draws models with wind, displacing their vertices over time.
+    wind_pipeline: wgpu::RenderPipeline,
+    wind_to_prep: HashMap<MeshId, HashMap<TextureId, Vec<ModelInstance>>>,
+    wind_instances: HashMap<MeshId, HashMap<TextureId, tools::InstanceBuffer<ModelInstance>>>,
 }
 
 impl ModelRenderer {
@@ -76,30 +197,254 @@ impl ModelRenderer {
     ) -> Self {
         log::debug!("Creating Model Renderer");
 
-        let pipeline = tools::create_pipeline(
+        let pipeline = Self::create_pipeline(device, config, shared, lighting, None);
+        let transparent_pipeline =
+            Self::create_transparent_pipeline(device, config, shared, lighting);
+        let wind_pipeline = Self::create_wind_pipeline(device, config, shared, lighting);
+
+        Self {
+            pipeline,
+
+            to_prep: HashMap::default(),
+            instances: HashMap::default(),
+            texture_storage: HashMap::default(),
+            mesh_storage: HashMap::default(),
+
+            uploaded_bytes: 0,
+            draw_calls: 0,
+            draw_order: None,
+            draw_order_cache: None,
+            indirect: device
+                .features()
+                .contains(OPTIONAL_FEATURES)
+                .then(|| IndirectDraws::new(device)),
+            indirect_enabled: true,
+
+            transparent_pipeline,
+            transparent_sort: TransparentSort::default(),
+            transparent_to_prep: Vec::new(),
+            transparent_instances: None,
+            transparent_runs: Vec::new(),
+
+            wind_pipeline,
+            wind_to_prep: HashMap::default(),
+            wind_instances: HashMap::default(),
+        }
+    }
+
+    /// Creates the draw order debug variant when `draw_order` is provided.
+    fn create_pipeline(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        lighting: &LightingManager,
+        draw_order: Option<&wgpu::BindGroupLayout>,
+    ) -> wgpu::RenderPipeline {
+        let shader = lighting.path().shader_with_lighting(SHADER);
+
+        shared.layouts().debug_validate_shader(
+            "Model Pipeline",
+            &shader,
+            &[layouts::CAMERA, lighting.path().layout(), layouts::TEXTURE],
+        );
+
+        let mut descriptor = tools::RenderPipelineDescriptor::default()
+            .with_depth(shared.depth_convention())
+            .with_sample_count(shared.sample_count())
+            .with_cache(shared.pipeline_cache())
+            .with_backface_culling();
+
+        let mut bind_group_layouts = vec![
+            shared.camera_bind_group_layout(),
+            lighting.bind_group_layout(),
+            shared.texture_bind_group_layout(),
+        ];
+
+        if let Some(layout) = draw_order {
+            bind_group_layouts.push(layout);
+            descriptor = descriptor.with_entry_points("vs_draw_order", "fs_draw_order");
+        }
+
+        tools::create_pipeline(
             device,
             config,
-            "Model Pipeline",
+            match draw_order {
+                Some(_) => "Model Draw Order Pipeline",
+                None => "Model Pipeline",
+            },
+            &bind_group_layouts,
+            &[ModelVertex::desc(), ModelInstance::desc()],
+            &shader,
+            descriptor,
+        )
+    }
+
+    /// Variant displacing vertices by each instance's wind.
+    fn create_wind_pipeline(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        lighting: &LightingManager,
+    ) -> wgpu::RenderPipeline {
+        let descriptor = tools::RenderPipelineDescriptor::default()
+            .with_depth(shared.depth_convention())
+            .with_sample_count(shared.sample_count())
+            .with_cache(shared.pipeline_cache())
+            .with_backface_culling()
+            .with_entry_points("vs_wind", "fs_main");
+
+        tools::create_pipeline(
+            device,
+            config,
+            "Model Wind Pipeline",
             &[
                 shared.camera_bind_group_layout(),
                 lighting.bind_group_layout(),
                 shared.texture_bind_group_layout(),
             ],
             &[ModelVertex::desc(), ModelInstance::desc()],
-            include_str!("shaders/model.wgsl"),
-            tools::RenderPipelineDescriptor::default()
-                .with_depth_stencil()
-                .with_backface_culling(),
-        );
+            &lighting.path().shader_with_lighting(SHADER),
+            descriptor,
+        )
+    }
 
-        Self {
-            pipeline,
+    /// Alpha blended variant for models with a color alpha below 1. Depth is tested but
+    /// not written, so models further back still show through. Applies wind, which is
+    /// zero for models without it.
+    fn create_transparent_pipeline(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        lighting: &LightingManager,
+    ) -> wgpu::RenderPipeline {
+        let depth_convention = shared.depth_convention();
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
 
-            to_prep: HashMap::default(),
-            instances: HashMap::default(),
-            texture_storage: HashMap::default(),
-            mesh_storage: HashMap::default(),
+        let descriptor = tools::RenderPipelineDescriptor {
+            depth_stencil: Some(
+                depth_convention.depth_stencil_state(false, depth_convention.compare()),
+            ),
+            multisample: shared.multisample(),
+            cache: shared.pipeline_cache(),
+            fragment_targets: Some(&fragment_targets),
+            ..Default::default()
         }
+        .with_backface_culling()
+        .with_entry_points("vs_wind", "fs_main");
+
+        tools::create_pipeline(
+            device,
+            config,
+            "Model Transparent Pipeline",
+            &[
+                shared.camera_bind_group_layout(),
+                lighting.bind_group_layout(),
+                shared.texture_bind_group_layout(),
+            ],
+            &[ModelVertex::desc(), ModelInstance::desc()],
+            &lighting.path().shader_with_lighting(SHADER),
+            descriptor,
+        )
+    }
+
+    /// Swap to a debug pipeline that tints models by draw order. See `DrawOrderDebug`.
+    /// Transparent models are still drawn blended, without a tint.
+    pub fn set_draw_order_debug(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        lighting: &LightingManager,
+        enabled: bool,
+    ) {
+        match (enabled, self.draw_order.is_some()) {
+            (true, false) => {
+                self.draw_order = Some(self.draw_order_cache.take().unwrap_or_else(|| {
+                    Self::create_draw_order_debug(device, config, shared, lighting)
+                }))
+            }
+            (false, true) => self.draw_order_cache = self.draw_order.take(),
+            _ => {}
+        }
+    }
+
+    /// Create the draw order debug pipeline ahead of time, so enabling it doesn't
+    /// compile shaders mid frame.
+    pub fn prewarm_draw_order_debug(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        lighting: &LightingManager,
+    ) {
+        if self.draw_order.is_none() && self.draw_order_cache.is_none() {
+            self.draw_order_cache = Some(Self::create_draw_order_debug(
+                device, config, shared, lighting,
+            ));
+        }
+    }
+
+    fn create_draw_order_debug(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        lighting: &LightingManager,
+    ) -> DrawOrderDebug {
+        let layout = DrawOrderDebug::create_bind_group_layout(device);
+        let pipeline = Self::create_pipeline(device, config, shared, lighting, Some(&layout));
+
+        DrawOrderDebug::new(device, layout, pipeline)
+    }
+
+    /// Batches drawn last frame, in submission order. Empty unless draw order debugging is enabled.
+    #[inline]
+    pub fn draw_order_batches(&self) -> &[DrawOrderBatch] {
+        match &self.draw_order {
+            Some(draw_order) => draw_order.batches(),
+            None => &[],
+        }
+    }
+
+    /// Instance bytes written to the gpu during the last `finish_prep`.
+    #[inline]
+    pub fn uploaded_bytes(&self) -> u64 {
+        self.uploaded_bytes
+    }
+
+    /// Draw calls issued by the last `render`. Each indirect draw counts once.
+    #[inline]
+    pub fn draw_calls(&self) -> u32 {
+        self.draw_calls
+    }
+
+    /// Draw meshes loaded into a `MeshPool` with one indirect draw per pool and
+    /// texture. On by default. Has no effect if the device lacks
+    /// `MULTI_DRAW_INDIRECT` or `INDIRECT_FIRST_INSTANCE`, or while draw order
+    /// debugging is on, where meshes are drawn one at a time instead.
+    #[inline]
+    pub fn set_indirect(&mut self, enabled: bool) {
+        self.indirect_enabled = enabled;
+    }
+
+    #[inline]
+    pub fn indirect_enabled(&self) -> bool {
+        self.indirect_enabled
+    }
+
+    /// Whether pooled meshes are currently drawn indirectly.
+    #[inline]
+    pub fn indirect_active(&self) -> bool {
+        self.indirect_enabled && self.indirect.is_some() && self.draw_order.is_none()
+    }
+
+    /// Whether any models with wind were prepped.
+    #[inline]
+    pub fn has_wind(&self) -> bool {
+        !self.wind_instances.is_empty()
     }
 
     #[inline]
@@ -107,9 +452,37 @@ impl ModelRenderer {
         !self.mesh_storage.is_empty() || !self.texture_storage.is_empty()
     }
 
+    /// Whether any models were prepped with a color alpha below 1.
+    #[inline]
+    pub fn has_transparent(&self) -> bool {
+        !self.transparent_runs.is_empty()
+    }
+
+    /// Orders this frame's transparent models. Must be set before they are prepped.
+    #[inline]
+    pub fn set_transparent_sort(&mut self, sort: TransparentSort) {
+        self.transparent_sort = sort;
+    }
+
+    /// Models with a color alpha below 1 are alpha blended and drawn back to front
+    /// after everything opaque. Fully transparent models are skipped. Models with wind
+    /// are drawn without it while draw order debugging is on.
     pub fn prep_model(&mut self, model: ModelData, transform: glam::Mat4) {
+        match model.color[3] {
+            alpha if alpha <= 0. => return,
+            alpha if alpha < 1. => return self.prep_transparent(&model, transform),
+            _ => {}
+        }
+
+        let instance = ModelInstance::new(&model, transform);
+
+        let to_prep = match model.wind.is_some() && self.draw_order.is_none() {
+            true => &mut self.wind_to_prep,
+            false => &mut self.to_prep,
+        };
+
         model.meshes.iter().for_each(|(mesh, texture)| {
-            let mesh_entry = self.to_prep.entry(mesh.id()).or_insert_with(|| {
+            let mesh_entry = to_prep.entry(mesh.id()).or_insert_with(|| {
                 if !self.mesh_storage.contains_key(&mesh.id()) {
                     self.mesh_storage.insert(mesh.id(), mesh.clone());
                 }
@@ -117,9 +490,6 @@ impl ModelRenderer {
                 HashMap::new()
             });
 
-            let rotation = transform.to_scale_rotation_translation().1;
-            let normal_matrix = glam::Mat3::from_quat(rotation);
-
             mesh_entry
                 .entry(texture.id())
                 .or_insert_with(|| {
@@ -128,15 +498,86 @@ impl ModelRenderer {
                     }
                     Vec::new()
                 })
-                .push(ModelInstance {
-                    transform,
-                    color: model.color.into(),
-                    normal: normal_matrix,
-                    scale: model.scale,
-                });
+                .push(instance);
         });
     }
 
+    fn prep_transparent(&mut self, model: &ModelData, transform: glam::Mat4) {
+        let instance = ModelInstance::new(model, transform);
+        let depth = self.transparent_sort.depth(transform.w_axis.truncate());
+
+        model.meshes.iter().for_each(|(mesh, texture)| {
+            self.mesh_storage
+                .entry(mesh.id())
+                .or_insert_with(|| mesh.clone());
+            self.texture_storage
+                .entry(texture.id())
+                .or_insert_with(|| texture.clone());
+
+            self.transparent_to_prep.push(TransparentInstance {
+                depth,
+                mesh: mesh.id(),
+                texture: texture.id(),
+                instance,
+            });
+        });
+    }
+
+    /// Prep many models at once. With the `rayon` feature enabled (native only),
+    /// instances are gathered into per mesh/texture batches in parallel before
+    /// being merged into the serial prep data.
+    pub fn prep_models(&mut self, models: &[(ModelData, glam::Mat4)]) {
+        #[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+        {
+            let gathered = models
+                .par_iter()
+                .filter(|(model, _)| model.color[3] >= 1. && model.wind.is_none())
+                .fold(GatheredInstances::default, |mut acc, (model, transform)| {
+                    acc.gather(model, *transform);
+                    acc
+                })
+                .reduce(GatheredInstances::default, GatheredInstances::merge);
+
+            self.merge_gathered(gathered);
+
+            // Transparent models are sorted later and models with wind are split off by
+            // `prep_model`, so gathering them in parallel gains little
+            models
+                .iter()
+                .filter(|(model, _)| model.color[3] < 1. || model.wind.is_some())
+                .for_each(|(model, transform)| self.prep_model(*model, *transform));
+        }
+
+        #[cfg(not(all(feature = "rayon", not(target_arch = "wasm32"))))]
+        models
+            .iter()
+            .for_each(|(model, transform)| self.prep_model(*model, *transform));
+    }
+
+    #[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+    fn merge_gathered(&mut self, gathered: GatheredInstances) {
+        gathered
+            .meshes
+            .into_iter()
+            .for_each(|(mesh_id, (mesh, textures))| {
+                self.mesh_storage
+                    .entry(mesh_id)
+                    .or_insert_with(|| mesh.clone());
+
+                let mesh_entry = self.to_prep.entry(mesh_id).or_default();
+
+                textures
+                    .into_iter()
+                    .for_each(|(texture_id, (texture, instances))| {
+                        self.texture_storage
+                            .entry(texture_id)
+                            .or_insert_with(|| texture.clone());
+
+                        mesh_entry.entry(texture_id).or_default().extend(instances);
+                    });
+            });
+    }
+
     pub fn finish_prep(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
         let mut previous = self
             .instances
@@ -148,21 +589,56 @@ impl ModelRenderer {
 
         let mut meshes_used = HashSet::new();
         let mut textures_used = HashSet::new();
+        let mut first_positions = HashMap::new();
+
+        self.uploaded_bytes = 0;
+        let instance_size = std::mem::size_of::<ModelInstance>() as u64;
+
+        // Sorted so the combined instance data is stable between frames
+        let mut pooled = BTreeMap::<(MeshPoolId, TextureId), Vec<(MeshId, Vec<_>)>>::new();
+        let indirect_active = self.indirect_active();
 
         self.to_prep.drain().for_each(|(mesh_id, texture_data)| {
             meshes_used.insert(mesh_id);
 
+            let pool = match indirect_active {
+                true => self.mesh_storage.get(&mesh_id).and_then(LoadedMesh::pool),
+                false => None,
+            };
+
             texture_data.into_iter().for_each(|(texture_id, raw)| {
                 textures_used.insert(texture_id);
 
+                if let Some(pool) = pool {
+                    pooled
+                        .entry((pool, texture_id))
+                        .or_default()
+                        .push((mesh_id, raw));
+                    return;
+                }
+
                 previous.remove(&(mesh_id, texture_id));
 
+                if self.draw_order.is_some() {
+                    first_positions
+                        .insert((mesh_id, texture_id), raw[0].transform.w_axis.truncate());
+                }
+
+                let uploaded = &mut self.uploaded_bytes;
+
                 self.instances
                     .entry(mesh_id)
                     .or_insert(HashMap::default())
                     .entry(texture_id)
-                    .and_modify(|instance| instance.update(device, queue, &raw))
-                    .or_insert_with(|| tools::InstanceBuffer::new(device, &raw));
+                    .and_modify(|instance| {
+                        if instance.update(device, queue, &raw) {
+                            *uploaded += raw.len() as u64 * instance_size;
+                        }
+                    })
+                    .or_insert_with(|| {
+                        *uploaded += raw.len() as u64 * instance_size;
+                        tools::InstanceBuffer::new(device, &raw)
+                    });
             });
         });
 
@@ -174,11 +650,210 @@ impl ModelRenderer {
                 .remove(&texture_id);
         });
 
-        self.texture_storage
-            .retain(|texture_id, _| textures_used.contains(texture_id));
+        if let Some(indirect) = &mut self.indirect {
+            let mut instances = Vec::new();
+            let mut args = Vec::new();
+            indirect.batches.clear();
+
+            pooled.into_iter().for_each(|((_, texture), mut meshes)| {
+                meshes.sort_by_key(|(mesh_id, _)| *mesh_id);
+                let first_draw = args.len() as u32;
+
+                meshes.iter().for_each(|(mesh_id, raw)| {
+                    let mesh = self.mesh_storage.get(mesh_id).unwrap();
+
+                    args.push(DrawIndexedArgs {
+                        index_count: mesh.index_count(),
+                        instance_count: raw.len() as u32,
+                        first_index: mesh.first_index(),
+                        base_vertex: mesh.base_vertex(),
+                        first_instance: instances.len() as u32,
+                    });
+
+                    instances.extend_from_slice(raw);
+                });
+
+                indirect.batches.push(IndirectBatch {
+                    mesh: meshes[0].0,
+                    texture,
+                    first_draw,
+                    draw_count: args.len() as u32 - first_draw,
+                });
+            });
+
+            if indirect.instances.update(device, queue, &instances) {
+                self.uploaded_bytes += instances.len() as u64 * instance_size;
+            }
+
+            tools::update_buffer_data(
+                device,
+                queue,
+                tools::BufferType::Indirect,
+                "Model Draw Args",
+                &mut indirect.args,
+                &mut indirect.args_count,
+                &args,
+            );
+        }
 
-        self.mesh_storage
-            .retain(|mesh_id, _| meshes_used.contains(mesh_id));
+        self.finish_wind(device, queue, &mut meshes_used, &mut textures_used);
+        self.finish_transparent(device, queue);
+
+        self.texture_storage.retain(|texture_id, _| {
+            textures_used.contains(texture_id)
+                || self
+                    .transparent_runs
+                    .iter()
+                    .any(|(_, texture, _)| texture == texture_id)
+        });
+
+        // Pick up levels streamed in or dropped since the last frame
+        self.texture_storage.values_mut().for_each(|texture| {
+            texture.refresh();
+        });
+
+        self.mesh_storage.retain(|mesh_id, _| {
+            meshes_used.contains(mesh_id)
+                || self
+                    .transparent_runs
+                    .iter()
+                    .any(|(mesh, _, _)| mesh == mesh_id)
+        });
+
+        if let Some(draw_order) = &mut self.draw_order {
+            let batches = self
+                .instances
+                .iter()
+                .flat_map(|(mesh_id, textures)| {
+                    textures
+                        .iter()
+                        .map(move |(texture_id, instance)| (*mesh_id, *texture_id, instance))
+                })
+                .enumerate()
+                .map(|(order, (mesh_id, texture_id, instance))| DrawOrderBatch {
+                    order: order as u32,
+                    mesh: Some(mesh_id),
+                    texture: texture_id,
+                    instances: instance.count(),
+                    position: first_positions
+                        .get(&(mesh_id, texture_id))
+                        .copied()
+                        .unwrap_or_default(),
+                })
+                .collect();
+
+            draw_order.update(device, queue, batches);
+        }
+    }
+
+    /// Upload this frame's models with wind, dropping the buffers of any no longer drawn.
+    fn finish_wind(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        meshes_used: &mut HashSet<MeshId>,
+        textures_used: &mut HashSet<TextureId>,
+    ) {
+        let mut previous = self
+            .wind_instances
+            .iter()
+            .flat_map(|(mesh_id, textures)| {
+                textures.keys().map(|texture_id| (*mesh_id, *texture_id))
+            })
+            .collect::<HashSet<_>>();
+
+        let instance_size = std::mem::size_of::<ModelInstance>() as u64;
+
+        self.wind_to_prep
+            .drain()
+            .for_each(|(mesh_id, texture_data)| {
+                meshes_used.insert(mesh_id);
+
+                texture_data.into_iter().for_each(|(texture_id, raw)| {
+                    textures_used.insert(texture_id);
+                    previous.remove(&(mesh_id, texture_id));
+
+                    let uploaded = &mut self.uploaded_bytes;
+
+                    self.wind_instances
+                        .entry(mesh_id)
+                        .or_default()
+                        .entry(texture_id)
+                        .and_modify(|instance| {
+                            if instance.update(device, queue, &raw) {
+                                *uploaded += raw.len() as u64 * instance_size;
+                            }
+                        })
+                        .or_insert_with(|| {
+                            *uploaded += raw.len() as u64 * instance_size;
+                            tools::InstanceBuffer::new(device, &raw)
+                        });
+                });
+            });
+
+        previous.into_iter().for_each(|(mesh_id, texture_id)| {
+            log::trace!("Removing model wind instance {} - {}", mesh_id, texture_id);
+            self.wind_instances
+                .get_mut(&mesh_id)
+                .unwrap()
+                .remove(&texture_id);
+        });
+
+        self.wind_instances
+            .retain(|_, textures| !textures.is_empty());
+    }
+
+    /// Sort this frame's transparent instances back to front and upload them as one buffer.
+    fn finish_transparent(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.transparent_runs.clear();
+
+        if self.transparent_to_prep.is_empty() {
+            self.transparent_instances = None;
+            return;
+        }
+
+        // Stable, so instances at the same depth keep their prep order
+        self.transparent_to_prep
+            .sort_by(|a, b| b.depth.total_cmp(&a.depth));
+
+        let raw = self
+            .transparent_to_prep
+            .iter()
+            .enumerate()
+            .map(|(index, transparent)| {
+                let index = index as u32;
+
+                match self.transparent_runs.last_mut() {
+                    Some((mesh, texture, range))
+                        if *mesh == transparent.mesh && *texture == transparent.texture =>
+                    {
+                        range.end = index + 1
+                    }
+                    _ => self.transparent_runs.push((
+                        transparent.mesh,
+                        transparent.texture,
+                        index..index + 1,
+                    )),
+                }
+
+                transparent.instance
+            })
+            .collect::<Vec<_>>();
+
+        self.transparent_to_prep.clear();
+        let bytes = (raw.len() * std::mem::size_of::<ModelInstance>()) as u64;
+
+        match &mut self.transparent_instances {
+            Some(instances) => {
+                if instances.update(device, queue, &raw) {
+                    self.uploaded_bytes += bytes;
+                }
+            }
+            None => {
+                self.uploaded_bytes += bytes;
+                self.transparent_instances = Some(tools::InstanceBuffer::new(device, &raw));
+            }
+        }
     }
 
     pub fn render(
@@ -187,25 +862,177 @@ impl ModelRenderer {
         camera_bind_group: &wgpu::BindGroup,
         lighting_bind_group: &wgpu::BindGroup,
     ) {
-        pass.set_pipeline(&self.pipeline);
+        match &self.draw_order {
+            Some(draw_order) => pass.set_pipeline(draw_order.pipeline()),
+            None => pass.set_pipeline(&self.pipeline),
+        }
         pass.set_bind_group(0, camera_bind_group, &[]);
         pass.set_bind_group(1, lighting_bind_group, &[]);
 
+        let mut order = 0;
+        self.draw_calls = 0;
+
         self.instances.iter().for_each(|(mesh_id, instance)| {
+            if instance.is_empty() {
+                return;
+            }
+
+            let mesh = self.mesh_storage.get(mesh_id).unwrap();
+
+            pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+            pass.set_index_buffer(mesh.index_buffer().slice(..), mesh.index_format());
+
+            instance.iter().for_each(|(texture_id, instance)| {
+                let texture = self.texture_storage.get(texture_id).unwrap();
+
+                if let Some(draw_order) = &self.draw_order {
+                    draw_order.set_bind_group(pass, 3, order);
+                    order += 1;
+                }
+
+                pass.set_bind_group(2, texture.bind_group(), &[]);
+                pass.set_vertex_buffer(1, instance.slice(..));
+                pass.draw_indexed(mesh.index_range(), mesh.base_vertex(), 0..instance.count());
+                self.draw_calls += 1;
+            });
+        });
+
+        if let Some(indirect) = self
+            .indirect
+            .as_ref()
+            .filter(|indirect| !indirect.batches.is_empty())
+        {
+            pass.set_vertex_buffer(1, indirect.instances.slice(..));
+            let mut bound_pool = None;
+
+            indirect.batches.iter().for_each(|batch| {
+                let mesh = self.mesh_storage.get(&batch.mesh).unwrap();
+                let texture = self.texture_storage.get(&batch.texture).unwrap();
+
+                if bound_pool != mesh.pool() {
+                    bound_pool = mesh.pool();
+                    pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+                    pass.set_index_buffer(mesh.index_buffer().slice(..), mesh.index_format());
+                }
+
+                pass.set_bind_group(2, texture.bind_group(), &[]);
+                pass.multi_draw_indexed_indirect(
+                    &indirect.args,
+                    batch.first_draw as u64 * std::mem::size_of::<DrawIndexedArgs>() as u64,
+                    batch.draw_count,
+                );
+                self.draw_calls += 1;
+            });
+        }
+
+        if self.wind_instances.is_empty() {
+            return;
+        }
+
+        // Same bind groups, so only the pipeline changes
+        pass.set_pipeline(&self.wind_pipeline);
+
+        self.wind_instances.iter().for_each(|(mesh_id, instance)| {
             let mesh = self.mesh_storage.get(mesh_id).unwrap();
 
             pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
-            pass.set_index_buffer(mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+            pass.set_index_buffer(mesh.index_buffer().slice(..), mesh.index_format());
 
             instance.iter().for_each(|(texture_id, instance)| {
                 let texture = self.texture_storage.get(texture_id).unwrap();
 
                 pass.set_bind_group(2, texture.bind_group(), &[]);
                 pass.set_vertex_buffer(1, instance.slice(..));
-                pass.draw_indexed(0..mesh.index_count(), 0, 0..instance.count());
+                pass.draw_indexed(mesh.index_range(), mesh.base_vertex(), 0..instance.count());
+                self.draw_calls += 1;
+            });
+        });
+    }
+
+    /// Draw the models prepped with alpha below 1. Should be rendered after every
+    /// opaque pipeline, see `RenderPipeline::render_transparent`.
+    pub fn render_transparent(
+        &mut self,
+        pass: &mut wgpu::RenderPass,
+        camera_bind_group: &wgpu::BindGroup,
+        lighting_bind_group: &wgpu::BindGroup,
+    ) {
+        let Some(instances) = &self.transparent_instances else {
+            return;
+        };
+
+        pass.set_pipeline(&self.transparent_pipeline);
+        pass.set_bind_group(0, camera_bind_group, &[]);
+        pass.set_bind_group(1, lighting_bind_group, &[]);
+        pass.set_vertex_buffer(1, instances.slice(..));
+
+        self.transparent_runs
+            .iter()
+            .for_each(|(mesh_id, texture_id, range)| {
+                let mesh = self.mesh_storage.get(mesh_id).unwrap();
+                let texture = self.texture_storage.get(texture_id).unwrap();
+
+                pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+                pass.set_index_buffer(mesh.index_buffer().slice(..), mesh.index_format());
+                pass.set_bind_group(2, texture.bind_group(), &[]);
+                pass.draw_indexed(mesh.index_range(), mesh.base_vertex(), range.clone());
+                self.draw_calls += 1;
             });
+    }
+}
+
+//====================================================================
+
+#[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+type GatheredTextures<'a> = HashMap<TextureId, (&'a LoadedTexture, Vec<ModelInstance>), FastHasher>;
+
+#[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+#[derive(Default)]
+struct GatheredInstances<'a> {
+    meshes: HashMap<MeshId, (&'a LoadedMesh, GatheredTextures<'a>), FastHasher>,
+}
+
+#[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+impl<'a> GatheredInstances<'a> {
+    fn gather(&mut self, model: &ModelData<'a>, transform: glam::Mat4) {
+        let instance = ModelInstance::new(model, transform);
+
+        model.meshes.iter().for_each(|(mesh, texture)| {
+            self.meshes
+                .entry(mesh.id())
+                .or_insert_with(|| (mesh, HashMap::default()))
+                .1
+                .entry(texture.id())
+                .or_insert_with(|| (texture, Vec::new()))
+                .1
+                .push(instance);
         });
     }
+
+    fn merge(mut self, other: Self) -> Self {
+        other
+            .meshes
+            .into_iter()
+            .for_each(|(mesh_id, (mesh, textures))| {
+                let mesh_entry = self
+                    .meshes
+                    .entry(mesh_id)
+                    .or_insert_with(|| (mesh, HashMap::default()));
+
+                textures
+                    .into_iter()
+                    .for_each(|(texture_id, (texture, instances))| {
+                        mesh_entry
+                            .1
+                            .entry(texture_id)
+                            .or_insert_with(|| (texture, Vec::new()))
+                            .1
+                            .extend(instances);
+                    });
+            });
+
+        self
+    }
 }
 
 //====================================================================
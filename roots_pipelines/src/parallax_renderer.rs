@@ -0,0 +1,436 @@
+//====================================================================
+
+use std::collections::HashMap;
+
+use roots_renderer::{
+    layouts,
+    shared::{SharedRenderResources, Vertex},
+    texture::{
+        LoadedTexture, TextureId, TextureRectVertex, TEXTURE_RECT_INDEX_COUNT,
+        TEXTURE_RECT_INDICES, TEXTURE_RECT_VERTICES,
+    },
+    tools,
+};
+
+use crate::transparency::TransparentSort;
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+pub struct ParallaxInstance {
+    pub color: glam::Vec4,
+    pub pos: glam::Vec3,
+    pub repeat_y: u32,
+    pub size: glam::Vec2,
+    /// Uv at the top left of the view, wrapped to `0..1` on repeating axes.
+    pub uv_origin: glam::Vec2,
+    /// Uv distance across the view.
+    pub uv_span: glam::Vec2,
+    pub pad: [u32; 2],
+}
+
+impl Vertex for ParallaxInstance {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+            2 => Float32x4, // Color
+            3 => Float32x3, // Pos
+            4 => Uint32,    // Repeat y
+            5 => Float32x2, // Size
+            6 => Float32x2, // Uv origin
+            7 => Float32x2, // Uv span
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &VERTEX_ATTRIBUTES,
+        }
+    }
+}
+
+/// Which axes a parallax layer's texture repeats along.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParallaxTiling {
+    /// A single horizontal strip, such as a skyline.
+    #[default]
+    RepeatX,
+    /// Fills the whole view, such as clouds or stars.
+    Repeat,
+}
+
+pub struct ParallaxData<'a> {
+    pub texture: &'a LoadedTexture,
+    pub tiling: ParallaxTiling,
+    /// Fraction of the camera's movement the layer follows. `1` moves with the world
+    /// and `0` stays fixed on screen, so distant layers use small factors.
+    pub scroll_factor: glam::Vec2,
+    /// World size of one repeat of the texture.
+    pub tile_size: glam::Vec2,
+    /// World y of the bottom of the strip, before scrolling.
+    pub offset_y: f32,
+    pub z: f32,
+    pub color: glam::Vec4,
+    /// Snap the scroll to whole texels and sample with nearest filtering, for pixel art.
+    pub pixel_snap: bool,
+}
+
+/// A layer with alpha below 1, recorded during prep.
+#[derive(Debug, Clone, Copy)]
+struct TransparentLayer {
+    depth: f32,
+    key: (TextureId, bool),
+    instance: ParallaxInstance,
+}
+
+//====================================================================
+
+/// Draws infinitely scrolling background layers, each as one quad covering the view
+/// with its texture offset by the camera position. Cost doesn't depend on how far the
+/// camera travels or how large the world is.
+///
+/// Opaque layers are cut out at half alpha and write depth, so they sort against
+/// sprites by `z`. Layers with alpha below 1 are blended after everything opaque.
+#[derive(Debug)]
+pub struct ParallaxRenderer {
+    pipeline: wgpu::RenderPipeline,
+    transparent_pipeline: wgpu::RenderPipeline,
+
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+
+    linear_sampler: wgpu::Sampler,
+    nearest_sampler: wgpu::Sampler,
+    /// Textures bound with a repeating sampler, keyed by whether they're pixel snapped.
+    bind_groups: HashMap<(TextureId, bool), (LoadedTexture, wgpu::BindGroup)>,
+
+    view_center: glam::Vec2,
+    view_size: glam::Vec2,
+    render_origin: glam::DVec2,
+
+    to_prep: Vec<((TextureId, bool), ParallaxInstance)>,
+    transparent_sort: TransparentSort,
+    transparent_to_prep: Vec<TransparentLayer>,
+
+    instances: Option<tools::InstanceBuffer<ParallaxInstance>>,
+    /// Layer keys in instance order, opaque then transparent.
+    draws: Vec<(TextureId, bool)>,
+    opaque_count: usize,
+}
+
+impl ParallaxRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> Self {
+        log::debug!("Creating Parallax Renderer");
+
+        let pipeline =
+            Self::create_pipeline(device, config, shared, wgpu::BlendState::REPLACE, "fs_main");
+        let transparent_pipeline = Self::create_pipeline(
+            device,
+            config,
+            shared,
+            wgpu::BlendState::ALPHA_BLENDING,
+            "fs_blend",
+        );
+
+        let vertex_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Vertex,
+            "Parallax",
+            &TEXTURE_RECT_VERTICES,
+        );
+
+        let index_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Index,
+            "Parallax",
+            &TEXTURE_RECT_INDICES,
+        );
+
+        let create_sampler = |label, filter| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some(label),
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                mag_filter: filter,
+                min_filter: filter,
+                ..Default::default()
+            })
+        };
+
+        Self {
+            pipeline,
+            transparent_pipeline,
+
+            vertex_buffer,
+            index_buffer,
+            index_count: TEXTURE_RECT_INDEX_COUNT,
+
+            linear_sampler: create_sampler("Parallax Linear Sampler", wgpu::FilterMode::Linear),
+            nearest_sampler: create_sampler("Parallax Nearest Sampler", wgpu::FilterMode::Nearest),
+            bind_groups: HashMap::default(),
+
+            view_center: glam::Vec2::ZERO,
+            view_size: glam::Vec2::ONE,
+            render_origin: glam::DVec2::ZERO,
+
+            to_prep: Vec::new(),
+            transparent_sort: TransparentSort::default(),
+            transparent_to_prep: Vec::new(),
+
+            instances: None,
+            draws: Vec::new(),
+            opaque_count: 0,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        blend: wgpu::BlendState,
+        fragment_entry: &str,
+    ) -> wgpu::RenderPipeline {
+        let depth_convention = shared.depth_convention();
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(blend),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let shader = include_str!("shaders/parallax.wgsl");
+        let layouts = [layouts::CAMERA, layouts::TEXTURE];
+        shared
+            .layouts()
+            .debug_validate_shader("Parallax Pipeline", shader, &layouts);
+
+        tools::create_pipeline(
+            device,
+            config,
+            "Parallax Pipeline",
+            &shared.layouts().layouts(&layouts),
+            &[TextureRectVertex::desc(), ParallaxInstance::desc()],
+            shader,
+            tools::RenderPipelineDescriptor {
+                depth_stencil: Some(depth_convention.depth_stencil_state(
+                    blend == wgpu::BlendState::REPLACE,
+                    depth_convention.compare_equal(),
+                )),
+                fragment_targets: Some(&fragment_targets),
+                multisample: shared.multisample(),
+                cache: shared.pipeline_cache(),
+                ..Default::default()
+            }
+            .with_entry_points("vs_main", fragment_entry),
+        )
+    }
+
+    /// The area layers cover this frame, in world units. Set before prepping layers.
+    #[inline]
+    pub fn set_view(&mut self, center: glam::Vec2, size: glam::Vec2) {
+        self.view_center = center;
+        self.view_size = size;
+    }
+
+    /// Subtracted from where layers are drawn without changing how they scroll, for
+    /// cameras rendering relative to an origin.
+    #[inline]
+    pub fn set_render_origin(&mut self, origin: glam::DVec2) {
+        self.render_origin = origin;
+    }
+
+    /// Orders this frame's transparent layers. Must be set before they are prepped.
+    #[inline]
+    pub fn set_transparent_sort(&mut self, sort: TransparentSort) {
+        self.transparent_sort = sort;
+    }
+
+    pub fn prep_layer(
+        &mut self,
+        device: &wgpu::Device,
+        shared: &SharedRenderResources,
+        data: ParallaxData,
+    ) {
+        if data.color.w <= 0. || data.tile_size.cmple(glam::Vec2::ZERO).any() {
+            return;
+        }
+
+        let key = (data.texture.id(), data.pixel_snap);
+
+        if !self.bind_groups.contains_key(&key) {
+            let sampler = match data.pixel_snap {
+                true => &self.nearest_sampler,
+                false => &self.linear_sampler,
+            };
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Parallax Bind Group"),
+                layout: shared.texture_bind_group_layout(),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&data.texture.texture().view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            });
+
+            self.bind_groups
+                .insert(key, (data.texture.clone(), bind_group));
+        }
+
+        let instance = ParallaxInstance {
+            color: data.color,
+            pos: (self.view_center.as_dvec2() - self.render_origin)
+                .as_vec2()
+                .extend(data.z),
+            repeat_y: (data.tiling == ParallaxTiling::Repeat) as u32,
+            size: self.view_size,
+            uv_origin: self.uv_origin(&data),
+            uv_span: self.view_size / data.tile_size,
+            pad: [0; 2],
+        };
+
+        match data.color.w < 1. {
+            true => self.transparent_to_prep.push(TransparentLayer {
+                depth: self.transparent_sort.depth(instance.pos),
+                key,
+                instance,
+            }),
+            false => self.to_prep.push((key, instance)),
+        }
+    }
+
+    /// Uv at the top left corner of the view. Worked out in f64 and wrapped, so the
+    /// offset stays precise however far the camera has travelled.
+    fn uv_origin(&self, data: &ParallaxData) -> glam::Vec2 {
+        let center = self.view_center.as_dvec2();
+        let half_size = self.view_size.as_dvec2() / 2.;
+        let tile = data.tile_size.as_dvec2();
+
+        // Layers lag behind the camera by the part of its movement they don't follow
+        let lag = center * (glam::DVec2::ONE - data.scroll_factor.as_dvec2());
+        let left = center.x - half_size.x - lag.x;
+        let top = center.y + half_size.y - lag.y;
+
+        // Texture v runs down from the top of the strip
+        let mut uv = glam::dvec2(
+            (left / tile.x).rem_euclid(1.),
+            (data.offset_y as f64 + tile.y - top) / tile.y,
+        );
+
+        if data.tiling == ParallaxTiling::Repeat {
+            uv.y = uv.y.rem_euclid(1.);
+        }
+
+        if data.pixel_snap {
+            let texture = &data.texture.texture().texture;
+            let texels = glam::dvec2(texture.width() as f64, texture.height() as f64);
+            uv = (uv * texels).round() / texels;
+        }
+
+        uv.as_vec2()
+    }
+
+    pub fn finish_prep(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.transparent_to_prep
+            .sort_by(|a, b| b.depth.total_cmp(&a.depth));
+
+        self.opaque_count = self.to_prep.len();
+        self.draws.clear();
+
+        let raw = self
+            .to_prep
+            .drain(..)
+            .chain(
+                self.transparent_to_prep
+                    .drain(..)
+                    .map(|layer| (layer.key, layer.instance)),
+            )
+            .map(|(key, instance)| {
+                self.draws.push(key);
+                instance
+            })
+            .collect::<Vec<_>>();
+
+        self.bind_groups.retain(|key, _| self.draws.contains(key));
+
+        if raw.is_empty() {
+            self.instances = None;
+            return;
+        }
+
+        match &mut self.instances {
+            Some(instances) => {
+                instances.update(device, queue, &raw);
+            }
+            None => self.instances = Some(tools::InstanceBuffer::new(device, &raw)),
+        }
+    }
+
+    fn draw(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        camera_bind_group: &wgpu::BindGroup,
+        pipeline: &wgpu::RenderPipeline,
+        first: usize,
+        last: usize,
+    ) {
+        let Some(instances) = &self.instances else {
+            return;
+        };
+
+        if first == last {
+            return;
+        }
+
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, camera_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        pass.set_vertex_buffer(1, instances.slice(..));
+
+        (first..last).for_each(|index| {
+            let (_, bind_group) = self.bind_groups.get(&self.draws[index]).unwrap();
+            let index = index as u32;
+
+            pass.set_bind_group(1, bind_group, &[]);
+            pass.draw_indexed(0..self.index_count, 0, index..index + 1);
+        });
+    }
+
+    pub fn render(&self, pass: &mut wgpu::RenderPass, camera_bind_group: &wgpu::BindGroup) {
+        self.draw(
+            pass,
+            camera_bind_group,
+            &self.pipeline,
+            0,
+            self.opaque_count,
+        );
+    }
+
+    /// Draw the layers prepped with alpha below 1, back to front. Should be rendered
+    /// after every opaque pipeline (see `RenderPipeline::render_transparent`).
+    pub fn render_transparent(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        self.draw(
+            pass,
+            camera_bind_group,
+            &self.transparent_pipeline,
+            self.opaque_count,
+            self.draws.len(),
+        );
+    }
+}
+
+//====================================================================
@@ -0,0 +1,295 @@
+//====================================================================
+
+use std::time::Duration;
+
+use roots_common::Size;
+use roots_renderer::{
+    layouts,
+    shared::SharedRenderResources,
+    target_pool::{PooledTarget, RenderTargetPool, TargetDesc, TargetSize},
+    tools::{self, RenderPipelineDescriptor},
+    RenderEncoder, RenderPassDesc,
+};
+
+use crate::manager::PipelineTargets;
+
+//====================================================================
+
+/// Scales are kept to multiples of this, so small changes don't recreate the targets.
+const SCALE_STEP: f32 = 0.05;
+
+/// Weight of each new frame in the rolling average frame time.
+const AVERAGE_WEIGHT: f32 = 0.1;
+
+/// Frames to wait after a change before changing again, giving the rolling average
+/// time to settle at the new scale.
+const DROP_COOLDOWN: u32 = 30;
+const RECOVER_COOLDOWN: u32 = 60;
+
+/// Only recover while the average frame time is below this fraction of the budget.
+const RECOVER_THRESHOLD: f32 = 0.8;
+
+/// Picks the internal resolution of the 3D scene from frame time feedback.
+///
+/// While a target frame time is set, the scale drops as soon as the rolling average goes
+/// over budget and recovers one step at a time while there is headroom.
+#[derive(Debug, Clone)]
+pub struct ResolutionScaler {
+    pub min_scale: f32,
+    pub max_scale: f32,
+
+    scale: f32,
+    budget: Option<Duration>,
+    /// Rolling average frame time in seconds.
+    average: Option<f32>,
+    cooldown: u32,
+}
+
+impl Default for ResolutionScaler {
+    fn default() -> Self {
+        Self {
+            min_scale: 0.5,
+            max_scale: 1.,
+            scale: 1.,
+            budget: None,
+            average: None,
+            cooldown: 0,
+        }
+    }
+}
+
+impl ResolutionScaler {
+    #[inline]
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Fix the scale, turning off dynamic scaling.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.budget = None;
+        self.scale = scale.clamp(self.min_scale, self.max_scale);
+    }
+
+    #[inline]
+    pub fn target_frame_time(&self) -> Option<Duration> {
+        self.budget
+    }
+
+    /// Scale automatically to keep frames within `budget`, or stop scaling with `None`.
+    /// The current scale is kept either way.
+    pub fn set_target_frame_time(&mut self, budget: Option<Duration>) {
+        self.budget = budget;
+        self.average = None;
+        self.cooldown = 0;
+    }
+
+    #[inline]
+    pub fn is_dynamic(&self) -> bool {
+        self.budget.is_some()
+    }
+
+    /// Feed in the time the last frame took. Returns true if the scale changed.
+    pub fn update(&mut self, frame_time: Duration) -> bool {
+        let Some(budget) = self.budget else {
+            return false;
+        };
+
+        let frame_time = frame_time.as_secs_f32();
+        let average = match self.average {
+            Some(average) => average + (frame_time - average) * AVERAGE_WEIGHT,
+            None => frame_time,
+        };
+        self.average = Some(average);
+
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return false;
+        }
+
+        let budget = budget.as_secs_f32();
+
+        let target = match average {
+            // Cost follows the pixel count, which is the square of the scale
+            _ if average > budget => {
+                self.cooldown = DROP_COOLDOWN;
+                (self.scale * (budget / average).sqrt()).min(self.scale - SCALE_STEP)
+            }
+            _ if average < budget * RECOVER_THRESHOLD => {
+                self.cooldown = RECOVER_COOLDOWN;
+                self.scale + SCALE_STEP
+            }
+            _ => return false,
+        };
+
+        let target =
+            ((target / SCALE_STEP).round() * SCALE_STEP).clamp(self.min_scale, self.max_scale);
+        let changed = target != self.scale;
+        self.scale = target;
+
+        changed
+    }
+
+    /// `size` at the current scale. Never smaller than 1x1.
+    #[inline]
+    pub fn scaled_size(&self, size: Size<u32>) -> Size<u32> {
+        Size::new(
+            ((size.width as f32 * self.scale).round() as u32).max(1),
+            ((size.height as f32 * self.scale).round() as u32).max(1),
+        )
+    }
+}
+
+//====================================================================
+
+/// Color and depth targets the 3D scene is rendered into below native resolution,
+/// then upscaled to the surface with bilinear filtering. The targets are borrowed
+/// from a `RenderTargetPool`, with a multisampled color target resolved into `color`
+/// when `SharedRenderResources::sample_count` is above 1.
+pub struct SceneTarget {
+    color: PooledTarget,
+    multisampled: Option<PooledTarget>,
+    depth: PooledTarget,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    size: Size<u32>,
+}
+
+impl SceneTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        target_pool: &mut RenderTargetPool,
+        size: Size<u32>,
+    ) -> Self {
+        log::debug!("Creating Scene Target");
+
+        let (color, multisampled, depth, bind_group) =
+            Self::acquire_targets(device, config, shared, target_pool, size);
+
+        let shader = include_str!("shaders/upscale.wgsl");
+        let layouts = [layouts::TEXTURE];
+        shared
+            .layouts()
+            .debug_validate_shader("Scene Upscale Pipeline", shader, &layouts);
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Scene Upscale Pipeline",
+            &shared.layouts().layouts(&layouts),
+            &[],
+            shader,
+            RenderPipelineDescriptor {
+                multisample: shared.multisample(),
+                cache: shared.pipeline_cache(),
+                ..Default::default()
+            },
+        );
+
+        Self {
+            color,
+            multisampled,
+            depth,
+            bind_group,
+            pipeline,
+            size,
+        }
+    }
+
+    fn acquire_targets(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        target_pool: &mut RenderTargetPool,
+        size: Size<u32>,
+    ) -> (
+        PooledTarget,
+        Option<PooledTarget>,
+        PooledTarget,
+        wgpu::BindGroup,
+    ) {
+        let size = TargetSize::fixed(size);
+        let sample_count = shared.sample_count();
+
+        let color = target_pool.acquire(
+            device,
+            TargetDesc::color(size, config.format),
+            "Scene Target Texture",
+        );
+        let multisampled = (sample_count > 1).then(|| {
+            target_pool.acquire(
+                device,
+                TargetDesc::color(size, config.format).with_sample_count(sample_count),
+                "Scene Target Texture: Multisampled",
+            )
+        });
+        let depth = target_pool.acquire(
+            device,
+            TargetDesc::depth(size, shared.depth_convention()).with_sample_count(sample_count),
+            "Depth Texture: Scene Target",
+        );
+
+        let bind_group =
+            shared.create_texture_bind_group(device, &color, Some("Scene Target Bind Group"));
+
+        (color, multisampled, depth, bind_group)
+    }
+
+    /// Recreate the targets at `size`. The depth target always matches the color target,
+    /// so nothing samples mismatched sizes after a change.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        target_pool: &mut RenderTargetPool,
+        size: Size<u32>,
+    ) {
+        let (color, multisampled, depth, bind_group) =
+            Self::acquire_targets(device, config, shared, target_pool, size);
+
+        self.color = color;
+        self.multisampled = multisampled;
+        self.depth = depth;
+        self.bind_group = bind_group;
+        self.size = size;
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+
+    #[inline]
+    pub fn color_view(&self) -> &wgpu::TextureView {
+        &self.color.view
+    }
+
+    /// The view the scene is drawn into when multisampling, resolved into `color_view`.
+    #[inline]
+    pub fn multisampled_view(&self) -> Option<&wgpu::TextureView> {
+        self.multisampled.as_ref().map(|target| &target.view)
+    }
+
+    #[inline]
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.depth.view
+    }
+
+    /// Stretch the scene over the whole of `targets`. When multisampling, the scene is
+    /// also drawn into the multisampled view so later passes load it from there.
+    pub fn upscale(&self, encoder: &mut RenderEncoder, targets: &PipelineTargets) {
+        let mut render_pass = encoder.begin_render_pass(RenderPassDesc {
+            color_target: targets.color,
+            multisampled: targets.multisampled,
+            ..RenderPassDesc::none()
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,80 @@
+//====================================================================
+// Uniforms
+
+struct Camera {
+    projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+
+//====================================================================
+
+struct VertexIn {
+    @builtin(vertex_index) index: u32,
+
+    // Instance
+    @location(0) transform_0: vec4<f32>,
+    @location(1) transform_1: vec4<f32>,
+    @location(2) transform_2: vec4<f32>,
+    @location(3) transform_3: vec4<f32>,
+    @location(4) color: vec4<f32>,
+    @location(5) softness: f32,
+}
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) softness: f32,
+}
+
+//====================================================================
+
+@vertex
+fn vs_main(in: VertexIn) -> VertexOut {
+    var out: VertexOut;
+
+    // 0 = Top Left, 1 = Bottom Left, 2 = Top Right, 3 = Bottom Right
+    let corner = vec2<f32>(f32(in.index / 2u), f32(in.index % 2u));
+
+    // A unit circle on the xy plane, scaled to the radius by the transform
+    out.local = corner * 2. - 1.;
+
+    let transform = mat4x4<f32>(
+        in.transform_0,
+        in.transform_1,
+        in.transform_2,
+        in.transform_3,
+    );
+
+    out.clip_position =
+        camera.projection
+        * transform
+        * vec4<f32>(out.local, 0., 1.);
+
+    out.color = in.color;
+    out.softness = in.softness;
+
+    return out;
+}
+
+//====================================================================
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    // Signed distance to the edge of the circle, as a fraction of the radius
+    let distance = length(in.local) - 1.;
+
+    let softness = clamp(in.softness, 0.001, 1.);
+    let width = max(fwidth(distance), 0.0001);
+
+    // Falls off smoothly over the soft outer band, anti-aliased at the edge
+    let falloff = 1. - smoothstep(-softness, 0., distance);
+    let edge = clamp(0.5 - distance / width, 0., 1.);
+
+    return vec4<f32>(in.color.rgb, in.color.a * falloff * edge);
+}
+
+//====================================================================
@@ -0,0 +1,147 @@
+//====================================================================
+// Uniforms
+
+struct Camera {
+    projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+struct Emitter {
+    position: vec3<f32>,
+    lifetime: f32,
+    velocity: vec3<f32>,
+    delta: f32,
+    velocity_variance: vec3<f32>,
+    spawn_start: u32,
+    acceleration: vec3<f32>,
+    spawn_count: u32,
+    start_color: vec4<f32>,
+    end_color: vec4<f32>,
+    start_size: vec2<f32>,
+    end_size: vec2<f32>,
+    capacity: u32,
+    seed: u32,
+    reset: u32,
+}
+
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    // 1 while alive, 0 once dead or never spawned
+    alive: f32,
+}
+
+//====================================================================
+// Simulation
+
+@group(0) @binding(0) var<uniform> sim_emitter: Emitter;
+@group(0) @binding(1) var<storage, read_write> sim_particles: array<Particle>;
+
+fn hash(value: u32) -> u32 {
+    // PCG hash
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random_signed(seed: u32) -> f32 {
+    return f32(hash(seed)) / 4294967295. * 2. - 1.;
+}
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= sim_emitter.capacity {
+        return;
+    }
+
+    var particle = sim_particles[index];
+
+    if sim_emitter.reset != 0u {
+        particle.alive = 0.;
+    }
+
+    // Particles are spawned into a ring, replacing the oldest
+    let offset = (index + sim_emitter.capacity - sim_emitter.spawn_start) % sim_emitter.capacity;
+
+    if offset < sim_emitter.spawn_count {
+        let seed = hash(index ^ hash(sim_emitter.seed)) * 3u;
+        let variance = vec3<f32>(
+            random_signed(seed),
+            random_signed(seed + 1u),
+            random_signed(seed + 2u),
+        ) * sim_emitter.velocity_variance;
+
+        particle.position = sim_emitter.position;
+        particle.velocity = sim_emitter.velocity + variance;
+        particle.age = 0.;
+        particle.alive = 1.;
+    } else if particle.alive > 0. {
+        particle.age += sim_emitter.delta;
+
+        if particle.age >= sim_emitter.lifetime {
+            particle.alive = 0.;
+        } else {
+            particle.velocity += sim_emitter.acceleration * sim_emitter.delta;
+            particle.position += particle.velocity * sim_emitter.delta;
+        }
+    }
+
+    sim_particles[index] = particle;
+}
+
+//====================================================================
+// Rendering
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+@group(1) @binding(0) var texture: texture_2d<f32>;
+@group(1) @binding(1) var texture_sampler: sampler;
+
+@group(2) @binding(0) var<uniform> emitter: Emitter;
+@group(2) @binding(1) var<storage, read> particles: array<Particle>;
+
+struct VertexIn {
+    @location(0) vertex_position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+}
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexIn, @builtin(instance_index) instance: u32) -> VertexOut {
+    var out: VertexOut;
+
+    let particle = particles[instance];
+    let t = clamp(particle.age / emitter.lifetime, 0., 1.);
+
+    // Dead particles collapse to nothing
+    let size = mix(emitter.start_size, emitter.end_size, t) * particle.alive;
+
+    let vertex_pos =
+        vec3<f32>(in.vertex_position * size, 0.)
+        + particle.position;
+
+    out.clip_position =
+        camera.projection
+        * vec4<f32>(vertex_pos, 1.);
+
+    out.uv = in.uv;
+    out.color = mix(emitter.start_color, emitter.end_color, t);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let tex_color = textureSample(texture, texture_sampler, in.uv);
+
+    return tex_color * in.color;
+}
+
+//====================================================================
@@ -4,24 +4,13 @@
 struct Camera {
     projection: mat4x4<f32>,
     position: vec3<f32>,
-}
-
-struct GlobalLightData {
-    ambient_color: vec3<f32>,
-    ambient_strength: f32,
-}
-
-struct Light {
-    position: vec4<f32>,
-    direction: vec4<f32>,
-    diffuse_color: vec4<f32>,
-    specular_color: vec4<f32>,
+    time: f32,
 }
 
 @group(0) @binding(0) var<uniform> camera: Camera;
 
-@group(1) @binding(0) var<uniform> global_lighting: GlobalLightData;
-@group(1) @binding(1) var<storage, read> light_array: array<Light>;
+// Group 1 - `global_lighting`, `light_count` and `get_light` are declared by the
+// `LightingPath` the renderer was created with, prepended to this shader.
 
 @group(2) @binding(0) var texture: texture_2d<f32>;
 @group(2) @binding(1) var texture_sampler: sampler;
@@ -34,18 +23,24 @@ struct VertexIn {
     @location(0) vertex_position: vec3<f32>,
     @location(1) uv: vec2<f32>,
     @location(2) normal: vec3<f32>,
+    @location(3) tangent: vec4<f32>,
 
     // Instance
-    @location(3) transform_1: vec4<f32>,
-    @location(4) transform_2: vec4<f32>,
-    @location(5) transform_3: vec4<f32>,
-    @location(6) transform_4: vec4<f32>,
+    @location(4) transform_1: vec4<f32>,
+    @location(5) transform_2: vec4<f32>,
+    @location(6) transform_3: vec4<f32>,
+    @location(7) transform_4: vec4<f32>,
+
+    @location(8) color: vec4<f32>,
 
-    @location(7) color: vec4<f32>,
+    @location(9) normal_0: vec3<f32>,
+    @location(10) normal_1: vec3<f32>,
+    @location(11) normal_2: vec3<f32>,
 
-    @location(8) normal_0: vec3<f32>,
-    @location(9) normal_1: vec3<f32>,
-    @location(10) normal_2: vec3<f32>,
+    // Sway direction * amplitude, frequency
+    @location(13) wind: vec4<f32>,
+    // Height mask base, 1 / mask span, mask enabled, wave number
+    @location(14) wind_shape: vec4<f32>,
 }
 
 struct VertexOut {
@@ -60,6 +55,10 @@ struct VertexOut {
 
 @vertex
 fn vs_main(in: VertexIn) -> VertexOut {
+    return vertex(in);
+}
+
+fn vertex(in: VertexIn) -> VertexOut {
     var out: VertexOut;
     
     let transform = mat4x4<f32>(
@@ -86,33 +85,88 @@ fn vs_main(in: VertexIn) -> VertexOut {
     return out;
 }
 
+//====================================================================
+// Wind variant
+
+const TAU: f32 = 6.28318530718;
+
+@vertex
+fn vs_wind(in: VertexIn) -> VertexOut {
+    var out = vertex(in);
+
+    let amplitude = length(in.wind.xyz);
+    let direction = in.wind.xyz / max(amplitude, 0.0001);
+
+    // Gusts travel along the wind, so neighbouring models sway out of step
+    let phase = camera.time * in.wind.w * TAU - dot(in.transform_4.xyz, direction) * in.wind_shape.w;
+    let sway = in.wind.xyz * (sin(phase) + 0.3 * sin(phase * 2.3 + 1.));
+
+    let masked = in.wind_shape.z > 0.;
+    let weight = select(1., clamp((in.vertex_position.y - in.wind_shape.x) * in.wind_shape.y, 0., 1.), masked);
+    let position = out.position + sway * weight;
+
+    out.clip_position = camera.projection * vec4<f32>(position, 1.);
+    out.position = position;
+
+    // Lean normals the way the model bends. Only the height mask bends it, by roughly
+    // the sway per unit of world height.
+    let up = in.transform_2.xyz;
+    let up_length = max(length(up), 0.0001);
+    let bend = sway * select(0., in.wind_shape.y / up_length, masked);
+    let normal = normalize(out.normal);
+    out.normal = normal - (up / up_length) * dot(normal, bend);
+
+    return out;
+}
+
 //====================================================================
 
 const DEFAULT_MATERIAL_SHININESS: f32 = 32.;
 
+// Inverse square falloff windowed to reach 0 at `radius`, or none if `radius` is 0
+fn range_attenuation(distance: f32, radius: f32) -> f32 {
+    if radius <= 0. {
+        return 1.;
+    }
+
+    let window = clamp(1. - pow(distance / radius, 4.), 0., 1.);
+    return window * window / (1. + distance * distance);
+}
+
 @fragment
 fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
 
     let ambient = vec3<f32>(global_lighting.ambient_strength * global_lighting.ambient_color);
 
-    let light_count = bitcast<i32>(arrayLength(&light_array));
+    let count = light_count();
 
     var sum_diffuse = vec3<f32>();
     var sum_specular = vec3<f32>();
 
-    for (var i = 0; i < light_count; i += 1) {
+    for (var i = 0; i < count; i += 1) {
+        let light = get_light(i);
+
+        // Directional lights shine from infinitely far away, point lights fade with distance
+        var light_dir = -light.direction;
+        var attenuation = 1.;
+
+        if light.light_type == LIGHT_POINT {
+            let to_light = light.position.xyz - in.position;
+            light_dir = normalize(to_light);
+            attenuation = range_attenuation(length(to_light), light.position.w);
+        }
+
         // Calculate Diffuse Color
         let norm = normalize(in.normal);
-        let light_dir = normalize(light_array[i].position.xyz - in.position);
 
         let diffuse_strength = max(dot(norm, light_dir), 0.0);
-        sum_diffuse += light_array[i].diffuse_color.xyz * diffuse_strength;
+        sum_diffuse += light.diffuse_color.xyz * diffuse_strength * attenuation;
 
         // Specular
         let view_dir = normalize(camera.position - in.position);
         let half_dir = normalize(view_dir + light_dir);
         let specular_strength = pow(max(dot(norm, half_dir), 0.0), DEFAULT_MATERIAL_SHININESS);
-        sum_specular += light_array[i].specular_color.xyz * specular_strength;
+        sum_specular += light.specular_color.xyz * specular_strength * attenuation;
     }
 
     let result = (
@@ -125,5 +179,35 @@ fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
 }
 
 //====================================================================
+// Draw order debug variant
+
+struct DrawOrder {
+    batch: u32,
+    batch_count: u32,
+    first_instance: u32,
+    total_instances: u32,
+}
+
+@group(3) @binding(0) var<uniform> draw_order: DrawOrder;
 
+@vertex
+fn vs_draw_order(in: VertexIn, @builtin(instance_index) instance: u32) -> VertexOut {
+    var out = vertex(in);
+
+    // Blue for the first instance drawn through to red for the last
+    let order = f32(draw_order.first_instance + instance) / f32(max(draw_order.total_instances, 2u) - 1u);
+    let shade = select(1., 0.6, draw_order.batch % 2u == 1u);
+    out.color = vec4<f32>(mix(vec3<f32>(0.1, 0.3, 1.), vec3<f32>(1., 0.2, 0.1), order) * shade, 1.);
+
+    return out;
+}
 
+@fragment
+fn fs_draw_order(in: VertexOut) -> @location(0) vec4<f32> {
+    // Fixed directional shading so shapes stay readable
+    let shading = 0.6 + 0.4 * max(dot(normalize(in.normal), normalize(vec3<f32>(0.3, 1., 0.5))), 0.);
+
+    return vec4<f32>(in.color.rgb * shading, 1.);
+}
+
+//====================================================================
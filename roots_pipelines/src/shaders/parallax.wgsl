@@ -0,0 +1,86 @@
+//====================================================================
+// Uniforms
+
+struct Camera {
+    projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+@group(1) @binding(0) var texture: texture_2d<f32>;
+@group(1) @binding(1) var texture_sampler: sampler;
+
+
+//====================================================================
+
+struct VertexIn {
+    // Vertex
+    @location(0) vertex_position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+
+    // Instance
+    @location(2) color: vec4<f32>,
+    @location(3) position: vec3<f32>,
+    @location(4) repeat_y: u32,
+    @location(5) size: vec2<f32>,
+    @location(6) uv_origin: vec2<f32>,
+    @location(7) uv_span: vec2<f32>,
+}
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) repeat_y: u32,
+}
+
+//====================================================================
+
+@vertex
+fn vs_main(in: VertexIn) -> VertexOut {
+    var out: VertexOut;
+
+    let vertex_pos =
+        vec3<f32>(in.vertex_position * in.size, 0.)
+        + in.position;
+
+    out.clip_position =
+        camera.projection
+        * vec4<f32>(vertex_pos, 1.);
+
+    // The sampler repeats, so the uv only needs to be continuous across the quad
+    out.uv = in.uv_origin + in.uv * in.uv_span;
+    out.color = in.color;
+    out.repeat_y = in.repeat_y;
+
+    return out;
+}
+
+fn layer_color(in: VertexOut) -> vec4<f32> {
+    let color = textureSample(texture, texture_sampler, in.uv) * in.color;
+
+    // Layers only repeating horizontally are a single strip
+    let outside = in.repeat_y == 0u && (in.uv.y < 0. || in.uv.y > 1.);
+
+    return select(color, vec4<f32>(0.), outside);
+}
+
+// Cut out, so opaque layers write depth and sort against sprites
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let color = layer_color(in);
+
+    if color.a < 0.5 {
+        discard;
+    }
+
+    return color;
+}
+
+@fragment
+fn fs_blend(in: VertexOut) -> @location(0) vec4<f32> {
+    return layer_color(in);
+}
+
+//====================================================================
@@ -35,6 +35,10 @@ struct VertexOut {
 
 @vertex
 fn vs_main(in: VertexIn) -> VertexOut {
+    return vertex(in);
+}
+
+fn vertex(in: VertexIn) -> VertexOut {
     var out: VertexOut;
     
     let vertex_pos = 
@@ -59,3 +63,34 @@ fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
 }
 
 //====================================================================
+// Draw order debug variant
+
+struct DrawOrder {
+    batch: u32,
+    batch_count: u32,
+    first_instance: u32,
+    total_instances: u32,
+}
+
+@group(2) @binding(0) var<uniform> draw_order: DrawOrder;
+
+@vertex
+fn vs_draw_order(in: VertexIn, @builtin(instance_index) instance: u32) -> VertexOut {
+    var out = vertex(in);
+
+    // Blue for the first instance drawn through to red for the last
+    let order = f32(draw_order.first_instance + instance) / f32(max(draw_order.total_instances, 2u) - 1u);
+    let shade = select(1., 0.6, draw_order.batch % 2u == 1u);
+    out.color = vec4<f32>(mix(vec3<f32>(0.1, 0.3, 1.), vec3<f32>(1., 0.2, 0.1), order) * shade, 1.);
+
+    return out;
+}
+
+@fragment
+fn fs_draw_order(in: VertexOut) -> @location(0) vec4<f32> {
+    let alpha = textureSample(texture, texture_sampler, in.uv).a;
+
+    return vec4<f32>(in.color.rgb, alpha);
+}
+
+//====================================================================
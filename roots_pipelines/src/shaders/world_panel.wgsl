@@ -0,0 +1,118 @@
+//====================================================================
+// Uniforms
+
+struct Camera {
+    projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+@group(1) @binding(0) var texture: texture_2d<f32>;
+@group(1) @binding(1) var texture_sampler: sampler;
+
+
+//====================================================================
+
+struct VertexIn {
+    @builtin(vertex_index) index: u32,
+
+    // Instance
+    @location(0) transform_0: vec4<f32>,
+    @location(1) transform_1: vec4<f32>,
+    @location(2) transform_2: vec4<f32>,
+    @location(3) transform_3: vec4<f32>,
+    @location(4) color: vec4<f32>,
+    @location(5) highlight_color: vec4<f32>,
+    @location(6) size: vec2<f32>,
+    @location(7) anchor: vec2<f32>,
+    @location(8) highlight_range: vec2<f32>,
+    @location(9) corner_radius: f32,
+}
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) highlight_color: vec4<f32>,
+    @location(3) size: vec2<f32>,
+    @location(4) highlight_range: vec2<f32>,
+    @location(5) corner_radius: f32,
+}
+
+//====================================================================
+
+@vertex
+fn vs_main(in: VertexIn) -> VertexOut {
+    var out: VertexOut;
+
+    // 0 = Top Left, 1 = Bottom Left, 2 = Top Right, 3 = Bottom Right
+    let corner = vec2<f32>(f32(in.index / 2u), f32(in.index % 2u));
+    out.uv = corner;
+
+    // Local space is y up, with the anchor at the origin
+    let vertex_pos = vec2<f32>(
+        (corner.x - in.anchor.x) * in.size.x,
+        (in.anchor.y - corner.y) * in.size.y,
+    );
+
+    let transform = mat4x4<f32>(
+        in.transform_0,
+        in.transform_1,
+        in.transform_2,
+        in.transform_3,
+    );
+
+    out.clip_position =
+        camera.projection
+        * transform
+        * vec4<f32>(vertex_pos, 0., 1.);
+
+    out.color = in.color;
+    out.highlight_color = in.highlight_color;
+    out.size = in.size;
+    out.highlight_range = in.highlight_range;
+    out.corner_radius = in.corner_radius;
+
+    return out;
+}
+
+//====================================================================
+
+/// Coverage of the rounded rectangle, anti-aliased over one pixel.
+fn panel_coverage(in: VertexOut) -> f32 {
+    let half_size = in.size / 2.;
+    let radius = clamp(in.corner_radius, 0., min(half_size.x, half_size.y));
+
+    let local = (in.uv - 0.5) * in.size;
+    let q = abs(local) - half_size + radius;
+    let distance = length(max(q, vec2<f32>(0.))) + min(max(q.x, q.y), 0.) - radius;
+
+    let width = max(fwidth(distance), 0.0001);
+    return clamp(0.5 - distance / width, 0., 1.);
+}
+
+fn panel_color(in: VertexOut, base: vec4<f32>, coverage: f32) -> vec4<f32> {
+    var color = base;
+
+    if in.uv.y > in.highlight_range.x && in.uv.y < in.highlight_range.y {
+        color = in.highlight_color;
+    }
+
+    return vec4<f32>(color.rgb, color.a * coverage);
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    return panel_color(in, in.color, panel_coverage(in));
+}
+
+@fragment
+fn fs_textured(in: VertexOut) -> @location(0) vec4<f32> {
+    let coverage = panel_coverage(in);
+    let tex_color = textureSample(texture, texture_sampler, in.uv);
+
+    return panel_color(in, tex_color * in.color, coverage);
+}
+
+//====================================================================
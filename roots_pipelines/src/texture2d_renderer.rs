@@ -1,8 +1,12 @@
 //====================================================================
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
 
 use roots_renderer::{
+    layouts,
     shared::{SharedRenderResources, Vertex},
     texture::{
         LoadedTexture, TextureId, TextureRectVertex, TEXTURE_RECT_INDEX_COUNT,
@@ -11,6 +15,11 @@ use roots_renderer::{
     tools::{self},
 };
 
+use crate::{
+    draw_order::{DrawOrderBatch, DrawOrderDebug},
+    transparency::TransparentSort,
+};
+
 //====================================================================
 
 #[repr(C)]
@@ -45,6 +54,44 @@ pub struct TextureData<'a> {
     pub color: glam::Vec4,
 }
 
+/// How the sprites in a layer are ordered relative to each other. Layers are
+/// separated by depth, so a ground layer placed behind a y sorted layer is always
+/// drawn under it.
+///
+/// Only sprites take part in y sorting - props that characters walk behind should be
+/// sprites in the same layer as the characters rather than part of the ground.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SortMode {
+    /// Batched by texture and ordered by depth alone.
+    #[default]
+    Depth,
+    /// Drawn back to front by the y of each sprite's sort origin, so sprites lower on
+    /// the screen are drawn over those above them. `origin_offset` moves the origin from
+    /// the centre of the sprite as a fraction of its height - `-0.5` sorts by the bottom
+    /// edge, where a character's feet usually are.
+    YSort { origin_offset: f32 },
+}
+
+/// A sprite in a `SortMode::YSort` layer, recorded during prep.
+#[derive(Debug, Clone, Copy)]
+struct SortedInstance {
+    sort_y: f32,
+    tie_break: u64,
+    texture: TextureId,
+    instance: TextureInstance,
+}
+
+/// A sprite with alpha below 1, recorded during prep.
+#[derive(Debug, Clone, Copy)]
+struct TransparentInstance {
+    depth: f32,
+    /// Only set for sprites in a `SortMode::YSort` layer, otherwise zero.
+    sort_y: f32,
+    tie_break: u64,
+    texture: TextureId,
+    instance: TextureInstance,
+}
+
 //====================================================================
 
 #[derive(Debug)]
@@ -58,27 +105,92 @@ pub struct Texture2dRenderer {
     to_prep: HashMap<TextureId, Vec<TextureInstance>>,
     instances: HashMap<TextureId, tools::InstanceBuffer<TextureInstance>>,
     texture_storage: HashMap<TextureId, LoadedTexture>,
+
+    layers: HashMap<u32, SortMode>,
+    /// Drawn in order after the batched instances, with depth compared inclusively
+    /// so later sprites at the same depth draw over earlier ones.
+    sorted_pipeline: wgpu::RenderPipeline,
+    sorted_to_prep: Vec<SortedInstance>,
+    sorted_instances: Option<tools::InstanceBuffer<TextureInstance>>,
+    /// Consecutive sorted instances sharing a texture, drawn with one call each.
+    sorted_runs: Vec<(TextureId, Range<u32>)>,
+
+    /// Sprites with alpha below 1, drawn back to front after everything opaque. Only
+    /// used while the renderer replaces rather than blends.
+    transparent_pipeline: wgpu::RenderPipeline,
+    transparent_sort: TransparentSort,
+    transparent_to_prep: Vec<TransparentInstance>,
+    transparent_instances: Option<tools::InstanceBuffer<TextureInstance>>,
+    transparent_runs: Vec<(TextureId, Range<u32>)>,
+
+    use_depth: bool,
+    blend: wgpu::BlendState,
+    draw_order: Option<DrawOrderDebug>,
+    /// Kept while draw order debugging is off, so enabling it again doesn't stall.
+    draw_order_cache: Option<DrawOrderDebug>,
 }
 
 impl Texture2dRenderer {
+    #[inline]
     pub fn new(
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
         shared: &SharedRenderResources,
+    ) -> Self {
+        Self::new_with_depth(device, config, shared, true)
+    }
+
+    /// Create a renderer that optionally skips depth testing entirely. Without depth
+    /// the renderer must be used in a render pass without a depth attachment and
+    /// sprites are drawn in submission order.
+    pub fn new_with_depth(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        use_depth: bool,
+    ) -> Self {
+        Self::new_with_blend(device, config, shared, use_depth, wgpu::BlendState::REPLACE)
+    }
+
+    /// Create a renderer with a custom blend state. Blended sprites (anything other
+    /// than `BlendState::REPLACE`) are depth tested but don't write depth.
+    pub fn new_with_blend(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        use_depth: bool,
+        blend: wgpu::BlendState,
     ) -> Self {
         log::debug!("Creating Texture2d Renderer");
 
-        let pipeline = tools::create_pipeline(
+        let depth_convention = shared.depth_convention();
+        let pipeline = Self::create_pipeline(
             device,
             config,
-            "Texture Pipeline",
-            &[
-                shared.camera_bind_group_layout(),
-                shared.texture_bind_group_layout(),
-            ],
-            &[TextureRectVertex::desc(), TextureInstance::desc()],
-            include_str!("shaders/texture2d.wgsl"),
-            tools::RenderPipelineDescriptor::default().with_depth_stencil(),
+            shared,
+            use_depth,
+            blend,
+            depth_convention.compare(),
+            None,
+        );
+        let sorted_pipeline = Self::create_pipeline(
+            device,
+            config,
+            shared,
+            use_depth,
+            blend,
+            depth_convention.compare_equal(),
+            None,
+        );
+        // Inclusive so sprites at the depth of an opaque sprite in the same layer still show
+        let transparent_pipeline = Self::create_pipeline(
+            device,
+            config,
+            shared,
+            use_depth,
+            wgpu::BlendState::ALPHA_BLENDING,
+            depth_convention.compare_equal(),
+            None,
         );
 
         let vertex_buffer = tools::create_buffer(
@@ -108,11 +220,256 @@ impl Texture2dRenderer {
             to_prep: HashMap::default(),
             instances,
             texture_storage,
+
+            layers: HashMap::default(),
+            sorted_pipeline,
+            sorted_to_prep: Vec::new(),
+            sorted_instances: None,
+            sorted_runs: Vec::new(),
+
+            transparent_pipeline,
+            transparent_sort: TransparentSort::default(),
+            transparent_to_prep: Vec::new(),
+            transparent_instances: None,
+            transparent_runs: Vec::new(),
+
+            use_depth,
+            blend,
+            draw_order: None,
+            draw_order_cache: None,
+        }
+    }
+
+    /// Creates the draw order debug variant when `draw_order` is provided.
+    fn create_pipeline(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        use_depth: bool,
+        blend: wgpu::BlendState,
+        compare: wgpu::CompareFunction,
+        draw_order: Option<&wgpu::BindGroupLayout>,
+    ) -> wgpu::RenderPipeline {
+        let depth_convention = shared.depth_convention();
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(blend),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let mut descriptor = tools::RenderPipelineDescriptor {
+            depth_stencil: match use_depth {
+                true => Some(
+                    depth_convention
+                        .depth_stencil_state(blend == wgpu::BlendState::REPLACE, compare),
+                ),
+                false => None,
+            },
+            fragment_targets: Some(&fragment_targets),
+            multisample: shared.multisample(),
+            cache: shared.pipeline_cache(),
+            ..Default::default()
+        };
+
+        let shader = include_str!("shaders/texture2d.wgsl");
+        let layouts = [layouts::CAMERA, layouts::TEXTURE];
+        shared
+            .layouts()
+            .debug_validate_shader("Texture Pipeline", shader, &layouts);
+
+        let mut bind_group_layouts = shared.layouts().layouts(&layouts);
+
+        if let Some(layout) = draw_order {
+            bind_group_layouts.push(layout);
+            descriptor = descriptor.with_entry_points("vs_draw_order", "fs_draw_order");
+        }
+
+        tools::create_pipeline(
+            device,
+            config,
+            match draw_order {
+                Some(_) => "Texture Draw Order Pipeline",
+                None => "Texture Pipeline",
+            },
+            &bind_group_layouts,
+            &[TextureRectVertex::desc(), TextureInstance::desc()],
+            shader,
+            descriptor,
+        )
+    }
+
+    #[inline]
+    pub fn use_depth(&self) -> bool {
+        self.use_depth
+    }
+
+    /// Swap to a debug pipeline that tints sprites by draw order. See `DrawOrderDebug`.
+    pub fn set_draw_order_debug(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        enabled: bool,
+    ) {
+        match (enabled, self.draw_order.is_some()) {
+            (true, false) => {
+                self.draw_order = Some(
+                    self.draw_order_cache
+                        .take()
+                        .unwrap_or_else(|| self.create_draw_order_debug(device, config, shared)),
+                )
+            }
+            (false, true) => self.draw_order_cache = self.draw_order.take(),
+            _ => {}
+        }
+    }
+
+    /// Create the draw order debug pipeline ahead of time, so enabling it doesn't
+    /// compile shaders mid frame.
+    pub fn prewarm_draw_order_debug(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) {
+        if self.draw_order.is_none() && self.draw_order_cache.is_none() {
+            self.draw_order_cache = Some(self.create_draw_order_debug(device, config, shared));
+        }
+    }
+
+    fn create_draw_order_debug(
+        &self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> DrawOrderDebug {
+        let layout = DrawOrderDebug::create_bind_group_layout(device);
+        let pipeline = Self::create_pipeline(
+            device,
+            config,
+            shared,
+            self.use_depth,
+            self.blend,
+            shared.depth_convention().compare(),
+            Some(&layout),
+        );
+
+        DrawOrderDebug::new(device, layout, pipeline)
+    }
+
+    /// Batches drawn last frame, in submission order. Empty unless draw order debugging is enabled.
+    #[inline]
+    pub fn draw_order_batches(&self) -> &[DrawOrderBatch] {
+        match &self.draw_order {
+            Some(draw_order) => draw_order.batches(),
+            None => &[],
+        }
+    }
+
+    /// Set how sprites prepped with `prep_texture_in_layer` for `layer` are ordered.
+    #[inline]
+    pub fn set_layer_sort(&mut self, layer: u32, sort: SortMode) {
+        self.layers.insert(layer, sort);
+    }
+
+    #[inline]
+    pub fn layer_sort(&self, layer: u32) -> SortMode {
+        self.layers.get(&layer).copied().unwrap_or_default()
+    }
+
+    /// Prep a sprite in a layer. `sort_offset` is added to the sort origin's y in world
+    /// units, and `tie_break` (such as an entity id) keeps the order of sprites sharing a
+    /// y stable between frames.
+    pub fn prep_texture_in_layer(
+        &mut self,
+        data: TextureData,
+        layer: u32,
+        sort_offset: f32,
+        tie_break: u64,
+    ) {
+        if data.color.w <= 0. {
+            return;
+        }
+
+        let origin_offset = match self.layer_sort(layer) {
+            SortMode::Depth => return self.prep_texture(data),
+            SortMode::YSort { origin_offset } => origin_offset,
+        };
+
+        let sort_y = data.pos.y + data.size.y * origin_offset + sort_offset;
+
+        if self.is_transparent(&data) {
+            return self.prep_transparent(data, sort_y, tie_break);
         }
+
+        self.texture_storage
+            .entry(data.texture.id())
+            .or_insert_with(|| data.texture.clone());
+
+        self.sorted_to_prep.push(SortedInstance {
+            sort_y,
+            tie_break,
+            texture: data.texture.id(),
+            instance: TextureInstance {
+                color: data.color,
+                size: data.size,
+                pos: data.pos,
+                pad: [0; 3],
+            },
+        });
     }
 
+    /// Whether `data` should be drawn blended with the transparent sprites. Fully
+    /// transparent sprites are skipped before this is checked.
+    #[inline]
+    fn is_transparent(&self, data: &TextureData) -> bool {
+        data.color.w < 1. && self.blend == wgpu::BlendState::REPLACE
+    }
+
+    fn prep_transparent(&mut self, data: TextureData, sort_y: f32, tie_break: u64) {
+        self.texture_storage
+            .entry(data.texture.id())
+            .or_insert_with(|| data.texture.clone());
+
+        self.transparent_to_prep.push(TransparentInstance {
+            depth: self.transparent_sort.depth(data.pos),
+            sort_y,
+            tie_break,
+            texture: data.texture.id(),
+            instance: TextureInstance {
+                color: data.color,
+                size: data.size,
+                pos: data.pos,
+                pad: [0; 3],
+            },
+        });
+    }
+
+    /// Orders this frame's transparent sprites. Must be set before they are prepped.
+    #[inline]
+    pub fn set_transparent_sort(&mut self, sort: TransparentSort) {
+        self.transparent_sort = sort;
+    }
+
+    /// Whether any sprites were prepped with alpha below 1 in a replacing renderer.
+    #[inline]
+    pub fn has_transparent(&self) -> bool {
+        !self.transparent_runs.is_empty()
+    }
+
+    /// Sprites with alpha below 1 are blended and drawn back to front after everything
+    /// opaque, unless the renderer was created with its own blend state. Fully
+    /// transparent sprites are skipped.
     #[inline]
     pub fn prep_texture(&mut self, data: TextureData) {
+        if data.color.w <= 0. {
+            return;
+        }
+
+        if self.is_transparent(&data) {
+            return self.prep_transparent(data, 0., 0);
+        }
+
         self.to_prep
             .entry(data.texture.id())
             .or_insert_with(|| {
@@ -133,37 +490,223 @@ impl Texture2dRenderer {
 
     pub fn finish_prep(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
         let mut previous = self.instances.keys().map(|id| *id).collect::<HashSet<_>>();
+        let mut first_positions = HashMap::new();
 
         self.to_prep.drain().for_each(|(id, raw)| {
             previous.remove(&id);
 
+            if self.draw_order.is_some() {
+                first_positions.insert(id, raw[0].pos);
+            }
+
             self.instances
                 .entry(id)
-                .and_modify(|instance| instance.update(device, queue, &raw))
+                .and_modify(|instance| {
+                    instance.update(device, queue, &raw);
+                })
                 .or_insert_with(|| tools::InstanceBuffer::new(device, &raw));
         });
 
         previous.into_iter().for_each(|id| {
             log::trace!("Removing texture instance '{}'", id);
             self.instances.remove(&id);
-            self.texture_storage.remove(&id);
         });
+
+        self.finish_sorted(device, queue);
+        self.finish_transparent(device, queue);
+
+        self.texture_storage.retain(|id, _| {
+            self.instances.contains_key(id)
+                || self.sorted_runs.iter().any(|(texture, _)| texture == id)
+                || self
+                    .transparent_runs
+                    .iter()
+                    .any(|(texture, _)| texture == id)
+        });
+
+        // Pick up levels streamed in or dropped since the last frame
+        self.texture_storage.values_mut().for_each(|texture| {
+            texture.refresh();
+        });
+
+        if let Some(draw_order) = &mut self.draw_order {
+            let batches = self
+                .instances
+                .iter()
+                .enumerate()
+                .map(|(order, (id, instance))| DrawOrderBatch {
+                    order: order as u32,
+                    mesh: None,
+                    texture: *id,
+                    instances: instance.count(),
+                    position: first_positions.get(id).copied().unwrap_or_default(),
+                })
+                .collect();
+
+            draw_order.update(device, queue, batches);
+        }
+    }
+
+    /// Sort this frame's y sorted sprites back to front and upload them as one buffer.
+    fn finish_sorted(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.sorted_runs.clear();
+
+        if self.sorted_to_prep.is_empty() {
+            self.sorted_instances = None;
+            return;
+        }
+
+        // Higher sprites are further back. Ties keep a stable order so overlapping
+        // sprites at the same y don't flicker.
+        self.sorted_to_prep.sort_by(|a, b| {
+            b.sort_y
+                .total_cmp(&a.sort_y)
+                .then(a.tie_break.cmp(&b.tie_break))
+        });
+
+        let raw = self
+            .sorted_to_prep
+            .iter()
+            .enumerate()
+            .map(|(index, sorted)| {
+                let index = index as u32;
+
+                match self.sorted_runs.last_mut() {
+                    Some((texture, range)) if *texture == sorted.texture => range.end = index + 1,
+                    _ => self.sorted_runs.push((sorted.texture, index..index + 1)),
+                }
+
+                sorted.instance
+            })
+            .collect::<Vec<_>>();
+
+        self.sorted_to_prep.clear();
+
+        match &mut self.sorted_instances {
+            Some(instances) => {
+                instances.update(device, queue, &raw);
+            }
+            None => self.sorted_instances = Some(tools::InstanceBuffer::new(device, &raw)),
+        }
+    }
+
+    /// Sort this frame's transparent sprites back to front and upload them as one buffer.
+    /// Sprites at the same depth, such as in one y sorted layer, keep their y order.
+    fn finish_transparent(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.transparent_runs.clear();
+
+        if self.transparent_to_prep.is_empty() {
+            self.transparent_instances = None;
+            return;
+        }
+
+        self.transparent_to_prep.sort_by(|a, b| {
+            b.depth
+                .total_cmp(&a.depth)
+                .then(b.sort_y.total_cmp(&a.sort_y))
+                .then(a.tie_break.cmp(&b.tie_break))
+        });
+
+        let raw = self
+            .transparent_to_prep
+            .iter()
+            .enumerate()
+            .map(|(index, transparent)| {
+                let index = index as u32;
+
+                match self.transparent_runs.last_mut() {
+                    Some((texture, range)) if *texture == transparent.texture => {
+                        range.end = index + 1
+                    }
+                    _ => self
+                        .transparent_runs
+                        .push((transparent.texture, index..index + 1)),
+                }
+
+                transparent.instance
+            })
+            .collect::<Vec<_>>();
+
+        self.transparent_to_prep.clear();
+
+        match &mut self.transparent_instances {
+            Some(instances) => {
+                instances.update(device, queue, &raw);
+            }
+            None => self.transparent_instances = Some(tools::InstanceBuffer::new(device, &raw)),
+        }
+    }
+
+    /// Draw the sprites prepped with alpha below 1. With depth, this should be rendered
+    /// after every opaque pipeline (see `RenderPipeline::render_transparent`). Without
+    /// depth, `render` draws them itself.
+    pub fn render_transparent(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        let Some(transparent) = &self.transparent_instances else {
+            return;
+        };
+
+        pass.set_pipeline(&self.transparent_pipeline);
+        pass.set_bind_group(0, camera_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        pass.set_vertex_buffer(1, transparent.slice(..));
+
+        self.transparent_runs
+            .iter()
+            .for_each(|(texture_id, range)| {
+                let texture = self.texture_storage.get(texture_id).unwrap();
+
+                pass.set_bind_group(1, texture.bind_group(), &[]);
+                pass.draw_indexed(0..self.index_count, 0, range.clone());
+            });
     }
 
     pub fn render(&self, pass: &mut wgpu::RenderPass, camera_bind_group: &wgpu::BindGroup) {
-        pass.set_pipeline(&self.pipeline);
+        match &self.draw_order {
+            Some(draw_order) => pass.set_pipeline(draw_order.pipeline()),
+            None => pass.set_pipeline(&self.pipeline),
+        }
         pass.set_bind_group(0, camera_bind_group, &[]);
 
         pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-        self.instances.iter().for_each(|(texture_id, instance)| {
-            let texture = self.texture_storage.get(texture_id).unwrap();
+        self.instances
+            .iter()
+            .enumerate()
+            .for_each(|(order, (texture_id, instance))| {
+                let texture = self.texture_storage.get(texture_id).unwrap();
 
-            pass.set_bind_group(1, texture.bind_group(), &[]);
-            pass.set_vertex_buffer(1, instance.slice(..));
-            pass.draw_indexed(0..self.index_count, 0, 0..instance.count());
-        });
+                if let Some(draw_order) = &self.draw_order {
+                    draw_order.set_bind_group(pass, 2, order as u32);
+                }
+
+                pass.set_bind_group(1, texture.bind_group(), &[]);
+                pass.set_vertex_buffer(1, instance.slice(..));
+                pass.draw_indexed(0..self.index_count, 0, 0..instance.count());
+            });
+
+        if let Some(sorted) = &self.sorted_instances {
+            pass.set_pipeline(&self.sorted_pipeline);
+            pass.set_bind_group(0, camera_bind_group, &[]);
+            pass.set_vertex_buffer(1, sorted.slice(..));
+
+            self.sorted_runs.iter().for_each(|(texture_id, range)| {
+                let texture = self.texture_storage.get(texture_id).unwrap();
+
+                pass.set_bind_group(1, texture.bind_group(), &[]);
+                pass.draw_indexed(0..self.index_count, 0, range.clone());
+            });
+        }
+
+        // Without depth, draw order alone decides what's in front
+        if !self.use_depth {
+            self.render_transparent(pass, camera_bind_group);
+        }
     }
 }
 
@@ -0,0 +1,195 @@
+//====================================================================
+
+use std::collections::{HashMap, HashSet};
+
+use roots_renderer::{
+    layouts,
+    shared::{SharedRenderResources, Vertex},
+    texture::{
+        LoadedTextureArray, TextureId, TextureRectVertex, TEXTURE_RECT_INDEX_COUNT,
+        TEXTURE_RECT_INDICES, TEXTURE_RECT_VERTICES,
+    },
+    tools::{self},
+};
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+pub struct TextureArrayInstance {
+    pub color: glam::Vec4,
+    pub size: glam::Vec2,
+    pub pos: glam::Vec3,
+    pub layer: u32,
+    pub pad: [u32; 2],
+}
+
+impl Vertex for TextureArrayInstance {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            2 => Float32x4, // Color
+            3 => Float32x2, // Size
+            4 => Float32x3, // Pos
+            5 => Uint32, // Layer
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &VERTEX_ATTRIBUTES,
+        }
+    }
+}
+
+pub struct TextureArrayData<'a> {
+    pub texture: &'a LoadedTextureArray,
+    pub layer: u32,
+    pub size: glam::Vec2,
+    pub pos: glam::Vec3,
+    pub color: glam::Vec4,
+}
+
+//====================================================================
+
+/// Sprite renderer where each instance picks a layer of a `LoadedTextureArray`.
+/// All sprites using the same array are drawn with one bind group and one draw call.
+#[derive(Debug)]
+pub struct TextureArrayRenderer {
+    pipeline: wgpu::RenderPipeline,
+
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+
+    to_prep: HashMap<TextureId, Vec<TextureArrayInstance>>,
+    instances: HashMap<TextureId, tools::InstanceBuffer<TextureArrayInstance>>,
+    texture_storage: HashMap<TextureId, LoadedTextureArray>,
+}
+
+impl TextureArrayRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> Self {
+        log::debug!("Creating Texture Array Renderer");
+
+        let depth_convention = shared.depth_convention();
+
+        let shader = include_str!("shaders/texture_array.wgsl");
+        let layouts = [layouts::CAMERA, layouts::TEXTURE_ARRAY];
+        shared
+            .layouts()
+            .debug_validate_shader("Texture Array Pipeline", shader, &layouts);
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Texture Array Pipeline",
+            &shared.layouts().layouts(&layouts),
+            &[TextureRectVertex::desc(), TextureArrayInstance::desc()],
+            shader,
+            tools::RenderPipelineDescriptor {
+                depth_stencil: Some(
+                    depth_convention.depth_stencil_state(true, depth_convention.compare()),
+                ),
+                multisample: shared.multisample(),
+                cache: shared.pipeline_cache(),
+                ..Default::default()
+            },
+        );
+
+        let vertex_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Vertex,
+            "Texture Array",
+            &TEXTURE_RECT_VERTICES,
+        );
+
+        let index_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Index,
+            "Texture Array",
+            &TEXTURE_RECT_INDICES,
+        );
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            index_count: TEXTURE_RECT_INDEX_COUNT,
+
+            to_prep: HashMap::default(),
+            instances: HashMap::default(),
+            texture_storage: HashMap::default(),
+        }
+    }
+
+    #[inline]
+    pub fn prep_texture(&mut self, data: TextureArrayData) {
+        if data.layer >= data.texture.layers() {
+            log::warn!(
+                "Texture array layer {} out of range - array only has {} layers",
+                data.layer,
+                data.texture.layers()
+            );
+            return;
+        }
+
+        self.to_prep
+            .entry(data.texture.id())
+            .or_insert_with(|| {
+                self.texture_storage
+                    .entry(data.texture.id())
+                    .or_insert_with(|| data.texture.clone());
+
+                Vec::new()
+            })
+            .push(TextureArrayInstance {
+                color: data.color,
+                size: data.size,
+                pos: data.pos,
+                layer: data.layer,
+                pad: [0; 2],
+            });
+    }
+
+    pub fn finish_prep(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mut previous = self.instances.keys().copied().collect::<HashSet<_>>();
+
+        self.to_prep.drain().for_each(|(id, raw)| {
+            previous.remove(&id);
+
+            self.instances
+                .entry(id)
+                .and_modify(|instance| {
+                    instance.update(device, queue, &raw);
+                })
+                .or_insert_with(|| tools::InstanceBuffer::new(device, &raw));
+        });
+
+        previous.into_iter().for_each(|id| {
+            log::trace!("Removing texture array instance '{}'", id);
+            self.instances.remove(&id);
+            self.texture_storage.remove(&id);
+        });
+    }
+
+    pub fn render(&self, pass: &mut wgpu::RenderPass, camera_bind_group: &wgpu::BindGroup) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, camera_bind_group, &[]);
+
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        self.instances.iter().for_each(|(texture_id, instance)| {
+            let texture = self.texture_storage.get(texture_id).unwrap();
+
+            pass.set_bind_group(1, texture.bind_group(), &[]);
+            pass.set_vertex_buffer(1, instance.slice(..));
+            pass.draw_indexed(0..self.index_count, 0, 0..instance.count());
+        });
+    }
+}
+
+//====================================================================
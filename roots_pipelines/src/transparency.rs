@@ -0,0 +1,46 @@
+//====================================================================
+
+use roots_renderer::shared::DepthConvention;
+
+//====================================================================
+
+/// Orders transparent draws back to front from the camera, so blended surfaces cover
+/// the ones behind them. Pass the first camera's view projection to each renderer with
+/// transparent draws before prepping it.
+#[derive(Debug, Clone, Copy)]
+pub struct TransparentSort {
+    view_projection: glam::Mat4,
+    reversed_z: bool,
+}
+
+impl Default for TransparentSort {
+    fn default() -> Self {
+        Self {
+            view_projection: glam::Mat4::IDENTITY,
+            reversed_z: false,
+        }
+    }
+}
+
+impl TransparentSort {
+    #[inline]
+    pub fn new(view_projection: glam::Mat4, depth_convention: &DepthConvention) -> Self {
+        Self {
+            view_projection,
+            reversed_z: depth_convention.reversed_z,
+        }
+    }
+
+    /// Sort key of `position`. Larger keys are further from the camera.
+    #[inline]
+    pub fn depth(&self, position: glam::Vec3) -> f32 {
+        let depth = self.view_projection.project_point3(position).z;
+
+        match self.reversed_z {
+            true => -depth,
+            false => depth,
+        }
+    }
+}
+
+//====================================================================
@@ -0,0 +1,75 @@
+//====================================================================
+
+//====================================================================
+
+/// How much of the wind each vertex of a model takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindWeight {
+    /// Every vertex moves the full amount, so the whole model sways as one.
+    Uniform,
+    /// Vertices at or below `base` on the model's own y axis stay put, rising to the
+    /// full amount at `top`. For plants rooted at their origin.
+    Height { base: f32, top: f32 },
+}
+
+impl Default for WindWeight {
+    fn default() -> Self {
+        Self::Height { base: 0., top: 1. }
+    }
+}
+
+/// Sways a model's vertices back and forth over time, for grass, flags and foliage.
+/// Models with wind are drawn by an animated variant of the model pipeline but are
+/// otherwise batched as usual. The time comes from the camera uniform, see
+/// `CameraUniformRaw::with_time`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wind {
+    /// World space direction vertices are pushed in.
+    pub direction: glam::Vec3,
+    /// Furthest a vertex with full weight moves, in world units.
+    pub amplitude: f32,
+    /// Sways per second.
+    pub frequency: f32,
+    /// World units between gusts travelling along `direction`, so neighbouring models
+    /// sway out of step. Zero sways everything together.
+    pub wavelength: f32,
+    pub weight: WindWeight,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: glam::Vec3::X,
+            amplitude: 0.1,
+            frequency: 0.5,
+            wavelength: 6.,
+            weight: WindWeight::default(),
+        }
+    }
+}
+
+impl Wind {
+    /// Packed for a model instance - the sway and frequency, then the weight mask and
+    /// wave number.
+    #[inline]
+    pub(crate) fn to_raw(self) -> (glam::Vec4, glam::Vec4) {
+        let sway = self.direction.normalize_or_zero() * self.amplitude;
+
+        let (base, inverse_span, masked) = match self.weight {
+            WindWeight::Uniform => (0., 0., 0.),
+            WindWeight::Height { base, top } => (base, 1. / (top - base).max(f32::EPSILON), 1.),
+        };
+
+        let wave_number = match self.wavelength > 0. {
+            true => std::f32::consts::TAU / self.wavelength,
+            false => 0.,
+        };
+
+        (
+            sway.extend(self.frequency),
+            glam::vec4(base, inverse_span, masked, wave_number),
+        )
+    }
+}
+
+//====================================================================
@@ -0,0 +1,328 @@
+//====================================================================
+
+use std::collections::{HashMap, HashSet};
+
+use roots_renderer::{
+    layouts,
+    shared::{SharedRenderResources, Vertex},
+    texture::{LoadedTexture, TextureId},
+    tools,
+};
+
+//====================================================================
+
+/// A band drawn across a panel, such as the selected row of a menu.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanelHighlight {
+    /// Top of the band as a fraction of the panel height, from the top.
+    pub start: f32,
+    /// Bottom of the band as a fraction of the panel height, from the top.
+    pub end: f32,
+    pub color: glam::Vec4,
+}
+
+/// A flat, optionally rounded rectangle drawn in world space by the `WorldPanelRenderer`.
+/// The panel lies on the xy plane of its transform, facing +z.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldPanel {
+    /// Size in local units before the transform is applied.
+    pub size: glam::Vec2,
+    /// The point of the panel placed at the transform's origin, as a fraction of
+    /// its size. `(0, 0)` is the top left and `(1, 1)` the bottom right.
+    pub anchor: glam::Vec2,
+    /// Multiplied with the texture, if any.
+    pub color: glam::Vec4,
+    /// Corner radius in local units. Clamped to half of the smallest side.
+    pub corner_radius: f32,
+    pub highlight: Option<PanelHighlight>,
+}
+
+impl Default for WorldPanel {
+    fn default() -> Self {
+        Self {
+            size: glam::Vec2::ONE,
+            anchor: glam::vec2(0.5, 0.5),
+            color: glam::Vec4::ONE,
+            corner_radius: 0.,
+            highlight: None,
+        }
+    }
+}
+
+impl WorldPanel {
+    #[inline]
+    pub fn new(size: glam::Vec2) -> Self {
+        Self {
+            size,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn with_anchor(mut self, anchor: glam::Vec2) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    #[inline]
+    pub fn with_color(mut self, color: glam::Vec4) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[inline]
+    pub fn with_corner_radius(mut self, corner_radius: f32) -> Self {
+        self.corner_radius = corner_radius;
+        self
+    }
+
+    #[inline]
+    pub fn with_highlight(mut self, highlight: PanelHighlight) -> Self {
+        self.highlight = Some(highlight);
+        self
+    }
+
+    fn to_instance(&self, transform: glam::Mat4) -> WorldPanelInstance {
+        let (highlight_range, highlight_color) = match &self.highlight {
+            Some(highlight) => (glam::vec2(highlight.start, highlight.end), highlight.color),
+            None => (glam::Vec2::ZERO, glam::Vec4::ZERO),
+        };
+
+        WorldPanelInstance {
+            transform,
+            color: self.color,
+            highlight_color,
+            size: self.size,
+            anchor: self.anchor,
+            highlight_range,
+            corner_radius: self.corner_radius,
+            pad: 0.,
+        }
+    }
+}
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+pub struct WorldPanelInstance {
+    pub transform: glam::Mat4,
+    pub color: glam::Vec4,
+    pub highlight_color: glam::Vec4,
+    pub size: glam::Vec2,
+    pub anchor: glam::Vec2,
+    pub highlight_range: glam::Vec2,
+    pub corner_radius: f32,
+    pub pad: f32,
+}
+
+impl Vertex for WorldPanelInstance {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 10] = wgpu::vertex_attr_array![
+            0 => Float32x4, // Transform
+            1 => Float32x4,
+            2 => Float32x4,
+            3 => Float32x4,
+            4 => Float32x4, // Color
+            5 => Float32x4, // Highlight color
+            6 => Float32x2, // Size
+            7 => Float32x2, // Anchor
+            8 => Float32x2, // Highlight range
+            9 => Float32, // Corner radius
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &VERTEX_ATTRIBUTES,
+        }
+    }
+}
+
+//====================================================================
+
+/// Renders `WorldPanel`s. Panels are batched by texture, so every untextured
+/// panel is drawn in a single draw call. Panels are alpha blended and don't
+/// write depth.
+#[derive(Debug)]
+pub struct WorldPanelRenderer {
+    pipeline: wgpu::RenderPipeline,
+    textured_pipeline: wgpu::RenderPipeline,
+
+    to_prep: HashMap<Option<TextureId>, Vec<WorldPanelInstance>>,
+    instances: HashMap<Option<TextureId>, tools::InstanceBuffer<WorldPanelInstance>>,
+    texture_storage: HashMap<TextureId, LoadedTexture>,
+}
+
+impl WorldPanelRenderer {
+    /// Panels are depth tested against the rest of the scene.
+    #[inline]
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> Self {
+        let compare = shared.depth_convention().compare();
+        Self::new_with_compare(device, config, shared, compare)
+    }
+
+    /// Create a renderer with a custom depth compare function. Use
+    /// `CompareFunction::Always` to draw panels over everything already rendered.
+    pub fn new_with_compare(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        compare: wgpu::CompareFunction,
+    ) -> Self {
+        log::debug!("Creating World Panel Renderer");
+
+        let pipeline = Self::create_pipeline(device, config, shared, compare, false);
+        let textured_pipeline = Self::create_pipeline(device, config, shared, compare, true);
+
+        Self {
+            pipeline,
+            textured_pipeline,
+            to_prep: HashMap::default(),
+            instances: HashMap::default(),
+            texture_storage: HashMap::default(),
+        }
+    }
+
+    /// Untextured panels use a separate entry point that doesn't bind a texture.
+    fn create_pipeline(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        compare: wgpu::CompareFunction,
+        textured: bool,
+    ) -> wgpu::RenderPipeline {
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let descriptor = tools::RenderPipelineDescriptor {
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            fragment_targets: Some(&fragment_targets),
+            depth_stencil: Some(
+                shared
+                    .depth_convention()
+                    .depth_stencil_state(false, compare),
+            ),
+            multisample: shared.multisample(),
+            cache: shared.pipeline_cache(),
+            ..Default::default()
+        };
+
+        let (label, layouts, descriptor) = match textured {
+            true => (
+                "Textured World Panel Pipeline",
+                &[layouts::CAMERA, layouts::TEXTURE][..],
+                descriptor.with_entry_points("vs_main", "fs_textured"),
+            ),
+            false => ("World Panel Pipeline", &[layouts::CAMERA][..], descriptor),
+        };
+
+        let shader = include_str!("shaders/world_panel.wgsl");
+        shared
+            .layouts()
+            .debug_validate_shader(label, shader, layouts);
+
+        tools::create_pipeline(
+            device,
+            config,
+            label,
+            &shared.layouts().layouts(layouts),
+            &[WorldPanelInstance::desc()],
+            shader,
+            descriptor,
+        )
+    }
+
+    /// Queue a panel for this frame. Panels are only kept until the next `finish_prep`.
+    pub fn prep_panel(
+        &mut self,
+        panel: &WorldPanel,
+        texture: Option<&LoadedTexture>,
+        transform: glam::Mat4,
+    ) {
+        let id = texture.map(|texture| {
+            self.texture_storage
+                .entry(texture.id())
+                .or_insert_with(|| texture.clone());
+
+            texture.id()
+        });
+
+        self.to_prep
+            .entry(id)
+            .or_default()
+            .push(panel.to_instance(transform));
+    }
+
+    pub fn finish_prep(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mut previous = self.instances.keys().copied().collect::<HashSet<_>>();
+
+        self.to_prep.drain().for_each(|(id, raw)| {
+            previous.remove(&id);
+
+            self.instances
+                .entry(id)
+                .and_modify(|instance| {
+                    instance.update(device, queue, &raw);
+                })
+                .or_insert_with(|| tools::InstanceBuffer::new(device, &raw));
+        });
+
+        previous.into_iter().for_each(|id| {
+            self.instances.remove(&id);
+
+            if let Some(id) = id {
+                log::trace!("Removing world panel texture '{}'", id);
+                self.texture_storage.remove(&id);
+            }
+        });
+    }
+
+    /// Number of panels prepped last frame.
+    #[inline]
+    pub fn panel_count(&self) -> u32 {
+        self.instances
+            .values()
+            .map(|instance| instance.count())
+            .sum()
+    }
+
+    pub fn render(&self, pass: &mut wgpu::RenderPass, camera_bind_group: &wgpu::BindGroup) {
+        if let Some(instance) = self.instances.get(&None) {
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, camera_bind_group, &[]);
+            pass.set_vertex_buffer(0, instance.slice(..));
+            pass.draw(0..4, 0..instance.count());
+        }
+
+        if self.texture_storage.is_empty() {
+            return;
+        }
+
+        pass.set_pipeline(&self.textured_pipeline);
+        pass.set_bind_group(0, camera_bind_group, &[]);
+
+        self.instances.iter().for_each(|(id, instance)| {
+            let Some(texture) = id.and_then(|id| self.texture_storage.get(&id)) else {
+                return;
+            };
+
+            pass.set_bind_group(1, texture.bind_group(), &[]);
+            pass.set_vertex_buffer(0, instance.slice(..));
+            pass.draw(0..4, 0..instance.count());
+        });
+    }
+}
+
+//====================================================================
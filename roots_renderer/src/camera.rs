@@ -1,12 +1,20 @@
 //====================================================================
 
+use roots_common::{
+    coords::{CoordinateConvention, NdcPos, WindowPx, WorldPos2},
+    input::MouseInput,
+    Size,
+};
 use wgpu::util::DeviceExt;
 
+use crate::shared::DepthConvention;
+
 //====================================================================
 
 pub struct Camera {
     buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
+    depth_convention: DepthConvention,
 }
 
 impl Camera {
@@ -32,7 +40,11 @@ impl Camera {
             }],
         });
 
-        Self { buffer, bind_group }
+        Self {
+            buffer,
+            bind_group,
+            depth_convention: DepthConvention::default(),
+        }
     }
 
     #[inline]
@@ -49,13 +61,33 @@ impl Camera {
                 wgpu::BufferSize::new(std::mem::size_of::<CameraUniformRaw>() as u64).unwrap(),
             )
             .unwrap()
-            .copy_from_slice(bytemuck::cast_slice(&[data.get_camera_uniform(transform)]));
+            .copy_from_slice(bytemuck::cast_slice(&[
+                data.get_camera_uniform_with(transform, &self.depth_convention)
+            ]));
+    }
+
+    /// Write an already calculated uniform, such as one eye of a `StereoCamera`.
+    #[inline]
+    pub fn update_camera_raw(&self, queue: &wgpu::Queue, raw: &CameraUniformRaw) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[*raw]));
     }
 
     #[inline]
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.bind_group
     }
+
+    #[inline]
+    pub fn depth_convention(&self) -> &DepthConvention {
+        &self.depth_convention
+    }
+
+    /// Set the depth convention projections are remapped to. Must match the
+    /// convention of the pipelines this camera is used with.
+    #[inline]
+    pub fn set_depth_convention(&mut self, depth_convention: DepthConvention) {
+        self.depth_convention = depth_convention;
+    }
 }
 
 //====================================================================
@@ -71,14 +103,42 @@ pub trait CameraUniform {
             transform.translation.into(),
         )
     }
+
+    #[inline]
+    fn get_camera_uniform_with(
+        &self,
+        transform: &glam::Affine3A,
+        depth_convention: &DepthConvention,
+    ) -> CameraUniformRaw {
+        CameraUniformRaw::new(
+            depth_convention.adjust_projection(self.get_projection_matrix())
+                * self.get_view_matrix(transform),
+            transform.translation.into(),
+        )
+    }
+
+    /// World space ray from a camera at `transform` through `ndc`, such as the cursor
+    /// from `WindowPx::to_ndc`. Returns the origin on the near plane and the normalized
+    /// direction, which points along the view for perspective cameras and straight
+    /// ahead for orthographic ones.
+    fn screen_to_ray(&self, transform: &glam::Affine3A, ndc: NdcPos) -> (glam::Vec3, glam::Vec3) {
+        let inverse = (self.get_projection_matrix() * self.get_view_matrix(transform)).inverse();
+
+        // Close to the near plane rather than at the far one, which loses precision
+        // with large view distances
+        let near = ndc.unproject(0., inverse).0;
+        let further = ndc.unproject(0.5, inverse).0;
+
+        (near, (further - near).normalize_or_zero())
+    }
 }
 
 #[repr(C)]
-#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, PartialEq)]
 pub struct CameraUniformRaw {
     view_projection: glam::Mat4,
     camera_position: glam::Vec3,
-    _padding: u32,
+    time: f32,
 }
 
 impl CameraUniformRaw {
@@ -86,9 +146,95 @@ impl CameraUniformRaw {
         Self {
             view_projection,
             camera_position,
-            _padding: 0,
+            time: 0.,
         }
     }
+
+    /// Seconds for shaders that animate, such as model wind. Fills what would
+    /// otherwise be padding, so costs nothing for shaders that ignore it.
+    #[inline]
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+}
+
+//--------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// Side-by-side stereo rendering. Each eye is offset by half the `ipd` (inter-pupillary
+/// distance) along the camera's right axis and uses an off-axis projection so objects at
+/// the `convergence` distance have zero parallax. Each eye renders to half of the frame,
+/// so the camera's aspect ratio should be that of a single eye.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StereoCamera {
+    pub ipd: f32,
+    pub convergence: f32,
+}
+
+impl Default for StereoCamera {
+    fn default() -> Self {
+        Self {
+            ipd: 0.064,
+            convergence: 10.,
+        }
+    }
+}
+
+impl StereoCamera {
+    pub fn eye_uniform<C: CameraUniform>(
+        &self,
+        eye: Eye,
+        data: &C,
+        transform: &glam::Affine3A,
+        depth_convention: &DepthConvention,
+    ) -> CameraUniformRaw {
+        let offset = match eye {
+            Eye::Left => -self.ipd / 2.,
+            Eye::Right => self.ipd / 2.,
+        };
+
+        let right = (transform.matrix3 * glam::Vec3::X).normalize_or_zero();
+        let eye_transform = glam::Affine3A {
+            matrix3: transform.matrix3,
+            translation: transform.translation + glam::Vec3A::from(right * offset),
+        };
+
+        let projection = data.get_projection_matrix();
+
+        // Shift the frustum back towards the center so both eyes converge
+        let shift = projection.x_axis.x * offset / self.convergence.max(f32::EPSILON);
+        let shear = glam::Mat4::from_cols(
+            glam::Vec4::X,
+            glam::Vec4::Y,
+            glam::Vec4::Z,
+            glam::vec4(shift, 0., 0., 1.),
+        );
+
+        CameraUniformRaw::new(
+            depth_convention.adjust_projection(shear * projection)
+                * data.get_view_matrix(&eye_transform),
+            eye_transform.translation.into(),
+        )
+    }
+
+    #[inline]
+    pub fn eye_uniforms<C: CameraUniform>(
+        &self,
+        data: &C,
+        transform: &glam::Affine3A,
+        depth_convention: &DepthConvention,
+    ) -> [CameraUniformRaw; 2] {
+        [
+            self.eye_uniform(Eye::Left, data, transform, depth_convention),
+            self.eye_uniform(Eye::Right, data, transform, depth_convention),
+        ]
+    }
 }
 
 //--------------------------------------------------
@@ -131,6 +277,8 @@ impl CameraUniform for OrthographicCamera {
 }
 
 impl OrthographicCamera {
+    /// Always left handed - screen space pipelines order by depth along +Z, whatever
+    /// the 3D `CoordinateConvention`.
     #[inline]
     fn get_projection(&self) -> glam::Mat4 {
         glam::Mat4::orthographic_lh(
@@ -176,23 +324,155 @@ impl OrthographicCamera {
         self.top = half_height;
         self.bottom = -half_height;
     }
+
+    /// Width and height of the view in world units.
+    #[inline]
+    pub fn size(&self) -> glam::Vec2 {
+        glam::vec2(
+            (self.right - self.left).abs(),
+            (self.top - self.bottom).abs(),
+        )
+    }
+
+    #[inline]
+    pub fn center(&self) -> glam::Vec2 {
+        glam::vec2(self.left + self.right, self.bottom + self.top) / 2.
+    }
+
+    /// Screen pixels per world unit horizontally for a viewport of `viewport` pixels.
+    #[inline]
+    pub fn pixels_per_unit(&self, viewport: Size<u32>) -> f32 {
+        viewport.width.max(1) as f32 / self.size().x
+    }
+
+    /// The point under `screen_pos` in the camera's local space, which is world space
+    /// while its transform is at the origin. See `screen_to_world` otherwise.
+    pub fn screen_to_view(&self, screen_pos: WindowPx, viewport: Size<u32>) -> WorldPos2 {
+        let ndc = screen_pos.to_ndc(viewport).0;
+        let half = glam::vec2(self.right - self.left, self.top - self.bottom) / 2.;
+
+        WorldPos2(self.center() + ndc * half)
+    }
+
+    /// The point under `screen_pos` in world space, for a camera at `transform`.
+    pub fn screen_to_world(
+        &self,
+        screen_pos: WindowPx,
+        viewport: Size<u32>,
+        transform: &glam::Affine3A,
+    ) -> WorldPos2 {
+        let view = self.screen_to_view(screen_pos, viewport).0;
+        let inverse_view = self.get_view_matrix(transform).inverse();
+
+        WorldPos2(inverse_view.transform_point3(view.extend(0.)).truncate())
+    }
+
+    /// Move the view by `offset` world units.
+    #[inline]
+    pub fn translate(&mut self, offset: glam::Vec2) {
+        self.left += offset.x;
+        self.right += offset.x;
+        self.bottom += offset.y;
+        self.top += offset.y;
+    }
+
+    /// Zoom in by `factor` (or out if less than 1) around the center of the view,
+    /// scaling both axes so the aspect ratio is kept.
+    pub fn zoom(&mut self, factor: f32) {
+        if factor <= 0. || !factor.is_finite() {
+            return;
+        }
+
+        let center = self.center();
+        let half = glam::vec2(self.right - self.left, self.top - self.bottom) / (2. * factor);
+
+        self.left = center.x - half.x;
+        self.right = center.x + half.x;
+        self.bottom = center.y - half.y;
+        self.top = center.y + half.y;
+    }
+
+    /// Zoom in by `factor` (or out if less than 1) around `anchor_screen_pos`, so the
+    /// point under the anchor stays under it.
+    pub fn zoom_by(&mut self, factor: f32, anchor_screen_pos: WindowPx, viewport: Size<u32>) {
+        if factor <= 0. || !factor.is_finite() {
+            return;
+        }
+
+        let anchor = self.screen_to_view(anchor_screen_pos, viewport).0;
+        let scale = 1. / factor;
+
+        self.left = anchor.x + (self.left - anchor.x) * scale;
+        self.right = anchor.x + (self.right - anchor.x) * scale;
+        self.bottom = anchor.y + (self.bottom - anchor.y) * scale;
+        self.top = anchor.y + (self.top - anchor.y) * scale;
+    }
+
+    /// Move the view so the content follows a drag of `delta_px` screen pixels at the
+    /// current zoom.
+    pub fn pan_by_pixels(&mut self, delta_px: glam::Vec2, viewport: Size<u32>) {
+        let units_per_pixel = glam::vec2(
+            (self.right - self.left) / viewport.width.max(1) as f32,
+            (self.bottom - self.top) / viewport.height.max(1) as f32,
+        );
+
+        self.translate(-delta_px * units_per_pixel);
+    }
+
+    /// Frame the rect from `world_min` to `world_max` with `padding` world units on each
+    /// side, keeping the current aspect ratio and centering the rect on the other axis.
+    pub fn fit_rect(&mut self, world_min: glam::Vec2, world_max: glam::Vec2, padding: f32) {
+        let center = (world_min + world_max) / 2.;
+        let mut half = (world_max - world_min).abs() / 2. + padding;
+
+        let size = self.size();
+        let aspect = match size.y > 0. && size.x > 0. {
+            true => size.x / size.y,
+            false => 1.,
+        };
+
+        match half.x / half.y < aspect {
+            true => half.x = half.y * aspect,
+            false => half.y = half.x / aspect,
+        }
+
+        // Keep flipped axes flipped
+        let direction = glam::vec2(
+            (self.right - self.left).signum(),
+            (self.top - self.bottom).signum(),
+        );
+        half *= direction;
+
+        self.left = center.x - half.x;
+        self.right = center.x + half.x;
+        self.bottom = center.y - half.y;
+        self.top = center.y + half.y;
+    }
 }
 
 //--------------------------------------------------
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct PerspectiveCamera {
+    /// Defaults to the active convention when the camera is created.
+    pub convention: CoordinateConvention,
     pub up: glam::Vec3,
     pub aspect: f32,
     pub fovy: f32,
     pub z_near: f32,
+    /// `f32::INFINITY` for a projection without a far plane, such as for skyboxes and
+    /// distant terrain. Pairs best with reversed-Z depth, which keeps the precision
+    /// that an infinite far plane spreads out.
     pub z_far: f32,
 }
 
 impl Default for PerspectiveCamera {
     fn default() -> Self {
+        let convention = CoordinateConvention::active();
+
         Self {
-            up: glam::Vec3::Y,
+            convention,
+            up: convention.up(),
             aspect: 1.7777777778,
             fovy: 45.,
             z_near: 0.1,
@@ -208,18 +488,399 @@ impl CameraUniform for PerspectiveCamera {
     }
 
     fn get_view_matrix(&self, transform: &glam::Affine3A) -> glam::Mat4 {
-        let forward = (transform.matrix3 * glam::Vec3::Z).normalize_or_zero();
-        let translation = transform.translation.into();
+        let forward = (transform.matrix3 * self.convention.forward()).normalize_or_zero();
 
-        glam::Mat4::look_at_lh(translation, translation + forward, self.up)
+        self.convention
+            .look_to(transform.translation.into(), forward, self.up)
     }
 }
 
 impl PerspectiveCamera {
+    /// Match the aspect to a target of `width` by `height`, such as the window after a
+    /// resize. Zero sized targets are ignored. Outside of hecs, call `update_camera`
+    /// afterwards to upload the new projection.
+    #[inline]
+    pub fn set_aspect(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.aspect = width as f32 / height as f32;
+        }
+    }
+
+    /// The projection with no far plane, whatever `z_far` is.
+    #[inline]
+    pub fn get_projection_infinite(&self) -> glam::Mat4 {
+        self.convention
+            .perspective_infinite(self.fovy, self.aspect, self.z_near)
+    }
+
+    #[inline]
+    pub fn is_infinite(&self) -> bool {
+        self.z_far == f32::INFINITY
+    }
+
     #[inline]
     fn get_projection(&self) -> glam::Mat4 {
-        glam::Mat4::perspective_lh(self.fovy, self.aspect, self.z_near, self.z_far)
+        match self.is_infinite() {
+            true => self.get_projection_infinite(),
+            false => self
+                .convention
+                .perspective(self.fovy, self.aspect, self.z_near, self.z_far),
+        }
+    }
+}
+
+//--------------------------------------------------
+
+/// Turns a camera around a focus point with the mouse, for inspecting a model or
+/// scene. Mouse motion orbits and scrolling zooms, producing the transform to pass to
+/// `update_camera` with a `PerspectiveCamera` in the same convention.
+///
+/// `update` orbits with any mouse motion. To only orbit while a button is held, call
+/// `orbit` and `zoom` yourself and then `transform`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitController {
+    /// Defaults to the active convention when the controller is created.
+    pub convention: CoordinateConvention,
+    pub focus: glam::Vec3,
+    /// Radians around the up axis, with 0 looking along the convention's forward axis.
+    pub yaw: f32,
+    /// Radians above the focus, with positive values looking down at it.
+    pub pitch: f32,
+    pub distance: f32,
+
+    /// Radians turned per unit of mouse motion.
+    pub sensitivity: f32,
+    /// How much one step of the scroll wheel zooms by.
+    pub zoom_speed: f32,
+    /// Pitch is clamped to this many radians above or below the horizon, so the
+    /// camera never flips over the poles.
+    pub pitch_limit: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub invert_y: bool,
+    /// Seconds taken to mostly catch up with input. 0 follows it immediately.
+    pub smoothing: f32,
+
+    /// Yaw, pitch and distance as of the last `update`, trailing the targets above
+    /// while smoothing.
+    current: Option<(f32, f32, f32)>,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self {
+            convention: CoordinateConvention::active(),
+            focus: glam::Vec3::ZERO,
+            yaw: 0.,
+            pitch: 0.3,
+            distance: 10.,
+            sensitivity: 0.005,
+            zoom_speed: 0.1,
+            pitch_limit: std::f32::consts::FRAC_PI_2 - 0.01,
+            min_distance: 0.1,
+            max_distance: 10000.,
+            invert_y: false,
+            smoothing: 0.,
+            current: None,
+        }
+    }
+}
+
+impl OrbitController {
+    #[inline]
+    pub fn new(focus: glam::Vec3, distance: f32) -> Self {
+        Self {
+            focus,
+            distance,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn with_angles(mut self, yaw: f32, pitch: f32) -> Self {
+        self.yaw = yaw;
+        self.pitch = pitch.clamp(-self.pitch_limit, self.pitch_limit);
+        self
+    }
+
+    #[inline]
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    #[inline]
+    pub fn with_distance_limits(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self
+    }
+
+    #[inline]
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Orbit by `motion`, in the units of `MouseInput::motion_delta`.
+    pub fn orbit(&mut self, motion: glam::Vec2) {
+        let motion_y = match self.invert_y {
+            true => -motion.y,
+            false => motion.y,
+        };
+
+        self.yaw += motion.x * self.sensitivity;
+        self.pitch =
+            (self.pitch + motion_y * self.sensitivity).clamp(-self.pitch_limit, self.pitch_limit);
+    }
+
+    /// Zoom by `scroll` steps, in the units of `MouseInput::scroll`. Positive steps
+    /// move towards the focus.
+    pub fn zoom(&mut self, scroll: f32) {
+        self.distance = (self.distance / (1. + self.zoom_speed).powf(scroll))
+            .clamp(self.min_distance, self.max_distance);
+    }
+
+    /// Orbit and zoom from this frame's mouse input, returning the camera's new
+    /// transform. `dt` is only used for smoothing.
+    pub fn update(&mut self, mouse: &MouseInput, dt: f32) -> glam::Affine3A {
+        self.orbit(mouse.motion_delta());
+
+        let scroll = mouse.scroll().y;
+        if scroll != 0. {
+            self.zoom(scroll);
+        }
+
+        let target = (self.yaw, self.pitch, self.distance);
+
+        let current = match (self.current, self.smoothing > 0.) {
+            (Some((yaw, pitch, distance)), true) => {
+                let t = 1. - (-dt / self.smoothing).exp();
+                (
+                    yaw + (target.0 - yaw) * t,
+                    pitch + (target.1 - pitch) * t,
+                    distance + (target.2 - distance) * t,
+                )
+            }
+            _ => target,
+        };
+
+        self.current = Some(current);
+        self.transform_with(current.0, current.1, current.2)
+    }
+
+    /// Whether smoothing is still catching up with input, so callers can keep
+    /// redrawing until it settles.
+    pub fn is_settling(&self) -> bool {
+        match self.current {
+            Some((yaw, pitch, distance)) => {
+                (self.yaw - yaw).abs() > 0.0001
+                    || (self.pitch - pitch).abs() > 0.0001
+                    || (self.distance - distance).abs() > 0.0001 * self.distance
+            }
+            None => false,
+        }
+    }
+
+    /// The camera's transform at the target yaw, pitch and distance, ignoring smoothing.
+    #[inline]
+    pub fn transform(&self) -> glam::Affine3A {
+        self.transform_with(self.yaw, self.pitch, self.distance)
+    }
+
+    fn transform_with(&self, yaw: f32, pitch: f32, distance: f32) -> glam::Affine3A {
+        let up = self.convention.up();
+
+        let horizontal =
+            self.convention.forward() * yaw.cos() + self.convention.right() * yaw.sin();
+        let direction = horizontal * pitch.cos() - up * pitch.sin();
+
+        let rotation = self.convention.rotation_looking_to(direction, up);
+        let eye = self.focus - direction * distance;
+
+        glam::Affine3A::from_rotation_translation(rotation, eye)
     }
 }
 
 //====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONVENTIONS: [CoordinateConvention; 3] = [
+        CoordinateConvention::LeftHandedYUp,
+        CoordinateConvention::RightHandedYUp,
+        CoordinateConvention::RightHandedZUp,
+    ];
+
+    fn perspective(convention: CoordinateConvention) -> PerspectiveCamera {
+        PerspectiveCamera {
+            convention,
+            up: convention.up(),
+            aspect: 1.,
+            fovy: std::f32::consts::FRAC_PI_3,
+            ..Default::default()
+        }
+    }
+
+    fn assert_vec3_eq(actual: glam::Vec3, expected: glam::Vec3) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-4),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn center_ray_points_forward() {
+        CONVENTIONS.into_iter().for_each(|convention| {
+            let camera = perspective(convention);
+            let (origin, direction) =
+                camera.screen_to_ray(&glam::Affine3A::IDENTITY, NdcPos::new(0., 0.));
+
+            assert_vec3_eq(direction, convention.forward());
+            assert_vec3_eq(origin, convention.forward() * camera.z_near);
+        });
+
+        // The default convention looks along +Z
+        let (_, direction) = perspective(CoordinateConvention::default())
+            .screen_to_ray(&glam::Affine3A::IDENTITY, NdcPos::new(0., 0.));
+        assert_vec3_eq(direction, glam::Vec3::Z);
+    }
+
+    #[test]
+    fn edge_rays_spread_by_the_field_of_view() {
+        CONVENTIONS.into_iter().for_each(|convention| {
+            let camera = perspective(convention);
+            let half_fov = camera.fovy / 2.;
+
+            let (_, right) = camera.screen_to_ray(&glam::Affine3A::IDENTITY, NdcPos::new(1., 0.));
+            let (_, up) = camera.screen_to_ray(&glam::Affine3A::IDENTITY, NdcPos::new(0., 1.));
+
+            assert!(right.dot(convention.right()) > 0.);
+            assert!(up.dot(convention.up()) > 0.);
+            assert!((up.angle_between(convention.forward()) - half_fov).abs() < 1e-4);
+            // Square aspect
+            assert!((right.angle_between(convention.forward()) - half_fov).abs() < 1e-4);
+        });
+    }
+
+    #[test]
+    fn rays_follow_the_camera_transform() {
+        let camera = perspective(CoordinateConvention::LeftHandedYUp);
+        let transform = glam::Affine3A::from_rotation_translation(
+            glam::Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            glam::vec3(5., 2., -3.),
+        );
+
+        let (origin, direction) = camera.screen_to_ray(&transform, NdcPos::new(0., 0.));
+
+        assert_vec3_eq(direction, glam::Vec3::X);
+        assert_vec3_eq(origin, glam::vec3(5. + camera.z_near, 2., -3.));
+    }
+
+    const VIEWPORT: Size<u32> = Size {
+        width: 800,
+        height: 400,
+    };
+
+    #[test]
+    fn zooming_in_shrinks_the_view_around_its_center() {
+        let mut camera = OrthographicCamera::new_centered(400., 200.);
+        camera.translate(glam::vec2(50., -20.));
+
+        camera.zoom(2.);
+        assert_eq!(camera.size(), glam::vec2(400., 200.));
+        assert_eq!(camera.center(), glam::vec2(50., -20.));
+
+        camera.zoom(0.25);
+        assert_eq!(camera.size(), glam::vec2(1600., 800.));
+
+        // Invalid factors are ignored
+        [0., -1., f32::NAN, f32::INFINITY]
+            .into_iter()
+            .for_each(|factor| camera.zoom(factor));
+        assert_eq!(camera.size(), glam::vec2(1600., 800.));
+    }
+
+    #[test]
+    fn zoom_by_keeps_the_anchor_in_place() {
+        let mut camera = OrthographicCamera::new_centered(400., 200.);
+        let anchor = WindowPx::new(600., 100.);
+        let before = camera.screen_to_view(anchor, VIEWPORT);
+
+        camera.zoom_by(2., anchor, VIEWPORT);
+
+        assert_eq!(camera.size(), glam::vec2(400., 200.));
+        assert_eq!(camera.screen_to_view(anchor, VIEWPORT), before);
+    }
+
+    #[test]
+    fn panning_follows_the_cursor() {
+        // Two world units per pixel
+        let mut camera = OrthographicCamera::new_centered(800., 400.);
+        let grabbed = camera.screen_to_view(WindowPx::new(200., 100.), VIEWPORT);
+
+        camera.pan_by_pixels(glam::vec2(100., 50.), VIEWPORT);
+
+        assert_eq!(camera.center(), glam::vec2(-200., 100.));
+        assert_eq!(
+            camera.screen_to_view(WindowPx::new(300., 150.), VIEWPORT),
+            grabbed
+        );
+    }
+
+    #[test]
+    fn screen_to_world_maps_the_window_onto_the_view() {
+        let camera = OrthographicCamera::new_centered(400., 200.);
+
+        let world = |x, y, transform| {
+            camera
+                .screen_to_world(WindowPx::new(x, y), VIEWPORT, transform)
+                .0
+        };
+
+        let identity = glam::Affine3A::IDENTITY;
+        assert_eq!(world(400., 200., &identity), glam::Vec2::ZERO);
+        // Window y grows downwards
+        assert_eq!(world(0., 0., &identity), glam::vec2(-400., 200.));
+        assert_eq!(world(800., 400., &identity), glam::vec2(400., -200.));
+
+        // Moved cameras give the point that renders under the cursor
+        let transform = glam::Affine3A::from_rotation_translation(
+            glam::Quat::from_rotation_z(0.5),
+            glam::vec3(10., 5., 0.),
+        );
+        let view_projection = camera.get_projection_matrix() * camera.get_view_matrix(&transform);
+
+        [(0., 0.), (400., 200.), (123., 321.)]
+            .into_iter()
+            .for_each(|(x, y)| {
+                let ndc = view_projection.project_point3(world(x, y, &transform).extend(0.));
+                let expected = WindowPx::new(x, y).to_ndc(VIEWPORT).0;
+                assert!(
+                    ndc.truncate().abs_diff_eq(expected, 1e-5),
+                    "{} != {}",
+                    ndc,
+                    expected
+                );
+            });
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let camera = OrthographicCamera::new_centered(100., 50.);
+
+        let (center, center_direction) =
+            camera.screen_to_ray(&glam::Affine3A::IDENTITY, NdcPos::new(0., 0.));
+        let (corner, corner_direction) =
+            camera.screen_to_ray(&glam::Affine3A::IDENTITY, NdcPos::new(1., 1.));
+
+        assert_vec3_eq(center, glam::Vec3::ZERO);
+        assert_vec3_eq(corner, glam::vec3(100., 50., 0.));
+        assert_vec3_eq(center_direction, glam::Vec3::Z);
+        assert_vec3_eq(corner_direction, glam::Vec3::Z);
+    }
+}
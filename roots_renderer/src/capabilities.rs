@@ -0,0 +1,313 @@
+//====================================================================
+
+use std::fmt::Display;
+
+use crate::lighting::{LightingPath, MAX_UNIFORM_LIGHTS};
+
+//====================================================================
+
+/// Block compressed texture formats the device can sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureCompression {
+    Bc,
+    Etc2,
+    Astc,
+}
+
+/// What the device can do, probed once after it's created. See `GraphicsSettings::evaluate`
+/// for how it limits the renderer's settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapabilityReport {
+    /// `Backend::Empty` if the device was created outside the renderer.
+    pub backend: wgpu::Backend,
+    pub adapter_name: String,
+    pub device_type: wgpu::DeviceType,
+
+    /// Largest width or height of a 2D texture.
+    pub max_texture_size: u32,
+    /// Storage buffers can be read from vertex and fragment shaders.
+    pub storage_buffers: bool,
+    pub compute_shaders: bool,
+    /// Sample counts usable for multisampled surface format render targets.
+    pub sample_counts: Vec<u32>,
+    pub compression: Vec<TextureCompression>,
+    pub indirect_draw: bool,
+    pub multi_draw_indirect: bool,
+    /// Pipelines can be created with push constant ranges.
+    pub push_constants: bool,
+    pub timestamp_queries: bool,
+    /// `Rgba16Float` can be rendered to and filtered.
+    pub float_render_targets: bool,
+    /// The surface can be configured with a floating point format.
+    pub float_surface: bool,
+    /// Meets the full WebGPU limits and downlevel flags. Downlevel devices (such as
+    /// WebGL2 or old GLES drivers) miss some of them.
+    pub webgpu_compliant: bool,
+}
+
+impl CapabilityReport {
+    /// Probe an adapter and the device created from it.
+    pub fn from_adapter(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        surface_capabilities: &wgpu::SurfaceCapabilities,
+        surface_format: wgpu::TextureFormat,
+    ) -> Self {
+        let info = adapter.get_info();
+        let downlevel = adapter.get_downlevel_capabilities();
+
+        let float_features = adapter.get_texture_format_features(wgpu::TextureFormat::Rgba16Float);
+        let float_render_targets = float_features
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+            && float_features
+                .flags
+                .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE);
+
+        let mut report = Self::from_device(device);
+
+        report.backend = info.backend;
+        report.adapter_name = info.name;
+        report.device_type = info.device_type;
+        report.sample_counts = adapter
+            .get_texture_format_features(surface_format)
+            .flags
+            .supported_sample_counts();
+        report.compute_shaders &= downlevel
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS);
+        report.storage_buffers &= downlevel
+            .flags
+            .contains(wgpu::DownlevelFlags::VERTEX_STORAGE);
+        report.indirect_draw = downlevel
+            .flags
+            .contains(wgpu::DownlevelFlags::INDIRECT_EXECUTION);
+        report.float_render_targets = float_render_targets;
+        report.float_surface = surface_capabilities
+            .formats
+            .contains(&wgpu::TextureFormat::Rgba16Float);
+        report.webgpu_compliant &= downlevel.is_webgpu_compliant();
+
+        report
+    }
+
+    /// What can be told from a device alone, for devices created by a host application.
+    /// Assumes the sample counts WebGPU guarantees and no floating point surface.
+    pub fn from_device(device: &wgpu::Device) -> Self {
+        let limits = device.limits();
+        let features = device.features();
+
+        let compression = [
+            (
+                wgpu::Features::TEXTURE_COMPRESSION_BC,
+                TextureCompression::Bc,
+            ),
+            (
+                wgpu::Features::TEXTURE_COMPRESSION_ETC2,
+                TextureCompression::Etc2,
+            ),
+            (
+                wgpu::Features::TEXTURE_COMPRESSION_ASTC,
+                TextureCompression::Astc,
+            ),
+        ]
+        .into_iter()
+        .filter(|(feature, _)| features.contains(*feature))
+        .map(|(_, compression)| compression)
+        .collect();
+
+        Self {
+            backend: wgpu::Backend::Empty,
+            adapter_name: String::new(),
+            device_type: wgpu::DeviceType::Other,
+            max_texture_size: limits.max_texture_dimension_2d,
+            storage_buffers: limits.max_storage_buffers_per_shader_stage > 0,
+            compute_shaders: limits.max_compute_workgroups_per_dimension > 0,
+            sample_counts: vec![1, 4],
+            compression,
+            indirect_draw: true,
+            multi_draw_indirect: features.contains(wgpu::Features::MULTI_DRAW_INDIRECT),
+            push_constants: features.contains(wgpu::Features::PUSH_CONSTANTS)
+                && limits.max_push_constant_size > 0,
+            timestamp_queries: features.contains(wgpu::Features::TIMESTAMP_QUERY),
+            float_render_targets: true,
+            float_surface: false,
+            webgpu_compliant: wgpu::Limits::default().check_limits(&limits),
+        }
+    }
+
+    #[inline]
+    pub fn supports_sample_count(&self, samples: u32) -> bool {
+        self.sample_counts.contains(&samples)
+    }
+}
+
+//====================================================================
+
+/// A setting that can be turned down or off to fit the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphicsFeature {
+    Msaa,
+    Shadows,
+    Ssao,
+    HdrSurface,
+    /// Unlimited lights read from a storage buffer.
+    StorageLighting,
+}
+
+impl Display for GraphicsFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GraphicsFeature::Msaa => "MSAA",
+            GraphicsFeature::Shadows => "Shadows",
+            GraphicsFeature::Ssao => "SSAO",
+            GraphicsFeature::HdrSurface => "HDR output",
+            GraphicsFeature::StorageLighting => "Unlimited lights",
+        })
+    }
+}
+
+/// A requested setting the device couldn't provide, for showing in a settings menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DegradedFeature {
+    pub feature: GraphicsFeature,
+    pub reason: String,
+}
+
+impl Display for DegradedFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} unavailable on this device: {}",
+            self.feature, self.reason
+        )
+    }
+}
+
+/// Quality settings read by the renderer and the pipelines supporting them. Request
+/// settings with `RendererState::set_graphics_settings`, which turns down whatever
+/// the `CapabilityReport` rules out.
+///
+/// With the `serde` feature, settings are saved with `roots_common::versioned`. Fields
+/// missing from older saves fall back to `platform_default`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GraphicsSettings {
+    /// Samples per pixel of multisampled passes. 1 disables MSAA.
+    pub msaa_samples: u32,
+    pub shadows: bool,
+    pub ssao: bool,
+    /// Render to a floating point surface for HDR output.
+    pub hdr_surface: bool,
+    /// How lights reach the shaders. Always chosen from the capabilities - requesting
+    /// a path has no effect, so it isn't saved.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub lighting: LightingPath,
+}
+
+#[cfg(feature = "serde")]
+impl roots_common::versioned::VersionedFormat for GraphicsSettings {
+    const FORMAT: &'static str = "graphics_settings";
+    const VERSION: u32 = 1;
+}
+
+impl Default for GraphicsSettings {
+    #[inline]
+    fn default() -> Self {
+        Self::platform_default()
+    }
+}
+
+impl GraphicsSettings {
+    /// Web builds are more likely to run on weak integrated gpus, so they default
+    /// to cheaper settings.
+    pub fn platform_default() -> Self {
+        Self {
+            msaa_samples: 4,
+            shadows: true,
+            ssao: cfg!(not(target_arch = "wasm32")),
+            hdr_surface: false,
+            lighting: LightingPath::StorageBuffer,
+        }
+    }
+
+    /// The settings the device can provide, with every requested feature it had to
+    /// turn down and why.
+    pub fn evaluate(&self, report: &CapabilityReport) -> (GraphicsSettings, Vec<DegradedFeature>) {
+        let mut effective = *self;
+        let mut degraded = Vec::new();
+
+        let mut degrade =
+            |feature, reason: String| degraded.push(DegradedFeature { feature, reason });
+
+        if self.msaa_samples > 1 && !report.supports_sample_count(self.msaa_samples) {
+            effective.msaa_samples = report
+                .sample_counts
+                .iter()
+                .copied()
+                .filter(|samples| *samples <= self.msaa_samples)
+                .max()
+                .unwrap_or(1);
+
+            degrade(
+                GraphicsFeature::Msaa,
+                match effective.msaa_samples {
+                    1 => "multisampling isn't supported".into(),
+                    samples => {
+                        format!("{}x isn't supported, using {}x", self.msaa_samples, samples)
+                    }
+                },
+            );
+        }
+
+        if self.shadows && !report.webgpu_compliant {
+            effective.shadows = false;
+            degrade(
+                GraphicsFeature::Shadows,
+                "the device has downlevel limits".into(),
+            );
+        }
+
+        if self.ssao && (!report.webgpu_compliant || !report.float_render_targets) {
+            effective.ssao = false;
+            degrade(
+                GraphicsFeature::Ssao,
+                match report.float_render_targets {
+                    true => "the device has downlevel limits".into(),
+                    false => "floating point render targets aren't supported".into(),
+                },
+            );
+        }
+
+        if self.hdr_surface && !report.float_surface {
+            effective.hdr_surface = false;
+            degrade(
+                GraphicsFeature::HdrSurface,
+                "the surface has no floating point format".into(),
+            );
+        }
+
+        effective.lighting = match report.storage_buffers {
+            true => LightingPath::StorageBuffer,
+            false => {
+                degrade(
+                    GraphicsFeature::StorageLighting,
+                    format!(
+                        "shaders can't read storage buffers on the {:?} backend, \
+                        so only {} lights are drawn",
+                        report.backend, MAX_UNIFORM_LIGHTS
+                    ),
+                );
+                LightingPath::UniformArray
+            }
+        };
+
+        (effective, degraded)
+    }
+}
+
+//====================================================================
@@ -0,0 +1,253 @@
+//====================================================================
+
+pub use image::RgbaImage;
+
+use crate::Error;
+
+//====================================================================
+
+/// A copy of a texture into a mappable buffer, recorded into an encoder and read back
+/// with `read` once the encoder has been submitted.
+///
+/// Only 8 bit rgba and bgra textures with `COPY_SRC` usage can be captured. Reading
+/// blocks until the gpu has finished, so isn't available on wasm, where buffers can
+/// only be mapped asynchronously.
+pub struct PendingCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    bgra: bool,
+}
+
+impl PendingCapture {
+    pub fn new(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Result<Self, Error> {
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err(Error::Capture(
+                "the texture wasn't created with COPY_SRC usage".into(),
+            ));
+        }
+
+        if texture.sample_count() != 1 {
+            return Err(Error::Capture(
+                "multisampled textures must be resolved first".into(),
+            ));
+        }
+
+        let bgra = match texture.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => {
+                return Err(Error::Capture(format!(
+                    "{:?} textures can't be read as rgba",
+                    format
+                )))
+            }
+        };
+
+        let width = texture.width();
+        let height = texture.height();
+
+        // Rows of the copy must be aligned, so they're padded and trimmed when read
+        let padded_bytes_per_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(Self {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            bgra,
+        })
+    }
+
+    /// Wait for the copy and read it as an image. The encoder it was recorded into
+    /// must have been submitted.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read(self, device: &wgpu::Device) -> Result<RgbaImage, Error> {
+        let slice = self.buffer.slice(..);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).ok();
+        });
+        device.poll(wgpu::Maintain::Wait);
+
+        receiver
+            .recv()
+            .map_err(|_| Error::Capture("the buffer was never mapped".into()))?
+            .map_err(|e| Error::Capture(e.to_string()))?;
+
+        let row_bytes = self.width as usize * 4;
+        let mut pixels = Vec::with_capacity(row_bytes * self.height as usize);
+
+        slice
+            .get_mapped_range()
+            .chunks(self.padded_bytes_per_row as usize)
+            .for_each(|row| pixels.extend_from_slice(&row[..row_bytes]));
+
+        self.buffer.unmap();
+
+        if self.bgra {
+            pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        RgbaImage::from_raw(self.width, self.height, pixels)
+            .ok_or_else(|| Error::Capture("the buffer was smaller than the texture".into()))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn read(self, _device: &wgpu::Device) -> Result<RgbaImage, Error> {
+        Err(Error::Capture(
+            "buffers can only be mapped asynchronously on wasm".into(),
+        ))
+    }
+}
+
+/// Read `texture` back as an image, blocking until the gpu has finished. See
+/// `PendingCapture` for which textures can be read.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<RgbaImage, Error> {
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Capture Command Encoder"),
+    });
+
+    let capture = PendingCapture::new(device, &mut encoder, texture)?;
+    queue.submit(Some(encoder.finish()));
+
+    capture.read(device)
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    /// Clear a texture to `color` and read it back.
+    fn clear_and_read(
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        color: wgpu::Color,
+    ) -> Option<Result<RgbaImage, Error>> {
+        let (device, queue) = test_utils::device()?;
+
+        // Rows of 30 pixels need padding to the copy alignment
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Test Texture"),
+            size: wgpu::Extent3d {
+                width: 30,
+                height: 20,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | usage,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&Default::default());
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Capture Test Clear"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        queue.submit(Some(encoder.finish()));
+
+        Some(read_texture(&device, &queue, &texture))
+    }
+
+    const COLOR: wgpu::Color = wgpu::Color {
+        r: 1.,
+        g: 0.5,
+        b: 0.,
+        a: 1.,
+    };
+
+    #[test]
+    fn reads_back_cleared_rgba() {
+        let Some(image) = clear_and_read(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureUsages::COPY_SRC,
+            COLOR,
+        ) else {
+            return;
+        };
+        let image = image.unwrap();
+
+        assert_eq!(image.dimensions(), (30, 20));
+        assert_eq!(image.get_pixel(15, 10).0, [255, 128, 0, 255]);
+        assert_eq!(image.get_pixel(29, 19).0, [255, 128, 0, 255]);
+    }
+
+    #[test]
+    fn reads_back_cleared_bgra_as_rgba() {
+        let Some(image) = clear_and_read(
+            wgpu::TextureFormat::Bgra8Unorm,
+            wgpu::TextureUsages::COPY_SRC,
+            COLOR,
+        ) else {
+            return;
+        };
+
+        assert_eq!(image.unwrap().get_pixel(15, 10).0, [255, 128, 0, 255]);
+    }
+
+    #[test]
+    fn rejects_textures_without_copy_src() {
+        let Some(image) = clear_and_read(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureUsages::empty(),
+            COLOR,
+        ) else {
+            return;
+        };
+
+        assert!(matches!(image, Err(Error::Capture(_))));
+    }
+}
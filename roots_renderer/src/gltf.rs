@@ -0,0 +1,477 @@
+//====================================================================
+
+use std::{collections::HashMap, path::Path};
+
+use roots_common::{
+    coords::{CoordinateConvention, GLTF_BASIS},
+    spatial::Transform,
+};
+
+use crate::{
+    model::{self, LoadedMesh, ModelVertex},
+    shared::SharedRenderResources,
+    texture::{LoadedTexture, Texture},
+    Error,
+};
+
+//====================================================================
+
+/// A single mesh primitive with its base color texture and factor.
+#[derive(Clone, Debug)]
+pub struct GltfPrimitive {
+    pub mesh: LoadedMesh,
+    pub texture: LoadedTexture,
+    pub base_color: [f32; 4],
+}
+
+/// A node of the imported scene. Nodes are stored parent-first, so `parent`
+/// always points to an earlier index in `GltfScene::nodes`.
+#[derive(Clone, Debug)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    pub transform: Transform,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+impl GltfNode {
+    #[inline]
+    pub fn meshes(&self) -> impl Iterator<Item = (LoadedMesh, LoadedTexture)> + '_ {
+        self.primitives
+            .iter()
+            .map(|primitive| (primitive.mesh.clone(), primitive.texture.clone()))
+    }
+
+    /// Base color of the first primitive (or white if the node has none).
+    #[inline]
+    pub fn base_color(&self) -> [f32; 4] {
+        self.primitives
+            .first()
+            .map(|primitive| primitive.base_color)
+            .unwrap_or([1., 1., 1., 1.])
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GltfScene {
+    pub nodes: Vec<GltfNode>,
+}
+
+impl GltfScene {
+    #[inline]
+    pub fn roots(&self) -> impl Iterator<Item = (usize, &GltfNode)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.parent.is_none())
+    }
+}
+
+//====================================================================
+
+/// Load the default scene (or first scene) of a .gltf/.glb file. External
+/// buffers and images are resolved relative to the file. Meshes and node
+/// transforms are converted into the active `CoordinateConvention`, so the front
+/// of the asset faces forward.
+pub fn load(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    path: impl AsRef<Path>,
+) -> Result<GltfScene, Error> {
+    let path = path.as_ref();
+    log::debug!("Loading gltf scene '{}'", path.display());
+
+    let (document, buffers, images) = ::gltf::import(path)?;
+    Ok(build_scene(
+        device, queue, shared, &document, &buffers, &images,
+    ))
+}
+
+/// Load a scene from memory. Only embedded (glb or data uri) buffers and images
+/// can be resolved this way.
+pub fn load_from_slice(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    bytes: &[u8],
+) -> Result<GltfScene, Error> {
+    let (document, buffers, images) = ::gltf::import_slice(bytes)?;
+    Ok(build_scene(
+        device, queue, shared, &document, &buffers, &images,
+    ))
+}
+
+/// Load every primitive of the default scene (or first scene) of a .gltf/.glb file as
+/// one list, with node transforms baked into the vertices, for drawing the whole
+/// scene as a single model. Converted like `load`.
+pub fn load_primitives(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    path: impl AsRef<Path>,
+) -> Result<Vec<GltfPrimitive>, Error> {
+    let path = path.as_ref();
+    log::debug!("Loading gltf primitives '{}'", path.display());
+
+    let (document, buffers, images) = ::gltf::import(path)?;
+    Ok(build_primitives(
+        device, queue, shared, &document, &buffers, &images,
+    ))
+}
+
+/// `load_primitives` from memory. Only embedded (glb or data uri) buffers and images
+/// can be resolved this way.
+pub fn load_primitives_from_slice(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    bytes: &[u8],
+) -> Result<Vec<GltfPrimitive>, Error> {
+    let (document, buffers, images) = ::gltf::import_slice(bytes)?;
+    Ok(build_primitives(
+        device, queue, shared, &document, &buffers, &images,
+    ))
+}
+
+//====================================================================
+
+struct SceneBuilder<'a> {
+    device: &'a wgpu::Device,
+    queue: &'a wgpu::Queue,
+    shared: &'a SharedRenderResources,
+
+    buffers: &'a [::gltf::buffer::Data],
+    images: &'a [::gltf::image::Data],
+
+    /// From glTF axes into the active convention.
+    conversion: glam::Mat3,
+
+    textures: HashMap<usize, LoadedTexture>,
+    blank: Option<LoadedTexture>,
+    meshes: HashMap<usize, Vec<GltfPrimitive>>,
+
+    nodes: Vec<GltfNode>,
+}
+
+fn default_scene(document: &::gltf::Document) -> Option<::gltf::Scene<'_>> {
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next());
+
+    if scene.is_none() {
+        log::warn!("Gltf document contains no scenes");
+    }
+
+    scene
+}
+
+fn build_scene(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    document: &::gltf::Document,
+    buffers: &[::gltf::buffer::Data],
+    images: &[::gltf::image::Data],
+) -> GltfScene {
+    let Some(scene) = default_scene(document) else {
+        return GltfScene::default();
+    };
+
+    let mut builder = SceneBuilder::new(device, queue, shared, buffers, images);
+    scene.nodes().for_each(|node| builder.add_node(&node, None));
+
+    log::trace!("Loaded gltf scene with {} nodes", builder.nodes.len());
+
+    GltfScene {
+        nodes: builder.nodes,
+    }
+}
+
+fn build_primitives(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    document: &::gltf::Document,
+    buffers: &[::gltf::buffer::Data],
+    images: &[::gltf::image::Data],
+) -> Vec<GltfPrimitive> {
+    let Some(scene) = default_scene(document) else {
+        return Vec::new();
+    };
+
+    let mut builder = SceneBuilder::new(device, queue, shared, buffers, images);
+    let mut primitives = Vec::new();
+
+    scene
+        .nodes()
+        .for_each(|node| builder.add_baked_node(&node, glam::Mat4::IDENTITY, &mut primitives));
+
+    log::trace!("Loaded {} gltf primitives", primitives.len());
+
+    primitives
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn new(
+        device: &'a wgpu::Device,
+        queue: &'a wgpu::Queue,
+        shared: &'a SharedRenderResources,
+        buffers: &'a [::gltf::buffer::Data],
+        images: &'a [::gltf::image::Data],
+    ) -> Self {
+        Self {
+            device,
+            queue,
+            shared,
+            buffers,
+            images,
+            conversion: CoordinateConvention::active().conversion_from_basis(GLTF_BASIS),
+            textures: HashMap::new(),
+            blank: None,
+            meshes: HashMap::new(),
+            nodes: Vec::new(),
+        }
+    }
+}
+
+impl SceneBuilder<'_> {
+    fn add_node(&mut self, node: &::gltf::Node, parent: Option<usize>) {
+        let matrix = glam::Mat4::from_mat3(self.conversion)
+            * glam::Mat4::from_cols_array_2d(&node.transform().matrix())
+            * glam::Mat4::from_mat3(self.conversion.transpose());
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+
+        let primitives = match node.mesh() {
+            Some(mesh) => self.load_mesh(&mesh),
+            None => Vec::new(),
+        };
+
+        let index = self.nodes.len();
+        self.nodes.push(GltfNode {
+            name: node.name().map(str::to_string),
+            parent,
+            transform: Transform::from_scale_rotation_translation(scale, rotation, translation),
+            primitives,
+        });
+
+        node.children()
+            .for_each(|child| self.add_node(&child, Some(index)));
+    }
+
+    /// Load the primitives of `node` and its children with their transforms, in glTF
+    /// axes, baked in. Meshes used by several nodes are loaded once per node.
+    fn add_baked_node(
+        &mut self,
+        node: &::gltf::Node,
+        parent: glam::Mat4,
+        primitives: &mut Vec<GltfPrimitive>,
+    ) {
+        let transform = parent * glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+
+        if let Some(mesh) = node.mesh() {
+            primitives.extend(
+                mesh.primitives()
+                    .filter_map(|primitive| self.load_primitive(&primitive, Some(transform))),
+            );
+        }
+
+        node.children()
+            .for_each(|child| self.add_baked_node(&child, transform, primitives));
+    }
+
+    fn load_mesh(&mut self, mesh: &::gltf::Mesh) -> Vec<GltfPrimitive> {
+        if let Some(primitives) = self.meshes.get(&mesh.index()) {
+            return primitives.clone();
+        }
+
+        let primitives = mesh
+            .primitives()
+            .filter_map(|primitive| self.load_primitive(&primitive, None))
+            .collect::<Vec<_>>();
+
+        self.meshes.insert(mesh.index(), primitives.clone());
+        primitives
+    }
+
+    /// Load a primitive, first transforming it by `bake` if given.
+    fn load_primitive(
+        &mut self,
+        primitive: &::gltf::Primitive,
+        bake: Option<glam::Mat4>,
+    ) -> Option<GltfPrimitive> {
+        if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+            log::warn!(
+                "Skipping gltf primitive with unsupported mode {:?}",
+                primitive.mode()
+            );
+            return None;
+        }
+
+        let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+
+        let positions = match reader.read_positions() {
+            Some(positions) => positions.collect::<Vec<_>>(),
+            None => {
+                log::warn!("Skipping gltf primitive without positions");
+                return None;
+            }
+        };
+
+        let mut normals = reader
+            .read_normals()
+            .map(|normals| normals.map(glam::Vec3::from));
+        let has_normals = normals.is_some();
+        let mut uvs = reader
+            .read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().map(glam::Vec2::from));
+        // Tangents are only meaningful with the normals they were authored against
+        let mut tangents = reader
+            .read_tangents()
+            .filter(|_| has_normals)
+            .map(|tangents| tangents.map(glam::Vec4::from));
+        let has_tangents = tangents.is_some();
+
+        let mut vertices = positions
+            .into_iter()
+            .map(|pos| ModelVertex {
+                pos: pos.into(),
+                uv: uvs
+                    .as_mut()
+                    .and_then(|uvs| uvs.next())
+                    .unwrap_or(glam::Vec2::ZERO),
+                normal: normals
+                    .as_mut()
+                    .and_then(|normals| normals.next())
+                    .unwrap_or(glam::Vec3::Y),
+                tangent: tangents
+                    .as_mut()
+                    .and_then(|tangents| tangents.next())
+                    .unwrap_or(glam::Vec4::ZERO),
+            })
+            .collect::<Vec<_>>();
+
+        let mut indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+            None => (0..vertices.len() as u32).collect(),
+        };
+
+        if let Some(bake) = bake {
+            model::transform_mesh(&mut vertices, &mut indices, bake);
+        }
+
+        model::convert_mesh(&mut vertices, self.conversion);
+
+        if !has_normals {
+            model::compute_normals(
+                &mut vertices,
+                &indices,
+                CoordinateConvention::active().is_right_handed(),
+            );
+        }
+
+        if !has_tangents {
+            model::compute_tangents(&mut vertices, &indices);
+        }
+
+        let material = primitive.material();
+        let pbr = material.pbr_metallic_roughness();
+
+        let texture = match pbr.base_color_texture() {
+            Some(info) => self.load_texture(&info.texture()),
+            None => self.blank(),
+        };
+
+        Some(GltfPrimitive {
+            mesh: LoadedMesh::load_from_data(self.device, &vertices, &indices),
+            texture,
+            base_color: pbr.base_color_factor(),
+        })
+    }
+
+    fn load_texture(&mut self, texture: &::gltf::Texture) -> LoadedTexture {
+        if let Some(loaded) = self.textures.get(&texture.index()) {
+            return loaded.clone();
+        }
+
+        let data = &self.images[texture.source().index()];
+
+        let image = match to_dynamic_image(data) {
+            Some(image) => image,
+            None => {
+                log::warn!(
+                    "Unsupported gltf image format {:?} - using blank texture",
+                    data.format
+                );
+                return self.blank();
+            }
+        };
+
+        let sampler = texture.sampler();
+        let sampler = wgpu::SamplerDescriptor {
+            address_mode_u: to_address_mode(sampler.wrap_s()),
+            address_mode_v: to_address_mode(sampler.wrap_t()),
+            mag_filter: match sampler.mag_filter() {
+                Some(::gltf::texture::MagFilter::Nearest) => wgpu::FilterMode::Nearest,
+                _ => wgpu::FilterMode::Linear,
+            },
+            min_filter: match sampler.min_filter() {
+                Some(::gltf::texture::MinFilter::Nearest)
+                | Some(::gltf::texture::MinFilter::NearestMipmapNearest)
+                | Some(::gltf::texture::MinFilter::NearestMipmapLinear) => {
+                    wgpu::FilterMode::Nearest
+                }
+                _ => wgpu::FilterMode::Linear,
+            },
+            ..Default::default()
+        };
+
+        let loaded = LoadedTexture::load_texture(
+            self.device,
+            self.shared,
+            Texture::from_image(
+                self.device,
+                self.queue,
+                &image,
+                texture.name().or(Some("Gltf Texture")),
+                Some(&sampler),
+            ),
+        );
+
+        self.textures.insert(texture.index(), loaded.clone());
+        loaded
+    }
+
+    fn blank(&mut self) -> LoadedTexture {
+        self.blank
+            .get_or_insert_with(|| LoadedTexture::load_blank(self.device, self.queue, self.shared))
+            .clone()
+    }
+}
+
+//--------------------------------------------------
+
+fn to_dynamic_image(data: &::gltf::image::Data) -> Option<image::DynamicImage> {
+    use ::gltf::image::Format;
+
+    let (width, height, pixels) = (data.width, data.height, data.pixels.clone());
+
+    match data.format {
+        Format::R8 => image::GrayImage::from_raw(width, height, pixels).map(Into::into),
+        Format::R8G8 => image::GrayAlphaImage::from_raw(width, height, pixels).map(Into::into),
+        Format::R8G8B8 => image::RgbImage::from_raw(width, height, pixels).map(Into::into),
+        Format::R8G8B8A8 => image::RgbaImage::from_raw(width, height, pixels).map(Into::into),
+        _ => None,
+    }
+}
+
+#[inline]
+fn to_address_mode(mode: ::gltf::texture::WrappingMode) -> wgpu::AddressMode {
+    match mode {
+        ::gltf::texture::WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        ::gltf::texture::WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+        ::gltf::texture::WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+    }
+}
+
+//====================================================================
@@ -0,0 +1,449 @@
+//====================================================================
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::tools::{self, BgEntryType};
+
+//====================================================================
+
+/// Uniform buffer with a camera's view projection, visible to every stage.
+pub const CAMERA: &str = "camera";
+/// Filterable 2D texture at binding 0 and its sampler at binding 1.
+pub const TEXTURE: &str = "texture2d";
+/// Filterable 2D array texture at binding 0 and its sampler at binding 1.
+pub const TEXTURE_ARRAY: &str = "texture_array";
+/// Light globals uniform at binding 0 and the light instance storage buffer at binding 1.
+pub const LIGHTING: &str = "lighting";
+/// Light globals uniform at binding 0 and a fixed size uniform light array at binding 1,
+/// for devices without storage buffers. See `LightingPath::UniformArray`.
+pub const LIGHTING_UNIFORM: &str = "lighting_uniform";
+/// Glyph atlas texture at binding 0 and its sampler at binding 1.
+pub const TEXT_ATLAS: &str = "text_atlas";
+/// A single uniform read by the vertex shader, such as the position of some text.
+pub const UI_UNIFORM: &str = "ui_uniform";
+
+//====================================================================
+
+/// A shader binding that doesn't match the layout a pipeline uses for its group.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error(
+    "Shader '{shader}' @group({group}) @binding({binding}) '{variable}' doesn't match \
+    bind group layout '{layout}': {problem}"
+)]
+pub struct LayoutMismatch {
+    pub shader: String,
+    pub layout: String,
+    pub group: u32,
+    pub binding: u32,
+    pub variable: String,
+    pub problem: String,
+}
+
+#[derive(Debug, Clone)]
+struct RegisteredLayout {
+    entries: Vec<wgpu::BindGroupLayoutEntry>,
+    layout: Arc<wgpu::BindGroupLayout>,
+}
+
+/// Bind group layouts by name, so every pipeline using a layout shares a single
+/// definition of it. Owned by `SharedRenderResources`.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutRegistry {
+    layouts: HashMap<&'static str, RegisteredLayout>,
+}
+
+impl LayoutRegistry {
+    /// A registry with every layout named in this module.
+    pub fn new(device: &wgpu::Device) -> Self {
+        use wgpu::ShaderStages as Stages;
+
+        let mut registry = Self::default();
+
+        registry.register(
+            device,
+            CAMERA,
+            &[tools::bgl_entry(
+                BgEntryType::Uniform,
+                0,
+                Stages::VERTEX_FRAGMENT,
+            )],
+        );
+
+        [TEXTURE, TEXT_ATLAS].into_iter().for_each(|name| {
+            registry.register(
+                device,
+                name,
+                &[
+                    tools::bgl_entry(BgEntryType::Texture, 0, Stages::FRAGMENT),
+                    tools::bgl_entry(BgEntryType::Sampler, 1, Stages::FRAGMENT),
+                ],
+            )
+        });
+
+        registry.register(
+            device,
+            TEXTURE_ARRAY,
+            &[
+                tools::bgl_entry(BgEntryType::TextureArray, 0, Stages::FRAGMENT),
+                tools::bgl_entry(BgEntryType::Sampler, 1, Stages::FRAGMENT),
+            ],
+        );
+
+        registry.register(
+            device,
+            LIGHTING,
+            &[
+                tools::bgl_entry(BgEntryType::Uniform, 0, Stages::FRAGMENT),
+                tools::bgl_entry(BgEntryType::Storage, 1, Stages::FRAGMENT),
+            ],
+        );
+
+        registry.register(
+            device,
+            LIGHTING_UNIFORM,
+            &[
+                tools::bgl_entry(BgEntryType::Uniform, 0, Stages::FRAGMENT),
+                tools::bgl_entry(BgEntryType::Uniform, 1, Stages::FRAGMENT),
+            ],
+        );
+
+        registry.register(
+            device,
+            UI_UNIFORM,
+            &[tools::bgl_entry(BgEntryType::Uniform, 0, Stages::VERTEX)],
+        );
+
+        registry
+    }
+
+    /// Create and store a layout. Replaces any layout already registered as `name`.
+    pub fn register(
+        &mut self,
+        device: &wgpu::Device,
+        name: &'static str,
+        entries: &[wgpu::BindGroupLayoutEntry],
+    ) {
+        log::trace!("Registering bind group layout '{}'", name);
+
+        if self.layouts.contains_key(name) {
+            log::warn!("Replacing bind group layout '{}'", name);
+        }
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&format!("{} Bind Group Layout", name)),
+            entries,
+        });
+
+        self.layouts.insert(
+            name,
+            RegisteredLayout {
+                entries: entries.to_vec(),
+                layout: Arc::new(layout),
+            },
+        );
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&wgpu::BindGroupLayout> {
+        self.layouts.get(name).map(|registered| &*registered.layout)
+    }
+
+    /// A handle to the layout, for types that create bind groups with it later.
+    #[inline]
+    pub fn clone_layout(&self, name: &str) -> Option<Arc<wgpu::BindGroupLayout>> {
+        self.layouts
+            .get(name)
+            .map(|registered| registered.layout.clone())
+    }
+
+    #[inline]
+    pub fn entries(&self, name: &str) -> Option<&[wgpu::BindGroupLayoutEntry]> {
+        self.layouts
+            .get(name)
+            .map(|registered| registered.entries.as_slice())
+    }
+
+    /// The layouts registered as `names`, in order.
+    ///
+    /// # Panics
+    /// If a name isn't registered.
+    pub fn layouts(&self, names: &[&str]) -> Vec<&wgpu::BindGroupLayout> {
+        names
+            .iter()
+            .map(|name| {
+                self.get(name)
+                    .unwrap_or_else(|| panic!("No bind group layout registered as '{}'", name))
+            })
+            .collect()
+    }
+
+    /// Check every binding `shader` declares in the groups listed in `groups`, where
+    /// `groups[n]` is the name of the layout used for group `n`. Groups past the end of
+    /// `groups` aren't checked. Shaders that don't parse are left to pipeline creation
+    /// to report.
+    pub fn validate_shader(
+        &self,
+        label: &str,
+        shader: &str,
+        groups: &[&str],
+    ) -> Result<(), LayoutMismatch> {
+        let module = match naga::front::wgsl::parse_str(shader) {
+            Ok(module) => module,
+            Err(_) => return Ok(()),
+        };
+
+        let result = module
+            .global_variables
+            .iter()
+            .filter_map(|(_, variable)| Some((variable.binding.as_ref()?, variable)))
+            .filter(|(binding, _)| (binding.group as usize) < groups.len())
+            .try_for_each(|(binding, variable)| {
+                let layout = groups[binding.group as usize];
+
+                let mismatch = |problem: String| LayoutMismatch {
+                    shader: label.into(),
+                    layout: layout.into(),
+                    group: binding.group,
+                    binding: binding.binding,
+                    variable: variable.name.clone().unwrap_or_default(),
+                    problem,
+                };
+
+                let entries = self
+                    .entries(layout)
+                    .ok_or_else(|| mismatch("the layout isn't registered".into()))?;
+
+                let entry = entries
+                    .iter()
+                    .find(|entry| entry.binding == binding.binding)
+                    .ok_or_else(|| mismatch("the layout has no entry for this binding".into()))?;
+
+                let declared = declared_binding(&module, variable);
+
+                match compatible(&declared, &entry.ty) {
+                    true => Ok(()),
+                    false => Err(mismatch(format!(
+                        "the shader declares {} but the layout has {}",
+                        declared.describe(),
+                        describe_layout(&entry.ty)
+                    ))),
+                }
+            });
+
+        result
+    }
+
+    /// In debug builds, panic with the first binding of `shader` that doesn't match
+    /// its layout. Does nothing in release builds.
+    #[inline]
+    pub fn debug_validate_shader(&self, label: &str, shader: &str, groups: &[&str]) {
+        if cfg!(debug_assertions) {
+            if let Err(mismatch) = self.validate_shader(label, shader, groups) {
+                panic!("{}", mismatch);
+            }
+        }
+    }
+}
+
+//====================================================================
+
+/// The kind of resource a shader declares, in terms comparable to a layout entry.
+enum DeclaredBinding {
+    Uniform,
+    Storage {
+        writes: bool,
+    },
+    Texture {
+        view_dimension: wgpu::TextureViewDimension,
+        kind: Option<naga::ScalarKind>,
+        multisampled: bool,
+    },
+    StorageTexture {
+        view_dimension: wgpu::TextureViewDimension,
+    },
+    Sampler {
+        comparison: bool,
+    },
+    Other,
+}
+
+impl DeclaredBinding {
+    fn describe(&self) -> String {
+        match self {
+            DeclaredBinding::Uniform => "a uniform buffer".into(),
+            DeclaredBinding::Storage { writes: true } => "a read write storage buffer".into(),
+            DeclaredBinding::Storage { writes: false } => "a read only storage buffer".into(),
+            DeclaredBinding::Texture {
+                view_dimension,
+                kind,
+                multisampled,
+            } => format!(
+                "a {:?} {} texture{}",
+                view_dimension,
+                match kind {
+                    Some(kind) => format!("{:?}", kind).to_lowercase(),
+                    None => "depth".into(),
+                },
+                match multisampled {
+                    true => " (multisampled)",
+                    false => "",
+                }
+            ),
+            DeclaredBinding::StorageTexture { view_dimension } => {
+                format!("a {:?} storage texture", view_dimension)
+            }
+            DeclaredBinding::Sampler { comparison: true } => "a comparison sampler".into(),
+            DeclaredBinding::Sampler { comparison: false } => "a sampler".into(),
+            DeclaredBinding::Other => "an unsupported binding".into(),
+        }
+    }
+}
+
+fn declared_binding(module: &naga::Module, variable: &naga::GlobalVariable) -> DeclaredBinding {
+    let view_dimension = |dim: naga::ImageDimension, arrayed: bool| match (dim, arrayed) {
+        (naga::ImageDimension::D1, _) => wgpu::TextureViewDimension::D1,
+        (naga::ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+        (naga::ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+        (naga::ImageDimension::D3, _) => wgpu::TextureViewDimension::D3,
+        (naga::ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+        (naga::ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+    };
+
+    match variable.space {
+        naga::AddressSpace::Uniform => DeclaredBinding::Uniform,
+        naga::AddressSpace::Storage { access } => DeclaredBinding::Storage {
+            writes: access.contains(naga::StorageAccess::STORE),
+        },
+
+        naga::AddressSpace::Handle => match module.types[variable.ty].inner {
+            naga::TypeInner::Image {
+                dim,
+                arrayed,
+                class,
+            } => match class {
+                naga::ImageClass::Sampled { kind, multi } => DeclaredBinding::Texture {
+                    view_dimension: view_dimension(dim, arrayed),
+                    kind: Some(kind),
+                    multisampled: multi,
+                },
+                naga::ImageClass::Depth { multi } => DeclaredBinding::Texture {
+                    view_dimension: view_dimension(dim, arrayed),
+                    kind: None,
+                    multisampled: multi,
+                },
+                naga::ImageClass::Storage { .. } => DeclaredBinding::StorageTexture {
+                    view_dimension: view_dimension(dim, arrayed),
+                },
+            },
+            naga::TypeInner::Sampler { comparison } => DeclaredBinding::Sampler { comparison },
+            _ => DeclaredBinding::Other,
+        },
+
+        _ => DeclaredBinding::Other,
+    }
+}
+
+fn compatible(declared: &DeclaredBinding, layout: &wgpu::BindingType) -> bool {
+    use wgpu::{BindingType, BufferBindingType, SamplerBindingType, TextureSampleType};
+
+    match (declared, layout) {
+        (
+            DeclaredBinding::Uniform,
+            BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                ..
+            },
+        ) => true,
+
+        (
+            DeclaredBinding::Storage { writes },
+            BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                ..
+            },
+        ) => !(*writes && *read_only),
+
+        (
+            DeclaredBinding::Texture {
+                view_dimension,
+                kind,
+                multisampled,
+            },
+            BindingType::Texture {
+                sample_type,
+                view_dimension: layout_dimension,
+                multisampled: layout_multisampled,
+            },
+        ) => {
+            let sample_type_matches = matches!(
+                (kind, sample_type),
+                (
+                    Some(naga::ScalarKind::Float),
+                    TextureSampleType::Float { .. }
+                ) | (Some(naga::ScalarKind::Sint), TextureSampleType::Sint)
+                    | (Some(naga::ScalarKind::Uint), TextureSampleType::Uint)
+                    | (None, TextureSampleType::Depth)
+            );
+
+            sample_type_matches
+                && view_dimension == layout_dimension
+                && multisampled == layout_multisampled
+        }
+
+        (
+            DeclaredBinding::StorageTexture { view_dimension },
+            BindingType::StorageTexture {
+                view_dimension: layout_dimension,
+                ..
+            },
+        ) => view_dimension == layout_dimension,
+
+        (DeclaredBinding::Sampler { comparison }, BindingType::Sampler(sampler)) => {
+            *comparison == (*sampler == SamplerBindingType::Comparison)
+        }
+
+        _ => false,
+    }
+}
+
+fn describe_layout(layout: &wgpu::BindingType) -> String {
+    match layout {
+        wgpu::BindingType::Buffer { ty, .. } => match ty {
+            wgpu::BufferBindingType::Uniform => "a uniform buffer".into(),
+            wgpu::BufferBindingType::Storage { read_only: true } => {
+                "a read only storage buffer".into()
+            }
+            wgpu::BufferBindingType::Storage { read_only: false } => {
+                "a read write storage buffer".into()
+            }
+        },
+        wgpu::BindingType::Texture {
+            sample_type,
+            view_dimension,
+            multisampled,
+        } => format!(
+            "a {:?} {} texture{}",
+            view_dimension,
+            match sample_type {
+                wgpu::TextureSampleType::Float { .. } => "float",
+                wgpu::TextureSampleType::Sint => "sint",
+                wgpu::TextureSampleType::Uint => "uint",
+                wgpu::TextureSampleType::Depth => "depth",
+            },
+            match multisampled {
+                true => " (multisampled)",
+                false => "",
+            }
+        ),
+        wgpu::BindingType::StorageTexture { view_dimension, .. } => {
+            format!("a {:?} storage texture", view_dimension)
+        }
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison) => {
+            "a comparison sampler".into()
+        }
+        wgpu::BindingType::Sampler(_) => "a sampler".into(),
+        wgpu::BindingType::AccelerationStructure => "an acceleration structure".into(),
+    }
+}
+
+//====================================================================
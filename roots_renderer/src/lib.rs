@@ -1,20 +1,49 @@
 //====================================================================
 
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
 
 use roots_common::Size;
+use shared::DepthConvention;
 use wgpu::SurfaceTarget;
 
 pub mod camera;
+pub mod capabilities;
+pub mod capture;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod layouts;
 pub mod lighting;
+pub mod memory;
 pub mod model;
+#[cfg(feature = "obj")]
+pub mod obj;
+pub mod offscreen;
+pub mod pipeline_cache;
 pub mod shared;
+pub mod splash;
+pub mod streaming;
+pub mod target_pool;
+#[cfg(test)]
+mod test_utils;
 pub mod texture;
 pub mod tools;
+pub mod uploads;
+pub mod uv_projection;
+pub mod watchdog;
 
 //====================================================================
 
-pub struct Device(wgpu::Device);
+/// Shared handle to the device. Clones refer to the same device, so on native a
+/// clone can be sent to worker threads to create resources there.
+#[derive(Clone)]
+pub struct Device(Arc<wgpu::Device>);
 impl Deref for Device {
     type Target = wgpu::Device;
 
@@ -24,7 +53,9 @@ impl Deref for Device {
     }
 }
 
-pub struct Queue(wgpu::Queue);
+/// Shared handle to the queue. See `Device`.
+#[derive(Clone)]
+pub struct Queue(Arc<wgpu::Queue>);
 impl Deref for Queue {
     type Target = wgpu::Queue;
 
@@ -34,6 +65,20 @@ impl Deref for Queue {
     }
 }
 
+impl From<wgpu::Device> for Device {
+    #[inline]
+    fn from(value: wgpu::Device) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
+impl From<wgpu::Queue> for Queue {
+    #[inline]
+    fn from(value: wgpu::Queue) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
 pub struct Surface<'a>(wgpu::Surface<'a>);
 impl<'a> Deref for Surface<'a> {
     type Target = wgpu::Surface<'a>;
@@ -54,6 +99,20 @@ impl Deref for SurfaceConfig {
     }
 }
 
+impl<'a> From<wgpu::Surface<'a>> for Surface<'a> {
+    #[inline]
+    fn from(value: wgpu::Surface<'a>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<wgpu::SurfaceConfiguration> for SurfaceConfig {
+    #[inline]
+    fn from(value: wgpu::SurfaceConfiguration) -> Self {
+        Self(value)
+    }
+}
+
 impl DerefMut for SurfaceConfig {
     #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
@@ -61,32 +120,248 @@ impl DerefMut for SurfaceConfig {
     }
 }
 
+impl SurfaceConfig {
+    /// Format of the surface, which pipelines drawing to it should target.
+    #[inline]
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.0.format
+    }
+}
+
 //====================================================================
 
+/// Features requested when the adapter supports them. Pipelines check
+/// `Device::features` before using them.
+pub const OPTIONAL_FEATURES: wgpu::Features = wgpu::Features::MULTI_DRAW_INDIRECT
+    .union(wgpu::Features::INDIRECT_FIRST_INSTANCE)
+    .union(wgpu::Features::PUSH_CONSTANTS)
+    .union(wgpu::Features::PIPELINE_CACHE);
+
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_BACKENDS: wgpu::Backends = wgpu::Backends::PRIMARY;
+#[cfg(target_arch = "wasm32")]
+const DEFAULT_BACKENDS: wgpu::Backends = wgpu::Backends::GL;
+
+/// Options for creating a `RenderCore`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderCoreConfig {
+    /// Requested presentation mode, such as `AutoVsync` or `Mailbox`. Falls back to the
+    /// first mode the surface supports if it isn't available. Kept on the
+    /// `SurfaceConfig`, so the surface is reconfigured with it after a resize.
+    pub present_mode: wgpu::PresentMode,
+    /// Surface formats in order of preference, such as `Rgba16Float` for HDR or a
+    /// non-srgb format for manual gamma. The first supported one is used, otherwise
+    /// the first srgb format the surface supports.
+    pub preferred_formats: Vec<wgpu::TextureFormat>,
+    /// Backends the adapter may be chosen from, such as forcing Vulkan over GL. Defaults
+    /// to the primary backends natively and GL on wasm when `None`.
+    pub backends: Option<wgpu::Backends>,
+    /// Which adapter to prefer on systems with several, such as `HighPerformance` for
+    /// the discrete gpu of a laptop.
+    pub power_preference: wgpu::PowerPreference,
+    /// Directory a `PersistentPipelineCache` is loaded from and saved to, where the
+    /// device supports one.
+    pub pipeline_cache_dir: Option<PathBuf>,
+    /// Frames the gpu may queue ahead of presentation, clamped to 1 to 3. Lower cuts
+    /// input latency, higher smooths out uneven frame times. Kept on the
+    /// `SurfaceConfig`, so it survives reconfiguring after a resize.
+    pub max_frame_latency: u32,
+}
+
+impl Default for RenderCoreConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            present_mode: wgpu::PresentMode::AutoNoVsync,
+            preferred_formats: Vec::new(),
+            backends: None,
+            power_preference: wgpu::PowerPreference::default(),
+            pipeline_cache_dir: None,
+            max_frame_latency: 2,
+        }
+    }
+}
+
+impl RenderCoreConfig {
+    #[inline]
+    pub fn with_present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    /// `AutoVsync` or `AutoNoVsync`, which are supported by every surface.
+    #[inline]
+    pub fn with_vsync(self, vsync: bool) -> Self {
+        self.with_present_mode(match vsync {
+            true => wgpu::PresentMode::AutoVsync,
+            false => wgpu::PresentMode::AutoNoVsync,
+        })
+    }
+
+    #[inline]
+    pub fn with_preferred_formats(
+        mut self,
+        preferred_formats: impl IntoIterator<Item = wgpu::TextureFormat>,
+    ) -> Self {
+        self.preferred_formats = preferred_formats.into_iter().collect();
+        self
+    }
+
+    #[inline]
+    pub fn with_backends(mut self, backends: wgpu::Backends) -> Self {
+        self.backends = Some(backends);
+        self
+    }
+
+    #[inline]
+    pub fn with_power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    #[inline]
+    pub fn with_pipeline_cache_dir(mut self, pipeline_cache_dir: impl Into<PathBuf>) -> Self {
+        self.pipeline_cache_dir = Some(pipeline_cache_dir.into());
+        self
+    }
+
+    #[inline]
+    pub fn with_max_frame_latency(mut self, max_frame_latency: u32) -> Self {
+        self.max_frame_latency = max_frame_latency;
+        self
+    }
+
+    #[inline]
+    fn backends(&self) -> wgpu::Backends {
+        self.backends.unwrap_or(DEFAULT_BACKENDS)
+    }
+
+    /// The first of `preferred_formats` the surface supports, otherwise its first srgb
+    /// format, otherwise its first format.
+    fn choose_surface_format(
+        &self,
+        surface_capabilities: &wgpu::SurfaceCapabilities,
+    ) -> wgpu::TextureFormat {
+        let preferred = self
+            .preferred_formats
+            .iter()
+            .find(|format| surface_capabilities.formats.contains(format))
+            .copied();
+
+        if preferred.is_none() && !self.preferred_formats.is_empty() {
+            log::warn!(
+                "None of the preferred surface formats {:?} are supported. Supported formats = {:?}",
+                self.preferred_formats,
+                surface_capabilities.formats
+            );
+        }
+
+        preferred
+            .or_else(|| {
+                surface_capabilities
+                    .formats
+                    .iter()
+                    .find(|format| format.is_srgb())
+                    .copied()
+            })
+            .unwrap_or(surface_capabilities.formats[0])
+    }
+
+    /// `present_mode` if the surface supports it, otherwise the first supported mode.
+    fn choose_present_mode(
+        &self,
+        surface_capabilities: &wgpu::SurfaceCapabilities,
+    ) -> wgpu::PresentMode {
+        let supported = matches!(
+            self.present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync
+        ) || surface_capabilities
+            .present_modes
+            .contains(&self.present_mode);
+
+        match (supported, surface_capabilities.present_modes.first()) {
+            (true, _) | (false, None) => self.present_mode,
+            (false, Some(fallback)) => {
+                log::warn!(
+                    "Present mode {:?} isn't supported by the surface, using {:?}. Supported modes = {:?}",
+                    self.present_mode,
+                    fallback,
+                    surface_capabilities.present_modes
+                );
+                *fallback
+            }
+        }
+    }
+}
+
 pub struct RenderCore<'a> {
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub surface: wgpu::Surface<'a>,
     pub config: wgpu::SurfaceConfiguration,
+    /// Probed from the adapter, which isn't kept past creation.
+    pub capabilities: capabilities::CapabilityReport,
+    /// Loaded from `RenderCoreConfig::pipeline_cache_dir`, if set and supported.
+    pub pipeline_cache: Option<pipeline_cache::PersistentPipelineCache>,
 }
 
-#[derive(thiserror::Error)]
-pub enum CreateRendererError {
-    UnableToRequestAdapter,
-}
+/// Errors returned by the fallible parts of the renderer.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Unable to create surface: {0}")]
+    SurfaceCreation(String),
 
-impl std::fmt::Debug for CreateRendererError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            CreateRendererError::UnableToRequestAdapter => f.write_str("Unable to request adapter"),
-        }
-    }
+    #[error("Unable to request adapter")]
+    AdapterRequest,
+
+    /// The wgpu error isn't `Send` on wasm so only its message is kept.
+    #[error("Unable to request device: {0}")]
+    DeviceRequest(String),
+
+    #[error("Unable to get surface texture: {0}")]
+    Surface(#[from] wgpu::SurfaceError),
+
+    #[error("Unable to decode texture: {0}")]
+    TextureDecode(#[from] image::ImageError),
+
+    #[error("{0}")]
+    TextureArray(#[from] texture::TextureArrayError),
+
+    #[error("Mip level {level} of streamed texture '{label}' is missing or the wrong size")]
+    MipLevel { label: String, level: u32 },
+
+    #[error("Unable to compile '{label}': {message}")]
+    ShaderCompilation { label: String, message: String },
+
+    #[error("Out of GPU memory")]
+    OutOfMemory,
+
+    #[error("Internal GPU error: {0}")]
+    Internal(String),
+
+    #[error("Unable to capture frame: {0}")]
+    Capture(String),
+
+    #[cfg(feature = "gltf")]
+    #[error("Unable to load gltf: {0}")]
+    Gltf(#[from] ::gltf::Error),
+
+    #[cfg(feature = "obj")]
+    #[error("Unable to load obj: {0}")]
+    Obj(String),
 }
 
-impl std::fmt::Display for CreateRendererError {
-    #[inline]
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(self, f)
+impl Error {
+    /// Convert an error captured from a wgpu error scope.
+    pub fn from_wgpu(label: &str, error: wgpu::Error) -> Self {
+        match error {
+            wgpu::Error::OutOfMemory { .. } => Self::OutOfMemory,
+            wgpu::Error::Validation { description, .. } => Self::ShaderCompilation {
+                label: label.to_string(),
+                message: description,
+            },
+            wgpu::Error::Internal { description, .. } => Self::Internal(description),
+        }
     }
 }
 
@@ -94,7 +369,8 @@ impl<'a> RenderCore<'a> {
     pub async fn new(
         window: impl Into<SurfaceTarget<'a>>,
         window_size: Size<u32>,
-    ) -> anyhow::Result<Self> {
+        core_config: &RenderCoreConfig,
+    ) -> Result<Self, Error> {
         log::info!("Creating core wgpu renderer components.");
         log::debug!("Window inner size = {:?}", window_size);
 
@@ -107,67 +383,108 @@ impl<'a> RenderCore<'a> {
         };
 
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            #[cfg(not(target_arch = "wasm32"))]
-            backends: wgpu::Backends::PRIMARY,
-            #[cfg(target_arch = "wasm32")]
-            backends: wgpu::Backends::GL,
+            backends: core_config.backends(),
             ..Default::default()
         });
 
-        let surface = instance.create_surface(window)?;
+        let surface = instance
+            .create_surface(window)
+            .map_err(|e| Error::SurfaceCreation(e.to_string()))?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
+        let request_adapter = |force_fallback_adapter| {
+            instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: core_config.power_preference,
+                force_fallback_adapter,
                 compatible_surface: Some(&surface),
             })
-            .await
-            .ok_or_else(|| CreateRendererError::UnableToRequestAdapter)?;
+        };
 
-        log::debug!("Chosen device adapter: {:#?}", adapter.get_info());
+        let adapter = match request_adapter(false).await {
+            Some(adapter) => adapter,
+            None => {
+                log::warn!(
+                    "No adapter found with power preference {:?}, trying the fallback adapter",
+                    core_config.power_preference
+                );
+                request_adapter(true).await.ok_or(Error::AdapterRequest)?
+            }
+        };
 
-        let device_future = adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                #[cfg(target_arch = "wasm32")]
-                required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
-                ..Default::default()
-            },
-            None,
+        let adapter_info = adapter.get_info();
+        log::info!(
+            "Using {} on the {:?} backend",
+            adapter_info.name,
+            adapter_info.backend
         );
-
-        // TODO - This WGPU error cannot be returned on wasm - Try to return custom type
-        #[cfg(target_arch = "wasm32")]
-        let (device, queue) = match device_future.await {
-            Ok(result) => result,
-            Err(e) => panic!("{}", e),
-        };
+        log::debug!("Chosen device adapter: {:#?}", adapter_info);
 
         #[cfg(not(target_arch = "wasm32"))]
-        let (device, queue) = device_future.await?;
+        let default_limits = wgpu::Limits::default();
+        #[cfg(target_arch = "wasm32")]
+        let default_limits = wgpu::Limits::downlevel_webgl2_defaults();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features: adapter.features() & OPTIONAL_FEATURES,
+                    required_limits: wgpu::Limits {
+                        // Zero unless the adapter supports push constants
+                        max_push_constant_size: adapter.limits().max_push_constant_size,
+                        ..default_limits
+                    },
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .map_err(|e| Error::DeviceRequest(e.to_string()))?;
 
         let surface_capabilities = surface.get_capabilities(&adapter);
 
-        let surface_format = surface_capabilities
-            .formats
-            .iter()
-            .find(|format| format.is_srgb())
-            .copied()
-            .unwrap_or(surface_capabilities.formats[0]);
+        let surface_format = core_config.choose_surface_format(&surface_capabilities);
+        log::debug!("Chosen surface format: {:?}", surface_format);
+
+        // Frames can only be captured from surfaces that allow copying from
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (surface_capabilities.usages & wgpu::TextureUsages::COPY_SRC);
+
+        let max_frame_latency = core_config.max_frame_latency.clamp(1, 3);
+        match max_frame_latency == core_config.max_frame_latency {
+            true => log::debug!("Maximum frame latency: {}", max_frame_latency),
+            false => log::warn!(
+                "Maximum frame latency of {} is out of range, using {}",
+                core_config.max_frame_latency,
+                max_frame_latency
+            ),
+        }
 
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: window_size.width,
             height: window_size.height,
-            present_mode: wgpu::PresentMode::AutoNoVsync,
-            desired_maximum_frame_latency: 2,
+            present_mode: core_config.choose_present_mode(&surface_capabilities),
+            desired_maximum_frame_latency: max_frame_latency,
             alpha_mode: surface_capabilities.alpha_modes[0],
             view_formats: vec![],
         };
 
         surface.configure(&device, &config);
 
+        let capabilities = capabilities::CapabilityReport::from_adapter(
+            &adapter,
+            &device,
+            &surface_capabilities,
+            surface_format,
+        );
+
+        let pipeline_cache = core_config
+            .pipeline_cache_dir
+            .as_ref()
+            .and_then(|directory| {
+                pipeline_cache::PersistentPipelineCache::load(&device, &adapter_info, directory)
+            });
+
         log::info!("Successfully created core wgpu components.");
 
         Ok(Self {
@@ -175,6 +492,8 @@ impl<'a> RenderCore<'a> {
             queue,
             surface,
             config,
+            capabilities,
+            pipeline_cache,
         })
     }
 
@@ -182,15 +501,16 @@ impl<'a> RenderCore<'a> {
     pub fn new_blocked(
         window: impl Into<SurfaceTarget<'a>>,
         window_size: Size<u32>,
-    ) -> anyhow::Result<Self> {
-        pollster::block_on(Self::new(window, window_size))
+        core_config: &RenderCoreConfig,
+    ) -> Result<Self, Error> {
+        pollster::block_on(Self::new(window, window_size, core_config))
     }
 
     #[inline]
     pub fn break_down(self) -> (Device, Queue, Surface<'a>, SurfaceConfig) {
         (
-            Device(self.device),
-            Queue(self.queue),
+            self.device.into(),
+            self.queue.into(),
             Surface(self.surface),
             SurfaceConfig(self.config),
         )
@@ -200,15 +520,28 @@ impl<'a> RenderCore<'a> {
 //====================================================================
 
 pub struct RenderPassDesc<'a> {
+    /// Render into this view instead of the encoder's surface view.
+    pub color_target: Option<&'a wgpu::TextureView>,
+    /// Multisampled view rendered into instead of the color target, which it's resolved
+    /// into at the end of the pass. Pipelines and the depth view must share its sample
+    /// count.
+    pub multisampled: Option<&'a wgpu::TextureView>,
     pub use_depth: Option<&'a wgpu::TextureView>,
     pub clear_color: Option<Color>,
+    /// Clear the depth attachment (if any) or load its previous contents.
+    pub clear_depth: bool,
+    pub depth_convention: DepthConvention,
 }
 
 impl RenderPassDesc<'_> {
     pub fn none() -> Self {
         Self {
+            color_target: None,
+            multisampled: None,
             use_depth: None,
             clear_color: None,
+            clear_depth: true,
+            depth_convention: DepthConvention::default(),
         }
     }
 }
@@ -216,8 +549,12 @@ impl RenderPassDesc<'_> {
 impl Default for RenderPassDesc<'_> {
     fn default() -> Self {
         Self {
+            color_target: None,
+            multisampled: None,
             use_depth: None,
             clear_color: Some(Color::new(0.2, 0.2, 0.2, 1.)),
+            clear_depth: true,
+            depth_convention: DepthConvention::default(),
         }
     }
 }
@@ -280,37 +617,100 @@ impl Color {
 pub use wgpu::SurfaceError;
 
 pub struct RenderEncoder {
-    surface_texture: wgpu::SurfaceTexture,
+    surface_texture: Option<wgpu::SurfaceTexture>,
     surface_view: wgpu::TextureView,
     encoder: wgpu::CommandEncoder,
 }
 
+static SURFACE_RECONFIGURES: AtomicU32 = AtomicU32::new(0);
+
+/// Number of times the surface has been reconfigured because it reported being suboptimal.
+#[inline]
+pub fn suboptimal_reconfigure_count() -> u32 {
+    SURFACE_RECONFIGURES.load(Ordering::Relaxed)
+}
+
 impl RenderEncoder {
     pub fn new(device: &wgpu::Device, surface: &wgpu::Surface) -> Result<Self, wgpu::SurfaceError> {
-        let (surface_texture, surface_view) = match surface.get_current_texture() {
-            Ok(texture) => {
-                let view = texture
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default());
-                (texture, view)
+        let surface_texture = surface.get_current_texture()?;
+        Ok(Self::from_surface_texture(device, surface_texture))
+    }
+
+    /// Like `new` but reconfigures the surface and retries once if the surface texture
+    /// is suboptimal, rather than presenting a stretched frame.
+    pub fn new_configured(
+        device: &wgpu::Device,
+        surface: &wgpu::Surface,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Result<Self, wgpu::SurfaceError> {
+        let surface_texture = match surface.get_current_texture()? {
+            texture if texture.suboptimal => {
+                log::debug!("Surface texture suboptimal - reconfiguring surface");
+                SURFACE_RECONFIGURES.fetch_add(1, Ordering::Relaxed);
+
+                // The suboptimal texture must be released before reconfiguring
+                std::mem::drop(texture);
+                surface.configure(device, config);
+                surface.get_current_texture()?
             }
-            Err(e) => return Err(e),
+            texture => texture,
         };
 
+        Ok(Self::from_surface_texture(device, surface_texture))
+    }
+
+    fn from_surface_texture(device: &wgpu::Device, surface_texture: wgpu::SurfaceTexture) -> Self {
+        let surface_view = surface_texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
         let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Main Command Encoder"),
         });
 
-        Ok(RenderEncoder {
-            surface_texture,
+        RenderEncoder {
+            surface_texture: Some(surface_texture),
             surface_view,
             encoder,
-        })
+        }
     }
 
+    /// Render into an arbitrary texture view instead of a surface, such as a
+    /// viewport texture owned by a host application.
+    pub fn from_view(device: &wgpu::Device, view: wgpu::TextureView) -> Self {
+        let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("View Command Encoder"),
+        });
+
+        RenderEncoder {
+            surface_texture: None,
+            surface_view: view,
+            encoder,
+        }
+    }
+
+    /// Render into an `OffscreenTarget` instead of a surface. Passes without a color
+    /// target render into it, and `finish` only submits.
+    pub fn new_offscreen(device: &wgpu::Device, target: &offscreen::OffscreenTarget) -> Self {
+        let view = target
+            .render_texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self::from_view(device, view)
+    }
+
+    /// Submit the encoded commands and present the surface texture (if rendering to a surface).
     pub fn finish(self, queue: &wgpu::Queue) {
+        if let Some(surface_texture) = self.submit(queue) {
+            surface_texture.present();
+        }
+    }
+
+    /// Submit the encoded commands without presenting, so presenting can be timed
+    /// separately. The returned surface texture must be presented.
+    pub fn submit(self, queue: &wgpu::Queue) -> Option<wgpu::SurfaceTexture> {
         queue.submit(Some(self.encoder.finish()));
-        self.surface_texture.present();
+        self.surface_texture
     }
 
     pub fn begin_render_pass(&mut self, desc: RenderPassDesc) -> RenderPass {
@@ -319,10 +719,22 @@ impl RenderEncoder {
             Some(view) => Some(wgpu::RenderPassDepthStencilAttachment {
                 view,
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.),
+                    load: match desc.clear_depth {
+                        true => wgpu::LoadOp::Clear(desc.depth_convention.clear_value()),
+                        false => wgpu::LoadOp::Load,
+                    },
                     store: wgpu::StoreOp::Store,
                 }),
-                stencil_ops: None,
+                stencil_ops: match desc.depth_convention.format.has_stencil() {
+                    true => Some(wgpu::Operations {
+                        load: match desc.clear_depth {
+                            true => wgpu::LoadOp::Clear(0),
+                            false => wgpu::LoadOp::Load,
+                        },
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    false => None,
+                },
             }),
             None => None,
         };
@@ -332,11 +744,17 @@ impl RenderEncoder {
             None => wgpu::LoadOp::Load,
         };
 
+        let color_target = desc.color_target.unwrap_or(&self.surface_view);
+        let (view, resolve_target) = match desc.multisampled {
+            Some(multisampled) => (multisampled, Some(color_target)),
+            None => (color_target, None),
+        };
+
         let render_pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Render Tools Basic Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.surface_view,
-                resolve_target: None,
+                view,
+                resolve_target,
                 ops: wgpu::Operations {
                     load,
                     store: wgpu::StoreOp::Store,
@@ -350,6 +768,42 @@ impl RenderEncoder {
         RenderPass(render_pass)
     }
 
+    /// Begin a compute pass. Its work runs before any render passes begun after it.
+    #[inline]
+    pub fn begin_compute_pass(&mut self, label: &str) -> wgpu::ComputePass<'_> {
+        self.encoder
+            .begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(label),
+                timestamp_writes: None,
+            })
+    }
+
+    /// Record a copy of the surface texture, to be read back after the encoder is
+    /// submitted and before the frame is presented. Errors when rendering to a view or
+    /// if the surface wasn't configured with `COPY_SRC` usage.
+    pub fn capture_surface(
+        &mut self,
+        device: &wgpu::Device,
+    ) -> Result<capture::PendingCapture, Error> {
+        let Some(surface_texture) = &self.surface_texture else {
+            return Err(Error::Capture(
+                "there is no surface texture when rendering to a view".into(),
+            ));
+        };
+
+        capture::PendingCapture::new(device, &mut self.encoder, &surface_texture.texture)
+    }
+
+    /// Copy the whole of `source` into `destination`, which must share its size, format
+    /// and sample count of 1.
+    pub fn copy_texture(&mut self, source: &wgpu::Texture, destination: &wgpu::Texture) {
+        self.encoder.copy_texture_to_texture(
+            source.as_image_copy(),
+            destination.as_image_copy(),
+            source.size(),
+        );
+    }
+
     #[inline]
     pub fn begin_render_pass_wgpu(&mut self, desc: &wgpu::RenderPassDescriptor) -> RenderPass {
         let render_pass = self.encoder.begin_render_pass(desc);
@@ -1,6 +1,8 @@
 //====================================================================
 
-use crate::tools;
+use std::sync::Arc;
+
+use crate::{layouts, shared::SharedRenderResources, tools};
 
 //====================================================================
 
@@ -21,11 +23,55 @@ impl Default for GlobalLightData {
     }
 }
 
+/// A light to draw, lowered into a `LightInstance` for the shaders. Directional and
+/// point lights can be mixed freely in one `update_lights`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    /// Infinitely far away and shining along `direction`, such as the sun.
+    Directional {
+        direction: glam::Vec3,
+        color: glam::Vec3,
+    },
+    /// Shining from `position` and fading smoothly to nothing at `radius`, or lighting
+    /// everything equally if `radius` is 0.
+    Point {
+        position: glam::Vec3,
+        color: glam::Vec3,
+        radius: f32,
+    },
+}
+
+impl From<Light> for LightInstance {
+    #[inline]
+    fn from(value: Light) -> Self {
+        match value {
+            Light::Directional { direction, color } => LightInstance::directional(direction, color),
+            Light::Point {
+                position,
+                color,
+                radius,
+            } => LightInstance::point(position, color, radius),
+        }
+    }
+}
+
+/// `LightInstance::light_type` of directional lights. Must match `shaders/lighting_*.wgsl`.
+pub const LIGHT_TYPE_DIRECTIONAL: u32 = 0;
+/// `LightInstance::light_type` of point lights. Must match `shaders/lighting_*.wgsl`.
+pub const LIGHT_TYPE_POINT: u32 = 1;
+
+/// A light as the shaders read it. Created from a `Light` or with `point` or
+/// `directional`, and zeroed instances light nothing.
 #[repr(C)]
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug, Default)]
 pub struct LightInstance {
+    /// Position of point lights, with the radius they fade out at in `w` (0 for no
+    /// falloff).
     position: glam::Vec4,
-    direction: glam::Vec4,
+    /// Direction directional lights shine in.
+    direction: glam::Vec3,
+    /// `LIGHT_TYPE_DIRECTIONAL` or `LIGHT_TYPE_POINT`, in the padding after `direction`.
+    light_type: u32,
     diffuse: glam::Vec4,
     specular: glam::Vec4,
 }
@@ -33,26 +79,131 @@ pub struct LightInstance {
 impl LightInstance {
     const ZERO: LightInstance = LightInstance {
         position: glam::Vec4::ZERO,
-        direction: glam::Vec4::ZERO,
+        direction: glam::Vec3::ZERO,
+        light_type: LIGHT_TYPE_DIRECTIONAL,
         diffuse: glam::Vec4::ZERO,
         specular: glam::Vec4::ZERO,
     };
+
+    /// See `Light::Point`.
+    #[inline]
+    pub fn point(position: glam::Vec3, color: glam::Vec3, radius: f32) -> Self {
+        Self {
+            position: position.extend(radius.max(0.)),
+            direction: glam::Vec3::ZERO,
+            light_type: LIGHT_TYPE_POINT,
+            diffuse: color.extend(1.),
+            specular: color.extend(1.),
+        }
+    }
+
+    /// See `Light::Directional`.
+    #[inline]
+    pub fn directional(direction: glam::Vec3, color: glam::Vec3) -> Self {
+        Self {
+            position: glam::Vec4::ZERO,
+            direction: direction.normalize_or(glam::Vec3::NEG_Y),
+            light_type: LIGHT_TYPE_DIRECTIONAL,
+            diffuse: color.extend(1.),
+            specular: color.extend(1.),
+        }
+    }
+
+    /// Color of highlights, which defaults to the light's color.
+    #[inline]
+    pub fn with_specular(mut self, specular: glam::Vec3) -> Self {
+        self.specular = specular.extend(1.);
+        self
+    }
+
+    #[inline]
+    pub fn light_type(&self) -> u32 {
+        self.light_type
+    }
+}
+
+//====================================================================
+
+/// Lights drawn by the `LightingPath::UniformArray` path. Must match the array
+/// length in `shaders/lighting_uniform.wgsl`.
+pub const MAX_UNIFORM_LIGHTS: usize = 16;
+
+/// How light instances reach the shaders. Chosen from the device capabilities
+/// when the renderer is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LightingPath {
+    /// Any number of lights in a storage buffer.
+    #[default]
+    StorageBuffer,
+    /// Up to `MAX_UNIFORM_LIGHTS` lights in a uniform array, for devices that can't
+    /// read storage buffers from shaders such as WebGL2.
+    UniformArray,
+}
+
+impl LightingPath {
+    /// Name of the bind group layout the lighting bind group uses.
+    #[inline]
+    pub fn layout(self) -> &'static str {
+        match self {
+            LightingPath::StorageBuffer => layouts::LIGHTING,
+            LightingPath::UniformArray => layouts::LIGHTING_UNIFORM,
+        }
+    }
+
+    /// WGSL declaring the lighting bindings at group 1, with `global_lighting`,
+    /// `light_count()` and `get_light(index)` for shaders to use.
+    #[inline]
+    pub fn shader_declarations(self) -> &'static str {
+        match self {
+            LightingPath::StorageBuffer => include_str!("shaders/lighting_storage.wgsl"),
+            LightingPath::UniformArray => include_str!("shaders/lighting_uniform.wgsl"),
+        }
+    }
+
+    /// `shader` with the lighting declarations prepended.
+    #[inline]
+    pub fn shader_with_lighting(self, shader: &str) -> String {
+        format!("{}\n{}", self.shader_declarations(), shader)
+    }
+
+    #[inline]
+    pub fn max_lights(self) -> Option<usize> {
+        match self {
+            LightingPath::StorageBuffer => None,
+            LightingPath::UniformArray => Some(MAX_UNIFORM_LIGHTS),
+        }
+    }
 }
 
+/// Size of the uniform light array - the lights followed by the count, padded to 16 bytes.
+const UNIFORM_LIGHTS_SIZE: u64 =
+    (std::mem::size_of::<LightInstance>() * MAX_UNIFORM_LIGHTS + 16) as u64;
+
 //====================================================================
 
 pub struct LightingManager {
+    path: LightingPath,
+
     globals_uniform: wgpu::Buffer,
     light_instances: wgpu::Buffer,
     light_instance_count: u32,
 
     bind_group: wgpu::BindGroup,
-    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group_layout: Arc<wgpu::BindGroupLayout>,
 }
 
 impl LightingManager {
-    pub fn new(device: &wgpu::Device) -> Self {
-        log::debug!("Creating lighting manager");
+    #[inline]
+    pub fn new(device: &wgpu::Device, shared: &SharedRenderResources) -> Self {
+        Self::new_with_path(device, shared, LightingPath::default())
+    }
+
+    pub fn new_with_path(
+        device: &wgpu::Device,
+        shared: &SharedRenderResources,
+        path: LightingPath,
+    ) -> Self {
+        log::debug!("Creating lighting manager using {:?}", path);
 
         let globals_uniform = tools::create_buffer(
             device,
@@ -61,20 +212,22 @@ impl LightingManager {
             &[GlobalLightData::default()],
         );
 
-        let light_instances = tools::create_buffer(
-            device,
-            tools::BufferType::Storage,
-            "Light instances",
-            &[LightInstance::ZERO],
-        );
+        let light_instances = match path {
+            LightingPath::StorageBuffer => tools::create_buffer(
+                device,
+                tools::BufferType::Storage,
+                "Light instances",
+                &[LightInstance::ZERO],
+            ),
+            LightingPath::UniformArray => device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Light instances"),
+                size: UNIFORM_LIGHTS_SIZE,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+        };
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Light uniform bind group layout"),
-            entries: &[
-                tools::bgl_entry(tools::BgEntryType::Uniform, 0, wgpu::ShaderStages::FRAGMENT),
-                tools::bgl_entry(tools::BgEntryType::Storage, 1, wgpu::ShaderStages::FRAGMENT),
-            ],
-        });
+        let bind_group_layout = shared.clone_layout(path.layout());
 
         let bind_group = Self::bind_lighting_buffers(
             device,
@@ -84,6 +237,7 @@ impl LightingManager {
         );
 
         Self {
+            path,
             globals_uniform,
             light_instances,
             light_instance_count: 0,
@@ -118,6 +272,11 @@ impl LightingManager {
         })
     }
 
+    #[inline]
+    pub fn path(&self) -> LightingPath {
+        self.path
+    }
+
     #[inline]
     pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
         &self.bind_group_layout
@@ -138,6 +297,10 @@ impl LightingManager {
         queue: &wgpu::Queue,
         lights: &[LightInstance],
     ) {
+        if self.path == LightingPath::UniformArray {
+            return self.update_uniform_lights(queue, lights);
+        }
+
         match lights.is_empty() {
             true => {
                 self.light_instances = tools::create_buffer(
@@ -157,11 +320,9 @@ impl LightingManager {
 
             false => {
                 if lights.len() <= self.light_instance_count as usize {
-                    // queue.write_buffer(&self.light_instances, 0, bytemuck::cast_slice(lights));
-                    // return;
-
-                    let buffer_size = std::mem::size_of::<LightInstance> as u64
-                        * self.light_instance_count as u64;
+                    // Instances past the new lights are zeroed, so they light nothing
+                    let light_size = std::mem::size_of::<LightInstance>();
+                    let buffer_size = (light_size * self.light_instance_count as usize) as u64;
 
                     let mut buffer_slice = queue
                         .write_buffer_with(
@@ -171,7 +332,7 @@ impl LightingManager {
                         )
                         .unwrap();
 
-                    let (data, empty) = buffer_slice.split_at_mut(lights.len());
+                    let (data, empty) = buffer_slice.split_at_mut(std::mem::size_of_val(lights));
                     data.copy_from_slice(bytemuck::cast_slice(lights));
                     empty.fill(0);
 
@@ -183,12 +344,45 @@ impl LightingManager {
                     device,
                     tools::BufferType::Storage,
                     "Light instances",
-                    &[LightInstance::ZERO],
+                    lights,
+                );
+                self.bind_group = Self::bind_lighting_buffers(
+                    device,
+                    &self.bind_group_layout,
+                    &self.globals_uniform,
+                    &self.light_instances,
                 );
             }
         }
     }
 
+    /// Lights past `MAX_UNIFORM_LIGHTS` are dropped.
+    fn update_uniform_lights(&mut self, queue: &wgpu::Queue, lights: &[LightInstance]) {
+        if lights.len() > MAX_UNIFORM_LIGHTS
+            && self.light_instance_count <= MAX_UNIFORM_LIGHTS as u32
+        {
+            log::warn!(
+                "{} lights set but only {} are drawn without storage buffers",
+                lights.len(),
+                MAX_UNIFORM_LIGHTS
+            );
+        }
+
+        let count = lights.len().min(MAX_UNIFORM_LIGHTS);
+
+        let mut data = [LightInstance::ZERO; MAX_UNIFORM_LIGHTS];
+        data[..count].copy_from_slice(&lights[..count]);
+
+        queue.write_buffer(&self.light_instances, 0, bytemuck::cast_slice(&data));
+        queue.write_buffer(
+            &self.light_instances,
+            (std::mem::size_of::<LightInstance>() * MAX_UNIFORM_LIGHTS) as u64,
+            bytemuck::cast_slice(&[count as u32, 0, 0, 0]),
+        );
+
+        self.light_instance_count = lights.len() as u32;
+    }
+
     #[inline]
     pub fn update_globals(&self, queue: &wgpu::Queue, data: GlobalLightData) {
         queue
@@ -205,3 +399,57 @@ impl LightingManager {
 //====================================================================
 
 //====================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::hash::BuildHasher;
+
+    use roots_common::FastHasher;
+
+    use super::*;
+    use crate::test_utils;
+
+    /// Bind groups hash by their allocation. A replacement is created before the
+    /// previous one is dropped, so the two never share a hash.
+    fn bind_group_hash(lighting: &LightingManager) -> u64 {
+        FastHasher::default().hash_one(lighting.bind_group())
+    }
+
+    fn lights(count: usize) -> Vec<LightInstance> {
+        (0..count)
+            .map(|index| LightInstance::point(glam::Vec3::X * index as f32, glam::Vec3::ONE, 5.))
+            .collect()
+    }
+
+    #[test]
+    fn growing_lights_rebinds_a_larger_buffer() {
+        let Some((device, queue)) = test_utils::device() else {
+            return;
+        };
+        let shared = SharedRenderResources::new(&device);
+        let mut lighting = LightingManager::new(&device, &shared);
+        let light_size = std::mem::size_of::<LightInstance>() as u64;
+
+        let empty = bind_group_hash(&lighting);
+
+        lighting.update_lights(&device, &queue, &lights(1));
+        assert_eq!(lighting.light_instances.size(), light_size);
+        let one = bind_group_hash(&lighting);
+        assert_ne!(one, empty);
+
+        lighting.update_lights(&device, &queue, &lights(10));
+        assert_eq!(lighting.light_instance_count, 10);
+        assert_eq!(lighting.light_instances.size(), light_size * 10);
+        let ten = bind_group_hash(&lighting);
+        assert_ne!(ten, one);
+
+        // Fewer lights reuse the buffer
+        lighting.update_lights(&device, &queue, &lights(3));
+        assert_eq!(lighting.light_instances.size(), light_size * 10);
+        assert_eq!(bind_group_hash(&lighting), ten);
+
+        lighting.update_lights(&device, &queue, &[]);
+        assert_eq!(lighting.light_instance_count, 0);
+        assert_ne!(bind_group_hash(&lighting), ten);
+    }
+}
@@ -0,0 +1,340 @@
+//====================================================================
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+//====================================================================
+
+/// What a tracked gpu allocation is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryCategory {
+    Textures,
+    /// Depth and other textures sized to the render target.
+    RenderTargets,
+    /// Render targets shared through a `RenderTargetPool`, whether in use or free.
+    PooledTargets,
+    Meshes,
+    InstanceBuffers,
+    Atlas,
+}
+
+impl MemoryCategory {
+    pub const ALL: [MemoryCategory; 6] = [
+        MemoryCategory::Textures,
+        MemoryCategory::RenderTargets,
+        MemoryCategory::PooledTargets,
+        MemoryCategory::Meshes,
+        MemoryCategory::InstanceBuffers,
+        MemoryCategory::Atlas,
+    ];
+
+    #[inline]
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+//====================================================================
+
+static TOTALS: [AtomicU64; 6] = [const { AtomicU64::new(0) }; 6];
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+/// Zero means no budget.
+static BUDGET: AtomicU64 = AtomicU64::new(0);
+static OVER_BUDGET: AtomicBool = AtomicBool::new(false);
+
+struct LiveResource {
+    label: String,
+    category: MemoryCategory,
+    bytes: u64,
+}
+
+static LIVE: Mutex<Option<HashMap<u64, LiveResource>>> = Mutex::new(None);
+
+/// Releases its bytes from the `GpuMemoryTracker` when dropped. Stored next to the
+/// gpu resource it measures.
+#[derive(Debug)]
+pub struct MemoryGuard {
+    id: u64,
+    category: MemoryCategory,
+    bytes: u64,
+}
+
+impl MemoryGuard {
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    #[inline]
+    pub fn category(&self) -> MemoryCategory {
+        self.category
+    }
+
+    /// Update the size after the resource was recreated at a new size.
+    pub fn set_bytes(&mut self, bytes: u64) {
+        if bytes == self.bytes {
+            return;
+        }
+
+        TOTALS[self.category.index()].fetch_sub(self.bytes, Ordering::Relaxed);
+        TOTALS[self.category.index()].fetch_add(bytes, Ordering::Relaxed);
+        self.bytes = bytes;
+
+        if let Some(resource) = LIVE
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|live| live.get_mut(&self.id))
+        {
+            resource.bytes = bytes;
+        }
+
+        GpuMemoryTracker::check_budget();
+    }
+
+    /// Move the bytes to another category, such as a texture used as an atlas.
+    pub fn set_category(&mut self, category: MemoryCategory) {
+        TOTALS[self.category.index()].fetch_sub(self.bytes, Ordering::Relaxed);
+        TOTALS[category.index()].fetch_add(self.bytes, Ordering::Relaxed);
+        self.category = category;
+
+        if let Some(resource) = LIVE
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|live| live.get_mut(&self.id))
+        {
+            resource.category = category;
+        }
+    }
+}
+
+impl Drop for MemoryGuard {
+    fn drop(&mut self) {
+        TOTALS[self.category.index()].fetch_sub(self.bytes, Ordering::Relaxed);
+
+        if let Some(live) = LIVE.lock().unwrap().as_mut() {
+            live.remove(&self.id);
+        }
+
+        GpuMemoryTracker::check_budget();
+    }
+}
+
+//====================================================================
+
+/// Global accounting of the gpu memory created through this crate. Sizes are
+/// calculated from what was requested, so driver overhead and padding aren't included.
+pub struct GpuMemoryTracker;
+
+impl GpuMemoryTracker {
+    /// Record an allocation. The bytes are released when the guard is dropped.
+    pub fn track(category: MemoryCategory, label: impl Into<String>, bytes: u64) -> MemoryGuard {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        TOTALS[category.index()].fetch_add(bytes, Ordering::Relaxed);
+
+        LIVE.lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(
+                id,
+                LiveResource {
+                    label: label.into(),
+                    category,
+                    bytes,
+                },
+            );
+
+        Self::check_budget();
+
+        MemoryGuard {
+            id,
+            category,
+            bytes,
+        }
+    }
+
+    #[inline]
+    pub fn total(category: MemoryCategory) -> u64 {
+        TOTALS[category.index()].load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn total_all() -> u64 {
+        MemoryCategory::ALL
+            .iter()
+            .map(|category| Self::total(*category))
+            .sum()
+    }
+
+    /// Log a warning whenever the total crosses `budget` bytes. `None` removes the budget.
+    pub fn set_budget(budget: Option<u64>) {
+        BUDGET.store(budget.unwrap_or(0), Ordering::Relaxed);
+        OVER_BUDGET.store(false, Ordering::Relaxed);
+        Self::check_budget();
+    }
+
+    #[inline]
+    pub fn budget() -> Option<u64> {
+        match BUDGET.load(Ordering::Relaxed) {
+            0 => None,
+            budget => Some(budget),
+        }
+    }
+
+    fn check_budget() {
+        let Some(budget) = Self::budget() else {
+            return;
+        };
+
+        let total = Self::total_all();
+        let over = total > budget;
+
+        if OVER_BUDGET.swap(over, Ordering::Relaxed) != over && over {
+            log::warn!(
+                "Gpu memory over budget: {} used of {}",
+                format_bytes(total),
+                format_bytes(budget)
+            );
+        }
+    }
+
+    /// Totals by category and the `top` largest live resources.
+    pub fn report(top: usize) -> MemoryReport {
+        let mut largest = LIVE
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|live| {
+                live.values()
+                    .map(|resource| MemoryEntry {
+                        label: resource.label.clone(),
+                        category: resource.category,
+                        bytes: resource.bytes,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        largest.sort_unstable_by_key(|entry| std::cmp::Reverse(entry.bytes));
+        largest.truncate(top);
+
+        MemoryReport {
+            totals: MemoryCategory::ALL.map(|category| (category, Self::total(category))),
+            total: Self::total_all(),
+            budget: Self::budget(),
+            largest,
+        }
+    }
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryEntry {
+    pub label: String,
+    pub category: MemoryCategory,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryReport {
+    pub totals: [(MemoryCategory, u64); 6],
+    pub total: u64,
+    pub budget: Option<u64>,
+    /// Largest live resources, largest first.
+    pub largest: Vec<MemoryEntry>,
+}
+
+impl Display for MemoryReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.budget {
+            Some(budget) => writeln!(
+                f,
+                "Gpu memory: {} of {}",
+                format_bytes(self.total),
+                format_bytes(budget)
+            )?,
+            None => writeln!(f, "Gpu memory: {}", format_bytes(self.total))?,
+        }
+
+        for (category, bytes) in &self.totals {
+            writeln!(f, "  {:?}: {}", category, format_bytes(*bytes))?;
+        }
+
+        for entry in &self.largest {
+            writeln!(
+                f,
+                "  {} ({:?}): {}",
+                entry.label,
+                entry.category,
+                format_bytes(entry.bytes)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Format a byte count with a binary unit, such as `12.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let (value, unit) = UNITS[1..]
+        .iter()
+        .fold((bytes as f64, UNITS[0]), |(value, unit), next| {
+            match value >= 1024. {
+                true => (value / 1024., *next),
+                false => (value, unit),
+            }
+        });
+
+    match unit {
+        "B" => format!("{} B", bytes),
+        _ => format!("{:.1} {}", value, unit),
+    }
+}
+
+//====================================================================
+
+/// Bytes used by a texture with every mip level, layer and sample, from its
+/// format's block size.
+pub fn texture_bytes(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format
+        .block_copy_size(None)
+        .or_else(|| {
+            // Depth-stencil formats only have a size per aspect
+            let depth = format.block_copy_size(Some(wgpu::TextureAspect::DepthOnly));
+            let stencil = format.block_copy_size(Some(wgpu::TextureAspect::StencilOnly));
+            match (depth, stencil) {
+                (None, None) => None,
+                (depth, stencil) => Some(depth.unwrap_or(0) + stencil.unwrap_or(0)),
+            }
+        })
+        .unwrap_or(4) as u64;
+
+    let size = texture.size();
+    let dimension = texture.dimension();
+
+    let bytes = (0..texture.mip_level_count())
+        .map(|level| {
+            let mip = size.mip_level_size(level, dimension);
+            let blocks_wide = mip.width.div_ceil(block_width) as u64;
+            let blocks_high = mip.height.div_ceil(block_height) as u64;
+
+            blocks_wide * blocks_high * block_size * mip.depth_or_array_layers as u64
+        })
+        .sum::<u64>();
+
+    bytes * texture.sample_count() as u64
+}
+
+//====================================================================
@@ -1,28 +1,54 @@
 //====================================================================
 
-use std::sync::{atomic::AtomicU32, Arc};
+use std::{
+    ops::Range,
+    sync::{atomic::AtomicU32, Arc},
+};
 
-use crate::{shared::Vertex, tools};
+use roots_common::{coords::CoordinateConvention, spatial::Aabb};
+
+use crate::{
+    memory::{GpuMemoryTracker, MemoryCategory, MemoryGuard},
+    shared::Vertex,
+    tools,
+};
 
 //====================================================================
 
 pub type MeshId = u32;
+pub type MeshPoolId = u32;
 
 static CURRENT_MESH_ID: AtomicU32 = AtomicU32::new(0);
+static CURRENT_POOL_ID: AtomicU32 = AtomicU32::new(0);
 
 #[derive(Clone, Debug)]
 pub struct LoadedMesh {
     id: MeshId,
-    mesh: Arc<Mesh>,
+    source: MeshSource,
+    aabb: Aabb,
+}
+
+#[derive(Clone, Debug)]
+enum MeshSource {
+    Owned(Arc<Mesh>),
+    Pooled(Arc<PoolBuffers>, MeshRange),
+}
+
+/// Where a pooled mesh sits in its pool's buffers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct MeshRange {
+    base_vertex: i32,
+    first_index: u32,
+    index_count: u32,
 }
 
 impl LoadedMesh {
     #[inline]
     pub fn load_mesh(mesh: Mesh) -> Self {
-        let id = CURRENT_MESH_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         Self {
-            id,
-            mesh: Arc::new(mesh),
+            id: next_mesh_id(),
+            aabb: mesh.aabb,
+            source: MeshSource::Owned(Arc::new(mesh)),
         }
     }
 
@@ -35,24 +61,226 @@ impl LoadedMesh {
         Self::load_mesh(Mesh::load_mesh(device, vertices, indices))
     }
 
+    #[inline]
+    pub fn load_from_data_u16(
+        device: &wgpu::Device,
+        vertices: &[ModelVertex],
+        indices: &[u16],
+    ) -> Self {
+        Self::load_mesh(Mesh::load_mesh_u16(device, vertices, indices))
+    }
+
+    /// The tracked size of the vertex and index buffers. Pooled meshes share the
+    /// tracking of their whole pool.
+    #[inline]
+    pub fn memory(&self) -> &MemoryGuard {
+        match &self.source {
+            MeshSource::Owned(mesh) => &mesh.memory,
+            MeshSource::Pooled(pool, _) => &pool.memory,
+        }
+    }
+
     #[inline]
     pub fn id(&self) -> MeshId {
         self.id
     }
 
+    /// Local space bounds of the vertices the mesh was loaded from.
+    #[inline]
+    pub fn aabb(&self) -> Aabb {
+        self.aabb
+    }
+
     #[inline]
     pub fn vertex_buffer(&self) -> &wgpu::Buffer {
-        &self.mesh.vertex_buffer
+        match &self.source {
+            MeshSource::Owned(mesh) => &mesh.vertex_buffer,
+            MeshSource::Pooled(pool, _) => &pool.vertex_buffer,
+        }
     }
 
     #[inline]
     pub fn index_buffer(&self) -> &wgpu::Buffer {
-        &self.mesh.index_buffer
+        match &self.source {
+            MeshSource::Owned(mesh) => &mesh.index_buffer,
+            MeshSource::Pooled(pool, _) => &pool.index_buffer,
+        }
     }
 
     #[inline]
     pub fn index_count(&self) -> u32 {
-        self.mesh.index_count
+        match &self.source {
+            MeshSource::Owned(mesh) => mesh.index_count,
+            MeshSource::Pooled(_, range) => range.index_count,
+        }
+    }
+
+    /// The format to bind `index_buffer` with. Pooled meshes always use 32 bit indices.
+    #[inline]
+    pub fn index_format(&self) -> wgpu::IndexFormat {
+        match &self.source {
+            MeshSource::Owned(mesh) => mesh.index_format,
+            MeshSource::Pooled(..) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    /// The pool this mesh was loaded into. `None` if it owns its buffers.
+    #[inline]
+    pub fn pool(&self) -> Option<MeshPoolId> {
+        match &self.source {
+            MeshSource::Owned(_) => None,
+            MeshSource::Pooled(pool, _) => Some(pool.id),
+        }
+    }
+
+    /// Offset added to each index. Zero unless pooled.
+    #[inline]
+    pub fn base_vertex(&self) -> i32 {
+        match &self.source {
+            MeshSource::Owned(_) => 0,
+            MeshSource::Pooled(_, range) => range.base_vertex,
+        }
+    }
+
+    /// Offset of the first index in the index buffer. Zero unless pooled.
+    #[inline]
+    pub fn first_index(&self) -> u32 {
+        match &self.source {
+            MeshSource::Owned(_) => 0,
+            MeshSource::Pooled(_, range) => range.first_index,
+        }
+    }
+
+    /// The indices to draw from `index_buffer`.
+    #[inline]
+    pub fn index_range(&self) -> Range<u32> {
+        self.first_index()..self.first_index() + self.index_count()
+    }
+}
+
+#[inline]
+fn next_mesh_id() -> MeshId {
+    CURRENT_MESH_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+//--------------------------------------------------
+
+#[derive(Debug)]
+struct PoolBuffers {
+    id: MeshPoolId,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    memory: MemoryGuard,
+}
+
+/// Fixed size vertex and index buffers that many meshes are loaded into. Meshes
+/// sharing a pool can be drawn together with one indirect draw, see
+/// `ModelRenderer`. Space isn't reclaimed when meshes are dropped, so pools suit
+/// meshes loaded up front.
+#[derive(Debug)]
+pub struct MeshPool {
+    buffers: Arc<PoolBuffers>,
+    vertex_capacity: u32,
+    index_capacity: u32,
+    vertex_count: u32,
+    index_count: u32,
+}
+
+impl MeshPool {
+    pub fn new(device: &wgpu::Device, vertex_capacity: u32, index_capacity: u32) -> Self {
+        let id = CURRENT_POOL_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        let vertex_buffer = tools::create_buffer_empty(
+            device,
+            tools::BufferType::VertexDynamic,
+            "Mesh Pool",
+            vertex_capacity as u64 * std::mem::size_of::<ModelVertex>() as u64,
+        );
+        let index_buffer = tools::create_buffer_empty(
+            device,
+            tools::BufferType::IndexDynamic,
+            "Mesh Pool",
+            index_capacity as u64 * std::mem::size_of::<u32>() as u64,
+        );
+
+        let memory = GpuMemoryTracker::track(
+            MemoryCategory::Meshes,
+            "Mesh Pool",
+            vertex_buffer.size() + index_buffer.size(),
+        );
+
+        Self {
+            buffers: Arc::new(PoolBuffers {
+                id,
+                vertex_buffer,
+                index_buffer,
+                memory,
+            }),
+            vertex_capacity,
+            index_capacity,
+            vertex_count: 0,
+            index_count: 0,
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> MeshPoolId {
+        self.buffers.id
+    }
+
+    /// Vertices and indices that can still be loaded.
+    #[inline]
+    pub fn remaining(&self) -> (u32, u32) {
+        (
+            self.vertex_capacity - self.vertex_count,
+            self.index_capacity - self.index_count,
+        )
+    }
+
+    /// Load a mesh into the pool. `None` if there isn't enough space left.
+    pub fn load(
+        &mut self,
+        queue: &wgpu::Queue,
+        vertices: &[ModelVertex],
+        indices: &[u32],
+    ) -> Option<LoadedMesh> {
+        let (vertices_left, indices_left) = self.remaining();
+
+        if vertices.len() > vertices_left as usize || indices.len() > indices_left as usize {
+            log::warn!(
+                "Mesh pool {} is full - {} vertices and {} indices left",
+                self.id(),
+                vertices_left,
+                indices_left
+            );
+            return None;
+        }
+
+        let range = MeshRange {
+            base_vertex: self.vertex_count as i32,
+            first_index: self.index_count,
+            index_count: indices.len() as u32,
+        };
+
+        queue.write_buffer(
+            &self.buffers.vertex_buffer,
+            self.vertex_count as u64 * std::mem::size_of::<ModelVertex>() as u64,
+            bytemuck::cast_slice(vertices),
+        );
+        queue.write_buffer(
+            &self.buffers.index_buffer,
+            self.index_count as u64 * std::mem::size_of::<u32>() as u64,
+            bytemuck::cast_slice(indices),
+        );
+
+        self.vertex_count += vertices.len() as u32;
+        self.index_count += indices.len() as u32;
+
+        Some(LoadedMesh {
+            id: next_mesh_id(),
+            source: MeshSource::Pooled(self.buffers.clone(), range),
+            aabb: Aabb::from_points(vertices.iter().map(|vertex| vertex.pos)),
+        })
     }
 }
 
@@ -63,19 +291,49 @@ pub struct Mesh {
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub index_count: u32,
+    pub index_format: wgpu::IndexFormat,
+    pub aabb: Aabb,
+    memory: MemoryGuard,
 }
 
 impl Mesh {
+    #[inline]
     pub fn load_mesh(device: &wgpu::Device, vertices: &[ModelVertex], indices: &[u32]) -> Self {
+        Self::load_indexed(device, vertices, indices, wgpu::IndexFormat::Uint32)
+    }
+
+    /// Load a mesh with 16 bit indices, halving the size of the index buffer. Only
+    /// the first 65536 vertices can be indexed.
+    #[inline]
+    pub fn load_mesh_u16(device: &wgpu::Device, vertices: &[ModelVertex], indices: &[u16]) -> Self {
+        Self::load_indexed(device, vertices, indices, wgpu::IndexFormat::Uint16)
+    }
+
+    fn load_indexed<I: bytemuck::Pod>(
+        device: &wgpu::Device,
+        vertices: &[ModelVertex],
+        indices: &[I],
+        index_format: wgpu::IndexFormat,
+    ) -> Self {
         let vertex_buffer =
             tools::create_buffer(device, tools::BufferType::Vertex, "Mesh", vertices);
         let index_buffer = tools::create_buffer(device, tools::BufferType::Index, "Mesh", indices);
         let index_count = indices.len() as u32;
+        let aabb = Aabb::from_points(vertices.iter().map(|vertex| vertex.pos));
+
+        let memory = GpuMemoryTracker::track(
+            MemoryCategory::Meshes,
+            "Mesh",
+            vertex_buffer.size() + index_buffer.size(),
+        );
 
         Self {
             vertex_buffer,
             index_buffer,
             index_count,
+            index_format,
+            aabb,
+            memory,
         }
     }
 }
@@ -90,14 +348,18 @@ pub struct ModelVertex {
     pub pos: glam::Vec3,
     pub uv: glam::Vec2,
     pub normal: glam::Vec3,
+    /// Direction u increases in, with the sign of the bitangent in `w`, so that
+    /// `normal.cross(tangent.xyz) * tangent.w` is the direction v increases in.
+    pub tangent: glam::Vec4,
 }
 
 impl Vertex for ModelVertex {
     fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
             0 => Float32x3,
             1 => Float32x2,
-            2 => Float32x3
+            2 => Float32x3,
+            3 => Float32x4
         ];
 
         wgpu::VertexBufferLayout {
@@ -115,24 +377,28 @@ pub const CUBE_VERTICES: [ModelVertex; 24] = [
         pos: glam::vec3(-0.5, 0.5, -0.5),
         uv: glam::vec2(0., 0.),
         normal: glam::Vec3::NEG_Z,
+        tangent: glam::Vec4::new(1., 0., 0., 1.),
     },
     // Top Right - 1
     ModelVertex {
         pos: glam::vec3(0.5, 0.5, -0.5),
         uv: glam::vec2(1., 0.),
         normal: glam::Vec3::NEG_Z,
+        tangent: glam::Vec4::new(1., 0., 0., 1.),
     },
     // Bottom Left - 2
     ModelVertex {
         pos: glam::vec3(-0.5, -0.5, -0.5),
         uv: glam::vec2(0., 1.),
         normal: glam::Vec3::NEG_Z,
+        tangent: glam::Vec4::new(1., 0., 0., 1.),
     },
     // Bottom Right - 3
     ModelVertex {
         pos: glam::vec3(0.5, -0.5, -0.5),
         uv: glam::vec2(1., 1.),
         normal: glam::Vec3::NEG_Z,
+        tangent: glam::Vec4::new(1., 0., 0., 1.),
     },
     //
     // Right (+x)
@@ -141,24 +407,28 @@ pub const CUBE_VERTICES: [ModelVertex; 24] = [
         pos: glam::vec3(0.5, 0.5, -0.5),
         uv: glam::vec2(0., 0.),
         normal: glam::Vec3::X,
+        tangent: glam::Vec4::new(0., 0., 1., 1.),
     },
     // Top Right - 5
     ModelVertex {
         pos: glam::vec3(0.5, 0.5, 0.5),
         uv: glam::vec2(1., 0.),
         normal: glam::Vec3::X,
+        tangent: glam::Vec4::new(0., 0., 1., 1.),
     },
     // Bottom Left - 6
     ModelVertex {
         pos: glam::vec3(0.5, -0.5, -0.5),
         uv: glam::vec2(0., 1.),
         normal: glam::Vec3::X,
+        tangent: glam::Vec4::new(0., 0., 1., 1.),
     },
     // Bottom Right - 7
     ModelVertex {
         pos: glam::vec3(0.5, -0.5, 0.5),
         uv: glam::vec2(1., 1.),
         normal: glam::Vec3::X,
+        tangent: glam::Vec4::new(0., 0., 1., 1.),
     },
     //
     // Front (+z)
@@ -167,24 +437,28 @@ pub const CUBE_VERTICES: [ModelVertex; 24] = [
         pos: glam::vec3(0.5, 0.5, 0.5),
         uv: glam::vec2(0., 0.),
         normal: glam::Vec3::Z,
+        tangent: glam::Vec4::new(-1., 0., 0., 1.),
     },
     // Top Right - 9
     ModelVertex {
         pos: glam::vec3(-0.5, 0.5, 0.5),
         uv: glam::vec2(1., 0.),
         normal: glam::Vec3::Z,
+        tangent: glam::Vec4::new(-1., 0., 0., 1.),
     },
     // Bottom Left - 10
     ModelVertex {
         pos: glam::vec3(0.5, -0.5, 0.5),
         uv: glam::vec2(0., 1.),
         normal: glam::Vec3::Z,
+        tangent: glam::Vec4::new(-1., 0., 0., 1.),
     },
     // Bottom Right - 11
     ModelVertex {
         pos: glam::vec3(-0.5, -0.5, 0.5),
         uv: glam::vec2(1., 1.),
         normal: glam::Vec3::Z,
+        tangent: glam::Vec4::new(-1., 0., 0., 1.),
     },
     //
     // Left (-x)
@@ -193,24 +467,28 @@ pub const CUBE_VERTICES: [ModelVertex; 24] = [
         pos: glam::vec3(-0.5, 0.5, 0.5),
         uv: glam::vec2(0., 0.),
         normal: glam::Vec3::NEG_X,
+        tangent: glam::Vec4::new(0., 0., -1., 1.),
     },
     // Top Right - 13
     ModelVertex {
         pos: glam::vec3(-0.5, 0.5, -0.5),
         uv: glam::vec2(1., 0.),
         normal: glam::Vec3::NEG_X,
+        tangent: glam::Vec4::new(0., 0., -1., 1.),
     },
     // Bottom Left - 14
     ModelVertex {
         pos: glam::vec3(-0.5, -0.5, 0.5),
         uv: glam::vec2(0., 1.),
         normal: glam::Vec3::NEG_X,
+        tangent: glam::Vec4::new(0., 0., -1., 1.),
     },
     // Bottom Right - 15
     ModelVertex {
         pos: glam::vec3(-0.5, -0.5, -0.5),
         uv: glam::vec2(1., 1.),
         normal: glam::Vec3::NEG_X,
+        tangent: glam::Vec4::new(0., 0., -1., 1.),
     },
     //
     // Top
@@ -219,24 +497,28 @@ pub const CUBE_VERTICES: [ModelVertex; 24] = [
         pos: glam::vec3(0.5, 0.5, -0.5),
         uv: glam::vec2(0., 0.),
         normal: glam::Vec3::Y,
+        tangent: glam::Vec4::new(-1., 0., 0., 1.),
     },
     // Top Right - 17
     ModelVertex {
         pos: glam::vec3(-0.5, 0.5, -0.5),
         uv: glam::vec2(1., 0.),
         normal: glam::Vec3::Y,
+        tangent: glam::Vec4::new(-1., 0., 0., 1.),
     },
     // Bottom Left - 18
     ModelVertex {
         pos: glam::vec3(0.5, 0.5, 0.5),
         uv: glam::vec2(0., 1.),
         normal: glam::Vec3::Y,
+        tangent: glam::Vec4::new(-1., 0., 0., 1.),
     },
     // Bottom Right - 19
     ModelVertex {
         pos: glam::vec3(-0.5, 0.5, 0.5),
         uv: glam::vec2(1., 1.),
         normal: glam::Vec3::Y,
+        tangent: glam::Vec4::new(-1., 0., 0., 1.),
     },
     //
     // Bottom
@@ -245,24 +527,28 @@ pub const CUBE_VERTICES: [ModelVertex; 24] = [
         pos: glam::vec3(0.5, -0.5, -0.5),
         uv: glam::vec2(0., 0.),
         normal: glam::Vec3::NEG_Y,
+        tangent: glam::Vec4::new(-1., 0., 0., -1.),
     },
     // Top Right - 21
     ModelVertex {
         pos: glam::vec3(-0.5, -0.5, -0.5),
         uv: glam::vec2(1., 0.),
         normal: glam::Vec3::NEG_Y,
+        tangent: glam::Vec4::new(-1., 0., 0., -1.),
     },
     // Bottom Left - 22
     ModelVertex {
         pos: glam::vec3(0.5, -0.5, 0.5),
         uv: glam::vec2(0., 1.),
         normal: glam::Vec3::NEG_Y,
+        tangent: glam::Vec4::new(-1., 0., 0., -1.),
     },
     // Bottom Right - 23
     ModelVertex {
         pos: glam::vec3(-0.5, -0.5, 0.5),
         uv: glam::vec2(1., 1.),
         normal: glam::Vec3::NEG_Y,
+        tangent: glam::Vec4::new(-1., 0., 0., -1.),
     },
 ];
 
@@ -277,4 +563,419 @@ pub const CUBE_INDICES: [u32; 36] = [
 
 pub const CUBE_INDEX_COUNT: u32 = CUBE_INDICES.len() as u32;
 
+/// The unit cube in the active `CoordinateConvention`. `CUBE_VERTICES` and
+/// `CUBE_INDICES` are authored left handed Y up.
+pub fn cube() -> ([ModelVertex; 24], [u32; 36]) {
+    let mut vertices = CUBE_VERTICES;
+
+    let convention = CoordinateConvention::active();
+    if convention != CoordinateConvention::LeftHandedYUp {
+        convert_mesh(
+            &mut vertices,
+            convention.conversion_from(CoordinateConvention::LeftHandedYUp),
+        );
+    }
+
+    (vertices, CUBE_INDICES)
+}
+
+/// A flat rectangle of `size`, centered on the origin and facing up in the active
+/// `CoordinateConvention`. Split into a grid of `subdivisions` quads, such as for
+/// displacing the vertices later. Texture coordinates count world units, so a texture
+/// with a repeating sampler tiles once per unit.
+pub fn plane(size: glam::Vec2, subdivisions: glam::UVec2) -> (Vec<ModelVertex>, Vec<u32>) {
+    let subdivisions = subdivisions.max(glam::UVec2::ONE);
+    let columns = subdivisions.x + 1;
+
+    let mut vertices = (0..=subdivisions.y)
+        .flat_map(|row| {
+            (0..=subdivisions.x).map(move |column| {
+                let t = glam::uvec2(column, row).as_vec2() / subdivisions.as_vec2();
+
+                // Rows run from the far edge towards the near one, like texture rows
+                ModelVertex {
+                    pos: glam::vec3((t.x - 0.5) * size.x, 0., (0.5 - t.y) * size.y),
+                    uv: t * size,
+                    normal: glam::Vec3::Y,
+                    tangent: glam::Vec4::new(1., 0., 0., 1.),
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let indices = (0..subdivisions.y)
+        .flat_map(|row| {
+            (0..subdivisions.x).flat_map(move |column| {
+                let far = row * columns + column;
+                let near = far + columns;
+
+                [far, near + 1, far + 1, far, near, near + 1]
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Authored left handed Y up, like the cube
+    let convention = CoordinateConvention::active();
+    if convention != CoordinateConvention::LeftHandedYUp {
+        convert_mesh(
+            &mut vertices,
+            convention.conversion_from(CoordinateConvention::LeftHandedYUp),
+        );
+    }
+
+    (vertices, indices)
+}
+
+/// A 1x1 `plane` facing up, with the whole texture across it.
+#[inline]
+pub fn quad() -> (Vec<ModelVertex>, Vec<u32>) {
+    plane(glam::Vec2::ONE, glam::UVec2::ONE)
+}
+
+/// A sphere with a radius of 1 in the active `CoordinateConvention`, split into
+/// `rings` from pole to pole and `sectors` around the up axis. The texture wraps around
+/// once, with its top at the upper pole. Has `rings * sectors * 6` indices, including
+/// degenerate triangles at the poles.
+pub fn uv_sphere(rings: u32, sectors: u32) -> (Vec<ModelVertex>, Vec<u32>) {
+    let rings = rings.max(2);
+    let sectors = sectors.max(3);
+
+    // Each ring has an extra vertex where the texture's seam wraps around
+    let mut vertices = (0..=rings)
+        .flat_map(|ring| {
+            let v = ring as f32 / rings as f32;
+            let (ring_radius, y) = (v * std::f32::consts::PI).sin_cos();
+
+            (0..=sectors).map(move |sector| {
+                let u = sector as f32 / sectors as f32;
+                let (z, x) = (u * std::f32::consts::TAU).sin_cos();
+
+                let normal = glam::vec3(x * ring_radius, y, z * ring_radius);
+                ModelVertex {
+                    pos: normal,
+                    uv: glam::vec2(u, v),
+                    normal,
+                    // Around the up axis, defined at the poles too
+                    tangent: glam::vec4(-z, 0., x, 1.),
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let indices = (0..rings)
+        .flat_map(|ring| {
+            (0..sectors).flat_map(move |sector| {
+                let top = ring * (sectors + 1) + sector;
+                let bottom = top + sectors + 1;
+
+                [top, bottom + 1, top + 1, top, bottom, bottom + 1]
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Authored left handed Y up, like the cube
+    let convention = CoordinateConvention::active();
+    if convention != CoordinateConvention::LeftHandedYUp {
+        convert_mesh(
+            &mut vertices,
+            convention.conversion_from(CoordinateConvention::LeftHandedYUp),
+        );
+    }
+
+    (vertices, indices)
+}
+
+/// Transform mesh data by `transform`, such as to bake a node's transform into its
+/// mesh. Reverses the winding of every triangle if the transform mirrors the mesh.
+pub fn transform_mesh(vertices: &mut [ModelVertex], indices: &mut [u32], transform: glam::Mat4) {
+    let normal_matrix = glam::Mat3::from_mat4(transform).inverse().transpose();
+    let handedness = transform.determinant().signum();
+
+    vertices.iter_mut().for_each(|vertex| {
+        vertex.pos = transform.transform_point3(vertex.pos);
+        vertex.normal = (normal_matrix * vertex.normal).normalize_or_zero();
+        vertex.tangent = transform
+            .transform_vector3(vertex.tangent.truncate())
+            .normalize_or_zero()
+            .extend(vertex.tangent.w * handedness);
+    });
+
+    if handedness < 0. {
+        indices
+            .chunks_exact_mut(3)
+            .for_each(|triangle| triangle.swap(1, 2));
+    }
+}
+
+/// Replace the normals of mesh data with smooth normals, averaged from the faces
+/// around each vertex and weighted by their area. Triangles are expected to wind
+/// counter clockwise in right handed axes if `right_handed`, or left handed ones
+/// otherwise, such as `CoordinateConvention::is_right_handed` of the active convention.
+pub fn compute_normals(vertices: &mut [ModelVertex], indices: &[u32], right_handed: bool) {
+    let mut normals = vec![glam::Vec3::ZERO; vertices.len()];
+
+    indices.chunks_exact(3).for_each(|triangle| {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index as usize);
+        if a >= vertices.len() || b >= vertices.len() || c >= vertices.len() {
+            return;
+        }
+
+        // Unnormalized, so larger faces contribute more
+        let face = (vertices[b].pos - vertices[a].pos).cross(vertices[c].pos - vertices[a].pos);
+        let face = match right_handed {
+            true => face,
+            false => -face,
+        };
+
+        normals[a] += face;
+        normals[b] += face;
+        normals[c] += face;
+    });
+
+    vertices
+        .iter_mut()
+        .zip(normals)
+        .for_each(|(vertex, normal)| vertex.normal = normal.normalize_or_zero());
+}
+
+/// Apply an axis conversion (see `CoordinateConvention::conversion_from`) to mesh
+/// data. Winding is kept even if the conversion flips handedness - each convention's
+/// projection is mirrored the same way, so faces stay counter clockwise on screen.
+pub fn convert_mesh(vertices: &mut [ModelVertex], conversion: glam::Mat3) {
+    let handedness = conversion.determinant().signum();
+
+    vertices.iter_mut().for_each(|vertex| {
+        vertex.pos = conversion * vertex.pos;
+        vertex.normal = (conversion * vertex.normal).normalize_or_zero();
+        vertex.tangent = (conversion * vertex.tangent.truncate())
+            .normalize_or_zero()
+            .extend(vertex.tangent.w * handedness);
+    });
+}
+
+/// Replace the tangents of mesh data with ones following its texture coordinates,
+/// averaged from the faces around each vertex. Normals must already be set. Triangles
+/// with no uv area don't contribute.
+pub fn compute_tangents(vertices: &mut [ModelVertex], indices: &[u32]) {
+    let mut directions = vec![(glam::Vec3::ZERO, glam::Vec3::ZERO); vertices.len()];
+
+    indices.chunks_exact(3).for_each(|triangle| {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index as usize);
+        if a >= vertices.len() || b >= vertices.len() || c >= vertices.len() {
+            return;
+        }
+
+        let edge_1 = vertices[b].pos - vertices[a].pos;
+        let edge_2 = vertices[c].pos - vertices[a].pos;
+        let uv_1 = vertices[b].uv - vertices[a].uv;
+        let uv_2 = vertices[c].uv - vertices[a].uv;
+
+        let area = uv_1.perp_dot(uv_2);
+        if area.abs() <= f32::EPSILON {
+            return;
+        }
+
+        let u_direction = (edge_1 * uv_2.y - edge_2 * uv_1.y) / area;
+        let v_direction = (edge_2 * uv_1.x - edge_1 * uv_2.x) / area;
+
+        [a, b, c].into_iter().for_each(|index| {
+            directions[index].0 += u_direction;
+            directions[index].1 += v_direction;
+        });
+    });
+
+    vertices
+        .iter_mut()
+        .zip(directions)
+        .for_each(|(vertex, (u_direction, v_direction))| {
+            vertex.tangent = tangent_from_directions(vertex.normal, u_direction, v_direction);
+        });
+}
+
+/// A `ModelVertex::tangent` for a surface with `normal`, where u and v increase along
+/// `u_direction` and `v_direction`.
+pub fn tangent_from_directions(
+    normal: glam::Vec3,
+    u_direction: glam::Vec3,
+    v_direction: glam::Vec3,
+) -> glam::Vec4 {
+    let tangent = (u_direction - normal * normal.dot(u_direction)).normalize_or_zero();
+
+    let handedness = match normal.cross(tangent).dot(v_direction) < 0. {
+        true => -1.,
+        false => 1.,
+    };
+
+    tangent.extend(handedness)
+}
+
 //====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_tangents_eq(a: &[ModelVertex], b: &[ModelVertex]) {
+        a.iter().zip(b).for_each(|(a, b)| {
+            assert!(
+                a.tangent.abs_diff_eq(b.tangent, 1e-4),
+                "{} != {}",
+                a.tangent,
+                b.tangent
+            );
+        });
+    }
+
+    #[test]
+    fn cube_tangents_follow_uvs() {
+        let mut computed = CUBE_VERTICES;
+        compute_tangents(&mut computed, &CUBE_INDICES);
+
+        assert_tangents_eq(&CUBE_VERTICES, &computed);
+    }
+
+    #[test]
+    fn converted_tangents_follow_uvs() {
+        [
+            CoordinateConvention::RightHandedYUp,
+            CoordinateConvention::RightHandedZUp,
+        ]
+        .into_iter()
+        .for_each(|convention| {
+            let mut converted = CUBE_VERTICES;
+            convert_mesh(
+                &mut converted,
+                convention.conversion_from(CoordinateConvention::LeftHandedYUp),
+            );
+
+            let mut computed = converted;
+            compute_tangents(&mut computed, &CUBE_INDICES);
+
+            assert_tangents_eq(&converted, &computed);
+        });
+    }
+
+    #[test]
+    fn mirrored_tangents_keep_following_uvs() {
+        let mut vertices = CUBE_VERTICES;
+        let mut indices = CUBE_INDICES;
+        transform_mesh(
+            &mut vertices,
+            &mut indices,
+            glam::Mat4::from_scale(glam::vec3(-2., 1., 1.)),
+        );
+
+        let mut computed = vertices;
+        compute_tangents(&mut computed, &indices);
+
+        assert_tangents_eq(&vertices, &computed);
+    }
+
+    /// Check every non degenerate triangle is wound the same way relative to its
+    /// vertex normals as the cube is.
+    fn assert_wound_like_cube(vertices: &[ModelVertex], indices: &[u32]) {
+        let facing = |vertices: &[ModelVertex], triangle: &[u32]| {
+            let [a, b, c] = [0, 1, 2].map(|corner| &vertices[triangle[corner] as usize]);
+            let cross = (b.pos - a.pos).cross(c.pos - a.pos);
+
+            (cross.length() > 1e-6).then(|| cross.dot(a.normal + b.normal + c.normal) > 0.)
+        };
+
+        let cube = facing(&CUBE_VERTICES, &CUBE_INDICES[..3]).unwrap();
+
+        indices
+            .chunks_exact(3)
+            .filter_map(|triangle| facing(vertices, triangle))
+            .for_each(|outward| assert_eq!(outward, cube));
+    }
+
+    #[test]
+    fn uv_sphere_counts() {
+        [(2, 3), (8, 16), (17, 5)]
+            .into_iter()
+            .for_each(|(rings, sectors)| {
+                let (vertices, indices) = uv_sphere(rings, sectors);
+
+                assert_eq!(vertices.len() as u32, (rings + 1) * (sectors + 1));
+                assert_eq!(indices.len() as u32, rings * sectors * 6);
+                assert!(indices
+                    .iter()
+                    .all(|index| (*index as usize) < vertices.len()));
+            });
+
+        // Clamped to the smallest closed sphere
+        assert_eq!(uv_sphere(0, 0).1.len(), 2 * 3 * 6);
+    }
+
+    #[test]
+    fn uv_sphere_is_a_unit_sphere() {
+        let (vertices, indices) = uv_sphere(12, 24);
+
+        vertices.iter().for_each(|vertex| {
+            assert!((vertex.pos.length() - 1.).abs() < 1e-5);
+            assert!((vertex.normal.length() - 1.).abs() < 1e-5);
+            assert!(vertex.normal.abs_diff_eq(vertex.pos, 1e-5));
+            assert!(
+                vertex.uv.cmpge(glam::Vec2::ZERO).all() && vertex.uv.cmple(glam::Vec2::ONE).all()
+            );
+        });
+
+        assert_wound_like_cube(&vertices, &indices);
+    }
+
+    #[test]
+    fn plane_counts() {
+        [(1, 1), (4, 2), (3, 7)].into_iter().for_each(|(x, y)| {
+            let (vertices, indices) = plane(glam::Vec2::ONE, glam::uvec2(x, y));
+
+            assert_eq!(vertices.len() as u32, (x + 1) * (y + 1));
+            assert_eq!(indices.len() as u32, x * y * 6);
+            assert!(indices
+                .iter()
+                .all(|index| (*index as usize) < vertices.len()));
+        });
+
+        // No subdivisions is a single quad
+        assert_eq!(plane(glam::Vec2::ONE, glam::UVec2::ZERO).0.len(), 4);
+    }
+
+    #[test]
+    fn plane_spans_its_size_facing_up() {
+        let size = glam::vec2(6., 4.);
+        let (vertices, indices) = plane(size, glam::uvec2(3, 2));
+
+        let (min, max) = vertices
+            .iter()
+            .fold((glam::Vec3::MAX, glam::Vec3::MIN), |(min, max), vertex| {
+                (min.min(vertex.pos), max.max(vertex.pos))
+            });
+        assert_eq!(min, glam::vec3(-3., 0., -2.));
+        assert_eq!(max, glam::vec3(3., 0., 2.));
+
+        // Uvs count world units
+        let (min, max) = vertices
+            .iter()
+            .fold((glam::Vec2::MAX, glam::Vec2::MIN), |(min, max), vertex| {
+                (min.min(vertex.uv), max.max(vertex.uv))
+            });
+        assert_eq!((min, max), (glam::Vec2::ZERO, size));
+
+        assert!(vertices.iter().all(|vertex| vertex.normal == glam::Vec3::Y));
+        assert_wound_like_cube(&vertices, &indices);
+    }
+
+    #[test]
+    fn quad_covers_the_whole_texture() {
+        let (vertices, indices) = quad();
+
+        let mut uvs = vertices
+            .iter()
+            .map(|vertex| vertex.uv.to_array())
+            .collect::<Vec<_>>();
+        uvs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(uvs, [[0., 0.], [0., 1.], [1., 0.], [1., 1.]]);
+
+        assert_eq!(indices.len(), 6);
+        assert_wound_like_cube(&vertices, &indices);
+    }
+}
@@ -0,0 +1,232 @@
+//====================================================================
+
+use std::{
+    collections::HashMap,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use roots_common::coords::CoordinateConvention;
+
+use crate::{
+    model::{self, LoadedMesh, ModelVertex},
+    shared::SharedRenderResources,
+    texture::{LoadedTexture, Texture},
+    Error,
+};
+
+//====================================================================
+
+/// The faces of an OBJ file using one material, with its diffuse texture and color.
+#[derive(Clone, Debug)]
+pub struct ObjMesh {
+    pub material: Option<String>,
+    pub mesh: LoadedMesh,
+    pub texture: LoadedTexture,
+    pub diffuse: [f32; 4],
+}
+
+//====================================================================
+
+/// Load an .obj file, with one mesh per material. Material libraries and textures are
+/// resolved relative to the file. OBJ has no fixed axes, so files are taken to be
+/// right handed Y up, as most tools export them, and converted into the active
+/// `CoordinateConvention`.
+pub fn load(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    path: impl AsRef<Path>,
+) -> Result<Vec<ObjMesh>, Error> {
+    let path = path.as_ref();
+    log::debug!("Loading obj '{}'", path.display());
+
+    let bytes = std::fs::read(path)
+        .map_err(|e| Error::Obj(format!("unable to read '{}': {}", path.display(), e)))?;
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    load_from_slice(device, queue, shared, &bytes, |name| {
+        let path = directory.join(name);
+        std::fs::read(&path)
+            .map_err(|e| log::warn!("Unable to read '{}': {}", path.display(), e))
+            .ok()
+    })
+}
+
+/// Load an .obj file from memory. `resolve` returns the contents of the material
+/// libraries and textures it names, or `None` if they can't be found. Missing material
+/// libraries and textures are skipped with a warning.
+pub fn load_from_slice(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    bytes: &[u8],
+    resolve: impl Fn(&Path) -> Option<Vec<u8>>,
+) -> Result<Vec<ObjMesh>, Error> {
+    let options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+    };
+
+    let (models, materials) = tobj::load_obj_buf(&mut BufReader::new(bytes), &options, |path| {
+        match resolve(path) {
+            Some(mtl) => tobj::load_mtl_buf(&mut BufReader::new(mtl.as_slice())),
+            None => Err(tobj::LoadError::OpenFileFailed),
+        }
+    })
+    .map_err(|e| Error::Obj(e.to_string()))?;
+
+    let materials = materials.unwrap_or_else(|e| {
+        log::warn!("Unable to load obj materials: {}", e);
+        Vec::new()
+    });
+
+    // Faces are grouped by material, so each material is drawn once
+    let mut groups: Vec<(Option<usize>, Vec<ModelVertex>, Vec<u32>)> = Vec::new();
+
+    models.iter().for_each(|obj_model| {
+        let mesh = &obj_model.mesh;
+        let material_id = mesh.material_id.filter(|id| *id < materials.len());
+
+        let index = match groups.iter().position(|(id, ..)| *id == material_id) {
+            Some(index) => index,
+            None => {
+                groups.push((material_id, Vec::new(), Vec::new()));
+                groups.len() - 1
+            }
+        };
+
+        let (_, vertices, indices) = &mut groups[index];
+        append_mesh(mesh, vertices, indices);
+    });
+
+    let conversion =
+        CoordinateConvention::active().conversion_from(CoordinateConvention::RightHandedYUp);
+
+    let mut textures = HashMap::new();
+    let mut blank = None;
+
+    let meshes = groups
+        .into_iter()
+        .filter(|(_, _, indices)| !indices.is_empty())
+        .map(|(material_id, mut vertices, indices)| {
+            model::convert_mesh(&mut vertices, conversion);
+
+            let material = material_id.map(|id| &materials[id]);
+
+            let texture = material
+                .and_then(|material| material.diffuse_texture.as_deref())
+                .and_then(|name| load_texture(device, queue, shared, &resolve, name, &mut textures))
+                .unwrap_or_else(|| {
+                    blank
+                        .get_or_insert_with(|| LoadedTexture::load_blank(device, queue, shared))
+                        .clone()
+                });
+
+            let diffuse = material
+                .map(|material| {
+                    let [r, g, b] = material.diffuse.unwrap_or([1., 1., 1.]);
+                    [r, g, b, material.dissolve.unwrap_or(1.)]
+                })
+                .unwrap_or([1., 1., 1., 1.]);
+
+            ObjMesh {
+                material: material.map(|material| material.name.clone()),
+                mesh: LoadedMesh::load_from_data(device, &vertices, &indices),
+                texture,
+                diffuse,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    log::trace!("Loaded obj with {} meshes", meshes.len());
+
+    Ok(meshes)
+}
+
+//====================================================================
+
+/// Add the triangles of `mesh` to `vertices` and `indices`, computing normals if the
+/// file has none and tangents from the texture coordinates.
+fn append_mesh(mesh: &tobj::Mesh, vertices: &mut Vec<ModelVertex>, indices: &mut Vec<u32>) {
+    let has_normals = mesh.normals.len() == mesh.positions.len();
+    let has_uvs = mesh.texcoords.len() / 2 == mesh.positions.len() / 3;
+
+    let mut mesh_vertices = mesh
+        .positions
+        .chunks_exact(3)
+        .enumerate()
+        .map(|(index, pos)| ModelVertex {
+            pos: glam::Vec3::from_slice(pos),
+            // OBJ texture coordinates start at the bottom left
+            uv: match has_uvs {
+                true => glam::vec2(
+                    mesh.texcoords[index * 2],
+                    1. - mesh.texcoords[index * 2 + 1],
+                ),
+                false => glam::Vec2::ZERO,
+            },
+            normal: match has_normals {
+                true => glam::Vec3::from_slice(&mesh.normals[index * 3..]),
+                false => glam::Vec3::ZERO,
+            },
+            tangent: glam::Vec4::ZERO,
+        })
+        .collect::<Vec<_>>();
+
+    // OBJ faces are wound counter clockwise in right handed axes
+    if !has_normals {
+        model::compute_normals(&mut mesh_vertices, &mesh.indices, true);
+    }
+
+    // OBJ has no tangents, so they always follow the texture coordinates
+    model::compute_tangents(&mut mesh_vertices, &mesh.indices);
+
+    let offset = vertices.len() as u32;
+    vertices.extend(mesh_vertices);
+    indices.extend(mesh.indices.iter().map(|index| index + offset));
+}
+
+fn load_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    resolve: &impl Fn(&Path) -> Option<Vec<u8>>,
+    name: &str,
+    textures: &mut HashMap<PathBuf, LoadedTexture>,
+) -> Option<LoadedTexture> {
+    let path = PathBuf::from(name);
+    if let Some(loaded) = textures.get(&path) {
+        return Some(loaded.clone());
+    }
+
+    let bytes = resolve(&path)?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| log::warn!("Unable to decode obj texture '{}': {}", name, e))
+        .ok()?;
+
+    let loaded = LoadedTexture::load_texture(
+        device,
+        shared,
+        Texture::from_image(
+            device,
+            queue,
+            &image,
+            Some(name),
+            Some(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+        ),
+    );
+
+    textures.insert(path, loaded.clone());
+    Some(loaded)
+}
+
+//====================================================================
@@ -0,0 +1,231 @@
+//====================================================================
+
+use roots_common::Size;
+
+use crate::{
+    memory::MemoryCategory,
+    shared::SharedRenderResources,
+    texture::{LoadedTexture, Texture},
+    Color, RenderEncoder, RenderPassDesc,
+};
+
+//====================================================================
+
+/// A fixed size target the scene can be rendered into instead of the surface, such as
+/// for a minimap, mirror or security camera screen.
+///
+/// Passes render into an internal texture that `present` copies into `color`, so the
+/// result can be drawn by the same pipelines that render into the target without
+/// sampling a texture that's also attached. Created with the format and sample count
+/// of the pipelines that will draw into it, and with depth in the shared
+/// `DepthConvention`.
+pub struct OffscreenTarget {
+    color: LoadedTexture,
+    render: Texture,
+    multisampled: Option<Texture>,
+    depth: Texture,
+    format: wgpu::TextureFormat,
+    size: Size<u32>,
+    label: String,
+}
+
+impl OffscreenTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        shared: &SharedRenderResources,
+        format: wgpu::TextureFormat,
+        size: Size<u32>,
+        label: &str,
+    ) -> Self {
+        log::debug!("Creating offscreen target '{}' with size {}", label, size);
+
+        let size = Size::new(size.width.max(1), size.height.max(1));
+        let (color, render, multisampled, depth) =
+            Self::create_textures(device, shared, format, size, label);
+
+        Self {
+            color,
+            render,
+            multisampled,
+            depth,
+            format,
+            size,
+            label: label.to_string(),
+        }
+    }
+
+    fn create_textures(
+        device: &wgpu::Device,
+        shared: &SharedRenderResources,
+        format: wgpu::TextureFormat,
+        size: Size<u32>,
+        label: &str,
+    ) -> (LoadedTexture, Texture, Option<Texture>, Texture) {
+        let sample_count = shared.sample_count();
+
+        let color = create_texture(
+            device,
+            format,
+            size,
+            1,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            &format!("Offscreen Texture: {}", label),
+        );
+        let color = LoadedTexture::load_texture(device, shared, color);
+
+        let render = create_texture(
+            device,
+            format,
+            size,
+            1,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            &format!("Offscreen Render Texture: {}", label),
+        );
+
+        let multisampled = (sample_count > 1).then(|| {
+            create_texture(
+                device,
+                format,
+                size,
+                sample_count,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+                &format!("Offscreen Multisampled Texture: {}", label),
+            )
+        });
+
+        let depth = Texture::create_depth_texture_with(
+            device,
+            size,
+            shared.depth_convention(),
+            sample_count,
+            Some(&format!("Offscreen {}", label)),
+        );
+
+        (color, render, multisampled, depth)
+    }
+
+    /// Recreate the textures at `size`. `color` becomes a new texture, so handles to
+    /// the old one keep showing its last contents.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        shared: &SharedRenderResources,
+        size: Size<u32>,
+    ) {
+        let size = Size::new(size.width.max(1), size.height.max(1));
+        if size == self.size {
+            return;
+        }
+
+        let (color, render, multisampled, depth) =
+            Self::create_textures(device, shared, self.format, size, &self.label);
+
+        self.color = color;
+        self.render = render;
+        self.multisampled = multisampled;
+        self.depth = depth;
+        self.size = size;
+    }
+
+    /// The rendered result as of the last `present`, for drawing like any other texture.
+    #[inline]
+    pub fn color(&self) -> &LoadedTexture {
+        &self.color
+    }
+
+    /// The view passes render (or resolve) into.
+    #[inline]
+    pub fn render_view(&self) -> &wgpu::TextureView {
+        &self.render.view
+    }
+
+    #[inline]
+    pub(crate) fn render_texture(&self) -> &wgpu::Texture {
+        &self.render.texture
+    }
+
+    #[inline]
+    pub fn multisampled_view(&self) -> Option<&wgpu::TextureView> {
+        self.multisampled.as_ref().map(|texture| &texture.view)
+    }
+
+    #[inline]
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.depth.view
+    }
+
+    #[inline]
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+
+    /// A pass rendering into the target, clearing it to `clear_color` and clearing
+    /// depth in the shared `DepthConvention`.
+    pub fn render_pass_desc(
+        &self,
+        shared: &SharedRenderResources,
+        clear_color: Option<Color>,
+    ) -> RenderPassDesc<'_> {
+        RenderPassDesc {
+            color_target: Some(self.render_view()),
+            multisampled: self.multisampled_view(),
+            use_depth: Some(self.depth_view()),
+            clear_color,
+            clear_depth: true,
+            depth_convention: *shared.depth_convention(),
+        }
+    }
+
+    /// Copy what was rendered into `color`. Call after the target's passes, in the
+    /// same encoder as or an earlier one than any passes drawing `color`.
+    pub fn present(&self, encoder: &mut RenderEncoder) {
+        encoder.copy_texture(self.render_texture(), &self.color.texture().texture);
+    }
+}
+
+fn create_texture(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    size: Size<u32>,
+    sample_count: u32,
+    usage: wgpu::TextureUsages,
+    label: &str,
+) -> Texture {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage,
+        view_formats: &[],
+    });
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some(label),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    Texture::new(
+        texture,
+        view,
+        sampler,
+        MemoryCategory::RenderTargets,
+        Some(label),
+    )
+}
+
+//====================================================================
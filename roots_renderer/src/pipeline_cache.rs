@@ -0,0 +1,106 @@
+//====================================================================
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+//====================================================================
+
+/// A `wgpu::PipelineCache` kept in a file between launches, so pipelines compiled by
+/// one run are loaded instead of compiled again by the next. Only available natively,
+/// on adapters with `Features::PIPELINE_CACHE` (currently Vulkan).
+///
+/// The file is named by `wgpu::util::pipeline_cache_key`, so caches of different
+/// adapters can share a directory. Data from an older driver or wgpu version is
+/// ignored and replaced on the next `save`.
+pub struct PersistentPipelineCache {
+    cache: Arc<wgpu::PipelineCache>,
+    path: PathBuf,
+}
+
+impl PersistentPipelineCache {
+    /// Load the cache of `adapter_info` from `directory`, or start an empty one if there
+    /// isn't one yet. `None` if the device can't use pipeline caches.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(
+        device: &wgpu::Device,
+        adapter_info: &wgpu::AdapterInfo,
+        directory: &Path,
+    ) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::PIPELINE_CACHE) {
+            log::debug!("Pipeline caches aren't supported by the device");
+            return None;
+        }
+
+        let path = directory.join(wgpu::util::pipeline_cache_key(adapter_info)?);
+
+        let data = match std::fs::read(&path) {
+            Ok(data) => {
+                log::debug!("Loaded pipeline cache {:?} ({} B)", path, data.len());
+                Some(data)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                log::warn!("Unable to read pipeline cache {:?}: {}", path, e);
+                None
+            }
+        };
+
+        // Safety - the data was written by `save`, from a cache of an adapter with the
+        // same key. Anything wgpu can't use is discarded thanks to `fallback`.
+        let cache = unsafe {
+            device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: Some("Persistent Pipeline Cache"),
+                data: data.as_deref(),
+                fallback: true,
+            })
+        };
+
+        Some(Self {
+            cache: Arc::new(cache),
+            path,
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(
+        _device: &wgpu::Device,
+        _adapter_info: &wgpu::AdapterInfo,
+        _directory: &Path,
+    ) -> Option<Self> {
+        None
+    }
+
+    #[inline]
+    pub fn cache(&self) -> &Arc<wgpu::PipelineCache> {
+        &self.cache
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the cache back to its file. Written to a temporary file first and moved over
+    /// the old one, so a crash mid write can't leave a corrupt cache.
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(data) = self.cache.get_data() else {
+            return Ok(());
+        };
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let temp_path = self.path.with_extension("temp");
+        std::fs::write(&temp_path, &data)?;
+        std::fs::rename(&temp_path, &self.path)?;
+
+        log::debug!("Saved pipeline cache {:?} ({} B)", self.path, data.len());
+
+        Ok(())
+    }
+}
+
+//====================================================================
@@ -0,0 +1,40 @@
+//====================================================================
+// Lighting - a fixed size uniform array for devices without storage buffers
+
+struct GlobalLightData {
+    ambient_color: vec3<f32>,
+    ambient_strength: f32,
+}
+
+// Must match `LIGHT_TYPE_DIRECTIONAL` and `LIGHT_TYPE_POINT`
+const LIGHT_DIRECTIONAL: u32 = 0u;
+const LIGHT_POINT: u32 = 1u;
+
+// See `LightInstance`
+struct Light {
+    // w - falloff radius of point lights, 0 for none
+    position: vec4<f32>,
+    direction: vec3<f32>,
+    light_type: u32,
+    diffuse_color: vec4<f32>,
+    specular_color: vec4<f32>,
+}
+
+// Must match `MAX_UNIFORM_LIGHTS`
+struct LightArray {
+    lights: array<Light, 16>,
+    count: u32,
+}
+
+@group(1) @binding(0) var<uniform> global_lighting: GlobalLightData;
+@group(1) @binding(1) var<uniform> light_array: LightArray;
+
+fn light_count() -> i32 {
+    return i32(min(light_array.count, 16u));
+}
+
+fn get_light(index: i32) -> Light {
+    return light_array.lights[index];
+}
+
+//====================================================================
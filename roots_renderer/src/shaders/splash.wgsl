@@ -0,0 +1,50 @@
+//====================================================================
+// Uniforms
+
+struct Layer {
+    // Left, bottom, right, top in clip space
+    rect: vec4<f32>,
+    color: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> layer: Layer;
+@group(0) @binding(1) var texture: texture_2d<f32>;
+@group(0) @binding(2) var texture_sampler: sampler;
+
+
+//====================================================================
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+//====================================================================
+
+// Two triangles covering the layer rect, without any vertex buffers
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOut {
+    var out: VertexOut;
+
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0., 0.),
+        vec2<f32>(1., 0.),
+        vec2<f32>(1., 1.),
+        vec2<f32>(0., 0.),
+        vec2<f32>(1., 1.),
+        vec2<f32>(0., 1.),
+    );
+    let corner = corners[index];
+
+    out.clip_position = vec4<f32>(mix(layer.rect.xy, layer.rect.zw, corner), 0., 1.);
+    out.uv = vec2<f32>(corner.x, 1. - corner.y);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    return textureSample(texture, texture_sampler, in.uv) * layer.color;
+}
+
+//====================================================================
@@ -1,9 +1,11 @@
 //====================================================================
 
+use std::sync::Arc;
+
 use crate::{
     camera::{Camera, CameraUniform},
+    layouts::{self, LayoutRegistry},
     texture::Texture,
-    tools,
 };
 
 //====================================================================
@@ -14,55 +16,273 @@ pub trait Vertex: bytemuck::Pod {
 
 //====================================================================
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthFormat {
+    #[default]
+    Depth32Float,
+    Depth24PlusStencil8,
+}
+
+impl DepthFormat {
+    #[inline]
+    pub fn texture_format(&self) -> wgpu::TextureFormat {
+        match self {
+            DepthFormat::Depth32Float => wgpu::TextureFormat::Depth32Float,
+            DepthFormat::Depth24PlusStencil8 => wgpu::TextureFormat::Depth24PlusStencil8,
+        }
+    }
+
+    #[inline]
+    pub fn has_stencil(&self) -> bool {
+        matches!(self, DepthFormat::Depth24PlusStencil8)
+    }
+}
+
+/// Depth buffer format and direction used by every pipeline, depth texture and
+/// camera created from the same `SharedRenderResources`.
+///
+/// With `reversed_z`, depth is cleared to 0.0 and nearer fragments have larger
+/// depth values, which gives far better precision for large view distances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DepthConvention {
+    pub format: DepthFormat,
+    pub reversed_z: bool,
+}
+
+impl DepthConvention {
+    pub const STANDARD: Self = Self {
+        format: DepthFormat::Depth32Float,
+        reversed_z: false,
+    };
+
+    pub const REVERSED: Self = Self {
+        format: DepthFormat::Depth32Float,
+        reversed_z: true,
+    };
+
+    #[inline]
+    pub fn with_format(mut self, format: DepthFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    #[inline]
+    pub fn texture_format(&self) -> wgpu::TextureFormat {
+        self.format.texture_format()
+    }
+
+    /// Value the depth buffer is cleared to at the start of a pass.
+    #[inline]
+    pub fn clear_value(&self) -> f32 {
+        match self.reversed_z {
+            true => 0.,
+            false => 1.,
+        }
+    }
+
+    /// Compare function passing fragments nearer than the stored depth.
+    #[inline]
+    pub fn compare(&self) -> wgpu::CompareFunction {
+        match self.reversed_z {
+            true => wgpu::CompareFunction::Greater,
+            false => wgpu::CompareFunction::Less,
+        }
+    }
+
+    /// Compare function passing fragments nearer than or equal to the stored depth.
+    #[inline]
+    pub fn compare_equal(&self) -> wgpu::CompareFunction {
+        match self.reversed_z {
+            true => wgpu::CompareFunction::GreaterEqual,
+            false => wgpu::CompareFunction::LessEqual,
+        }
+    }
+
+    #[inline]
+    pub fn depth_stencil_state(
+        &self,
+        depth_write_enabled: bool,
+        depth_compare: wgpu::CompareFunction,
+    ) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: self.texture_format(),
+            depth_write_enabled,
+            depth_compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+
+    /// Depth bias pulling fragments toward the camera, such as for decals drawn onto
+    /// coplanar geometry. `constant` is in units of the depth format's precision.
+    #[inline]
+    pub fn bias_toward_camera(&self, constant: i32, slope_scale: f32) -> wgpu::DepthBiasState {
+        let sign = match self.reversed_z {
+            true => 1,
+            false => -1,
+        };
+
+        wgpu::DepthBiasState {
+            constant: constant * sign,
+            slope_scale: slope_scale * sign as f32,
+            clamp: 0.,
+        }
+    }
+
+    /// Remap a standard `[0, 1]` depth projection into this convention.
+    /// Works for perspective (including infinite far) and orthographic projections.
+    #[inline]
+    pub fn adjust_projection(&self, projection: glam::Mat4) -> glam::Mat4 {
+        match self.reversed_z {
+            true => REVERSE_Z * projection,
+            false => projection,
+        }
+    }
+}
+
+/// Maps clip space depth `z` to `w - z`, flipping the `[0, 1]` depth range.
+const REVERSE_Z: glam::Mat4 = glam::Mat4::from_cols(
+    glam::Vec4::X,
+    glam::Vec4::Y,
+    glam::Vec4::new(0., 0., -1., 0.),
+    glam::Vec4::new(0., 0., 1., 1.),
+);
+
+//====================================================================
+
+/// Bind group layouts shared by every pipeline, registered by name in a
+/// `LayoutRegistry`. Cheap to clone, so a copy can be moved to worker threads to
+/// create textures there.
+#[derive(Clone)]
 pub struct SharedRenderResources {
-    texture_bind_group_layout: wgpu::BindGroupLayout,
-    camera_bind_group_layout: wgpu::BindGroupLayout,
+    layouts: Arc<LayoutRegistry>,
+    depth_convention: DepthConvention,
+    sample_count: u32,
+    pipeline_cache: Option<Arc<wgpu::PipelineCache>>,
 }
 
 impl SharedRenderResources {
+    #[inline]
     pub fn new(device: &wgpu::Device) -> Self {
-        log::debug!("Creating shared render resources");
-
-        let texture_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Shared Texture 3d Bind Group Layout"),
-                entries: &[
-                    tools::bgl_entry(tools::BgEntryType::Texture, 0, wgpu::ShaderStages::FRAGMENT),
-                    tools::bgl_entry(tools::BgEntryType::Sampler, 1, wgpu::ShaderStages::FRAGMENT),
-                ],
-            });
+        Self::new_with_depth(device, DepthConvention::default())
+    }
 
-        let camera_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Camera Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
+    pub fn new_with_depth(device: &wgpu::Device, depth_convention: DepthConvention) -> Self {
+        log::debug!(
+            "Creating shared render resources with depth convention {:?}",
+            depth_convention
+        );
 
         Self {
-            texture_bind_group_layout,
-            camera_bind_group_layout,
+            layouts: Arc::new(LayoutRegistry::new(device)),
+            depth_convention,
+            sample_count: 1,
+            pipeline_cache: None,
         }
     }
 }
 
 impl SharedRenderResources {
+    #[inline]
+    pub fn layouts(&self) -> &LayoutRegistry {
+        &self.layouts
+    }
+
+    /// The layout registered as `name`.
+    ///
+    /// # Panics
+    /// If no layout is registered as `name`.
+    #[inline]
+    pub fn layout(&self, name: &str) -> &wgpu::BindGroupLayout {
+        self.layouts
+            .get(name)
+            .unwrap_or_else(|| panic!("No bind group layout registered as '{}'", name))
+    }
+
+    /// A handle to the layout registered as `name`, for types that keep it to create
+    /// bind groups later.
+    ///
+    /// # Panics
+    /// If no layout is registered as `name`.
+    #[inline]
+    pub fn clone_layout(&self, name: &str) -> Arc<wgpu::BindGroupLayout> {
+        self.layouts
+            .clone_layout(name)
+            .unwrap_or_else(|| panic!("No bind group layout registered as '{}'", name))
+    }
+
+    /// Register a layout for pipelines outside roots to share. Only copies of the
+    /// resources made afterwards see it.
+    #[inline]
+    pub fn register_layout(
+        &mut self,
+        device: &wgpu::Device,
+        name: &'static str,
+        entries: &[wgpu::BindGroupLayoutEntry],
+    ) {
+        Arc::make_mut(&mut self.layouts).register(device, name, entries);
+    }
+
     #[inline]
     pub fn texture_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
-        &self.texture_bind_group_layout
+        self.layout(layouts::TEXTURE)
+    }
+
+    #[inline]
+    pub fn texture_array_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        self.layout(layouts::TEXTURE_ARRAY)
     }
 
     #[inline]
     pub fn camera_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
-        &self.camera_bind_group_layout
+        self.layout(layouts::CAMERA)
+    }
+
+    #[inline]
+    pub fn depth_convention(&self) -> &DepthConvention {
+        &self.depth_convention
+    }
+
+    /// Change the depth convention. Only affects pipelines, cameras and depth
+    /// textures created afterwards.
+    #[inline]
+    pub fn set_depth_convention(&mut self, depth_convention: DepthConvention) {
+        self.depth_convention = depth_convention;
+    }
+
+    /// Samples per pixel of the main color and depth targets. Every pipeline drawn
+    /// into them must be created with the same count, using `multisample`.
+    #[inline]
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Change the sample count. Only affects pipelines and targets created afterwards.
+    #[inline]
+    pub fn set_sample_count(&mut self, sample_count: u32) {
+        self.sample_count = sample_count.max(1);
+    }
+
+    /// Multisample state matching `sample_count`, for `RenderPipelineDescriptor`.
+    #[inline]
+    pub fn multisample(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: self.sample_count,
+            ..Default::default()
+        }
+    }
+
+    /// Cache pipelines should be created with, for `RenderPipelineDescriptor::cache`.
+    /// See `PersistentPipelineCache`.
+    #[inline]
+    pub fn pipeline_cache(&self) -> Option<&wgpu::PipelineCache> {
+        self.pipeline_cache.as_deref()
+    }
+
+    /// Only affects pipelines created afterwards.
+    #[inline]
+    pub fn set_pipeline_cache(&mut self, pipeline_cache: Option<Arc<wgpu::PipelineCache>>) {
+        self.pipeline_cache = pipeline_cache;
     }
 }
 
@@ -75,7 +295,30 @@ impl SharedRenderResources {
     ) -> wgpu::BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             label,
-            layout: &self.texture_bind_group_layout,
+            layout: self.texture_bind_group_layout(),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        })
+    }
+
+    /// Create a bind group for a `D2Array` texture such as one from `Texture::from_image_array`.
+    pub fn create_texture_array_bind_group(
+        &self,
+        device: &wgpu::Device,
+        texture: &Texture,
+        label: Option<&str>,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label,
+            layout: self.texture_array_bind_group_layout(),
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -91,8 +334,84 @@ impl SharedRenderResources {
 
     #[inline]
     pub fn create_camera<C: CameraUniform>(&self, device: &wgpu::Device, data: &C) -> Camera {
-        Camera::new(device, data, &self.camera_bind_group_layout)
+        let mut camera = Camera::new(device, data, self.camera_bind_group_layout());
+        camera.set_depth_convention(self.depth_convention);
+        camera
     }
 }
 
 //====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(convention: &DepthConvention, projection: glam::Mat4, distance: f32) -> f32 {
+        let clip = convention.adjust_projection(projection) * glam::vec4(0., 0., distance, 1.);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn compare_functions_mirror_each_other() {
+        assert_eq!(
+            DepthConvention::STANDARD.compare(),
+            wgpu::CompareFunction::Less
+        );
+        assert_eq!(
+            DepthConvention::REVERSED.compare(),
+            wgpu::CompareFunction::Greater
+        );
+        assert_eq!(
+            DepthConvention::STANDARD.compare_equal(),
+            wgpu::CompareFunction::LessEqual
+        );
+        assert_eq!(
+            DepthConvention::REVERSED.compare_equal(),
+            wgpu::CompareFunction::GreaterEqual
+        );
+    }
+
+    #[test]
+    fn clear_value_is_the_far_plane() {
+        let projection = glam::Mat4::perspective_lh(1., 1., 0.1, 100.);
+
+        [DepthConvention::STANDARD, DepthConvention::REVERSED]
+            .iter()
+            .for_each(|convention| {
+                let far = depth(convention, projection, 100.);
+                assert!((far - convention.clear_value()).abs() < 1e-5);
+            });
+    }
+
+    #[test]
+    fn reversed_projection_flips_depth_range() {
+        let projection = glam::Mat4::perspective_lh(1., 1., 0.1, 100.);
+        let reversed = DepthConvention::REVERSED;
+
+        assert!((depth(&reversed, projection, 0.1) - 1.).abs() < 1e-5);
+        assert!(depth(&reversed, projection, 100.).abs() < 1e-5);
+        assert!(depth(&reversed, projection, 10.) > depth(&reversed, projection, 20.));
+
+        let infinite = glam::Mat4::perspective_infinite_lh(1., 1., 0.1);
+        assert!((depth(&reversed, infinite, 0.1) - 1.).abs() < 1e-5);
+        assert!(depth(&reversed, infinite, 1e9) < 1e-5);
+    }
+
+    #[test]
+    fn reversed_z_separates_distant_geometry() {
+        // The default view distance, with geometry at 10 and 100,000 units
+        let projection = glam::Mat4::perspective_lh(1., 1., 0.1, 1000000.);
+        let standard = DepthConvention::STANDARD;
+        let reversed = DepthConvention::REVERSED;
+
+        assert!(depth(&standard, projection, 10.) < depth(&standard, projection, 10.01));
+        assert!(depth(&reversed, projection, 10.) > depth(&reversed, projection, 10.01));
+
+        // Standard depth has no precision left this far out, so the surfaces z-fight
+        assert_eq!(
+            depth(&standard, projection, 100000.),
+            depth(&standard, projection, 100010.)
+        );
+        assert!(depth(&reversed, projection, 100000.) > depth(&reversed, projection, 100010.));
+    }
+}
@@ -0,0 +1,349 @@
+//====================================================================
+
+use std::sync::Arc;
+
+use roots_common::Size;
+use web_time::Instant;
+
+use crate::{
+    texture::Texture,
+    tools::{self, BgEntryType},
+    Color, RenderEncoder, RenderPassDesc,
+};
+
+//====================================================================
+
+const SHADER: &str = include_str!("shaders/splash.wgsl");
+
+/// Largest fraction of the screen the logo covers. Larger logos are scaled down.
+const LOGO_MAX_COVERAGE: f32 = 0.8;
+
+//====================================================================
+
+/// Shown from as soon as the renderer exists until the app is ready, so the window
+/// isn't left blank while pipelines compile and assets load.
+#[derive(Debug, Clone)]
+pub struct SplashScreen {
+    pub clear_color: Color,
+    /// Encoded image bytes in any format the `image` crate reads, drawn in the centre at
+    /// its own pixel size. Logos too big for the screen are scaled down.
+    pub logo: Option<Arc<[u8]>>,
+    /// Seconds to fade into the app's first frames. Zero cuts straight to them.
+    pub fade_out: f32,
+}
+
+impl Default for SplashScreen {
+    fn default() -> Self {
+        Self {
+            clear_color: Color::new(0., 0., 0., 1.),
+            logo: None,
+            fade_out: 0.3,
+        }
+    }
+}
+
+impl SplashScreen {
+    #[inline]
+    pub fn new(clear_color: Color) -> Self {
+        Self {
+            clear_color,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn with_logo(mut self, bytes: impl Into<Arc<[u8]>>) -> Self {
+        self.logo = Some(bytes.into());
+        self
+    }
+
+    #[inline]
+    pub fn with_fade_out(mut self, seconds: f32) -> Self {
+        self.fade_out = seconds;
+        self
+    }
+}
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+struct SplashLayerRaw {
+    rect: [f32; 4],
+    color: [f32; 4],
+}
+
+/// A textured rect with its own uniform, so layers can be drawn in the same submission.
+struct SplashLayer {
+    uniform: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// Pixel size of the texture. `None` covers the whole screen.
+    size: Option<Size<u32>>,
+}
+
+impl SplashLayer {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        texture: &Texture,
+        size: Option<Size<u32>>,
+    ) -> Self {
+        let uniform = tools::create_buffer(
+            device,
+            tools::BufferType::Uniform,
+            "Splash Layer",
+            &[SplashLayerRaw {
+                rect: [-1., -1., 1., 1.],
+                color: [1.; 4],
+            }],
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Splash Layer Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        });
+
+        Self {
+            uniform,
+            bind_group,
+            size,
+        }
+    }
+
+    fn draw(
+        &self,
+        queue: &wgpu::Queue,
+        pass: &mut wgpu::RenderPass,
+        screen: Size<u32>,
+        color: [f32; 4],
+    ) {
+        let rect = match self.size {
+            Some(size) => {
+                let (width, height) = (size.width as f32, size.height as f32);
+                let (screen_width, screen_height) =
+                    (screen.width.max(1) as f32, screen.height.max(1) as f32);
+
+                let scale = (screen_width * LOGO_MAX_COVERAGE / width)
+                    .min(screen_height * LOGO_MAX_COVERAGE / height)
+                    .min(1.);
+
+                let half_width = width * scale / screen_width;
+                let half_height = height * scale / screen_height;
+
+                [-half_width, -half_height, half_width, half_height]
+            }
+            None => [-1., -1., 1., 1.],
+        };
+
+        queue.write_buffer(
+            &self.uniform,
+            0,
+            bytemuck::cast_slice(&[SplashLayerRaw { rect, color }]),
+        );
+
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..6, 0..1);
+    }
+}
+
+//====================================================================
+
+/// Draws a `SplashScreen` with a pipeline of its own, so it can be shown before any
+/// shared render resources exist.
+pub struct SplashRenderer {
+    pipeline: wgpu::RenderPipeline,
+    clear_color: Color,
+    fade_out: f32,
+
+    /// The clear color as a layer, for fading out over the app's frames.
+    background: SplashLayer,
+    logo: Option<SplashLayer>,
+    /// Set by `finish`.
+    finished_at: Option<Instant>,
+
+    _textures: Vec<Texture>,
+}
+
+impl SplashRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        splash: &SplashScreen,
+    ) -> Self {
+        log::debug!("Creating Splash Renderer");
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Splash Bind Group Layout"),
+            entries: &[
+                tools::bgl_entry(BgEntryType::Uniform, 0, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                tools::bgl_entry(BgEntryType::Texture, 1, wgpu::ShaderStages::FRAGMENT),
+                tools::bgl_entry(BgEntryType::Sampler, 2, wgpu::ShaderStages::FRAGMENT),
+            ],
+        });
+
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Splash Pipeline",
+            &[&layout],
+            &[],
+            SHADER,
+            tools::RenderPipelineDescriptor {
+                fragment_targets: Some(&fragment_targets),
+                ..Default::default()
+            },
+        );
+
+        let white = Texture::from_color(device, queue, [255; 3], Some("Splash Background"), None);
+        let background = SplashLayer::new(device, &layout, &white, None);
+        let mut textures = vec![white];
+
+        let logo = splash.logo.as_ref().and_then(|bytes| {
+            let logo = Texture::from_bytes(
+                device,
+                queue,
+                bytes,
+                Some("Splash Logo"),
+                Some(&wgpu::SamplerDescriptor {
+                    mag_filter: wgpu::FilterMode::Linear,
+                    min_filter: wgpu::FilterMode::Linear,
+                    ..Default::default()
+                }),
+            );
+
+            match logo {
+                Ok(logo) => {
+                    let size = logo.texture.size();
+                    let layer = SplashLayer::new(
+                        device,
+                        &layout,
+                        &logo,
+                        Some(Size::new(size.width, size.height)),
+                    );
+
+                    textures.push(logo);
+                    Some(layer)
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Unable to load splash logo - showing the clear color only: {}",
+                        e
+                    );
+                    None
+                }
+            }
+        });
+
+        Self {
+            pipeline,
+            clear_color: splash.clear_color,
+            fade_out: splash.fade_out,
+            background,
+            logo,
+            finished_at: None,
+            _textures: textures,
+        }
+    }
+
+    /// Start fading out. The splash is fully opaque until this is called.
+    #[inline]
+    pub fn finish(&mut self) {
+        self.finished_at.get_or_insert_with(Instant::now);
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.finished_at.is_some()
+    }
+
+    /// Whether the splash has finished and completely faded out.
+    #[inline]
+    pub fn is_faded(&self) -> bool {
+        self.is_finished() && self.alpha() <= 0.
+    }
+
+    pub fn alpha(&self) -> f32 {
+        match (self.finished_at, self.fade_out > 0.) {
+            (None, _) => 1.,
+            (Some(finished_at), true) => {
+                1. - (finished_at.elapsed().as_secs_f32() / self.fade_out).min(1.)
+            }
+            (Some(_), false) => 0.,
+        }
+    }
+
+    /// Draw the splash over everything already in `pass`, at its current alpha.
+    pub fn draw(&self, queue: &wgpu::Queue, pass: &mut wgpu::RenderPass, screen: Size<u32>) {
+        let alpha = self.alpha();
+        let clear = [
+            self.clear_color.r as f32,
+            self.clear_color.g as f32,
+            self.clear_color.b as f32,
+            alpha,
+        ];
+
+        pass.set_pipeline(&self.pipeline);
+        self.background.draw(queue, pass, screen, clear);
+
+        if let Some(logo) = &self.logo {
+            logo.draw(queue, pass, screen, [1., 1., 1., alpha]);
+        }
+    }
+
+    /// Clear `surface` to the splash color, draw the logo and present it. Does nothing
+    /// if the surface isn't available.
+    pub fn render(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface: &wgpu::Surface,
+        config: &wgpu::SurfaceConfiguration,
+    ) {
+        let Ok(mut encoder) = RenderEncoder::new_configured(device, surface, config) else {
+            log::trace!("Unable to get surface for splash screen");
+            return;
+        };
+
+        {
+            let mut pass = encoder.begin_render_pass(RenderPassDesc {
+                clear_color: Some(self.clear_color),
+                ..Default::default()
+            });
+
+            if let Some(logo) = &self.logo {
+                pass.set_pipeline(&self.pipeline);
+                logo.draw(
+                    queue,
+                    &mut pass,
+                    Size::new(config.width, config.height),
+                    [1., 1., 1., self.alpha()],
+                );
+            }
+        }
+
+        encoder.finish(queue);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,620 @@
+//====================================================================
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+use web_time::{Duration, Instant};
+
+use crate::{
+    memory::{GpuMemoryTracker, MemoryCategory},
+    shared::SharedRenderResources,
+    texture::Texture,
+    Error,
+};
+
+//====================================================================
+
+// Streamed textures keep their smallest mips resident at all times and gain or drop
+// the larger ones as the camera moves. Each change creates a new wgpu texture holding
+// every level from the most detailed resident one down, which `LoadedTexture::refresh`
+// picks up behind the same `TextureId`.
+
+/// Streamed textures always keep every level at or below this size resident.
+pub const BASE_RESIDENT_SIZE: u32 = 64;
+
+/// Default number of mip levels of extra detail streamed in past what's needed, so
+/// an approaching camera sharpens before the texture looks blurry.
+pub const DEFAULT_DETAIL_BIAS: f32 = 1.;
+
+/// Default frames a streamed texture can go unseen before its detail is dropped.
+pub const DEFAULT_DROP_DELAY: u32 = 120;
+
+static REGISTERED: Mutex<Vec<Weak<StreamSlot>>> = Mutex::new(Vec::new());
+
+//====================================================================
+
+/// Where a streamed texture reads its mip levels from. Level 0 is full resolution
+/// and each level after it halves both sides, rounding down to at least 1.
+pub trait MipSource: Send + Sync + 'static {
+    /// Size of level 0.
+    fn size(&self) -> (u32, u32);
+
+    fn level_count(&self) -> u32;
+
+    /// Tightly packed rgba8 pixels of a level. Called on the main thread whenever the
+    /// level is streamed in, so sources reading from disk should cache what they can.
+    fn load_level(&self, level: u32) -> Option<image::RgbaImage>;
+}
+
+/// Every mip level of an image held in memory, for textures without mips on disk.
+#[derive(Debug, Clone)]
+pub struct MipChain {
+    levels: Vec<image::RgbaImage>,
+}
+
+impl MipChain {
+    /// Downsample `image` to every mip level.
+    pub fn generate(image: &image::DynamicImage) -> Self {
+        let first = image.to_rgba8();
+        let level_count = mip_level_count(first.width(), first.height());
+
+        let mut levels = Vec::with_capacity(level_count as usize);
+        levels.push(first);
+
+        (1..level_count).for_each(|level| {
+            let (width, height) = level_size(levels[0].dimensions(), level);
+            let next = image::imageops::resize(
+                levels.last().unwrap(),
+                width,
+                height,
+                image::imageops::FilterType::Triangle,
+            );
+            levels.push(next);
+        });
+
+        Self { levels }
+    }
+
+    #[inline]
+    pub fn levels(&self) -> &[image::RgbaImage] {
+        &self.levels
+    }
+}
+
+impl MipSource for MipChain {
+    #[inline]
+    fn size(&self) -> (u32, u32) {
+        self.levels[0].dimensions()
+    }
+
+    #[inline]
+    fn level_count(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    #[inline]
+    fn load_level(&self, level: u32) -> Option<image::RgbaImage> {
+        self.levels.get(level as usize).cloned()
+    }
+}
+
+//--------------------------------------------------
+
+/// Number of levels in a full mip chain of a texture this size.
+#[inline]
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+#[inline]
+fn level_size((width, height): (u32, u32), level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// How many pixels tall something `size` world units across appears at `centre`,
+/// for a camera with `view_projection` drawing to a target `target_height` pixels tall.
+/// Works for both perspective and orthographic projections.
+pub fn projected_size(
+    view_projection: glam::Mat4,
+    target_height: f32,
+    centre: glam::Vec3,
+    size: f32,
+) -> f32 {
+    let w = (view_projection * centre.extend(1.)).w;
+    if w <= 0. {
+        return 0.;
+    }
+
+    let clip_per_unit = view_projection.row(1).truncate().length();
+    size * clip_per_unit / w * target_height / 2.
+}
+
+//====================================================================
+
+pub(crate) type TextureVersion = Arc<(Texture, wgpu::BindGroup)>;
+
+/// Shared by every clone of a streamed `LoadedTexture`.
+pub(crate) struct StreamSlot {
+    source: Box<dyn MipSource>,
+    label: String,
+    size: (u32, u32),
+    level_count: u32,
+    /// Most detailed level of the always resident base.
+    base_level: u32,
+    base: TextureVersion,
+    /// Levels from `resident_level` down, while more detail than the base is resident.
+    detail: Mutex<Option<TextureVersion>>,
+
+    resident_level: AtomicU32,
+    /// Bumped whenever `detail` changes.
+    version: AtomicU32,
+    /// Largest on screen size in pixels since the last update, as `f32` bits.
+    requested: AtomicU32,
+}
+
+impl fmt::Debug for StreamSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamSlot")
+            .field("label", &self.label)
+            .field("size", &self.size)
+            .field("level_count", &self.level_count)
+            .field("resident_level", &self.resident_level())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StreamSlot {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+        source: impl MipSource,
+        label: Option<&str>,
+    ) -> Result<Arc<Self>, Error> {
+        let label = label.unwrap_or("Streamed Texture").to_string();
+        let size = source.size();
+        let level_count = source
+            .level_count()
+            .clamp(1, mip_level_count(size.0, size.1));
+
+        let base_level = (0..level_count)
+            .find(|level| {
+                let (width, height) = level_size(size, *level);
+                width.max(height) <= BASE_RESIDENT_SIZE
+            })
+            .unwrap_or(level_count - 1);
+
+        let base = upload_levels(
+            device,
+            queue,
+            shared,
+            &source,
+            &label,
+            size,
+            base_level..level_count,
+        )?;
+
+        log::trace!(
+            "Created streamed texture '{}' of size {:?} with {} of {} levels resident",
+            label,
+            size,
+            level_count - base_level,
+            level_count
+        );
+
+        let slot = Arc::new(Self {
+            source: Box::new(source),
+            label,
+            size,
+            level_count,
+            base_level,
+            base,
+            detail: Mutex::new(None),
+            resident_level: AtomicU32::new(base_level),
+            version: AtomicU32::new(0),
+            requested: AtomicU32::new(0),
+        });
+
+        REGISTERED.lock().unwrap().push(Arc::downgrade(&slot));
+
+        Ok(slot)
+    }
+
+    #[inline]
+    pub(crate) fn base(&self) -> &TextureVersion {
+        &self.base
+    }
+
+    #[inline]
+    pub(crate) fn version(&self) -> u32 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// The most detailed resident version.
+    pub(crate) fn current(&self) -> TextureVersion {
+        self.detail
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| self.base.clone())
+    }
+
+    #[inline]
+    pub(crate) fn resident_level(&self) -> u32 {
+        self.resident_level.load(Ordering::Relaxed)
+    }
+
+    /// Non-negative floats order the same as their bits, so the largest request wins.
+    #[inline]
+    pub(crate) fn request(&self, pixels: f32) {
+        if pixels > 0. {
+            self.requested
+                .fetch_max(pixels.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    fn take_requested(&self) -> f32 {
+        f32::from_bits(self.requested.swap(0, Ordering::Relaxed))
+    }
+
+    /// The level whose size best matches `pixels` on screen, sharpened by `bias` levels.
+    fn level_for(&self, pixels: f32, bias: f32) -> u32 {
+        let largest = self.size.0.max(self.size.1) as f32;
+        let level = ((largest / pixels).log2() - bias).floor();
+
+        (level.max(0.) as u32).min(self.base_level)
+    }
+
+    /// Bytes resident beyond the base with `level` as the most detailed level.
+    fn detail_bytes(&self, level: u32) -> u64 {
+        match level < self.base_level {
+            true => (level..self.level_count)
+                .map(|level| {
+                    let (width, height) = level_size(self.size, level);
+                    width as u64 * height as u64 * 4
+                })
+                .sum(),
+            false => 0,
+        }
+    }
+
+    /// Recreate the detail texture with `level` as its most detailed level.
+    fn set_level(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+        level: u32,
+    ) -> Result<(), Error> {
+        let detail = match level < self.base_level {
+            true => Some(upload_levels(
+                device,
+                queue,
+                shared,
+                self.source.as_ref(),
+                &self.label,
+                self.size,
+                level..self.level_count,
+            )?),
+            false => None,
+        };
+
+        *self.detail.lock().unwrap() = detail;
+        self.resident_level.store(level, Ordering::Relaxed);
+        self.version.fetch_add(1, Ordering::Release);
+
+        Ok(())
+    }
+}
+
+/// Create a texture holding `levels` of `source`, with the first as its level 0.
+fn upload_levels(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &SharedRenderResources,
+    source: &dyn MipSource,
+    label: &str,
+    size: (u32, u32),
+    levels: std::ops::Range<u32>,
+) -> Result<TextureVersion, Error> {
+    let (width, height) = level_size(size, levels.start);
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: levels.len() as u32,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+
+    levels.clone().try_for_each(|level| -> Result<(), Error> {
+        let expected = level_size(size, level);
+        let image = source
+            .load_level(level)
+            .filter(|image| image.dimensions() == expected)
+            .ok_or_else(|| Error::MipLevel {
+                label: label.to_string(),
+                level,
+            })?;
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: level - levels.start,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &image,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * expected.0),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: expected.0,
+                height: expected.1,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(())
+    })?;
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some(label),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let texture = Texture::new(
+        texture,
+        view,
+        sampler,
+        MemoryCategory::Textures,
+        Some(label),
+    );
+    let bind_group = shared.create_texture_bind_group(device, &texture, Some(label));
+
+    Ok(Arc::new((texture, bind_group)))
+}
+
+//====================================================================
+
+/// What the last `TextureStreamer::update` did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamingStats {
+    /// Live streamed textures.
+    pub textures: usize,
+    /// Bytes of detail resident beyond the always resident base levels.
+    pub detail_bytes: u64,
+    /// Textures that gained a level.
+    pub streamed_in: usize,
+    /// Textures that dropped detail, because they went unseen or to fit the budget.
+    pub dropped: usize,
+    /// Textures still short of the detail they want, waiting for time in a later update.
+    pub pending: usize,
+}
+
+struct TrackedStream {
+    slot: Weak<StreamSlot>,
+    /// Updates since the texture was last requested.
+    unseen: u32,
+}
+
+struct StreamPlan {
+    slot: Arc<StreamSlot>,
+    /// Requested size as a fraction of full resolution. Zero if unseen.
+    priority: f32,
+    resident: u32,
+    target: u32,
+}
+
+/// Decides which streamed textures gain or drop mip levels each frame, from the sizes
+/// requested with `LoadedTexture::request_detail` since the last update.
+pub struct TextureStreamer {
+    /// Mip levels of extra detail streamed in past what's needed.
+    pub detail_bias: f32,
+    /// Updates a texture can go unseen before dropping back to its base levels.
+    pub drop_delay: u32,
+    memory_budget: Option<u64>,
+
+    streams: Vec<TrackedStream>,
+    stats: StreamingStats,
+}
+
+impl Default for TextureStreamer {
+    fn default() -> Self {
+        Self {
+            detail_bias: DEFAULT_DETAIL_BIAS,
+            drop_delay: DEFAULT_DROP_DELAY,
+            memory_budget: None,
+            streams: Vec::new(),
+            stats: StreamingStats::default(),
+        }
+    }
+}
+
+impl TextureStreamer {
+    #[inline]
+    pub fn memory_budget(&self) -> Option<u64> {
+        self.memory_budget
+    }
+
+    /// Cap the bytes of every texture tracked by the `GpuMemoryTracker`, streamed or
+    /// not. Streamed textures closest to full resolution on screen keep their detail
+    /// first, and the rest drop levels until everything fits. Base levels are never
+    /// dropped, so the cap can still be exceeded. `None` removes the cap.
+    #[inline]
+    pub fn set_memory_budget(&mut self, budget: Option<u64>) {
+        self.memory_budget = budget;
+    }
+
+    #[inline]
+    pub fn stats(&self) -> StreamingStats {
+        self.stats
+    }
+
+    /// Drop detail that is unseen or over budget, then stream in a level at a time
+    /// for the textures that want more, most wanted first, until `time_budget` runs
+    /// out. At least one level is streamed in per update so streaming always progresses.
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+        time_budget: Duration,
+    ) -> StreamingStats {
+        self.streams.extend(
+            std::mem::take(&mut *REGISTERED.lock().unwrap())
+                .into_iter()
+                .map(|slot| TrackedStream { slot, unseen: 0 }),
+        );
+        self.streams.retain(|stream| stream.slot.strong_count() > 0);
+
+        let mut plans = self
+            .streams
+            .iter_mut()
+            .filter_map(|stream| {
+                let slot = stream.slot.upgrade()?;
+                let pixels = slot.take_requested();
+                let resident = slot.resident_level();
+
+                let target = match pixels > 0. {
+                    true => {
+                        stream.unseen = 0;
+
+                        // Keep one level more than wanted while seen, so small camera
+                        // movements don't swap levels back and forth
+                        match slot.level_for(pixels, self.detail_bias) {
+                            level if level == resident + 1 => resident,
+                            level => level,
+                        }
+                    }
+                    false => {
+                        stream.unseen = stream.unseen.saturating_add(1);
+
+                        match stream.unseen > self.drop_delay {
+                            true => slot.base_level,
+                            false => resident,
+                        }
+                    }
+                };
+
+                let largest = slot.size.0.max(slot.size.1) as f32;
+
+                Some(StreamPlan {
+                    priority: pixels / largest,
+                    slot,
+                    resident,
+                    target,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        plans.sort_by(|a, b| b.priority.total_cmp(&a.priority));
+
+        if let Some(budget) = self.memory_budget {
+            let streamed = plans
+                .iter()
+                .map(|plan| plan.slot.detail_bytes(plan.resident))
+                .sum::<u64>();
+
+            let other = GpuMemoryTracker::total(MemoryCategory::Textures).saturating_sub(streamed);
+            let mut available = budget.saturating_sub(other);
+
+            plans.iter_mut().for_each(|plan| {
+                while plan.target < plan.slot.base_level
+                    && plan.slot.detail_bytes(plan.target) > available
+                {
+                    plan.target += 1;
+                }
+
+                available -= plan.slot.detail_bytes(plan.target);
+            });
+        }
+
+        let mut stats = StreamingStats {
+            textures: plans.len(),
+            ..Default::default()
+        };
+
+        // Drop detail first to make room for what's streamed in
+        plans
+            .iter_mut()
+            .filter(|plan| plan.target > plan.resident)
+            .for_each(|plan| {
+                match plan.slot.set_level(device, queue, shared, plan.target) {
+                    Ok(()) => plan.resident = plan.target,
+                    Err(e) => log::warn!("Unable to drop streamed texture detail: {}", e),
+                }
+                stats.dropped += 1;
+            });
+
+        let start = Instant::now();
+
+        // One level at a time, so the largest uploads are spread over frames
+        plans
+            .iter_mut()
+            .filter(|plan| plan.target < plan.resident)
+            .for_each(|plan| {
+                if stats.streamed_in > 0 && start.elapsed() >= time_budget {
+                    stats.pending += 1;
+                    return;
+                }
+
+                match plan
+                    .slot
+                    .set_level(device, queue, shared, plan.resident - 1)
+                {
+                    Ok(()) => {
+                        plan.resident -= 1;
+                        stats.streamed_in += 1;
+
+                        if plan.target < plan.resident {
+                            stats.pending += 1;
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Unable to stream in texture detail: {}", e);
+                        plan.target = plan.resident;
+                    }
+                }
+            });
+
+        stats.detail_bytes = plans
+            .iter()
+            .map(|plan| plan.slot.detail_bytes(plan.resident))
+            .sum();
+
+        if stats.streamed_in > 0 || stats.dropped > 0 {
+            log::trace!(
+                "Streamed in {} and dropped {} texture levels in {:?}, {} pending",
+                stats.streamed_in,
+                stats.dropped,
+                start.elapsed(),
+                stats.pending
+            );
+        }
+
+        self.stats = stats;
+        stats
+    }
+}
+
+//====================================================================
@@ -0,0 +1,321 @@
+//====================================================================
+
+use std::{ops::Deref, sync::Arc};
+
+use roots_common::Size;
+
+use crate::{memory::MemoryCategory, shared::DepthConvention, texture::Texture};
+
+//====================================================================
+
+/// Size of a pooled target. Window relative sizes are resolved against the pool's
+/// window size, so requests keep matching after a resize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetSize {
+    Fixed {
+        width: u32,
+        height: u32,
+    },
+    /// The window size divided by `divisor` and rounded up, such as 2 for a half
+    /// resolution effect.
+    Window {
+        divisor: u32,
+    },
+}
+
+impl TargetSize {
+    pub const WINDOW: Self = Self::Window { divisor: 1 };
+
+    #[inline]
+    pub fn fixed(size: Size<u32>) -> Self {
+        Self::Fixed {
+            width: size.width,
+            height: size.height,
+        }
+    }
+
+    /// Size in pixels, at least 1x1.
+    pub fn resolve(&self, window_size: Size<u32>) -> Size<u32> {
+        let (width, height) = match *self {
+            TargetSize::Fixed { width, height } => (width, height),
+            TargetSize::Window { divisor } => {
+                let divisor = divisor.max(1);
+                (
+                    window_size.width.div_ceil(divisor),
+                    window_size.height.div_ceil(divisor),
+                )
+            }
+        };
+
+        Size::new(width.max(1), height.max(1))
+    }
+}
+
+/// What a pooled target is created with. Targets are only shared between requests
+/// with equal descriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TargetDesc {
+    pub size: TargetSize,
+    pub format: wgpu::TextureFormat,
+    pub usage: wgpu::TextureUsages,
+    pub sample_count: u32,
+}
+
+impl TargetDesc {
+    #[inline]
+    pub fn new(size: TargetSize, format: wgpu::TextureFormat, usage: wgpu::TextureUsages) -> Self {
+        Self {
+            size,
+            format,
+            usage,
+            sample_count: 1,
+        }
+    }
+
+    /// A color target that can be rendered to and sampled.
+    #[inline]
+    pub fn color(size: TargetSize, format: wgpu::TextureFormat) -> Self {
+        Self::new(
+            size,
+            format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        )
+    }
+
+    /// A depth target in the format of `depth_convention`.
+    #[inline]
+    pub fn depth(size: TargetSize, depth_convention: &DepthConvention) -> Self {
+        Self::color(size, depth_convention.texture_format())
+    }
+
+    /// Multisampled targets are only rendered to and resolved, so lose
+    /// `TEXTURE_BINDING`, which some backends can't create them with.
+    #[inline]
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        if sample_count > 1 {
+            self.usage.remove(wgpu::TextureUsages::TEXTURE_BINDING);
+        }
+        self
+    }
+}
+
+//====================================================================
+
+/// A texture borrowed from a `RenderTargetPool`. Returned to the pool for reuse when
+/// every clone is dropped, so passes should only hold it for as long as they need
+/// its contents.
+#[derive(Debug, Clone)]
+pub struct PooledTarget {
+    texture: Arc<Texture>,
+    desc: TargetDesc,
+    size: Size<u32>,
+}
+
+impl PooledTarget {
+    #[inline]
+    pub fn desc(&self) -> &TargetDesc {
+        &self.desc
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+}
+
+impl Deref for PooledTarget {
+    type Target = Texture;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.texture
+    }
+}
+
+struct Slot {
+    desc: TargetDesc,
+    size: Size<u32>,
+    texture: Arc<Texture>,
+    last_used: u64,
+}
+
+impl Slot {
+    #[inline]
+    fn in_use(&self) -> bool {
+        Arc::strong_count(&self.texture) > 1
+    }
+}
+
+/// Shares render targets between passes that don't need them at the same time, such
+/// as the intermediate targets of post processing. Passes `acquire` a target at the
+/// start of their phase and drop it at the end, and later requests with the same
+/// description reuse it instead of allocating another.
+///
+/// Free targets unused for `max_idle_frames`, or whose size no longer matches after a
+/// resize, are destroyed by `end_frame`. Pooled memory is reported to the
+/// `GpuMemoryTracker` as `MemoryCategory::PooledTargets`.
+pub struct RenderTargetPool {
+    window_size: Size<u32>,
+    frame: u64,
+    /// Frames a free target is kept for before being destroyed.
+    pub max_idle_frames: u64,
+    slots: Vec<Slot>,
+}
+
+impl RenderTargetPool {
+    pub fn new(window_size: Size<u32>) -> Self {
+        Self {
+            window_size,
+            frame: 0,
+            max_idle_frames: 60,
+            slots: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn window_size(&self) -> Size<u32> {
+        self.window_size
+    }
+
+    /// Resolve window relative targets against `window_size` from now on. Targets
+    /// still held keep their old size, so holders of window relative targets should
+    /// acquire them again.
+    pub fn resize(&mut self, window_size: Size<u32>) {
+        self.window_size = window_size;
+
+        self.slots
+            .retain(|slot| slot.in_use() || slot.desc.size.resolve(window_size) == slot.size);
+    }
+
+    /// A free target matching `desc`, or a new one labeled with `label`.
+    pub fn acquire(
+        &mut self,
+        device: &wgpu::Device,
+        desc: TargetDesc,
+        label: &str,
+    ) -> PooledTarget {
+        let size = desc.size.resolve(self.window_size);
+
+        let reused = self
+            .slots
+            .iter_mut()
+            .find(|slot| slot.desc == desc && slot.size == size && !slot.in_use());
+
+        let texture = match reused {
+            Some(slot) => {
+                slot.last_used = self.frame;
+                slot.texture.clone()
+            }
+            None => {
+                log::trace!("Creating pooled target '{}' with size {}", label, size);
+
+                let texture = Arc::new(Self::create_texture(device, &desc, size, label));
+                self.slots.push(Slot {
+                    desc,
+                    size,
+                    texture: texture.clone(),
+                    last_used: self.frame,
+                });
+                texture
+            }
+        };
+
+        PooledTarget {
+            texture,
+            desc,
+            size,
+        }
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        desc: &TargetDesc,
+        size: Size<u32>,
+        label: &str,
+    ) -> Texture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: desc.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: desc.format,
+            usage: desc.usage,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let filter = match desc.format.is_depth_stencil_format() {
+            true => wgpu::FilterMode::Nearest,
+            false => wgpu::FilterMode::Linear,
+        };
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(label),
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        });
+
+        Texture::new(
+            texture,
+            view,
+            sampler,
+            MemoryCategory::PooledTargets,
+            Some(label),
+        )
+    }
+
+    /// Destroy free targets that have been idle too long or no longer match the
+    /// window size. Call once at the end of each frame.
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+
+        let frame = self.frame;
+        let window_size = self.window_size;
+        let max_idle_frames = self.max_idle_frames;
+
+        self.slots.retain_mut(|slot| {
+            if slot.in_use() {
+                slot.last_used = frame;
+                return true;
+            }
+
+            slot.desc.size.resolve(window_size) == slot.size
+                && frame - slot.last_used <= max_idle_frames
+        });
+    }
+
+    /// Destroy every free target.
+    #[inline]
+    pub fn clear_free(&mut self) {
+        self.slots.retain(Slot::in_use);
+    }
+
+    /// Targets in the pool, including free ones.
+    #[inline]
+    pub fn target_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Targets currently held by a `PooledTarget`.
+    #[inline]
+    pub fn in_use_count(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.in_use()).count()
+    }
+
+    /// Bytes of every target in the pool.
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        self.slots
+            .iter()
+            .map(|slot| slot.texture.memory().bytes())
+            .sum()
+    }
+}
+
+//====================================================================
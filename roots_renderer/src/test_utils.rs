@@ -0,0 +1,24 @@
+//====================================================================
+// Shared setup for tests that need a gpu. Tests should return early when no
+// adapter is available (such as on ci machines without a gpu).
+// Not every feature set uses every helper.
+#![allow(dead_code)]
+
+//====================================================================
+
+pub fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+    let Some(adapter) = pollster::block_on(instance.request_adapter(&Default::default())) else {
+        println!("No adapter available - skipping");
+        return None;
+    };
+
+    let device_queue =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .unwrap();
+
+    Some(device_queue)
+}
+
+//====================================================================
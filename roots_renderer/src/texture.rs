@@ -4,8 +4,15 @@ use std::sync::{atomic::AtomicU32, Arc};
 
 use image::GenericImageView;
 use roots_common::Size;
+use wgpu::util::DeviceExt;
 
-use crate::shared::{SharedRenderResources, Vertex};
+use crate::{
+    memory::{self, GpuMemoryTracker, MemoryCategory, MemoryGuard},
+    shared::{DepthConvention, SharedRenderResources, Vertex},
+    streaming::{MipSource, StreamSlot},
+    watchdog::{self, CreationKind},
+    Error,
+};
 
 //====================================================================
 
@@ -17,6 +24,8 @@ static CURRENT_TEXTURE_ID: AtomicU32 = AtomicU32::new(0);
 pub struct LoadedTexture {
     id: TextureId,
     texture: Arc<(Texture, wgpu::BindGroup)>,
+    /// Only set for streamed textures, with the version `texture` was taken from.
+    stream: Option<(Arc<StreamSlot>, u32)>,
 }
 
 impl LoadedTexture {
@@ -30,9 +39,31 @@ impl LoadedTexture {
         Self {
             id,
             texture: Arc::new((texture, bind_group)),
+            stream: None,
         }
     }
 
+    /// Create a texture with only its smallest mips resident. Larger levels are loaded
+    /// from `source` and dropped again by the `TextureStreamer`, going by the sizes
+    /// passed to `request_detail`. Returns `Error::MipLevel` if the smallest levels
+    /// can't be loaded.
+    pub fn load_streamed(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+        source: impl MipSource,
+        label: Option<&str>,
+    ) -> Result<Self, Error> {
+        let slot = StreamSlot::new(device, queue, shared, source, label)?;
+        let id = CURRENT_TEXTURE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(Self {
+            id,
+            texture: slot.base().clone(),
+            stream: Some((slot.clone(), slot.version())),
+        })
+    }
+
     #[inline]
     pub fn load_blank(
         device: &wgpu::Device,
@@ -64,6 +95,49 @@ impl LoadedTexture {
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.texture.1
     }
+
+    #[inline]
+    pub fn is_streamed(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// The most detailed mip level resident for a streamed texture, which may be newer
+    /// than the one this handle was last refreshed to. Always 0 for other textures.
+    #[inline]
+    pub fn resident_level(&self) -> u32 {
+        self.stream
+            .as_ref()
+            .map(|(slot, _)| slot.resident_level())
+            .unwrap_or(0)
+    }
+
+    /// Ask for a streamed texture to be sharp when drawn `pixels` across. Called while
+    /// prepping each instance - the largest request since the last
+    /// `TextureStreamer::update` decides its detail. Does nothing for other textures.
+    #[inline]
+    pub fn request_detail(&self, pixels: f32) {
+        if let Some((slot, _)) = &self.stream {
+            slot.request(pixels);
+        }
+    }
+
+    /// Switch to the latest levels streamed in or dropped, keeping the same id. Old
+    /// levels stay alive until every handle holding them has refreshed, so renderers
+    /// refresh the textures they store each frame. Returns true if the texture changed.
+    pub fn refresh(&mut self) -> bool {
+        let Some((slot, version)) = &mut self.stream else {
+            return false;
+        };
+
+        let latest = slot.version();
+        if *version == latest {
+            return false;
+        }
+
+        *version = latest;
+        self.texture = slot.current();
+        true
+    }
 }
 
 impl PartialEq for LoadedTexture {
@@ -73,6 +147,61 @@ impl PartialEq for LoadedTexture {
     }
 }
 
+//--------------------------------------------------
+
+/// A `D2Array` texture with its bind group. Shares ids with `LoadedTexture`.
+#[derive(Debug, Clone)]
+pub struct LoadedTextureArray {
+    id: TextureId,
+    layers: u32,
+    texture: Arc<(Texture, wgpu::BindGroup)>,
+}
+
+impl LoadedTextureArray {
+    pub fn load_texture_array(
+        device: &wgpu::Device,
+        shared: &SharedRenderResources,
+        texture: Texture,
+    ) -> Self {
+        let id = CURRENT_TEXTURE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let layers = texture.texture.depth_or_array_layers();
+        let bind_group = shared.create_texture_array_bind_group(device, &texture, None);
+
+        Self {
+            id,
+            layers,
+            texture: Arc::new((texture, bind_group)),
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> TextureId {
+        self.id
+    }
+
+    #[inline]
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    #[inline]
+    pub fn texture(&self) -> &Texture {
+        &self.texture.0
+    }
+
+    #[inline]
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.texture.1
+    }
+}
+
+impl PartialEq for LoadedTextureArray {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
 //====================================================================
 
 #[repr(C)]
@@ -126,18 +255,78 @@ pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
     pub sampler: wgpu::Sampler,
+    memory: MemoryGuard,
 }
 
 impl Texture {
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
+    /// Wrap a created texture and record its size with the `GpuMemoryTracker`.
+    pub fn new(
+        texture: wgpu::Texture,
+        view: wgpu::TextureView,
+        sampler: wgpu::Sampler,
+        category: MemoryCategory,
+        label: Option<&str>,
+    ) -> Self {
+        let label = label.unwrap_or("Unlabeled Texture");
+        let bytes = memory::texture_bytes(&texture);
+
+        watchdog::record_creation(CreationKind::Texture, label, bytes);
+        let memory = GpuMemoryTracker::track(category, label, bytes);
+
+        Self {
+            texture,
+            view,
+            sampler,
+            memory,
+        }
+    }
+
+    /// The tracked size of this texture.
+    #[inline]
+    pub fn memory(&self) -> &MemoryGuard {
+        &self.memory
+    }
+
+    #[inline]
+    pub fn set_memory_category(&mut self, category: MemoryCategory) {
+        self.memory.set_category(category);
+    }
+
+    /// `sample_count` must match the color attachment and pipelines the depth texture
+    /// is used with, such as `SharedRenderResources::sample_count`.
+    #[inline]
     pub fn create_depth_texture(
         device: &wgpu::Device,
         window_size: impl Into<Size<u32>>,
+        sample_count: u32,
+        label: Option<&str>,
+    ) -> Self {
+        Self::create_depth_texture_with(
+            device,
+            window_size,
+            &DepthConvention::default(),
+            sample_count,
+            label,
+        )
+    }
+
+    /// See `create_depth_texture`.
+    pub fn create_depth_texture_with(
+        device: &wgpu::Device,
+        window_size: impl Into<Size<u32>>,
+        depth_convention: &DepthConvention,
+        sample_count: u32,
         label: Option<&str>,
     ) -> Self {
         let window_size = window_size.into();
-        log::trace!("Creating depth texture with size {}", window_size);
+        log::trace!(
+            "Creating depth texture with size {}, format {:?} and {} samples",
+            window_size,
+            depth_convention.format,
+            sample_count
+        );
 
         let size = wgpu::Extent3d {
             width: window_size.width,
@@ -146,16 +335,24 @@ impl Texture {
         };
 
         let label = label.unwrap_or("default");
+        let format = depth_convention.texture_format();
+        let sample_count = sample_count.max(1);
+
+        // Multisampled depth can't be sampled as a regular texture
+        let usage = match sample_count {
+            1 => wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            _ => wgpu::TextureUsages::RENDER_ATTACHMENT,
+        };
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some(&format!("Depth Texture: {}", label)),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
-            format: Self::DEPTH_FORMAT,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[wgpu::TextureFormat::Depth32Float],
+            format,
+            usage,
+            view_formats: &[format],
         });
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor {
@@ -170,15 +367,17 @@ impl Texture {
             mipmap_filter: wgpu::FilterMode::Nearest,
             lod_min_clamp: 0.,
             lod_max_clamp: 100.,
-            compare: Some(wgpu::CompareFunction::LessEqual),
+            compare: Some(depth_convention.compare_equal()),
             ..Default::default()
         });
 
-        Self {
+        Self::new(
             texture,
             view,
             sampler,
-        }
+            MemoryCategory::RenderTargets,
+            Some(&format!("Depth Texture: {}", label)),
+        )
     }
 }
 
@@ -207,7 +406,7 @@ impl Texture {
     }
 
     /// Try to create a wgpu Texture from an array of bytes.
-    /// The image crate will return an error if it cannot determine the format
+    /// Returns `Error::TextureDecode` if the image crate cannot determine the format
     /// of the image.
     pub fn from_bytes(
         device: &wgpu::Device,
@@ -215,7 +414,7 @@ impl Texture {
         bytes: &[u8],
         label: Option<&str>,
         sampler: Option<&wgpu::SamplerDescriptor>,
-    ) -> Result<Self, image::ImageError> {
+    ) -> Result<Self, Error> {
         let img = image::load_from_memory(bytes)?;
         Ok(Self::from_image(device, queue, &img, label, sampler))
     }
@@ -271,11 +470,7 @@ impl Texture {
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(sampler.unwrap_or(&wgpu::SamplerDescriptor::default()));
 
-        Self {
-            texture,
-            view,
-            sampler,
-        }
+        Self::new(texture, view, sampler, MemoryCategory::Textures, label)
     }
 
     pub fn from_size(
@@ -303,11 +498,7 @@ impl Texture {
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(sampler.unwrap_or(&wgpu::SamplerDescriptor::default()));
 
-        Self {
-            texture,
-            view,
-            sampler,
-        }
+        Self::new(texture, view, sampler, MemoryCategory::Textures, label)
     }
 }
 
@@ -348,3 +539,309 @@ impl Texture {
 }
 
 //====================================================================
+
+struct WriteRegion {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    offset: u64,
+}
+
+/// Writes to areas of a single texture, uploaded together with one staging buffer
+/// and encoder instead of a `write_texture` call per area. Rows are padded to
+/// `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT` as buffer to texture copies require.
+pub struct TextureWriteBatch {
+    bytes_per_pixel: u32,
+    regions: Vec<WriteRegion>,
+    data: Vec<u8>,
+}
+
+impl TextureWriteBatch {
+    #[inline]
+    pub fn new(bytes_per_pixel: u32) -> Self {
+        Self {
+            bytes_per_pixel,
+            regions: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Number of areas waiting to be uploaded.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Size of the staging data, including row padding.
+    #[inline]
+    pub fn staged_bytes(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Queue tightly packed `data` to be written at (`x`, `y`). Empty areas are ignored.
+    pub fn push(&mut self, data: &[u8], x: u32, y: u32, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let row_size = (width * self.bytes_per_pixel) as usize;
+        if data.len() < row_size * height as usize {
+            log::warn!(
+                "Texture write of {}x{} only has {} bytes - skipping",
+                width,
+                height,
+                data.len()
+            );
+            return;
+        }
+
+        let bytes_per_row = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+        let offset = self.data.len() as u64;
+
+        data.chunks_exact(row_size)
+            .take(height as usize)
+            .for_each(|row| {
+                self.data.extend_from_slice(row);
+                self.data
+                    .resize(self.data.len() + bytes_per_row - row_size, 0);
+            });
+
+        self.regions.push(WriteRegion {
+            x,
+            y,
+            width,
+            height,
+            bytes_per_row: bytes_per_row as u32,
+            offset,
+        });
+    }
+
+    /// Upload every queued area to `texture` in a single submission and clear the batch.
+    /// Must be called before anything sampling the texture is rendered.
+    pub fn flush(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) {
+        if self.regions.is_empty() {
+            return;
+        }
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Texture Write Batch Staging Buffer"),
+            contents: &self.data,
+            usage: wgpu::BufferUsages::COPY_SRC,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Texture Write Batch Encoder"),
+        });
+
+        self.regions.iter().for_each(|region| {
+            encoder.copy_buffer_to_texture(
+                wgpu::ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: region.offset,
+                        bytes_per_row: Some(region.bytes_per_row),
+                        rows_per_image: Some(region.height),
+                    },
+                },
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: region.x,
+                        y: region.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::Extent3d {
+                    width: region.width,
+                    height: region.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        });
+
+        queue.submit(Some(encoder.finish()));
+        self.clear();
+    }
+
+    /// Drop every queued write without uploading it.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.data.clear();
+    }
+}
+
+//====================================================================
+
+#[derive(thiserror::Error, Debug)]
+pub enum TextureArrayError {
+    #[error("Texture array requires at least one image")]
+    Empty,
+
+    #[error("Texture array has {count} layers but the device only supports {max}")]
+    TooManyLayers { count: u32, max: u32 },
+
+    #[error("Texture array layer {index} is {found:?} but the first layer is {expected:?}")]
+    MismatchedSize {
+        index: usize,
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+
+    #[error("Unable to load texture array image: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("Unable to read texture array directory: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl Texture {
+    /// Create a `D2Array` texture with one layer per image. All images must share
+    /// the same dimensions.
+    pub fn from_image_array(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        images: &[image::DynamicImage],
+        label: Option<&str>,
+        sampler: Option<&wgpu::SamplerDescriptor>,
+    ) -> Result<Self, TextureArrayError> {
+        let first = images.first().ok_or(TextureArrayError::Empty)?;
+        let dimensions = first.dimensions();
+
+        let max = device.limits().max_texture_array_layers;
+        if images.len() as u32 > max {
+            return Err(TextureArrayError::TooManyLayers {
+                count: images.len() as u32,
+                max,
+            });
+        }
+
+        if let Some((index, image)) = images
+            .iter()
+            .enumerate()
+            .find(|(_, image)| image.dimensions() != dimensions)
+        {
+            return Err(TextureArrayError::MismatchedSize {
+                index,
+                expected: dimensions,
+                found: image.dimensions(),
+            });
+        }
+
+        log::trace!(
+            "Creating texture array with {} layers of size {:?}",
+            images.len(),
+            dimensions
+        );
+
+        let size = wgpu::Extent3d {
+            width: dimensions.0,
+            height: dimensions.1,
+            depth_or_array_layers: images.len() as u32,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        images.iter().enumerate().for_each(|(layer, image)| {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &image.to_rgba8(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * dimensions.0),
+                    rows_per_image: Some(dimensions.1),
+                },
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..size
+                },
+            );
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(sampler.unwrap_or(&wgpu::SamplerDescriptor::default()));
+
+        Ok(Self::new(
+            texture,
+            view,
+            sampler,
+            MemoryCategory::Textures,
+            label,
+        ))
+    }
+
+    /// Create a texture array from encoded images (png, jpeg, etc.) in memory.
+    pub fn from_bytes_array(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        images: &[&[u8]],
+        label: Option<&str>,
+        sampler: Option<&wgpu::SamplerDescriptor>,
+    ) -> Result<Self, TextureArrayError> {
+        let images = images
+            .iter()
+            .map(|bytes| image::load_from_memory(bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_image_array(device, queue, &images, label, sampler)
+    }
+
+    /// Create a texture array from every png in a directory. Layers are ordered by file name.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_directory(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: impl AsRef<std::path::Path>,
+        label: Option<&str>,
+        sampler: Option<&wgpu::SamplerDescriptor>,
+    ) -> Result<Self, TextureArrayError> {
+        let mut paths = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        paths.retain(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+        });
+        paths.sort();
+
+        let images = paths
+            .iter()
+            .map(image::open)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_image_array(device, queue, &images, label, sampler)
+    }
+}
+
+//====================================================================
@@ -4,7 +4,13 @@ use std::{marker::PhantomData, num::NonZeroU32};
 
 use wgpu::util::DeviceExt;
 
-use super::texture::Texture;
+use crate::{
+    memory::{GpuMemoryTracker, MemoryCategory, MemoryGuard},
+    shared::DepthConvention,
+    texture::Texture,
+    watchdog::{self, CreationKind},
+    Error,
+};
 
 //====================================================================
 
@@ -15,6 +21,11 @@ pub struct RenderPipelineDescriptor<'a> {
     pub fragment_targets: Option<&'a [Option<wgpu::ColorTargetState>]>,
     pub multiview: Option<NonZeroU32>,
     pub cache: Option<&'a wgpu::PipelineCache>,
+    /// Requires `wgpu::Features::PUSH_CONSTANTS`, which `RenderCore` requests when the
+    /// adapter supports it. See `CapabilityReport::push_constants`.
+    pub push_constant_ranges: &'a [wgpu::PushConstantRange],
+    pub vertex_entry: &'a str,
+    pub fragment_entry: &'a str,
 }
 
 impl<'a> Default for RenderPipelineDescriptor<'a> {
@@ -26,6 +37,9 @@ impl<'a> Default for RenderPipelineDescriptor<'a> {
             fragment_targets: None,
             multiview: None,
             cache: None,
+            push_constant_ranges: &[],
+            vertex_entry: "vs_main",
+            fragment_entry: "fs_main",
         }
     }
 }
@@ -43,10 +57,46 @@ impl<'a> RenderPipelineDescriptor<'a> {
         self
     }
 
+    /// Depth test and write using the provided convention's format and compare function.
+    pub fn with_depth(mut self, depth_convention: &DepthConvention) -> Self {
+        self.depth_stencil =
+            Some(depth_convention.depth_stencil_state(true, depth_convention.compare()));
+        self
+    }
+
+    /// Cull clockwise triangles. Meshes built by the renderer and its loaders are wound
+    /// counter clockwise in the active `CoordinateConvention`, so this holds in all of them.
     pub fn with_backface_culling(mut self) -> Self {
         self.primitive.cull_mode = Some(wgpu::Face::Back);
         self
     }
+
+    /// Samples per pixel, which must match the targets the pipeline is drawn into. See
+    /// `SharedRenderResources::sample_count`.
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.multisample.count = sample_count.max(1);
+        self
+    }
+
+    /// Speed up creation with a cache, such as `SharedRenderResources::pipeline_cache`.
+    pub fn with_cache(mut self, cache: Option<&'a wgpu::PipelineCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Push constants for small per draw data, such as an index or time value, set with
+    /// `wgpu::RenderPass::set_push_constants`.
+    pub fn with_push_constants(mut self, ranges: &'a [wgpu::PushConstantRange]) -> Self {
+        self.push_constant_ranges = ranges;
+        self
+    }
+
+    /// Use different shader entry points, for pipeline variants sharing a shader module.
+    pub fn with_entry_points(mut self, vertex_entry: &'a str, fragment_entry: &'a str) -> Self {
+        self.vertex_entry = vertex_entry;
+        self.fragment_entry = fragment_entry;
+        self
+    }
 }
 
 pub fn create_pipeline(
@@ -62,7 +112,7 @@ pub fn create_pipeline(
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some(&format!("{} layout", label)),
         bind_group_layouts,
-        push_constant_ranges: &[],
+        push_constant_ranges: desc.push_constant_ranges,
     });
 
     let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -77,12 +127,14 @@ pub fn create_pipeline(
     })];
     let fragment_targets = desc.fragment_targets.unwrap_or(&default_fragment_targets);
 
+    watchdog::record_creation(CreationKind::Pipeline, label, 0);
+
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: Some(label),
         layout: Some(&layout),
         vertex: wgpu::VertexState {
             module: &shader_module,
-            entry_point: Some("vs_main"),
+            entry_point: Some(desc.vertex_entry),
             compilation_options: Default::default(),
             buffers: vertex_buffers,
         },
@@ -91,7 +143,7 @@ pub fn create_pipeline(
         multisample: desc.multisample,
         fragment: Some(wgpu::FragmentState {
             module: &shader_module,
-            entry_point: Some("fs_main"),
+            entry_point: Some(desc.fragment_entry),
             compilation_options: Default::default(),
             targets: fragment_targets,
         }),
@@ -100,13 +152,81 @@ pub fn create_pipeline(
     })
 }
 
+pub fn create_compute_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    shader_module_data: &str,
+    entry_point: &str,
+    cache: Option<&wgpu::PipelineCache>,
+) -> wgpu::ComputePipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{} layout", label)),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(&format!("{} shader module", label)),
+        source: wgpu::ShaderSource::Wgsl(shader_module_data.into()),
+    });
+
+    watchdog::record_creation(CreationKind::Pipeline, label, 0);
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&layout),
+        module: &shader_module,
+        entry_point: Some(entry_point),
+        compilation_options: Default::default(),
+        cache,
+    })
+}
+
+/// Same as `create_pipeline`, but shader and pipeline validation errors are returned
+/// instead of going to the device's uncaptured error handler, which panics by default.
+pub fn try_create_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    label: &str,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    vertex_buffers: &[wgpu::VertexBufferLayout],
+    shader_module_data: &str,
+
+    desc: RenderPipelineDescriptor,
+) -> Result<wgpu::RenderPipeline, Error> {
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let pipeline = create_pipeline(
+        device,
+        config,
+        label,
+        bind_group_layouts,
+        vertex_buffers,
+        shader_module_data,
+        desc,
+    );
+
+    let validation = pollster::block_on(device.pop_error_scope());
+    let out_of_memory = pollster::block_on(device.pop_error_scope());
+
+    match validation.or(out_of_memory) {
+        Some(error) => Err(Error::from_wgpu(label, error)),
+        None => Ok(pipeline),
+    }
+}
+
 //====================================================================
 
 /// Bind Group Entry Type
 pub enum BgEntryType {
     Uniform,
     Storage,
+    /// Read write storage, not available to vertex shaders.
+    StorageWritable,
     Texture,
+    TextureArray,
     Sampler,
 }
 
@@ -132,12 +252,24 @@ pub fn bgl_entry(
                 min_binding_size: None,
             },
 
+            BgEntryType::StorageWritable => wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+
             BgEntryType::Texture => wgpu::BindingType::Texture {
                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
                 view_dimension: wgpu::TextureViewDimension::D2,
                 multisampled: false,
             },
 
+            BgEntryType::TextureArray => wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2Array,
+                multisampled: false,
+            },
+
             BgEntryType::Sampler => wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
         },
         count: None,
@@ -152,6 +284,7 @@ pub enum BufferType {
     Storage,
     VertexDynamic,
     IndexDynamic,
+    Indirect,
 }
 
 impl BufferType {
@@ -180,6 +313,10 @@ impl BufferType {
                 "Index",
                 wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
             ),
+            BufferType::Indirect => (
+                "Indirect",
+                wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            ),
         }
     }
 }
@@ -191,11 +328,35 @@ pub fn create_buffer<D: bytemuck::Pod>(
     data: &[D],
 ) -> wgpu::Buffer {
     let (name, usage) = buffer_type.get_data();
+    let label = format!("{} {} Buffer", label, name);
+    let contents = bytemuck::cast_slice(data);
+
+    watchdog::record_creation(CreationKind::Buffer, &label, contents.len() as u64);
 
     device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some(&format!("{} {} Buffer", label, name)),
-        contents: bytemuck::cast_slice(data),
+        label: Some(&label),
+        contents,
+        usage,
+    })
+}
+
+/// Create a zeroed buffer of `size` bytes, to be written to later.
+pub fn create_buffer_empty(
+    device: &wgpu::Device,
+    buffer_type: BufferType,
+    label: &str,
+    size: u64,
+) -> wgpu::Buffer {
+    let (name, usage) = buffer_type.get_data();
+    let label = format!("{} {} Buffer", label, name);
+
+    watchdog::record_creation(CreationKind::Buffer, &label, size);
+
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(&label),
+        size,
         usage,
+        mapped_at_creation: false,
     })
 }
 
@@ -243,25 +404,37 @@ pub struct InstanceBuffer<T> {
     phantom: PhantomData<T>,
     buffer: wgpu::Buffer,
     count: u32,
+    /// Copy of the last uploaded data, to skip identical uploads.
+    data: Vec<T>,
+    memory: MemoryGuard,
 }
 
 impl<T: bytemuck::Pod> InstanceBuffer<T> {
     #[inline]
     pub fn new(device: &wgpu::Device, data: &[T]) -> Self {
+        let label = format!("{} Instance Buffer", std::any::type_name::<T>());
+        let buffer = create_buffer(device, BufferType::Instance, &label, data);
+        let memory = GpuMemoryTracker::track(MemoryCategory::InstanceBuffers, label, buffer.size());
+
         Self {
             phantom: PhantomData,
-            buffer: create_buffer(
-                device,
-                BufferType::Instance,
-                &format!("{} Instance Buffer", std::any::type_name::<T>()),
-                data,
-            ),
+            buffer,
             count: data.len() as u32,
+            data: data.to_vec(),
+            memory,
         }
     }
 
+    /// Upload new instance data. The upload is skipped if the data is identical to the
+    /// previous update. Returns true if the buffer was written to.
     #[inline]
-    pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[T]) {
+    pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[T]) -> bool {
+        if bytemuck::cast_slice::<T, u8>(data) == bytemuck::cast_slice::<T, u8>(&self.data) {
+            return false;
+        }
+
+        self.data.clear();
+        self.data.extend_from_slice(data);
         update_buffer_data(
             device,
             queue,
@@ -271,6 +444,9 @@ impl<T: bytemuck::Pod> InstanceBuffer<T> {
             &mut self.count,
             data,
         );
+        self.memory.set_bytes(self.buffer.size());
+
+        true
     }
 
     #[inline]
@@ -344,3 +520,24 @@ impl<T: bytemuck::Pod> InstanceBuffer<T> {
 // }
 
 //====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn instance_buffer_skips_identical_uploads() {
+        let Some((device, queue)) = test_utils::device() else {
+            return;
+        };
+
+        let mut buffer = InstanceBuffer::new(&device, &[1u32, 2, 3]);
+
+        assert!(!buffer.update(&device, &queue, &[1, 2, 3]));
+        assert!(buffer.update(&device, &queue, &[1, 2, 4]));
+        assert!(!buffer.update(&device, &queue, &[1, 2, 4]));
+        assert!(buffer.update(&device, &queue, &[1, 2, 4, 5]));
+        assert_eq!(buffer.count(), 4);
+    }
+}
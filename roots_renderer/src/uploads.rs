@@ -0,0 +1,282 @@
+//====================================================================
+
+use std::{
+    collections::VecDeque,
+    sync::{mpsc, Arc, Mutex},
+};
+
+use roots_common::WasmWrapper;
+use web_time::{Duration, Instant};
+
+use crate::{
+    model::{LoadedMesh, ModelVertex},
+    shared::SharedRenderResources,
+    streaming::MipChain,
+    texture::{LoadedTexture, Texture},
+    Device, Queue,
+};
+
+//====================================================================
+
+/// Default time spent creating deferred uploads each frame.
+pub const DEFAULT_UPLOAD_BUDGET: Duration = Duration::from_millis(4);
+
+/// Decoded data waiting to become a gpu resource.
+pub enum UploadData {
+    Texture {
+        image: image::DynamicImage,
+        label: Option<String>,
+    },
+    /// Created with `LoadedTexture::load_streamed`. The mips are generated before
+    /// upload, so they can be built on the worker thread.
+    StreamedTexture {
+        mips: MipChain,
+        label: Option<String>,
+    },
+    Mesh {
+        vertices: Vec<ModelVertex>,
+        indices: Vec<u32>,
+    },
+}
+
+impl UploadData {
+    fn create(
+        self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+    ) -> Uploaded {
+        match self {
+            UploadData::Texture { image, label } => {
+                let texture = Texture::from_image(device, queue, &image, label.as_deref(), None);
+                Uploaded::Texture(LoadedTexture::load_texture(device, shared, texture))
+            }
+            UploadData::StreamedTexture { mips, label } => {
+                let full = image::DynamicImage::from(mips.levels()[0].clone());
+
+                let texture =
+                    LoadedTexture::load_streamed(device, queue, shared, mips, label.as_deref())
+                        .unwrap_or_else(|e| {
+                            log::warn!("{} - uploading without streaming", e);
+                            let texture =
+                                Texture::from_image(device, queue, &full, label.as_deref(), None);
+                            LoadedTexture::load_texture(device, shared, texture)
+                        });
+
+                Uploaded::Texture(texture)
+            }
+            UploadData::Mesh { vertices, indices } => {
+                Uploaded::Mesh(LoadedMesh::load_from_data(device, &vertices, &indices))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Uploaded {
+    Texture(LoadedTexture),
+    Mesh(LoadedMesh),
+}
+
+//====================================================================
+
+/// Receives a resource once it has been created. Can be polled from any thread.
+#[derive(Clone, Default)]
+pub struct UploadTicket(Arc<Mutex<Option<WasmWrapper<Uploaded>>>>);
+
+impl UploadTicket {
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.0.lock().unwrap().is_some()
+    }
+
+    /// Take the created resource. Returns `None` until it's ready, and after it was taken.
+    #[inline]
+    pub fn take(&self) -> Option<Uploaded> {
+        self.0
+            .lock()
+            .unwrap()
+            .take()
+            .map(|uploaded| uploaded.take())
+    }
+
+    #[inline]
+    fn fill(&self, uploaded: Uploaded) {
+        *self.0.lock().unwrap() = Some(WasmWrapper::new(uploaded));
+    }
+}
+
+//====================================================================
+
+/// How resources pushed through an `UploadContext` are created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadStrategy {
+    /// Created immediately on the calling thread. Native only, where the device and
+    /// queue can be shared between threads.
+    Direct,
+    /// Queued and created on the main thread within the per frame upload budget.
+    Deferred,
+}
+
+impl UploadStrategy {
+    /// `Direct` on native and `Deferred` on wasm.
+    #[inline]
+    pub fn platform_default() -> Self {
+        match cfg!(target_arch = "wasm32") {
+            true => UploadStrategy::Deferred,
+            false => UploadStrategy::Direct,
+        }
+    }
+}
+
+/// A cloneable handle for creating textures and meshes from worker threads.
+#[derive(Clone)]
+pub enum UploadContext {
+    #[cfg(not(target_arch = "wasm32"))]
+    Direct {
+        device: Device,
+        queue: Queue,
+        shared: SharedRenderResources,
+    },
+    Deferred(mpsc::Sender<(UploadData, UploadTicket)>),
+}
+
+impl UploadContext {
+    pub fn upload(&self, data: UploadData) -> UploadTicket {
+        let ticket = UploadTicket::default();
+
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            UploadContext::Direct {
+                device,
+                queue,
+                shared,
+            } => ticket.fill(data.create(device, queue, shared)),
+
+            UploadContext::Deferred(sender) => {
+                if sender.send((data, ticket.clone())).is_err() {
+                    log::warn!("Upload queue was dropped before an upload could be sent");
+                }
+            }
+        }
+
+        ticket
+    }
+
+    #[inline]
+    pub fn upload_texture(
+        &self,
+        image: image::DynamicImage,
+        label: Option<String>,
+    ) -> UploadTicket {
+        self.upload(UploadData::Texture { image, label })
+    }
+
+    /// Generate every mip of `image` on the calling thread and upload it as a streamed
+    /// texture. See `LoadedTexture::load_streamed`.
+    #[inline]
+    pub fn upload_streamed_texture(
+        &self,
+        image: &image::DynamicImage,
+        label: Option<String>,
+    ) -> UploadTicket {
+        self.upload(UploadData::StreamedTexture {
+            mips: MipChain::generate(image),
+            label,
+        })
+    }
+
+    #[inline]
+    pub fn upload_mesh(&self, vertices: Vec<ModelVertex>, indices: Vec<u32>) -> UploadTicket {
+        self.upload(UploadData::Mesh { vertices, indices })
+    }
+}
+
+//====================================================================
+
+/// Queue of uploads pushed from other threads, created on the main thread a few at a
+/// time so large batches trickle in without long frames.
+pub struct DeferredUploads {
+    sender: mpsc::Sender<(UploadData, UploadTicket)>,
+    receiver: mpsc::Receiver<(UploadData, UploadTicket)>,
+    pending: VecDeque<(UploadData, UploadTicket)>,
+    budget: Duration,
+}
+
+impl Default for DeferredUploads {
+    #[inline]
+    fn default() -> Self {
+        Self::new(DEFAULT_UPLOAD_BUDGET)
+    }
+}
+
+impl DeferredUploads {
+    pub fn new(budget: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            sender,
+            receiver,
+            pending: VecDeque::new(),
+            budget,
+        }
+    }
+
+    /// A context that pushes into this queue.
+    #[inline]
+    pub fn context(&self) -> UploadContext {
+        UploadContext::Deferred(self.sender.clone())
+    }
+
+    #[inline]
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    #[inline]
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Uploads received but not yet created.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Create queued uploads until the budget runs out. At least one upload is created
+    /// per call so the queue always makes progress. Returns the number created.
+    pub fn process(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+    ) -> usize {
+        self.pending.extend(self.receiver.try_iter());
+
+        let start = Instant::now();
+        let mut created = 0;
+
+        while let Some((data, ticket)) = self.pending.pop_front() {
+            ticket.fill(data.create(device, queue, shared));
+            created += 1;
+
+            if start.elapsed() >= self.budget {
+                break;
+            }
+        }
+
+        if created > 0 {
+            log::trace!(
+                "Created {} deferred uploads in {:?}, {} pending",
+                created,
+                start.elapsed(),
+                self.pending.len()
+            );
+        }
+
+        created
+    }
+}
+
+//====================================================================
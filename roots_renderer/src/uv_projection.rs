@@ -0,0 +1,541 @@
+//====================================================================
+
+use std::{collections::HashMap, f32::consts::TAU};
+
+use roots_common::FastHasher;
+
+use crate::model::{self, ModelVertex};
+
+//====================================================================
+
+// Re-project the uvs of simple shapes without a round trip through a modelling tool.
+// Projections that can give one vertex different uvs in different triangles (at a
+// seam or box edge) return new vertices and indices, duplicating vertices as needed.
+// Tangents are recomputed to follow the new uvs.
+
+/// Below this distance from the projection axis, a vertex has no meaningful angle.
+const AXIS_EPSILON: f32 = 1e-5;
+
+/// Project uvs onto the plane facing `axis`, with `scale` world units per repeat of the
+/// texture. Seen looking along `axis`, u runs right and v runs down.
+pub fn project_uvs_planar(vertices: &mut [ModelVertex], axis: glam::Vec3, scale: f32) {
+    let (right, up) = plane_basis(axis);
+    let scale = repeat_scale(scale);
+
+    vertices.iter_mut().for_each(|vertex| {
+        vertex.uv = planar_uv(vertex.pos, right, up) * scale;
+        vertex.tangent = model::tangent_from_directions(vertex.normal, right, -up);
+    });
+}
+
+/// Project each triangle onto the side of a box its normal faces most, with `scale`
+/// world units per repeat of the texture. Vertices shared by triangles facing different
+/// sides are duplicated.
+pub fn project_uvs_box(
+    vertices: &[ModelVertex],
+    indices: &[u32],
+    scale: f32,
+) -> (Vec<ModelVertex>, Vec<u32>) {
+    let scale = repeat_scale(scale);
+    let mut builder = UvBuilder::new(vertices);
+
+    indices.chunks_exact(3).for_each(|triangle| {
+        let corners = [0, 1, 2].map(|corner| vertices[triangle[corner] as usize]);
+        let (right, up) = plane_basis(-dominant_axis(face_normal(&corners)));
+
+        triangle.iter().zip(corners).for_each(|(index, vertex)| {
+            builder.push(*index, planar_uv(vertex.pos, right, up) * scale)
+        });
+    });
+
+    builder.finish()
+}
+
+/// Wrap uvs around a cylinder along `axis` through the origin. U goes once around,
+/// starting from the seam at `seam_angle` radians, and v runs from the top of the mesh
+/// to the bottom. Triangles crossing the seam get duplicated vertices past `u = 1`.
+pub fn project_uvs_cylindrical(
+    vertices: &[ModelVertex],
+    indices: &[u32],
+    axis: glam::Vec3,
+    seam_angle: f32,
+) -> (Vec<ModelVertex>, Vec<u32>) {
+    let axis = axis.try_normalize().unwrap_or(glam::Vec3::Y);
+    let (right, up) = plane_basis(axis);
+
+    let (min, max) = vertices
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), vertex| {
+            let height = vertex.pos.dot(axis);
+            (min.min(height), max.max(height))
+        });
+    let height = (max - min).max(f32::EPSILON);
+
+    wrapped_projection(vertices, indices, |pos| {
+        let radius = pos - axis * pos.dot(axis);
+
+        (
+            glam::vec2(
+                around_u(pos, right, up, seam_angle),
+                (max - pos.dot(axis)) / height,
+            ),
+            radius.length() < AXIS_EPSILON,
+        )
+    })
+}
+
+/// Wrap uvs around a sphere centred on the origin with its poles on the y axis. U goes
+/// once around from `-x` and v runs from the top pole to the bottom. Triangles crossing
+/// the seam get duplicated vertices past `u = 1`, and each triangle touching a pole gets
+/// its own pole vertex.
+pub fn project_uvs_spherical(
+    vertices: &[ModelVertex],
+    indices: &[u32],
+) -> (Vec<ModelVertex>, Vec<u32>) {
+    let (right, up) = plane_basis(glam::Vec3::Y);
+
+    wrapped_projection(vertices, indices, |pos| {
+        let direction = pos.normalize_or_zero();
+        let latitude = direction.y.clamp(-1., 1.).acos() / std::f32::consts::PI;
+
+        (
+            glam::vec2(around_u(pos, right, up, 0.), latitude),
+            glam::vec2(direction.x, direction.z).length() < AXIS_EPSILON,
+        )
+    })
+}
+
+//====================================================================
+
+/// Two unit axes perpendicular to `axis`, pointing right and up when looking along it.
+fn plane_basis(axis: glam::Vec3) -> (glam::Vec3, glam::Vec3) {
+    let axis = axis.try_normalize().unwrap_or(glam::Vec3::Z);
+
+    let up = match axis.y.abs() > 0.999 {
+        true => glam::Vec3::Z,
+        false => glam::Vec3::Y,
+    };
+
+    let right = up.cross(axis).normalize();
+    (right, axis.cross(right))
+}
+
+/// Uv of `pos` on the plane of `right` and `up`, with v running down like texture space.
+#[inline]
+fn planar_uv(pos: glam::Vec3, right: glam::Vec3, up: glam::Vec3) -> glam::Vec2 {
+    glam::vec2(pos.dot(right), -pos.dot(up))
+}
+
+#[inline]
+fn repeat_scale(scale: f32) -> f32 {
+    match scale > 0. {
+        true => 1. / scale,
+        false => 1.,
+    }
+}
+
+/// The fraction of a turn around the plane of `right` and `up` from `seam_angle`,
+/// increasing to the right when seen from outside.
+#[inline]
+fn around_u(pos: glam::Vec3, right: glam::Vec3, up: glam::Vec3, seam_angle: f32) -> f32 {
+    let angle = (-pos.dot(up)).atan2(pos.dot(right)) - seam_angle;
+    angle.rem_euclid(TAU) / TAU
+}
+
+/// The vertex normals of a triangle combined, or its winding normal if they cancel out.
+fn face_normal(corners: &[ModelVertex; 3]) -> glam::Vec3 {
+    let normal = corners[0].normal + corners[1].normal + corners[2].normal;
+
+    match normal.length_squared() > f32::EPSILON {
+        true => normal,
+        false => (corners[1].pos - corners[0].pos).cross(corners[2].pos - corners[0].pos),
+    }
+}
+
+/// The signed world axis closest to `normal`.
+fn dominant_axis(normal: glam::Vec3) -> glam::Vec3 {
+    let abs = normal.abs();
+
+    let axis = match (abs.x >= abs.y && abs.x >= abs.z, abs.y >= abs.z) {
+        (true, _) => glam::Vec3::X,
+        (false, true) => glam::Vec3::Y,
+        (false, false) => glam::Vec3::Z,
+    };
+
+    match axis.dot(normal) < 0. {
+        true => -axis,
+        false => axis,
+    }
+}
+
+/// Project every triangle with `project`, which returns a vertex's uv and whether it's
+/// on the axis the projection wraps around. Each triangle's u is made continuous across
+/// the seam, and vertices on the axis take the average u of the rest of the triangle.
+fn wrapped_projection(
+    vertices: &[ModelVertex],
+    indices: &[u32],
+    project: impl Fn(glam::Vec3) -> (glam::Vec2, bool),
+) -> (Vec<ModelVertex>, Vec<u32>) {
+    let mut builder = UvBuilder::new(vertices);
+
+    indices.chunks_exact(3).for_each(|triangle| {
+        let projected = [0, 1, 2].map(|corner| project(vertices[triangle[corner] as usize].pos));
+        let mut uvs = projected.map(|(uv, _)| uv);
+
+        let around = (0..3).filter(|corner| !projected[*corner].1);
+
+        let max = around
+            .clone()
+            .fold(f32::MIN, |max, corner| max.max(uvs[corner].x));
+
+        // Corners more than half a turn behind the others are past the seam
+        around.clone().for_each(|corner| {
+            if max - uvs[corner].x > 0.5 {
+                uvs[corner].x += 1.;
+            }
+        });
+
+        let (sum, count) = around.fold((0., 0), |(sum, count), corner| {
+            (sum + uvs[corner].x, count + 1)
+        });
+
+        if count > 0 {
+            (0..3)
+                .filter(|corner| projected[*corner].1)
+                .for_each(|corner| uvs[corner].x = sum / count as f32);
+        }
+
+        triangle
+            .iter()
+            .zip(uvs)
+            .for_each(|(index, uv)| builder.push(*index, uv));
+    });
+
+    builder.finish()
+}
+
+/// Collects the projected vertices, only sharing a vertex between triangles that give
+/// it the same uv.
+struct UvBuilder<'a> {
+    source: &'a [ModelVertex],
+    vertices: Vec<ModelVertex>,
+    indices: Vec<u32>,
+    lookup: HashMap<(u32, [u32; 2]), u32, FastHasher>,
+}
+
+impl<'a> UvBuilder<'a> {
+    fn new(source: &'a [ModelVertex]) -> Self {
+        Self {
+            source,
+            vertices: Vec::with_capacity(source.len()),
+            indices: Vec::new(),
+            lookup: HashMap::default(),
+        }
+    }
+
+    fn push(&mut self, index: u32, uv: glam::Vec2) {
+        let key = (index, uv.to_array().map(f32::to_bits));
+
+        let new_index = *self.lookup.entry(key).or_insert_with(|| {
+            let mut vertex = self.source[index as usize];
+            vertex.uv = uv;
+
+            self.vertices.push(vertex);
+            self.vertices.len() as u32 - 1
+        });
+
+        self.indices.push(new_index);
+    }
+
+    fn finish(mut self) -> (Vec<ModelVertex>, Vec<u32>) {
+        model::compute_tangents(&mut self.vertices, &self.indices);
+        (self.vertices, self.indices)
+    }
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(pos: glam::Vec3, normal: glam::Vec3) -> ModelVertex {
+        ModelVertex {
+            pos,
+            uv: glam::Vec2::ZERO,
+            normal,
+            tangent: glam::Vec4::ZERO,
+        }
+    }
+
+    /// A point `angle` radians around the y axis from `-x`, the spherical seam.
+    fn around_y(angle: f32, radius: f32, height: f32) -> glam::Vec3 {
+        glam::vec3(-angle.cos() * radius, height, -angle.sin() * radius)
+    }
+
+    /// An open cylinder around the y axis with no duplicated seam vertices.
+    fn welded_cylinder(sectors: u32) -> (Vec<ModelVertex>, Vec<u32>) {
+        let vertices = [1., 0.]
+            .into_iter()
+            .flat_map(|height| {
+                (0..sectors).map(move |sector| {
+                    let pos = around_y(sector as f32 / sectors as f32 * TAU, 1., height);
+                    vertex(pos, glam::vec3(pos.x, 0., pos.z))
+                })
+            })
+            .collect();
+
+        let indices = (0..sectors)
+            .flat_map(|sector| {
+                let next = (sector + 1) % sectors;
+                let (a, b) = (sector, next);
+                let (c, d) = (sector + sectors, next + sectors);
+                [a, c, b, b, c, d]
+            })
+            .collect();
+
+        (vertices, indices)
+    }
+
+    /// A sphere with one vertex per pole and no duplicated seam vertices.
+    fn welded_sphere(rings: u32, sectors: u32) -> (Vec<ModelVertex>, Vec<u32>) {
+        let mut vertices = vec![
+            vertex(glam::Vec3::Y, glam::Vec3::Y),
+            vertex(glam::Vec3::NEG_Y, glam::Vec3::NEG_Y),
+        ];
+
+        (1..rings).for_each(|ring| {
+            let latitude = ring as f32 / rings as f32 * std::f32::consts::PI;
+            (0..sectors).for_each(|sector| {
+                let angle = sector as f32 / sectors as f32 * TAU;
+                let pos = around_y(angle, latitude.sin(), latitude.cos());
+                vertices.push(vertex(pos, pos));
+            });
+        });
+
+        let ring_vertex = |ring: u32, sector: u32| 2 + (ring - 1) * sectors + sector % sectors;
+
+        let mut indices = Vec::new();
+        (0..sectors).for_each(|sector| {
+            indices.extend([0, ring_vertex(1, sector), ring_vertex(1, sector + 1)]);
+            indices.extend([
+                1,
+                ring_vertex(rings - 1, sector + 1),
+                ring_vertex(rings - 1, sector),
+            ]);
+
+            (1..rings - 1).for_each(|ring| {
+                let (a, b) = (ring_vertex(ring, sector), ring_vertex(ring, sector + 1));
+                let (c, d) = (
+                    ring_vertex(ring + 1, sector),
+                    ring_vertex(ring + 1, sector + 1),
+                );
+                indices.extend([a, c, b, b, c, d]);
+            });
+        });
+
+        (vertices, indices)
+    }
+
+    fn assert_in_unit_square(vertices: &[ModelVertex]) {
+        vertices.iter().for_each(|vertex| {
+            assert!(
+                vertex.uv.cmpge(glam::Vec2::ZERO).all() && vertex.uv.cmple(glam::Vec2::ONE).all(),
+                "uv {} of {} outside 0..1",
+                vertex.uv,
+                vertex.pos
+            );
+        });
+    }
+
+    /// No triangle's u jumps across the texture, which would smear it.
+    fn assert_continuous(vertices: &[ModelVertex], indices: &[u32], max_span: f32) {
+        indices.chunks_exact(3).for_each(|triangle| {
+            let u = triangle.iter().map(|index| vertices[*index as usize].uv.x);
+            let span = u.clone().fold(f32::MIN, f32::max) - u.fold(f32::MAX, f32::min);
+            assert!(span <= max_span + 1e-5, "triangle spans {} of u", span);
+        });
+    }
+
+    fn assert_finite(vertices: &[ModelVertex]) {
+        vertices.iter().for_each(|vertex| {
+            assert!(vertex.uv.is_finite(), "uv {}", vertex.uv);
+            assert!(vertex.tangent.is_finite(), "tangent {}", vertex.tangent);
+        });
+    }
+
+    #[test]
+    fn cylinder_duplicates_the_seam() {
+        let sectors = 8;
+        let (vertices, indices) = welded_cylinder(sectors);
+
+        let (projected, projected_indices) =
+            project_uvs_cylindrical(&vertices, &indices, glam::Vec3::Y, 0.);
+
+        // Only the column on the seam is duplicated, once per ring
+        assert_eq!(projected.len(), vertices.len() + 2);
+        assert_eq!(projected_indices.len(), indices.len());
+        assert_in_unit_square(&projected);
+        assert_continuous(&projected, &projected_indices, 1. / sectors as f32);
+
+        let seam = projected
+            .iter()
+            .filter(|vertex| vertex.pos == vertices[0].pos)
+            .map(|vertex| vertex.uv)
+            .collect::<Vec<_>>();
+        assert_eq!(seam.len(), 2);
+        assert!(seam.contains(&glam::vec2(0., 0.)));
+        assert!(seam.contains(&glam::vec2(1., 0.)));
+
+        // Positions and normals are kept
+        projected_indices
+            .iter()
+            .zip(&indices)
+            .for_each(|(new, old)| {
+                assert_eq!(projected[*new as usize].pos, vertices[*old as usize].pos);
+                assert_eq!(
+                    projected[*new as usize].normal,
+                    vertices[*old as usize].normal
+                );
+            });
+    }
+
+    #[test]
+    fn cylinder_seam_between_vertices() {
+        let sectors = 8;
+        let step = 1. / sectors as f32;
+        let (vertices, indices) = welded_cylinder(sectors);
+
+        let (projected, projected_indices) =
+            project_uvs_cylindrical(&vertices, &indices, glam::Vec3::Y, TAU * step / 2.);
+
+        // Triangles over the seam run past 1 by at most one sector
+        assert_eq!(projected.len(), vertices.len() + 2);
+        assert_continuous(&projected, &projected_indices, step);
+        projected.iter().for_each(|vertex| {
+            assert!(
+                vertex.uv.x >= 0. && vertex.uv.x <= 1. + step,
+                "{}",
+                vertex.uv
+            );
+            assert!(vertex.uv.y >= 0. && vertex.uv.y <= 1., "{}", vertex.uv);
+        });
+    }
+
+    #[test]
+    fn sphere_duplicates_the_seam_and_poles() {
+        let (rings, sectors) = (6, 8);
+        let (vertices, indices) = welded_sphere(rings, sectors);
+
+        let (projected, projected_indices) = project_uvs_spherical(&vertices, &indices);
+
+        // A pole vertex per triangle touching it, and one seam vertex per ring between
+        let ring_vertices = (rings - 1) * sectors;
+        assert_eq!(
+            projected.len() as u32,
+            2 * sectors + ring_vertices + (rings - 1)
+        );
+        assert_in_unit_square(&projected);
+        assert_continuous(&projected, &projected_indices, 1. / sectors as f32);
+        assert_finite(&projected);
+
+        projected
+            .iter()
+            .filter(|vertex| vertex.pos.y.abs() == 1.)
+            .for_each(|vertex| {
+                assert_eq!(vertex.uv.y, (1. - vertex.pos.y) / 2.);
+                // Between the two corners of its triangle on the first ring
+                assert!(((vertex.uv.x * sectors as f32) % 1. - 0.5).abs() < 1e-4);
+            });
+    }
+
+    #[test]
+    fn box_splits_shared_corners() {
+        let corners = (0..8)
+            .map(|corner| {
+                let pos = glam::vec3(
+                    (corner & 1) as f32 - 0.5,
+                    ((corner >> 1) & 1) as f32 - 0.5,
+                    ((corner >> 2) & 1) as f32 - 0.5,
+                );
+                vertex(pos, pos.normalize())
+            })
+            .collect::<Vec<_>>();
+
+        #[rustfmt::skip]
+        let indices = [
+            0, 2, 3, 0, 3, 1, // -z
+            4, 5, 7, 4, 7, 6, // +z
+            0, 4, 6, 0, 6, 2, // -x
+            1, 3, 7, 1, 7, 5, // +x
+            0, 1, 5, 0, 5, 4, // -y
+            2, 6, 7, 2, 7, 3, // +y
+        ];
+
+        let (projected, projected_indices) = project_uvs_box(&corners, &indices, 2.);
+        assert!(projected.len() > corners.len());
+        assert_finite(&projected);
+
+        let normals = [
+            glam::Vec3::NEG_Z,
+            glam::Vec3::Z,
+            glam::Vec3::NEG_X,
+            glam::Vec3::X,
+            glam::Vec3::NEG_Y,
+            glam::Vec3::Y,
+        ];
+
+        projected_indices
+            .chunks_exact(6)
+            .zip(normals)
+            .for_each(|(face, normal)| {
+                // Corners shared with other faces still have this face's uvs
+                let (right, up) = plane_basis(-normal);
+                face.iter().for_each(|index| {
+                    let vertex = projected[*index as usize];
+                    assert_eq!(vertex.uv, planar_uv(vertex.pos, right, up) * 0.5);
+                });
+
+                let uvs = face.iter().map(|index| projected[*index as usize].uv);
+                let min = uvs.clone().fold(glam::Vec2::MAX, glam::Vec2::min);
+                let max = uvs.fold(glam::Vec2::MIN, glam::Vec2::max);
+                assert_eq!(max - min, glam::Vec2::splat(0.5));
+            });
+    }
+
+    #[test]
+    fn degenerate_triangles_stay_finite() {
+        let vertices = vec![
+            // No area at all
+            vertex(glam::Vec3::X, glam::Vec3::ZERO),
+            vertex(glam::Vec3::X, glam::Vec3::ZERO),
+            // Collinear
+            vertex(glam::Vec3::Y, glam::Vec3::X),
+            vertex(glam::Vec3::Y * 2., glam::Vec3::X),
+            // On the wrapping axis
+            vertex(glam::Vec3::ZERO, glam::Vec3::Y),
+        ];
+        let indices = [0, 1, 0, 2, 3, 4, 4, 4, 4, 4, 2, 3];
+
+        let (projected, _) = project_uvs_box(&vertices, &indices, 1.);
+        assert_finite(&projected);
+
+        let (projected, _) = project_uvs_cylindrical(&vertices, &indices, glam::Vec3::Y, 0.);
+        assert_finite(&projected);
+
+        let (projected, _) = project_uvs_spherical(&vertices, &indices);
+        assert_finite(&projected);
+
+        // A plane seen edge on collapses every uv onto a line
+        let mut planar = vertices.clone();
+        project_uvs_planar(&mut planar, glam::Vec3::X, 0.);
+        assert_finite(&planar);
+
+        // A triangle with no uv area leaves tangents unset rather than dividing by zero
+        let (projected, projected_indices) = project_uvs_box(&vertices[..2], &[0, 1, 0], 1.);
+        assert_eq!(projected_indices.len(), 3);
+        assert!(projected
+            .iter()
+            .all(|vertex| vertex.tangent.truncate() == glam::Vec3::ZERO));
+    }
+}
@@ -0,0 +1,314 @@
+//====================================================================
+
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use web_time::Duration;
+
+use crate::memory;
+
+//====================================================================
+
+/// Creations kept per frame. Any more are only counted.
+const MAX_CREATIONS: usize = 64;
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static CREATIONS: Mutex<Vec<Creation>> = Mutex::new(Vec::new());
+static DROPPED_CREATIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreationKind {
+    Pipeline,
+    Buffer,
+    Texture,
+}
+
+/// A gpu resource created through `tools` or `Texture::new`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Creation {
+    pub kind: CreationKind,
+    pub label: String,
+    /// Zero for pipelines.
+    pub bytes: u64,
+}
+
+/// Record a creation in the current frame's log. Does nothing unless a `FrameWatchdog`
+/// is enabled.
+pub fn record_creation(kind: CreationKind, label: &str, bytes: u64) {
+    if !RECORDING.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut creations = CREATIONS.lock().unwrap();
+
+    match creations.len() < MAX_CREATIONS {
+        true => creations.push(Creation {
+            kind,
+            label: label.to_string(),
+            bytes,
+        }),
+        false => {
+            DROPPED_CREATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[inline]
+fn take_creations() -> (Vec<Creation>, usize) {
+    let creations = std::mem::take(&mut *CREATIONS.lock().unwrap());
+    (creations, DROPPED_CREATIONS.swap(0, Ordering::Relaxed))
+}
+
+//====================================================================
+
+/// The parts of a frame timed by the `FrameWatchdog`, in frame order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogPhase {
+    /// User code, such as `HecsApp::tick`.
+    Tick,
+    /// Sliced main thread tasks run after the tick.
+    Tasks,
+    /// Creating deferred uploads.
+    Uploads,
+    Prep,
+    /// Waiting for the next surface texture.
+    SurfaceAcquire,
+    /// Recording the render passes.
+    Encode,
+    Submit,
+    Present,
+}
+
+/// Times the phases of each frame and logs a `WatchdogReport` when any phase takes
+/// longer than the threshold.
+#[derive(Debug)]
+pub struct FrameWatchdog {
+    threshold: Duration,
+    enabled: bool,
+    started: bool,
+    phases: Vec<(WatchdogPhase, Duration)>,
+    last_phases: Vec<(WatchdogPhase, Duration)>,
+    last_report: Option<WatchdogReport>,
+}
+
+impl Default for FrameWatchdog {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Duration::from_millis(100))
+    }
+}
+
+impl FrameWatchdog {
+    pub fn new(threshold: Duration) -> Self {
+        RECORDING.store(true, Ordering::Relaxed);
+
+        Self {
+            threshold,
+            enabled: true,
+            started: false,
+            phases: Vec::new(),
+            last_phases: Vec::new(),
+            last_report: None,
+        }
+    }
+
+    #[inline]
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    #[inline]
+    pub fn set_threshold(&mut self, threshold: Duration) {
+        self.threshold = threshold;
+    }
+
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabling also stops creations being recorded.
+    #[inline]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        RECORDING.store(enabled, Ordering::Relaxed);
+    }
+
+    /// The last report logged, if any frame went over the threshold.
+    #[inline]
+    pub fn last_report(&self) -> Option<&WatchdogReport> {
+        self.last_report.as_ref()
+    }
+
+    /// Time spent in `phase` during the last finished frame.
+    #[inline]
+    pub fn last_frame_phase(&self, phase: WatchdogPhase) -> Duration {
+        self.last_phases
+            .iter()
+            .find(|(existing, _)| *existing == phase)
+            .map(|(_, duration)| *duration)
+            .unwrap_or_default()
+    }
+
+    /// Total time of every phase of the last finished frame. Waiting on the gpu shows
+    /// up in `SurfaceAcquire` and `Present`.
+    #[inline]
+    pub fn last_frame_time(&self) -> Duration {
+        self.last_phases.iter().map(|(_, duration)| *duration).sum()
+    }
+
+    /// Start timing a frame. Creations from before this point are discarded. Does
+    /// nothing if the frame was already started.
+    pub fn start_frame(&mut self) {
+        if self.started {
+            return;
+        }
+
+        self.started = true;
+        self.phases.clear();
+        take_creations();
+    }
+
+    /// Add time spent in `phase` to the current frame.
+    pub fn record(&mut self, phase: WatchdogPhase, duration: Duration) {
+        if !self.enabled {
+            return;
+        }
+
+        match self
+            .phases
+            .iter_mut()
+            .find(|(existing, _)| *existing == phase)
+        {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((phase, duration)),
+        }
+    }
+
+    /// End the frame, logging a report if a phase went over the threshold.
+    /// `pipelines` are the pipelines active during the frame.
+    pub fn finish_frame(&mut self, pipelines: impl FnOnce() -> Vec<String>) -> bool {
+        self.started = false;
+
+        if !self.enabled {
+            return false;
+        }
+
+        self.last_phases = std::mem::take(&mut self.phases);
+
+        let Some(slowest) = self
+            .last_phases
+            .iter()
+            .filter(|(_, duration)| *duration > self.threshold)
+            .max_by_key(|(_, duration)| *duration)
+            .copied()
+        else {
+            take_creations();
+            return false;
+        };
+
+        let (creations, dropped_creations) = take_creations();
+
+        let report = WatchdogReport {
+            slowest,
+            threshold: self.threshold,
+            phases: self.last_phases.clone(),
+            pipelines: pipelines(),
+            creations,
+            dropped_creations,
+        };
+
+        log::warn!("{}", report);
+        self.last_report = Some(report);
+
+        true
+    }
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchdogReport {
+    /// The slowest phase over the threshold.
+    pub slowest: (WatchdogPhase, Duration),
+    pub threshold: Duration,
+    pub phases: Vec<(WatchdogPhase, Duration)>,
+    pub pipelines: Vec<String>,
+    pub creations: Vec<Creation>,
+    /// Creations past the per frame limit, only counted.
+    pub dropped_creations: usize,
+}
+
+impl WatchdogReport {
+    /// Pipelines created during the frame. Each one likely compiled shaders.
+    #[inline]
+    pub fn created_pipelines(&self) -> impl Iterator<Item = &Creation> {
+        self.creations
+            .iter()
+            .filter(|creation| creation.kind == CreationKind::Pipeline)
+    }
+}
+
+impl Display for WatchdogReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Long frame - {:?} took {:.1}ms (threshold {:.1}ms)",
+            self.slowest.0,
+            self.slowest.1.as_secs_f32() * 1000.,
+            self.threshold.as_secs_f32() * 1000.
+        )?;
+
+        let phases = self
+            .phases
+            .iter()
+            .map(|(phase, duration)| format!("{:?} {:.1}ms", phase, duration.as_secs_f32() * 1000.))
+            .collect::<Vec<_>>();
+
+        writeln!(f, "  Phases: {}", phases.join(", "))?;
+        writeln!(f, "  Active pipelines: {}", self.pipelines.join(", "))?;
+
+        if !self.creations.is_empty() {
+            writeln!(f, "  Created this frame:")?;
+        }
+
+        for creation in &self.creations {
+            match creation.kind {
+                CreationKind::Pipeline => {
+                    writeln!(f, "    {:?} '{}'", creation.kind, creation.label)?
+                }
+                _ => writeln!(
+                    f,
+                    "    {:?} '{}' ({})",
+                    creation.kind,
+                    creation.label,
+                    memory::format_bytes(creation.bytes)
+                )?,
+            }
+        }
+
+        if self.dropped_creations > 0 {
+            writeln!(f, "    and {} more", self.dropped_creations)?;
+        }
+
+        let created_pipelines = self.created_pipelines().count();
+        if created_pipelines > 0 {
+            write!(
+                f,
+                "  {} pipeline(s) were created this frame and likely stalled on shader \
+                compilation. Create them while loading instead, such as with \
+                RendererState::prewarm_pipelines.",
+                created_pipelines
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+//====================================================================
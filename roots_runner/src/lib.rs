@@ -21,18 +21,36 @@ pub mod prelude {
 }
 
 pub mod runner;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
 pub mod window;
 
 //====================================================================
 
 pub enum WindowInputEvent {
-    KeyInput { key: KeyCode, pressed: bool },
-    MouseInput { button: MouseButton, pressed: bool },
-    CursorMoved { position: (f64, f64) },
+    KeyInput {
+        key: KeyCode,
+        pressed: bool,
+    },
+    MouseInput {
+        button: MouseButton,
+        pressed: bool,
+    },
+    CursorMoved {
+        position: (f64, f64),
+    },
     CursorEntered,
     CursorLeft,
-    MouseWheel { delta: (f32, f32) },
-    MouseMotion { delta: (f64, f64) },
+    MouseWheel {
+        delta: (f32, f32),
+    },
+    MouseMotion {
+        delta: (f64, f64),
+    },
+    /// Text produced by a key press, after keyboard layout and modifiers are applied.
+    Text {
+        text: String,
+    },
 }
 
 //====================================================================
@@ -102,12 +120,23 @@ pub trait RunnerState {
 
     fn resized(&mut self, new_size: Size<u32>);
 
+    /// The window moved to a display with a different scale factor, or the browser
+    /// zoom changed on the web. A `resized` call with the new physical size follows.
+    fn scale_factor_changed(&mut self, scale_factor: f64) {
+        let _ = scale_factor;
+    }
+
     fn close_requested(&mut self, event_loop: &ActiveEventLoop) {
         log::info!("Close requested. Closing App.");
         event_loop.exit();
     }
 
     fn tick(&mut self, event_loop: &ActiveEventLoop);
+
+    /// Every event queued so far has been handled and the event loop is about to wait.
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let _ = event_loop;
+    }
 }
 
 //====================================================================
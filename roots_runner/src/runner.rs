@@ -31,6 +31,9 @@ impl<S: RunnerState> ApplicationHandler for Runner<S> {
                 winit::event::WindowEvent::Resized(new_size) => {
                     runner_state.resized(Size::new(new_size.width, new_size.height))
                 }
+                winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    runner_state.scale_factor_changed(scale_factor)
+                }
 
                 //--------------------------------------------------
                 //
@@ -51,6 +54,12 @@ impl<S: RunnerState> ApplicationHandler for Runner<S> {
                             pressed: event.state.is_pressed(),
                         });
                     }
+
+                    if let (true, Some(text)) = (event.state.is_pressed(), &event.text) {
+                        runner_state.input_event(WindowInputEvent::Text {
+                            text: text.to_string(),
+                        });
+                    }
                 }
 
                 winit::event::WindowEvent::CursorMoved { position, .. } => runner_state
@@ -105,6 +114,13 @@ impl<S: RunnerState> ApplicationHandler for Runner<S> {
         }
     }
 
+    #[inline]
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        if let Some(state) = &mut self.state {
+            state.about_to_wait(event_loop);
+        }
+    }
+
     fn device_event(
         &mut self,
         event_loop: &winit::event_loop::ActiveEventLoop,
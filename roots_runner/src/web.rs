@@ -0,0 +1,93 @@
+//====================================================================
+
+use std::sync::Arc;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use winit::{dpi::LogicalSize, platform::web::WindowExtWebSys};
+
+//====================================================================
+
+/// Id of the element the canvas is added to. Its layout size, set with CSS, is the
+/// size of the window.
+pub const APP_ELEMENT_ID: &str = "roots_app";
+
+/// Keeps the canvas the same size as its parent element. Disconnected when dropped.
+pub(crate) struct CanvasResizeObserver {
+    observer: web_sys::ResizeObserver,
+    _callback: Closure<dyn FnMut(js_sys::Array)>,
+}
+
+impl Drop for CanvasResizeObserver {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+/// Add the canvas to the app element and size it to the element's layout size. Returns
+/// `None` if the element doesn't exist.
+pub(crate) fn attach_canvas(window: &winit::window::Window) -> Option<web_sys::Element> {
+    let parent = web_sys::window()?
+        .document()?
+        .get_element_by_id(APP_ELEMENT_ID)?;
+
+    let canvas = window.canvas()?;
+
+    // An inline canvas leaves a gap under it, which would grow a parent sized by its
+    // content every time the canvas follows it
+    canvas.style().set_property("display", "block").ok()?;
+    parent.append_child(&canvas).ok()?;
+
+    request_css_size(
+        window,
+        parent.client_width() as f64,
+        parent.client_height() as f64,
+    );
+
+    Some(parent)
+}
+
+/// Resize the canvas whenever `parent` changes size.
+pub(crate) fn observe_parent(
+    window: &Arc<winit::window::Window>,
+    parent: &web_sys::Element,
+) -> Option<CanvasResizeObserver> {
+    let window = Arc::downgrade(window);
+
+    let callback = Closure::<dyn FnMut(js_sys::Array)>::new(move |entries: js_sys::Array| {
+        let Some(window) = window.upgrade() else {
+            return;
+        };
+
+        let Ok(entry) = entries.get(0).dyn_into::<web_sys::ResizeObserverEntry>() else {
+            return;
+        };
+
+        let rect = entry.content_rect();
+        request_css_size(&window, rect.width(), rect.height());
+    });
+
+    let observer = web_sys::ResizeObserver::new(callback.as_ref().unchecked_ref()).ok()?;
+    observer.observe(parent);
+
+    Some(CanvasResizeObserver {
+        observer,
+        _callback: callback,
+    })
+}
+
+/// Size the canvas in CSS pixels. Winit scales these by the device pixel ratio for
+/// the surface, and sends a `Resized` event like on native platforms.
+fn request_css_size(window: &winit::window::Window, width: f64, height: f64) {
+    // Not laid out yet, so keep the current size
+    if width < 1. || height < 1. {
+        log::warn!(
+            "#{} has no size - give it a width and height with CSS",
+            APP_ELEMENT_ID
+        );
+        return;
+    }
+
+    let _ = window.request_inner_size(LogicalSize::new(width, height));
+}
+
+//====================================================================
@@ -3,47 +3,59 @@
 use std::sync::Arc;
 
 use roots_common::Size;
-use winit::{event_loop::ActiveEventLoop, window::WindowAttributes};
+use winit::{
+    dpi::PhysicalPosition,
+    event_loop::ActiveEventLoop,
+    monitor::{MonitorHandle, VideoModeHandle},
+    window::{Fullscreen, WindowAttributes},
+};
 
 //====================================================================
 
-pub struct Window(Arc<winit::window::Window>);
+pub struct Window {
+    window: Arc<winit::window::Window>,
+
+    #[cfg(target_arch = "wasm32")]
+    _resize_observer: Option<crate::web::CanvasResizeObserver>,
+}
+
 impl Window {
     pub fn new(event_loop: &ActiveEventLoop, window_attributes: Option<WindowAttributes>) -> Self {
         log::info!("Creating new window");
 
         let attributes = window_attributes.unwrap_or_default();
-        let window = event_loop.create_window(attributes).unwrap();
+        let window = Arc::new(event_loop.create_window(attributes).unwrap());
 
         #[cfg(target_arch = "wasm32")]
-        {
-            use winit::{dpi::PhysicalSize, platform::web::WindowExtWebSys};
-
+        let resize_observer = {
             log::info!("Adding canvas to window");
 
-            if let None = window.request_inner_size(PhysicalSize::new(450, 400)) {
-                log::warn!(
-                    "Wasm Window Resize Warning: Got none when requesting window inner size"
-                );
+            let parent = crate::web::attach_canvas(&window).unwrap_or_else(|| {
+                panic!(
+                    "Couldn't append canvas to #{} element",
+                    crate::web::APP_ELEMENT_ID
+                )
+            });
+
+            let observer = crate::web::observe_parent(&window, &parent);
+            if observer.is_none() {
+                log::warn!("Couldn't observe canvas parent - window won't follow its size");
             }
 
-            web_sys::window()
-                .and_then(|win| win.document())
-                .and_then(|doc| {
-                    let dst = doc.get_element_by_id("roots_app")?;
-                    let canvas = web_sys::Element::from(window.canvas()?);
-                    dst.append_child(&canvas).ok()?;
-                    Some(())
-                })
-                .expect("Couldn't append canvas to document body.");
-        }
+            observer
+        };
+
+        Self {
+            window,
 
-        Self(Arc::new(window))
+            #[cfg(target_arch = "wasm32")]
+            _resize_observer: resize_observer,
+        }
     }
 
     #[inline]
     pub fn size(&self) -> Size<u32> {
-        let window_size = self.0.inner_size();
+        let window_size = self.window.inner_size();
 
         Size {
             width: window_size.width,
@@ -55,7 +67,7 @@ impl Window {
     pub fn confine_cursor(&self, confined: bool) {
         log::trace!("Confining window cursor: {}", confined);
 
-        self.0
+        self.window
             .set_cursor_grab(match confined {
                 true => winit::window::CursorGrabMode::Confined,
                 false => winit::window::CursorGrabMode::None,
@@ -66,22 +78,300 @@ impl Window {
     #[inline]
     pub fn hide_cursor(&self, hidden: bool) {
         log::trace!("Hiding window cursor: {}", hidden);
-        self.0.set_cursor_visible(!hidden);
+        self.window.set_cursor_visible(!hidden);
     }
 
     #[inline]
     pub fn inner(&self) -> &winit::window::Window {
-        &self.0
+        &self.window
     }
 
     #[inline]
     pub fn arc(&self) -> &Arc<winit::window::Window> {
-        &self.0
+        &self.window
     }
 
     #[inline]
     pub fn clone_arc(&self) -> Arc<winit::window::Window> {
-        self.0.clone()
+        self.window.clone()
+    }
+}
+
+//====================================================================
+
+/// An exclusive fullscreen mode supported by a monitor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoModeInfo {
+    pub size: Size<u32>,
+    pub bit_depth: u16,
+    pub refresh_rate_millihertz: u32,
+    handle: VideoModeHandle,
+}
+
+impl VideoModeInfo {
+    fn new(handle: VideoModeHandle) -> Self {
+        let size = handle.size();
+
+        Self {
+            size: Size::new(size.width, size.height),
+            bit_depth: handle.bit_depth(),
+            refresh_rate_millihertz: handle.refresh_rate_millihertz(),
+            handle,
+        }
+    }
+
+    #[inline]
+    pub fn refresh_rate(&self) -> f32 {
+        self.refresh_rate_millihertz as f32 / 1000.
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    /// Size in physical pixels.
+    pub size: Size<u32>,
+    /// Top left of the monitor on the desktop, in physical pixels.
+    pub position: (i32, i32),
+    pub scale_factor: f64,
+    /// The current refresh rate, if known.
+    pub refresh_rate_millihertz: Option<u32>,
+    pub video_modes: Vec<VideoModeInfo>,
+    handle: MonitorHandle,
+}
+
+impl MonitorInfo {
+    fn new(handle: MonitorHandle) -> Self {
+        let size = handle.size();
+        let position = handle.position();
+
+        Self {
+            name: handle.name(),
+            size: Size::new(size.width, size.height),
+            position: (position.x, position.y),
+            scale_factor: handle.scale_factor(),
+            refresh_rate_millihertz: handle.refresh_rate_millihertz(),
+            video_modes: handle.video_modes().map(VideoModeInfo::new).collect(),
+            handle,
+        }
+    }
+
+    /// The current refresh rate in hertz, if known.
+    #[inline]
+    pub fn refresh_rate(&self) -> Option<f32> {
+        self.refresh_rate_millihertz
+            .map(|millihertz| millihertz as f32 / 1000.)
+    }
+
+    /// Every refresh rate in hertz the video modes support, highest first.
+    pub fn refresh_rates(&self) -> Vec<f32> {
+        let mut rates = self
+            .video_modes
+            .iter()
+            .map(|mode| mode.refresh_rate_millihertz)
+            .collect::<Vec<_>>();
+
+        rates.sort_unstable_by(|a, b| b.cmp(a));
+        rates.dedup();
+        rates
+            .into_iter()
+            .map(|millihertz| millihertz as f32 / 1000.)
+            .collect()
+    }
+
+    /// The video mode closest to `size` and `refresh_rate_millihertz`. Size is matched
+    /// first, then refresh rate, then the highest bit depth.
+    pub fn closest_video_mode(
+        &self,
+        size: Size<u32>,
+        refresh_rate_millihertz: u32,
+    ) -> Option<&VideoModeInfo> {
+        self.video_modes.iter().min_by_key(|mode| {
+            let size_diff = mode.size.width.abs_diff(size.width) as u64
+                + mode.size.height.abs_diff(size.height) as u64;
+            let refresh_diff = mode
+                .refresh_rate_millihertz
+                .abs_diff(refresh_rate_millihertz);
+
+            (size_diff, refresh_diff, u16::MAX - mode.bit_depth)
+        })
+    }
+}
+
+/// A fullscreen choice that can be stored with the app's settings and restored
+/// with `Window::apply_fullscreen`. Monitors are identified by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FullscreenSetting {
+    #[default]
+    Windowed,
+    Borderless {
+        monitor: Option<String>,
+    },
+    Exclusive {
+        monitor: Option<String>,
+        size: Size<u32>,
+        refresh_rate_millihertz: u32,
+    },
+}
+
+//--------------------------------------------------
+
+impl Window {
+    /// Every monitor currently connected.
+    pub fn monitors(&self) -> Vec<MonitorInfo> {
+        self.window
+            .available_monitors()
+            .map(MonitorInfo::new)
+            .collect()
+    }
+
+    /// The monitor the window is mostly on.
+    #[inline]
+    pub fn current_monitor(&self) -> Option<MonitorInfo> {
+        self.window.current_monitor().map(MonitorInfo::new)
+    }
+
+    #[inline]
+    pub fn primary_monitor(&self) -> Option<MonitorInfo> {
+        self.window.primary_monitor().map(MonitorInfo::new)
+    }
+
+    /// The first connected monitor called `name`.
+    #[inline]
+    pub fn find_monitor(&self, name: &str) -> Option<MonitorInfo> {
+        self.window
+            .available_monitors()
+            .find(|monitor| monitor.name().as_deref() == Some(name))
+            .map(MonitorInfo::new)
+    }
+
+    /// Top left of the window frame on the desktop, in physical pixels.
+    #[inline]
+    pub fn outer_position(&self) -> Option<(i32, i32)> {
+        self.window
+            .outer_position()
+            .ok()
+            .map(|position| (position.x, position.y))
+    }
+
+    /// Move the window frame, in physical pixels.
+    #[inline]
+    pub fn set_outer_position(&self, x: i32, y: i32) {
+        self.window.set_outer_position(PhysicalPosition::new(x, y));
+    }
+
+    /// Move the window to the centre of `monitor`.
+    pub fn center_on(&self, monitor: &MonitorInfo) {
+        let outer = self.window.outer_size();
+
+        let x = monitor.position.0 + (monitor.size.width as i32 - outer.width as i32) / 2;
+        let y = monitor.position.1 + (monitor.size.height as i32 - outer.height as i32) / 2;
+
+        self.set_outer_position(x, y);
+    }
+
+    /// Make the window fullscreen on `monitor`. With a video mode from that monitor
+    /// the fullscreen is exclusive, otherwise it's borderless.
+    pub fn set_fullscreen_on(&self, monitor: &MonitorInfo, video_mode: Option<&VideoModeInfo>) {
+        log::trace!(
+            "Setting fullscreen on monitor {:?} with video mode {:?}",
+            monitor.name,
+            video_mode.map(|mode| (mode.size, mode.refresh_rate_millihertz))
+        );
+
+        self.window.set_fullscreen(Some(match video_mode {
+            Some(mode) => Fullscreen::Exclusive(mode.handle.clone()),
+            None => Fullscreen::Borderless(Some(monitor.handle.clone())),
+        }));
+    }
+
+    #[inline]
+    pub fn exit_fullscreen(&self) {
+        self.window.set_fullscreen(None);
+    }
+
+    /// Toggle borderless fullscreen on the current monitor. On the web this requests
+    /// fullscreen for the canvas, which browsers only allow from an input event.
+    #[inline]
+    pub fn set_fullscreen(&self, fullscreen: bool) {
+        log::trace!("Setting fullscreen: {}", fullscreen);
+
+        self.window.set_fullscreen(match fullscreen {
+            true => Some(Fullscreen::Borderless(None)),
+            false => None,
+        });
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        self.window.fullscreen().is_some()
+    }
+
+    /// The current fullscreen state, for storing with `apply_fullscreen`.
+    pub fn fullscreen_setting(&self) -> FullscreenSetting {
+        match self.window.fullscreen() {
+            None => FullscreenSetting::Windowed,
+            Some(Fullscreen::Borderless(monitor)) => FullscreenSetting::Borderless {
+                monitor: monitor.and_then(|monitor| monitor.name()),
+            },
+            Some(Fullscreen::Exclusive(mode)) => {
+                let size = mode.size();
+
+                FullscreenSetting::Exclusive {
+                    monitor: mode.monitor().name(),
+                    size: Size::new(size.width, size.height),
+                    refresh_rate_millihertz: mode.refresh_rate_millihertz(),
+                }
+            }
+        }
+    }
+
+    /// Restore a stored fullscreen state. If the monitor isn't connected the current
+    /// monitor is used instead, and exclusive fullscreen uses the closest video mode
+    /// available, falling back to borderless.
+    pub fn apply_fullscreen(&self, setting: &FullscreenSetting) {
+        let monitor = |name: &Option<String>| {
+            name.as_deref()
+                .and_then(|name| {
+                    let found = self.find_monitor(name);
+                    if found.is_none() {
+                        log::warn!("Monitor '{}' not connected - using current monitor", name);
+                    }
+                    found
+                })
+                .or_else(|| self.current_monitor())
+                .or_else(|| self.primary_monitor())
+        };
+
+        match setting {
+            FullscreenSetting::Windowed => self.exit_fullscreen(),
+
+            FullscreenSetting::Borderless { monitor: name } => match monitor(name) {
+                Some(monitor) => self.set_fullscreen_on(&monitor, None),
+                None => self
+                    .window
+                    .set_fullscreen(Some(Fullscreen::Borderless(None))),
+            },
+
+            FullscreenSetting::Exclusive {
+                monitor: name,
+                size,
+                refresh_rate_millihertz,
+            } => match monitor(name) {
+                Some(monitor) => {
+                    let mode = monitor.closest_video_mode(*size, *refresh_rate_millihertz);
+
+                    if mode.is_none() {
+                        log::warn!("No video modes available - using borderless fullscreen");
+                    }
+
+                    self.set_fullscreen_on(&monitor, mode);
+                }
+                None => self
+                    .window
+                    .set_fullscreen(Some(Fullscreen::Borderless(None))),
+            },
+        }
     }
 }
 
@@ -0,0 +1,150 @@
+//====================================================================
+// Compares uploading a paragraph's glyphs with one `Texture::update_area` call
+// per glyph against a single `TextureWriteBatch` flush.
+// Run with `cargo bench -p roots_text`.
+
+use std::time::Instant;
+
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, SwashCache};
+use roots_renderer::texture::{Texture, TextureWriteBatch};
+
+//====================================================================
+
+const GLYPH_COUNT: usize = 2_000;
+const TEXTURE_SIZE: u32 = 2048;
+const ITERATIONS: u32 = 10;
+
+struct GlyphImage {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+fn main() {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+    let adapter = match pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        force_fallback_adapter: false,
+        ..Default::default()
+    })) {
+        Some(adapter) => adapter,
+        None => {
+            println!("No adapter available - skipping glyph upload benchmark");
+            return;
+        }
+    };
+
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .unwrap();
+
+    let glyphs = rasterize_paragraph();
+    let mut texture = Texture::from_size(
+        &device,
+        (TEXTURE_SIZE, TEXTURE_SIZE),
+        Some("Glyph Upload Bench"),
+        None,
+    );
+
+    let per_glyph = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            glyphs.iter().for_each(|glyph| {
+                texture.update_area(
+                    &queue,
+                    &glyph.data,
+                    glyph.x,
+                    glyph.y,
+                    glyph.width,
+                    glyph.height,
+                )
+            });
+            queue.submit(None);
+            device.poll(wgpu::Maintain::Wait);
+            start.elapsed().as_secs_f64()
+        })
+        .sum::<f64>()
+        / ITERATIONS as f64;
+
+    let mut batch = TextureWriteBatch::new(1);
+
+    let batched = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            glyphs.iter().for_each(|glyph| {
+                batch.push(&glyph.data, glyph.x, glyph.y, glyph.width, glyph.height)
+            });
+            batch.flush(&device, &queue, &texture.texture);
+            device.poll(wgpu::Maintain::Wait);
+            start.elapsed().as_secs_f64()
+        })
+        .sum::<f64>()
+        / ITERATIONS as f64;
+
+    println!(
+        "{} glyphs - update_area per glyph: {:.3}ms, TextureWriteBatch: {:.3}ms",
+        glyphs.len(),
+        per_glyph * 1000.,
+        batched * 1000.,
+    );
+}
+
+/// Rasterize every glyph of a paragraph, including repeats, and shelf pack them
+/// into the texture like a cold atlas would.
+fn rasterize_paragraph() -> Vec<GlyphImage> {
+    let mut font_system = FontSystem::new();
+    let mut swash_cache = SwashCache::new();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(18., 22.));
+    buffer.set_size(&mut font_system, Some(1200.), None);
+
+    let text = "The quick brown fox jumps over the lazy dog, 0123456789. ".repeat(GLYPH_COUNT / 50);
+    buffer.set_text(&mut font_system, &text, Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let keys = buffer
+        .layout_runs()
+        .flat_map(|run| {
+            run.glyphs
+                .iter()
+                .map(|glyph| glyph.physical((0., 0.), 1.).cache_key)
+                .collect::<Vec<_>>()
+        })
+        .take(GLYPH_COUNT)
+        .collect::<Vec<_>>();
+
+    let (mut x, mut y, mut shelf) = (0, 0, 0);
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let image = swash_cache.get_image_uncached(&mut font_system, key)?;
+            let (width, height) = (image.placement.width, image.placement.height);
+
+            if x + width > TEXTURE_SIZE {
+                x = 0;
+                y += shelf;
+                shelf = 0;
+            }
+
+            let glyph = GlyphImage {
+                x,
+                y,
+                width,
+                height,
+                data: image.data,
+            };
+
+            x += width + 1;
+            shelf = shelf.max(height + 1);
+
+            match y + height <= TEXTURE_SIZE {
+                true => Some(glyph),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+//====================================================================
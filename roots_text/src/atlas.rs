@@ -6,13 +6,27 @@ use cosmic_text::{CacheKey, SwashImage};
 use etagere::{euclid::Size2D, AllocId, BucketedAtlasAllocator};
 use lru::LruCache;
 use roots_common::Size;
-use roots_renderer::{texture::Texture, tools};
+use roots_renderer::{
+    layouts,
+    memory::MemoryCategory,
+    shared::SharedRenderResources,
+    texture::{Texture, TextureWriteBatch},
+};
 use rustc_hash::FxHasher;
 
+use crate::icons::{self, IconHandle, RasterIcon};
+
 //====================================================================
 
 type FastHasher = BuildHasherDefault<FxHasher>;
 
+/// Glyphs and icons share the atlas so both can be drawn with the same bind group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AtlasKey {
+    Glyph(CacheKey),
+    Icon { handle: IconHandle, bucket: u32 },
+}
+
 pub struct GlyphData {
     alloc_id: AllocId,
     pub uv_start: [f32; 2],
@@ -26,8 +40,10 @@ pub struct GlyphData {
 #[derive(Debug)]
 pub enum CacheGlyphError {
     NoGlyphImage,
+    UnknownIcon,
     OutOfSpace,
     LruStorageError,
+    Renderer(roots_renderer::Error),
 }
 
 impl Error for CacheGlyphError {}
@@ -36,34 +52,44 @@ impl Display for CacheGlyphError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let msg = match &self {
             CacheGlyphError::NoGlyphImage => "Unable to get image from proved glyph.",
+            CacheGlyphError::UnknownIcon => "Icon does not belong to the provided icon set.",
             CacheGlyphError::OutOfSpace => {
                 "Atlas texture is not big enough to store new glyphs - TODO"
             }
             CacheGlyphError::LruStorageError => {
                 "Error accessing glyphs from LRU - This shouldn't really happen."
             }
+            CacheGlyphError::Renderer(e) => return write!(f, "{}", e),
         };
 
         write!(f, "{}", msg)
     }
 }
 
+impl From<roots_renderer::Error> for CacheGlyphError {
+    #[inline]
+    fn from(value: roots_renderer::Error) -> Self {
+        Self::Renderer(value)
+    }
+}
+
 //====================================================================
 
 pub struct TextAtlas {
     packer: BucketedAtlasAllocator,
 
-    glyphs_in_use: HashSet<CacheKey, FastHasher>,
-    cached_glyphs: LruCache<CacheKey, GlyphData, FastHasher>,
+    glyphs_in_use: HashSet<AtlasKey, FastHasher>,
+    cached_glyphs: LruCache<AtlasKey, GlyphData, FastHasher>,
 
     texture: Texture,
     texture_size: Size<u32>,
-    bind_group_layout: wgpu::BindGroupLayout,
+    /// Glyph writes since the last `flush_uploads`.
+    uploads: TextureWriteBatch,
     bind_group: wgpu::BindGroup,
 }
 
 impl TextAtlas {
-    pub fn new(device: &wgpu::Device) -> Self {
+    pub fn new(device: &wgpu::Device, shared: &SharedRenderResources) -> Self {
         const DEFAULT_START_SIZE: u32 = 256;
 
         let packer = BucketedAtlasAllocator::new(Size2D::new(
@@ -74,19 +100,13 @@ impl TextAtlas {
         let cached_glyphs = LruCache::unbounded_with_hasher(FastHasher::default());
 
         let texture_size = Size::new(DEFAULT_START_SIZE, DEFAULT_START_SIZE);
-        let texture = Texture::from_size(device, texture_size, Some("Text Atlas Texture"), None);
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Text Atlas Bind Group Layout"),
-            entries: &[
-                tools::bgl_entry(tools::BgEntryType::Texture, 0, wgpu::ShaderStages::FRAGMENT),
-                tools::bgl_entry(tools::BgEntryType::Sampler, 1, wgpu::ShaderStages::FRAGMENT),
-            ],
-        });
+        let mut texture =
+            Texture::from_size(device, texture_size, Some("Text Atlas Texture"), None);
+        texture.set_memory_category(MemoryCategory::Atlas);
 
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Text Atlas Bind Group"),
-            layout: &bind_group_layout,
+            layout: shared.layout(layouts::TEXT_ATLAS),
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -105,16 +125,11 @@ impl TextAtlas {
             cached_glyphs,
             texture,
             texture_size,
-            bind_group_layout,
+            uploads: TextureWriteBatch::new(1),
             bind_group,
         }
     }
 
-    #[inline]
-    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
-        &self.bind_group_layout
-    }
-
     #[inline]
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.bind_group
@@ -124,51 +139,104 @@ impl TextAtlas {
 //--------------------------------------------------
 
 impl TextAtlas {
-    // Cache glyph if not already and then promote in LRU
+    /// Cache glyph if not already and then promote in LRU. New glyphs are only
+    /// uploaded to the atlas texture by `flush_uploads`.
     pub fn use_glyph(
         &mut self,
         device: &wgpu::Device,
-        queue: &wgpu::Queue,
         font_system: &mut cosmic_text::FontSystem,
         swash_cache: &mut cosmic_text::SwashCache,
         key: &CacheKey,
     ) -> Result<(), CacheGlyphError> {
+        let key = AtlasKey::Glyph(*key);
+
         // Already has glyph cached
-        if self.cached_glyphs.contains(key) {
-            self.cached_glyphs.promote(key);
-            self.glyphs_in_use.insert(*key);
+        if self.cached_glyphs.contains(&key) {
+            self.cached_glyphs.promote(&key);
+            self.glyphs_in_use.insert(key);
 
             Ok(())
         }
         // Try to cache glyph
         else {
+            let AtlasKey::Glyph(cache_key) = key else {
+                unreachable!()
+            };
+
             let image = swash_cache
-                .get_image_uncached(font_system, *key)
+                .get_image_uncached(font_system, cache_key)
                 .ok_or(CacheGlyphError::NoGlyphImage)?;
 
-            self.cache_glyph(device, queue, key, &image)?;
+            self.cache_glyph(device, key, &image)?;
 
-            self.cached_glyphs.promote(key);
-            self.glyphs_in_use.insert(*key);
+            self.cached_glyphs.promote(&key);
+            self.glyphs_in_use.insert(key);
             Ok(())
         }
     }
 
     #[inline]
     pub fn get_glyph_data(&mut self, key: &CacheKey) -> Option<&GlyphData> {
-        self.cached_glyphs.get(key)
+        self.cached_glyphs.get(&AtlasKey::Glyph(*key))
+    }
+
+    /// Cache an icon at the size bucket for `size` if not already and then promote in LRU.
+    /// `rasterize` is only called on a cache miss, usually with `IconSet::rasterize`.
+    /// Like glyphs, new icons are only uploaded by `flush_uploads`.
+    pub fn use_icon(
+        &mut self,
+        device: &wgpu::Device,
+        handle: IconHandle,
+        size: f32,
+        rasterize: impl FnOnce(u32) -> Option<RasterIcon>,
+    ) -> Result<(), CacheGlyphError> {
+        let bucket = icons::size_bucket(size);
+        let key = AtlasKey::Icon { handle, bucket };
+
+        if !self.cached_glyphs.contains(&key) {
+            let icon = rasterize(bucket).ok_or(CacheGlyphError::UnknownIcon)?;
+
+            self.cache_data(device, key, &icon.data, [0., 0.], [icon.width, icon.height])?;
+        }
+
+        self.cached_glyphs.promote(&key);
+        self.glyphs_in_use.insert(key);
+        Ok(())
+    }
+
+    /// Data for an icon cached with `use_icon`. Sizes are in the bucket's pixels.
+    #[inline]
+    pub fn get_icon_data(&mut self, handle: IconHandle, size: f32) -> Option<&GlyphData> {
+        self.cached_glyphs.get(&AtlasKey::Icon {
+            handle,
+            bucket: icons::size_bucket(size),
+        })
     }
 
+    #[inline]
     fn cache_glyph(
         &mut self,
         device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        key: &CacheKey,
+        key: AtlasKey,
         image: &SwashImage,
     ) -> Result<(), CacheGlyphError> {
-        let image_width = image.placement.width;
-        let image_height = image.placement.height;
+        self.cache_data(
+            device,
+            key,
+            &image.data,
+            [image.placement.left as f32, image.placement.top as f32],
+            [image.placement.width, image.placement.height],
+        )
+    }
 
+    fn cache_data(
+        &mut self,
+        device: &wgpu::Device,
+        key: AtlasKey,
+        data: &[u8],
+        [left, top]: [f32; 2],
+        [image_width, image_height]: [u32; 2],
+    ) -> Result<(), CacheGlyphError> {
         let size = etagere::Size::new(image_width.max(1) as i32, image_height.max(1) as i32);
 
         let allocation = loop {
@@ -183,8 +251,7 @@ impl TextAtlas {
         let x = allocation.rectangle.min.x as u32;
         let y = allocation.rectangle.min.y as u32;
 
-        self.texture
-            .update_area(queue, &image.data, x, y, image_width, image_height);
+        self.uploads.push(data, x, y, image_width, image_height);
 
         let uv_start = [
             allocation.rectangle.min.x as f32 / self.texture_size.width as f32,
@@ -196,10 +263,8 @@ impl TextAtlas {
             allocation.rectangle.max.y as f32 / self.texture_size.height as f32,
         ];
 
-        let left = image.placement.left as f32;
-        let top = image.placement.top as f32;
-        let width = image.placement.width as f32;
-        let height = image.placement.height as f32;
+        let width = image_width as f32;
+        let height = image_height as f32;
 
         // log::trace!(
         //     "Allocated glyph id {:?}, with size {:?} and uv ({:?}, {:?})",
@@ -219,7 +284,7 @@ impl TextAtlas {
             height,
         };
 
-        self.cached_glyphs.put(*key, glyph_data);
+        self.cached_glyphs.put(key, glyph_data);
 
         Ok(())
     }
@@ -246,6 +311,13 @@ impl TextAtlas {
         return Ok(());
     }
 
+    /// Upload every glyph and icon cached since the last flush in one submission.
+    /// Called once at the end of each prep, before the atlas is rendered from.
+    #[inline]
+    pub fn flush_uploads(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.uploads.flush(device, queue, &self.texture.texture);
+    }
+
     #[inline]
     pub fn post_render_trim(&mut self) {
         self.glyphs_in_use.clear();
@@ -0,0 +1,250 @@
+//====================================================================
+
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Shaping, Wrap};
+
+//====================================================================
+
+/// An ordered list of font families tried for each character before cosmic-text's
+/// own per-platform fallback. The first family with a glyph for a character is used,
+/// so the same fonts give the same result on every machine.
+///
+/// Characters none of the families cover are left to cosmic-text. Load bundled fonts
+/// into the font system first, such as with `IconSet::load_font`, to avoid that.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FontFallback {
+    families: Vec<String>,
+}
+
+impl FontFallback {
+    pub fn new<S: Into<String>>(families: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            families: families.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Just the family the font system uses for generic monospace text.
+    #[inline]
+    pub fn monospace(font_system: &FontSystem) -> Self {
+        Self::new([font_system.db().family_name(&Family::Monospace)])
+    }
+
+    #[inline]
+    pub fn families(&self) -> &[String] {
+        &self.families
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.families.is_empty()
+    }
+
+    /// Split `text` into spans, each using the first family that covers it. Characters
+    /// the current span's family covers stay in it, so spaces and punctuation don't
+    /// break runs up. Spans no family covers use `attrs` unchanged.
+    pub fn spans<'s, 'a>(
+        &'a self,
+        font_system: &mut FontSystem,
+        text: &'s str,
+        attrs: Attrs<'a>,
+    ) -> Vec<(&'s str, Attrs<'a>)> {
+        if self.families.is_empty() {
+            return vec![(text, attrs)];
+        }
+
+        let faces = self.faces(font_system, attrs);
+
+        let mut spans = Vec::new();
+        let mut start = 0;
+        let mut current = None;
+
+        text.char_indices().for_each(|(index, c)| {
+            if current.is_some_and(|family| covers(font_system, faces[family], c)) {
+                return;
+            }
+
+            let family = faces.iter().position(|face| covers(font_system, *face, c));
+
+            if family != current && !c.is_whitespace() {
+                if index > start {
+                    spans.push((&text[start..index], self.attrs(current, attrs)));
+                }
+                start = index;
+                current = family;
+            }
+        });
+
+        if start < text.len() {
+            spans.push((&text[start..], self.attrs(current, attrs)));
+        }
+
+        spans
+    }
+
+    /// Set `text` on `buffer` with the fallback chain applied.
+    pub fn set_text(
+        &self,
+        font_system: &mut FontSystem,
+        buffer: &mut Buffer,
+        text: &str,
+        attrs: Attrs,
+    ) {
+        let spans = self.spans(font_system, text, attrs);
+        buffer.set_rich_text(font_system, spans, attrs, Shaping::Advanced);
+    }
+
+    /// The family each character of `text` was shaped with, or `None` if no font had
+    /// a glyph for it. Line breaks are skipped.
+    pub fn coverage_report(
+        &self,
+        font_system: &mut FontSystem,
+        text: &str,
+        attrs: Attrs,
+    ) -> Vec<(char, Option<String>)> {
+        let mut buffer = Buffer::new_empty(cosmic_text::Metrics::new(16., 16.));
+        buffer.set_wrap(font_system, Wrap::None);
+        self.set_text(font_system, &mut buffer, text, attrs);
+        buffer.shape_until_scroll(font_system, false);
+
+        let mut report = Vec::new();
+
+        buffer.layout_runs().for_each(|run| {
+            run.glyphs.iter().for_each(|glyph| {
+                let family = match glyph.glyph_id {
+                    0 => None,
+                    _ => font_system
+                        .db()
+                        .face(glyph.font_id)
+                        .and_then(|face| face.families.first())
+                        .map(|(name, _)| name.clone()),
+                };
+
+                run.text[glyph.start..glyph.end]
+                    .char_indices()
+                    .for_each(|(index, c)| {
+                        report.push(((run.line_i, glyph.start + index), c, family.clone()))
+                    });
+            });
+        });
+
+        // Glyphs of right to left runs are in visual order, and a cluster can span
+        // several glyphs
+        report.sort_by_key(|(position, _, _)| *position);
+        report.dedup_by_key(|(position, _, _)| *position);
+
+        report
+            .into_iter()
+            .map(|(_, c, family)| (c, family))
+            .collect()
+    }
+
+    /// The face best matching `attrs` in each family, in order.
+    fn faces(&self, font_system: &FontSystem, attrs: Attrs) -> Vec<Option<fontdb::ID>> {
+        self.families
+            .iter()
+            .map(|family| {
+                font_system.db().query(&fontdb::Query {
+                    families: &[Family::Name(family)],
+                    weight: attrs.weight,
+                    stretch: attrs.stretch,
+                    style: attrs.style,
+                })
+            })
+            .collect()
+    }
+
+    #[inline]
+    fn attrs<'a>(&'a self, family: Option<usize>, attrs: Attrs<'a>) -> Attrs<'a> {
+        match family {
+            Some(family) => attrs.family(Family::Name(&self.families[family])),
+            None => attrs,
+        }
+    }
+}
+
+/// Whether `face` has a glyph for `c`.
+#[inline]
+fn covers(font_system: &mut FontSystem, face: Option<fontdb::ID>, c: char) -> bool {
+    face.and_then(|face| font_system.get_font(face))
+        .is_some_and(|font| font.rustybuzz().glyph_index(c).is_some())
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    const FAMILIES: [&str; 2] = ["DejaVu Sans Mono", "DejaVu Sans"];
+    const MIXED: &str = "Hi Ωμέγα Привет مرحبا ∰ 你好";
+
+    fn fallback(font_system: &FontSystem) -> Option<FontFallback> {
+        let loaded = FAMILIES.iter().all(|family| {
+            font_system
+                .db()
+                .faces()
+                .any(|face| face.families.iter().any(|(name, _)| name == family))
+        });
+
+        if !loaded {
+            println!("Fallback fonts not available - skipping");
+            return None;
+        }
+
+        Some(FontFallback::new(FAMILIES))
+    }
+
+    #[test]
+    fn mixed_script_fallback_is_deterministic() {
+        let Some(mut font_system) = test_utils::font_system() else {
+            return;
+        };
+        let Some(fallback) = fallback(&font_system) else {
+            return;
+        };
+
+        let first = fallback.coverage_report(&mut font_system, MIXED, Attrs::new());
+        let second = fallback.coverage_report(&mut font_system, MIXED, Attrs::new());
+        assert_eq!(first, second);
+
+        // A fresh font system must resolve the same fonts as a warmed up one
+        let mut fresh = test_utils::font_system().unwrap();
+        let third = fallback.coverage_report(&mut fresh, MIXED, Attrs::new());
+        assert_eq!(first, third);
+
+        assert_eq!(first.len(), MIXED.chars().count());
+        assert_eq!(first[0], ('H', Some(FAMILIES[0].to_string())));
+
+        first.iter().for_each(|(c, family)| {
+            if let Some(family) = family {
+                assert!(
+                    FAMILIES.contains(&family.as_str()),
+                    "'{c}' fell back outside the chain to {family}"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn spans_cover_text_in_order() {
+        let Some(mut font_system) = test_utils::font_system() else {
+            return;
+        };
+        let Some(fallback) = fallback(&font_system) else {
+            return;
+        };
+
+        let spans = fallback.spans(&mut font_system, MIXED, Attrs::new());
+        let joined = spans.iter().map(|(text, _)| *text).collect::<String>();
+        assert_eq!(joined, MIXED);
+
+        let again = fallback.spans(&mut font_system, MIXED, Attrs::new());
+        let families = |spans: &[(&str, Attrs)]| {
+            spans
+                .iter()
+                .map(|(text, attrs)| (text.to_string(), format!("{:?}", attrs.family)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(families(&spans), families(&again));
+    }
+}
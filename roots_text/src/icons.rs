@@ -0,0 +1,282 @@
+//====================================================================
+
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use cosmic_text::{fontdb, CacheKey, CacheKeyFlags, FontSystem, SwashCache};
+
+//====================================================================
+
+/// Icons are rasterized at the requested size rounded up to a multiple of this,
+/// the same way glyphs are cached per font size.
+const SIZE_BUCKET_STEP: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IconHandle(u32);
+
+#[derive(Debug)]
+pub enum IconError {
+    UnknownFont,
+    MissingGlyph(char),
+    Image(image::ImageError),
+    Io(std::io::Error),
+    Renderer(roots_renderer::Error),
+}
+
+impl Error for IconError {}
+
+impl Display for IconError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IconError::UnknownFont => write!(f, "Font has not been loaded into the font system"),
+            IconError::MissingGlyph(codepoint) => {
+                write!(f, "Font has no glyph for codepoint {:?}", codepoint)
+            }
+            IconError::Image(e) => write!(f, "Unable to load icon image: {}", e),
+            IconError::Io(e) => write!(f, "Unable to read icon: {}", e),
+            IconError::Renderer(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<image::ImageError> for IconError {
+    fn from(value: image::ImageError) -> Self {
+        Self::Image(value)
+    }
+}
+
+impl From<std::io::Error> for IconError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<roots_renderer::Error> for IconError {
+    fn from(value: roots_renderer::Error) -> Self {
+        Self::Renderer(value)
+    }
+}
+
+//====================================================================
+
+enum IconSource {
+    Glyph {
+        font_id: fontdb::ID,
+        glyph_id: u16,
+    },
+    /// Single channel coverage, like the glyphs in the text atlas.
+    Image {
+        width: u32,
+        height: u32,
+        coverage: Vec<u8>,
+    },
+}
+
+/// Coverage data for an icon rasterized at a size bucket.
+pub struct RasterIcon {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Named icons drawn from the text atlas. Icons are single channel masks tinted by
+/// the text color, so they share the text pipeline and bind groups.
+#[derive(Default)]
+pub struct IconSet {
+    names: HashMap<String, IconHandle>,
+    icons: Vec<IconSource>,
+}
+
+impl IconSet {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<IconHandle> {
+        self.names.get(name).copied()
+    }
+
+    #[inline]
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.keys().map(|name| name.as_str())
+    }
+
+    fn insert(&mut self, name: &str, source: IconSource) -> IconHandle {
+        let handle = IconHandle(self.icons.len() as u32);
+        self.icons.push(source);
+
+        if self.names.insert(name.to_string(), handle).is_some() {
+            log::warn!("Replacing existing icon '{}'", name);
+        }
+
+        handle
+    }
+
+    /// Load an icon font into the font system. Returns the id of each face, for use with `add_glyph`.
+    pub fn load_font(font_system: &mut FontSystem, data: Vec<u8>) -> Vec<fontdb::ID> {
+        font_system
+            .db_mut()
+            .load_font_source(fontdb::Source::Binary(std::sync::Arc::new(data)))
+            .into_iter()
+            .collect()
+    }
+
+    /// Add the glyph for `codepoint` from a loaded font as an icon.
+    pub fn add_glyph(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_id: fontdb::ID,
+        name: &str,
+        codepoint: char,
+    ) -> Result<IconHandle, IconError> {
+        let font = font_system
+            .get_font(font_id)
+            .ok_or(IconError::UnknownFont)?;
+
+        let glyph_id = font.as_swash().charmap().map(codepoint);
+        if glyph_id == 0 {
+            return Err(IconError::MissingGlyph(codepoint));
+        }
+
+        Ok(self.insert(name, IconSource::Glyph { font_id, glyph_id }))
+    }
+
+    /// Add an icon from rgba8 pixels. Only the alpha channel is kept.
+    pub fn add_image(&mut self, name: &str, width: u32, height: u32, rgba: &[u8]) -> IconHandle {
+        let coverage = rgba.chunks_exact(4).map(|pixel| pixel[3]).collect();
+
+        self.insert(
+            name,
+            IconSource::Image {
+                width,
+                height,
+                coverage,
+            },
+        )
+    }
+
+    /// Add an icon from an encoded image such as a png.
+    pub fn add_image_bytes(&mut self, name: &str, bytes: &[u8]) -> Result<IconHandle, IconError> {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        Ok(self.add_image(name, image.width(), image.height(), &image))
+    }
+
+    /// Add every png in a folder, named by file stem. Returns the number of icons added.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_image_dir(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<usize, IconError> {
+        let mut count = 0;
+
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+
+            let is_png = path
+                .extension()
+                .map(|extension| extension.eq_ignore_ascii_case("png"))
+                .unwrap_or(false);
+
+            let name = match (is_png, path.file_stem().and_then(|stem| stem.to_str())) {
+                (true, Some(name)) => name.to_string(),
+                _ => continue,
+            };
+
+            self.add_image_bytes(&name, &std::fs::read(&path)?)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+//--------------------------------------------------
+
+/// The size an icon drawn at `size` is rasterized at.
+#[inline]
+pub fn size_bucket(size: f32) -> u32 {
+    (size.max(1.).ceil() as u32).next_multiple_of(SIZE_BUCKET_STEP)
+}
+
+impl IconSet {
+    /// Rasterize an icon so its largest side is `bucket` pixels.
+    pub fn rasterize(
+        &self,
+        font_system: &mut FontSystem,
+        swash_cache: &mut SwashCache,
+        handle: IconHandle,
+        bucket: u32,
+    ) -> Option<RasterIcon> {
+        match self.icons.get(handle.0 as usize)? {
+            IconSource::Glyph { font_id, glyph_id } => {
+                let (key, _, _) = CacheKey::new(
+                    *font_id,
+                    *glyph_id,
+                    bucket as f32,
+                    (0., 0.),
+                    CacheKeyFlags::empty(),
+                );
+
+                let image = swash_cache.get_image_uncached(font_system, key)?;
+
+                Some(RasterIcon {
+                    width: image.placement.width,
+                    height: image.placement.height,
+                    data: image.data,
+                })
+            }
+
+            IconSource::Image {
+                width,
+                height,
+                coverage,
+            } => Some(resize_coverage(coverage, *width, *height, bucket)),
+        }
+    }
+}
+
+/// Box filter the image down (or nearest sample it up) so its largest side is `bucket`.
+fn resize_coverage(coverage: &[u8], width: u32, height: u32, bucket: u32) -> RasterIcon {
+    if width == 0 || height == 0 {
+        return RasterIcon {
+            width: 1,
+            height: 1,
+            data: vec![0],
+        };
+    }
+
+    let scale = bucket as f32 / width.max(height).max(1) as f32;
+
+    let target_width = ((width as f32 * scale).round() as u32).max(1);
+    let target_height = ((height as f32 * scale).round() as u32).max(1);
+
+    let step_x = width as f32 / target_width as f32;
+    let step_y = height as f32 / target_height as f32;
+
+    let data = (0..target_height)
+        .flat_map(|y| (0..target_width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let start_x = (x as f32 * step_x) as u32;
+            let start_y = (y as f32 * step_y) as u32;
+            let end_x = (((x + 1) as f32 * step_x).ceil() as u32).clamp(start_x + 1, width);
+            let end_y = (((y + 1) as f32 * step_y).ceil() as u32).clamp(start_y + 1, height);
+
+            let (total, count) = (start_y..end_y)
+                .flat_map(|sy| (start_x..end_x).map(move |sx| (sy * width + sx) as usize))
+                .fold((0_u32, 0_u32), |(total, count), index| {
+                    (total + coverage[index] as u32, count + 1)
+                });
+
+            (total / count.max(1)) as u8
+        })
+        .collect();
+
+    RasterIcon {
+        width: target_width,
+        height: target_height,
+        data,
+    }
+}
+
+//====================================================================
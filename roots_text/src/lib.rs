@@ -1,7 +1,15 @@
 //====================================================================
 
 pub mod atlas;
+pub mod fallback;
+pub mod icons;
+pub mod overflow;
+pub mod selection;
 pub mod shared;
+#[cfg(test)]
+mod test_utils;
+#[cfg(feature = "pipelines")]
+pub mod text2d_renderer;
 #[cfg(feature = "pipelines")]
 pub mod ui3d_renderer;
 
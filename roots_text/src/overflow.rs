@@ -0,0 +1,197 @@
+//====================================================================
+
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+use crate::fallback::FontFallback;
+
+//====================================================================
+
+const ELLIPSIS: &str = "…";
+
+/// Font size is reduced by this factor each step while shrinking to fit.
+const SHRINK_STEP: f32 = 0.9;
+
+/// What to do with text that doesn't fit inside its bounds.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TextOverflow {
+    /// Draw everything, even outside the bounds.
+    #[default]
+    Visible,
+    /// Draw everything but let the renderer scissor to the bounds.
+    Clip,
+    /// Reduce the font size until the text fits, down to `min_font_size`.
+    ShrinkToFit { min_font_size: f32 },
+    /// Drop the lines that don't fit and end the last visible line with an ellipsis.
+    /// Lines too wide for the bounds (when not wrapping) are also ellipsized.
+    Ellipsize,
+}
+
+/// The result of laying out text with `fit_text`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FitResult {
+    /// Size of the laid out text.
+    pub size: glam::Vec2,
+    pub font_size: f32,
+    /// True if any text was removed by ellipsizing.
+    pub truncated: bool,
+}
+
+//====================================================================
+
+/// Size of the laid out text in `buffer`, including lines below its height.
+pub fn measure(buffer: &Buffer) -> glam::Vec2 {
+    buffer.layout_runs().fold(glam::Vec2::ZERO, |size, run| {
+        glam::vec2(
+            size.x.max(run.line_w),
+            size.y.max(run.line_top + run.line_height),
+        )
+    })
+}
+
+/// Lay out `text` in `buffer` wrapped to `bounds.x`, applying `overflow` so it fits
+/// in `bounds`. Starts from the buffer's current metrics. The buffer's height is left
+/// unbounded so every kept line is laid out.
+pub fn fit_text(
+    font_system: &mut FontSystem,
+    buffer: &mut Buffer,
+    text: &str,
+    attrs: Attrs,
+    fallback: &FontFallback,
+    bounds: glam::Vec2,
+    overflow: TextOverflow,
+) -> FitResult {
+    let metrics = buffer.metrics();
+    let line_scale = metrics.line_height / metrics.font_size;
+
+    buffer.set_metrics_and_size(font_system, metrics, Some(bounds.x), None);
+    fallback.set_text(font_system, buffer, text, attrs);
+    buffer.shape_until_scroll(font_system, false);
+
+    let mut result = FitResult {
+        size: measure(buffer),
+        font_size: metrics.font_size,
+        truncated: false,
+    };
+
+    let fits = |size: glam::Vec2| size.x <= bounds.x + 0.5 && size.y <= bounds.y + 0.5;
+
+    match overflow {
+        TextOverflow::Visible | TextOverflow::Clip => {}
+
+        TextOverflow::ShrinkToFit { min_font_size } => {
+            while !fits(result.size) && result.font_size > min_font_size {
+                result.font_size = (result.font_size * SHRINK_STEP).max(min_font_size);
+
+                let metrics = Metrics::new(result.font_size, result.font_size * line_scale);
+                buffer.set_metrics_and_size(font_system, metrics, Some(bounds.x), None);
+                buffer.shape_until_scroll(font_system, false);
+
+                result.size = measure(buffer);
+            }
+        }
+
+        TextOverflow::Ellipsize => {
+            if fits(result.size) {
+                return result;
+            }
+
+            if let Some(truncated) = ellipsize(font_system, buffer, attrs, bounds) {
+                fallback.set_text(font_system, buffer, &truncated, attrs);
+                buffer.shape_until_scroll(font_system, false);
+
+                result.size = measure(buffer);
+                result.truncated = true;
+            }
+        }
+    }
+
+    result
+}
+
+//--------------------------------------------------
+
+/// Width of the ellipsis at the buffer's current metrics.
+fn ellipsis_width(font_system: &mut FontSystem, buffer: &Buffer, attrs: Attrs) -> f32 {
+    let mut ellipsis = Buffer::new(font_system, buffer.metrics());
+    ellipsis.set_text(font_system, ELLIPSIS, attrs, Shaping::Advanced);
+    ellipsis.shape_until_scroll(font_system, false);
+
+    measure(&ellipsis).x
+}
+
+/// Build the text that fits in `bounds`, or `None` if nothing needs removing.
+fn ellipsize(
+    font_system: &mut FontSystem,
+    buffer: &Buffer,
+    attrs: Attrs,
+    bounds: glam::Vec2,
+) -> Option<String> {
+    let ellipsis_width = ellipsis_width(font_system, buffer, attrs);
+
+    // Runs whose bottom is inside the bounds. Always keep at least one.
+    let runs = buffer.layout_runs().collect::<Vec<_>>();
+    let visible = runs
+        .iter()
+        .take_while(|run| run.line_top + run.line_height <= bounds.y + 0.5)
+        .count()
+        .max(1)
+        .min(runs.len());
+
+    let cut_lines = visible < runs.len();
+
+    let mut output = Vec::new();
+    let mut truncated = cut_lines;
+
+    // Group the visible runs by the text line they came from
+    let mut runs = runs.iter().take(visible).enumerate().peekable();
+
+    while let Some((index, run)) = runs.next() {
+        let line_i = run.line_i;
+        let line_text = buffer.lines[line_i].text();
+
+        let mut line_runs = vec![(index, run)];
+        while let Some(next) = runs.next_if(|(_, next)| next.line_i == line_i) {
+            line_runs.push(next);
+        }
+
+        // Cut at the first run wider than the bounds, or the last visible run if
+        // lines were dropped
+        let cut_run = line_runs.iter().find(|(index, run)| {
+            run.line_w > bounds.x + 0.5 || (cut_lines && *index == visible - 1)
+        });
+
+        match cut_run {
+            None => output.push(line_text.to_string()),
+            Some((_, run)) => {
+                let available = bounds.x - ellipsis_width;
+                let run_start = run
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.start)
+                    .min()
+                    .unwrap_or(0);
+
+                // Take glyphs from the left until out of space
+                let cut = run
+                    .glyphs
+                    .iter()
+                    .filter(|glyph| glyph.x + glyph.w <= available)
+                    .map(|glyph| glyph.end)
+                    .max()
+                    .unwrap_or(run_start);
+
+                output.push(format!("{}{}", line_text[..cut].trim_end(), ELLIPSIS));
+                truncated = true;
+            }
+        }
+    }
+
+    let output = output.join("\n");
+
+    match truncated {
+        true => Some(output),
+        false => None,
+    }
+}
+
+//====================================================================
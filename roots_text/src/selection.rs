@@ -0,0 +1,286 @@
+//====================================================================
+
+use cosmic_text::{Buffer, Cursor};
+
+//====================================================================
+
+/// A range of text from where the selection started (`anchor`) to where it currently
+/// ends (`focus`). The focus can be before the anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    pub anchor: Cursor,
+    pub focus: Cursor,
+}
+
+impl Selection {
+    /// An empty selection at `cursor`.
+    #[inline]
+    pub fn new(cursor: Cursor) -> Self {
+        Self {
+            anchor: cursor,
+            focus: cursor,
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.anchor.line == self.focus.line && self.anchor.index == self.focus.index
+    }
+
+    /// The start and end of the selection, in text order.
+    #[inline]
+    pub fn ordered(&self) -> (Cursor, Cursor) {
+        match (self.anchor.line, self.anchor.index) <= (self.focus.line, self.focus.index) {
+            true => (self.anchor, self.focus),
+            false => (self.focus, self.anchor),
+        }
+    }
+}
+
+//====================================================================
+
+/// The cursor nearest `local_point`, in pixels from the top left of the laid out text.
+#[inline]
+pub fn hit_position(buffer: &Buffer, local_point: glam::Vec2) -> Option<Cursor> {
+    buffer.hit(local_point.x, local_point.y)
+}
+
+/// The word (or run of whitespace or punctuation) around `cursor`.
+pub fn word_at(buffer: &Buffer, cursor: Cursor) -> Selection {
+    let Some(line) = buffer.lines.get(cursor.line) else {
+        return Selection::new(cursor);
+    };
+
+    let text = line.text();
+    let index = cursor.index.min(text.len());
+
+    // Classify by the character after the cursor, or before it at the end of the line
+    let class = match text[index..].chars().next() {
+        Some(character) => char_class(character),
+        None => match text[..index].chars().next_back() {
+            Some(character) => char_class(character),
+            None => return Selection::new(cursor),
+        },
+    };
+
+    let start = text[..index]
+        .char_indices()
+        .rev()
+        .take_while(|(_, character)| char_class(*character) == class)
+        .last()
+        .map_or(index, |(start, _)| start);
+
+    let end = text[index..]
+        .char_indices()
+        .find(|(_, character)| char_class(*character) != class)
+        .map_or(text.len(), |(offset, _)| index + offset);
+
+    Selection {
+        anchor: Cursor::new(cursor.line, start),
+        focus: Cursor::new(cursor.line, end),
+    }
+}
+
+#[derive(PartialEq)]
+enum CharClass {
+    Word,
+    Whitespace,
+    Other,
+}
+
+#[inline]
+fn char_class(character: char) -> CharClass {
+    match character {
+        _ if character.is_alphanumeric() || character == '_' => CharClass::Word,
+        _ if character.is_whitespace() => CharClass::Whitespace,
+        _ => CharClass::Other,
+    }
+}
+
+//====================================================================
+
+/// The text covered by `selection`, with lines joined by newlines.
+pub fn selected_text(buffer: &Buffer, selection: &Selection) -> String {
+    let (start, end) = selection.ordered();
+
+    buffer
+        .lines
+        .iter()
+        .enumerate()
+        .skip(start.line)
+        .take(end.line.saturating_sub(start.line) + 1)
+        .map(|(line_index, line)| {
+            let text = line.text();
+            let from = match line_index == start.line {
+                true => start.index.min(text.len()),
+                false => 0,
+            };
+            let to = match line_index == end.line {
+                true => end.index.min(text.len()),
+                false => text.len(),
+            };
+
+            text.get(from..to.max(from)).unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Highlight rects covering `selection`, as position and size in pixels from the top
+/// left of the laid out text. One rect per layout run, so wrapped lines get one each.
+pub fn selection_rects(buffer: &Buffer, selection: &Selection) -> Vec<(glam::Vec2, glam::Vec2)> {
+    if selection.is_empty() {
+        return Vec::new();
+    }
+
+    let (start, end) = selection.ordered();
+
+    buffer
+        .layout_runs()
+        .filter(|run| run.line_i >= start.line && run.line_i <= end.line)
+        .filter_map(|run| {
+            let (x, width) = run.highlight(start, end)?;
+
+            match width > 0. {
+                true => Some((
+                    glam::vec2(x, run.line_top),
+                    glam::vec2(width, run.line_height),
+                )),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+//====================================================================
+
+#[cfg(test)]
+mod tests {
+    use cosmic_text::{Attrs, FontSystem, Metrics, Shaping};
+
+    use super::*;
+    use crate::test_utils;
+
+    const LINE_HEIGHT: f32 = 20.;
+
+    fn buffer(font_system: &mut FontSystem, text: &str, width: Option<f32>) -> Buffer {
+        let mut buffer = Buffer::new(font_system, Metrics::new(16., LINE_HEIGHT));
+        buffer.set_size(font_system, width, None);
+        buffer.set_text(font_system, text, Attrs::new(), Shaping::Advanced);
+        buffer.shape_until_scroll(font_system, false);
+        buffer
+    }
+
+    fn line_width(buffer: &Buffer, line: usize) -> f32 {
+        buffer
+            .layout_runs()
+            .filter(|run| run.line_i == line)
+            .map(|run| run.line_w)
+            .fold(0., f32::max)
+    }
+
+    #[test]
+    fn hit_positions_at_line_boundaries() {
+        let Some(mut font_system) = test_utils::font_system() else {
+            return;
+        };
+        let buffer = buffer(&mut font_system, "hello world\nsecond line\nthird", None);
+
+        let hit = |x, y| {
+            let cursor = hit_position(&buffer, glam::vec2(x, y)).unwrap();
+            (cursor.line, cursor.index)
+        };
+
+        assert_eq!(hit(0., 1.), (0, 0));
+        assert_eq!(hit(0., LINE_HEIGHT - 1.), (0, 0));
+        assert_eq!(hit(0., LINE_HEIGHT + 1.), (1, 0));
+
+        // Past the end of a line hits its end, not the start of the next
+        assert_eq!(hit(1000., LINE_HEIGHT - 1.), (0, "hello world".len()));
+        assert_eq!(hit(1000., LINE_HEIGHT + 1.), (1, "second line".len()));
+        assert_eq!(hit(-10., LINE_HEIGHT * 2. + 1.), (2, 0));
+    }
+
+    #[test]
+    fn hit_positions_on_wrapped_lines() {
+        let Some(mut font_system) = test_utils::font_system() else {
+            return;
+        };
+        let text = "one two three four five six seven eight nine ten";
+        let unwrapped = buffer(&mut font_system, text, None);
+        let width = line_width(&unwrapped, 0) / 2.;
+
+        let buffer = buffer(&mut font_system, text, Some(width));
+        assert!(buffer.layout_runs().count() > 1);
+
+        // The start of the second row is partway through the same line
+        let cursor = hit_position(&buffer, glam::vec2(0., LINE_HEIGHT + 1.)).unwrap();
+        assert_eq!(cursor.line, 0);
+        assert!(cursor.index > 0 && cursor.index < text.len());
+        assert!(text[..cursor.index].ends_with(' '));
+    }
+
+    #[test]
+    fn selection_rects_span_lines() {
+        let Some(mut font_system) = test_utils::font_system() else {
+            return;
+        };
+        let buffer = buffer(&mut font_system, "hello world\nsecond line\nthird", None);
+
+        let selection = Selection {
+            anchor: Cursor::new(0, 6),
+            focus: Cursor::new(2, 3),
+        };
+        let rects = selection_rects(&buffer, &selection);
+        assert_eq!(rects.len(), 3);
+
+        // One rect per line, stacked without gaps
+        rects
+            .iter()
+            .enumerate()
+            .for_each(|(line, (position, size))| {
+                assert_eq!(position.y, line as f32 * LINE_HEIGHT);
+                assert_eq!(size.y, LINE_HEIGHT);
+            });
+
+        // The first starts partway along, the rest at the left edge
+        assert!(rects[0].0.x > 0.);
+        assert_eq!(rects[1].0.x, 0.);
+        assert_eq!(rects[2].0.x, 0.);
+        assert!(rects[2].1.x < line_width(&buffer, 2));
+
+        assert_eq!(
+            selected_text(&buffer, &selection),
+            "world\nsecond line\nthi"
+        );
+
+        // The focus can come before the anchor
+        let reversed = Selection {
+            anchor: selection.focus,
+            focus: selection.anchor,
+        };
+        assert_eq!(selection_rects(&buffer, &reversed), rects);
+        assert_eq!(selected_text(&buffer, &reversed), "world\nsecond line\nthi");
+    }
+
+    #[test]
+    fn selection_ending_at_a_line_start() {
+        let Some(mut font_system) = test_utils::font_system() else {
+            return;
+        };
+        let buffer = buffer(&mut font_system, "hello world\nsecond line\nthird", None);
+
+        // Nothing of the last line is covered, so it gets no rect
+        let selection = Selection {
+            anchor: Cursor::new(0, 0),
+            focus: Cursor::new(2, 0),
+        };
+        assert_eq!(selection_rects(&buffer, &selection).len(), 2);
+        assert_eq!(
+            selected_text(&buffer, &selection),
+            "hello world\nsecond line\n"
+        );
+
+        assert!(selection_rects(&buffer, &Selection::new(Cursor::new(1, 3))).is_empty());
+    }
+}
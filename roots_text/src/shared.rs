@@ -3,10 +3,21 @@
 use std::hash::{Hash, Hasher};
 
 use cosmic_text::CacheKey;
-use roots_renderer::{shared::Vertex, tools};
+#[cfg(feature = "pipelines")]
+use roots_renderer::layouts;
+use roots_renderer::{
+    memory::{GpuMemoryTracker, MemoryCategory, MemoryGuard},
+    shared::{SharedRenderResources, Vertex},
+    tools,
+};
 use rustc_hash::FxHasher;
 
-use crate::atlas::TextAtlas;
+use crate::{
+    atlas::TextAtlas,
+    fallback::FontFallback,
+    icons::{IconHandle, IconSet},
+    overflow::{FitResult, TextOverflow},
+};
 
 //====================================================================
 
@@ -14,22 +25,67 @@ pub struct TextResources {
     pub font_system: cosmic_text::FontSystem,
     pub swash_cache: cosmic_text::SwashCache,
     pub text_atlas: TextAtlas,
+    pub icons: IconSet,
+    /// Applied to text buffers created by the renderers.
+    pub fallback: FontFallback,
 }
 
 impl TextResources {
-    pub fn new(device: &wgpu::Device) -> Self {
+    pub fn new(device: &wgpu::Device, shared: &SharedRenderResources) -> Self {
         Self {
             font_system: cosmic_text::FontSystem::new(),
             swash_cache: cosmic_text::SwashCache::new(),
-            text_atlas: TextAtlas::new(device),
+            text_atlas: TextAtlas::new(device, shared),
+            icons: IconSet::new(),
+            fallback: FontFallback::default(),
         }
     }
+
+    #[inline]
+    pub fn with_fallback(mut self, fallback: FontFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    #[inline]
+    pub fn locale(&self) -> &str {
+        self.font_system.locale()
+    }
+
+    /// Set the locale used to pick cosmic-text's fallback fonts, such as `ja-JP` to
+    /// prefer Japanese forms of Han characters. cosmic-text only supports one locale
+    /// per font system, so this applies to all text. Loaded fonts are kept.
+    pub fn set_locale(&mut self, locale: &str) {
+        let font_system = std::mem::replace(
+            &mut self.font_system,
+            cosmic_text::FontSystem::new_with_locale_and_db(
+                String::new(),
+                cosmic_text::fontdb::Database::new(),
+            ),
+        );
+
+        let (_, db) = font_system.into_locale_and_db();
+        self.font_system = cosmic_text::FontSystem::new_with_locale_and_db(locale.into(), db);
+    }
+
+    /// The family each character of `text` resolves to with the default fallback, or
+    /// `None` if no font has a glyph for it. See `FontFallback::coverage_report`.
+    #[inline]
+    pub fn coverage_report(&mut self, text: &str) -> Vec<(char, Option<String>)> {
+        self.fallback
+            .coverage_report(&mut self.font_system, text, Attrs::new())
+    }
 }
 
 //====================================================================
 
+/// Bind group layouts of `text.wgsl`, shared by the text renderers.
+#[cfg(feature = "pipelines")]
+pub(crate) const TEXT_LAYOUTS: &[&str] =
+    &[layouts::CAMERA, layouts::TEXT_ATLAS, layouts::UI_UNIFORM];
+
 #[repr(C)]
-#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug, PartialEq)]
 pub struct TextVertex {
     glyph_pos: [f32; 2],
     glyph_size: [f32; 2],
@@ -58,7 +114,18 @@ impl Vertex for TextVertex {
 
 //====================================================================
 
-pub use cosmic_text::{Attrs, Buffer, Color, Metrics, Shaping, Wrap};
+pub use cosmic_text::{Attrs, Buffer, Color, Cursor, FontSystem, Metrics, Shaping, Wrap};
+
+/// Text color from a linear color, such as a `Theme` role.
+#[inline]
+pub fn color_from_vec4(color: glam::Vec4) -> Color {
+    let [r, g, b, a] = (color.clamp(glam::Vec4::ZERO, glam::Vec4::ONE) * 255.)
+        .round()
+        .to_array()
+        .map(|channel| channel as u8);
+
+    Color::rgba(r, g, b, a)
+}
 
 #[derive(Default, Debug)]
 struct TextBufferLine {
@@ -71,8 +138,10 @@ pub struct TextBuffer {
     vertex_buffer: wgpu::Buffer,
     vertex_count: u32,
     lines: Vec<TextBufferLine>,
+    memory: MemoryGuard,
 
     buffer: Buffer,
+    fallback: FontFallback,
     pub color: Color,
 }
 
@@ -80,6 +149,9 @@ pub struct TextBufferDescriptor<'a> {
     pub metrics: Metrics,
     pub word_wrap: Wrap,
     pub attributes: Attrs<'a>,
+    /// Fallback chain for this buffer, usually `TextResources::fallback`. `None` leaves
+    /// fallback to cosmic-text.
+    pub fallback: Option<&'a FontFallback>,
     pub text: &'a str,
     pub width: Option<f32>,
     pub height: Option<f32>,
@@ -92,6 +164,7 @@ impl<'a> Default for TextBufferDescriptor<'a> {
             metrics: Metrics::relative(30., 1.2),
             word_wrap: Wrap::WordOrGlyph,
             attributes: Attrs::new(),
+            fallback: None,
             text: "",
             width: Some(800.),
             height: None,
@@ -115,26 +188,108 @@ impl TextBuffer {
 
         let vertex_count = 0;
         let lines = Vec::new();
+        let memory =
+            GpuMemoryTracker::track(MemoryCategory::InstanceBuffers, "Text Vertex Buffer", 0);
 
         let mut buffer = Buffer::new(font_system, desc.metrics);
         buffer.set_size(font_system, desc.width, desc.height);
         buffer.set_wrap(font_system, desc.word_wrap);
-        buffer.set_text(font_system, desc.text, desc.attributes, Shaping::Advanced);
+        let fallback = desc.fallback.cloned().unwrap_or_default();
+        fallback.set_text(font_system, &mut buffer, desc.text, desc.attributes);
 
         Self {
             vertex_buffer,
             vertex_count,
             lines,
+            memory,
             buffer,
+            fallback,
             color: desc.color,
         }
     }
 
+    #[inline]
+    pub fn fallback(&self) -> &FontFallback {
+        &self.fallback
+    }
+
+    /// Takes effect the next time the text is set.
+    #[inline]
+    pub fn set_fallback(&mut self, fallback: FontFallback) {
+        self.fallback = fallback;
+    }
+
     #[inline]
     pub fn set_metrics(&mut self, font_system: &mut cosmic_text::FontSystem, metrics: Metrics) {
         self.buffer.set_metrics(font_system, metrics);
     }
 
+    /// Set the width and height text is wrapped and laid out within. Returns true if
+    /// the bounds changed - the text is only laid out again when they do.
+    pub fn set_bounds(
+        &mut self,
+        font_system: &mut cosmic_text::FontSystem,
+        width: Option<f32>,
+        height: Option<f32>,
+    ) -> bool {
+        if self.buffer.size() == (width, height) {
+            return false;
+        }
+
+        self.buffer.set_size(font_system, width, height);
+        true
+    }
+
+    #[inline]
+    pub fn bounds(&self) -> (Option<f32>, Option<f32>) {
+        self.buffer.size()
+    }
+
+    /// The cursor nearest `local_point`, in pixels from the top left of the text.
+    /// See `selection::hit_position`.
+    #[inline]
+    pub fn hit_position(&self, local_point: glam::Vec2) -> Option<Cursor> {
+        crate::selection::hit_position(&self.buffer, local_point)
+    }
+
+    /// Size of the laid out text. See `overflow::measure`.
+    #[inline]
+    pub fn measure(&self) -> glam::Vec2 {
+        crate::overflow::measure(&self.buffer)
+    }
+
+    /// Lay out `text` to fit `bounds` using `overflow`. See `overflow::fit_text`.
+    #[inline]
+    pub fn set_text_fitted(
+        &mut self,
+        font_system: &mut cosmic_text::FontSystem,
+        text: &str,
+        attributes: Attrs,
+        bounds: glam::Vec2,
+        overflow: TextOverflow,
+    ) -> FitResult {
+        crate::overflow::fit_text(
+            font_system,
+            &mut self.buffer,
+            text,
+            attributes,
+            &self.fallback,
+            bounds,
+            overflow,
+        )
+    }
+
+    #[inline]
+    pub fn set_text(
+        &mut self,
+        font_system: &mut cosmic_text::FontSystem,
+        text: &str,
+        attributes: Attrs,
+    ) {
+        self.fallback
+            .set_text(font_system, &mut self.buffer, text, attributes);
+    }
+
     #[inline]
     pub fn update_buffer(
         &mut self,
@@ -151,6 +306,7 @@ impl TextBuffer {
             &mut self.vertex_count,
             data,
         );
+        self.memory.set_bytes(self.vertex_buffer.size());
     }
 
     #[inline]
@@ -166,6 +322,31 @@ impl TextBuffer {
 
 //====================================================================
 
+/// Build an instance drawing an icon centred on `position`, `size` pixels along its
+/// largest side. Icons are drawn with the same pipeline and atlas as glyphs so they
+/// can be appended to a text vertex buffer. The icon must have been cached this
+/// frame with `TextAtlas::use_icon`.
+pub fn icon_vertex(
+    text_atlas: &mut TextAtlas,
+    icon: IconHandle,
+    size: f32,
+    position: [f32; 2],
+    color: Color,
+) -> Option<TextVertex> {
+    let data = text_atlas.get_icon_data(icon, size)?;
+    let scale = size / crate::icons::size_bucket(size) as f32;
+
+    Some(TextVertex {
+        glyph_pos: position,
+        glyph_size: [data.width * scale, data.height * scale],
+        uv_start: data.uv_start,
+        uv_end: data.uv_end,
+        color: color.0,
+    })
+}
+
+//====================================================================
+
 struct LocalGlyphData {
     x: f32,
     y: f32,
@@ -203,13 +384,9 @@ pub fn prep(
                     let physical = glyph.physical((0., 0.), 1.);
 
                     // Try to prep glyph in atlas
-                    if let Err(_) = text_atlas.use_glyph(
-                        device,
-                        queue,
-                        font_system,
-                        swash_cache,
-                        &physical.cache_key,
-                    ) {
+                    if let Err(_) =
+                        text_atlas.use_glyph(device, font_system, swash_cache, &physical.cache_key)
+                    {
                         unimplemented!()
                     }
 
@@ -259,6 +436,9 @@ pub fn prep(
         })
         .collect::<Vec<_>>();
 
+    // Upload every new glyph in one go now the buffer is done with
+    text_atlas.flush_uploads(device, queue);
+
     // TODO - OPTIMIZE - Only rebuild lines that need rebuilding
     match rebuild_all_lines {
         true => Some(
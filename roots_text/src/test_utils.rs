@@ -0,0 +1,20 @@
+//====================================================================
+// Shared setup for tests that need fonts. Tests should return early when the
+// system has none installed (such as minimal ci containers).
+
+use cosmic_text::FontSystem;
+
+//====================================================================
+
+pub fn font_system() -> Option<FontSystem> {
+    let font_system = FontSystem::new();
+
+    if font_system.db().faces().next().is_none() {
+        println!("No system fonts available - skipping");
+        return None;
+    }
+
+    Some(font_system)
+}
+
+//====================================================================
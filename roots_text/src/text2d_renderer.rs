@@ -0,0 +1,304 @@
+//====================================================================
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::Arc,
+};
+
+use cosmic_text::{Attrs, Metrics, Wrap};
+use roots_common::{coords::WindowPx, Size};
+use roots_renderer::{
+    layouts,
+    shared::{SharedRenderResources, Vertex},
+    tools,
+};
+
+use crate::{
+    atlas::TextAtlas,
+    shared::{Color, TextBuffer, TextBufferDescriptor, TextResources, TextVertex, TEXT_LAYOUTS},
+};
+
+//====================================================================
+
+/// Screen space text, drawn with a pixel sized orthographic camera such as
+/// `RenderContext::screen_camera`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text2d {
+    pub text: String,
+    /// Position in the render target.
+    pub position: WindowPx,
+    /// The point of the laid out text placed at `position`, as a fraction of its
+    /// size. `(0, 0)` is the top left and `(1, 1)` the bottom right.
+    pub anchor: glam::Vec2,
+    pub font_size: f32,
+    pub color: Color,
+}
+
+impl Default for Text2d {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            position: WindowPx::default(),
+            anchor: glam::Vec2::ZERO,
+            font_size: 20.,
+            color: Color::rgb(255, 255, 255),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Text2dData {
+    position_uniform_buffer: wgpu::Buffer,
+    position_uniform_bind_group: wgpu::BindGroup,
+
+    text: String,
+    font_size: f32,
+    text_buffer: TextBuffer,
+    /// Size of the laid out text, used for anchoring.
+    size: glam::Vec2,
+}
+
+//====================================================================
+
+pub struct Text2dRenderer<ID> {
+    pipeline: wgpu::RenderPipeline,
+    position_uniform_bind_group_layout: Arc<wgpu::BindGroupLayout>,
+
+    instances: HashMap<ID, Text2dData>,
+    previous: HashSet<ID>,
+}
+
+impl<ID> Text2dRenderer<ID>
+where
+    ID: Hash + PartialEq + Eq + Clone,
+{
+    /// The pipeline is created without a depth stencil, so it must be rendered in
+    /// a pass without depth.
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> Self {
+        let position_uniform_bind_group_layout = shared.clone_layout(layouts::UI_UNIFORM);
+
+        let shader = include_str!("shaders/text.wgsl");
+        shared
+            .layouts()
+            .debug_validate_shader("Text2d Renderer", shader, TEXT_LAYOUTS);
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Text2d Renderer",
+            &shared.layouts().layouts(TEXT_LAYOUTS),
+            &[TextVertex::desc()],
+            shader,
+            tools::RenderPipelineDescriptor {
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    cull_mode: Some(wgpu::Face::Back),
+                    ..Default::default()
+                },
+                fragment_targets: Some(&[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::all(),
+                })]),
+                depth_stencil: None,
+                multisample: shared.multisample(),
+                cache: shared.pipeline_cache(),
+                ..Default::default()
+            },
+        );
+
+        Self {
+            pipeline,
+            position_uniform_bind_group_layout,
+            instances: HashMap::default(),
+            previous: HashSet::default(),
+        }
+    }
+
+    /// Prep `text` for rendering this frame. The text is only laid out again when
+    /// its content or font size changes.
+    pub fn prep_text(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        resources: &mut TextResources,
+
+        id: ID,
+        text: &Text2d,
+        target: Size<u32>,
+    ) {
+        let TextResources {
+            font_system,
+            swash_cache,
+            text_atlas,
+            fallback,
+            ..
+        } = resources;
+
+        self.previous.remove(&id);
+
+        //--------------------------------------------------
+        // Insert new text data
+
+        if !self.instances.contains_key(&id) {
+            log::trace!("Inserting new text2d data");
+
+            let position_uniform_buffer = tools::create_buffer(
+                device,
+                tools::BufferType::Uniform,
+                "Text2d Position",
+                &[Text2dPositionUniformRaw {
+                    transform: glam::Mat4::default(),
+                }],
+            );
+
+            let position_uniform_bind_group =
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Text2d Position Bind Group"),
+                    layout: &self.position_uniform_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(
+                            position_uniform_buffer.as_entire_buffer_binding(),
+                        ),
+                    }],
+                });
+
+            let text_buffer = TextBuffer::new(
+                device,
+                font_system,
+                &TextBufferDescriptor {
+                    metrics: Metrics::relative(text.font_size, 1.2),
+                    word_wrap: Wrap::None,
+                    text: &text.text,
+                    width: None,
+                    color: text.color,
+                    fallback: Some(fallback),
+                    ..Default::default()
+                },
+            );
+
+            self.instances.insert(
+                id.clone(),
+                Text2dData {
+                    position_uniform_buffer,
+                    position_uniform_bind_group,
+                    text: text.text.clone(),
+                    font_size: text.font_size,
+                    text_buffer,
+                    size: glam::Vec2::ZERO,
+                },
+            );
+        }
+
+        let data = match self.instances.get_mut(&id) {
+            Some(data) => data,
+            None => return,
+        };
+
+        //--------------------------------------------------
+        // Build Text
+
+        if text.font_size != data.font_size {
+            data.text_buffer
+                .set_metrics(font_system, Metrics::relative(text.font_size, 1.2));
+            data.font_size = text.font_size;
+        }
+
+        if text.text != data.text {
+            data.text_buffer
+                .set_text(font_system, &text.text, Attrs::new());
+            data.text = text.text.clone();
+        }
+
+        data.text_buffer.color = text.color;
+
+        if let Some(vertices) = crate::shared::prep(
+            device,
+            queue,
+            text_atlas,
+            font_system,
+            swash_cache,
+            &mut data.text_buffer,
+        ) {
+            data.size = data.text_buffer.measure();
+            data.text_buffer.update_buffer(device, queue, &vertices);
+        }
+
+        //--------------------------------------------------
+        // Build Transform
+
+        // Glyphs extend down from the origin, so place the top left corner
+        let top_left = WindowPx(text.position.0 - text.anchor * data.size).to_screen_space(target);
+        let transform = glam::Mat4::from_translation(top_left.0.round().extend(0.));
+
+        queue
+            .write_buffer_with(
+                &data.position_uniform_buffer,
+                0,
+                wgpu::BufferSize::new(std::mem::size_of::<Text2dPositionUniformRaw>() as u64)
+                    .unwrap(),
+            )
+            .unwrap()
+            .copy_from_slice(bytemuck::cast_slice(&[Text2dPositionUniformRaw {
+                transform,
+            }]));
+    }
+
+    /// Size of the laid out text for `id` from its last prep.
+    #[inline]
+    pub fn text_size(&self, id: &ID) -> Option<glam::Vec2> {
+        self.instances.get(id).map(|data| data.size)
+    }
+
+    /// Remove the data for `id` immediately rather than waiting for the next `finish_prep`.
+    #[inline]
+    pub fn remove(&mut self, id: &ID) -> bool {
+        self.previous.remove(id);
+        self.instances.remove(id).is_some()
+    }
+
+    #[inline]
+    pub fn finish_prep(&mut self) {
+        self.previous.drain().for_each(|to_remove| {
+            self.instances.remove(&to_remove);
+        });
+
+        self.previous = self.instances.keys().cloned().collect();
+    }
+
+    pub fn render(
+        &mut self,
+        render_pass: &mut wgpu::RenderPass,
+        text_atlas: &TextAtlas,
+        screen_camera_bind_group: &wgpu::BindGroup,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, screen_camera_bind_group, &[]);
+        render_pass.set_bind_group(1, text_atlas.bind_group(), &[]);
+
+        self.instances
+            .values()
+            .filter(|instance| instance.text_buffer.vertex_count() > 0)
+            .for_each(|instance| {
+                render_pass.set_vertex_buffer(0, instance.text_buffer.vertex_buffer().slice(..));
+                render_pass.set_bind_group(2, &instance.position_uniform_bind_group, &[]);
+                render_pass.draw(0..4, 0..instance.text_buffer.vertex_count());
+            });
+    }
+}
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+struct Text2dPositionUniformRaw {
+    transform: glam::Mat4,
+}
+
+//====================================================================
@@ -3,67 +3,330 @@
 use std::{
     collections::{HashMap, HashSet},
     hash::Hash,
+    sync::Arc,
 };
 
 use cosmic_text::{Metrics, Wrap};
+use roots_common::{
+    coords::{Uv, WindowPx},
+    theme::Theme,
+    Size,
+};
+use roots_pipelines::world_panel_renderer::{PanelHighlight, WorldPanel, WorldPanelRenderer};
 use roots_renderer::{
+    camera::CameraUniform,
+    layouts,
     shared::{SharedRenderResources, Vertex},
-    texture::Texture,
     tools,
 };
 
 use crate::{
     atlas::TextAtlas,
-    shared::{TextBuffer, TextBufferDescriptor, TextResources, TextVertex},
+    icons::IconHandle,
+    shared::{
+        color_from_vec4, Color, TextBuffer, TextBufferDescriptor, TextResources, TextVertex,
+        TEXT_LAYOUTS,
+    },
 };
 
 //====================================================================
 
+/// A world space menu, drawn as a `WorldPanel` with the selected option highlighted
+/// and the options as text over it.
 #[derive(Debug, Clone)]
 pub struct Ui3d {
-    pub menu_color: [f32; 4],
-    pub selection_color: [f32; 4],
+    /// The theme's surface color if `None`.
+    pub menu_color: Option<[f32; 4]>,
+    /// The theme's accent color if `None`.
+    pub selection_color: Option<[f32; 4]>,
+    /// Readable text for the menu color if `None`. See `Theme::contrast_text_for`.
+    pub text_color: Option<Color>,
+    pub corner_radius: f32,
 
     pub options: Vec<String>,
     pub selected: u8,
     pub font_size: f32,
+    /// World units per point, where a point is one line of text. When set the menu
+    /// keeps its size whatever the `font_size`, which then only sets how finely the
+    /// text is rasterized. `None` uses one world unit per pixel of `font_size`.
+    pub world_units_per_point: Option<f32>,
+
+    /// Icons drawn over the panel in the same draw call as the text.
+    pub icons: Vec<Ui3dIcon>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ui3dIcon {
+    pub icon: IconHandle,
+    /// Centre of the icon in pixels from the top left of the panel.
+    pub position: glam::Vec2,
+    pub size: f32,
+    pub color: Color,
+}
+
+impl Ui3dIcon {
+    /// An icon centred vertically on the row for `option`, `x` pixels from the left.
+    pub fn on_option(icon: IconHandle, option: usize, x: f32, ui: &Ui3d) -> Self {
+        Self {
+            icon,
+            position: glam::vec2(x, ui.font_size * (option as f32 + 0.5)),
+            size: ui.font_size * 0.8,
+            color: Color::rgb(0, 0, 0),
+        }
+    }
+
+    #[inline]
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
 }
 
 impl Default for Ui3d {
     fn default() -> Self {
         Self {
-            menu_color: [0.5, 0.5, 0.5, 0.7],
-            selection_color: [0.7, 0.7, 0.7, 0.8],
+            menu_color: None,
+            selection_color: None,
+            text_color: None,
+            corner_radius: 0.,
             options: Vec::new(),
             selected: 0,
             font_size: 30.,
+            world_units_per_point: None,
+            icons: Vec::new(),
+        }
+    }
+}
+
+impl Ui3d {
+    /// Scale from the menu's layout, measured in pixels of `font_size`, to its
+    /// placement's units.
+    #[inline]
+    pub fn layout_scale(&self) -> f32 {
+        match self.world_units_per_point {
+            Some(units) => units / self.font_size,
+            None => 1.,
+        }
+    }
+
+    #[inline]
+    pub fn menu_color(&self, theme: &Theme) -> glam::Vec4 {
+        self.menu_color.map(Into::into).unwrap_or(theme.surface)
+    }
+
+    #[inline]
+    pub fn text_color(&self, theme: &Theme) -> Color {
+        self.text_color
+            .unwrap_or_else(|| color_from_vec4(theme.contrast_text_for(self.menu_color(theme))))
+    }
+
+    /// The background panel with the selected option highlighted, colored from
+    /// `theme` where no colors are set. `None` if there are no options.
+    pub fn panel(&self, theme: &Theme) -> Option<WorldPanel> {
+        let longest_line = self.options.iter().reduce(|a, b| match a.len() < b.len() {
+            true => a,
+            false => b,
+        })?;
+
+        let selected = self.selected.clamp(0, self.options.len() as u8) as f32;
+
+        let option_count = self.options.len() as f32;
+        let option_range = 1. / option_count;
+
+        let size = glam::vec2(
+            self.font_size * longest_line.len() as f32,
+            self.font_size * option_count,
+        );
+
+        Some(WorldPanel {
+            size,
+            // The first line of text sits slightly above the transform's origin
+            anchor: glam::vec2(0., 0.1),
+            color: self.menu_color(theme),
+            corner_radius: self.corner_radius,
+            highlight: Some(PanelHighlight {
+                start: option_range * selected,
+                end: option_range * (selected + 1.),
+                color: self.selection_color.map(Into::into).unwrap_or(theme.accent),
+            }),
+        })
+    }
+}
+
+//====================================================================
+
+/// NDC depth screen anchored menus are placed at. They are drawn over everything
+/// regardless, this only keeps them between the near and far planes.
+const SCREEN_DEPTH: f32 = 0.5;
+
+/// Where a `Ui3d` menu is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ui3dPlacement {
+    /// A fixed world transform.
+    WorldFixed(glam::Mat4),
+    /// Centred on `position` and turned to face the camera each frame.
+    Billboard { position: glam::Vec3, scale: f32 },
+    /// Pinned to the screen, where one unit is one pixel. `anchor` is a fraction of
+    /// the screen from `(0, 0)` top left to `(1, 1)` bottom right and the same point
+    /// of the menu is placed on it, so `(1, 0)` pins the menu's top right corner.
+    /// `offset_px` moves the menu in pixels, with y down.
+    ScreenAnchored { anchor: Uv, offset_px: glam::Vec2 },
+}
+
+impl From<glam::Mat4> for Ui3dPlacement {
+    #[inline]
+    fn from(value: glam::Mat4) -> Self {
+        Self::WorldFixed(value)
+    }
+}
+
+impl Ui3dPlacement {
+    /// The final transform of `ui`, including its `layout_scale`. Returns `None` for
+    /// placements relative to the camera when there's no view.
+    pub fn transform(&self, ui: &Ui3d, view: Option<&Ui3dView>) -> Option<glam::Mat4> {
+        let layout_scale = glam::Mat4::from_scale(glam::Vec3::splat(ui.layout_scale()));
+
+        let placed = match *self {
+            Ui3dPlacement::WorldFixed(transform) => transform,
+
+            Ui3dPlacement::Billboard { position, scale } => {
+                let view = view?;
+                let ndc = view.view_projection.project_point3(position);
+
+                let right =
+                    (view.unproject(ndc + glam::vec3(0.01, 0., 0.)) - position).normalize_or_zero();
+                let up =
+                    (view.unproject(ndc + glam::vec3(0., 0.01, 0.)) - position).normalize_or_zero();
+
+                // Text is laid out on the xy plane, so right and up keep it facing
+                // the camera with the same winding as an unrotated menu
+                glam::Mat4::from_cols(
+                    (right * scale).extend(0.),
+                    (up * scale).extend(0.),
+                    (right.cross(up) * scale).extend(0.),
+                    position.extend(1.),
+                )
+            }
+
+            Ui3dPlacement::ScreenAnchored { anchor, offset_px } => {
+                let view = view?;
+                let pixel = 2.
+                    / glam::vec2(
+                        view.viewport.width.max(1) as f32,
+                        view.viewport.height.max(1) as f32,
+                    );
+
+                let position = WindowPx(anchor.to_window(view.viewport).0 + offset_px);
+                let ndc = position.to_ndc(view.viewport).0.extend(SCREEN_DEPTH);
+
+                let origin = view.unproject(ndc);
+                let right = view.unproject(ndc + glam::vec3(pixel.x, 0., 0.)) - origin;
+                let up = view.unproject(ndc + glam::vec3(0., pixel.y, 0.)) - origin;
+                let forward = right.cross(up).normalize_or_zero() * right.length();
+
+                // Move the menu so its matching point sits on the anchor. Only the
+                // panel's size is used, so the theme doesn't matter.
+                let corner = match ui.panel(&Theme::default()) {
+                    Some(panel) => {
+                        glam::vec2(
+                            (anchor.0.x - panel.anchor.x) * panel.size.x,
+                            (panel.anchor.y - anchor.0.y) * panel.size.y,
+                        ) * ui.layout_scale()
+                    }
+                    None => glam::Vec2::ZERO,
+                };
+
+                glam::Mat4::from_cols(
+                    right.extend(0.),
+                    up.extend(0.),
+                    forward.extend(0.),
+                    origin.extend(1.),
+                ) * glam::Mat4::from_translation(-corner.extend(0.))
+            }
+        };
+
+        Some(placed * layout_scale)
+    }
+}
+
+/// The camera `Ui3dPlacement::Billboard` and `Ui3dPlacement::ScreenAnchored` menus are
+/// placed relative to. Set it on the renderer with `Ui3dRenderer::set_view` each frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ui3dView {
+    view_projection: glam::Mat4,
+    inverse_view_projection: glam::Mat4,
+    viewport: Size<u32>,
+}
+
+impl Ui3dView {
+    /// `viewport` is the size of the rendered area in pixels.
+    #[inline]
+    pub fn new(view_projection: glam::Mat4, viewport: Size<u32>) -> Self {
+        Self {
+            view_projection,
+            inverse_view_projection: view_projection.inverse(),
+            viewport,
         }
     }
+
+    #[inline]
+    pub fn from_camera<C: CameraUniform>(
+        camera: &C,
+        transform: &glam::Affine3A,
+        viewport: Size<u32>,
+    ) -> Self {
+        Self::new(
+            camera.get_projection_matrix() * camera.get_view_matrix(transform),
+            viewport,
+        )
+    }
+
+    #[inline]
+    pub fn view_projection(&self) -> glam::Mat4 {
+        self.view_projection
+    }
+
+    #[inline]
+    pub fn viewport(&self) -> Size<u32> {
+        self.viewport
+    }
+
+    #[inline]
+    fn unproject(&self, ndc: glam::Vec3) -> glam::Vec3 {
+        self.inverse_view_projection.project_point3(ndc)
+    }
 }
 
+//====================================================================
+
 #[derive(Debug)]
 struct Ui3dData {
-    ui_uniform_buffer: wgpu::Buffer,
-    ui_uniform_bind_group: wgpu::BindGroup,
-
     ui_position_uniform_buffer: wgpu::Buffer,
     ui_position_uniform_bind_group: wgpu::BindGroup,
-    size: [f32; 2],
 
+    text: String,
     text_buffer: TextBuffer,
+
+    /// Kept so icons can be rebuilt without re-shaping the text.
+    glyph_vertices: Vec<TextVertex>,
+    icon_vertices: Vec<TextVertex>,
 }
 
 //====================================================================
 
+/// Renders `Ui3d` menus. Every menu's panel is drawn by a shared `WorldPanelRenderer`
+/// before the text of all menus.
 pub struct Ui3dRenderer<ID> {
-    ui_pipeline: wgpu::RenderPipeline,
+    panels: WorldPanelRenderer,
     text_pipeline: wgpu::RenderPipeline,
 
-    ui_uniform_bind_group_layout: wgpu::BindGroupLayout,
-    ui_position_uniform_bind_group_layout: wgpu::BindGroupLayout,
+    ui_position_uniform_bind_group_layout: Arc<wgpu::BindGroupLayout>,
 
     instances: HashMap<ID, Ui3dData>,
     previous: HashSet<ID>,
+
+    view: Option<Ui3dView>,
+    theme: Theme,
 }
 
 impl<ID> Ui3dRenderer<ID>
@@ -73,73 +336,29 @@ where
     pub fn new(
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
-        shared: &mut SharedRenderResources,
-        text_shared: &mut TextResources,
+        shared: &SharedRenderResources,
     ) -> Self {
-        let ui_position_uniform_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Ui Instance Buffer Bind Group Layout"),
-                entries: &[tools::bgl_entry(
-                    tools::BgEntryType::Uniform,
-                    0,
-                    wgpu::ShaderStages::VERTEX,
-                )],
-            });
-
-        let ui_uniform_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Ui Instance Buffer Bind Group Layout"),
-                entries: &[tools::bgl_entry(
-                    tools::BgEntryType::Uniform,
-                    0,
-                    wgpu::ShaderStages::VERTEX,
-                )],
-            });
-
-        let ui_pipeline = tools::create_pipeline(
+        let ui_position_uniform_bind_group_layout = shared.clone_layout(layouts::UI_UNIFORM);
+
+        let panels = WorldPanelRenderer::new_with_compare(
             device,
             config,
-            "Ui Renderer",
-            &[
-                shared.camera_bind_group_layout(),
-                &ui_uniform_bind_group_layout,
-                &ui_position_uniform_bind_group_layout,
-            ],
-            &[],
-            include_str!("shaders/ui3d.wgsl"),
-            tools::RenderPipelineDescriptor {
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleStrip,
-                    cull_mode: Some(wgpu::Face::Back),
-                    ..Default::default()
-                },
-                fragment_targets: Some(&[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::all(),
-                })]),
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: Texture::DEPTH_FORMAT,
-                    depth_write_enabled: false,
-                    depth_compare: wgpu::CompareFunction::Always,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                ..Default::default()
-            },
+            shared,
+            wgpu::CompareFunction::Always,
         );
 
+        let shader = include_str!("shaders/text.wgsl");
+        shared
+            .layouts()
+            .debug_validate_shader("Ui Text Renderer", shader, TEXT_LAYOUTS);
+
         let text_pipeline = tools::create_pipeline(
             device,
             config,
             "Ui Text Renderer",
-            &[
-                shared.camera_bind_group_layout(),
-                text_shared.text_atlas.bind_group_layout(),
-                &ui_position_uniform_bind_group_layout,
-            ],
+            &shared.layouts().layouts(TEXT_LAYOUTS),
             &[TextVertex::desc()],
-            include_str!("shaders/text.wgsl"),
+            shader,
             tools::RenderPipelineDescriptor {
                 primitive: wgpu::PrimitiveState {
                     topology: wgpu::PrimitiveTopology::TriangleStrip,
@@ -151,41 +370,73 @@ where
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::all(),
                 })]),
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: Texture::DEPTH_FORMAT,
-                    depth_write_enabled: false,
-                    depth_compare: wgpu::CompareFunction::Always,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
+                depth_stencil: Some(
+                    shared
+                        .depth_convention()
+                        .depth_stencil_state(false, wgpu::CompareFunction::Always),
+                ),
+                multisample: shared.multisample(),
+                cache: shared.pipeline_cache(),
                 ..Default::default()
             },
         );
 
         Self {
-            ui_pipeline,
+            panels,
             text_pipeline,
-            ui_uniform_bind_group_layout,
             ui_position_uniform_bind_group_layout,
             instances: HashMap::default(),
             previous: HashSet::default(),
+            view: None,
+            theme: Theme::default(),
         }
     }
 
+    /// The camera used by camera relative placements until it's next set.
+    #[inline]
+    pub fn set_view(&mut self, view: Ui3dView) {
+        self.view = Some(view);
+    }
+
+    #[inline]
+    pub fn view(&self) -> Option<&Ui3dView> {
+        self.view.as_ref()
+    }
+
+    /// Colors menus without their own, from the next `prep_text`. Set each frame from
+    /// `RendererState::theme` to follow theme changes.
+    #[inline]
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = *theme;
+    }
+
     pub fn prep_text(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        text_atlas: &mut TextAtlas,
-        font_system: &mut cosmic_text::FontSystem,
-        swash_cache: &mut cosmic_text::SwashCache,
+        resources: &mut TextResources,
 
         id: ID,
         ui_data: &Ui3d,
-        transform: glam::Mat4,
+        placement: impl Into<Ui3dPlacement>,
     ) {
+        let TextResources {
+            font_system,
+            swash_cache,
+            text_atlas,
+            icons,
+            fallback,
+        } = resources;
+
         //--------------------------------------------------
 
+        // Unplaced menus aren't kept, so they're removed in `finish_prep`
+        let placement = placement.into();
+        let Some(transform) = placement.transform(ui_data, self.view.as_ref()) else {
+            log::warn!("Ui3d placement {:?} needs a view set first", placement);
+            return;
+        };
+
         self.previous.remove(&id);
 
         //--------------------------------------------------
@@ -194,31 +445,6 @@ where
         if !self.instances.contains_key(&id) {
             log::trace!("Inserting new ui3d data");
 
-            let ui_uniform_buffer = tools::create_buffer(
-                device,
-                tools::BufferType::Uniform,
-                "Ui",
-                &[UiUniformRaw {
-                    size: glam::Vec2::ONE,
-                    pad: [0.; 2],
-                    menu_color: glam::Vec4::ONE,
-                    selection_color: glam::Vec4::ONE,
-                    selection_range_y: glam::Vec2::ZERO,
-                    pad2: [0.; 2],
-                }],
-            );
-
-            let ui_uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Ui Bind Group"),
-                layout: &self.ui_uniform_bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(
-                        ui_uniform_buffer.as_entire_buffer_binding(),
-                    ),
-                }],
-            });
-
             let ui_position_uniform_buffer = tools::create_buffer(
                 device,
                 tools::BufferType::Uniform,
@@ -240,12 +466,7 @@ where
                     }],
                 });
 
-            let text = ui_data
-                .options
-                .iter()
-                .cloned()
-                .reduce(|a, b| format!("{}\n{}", a, b))
-                .unwrap_or(String::new());
+            let text = options_text(ui_data);
 
             let text_buffer = TextBuffer::new(
                 device,
@@ -254,6 +475,7 @@ where
                     metrics: Metrics::new(10., 10.),
                     word_wrap: Wrap::None,
                     text: &text,
+                    fallback: Some(fallback),
                     ..Default::default()
                 },
             );
@@ -261,12 +483,12 @@ where
             self.instances.insert(
                 id.clone(),
                 Ui3dData {
-                    ui_uniform_buffer,
-                    ui_uniform_bind_group,
                     ui_position_uniform_buffer,
                     ui_position_uniform_bind_group,
-                    size: [1., 1.],
+                    text,
                     text_buffer,
+                    glyph_vertices: Vec::new(),
+                    icon_vertices: Vec::new(),
                 },
             );
         }
@@ -280,7 +502,16 @@ where
         //--------------------------------------------------
         // Build Text
 
-        if let Some(rebuild) = crate::shared::prep(
+        data.text_buffer.color = ui_data.text_color(&self.theme);
+
+        let text = options_text(ui_data);
+        if text != data.text {
+            data.text_buffer
+                .set_text(font_system, &text, cosmic_text::Attrs::new());
+            data.text = text;
+        }
+
+        let text_rebuilt = match crate::shared::prep(
             device,
             queue,
             text_atlas,
@@ -288,7 +519,48 @@ where
             swash_cache,
             &mut data.text_buffer,
         ) {
-            data.text_buffer.update_buffer(device, queue, &rebuild);
+            Some(rebuild) => {
+                data.glyph_vertices = rebuild;
+                true
+            }
+            None => false,
+        };
+
+        // Icons are cached every frame to keep them in use in the atlas
+        let icon_vertices = ui_data
+            .icons
+            .iter()
+            .filter_map(|icon| {
+                if let Err(e) = text_atlas.use_icon(device, icon.icon, icon.size, |bucket| {
+                    icons.rasterize(font_system, swash_cache, icon.icon, bucket)
+                }) {
+                    log::warn!("Unable to cache icon {:?}: {}", icon.icon, e);
+                    return None;
+                }
+
+                crate::shared::icon_vertex(
+                    text_atlas,
+                    icon.icon,
+                    icon.size,
+                    [icon.position.x, -icon.position.y],
+                    icon.color,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        text_atlas.flush_uploads(device, queue);
+
+        if text_rebuilt || icon_vertices != data.icon_vertices {
+            data.icon_vertices = icon_vertices;
+
+            let vertices = data
+                .glyph_vertices
+                .iter()
+                .chain(data.icon_vertices.iter())
+                .copied()
+                .collect::<Vec<_>>();
+
+            data.text_buffer.update_buffer(device, queue, &vertices);
         }
 
         //--------------------------------------------------
@@ -308,64 +580,37 @@ where
         //--------------------------------------------------
         // Build UI Background
 
-        let longest_line = ui_data
-            .options
-            .iter()
-            .reduce(|a, b| match a.len() < b.len() {
-                true => a,
-                false => b,
-            });
-
-        let longest_line = match longest_line {
-            Some(val) => val,
+        let panel = match ui_data.panel(&self.theme) {
+            Some(panel) => panel,
             None => return,
         };
 
-        let selected = ui_data.selected.clamp(0, ui_data.options.len() as u8) as f32;
-
-        let option_count = ui_data.options.len() as f32;
-        let option_range = 1. / option_count;
-
-        let ui_size = glam::vec2(
-            ui_data.font_size * longest_line.len() as f32,
-            ui_data.font_size * option_count,
-        );
-
-        data.size = ui_size.to_array();
         data.text_buffer.set_metrics(
             font_system,
             Metrics::new(ui_data.font_size, ui_data.font_size),
         );
 
-        let ui_raw = UiUniformRaw {
-            size: ui_size,
-            menu_color: ui_data.menu_color.into(),
-            selection_color: ui_data.selection_color.into(),
-            selection_range_y: glam::vec2(option_range * selected, option_range * (selected + 1.)),
-
-            pad: [0.; 2],
-            pad2: [0.; 2],
-        };
-
-        queue
-            .write_buffer_with(
-                &data.ui_uniform_buffer,
-                0,
-                wgpu::BufferSize::new(std::mem::size_of::<UiUniformRaw>() as u64).unwrap(),
-            )
-            .unwrap()
-            .copy_from_slice(bytemuck::cast_slice(&[ui_raw]));
+        self.panels.prep_panel(&panel, None, transform);
 
         //--------------------------------------------------
     }
 
+    /// Remove the data for `id` immediately rather than waiting for the next `finish_prep`.
+    #[inline]
+    pub fn remove(&mut self, id: &ID) -> bool {
+        self.previous.remove(id);
+        self.instances.remove(id).is_some()
+    }
+
     #[inline]
-    pub fn finish_prep(&mut self) {
-        self.previous.drain().into_iter().for_each(|to_remove| {
+    pub fn finish_prep(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.previous.drain().for_each(|to_remove| {
             self.instances.remove(&to_remove);
         });
 
-        self.previous = self.instances.keys().map(|id| id.clone()).collect();
+        self.previous = self.instances.keys().cloned().collect();
+
+        self.panels.finish_prep(device, queue);
     }
 
     pub fn render(
@@ -374,23 +619,15 @@ where
         text_atlas: &TextAtlas,
         camera_bind_group: &wgpu::BindGroup,
     ) {
-        // Set camera (both pipelines)
-        render_pass.set_bind_group(0, camera_bind_group, &[]);
-
         // Draw UI background
-        render_pass.set_pipeline(&self.ui_pipeline);
-
-        self.instances.values().into_iter().for_each(|instance| {
-            render_pass.set_bind_group(1, &instance.ui_uniform_bind_group, &[]);
-            render_pass.set_bind_group(2, &instance.ui_position_uniform_bind_group, &[]);
-            render_pass.draw(0..4, 0..1);
-        });
+        self.panels.render(render_pass, camera_bind_group);
 
-        // // Draw Text
+        // Draw Text
         render_pass.set_pipeline(&self.text_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
         render_pass.set_bind_group(1, text_atlas.bind_group(), &[]);
 
-        self.instances.values().into_iter().for_each(|instance| {
+        self.instances.values().for_each(|instance| {
             render_pass.set_vertex_buffer(0, instance.text_buffer.vertex_buffer().slice(..));
             render_pass.set_bind_group(2, &instance.ui_position_uniform_bind_group, &[]);
             render_pass.draw(0..4, 0..instance.text_buffer.vertex_count());
@@ -398,6 +635,16 @@ where
     }
 }
 
+#[inline]
+fn options_text(ui_data: &Ui3d) -> String {
+    ui_data
+        .options
+        .iter()
+        .cloned()
+        .reduce(|a, b| format!("{}\n{}", a, b))
+        .unwrap_or(String::new())
+}
+
 //====================================================================
 
 #[repr(C)]
@@ -406,16 +653,4 @@ struct UiPositionUniformRaw {
     transform: glam::Mat4,
 }
 
-#[repr(C)]
-#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
-struct UiUniformRaw {
-    pub size: glam::Vec2,
-    pub pad: [f32; 2],
-
-    pub menu_color: glam::Vec4,
-    pub selection_color: glam::Vec4,
-    pub selection_range_y: glam::Vec2,
-    pub pad2: [f32; 2],
-}
-
 //====================================================================
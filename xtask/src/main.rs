@@ -0,0 +1,178 @@
+//====================================================================
+// Workspace maintenance tasks, run with `cargo xtask <task>`.
+//
+// features - check every crate with no default features, with each of its
+//            features on their own, with its defaults and with all features.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+};
+
+//====================================================================
+
+const TASKS: &str = "features";
+
+fn main() -> ExitCode {
+    let task = std::env::args().nth(1);
+
+    match task.as_deref() {
+        Some("features") => check_features(),
+        Some(task) => {
+            eprintln!("Unknown task '{}' - available tasks: {}", task, TASKS);
+            ExitCode::FAILURE
+        }
+        None => {
+            eprintln!("Usage: cargo xtask <task> - available tasks: {}", TASKS);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+//====================================================================
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask should be inside the workspace")
+        .to_path_buf()
+}
+
+/// Lines of the `[section]` table in a manifest, without comments or blank lines.
+fn manifest_section<'a>(manifest: &'a str, section: &str) -> Vec<&'a str> {
+    let header = format!("[{}]", section);
+
+    manifest
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != header)
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/// The quoted string at the start of `text`, if any.
+fn quoted(text: &str) -> Option<&str> {
+    let text = text.trim().strip_prefix('"')?;
+    text.split('"').next()
+}
+
+struct Package {
+    name: String,
+    features: Vec<String>,
+}
+
+fn workspace_packages(root: &Path) -> Vec<Package> {
+    let manifest =
+        fs::read_to_string(root.join("Cargo.toml")).expect("Unable to read workspace manifest");
+
+    let members = manifest
+        .split_once("members")
+        .and_then(|(_, rest)| rest.split_once('['))
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(list, _)| list)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(quoted)
+        .filter(|member| *member != "xtask")
+        .map(|member| member.to_string())
+        .collect::<Vec<_>>();
+
+    members
+        .iter()
+        .map(|member| {
+            let path = root.join(member).join("Cargo.toml");
+            let manifest = fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("Unable to read '{}': {}", path.display(), e));
+
+            let name = manifest_section(&manifest, "package")
+                .into_iter()
+                .find_map(|line| quoted(line.strip_prefix("name")?.trim().strip_prefix('=')?))
+                .unwrap_or(member)
+                .to_string();
+
+            let features = manifest_section(&manifest, "features")
+                .into_iter()
+                .filter_map(|line| line.split('=').next())
+                .map(str::trim)
+                .filter(|feature| *feature != "default")
+                .map(|feature| feature.to_string())
+                .collect();
+
+            Package { name, features }
+        })
+        .collect()
+}
+
+//====================================================================
+
+fn check_features() -> ExitCode {
+    let root = workspace_root();
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+
+    let mut failed = Vec::new();
+    let mut checked = 0;
+
+    workspace_packages(&root).iter().for_each(|package| {
+        let mut combinations = vec![
+            vec!["--no-default-features".to_string()],
+            vec![],
+            vec!["--all-features".to_string()],
+        ];
+
+        combinations.extend(package.features.iter().map(|feature| {
+            vec![
+                "--no-default-features".to_string(),
+                "--features".to_string(),
+                feature.clone(),
+            ]
+        }));
+
+        combinations.iter().for_each(|flags| {
+            let description = format!("{} {}", package.name, flags.join(" "));
+            println!("Checking {}", description.trim());
+
+            let status = Command::new(&cargo)
+                .current_dir(&root)
+                .args([
+                    "check",
+                    "--quiet",
+                    "--all-targets",
+                    "--package",
+                    &package.name,
+                ])
+                .args(flags)
+                .status();
+
+            checked += 1;
+
+            match status {
+                Ok(status) if status.success() => {}
+                Ok(_) => failed.push(description),
+                Err(e) => failed.push(format!("{} (unable to run cargo: {})", description, e)),
+            }
+        });
+    });
+
+    match failed.is_empty() {
+        true => {
+            println!("All {} feature combinations compile", checked);
+            ExitCode::SUCCESS
+        }
+        false => {
+            eprintln!(
+                "{} of {} feature combinations failed:",
+                failed.len(),
+                checked
+            );
+            failed
+                .iter()
+                .for_each(|description| eprintln!("    {}", description.trim()));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+//====================================================================